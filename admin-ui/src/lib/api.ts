@@ -57,6 +57,30 @@ export const cloudflareApi = {
   // Status & Connection
   getStatus: () => api.get('/cloudflare/status'),
   getZone: () => api.get('/cloudflare/zone'),
+  getZonePlan: () => api.get('/cloudflare/zone/plan'),
+  getAdvancedPerformance: () => api.get('/cloudflare/zone/performance/advanced'),
+  updateAdvancedPerformance: (settings: { crawler_hints?: boolean; http2_to_origin?: boolean }) =>
+    api.patch('/cloudflare/zone/performance/advanced', settings),
+  getPerformanceSettings: () => api.get('/cloudflare/performance/settings'),
+  updatePerformanceSettings: (settings: {
+    http2?: boolean;
+    http3?: boolean;
+    zero_rtt?: boolean;
+    websockets?: boolean;
+    early_hints?: boolean;
+    brotli_compression?: boolean;
+    rocket_loader?: boolean;
+  }) => api.patch('/cloudflare/performance/settings', settings),
+  getImageSettings: () => api.get('/cloudflare/performance/images'),
+  updateImageSettings: (settings: {
+    polish?: 'off' | 'lossless' | 'lossy';
+    webp?: boolean;
+    mirage?: boolean;
+    image_resizing?: boolean;
+  }) => api.patch('/cloudflare/performance/images', settings),
+  getArgo: () => api.get('/cloudflare/performance/argo'),
+  updateArgo: (settings: { smart_routing?: boolean; tiered_caching?: boolean }) =>
+    api.patch('/cloudflare/performance/argo', settings),
   getConnectionStatus: () => api.get('/cloudflare/connection'),
   testConnection: () => api.post('/cloudflare/connection/test'),
 
@@ -70,26 +94,48 @@ export const cloudflareApi = {
   saveCredentials: (apiToken: string, accountId: string, zoneId: string) =>
     api.post('/cloudflare/auth/save-credentials', { api_token: apiToken, account_id: accountId, zone_id: zoneId }),
   disconnect: () => api.post('/cloudflare/auth/disconnect'),
+  testCapabilities: () => api.post('/cloudflare/auth/test-capabilities'),
   listAccounts: (apiToken: string) => api.post('/cloudflare/auth/accounts', { api_token: apiToken }),
   listZones: (apiToken: string, accountId?: string) =>
     api.post('/cloudflare/auth/zones', { api_token: apiToken, account_id: accountId }),
 
   // Cache
-  purgeAll: () => api.post('/cloudflare/cache/purge/all'),
+  // Call without a token to get one, then again with it within 60s to purge
+  purgeAll: (confirmationToken?: string) =>
+    api.post('/cloudflare/cache/purge/all', confirmationToken ? { confirmation_token: confirmationToken } : undefined),
   purgeUrls: (urls: string[]) => api.post('/cloudflare/cache/purge', { urls }),
+  purgeUrl: (url: string) => api.post('/cloudflare/cache/purge/url', { url }),
   purgeTags: (tags: string[]) => api.post('/cloudflare/cache/purge/tags', { tags }),
   purgePrefix: (prefix: string) => api.post('/cloudflare/cache/purge/prefix', { prefix }),
+  purgeHosts: (hosts: string[]) => api.post('/cloudflare/cache/purge/hosts', { hosts }),
+  getPurgeHistory: (params?: { event_type?: string; page?: number; per_page?: number }) =>
+    api.get('/cloudflare/cache/history', { params }),
   getCacheStats: () => api.get('/cloudflare/cache/status'),
   warmCache: (urls: string[]) => api.post('/cloudflare/cache/warm', { urls }),
   clearLocalCache: () => api.post('/cloudflare/cache/local/clear'),
+  getCacheReserve: () => api.get('/cloudflare/cache/reserve'),
+  setCacheReserve: (enabled: boolean) => api.put('/cloudflare/cache/reserve', { enabled }),
+  getTieredCache: () => api.get('/cloudflare/cache/tiered'),
+  setTieredCache: (topology: 'off' | 'generic' | 'smart' | 'custom') =>
+    api.put('/cloudflare/cache/tiered', { topology }),
 
   // DNS
-  listDnsRecords: () => api.get('/cloudflare/dns/records'),
+  listDnsRecords: (params?: { type?: string; name?: string; page?: number; per_page?: number }) =>
+    api.get('/cloudflare/dns/records', { params }),
+  searchDnsRecords: (params: { type?: string; name?: string; content?: string; match?: 'all' | 'any' }) =>
+    api.get('/cloudflare/dns/records/search', { params }),
   createDnsRecord: (record: any) => api.post('/cloudflare/dns/records', record),
   updateDnsRecord: (id: string, record: any) => api.put(`/cloudflare/dns/records/${id}`, record),
   deleteDnsRecord: (id: string) => api.delete(`/cloudflare/dns/records/${id}`),
+  setDnsProxied: (id: string, proxied: boolean) =>
+    api.patch(`/cloudflare/dns/records/${id}/proxied`, { proxied }),
   exportZone: () => api.get('/cloudflare/dns/export'),
   importZone: (zoneFile: string) => api.post('/cloudflare/dns/import', { zone_file: zoneFile }),
+  diffDnsRecords: () => api.get('/cloudflare/dns/diff'),
+  scanDnsRecords: (domain: string, types?: string[]) =>
+    api.get('/cloudflare/dns/scan', { params: { domain, types: types?.join(',') } }),
+  getDnssec: () => api.get('/cloudflare/dns/dnssec'),
+  setDnssec: (enabled: boolean) => api.put('/cloudflare/dns/dnssec', { enabled }),
   syncDnsRecords: () => api.post('/cloudflare/dns/sync'),
 
   // Security
@@ -105,14 +151,64 @@ export const cloudflareApi = {
   allowIp: (ip: string, note?: string) => api.post('/cloudflare/security/ip-access/allow', { ip, note }),
   challengeIp: (ip: string, note?: string) => api.post('/cloudflare/security/ip-access/challenge', { ip, note }),
   deleteIpRule: (id: string) => api.delete(`/cloudflare/security/ip-access/rules/${id}`),
+  exportSecurityRules: () => api.get('/cloudflare/security/export'),
+  importSecurityRules: (exported: any, mode: 'merge' | 'replace' = 'merge') =>
+    api.post('/cloudflare/security/import', { export: exported, mode }),
+  listIpLists: () => api.get('/cloudflare/security/ip-lists'),
+  createIpList: (name: string, description?: string) =>
+    api.post('/cloudflare/security/ip-lists', { name, description }),
+  listIpListItems: (id: string) => api.get(`/cloudflare/security/ip-lists/${id}/items`),
+  addIpListItems: (id: string, items: { ip: string; comment?: string }[]) =>
+    api.post(`/cloudflare/security/ip-lists/${id}/items`, { items }),
+  removeIpListItems: (id: string, ids: string[]) =>
+    api.delete(`/cloudflare/security/ip-lists/${id}/items`, { data: { ids } }),
+  getIpListOperation: (operationId: string) => api.get(`/cloudflare/security/ip-lists/operations/${operationId}`),
+  listTurnstileWidgets: () => api.get('/cloudflare/security/turnstile/widgets'),
+  verifyTurnstile: (token: string, secret: string, remoteIp?: string) =>
+    api.post('/cloudflare/security/turnstile/verify', { token, secret, remote_ip: remoteIp }),
+  createTurnstileWidget: (widget: { name: string; domains: string[]; mode: string }) =>
+    api.post('/cloudflare/security/turnstile/widgets', widget),
+  rotateTurnstileSecret: (sitekey: string, invalidateImmediately = false) =>
+    api.post(`/cloudflare/security/turnstile/widgets/${sitekey}/rotate-secret`, {
+      invalidate_immediately: invalidateImmediately,
+    }),
+  deleteTurnstileWidget: (sitekey: string) =>
+    api.delete(`/cloudflare/security/turnstile/widgets/${sitekey}`),
   getSecurityEvents: (limit?: number) => api.get('/cloudflare/security/events', { params: { limit } }),
+  securityEventsStreamUrl: () => `${api.defaults.baseURL ?? ''}/cloudflare/security/events/stream`,
   getWafRules: () => api.get('/cloudflare/security/waf/rules'),
   updateWafRule: (id: string, enabled: boolean) => api.patch(`/cloudflare/security/waf/rules/${id}`, { enabled }),
+  listManagedRulesets: () => api.get('/cloudflare/security/waf/managed'),
+  deployManagedRuleset: (rulesetId: string, action = 'default') =>
+    api.post('/cloudflare/security/waf/managed', { ruleset_id: rulesetId, action }),
+  getBotManagement: () => api.get('/cloudflare/security/bots'),
+  updateBotManagement: (settings: {
+    fight_mode?: boolean;
+    enable_js?: boolean;
+    sbfm_definitely_automated?: string;
+    sbfm_likely_automated?: string;
+    sbfm_verified_bots?: string;
+    sbfm_static_resource_protection?: boolean;
+    optimize_wordpress?: boolean;
+  }) => api.put('/cloudflare/security/bots', settings),
+  listCustomRules: () => api.get('/cloudflare/security/custom-rules'),
+  createCustomRule: (rule: { expression: string; action: string; description?: string }) =>
+    api.post('/cloudflare/security/custom-rules', rule),
+  updateCustomRule: (id: string, rule: { expression: string; action: string; description?: string }) =>
+    api.put(`/cloudflare/security/custom-rules/${id}`, rule),
+  deleteCustomRule: (id: string) => api.delete(`/cloudflare/security/custom-rules/${id}`),
 
   // SSL
   getSslStatus: () => api.get('/cloudflare/ssl/status'),
   setSslMode: (mode: string) => api.put('/cloudflare/ssl/mode', { mode }),
+  setMinTlsVersion: (version: '1.0' | '1.1' | '1.2' | '1.3') => api.put('/cloudflare/ssl/min-tls', { version }),
+  setTls13: (enabled: boolean) => api.put('/cloudflare/ssl/tls13', { enabled }),
   listCertificates: () => api.get('/cloudflare/ssl/certificates'),
+  listCustomHostnames: () => api.get('/cloudflare/ssl/custom-hostnames'),
+  createCustomHostname: (hostname: string, sslMethod?: 'http' | 'txt' | 'email') =>
+    api.post('/cloudflare/ssl/custom-hostnames', { hostname, ssl_method: sslMethod }),
+  getCustomHostname: (id: string) => api.get(`/cloudflare/ssl/custom-hostnames/${id}`),
+  deleteCustomHostname: (id: string) => api.delete(`/cloudflare/ssl/custom-hostnames/${id}`),
   orderCertificate: (hosts: string[]) => api.post('/cloudflare/ssl/certificates', { hosts }),
   uploadCertificate: (certificate: string, privateKey: string) =>
     api.post('/cloudflare/ssl/certificates/custom', { certificate, private_key: privateKey }),
@@ -120,24 +216,32 @@ export const cloudflareApi = {
   // Workers
   listWorkers: () => api.get('/cloudflare/workers'),
   getWorker: (name: string) => api.get(`/cloudflare/workers/${name}`),
-  deployWorker: (name: string, script: string) => api.post('/cloudflare/workers', { name, script }),
-  updateWorker: (name: string, script: string) => api.put(`/cloudflare/workers/${name}`, { script }),
+  getWorkerSettings: (name: string) => api.get(`/cloudflare/workers/${name}/settings`),
+  // Live logs are server-sent events; open with `new EventSource(workerTailUrl(name))`
+  workerTailUrl: (name: string) => `${api.defaults.baseURL}/cloudflare/workers/${encodeURIComponent(name)}/tail`,
+  deployWorker: (name: string, script: string, expectedEtag?: string) =>
+    api.post('/cloudflare/workers', { name, script, expected_etag: expectedEtag }),
+  updateWorker: (name: string, script: string, expectedEtag?: string) =>
+    api.put(`/cloudflare/workers/${name}`, { script, expected_etag: expectedEtag }),
   deleteWorker: (name: string) => api.delete(`/cloudflare/workers/${name}`),
   listWorkerRoutes: () => api.get('/cloudflare/workers/routes'),
   createWorkerRoute: (pattern: string, script: string) =>
     api.post('/cloudflare/workers/routes', { pattern, script }),
   deleteWorkerRoute: (id: string) => api.delete(`/cloudflare/workers/routes/${id}`),
-  deployTemplate: (templateId: string, name: string) =>
-    api.post('/cloudflare/workers/templates/deploy', { template_id: templateId, name }),
+  deployTemplate: (templateId: string, name: string, params?: Record<string, unknown>) =>
+    api.post('/cloudflare/workers/templates/deploy', { template_id: templateId, name, params }),
 
   // KV Storage
   listKVNamespaces: () => api.get('/cloudflare/workers/kv/namespaces'),
-  createKVNamespace: (title: string) => api.post('/cloudflare/workers/kv/namespaces', { title }),
+  createKVNamespace: (title: string, allowDuplicate?: boolean) =>
+    api.post('/cloudflare/workers/kv/namespaces', { title, allow_duplicate: allowDuplicate }),
   deleteKVNamespace: (id: string) => api.delete(`/cloudflare/workers/kv/namespaces/${id}`),
   listKVKeys: (namespaceId: string, prefix?: string) =>
     api.get(`/cloudflare/workers/kv/namespaces/${namespaceId}/keys`, { params: { prefix } }),
   getKVValue: (namespaceId: string, key: string) =>
     api.get(`/cloudflare/workers/kv/namespaces/${namespaceId}/values/${key}`),
+  getKVEntry: (namespaceId: string, key: string) =>
+    api.get(`/cloudflare/workers/kv/${namespaceId}/entries/${encodeURIComponent(key)}`),
   putKVValue: (namespaceId: string, key: string, value: string) =>
     api.put(`/cloudflare/workers/kv/namespaces/${namespaceId}/values/${key}`, { value }),
   deleteKVValue: (namespaceId: string, key: string) =>
@@ -147,12 +251,16 @@ export const cloudflareApi = {
   listR2Buckets: () => api.get('/cloudflare/r2/buckets'),
   createR2Bucket: (name: string) => api.post('/cloudflare/r2/buckets', { name }),
   deleteR2Bucket: (name: string) => api.delete(`/cloudflare/r2/buckets/${name}`),
+  getR2BucketCors: (bucket: string) => api.get(`/cloudflare/r2/buckets/${bucket}/cors`),
+  setR2BucketCors: (bucket: string, rules: any[]) =>
+    api.put(`/cloudflare/r2/buckets/${bucket}/cors`, rules),
   listR2Objects: (bucket: string, prefix?: string) =>
     api.get(`/cloudflare/r2/buckets/${bucket}/objects`, { params: { prefix } }),
   uploadR2Object: (bucket: string, key: string, file: File) => {
     const formData = new FormData();
-    formData.append('file', file);
+    // The key must precede the file: the server streams the file as it arrives
     formData.append('key', key);
+    formData.append('file', file);
     return api.post(`/cloudflare/r2/buckets/${bucket}/objects`, formData, {
       headers: { 'Content-Type': 'multipart/form-data' },
     });
@@ -161,6 +269,11 @@ export const cloudflareApi = {
     api.delete(`/cloudflare/r2/buckets/${bucket}/objects/${encodeURIComponent(key)}`),
   getR2ObjectUrl: (bucket: string, key: string) =>
     api.get(`/cloudflare/r2/buckets/${bucket}/objects/${encodeURIComponent(key)}/url`),
+  downloadR2Object: (bucket: string, key: string, range?: string) =>
+    api.get(`/cloudflare/r2/buckets/${bucket}/download/${encodeURIComponent(key)}`, {
+      responseType: 'blob',
+      headers: range ? { Range: range } : undefined,
+    }),
 
   // Analytics
   getAnalytics: (timeRange?: string) => api.get('/cloudflare/analytics', { params: { time_range: timeRange } }),
@@ -169,17 +282,33 @@ export const cloudflareApi = {
   getBandwidthStats: () => api.get('/cloudflare/analytics/bandwidth'),
   getSecurityAnalytics: () => api.get('/cloudflare/analytics/security'),
   getCacheAnalytics: () => api.get('/cloudflare/analytics/cache'),
+  getCacheTimeseries: (params?: {
+    hours?: number;
+    bucket?: 'minute' | 'five_minutes' | 'fifteen_minutes' | 'hour' | 'day';
+  }) => api.get('/cloudflare/analytics/cache/timeseries', { params }),
 
   // Page Rules
-  listPageRules: () => api.get('/cloudflare/page-rules'),
-  createPageRule: (rule: any) => api.post('/cloudflare/page-rules', rule),
-  updatePageRule: (id: string, rule: any) => api.put(`/cloudflare/page-rules/${id}`, rule),
-  deletePageRule: (id: string) => api.delete(`/cloudflare/page-rules/${id}`),
+  listPageRules: () => api.get('/cloudflare/rules/pages'),
+  getPageRule: (id: string) => api.get(`/cloudflare/rules/pages/${id}`),
+  createPageRule: (rule: any) => api.post('/cloudflare/rules/pages', rule),
+  updatePageRule: (id: string, rule: any) => api.put(`/cloudflare/rules/pages/${id}`, rule),
+  deletePageRule: (id: string) => api.delete(`/cloudflare/rules/pages/${id}`),
+  importBulkRedirects: (name: string, file: File) => {
+    const formData = new FormData();
+    formData.append('name', name);
+    formData.append('file', file);
+    return api.post('/cloudflare/rules/bulk-redirects/import', formData, {
+      headers: { 'Content-Type': 'multipart/form-data' },
+    });
+  },
 
   // Zone Settings
   getZoneSettings: () => api.get('/cloudflare/zone/settings'),
-  updateZoneSettings: (settings: any) => api.patch('/cloudflare/zone/settings', settings),
-  toggleDevMode: (enabled: boolean) => api.post('/cloudflare/zone/development-mode', { enabled }),
+  updateZoneSettings: (settings: any, transactional = false) =>
+    api.patch('/cloudflare/zone/settings', settings, { params: { transactional } }),
+  toggleDevMode: (enabled: boolean, duration?: number) =>
+    api.post('/cloudflare/zone/development-mode', { enabled, duration }),
+  pauseZone: (paused: boolean) => api.post('/cloudflare/zone/pause', { paused }),
 
   // Plugin Settings
   getPluginSettings: () => api.get('/cloudflare/settings'),
@@ -200,8 +329,20 @@ export const cloudflareApi = {
     auto_purge_archives?: boolean;
     auto_purge_custom_urls?: string;
     auto_purge_delay_ms?: number;
+    auto_purge_quota_pause_minutes?: number;
+    auto_purge_dry_run?: boolean;
   }) => api.put('/cloudflare/settings/auto-purge', settings),
 
+  // Feature Flags
+  getFeatureFlags: () => api.get('/cloudflare/settings/features'),
+  updateFeatureFlags: (flags: Record<string, boolean>) => api.put('/cloudflare/settings/features', flags),
+
+  // Settings Backup
+  exportSettings: (includeSecrets = false) =>
+    api.get('/cloudflare/settings/export', { params: { include_secrets: includeSecrets } }),
+  importSettings: (document: any, overwrite = false) =>
+    api.post('/cloudflare/settings/import', { document, overwrite }),
+
   // Cache Warming Settings
   updateCacheWarmingSettings: (settings: {
     cache_warming_enabled?: boolean;
@@ -212,36 +353,73 @@ export const cloudflareApi = {
   updateNotificationSettings: (settings: {
     security_email_alerts?: boolean;
     security_slack_webhook?: string;
+    security_webhook_alerts?: boolean;
   }) => api.put('/cloudflare/settings/notifications', settings),
 
+  // Under Attack auto-trigger
+  updateUnderAttackAutoSettings: (settings: {
+    under_attack_auto_enabled?: boolean;
+    under_attack_threshold?: number;
+    under_attack_window_minutes?: number;
+    under_attack_cooldown_minutes?: number;
+  }) => api.put('/cloudflare/settings/under-attack', settings),
+
   // Advanced Settings
   updateAdvancedSettings: (settings: {
     development_mode_duration?: number;
     analytics_retention_days?: number;
+    analytics_cache_ttl_secs?: number;
     r2_default_bucket?: string;
     workers_enabled?: boolean;
+    dns_scan_probes?: { name: string; record_type: string }[];
+    dns_scan_nameservers?: string;
   }) => api.put('/cloudflare/settings/advanced', settings),
 
   // D1 Databases
   listD1Databases: () => api.get('/cloudflare/d1/databases'),
   getD1Database: (id: string) => api.get(`/cloudflare/d1/databases/${id}`),
   createD1Database: (name: string) => api.post('/cloudflare/d1/databases', { name }),
-  deleteD1Database: (id: string) => api.delete(`/cloudflare/d1/databases/${id}`),
+  deleteD1Database: (id: string, confirmName: string) =>
+    api.delete(`/cloudflare/d1/databases/${id}`, { data: { confirm_name: confirmName } }),
   executeD1Query: (databaseId: string, sql: string) =>
     api.post(`/cloudflare/d1/databases/${databaseId}/query`, { sql }),
   executeD1Batch: (databaseId: string, statements: string[]) =>
     api.post(`/cloudflare/d1/databases/${databaseId}/batch`, { statements }),
+  exportD1Database: (databaseId: string) =>
+    api.get(`/cloudflare/d1/databases/${databaseId}/export`, { responseType: 'text' }),
+  importD1Database: (databaseId: string, sql: string) =>
+    api.post(`/cloudflare/d1/databases/${databaseId}/import`, { sql }),
   listD1Tables: (databaseId: string) =>
     api.get(`/cloudflare/d1/databases/${databaseId}/tables`),
   getD1TableSchema: (databaseId: string, tableName: string) =>
     api.get(`/cloudflare/d1/databases/${databaseId}/tables/${tableName}/schema`),
 
   // Stream Videos
-  listStreamVideos: () => api.get('/cloudflare/stream/videos'),
+  listStreamVideos: (params?: {
+    search?: string;
+    status?: string;
+    creator?: string;
+    asc?: boolean;
+    start?: string;
+    end?: string;
+    limit?: number;
+    cursor?: string;
+    page?: number;
+    per_page?: number;
+  }) => api.get('/cloudflare/stream/videos', { params }),
   getStreamVideo: (id: string) => api.get(`/cloudflare/stream/videos/${id}`),
   deleteStreamVideo: (id: string) => api.delete(`/cloudflare/stream/videos/${id}`),
-  searchStreamVideos: (query: string) =>
-    api.get('/cloudflare/stream/videos/search', { params: { q: query } }),
+  searchStreamVideos: (query: string, page?: number, perPage?: number) =>
+    api.get('/cloudflare/stream/videos/search', { params: { q: query, page, per_page: perPage } }),
+  copyStreamVideo: (url: string, name?: string) =>
+    api.post('/cloudflare/stream/videos/copy', { url, name }),
+  listStreamCaptions: (id: string) => api.get(`/cloudflare/stream/videos/${id}/captions`),
+  uploadStreamCaption: (id: string, language: string, vtt: string) =>
+    api.put(`/cloudflare/stream/videos/${id}/captions/${language}`, vtt, {
+      headers: { 'Content-Type': 'text/vtt' },
+    }),
+  deleteStreamCaption: (id: string, language: string) =>
+    api.delete(`/cloudflare/stream/videos/${id}/captions/${language}`),
   getStreamVideoUrls: (id: string) => api.get(`/cloudflare/stream/videos/${id}/urls`),
   getStreamEmbedCode: (id: string, options?: {
     autoplay?: boolean;
@@ -253,16 +431,35 @@ export const cloudflareApi = {
     width?: string;
     height?: string;
   }) => api.get(`/cloudflare/stream/videos/${id}/embed`, { params: options }),
+  getStreamSignedUrl: (id: string, options?: { ttl?: number; downloadable?: boolean }) =>
+    api.get(`/cloudflare/stream/videos/${id}/signed-url`, { params: options }),
   getStreamStats: () => api.get('/cloudflare/stream/stats'),
+  getStreamWebhook: () => api.get('/cloudflare/stream/webhook'),
+  setStreamWebhook: (notificationUrl: string) =>
+    api.put('/cloudflare/stream/webhook', { notification_url: notificationUrl }),
+  deleteStreamWebhook: () => api.delete('/cloudflare/stream/webhook'),
 
   // Stream Live Inputs
   listLiveInputs: () => api.get('/cloudflare/stream/live-inputs'),
-  createLiveInput: (options: { name?: string; recordingMode?: string; timeoutSeconds?: number }) =>
+  createLiveInput: (options: {
+    name?: string;
+    recordingMode?: string;
+    timeoutSeconds?: number;
+    requireSignedUrls?: boolean;
+    latencyMode?: 'standard' | 'low_latency' | 'dvr';
+    deleteRecordingAfterDays?: number;
+    defaultCreator?: string;
+  }) =>
     api.post('/cloudflare/stream/live-inputs', {
       name: options.name,
       recording_mode: options.recordingMode,
       timeout_seconds: options.timeoutSeconds,
+      require_signed_urls: options.requireSignedUrls,
+      latency_mode: options.latencyMode,
+      delete_recording_after_days: options.deleteRecordingAfterDays,
+      default_creator: options.defaultCreator,
     }),
+  getLiveInput: (id: string) => api.get(`/cloudflare/stream/live-inputs/${id}`),
   deleteLiveInput: (id: string) => api.delete(`/cloudflare/stream/live-inputs/${id}`),
   getLiveInputUrls: (id: string) => api.get(`/cloudflare/stream/live-inputs/${id}/urls`),
 };
@@ -13,7 +13,8 @@ export function CachePage() {
     if (!confirm('Purge entire cache? This cannot be undone.')) return;
     setIsPurging(true);
     try {
-      await cloudflareApi.purgeAll();
+      const { data: confirmation } = await cloudflareApi.purgeAll();
+      await cloudflareApi.purgeAll(confirmation.data.token);
       toast.success('Entire cache purged successfully');
     } catch (error) {
       toast.error('Failed to purge cache');
@@ -28,8 +29,12 @@ export function CachePage() {
 
     setIsPurging(true);
     try {
-      await cloudflareApi.purgeUrls(urls);
-      toast.success(`${urls.length} URLs purged successfully`);
+      const { data: response } = await cloudflareApi.purgeUrls(urls);
+      const skipped: string[] = response?.data?.skipped_urls ?? [];
+      if (skipped.length > 0) {
+        toast.error(`Skipped ${skipped.length} URLs outside this zone: ${skipped.join(', ')}`);
+      }
+      toast.success(response?.message ?? `${urls.length} URLs purged successfully`);
       reset();
     } catch (error) {
       toast.error('Failed to purge URLs');
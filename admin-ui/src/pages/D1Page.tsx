@@ -61,6 +61,7 @@ export function D1Page() {
   const [queryResults, setQueryResults] = useState<QueryResult | null>(null);
   const [showCreateModal, setShowCreateModal] = useState(false);
   const [showDeleteModal, setShowDeleteModal] = useState(false);
+  const [deleteConfirmName, setDeleteConfirmName] = useState('');
   const [showSchemaModal, setShowSchemaModal] = useState<string | null>(null);
   const [newDbName, setNewDbName] = useState('');
   const [queryHistory, setQueryHistory] = useState<QueryHistoryItem[]>([]);
@@ -105,14 +106,15 @@ export function D1Page() {
   });
 
   const deleteDbMutation = useMutation({
-    mutationFn: async (databaseId: string) => {
-      const response = await cloudflareApi.deleteD1Database(databaseId);
+    mutationFn: async ({ databaseId, confirmName }: { databaseId: string; confirmName: string }) => {
+      const response = await cloudflareApi.deleteD1Database(databaseId, confirmName);
       return response.data;
     },
     onSuccess: () => {
       queryClient.invalidateQueries({ queryKey: ['d1-databases'] });
       toast.success('Database deleted successfully');
       setShowDeleteModal(false);
+      setDeleteConfirmName('');
       setSelectedDb(null);
     },
     onError: () => {
@@ -771,19 +773,33 @@ export function D1Page() {
             <p className="text-neutral-300 mb-2">
               Are you sure you want to delete <strong className="text-white">{selectedDb.name}</strong>?
             </p>
-            <p className="text-sm text-red-400 mb-6">
+            <p className="text-sm text-red-400 mb-4">
               This action cannot be undone. All tables and data will be permanently deleted.
             </p>
+            <label className="block text-sm text-neutral-400 mb-2">
+              Type <strong className="text-white">{selectedDb.name}</strong> to confirm
+            </label>
+            <input
+              type="text"
+              value={deleteConfirmName}
+              onChange={(e) => setDeleteConfirmName(e.target.value)}
+              className="w-full bg-neutral-900 border border-neutral-700 rounded-lg px-4 py-3 mb-6 text-white placeholder-neutral-500 focus:outline-none focus:border-red-500 font-mono"
+            />
             <div className="flex justify-end gap-3">
               <button
-                onClick={() => setShowDeleteModal(false)}
+                onClick={() => {
+                  setShowDeleteModal(false);
+                  setDeleteConfirmName('');
+                }}
                 className="px-4 py-2 text-neutral-400 hover:text-white transition-colors"
               >
                 Cancel
               </button>
               <button
-                onClick={() => deleteDbMutation.mutate(selectedDb.uuid)}
-                disabled={deleteDbMutation.isPending}
+                onClick={() =>
+                  deleteDbMutation.mutate({ databaseId: selectedDb.uuid, confirmName: deleteConfirmName })
+                }
+                disabled={deleteDbMutation.isPending || deleteConfirmName !== selectedDb.name}
                 className="flex items-center gap-2 px-5 py-2 bg-red-500 hover:bg-red-600 disabled:bg-neutral-600 text-white rounded-lg transition-colors font-medium"
               >
                 {deleteDbMutation.isPending && <Loader2 className="w-4 h-4 animate-spin" />}
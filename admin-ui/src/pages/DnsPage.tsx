@@ -14,6 +14,8 @@ import {
   Search,
   Check,
   X,
+  ChevronLeft,
+  ChevronRight,
 } from 'lucide-react';
 import { cloudflareApi } from '../lib/api';
 import toast from 'react-hot-toast';
@@ -24,6 +26,7 @@ interface DnsRecord {
   type: string;
   name: string;
   content: string;
+  proxiable: boolean;
   proxied: boolean;
   ttl: number;
   priority?: number;
@@ -37,10 +40,17 @@ export function DnsPage() {
   const [editingRecord, setEditingRecord] = useState<DnsRecord | null>(null);
   const [searchTerm, setSearchTerm] = useState('');
   const [filterType, setFilterType] = useState<string>('all');
+  const [page, setPage] = useState(1);
 
   const { data: records, isLoading } = useQuery({
-    queryKey: ['dns-records'],
-    queryFn: () => cloudflareApi.listDnsRecords(),
+    queryKey: ['dns-records', page, searchTerm, filterType],
+    queryFn: () =>
+      cloudflareApi.listDnsRecords({
+        page,
+        per_page: 100,
+        name: searchTerm.trim() || undefined,
+        type: filterType === 'all' ? undefined : filterType,
+      }),
   });
 
   const createMutation = useMutation({
@@ -64,6 +74,17 @@ export function DnsPage() {
     onError: () => toast.error('Failed to update record'),
   });
 
+  const proxiedMutation = useMutation({
+    mutationFn: ({ id, proxied }: { id: string; proxied: boolean }) =>
+      cloudflareApi.setDnsProxied(id, proxied),
+    onSuccess: (_, { proxied }) => {
+      queryClient.invalidateQueries({ queryKey: ['dns-records'] });
+      toast.success(proxied ? 'Proxy enabled' : 'Proxy disabled');
+    },
+    onError: (error: any) =>
+      toast.error(error.response?.data?.error?.message ?? 'Failed to change proxy status'),
+  });
+
   const deleteMutation = useMutation({
     mutationFn: (id: string) => cloudflareApi.deleteDnsRecord(id),
     onSuccess: () => {
@@ -88,14 +109,9 @@ export function DnsPage() {
     }
   };
 
+  // Search and type filters are applied by the API, so paging covers the whole zone
   const dnsRecords: DnsRecord[] = records?.data?.data || [];
-  const filteredRecords = dnsRecords.filter((record) => {
-    const matchesSearch =
-      record.name.toLowerCase().includes(searchTerm.toLowerCase()) ||
-      record.content.toLowerCase().includes(searchTerm.toLowerCase());
-    const matchesType = filterType === 'all' || record.type === filterType;
-    return matchesSearch && matchesType;
-  });
+  const totalPages: number = records?.data?.total_pages || 1;
 
   return (
     <div className="p-6">
@@ -129,15 +145,21 @@ export function DnsPage() {
           <Search className="absolute left-3 top-1/2 -translate-y-1/2 w-5 h-5 text-gray-400" />
           <input
             type="text"
-            placeholder="Search records..."
+            placeholder="Search by record name..."
             value={searchTerm}
-            onChange={(e) => setSearchTerm(e.target.value)}
+            onChange={(e) => {
+              setSearchTerm(e.target.value);
+              setPage(1);
+            }}
             className="w-full pl-10 pr-4 py-2 border border-neutral-600 rounded-lg bg-neutral-900 text-neutral-100 placeholder-neutral-500"
           />
         </div>
         <select
           value={filterType}
-          onChange={(e) => setFilterType(e.target.value)}
+          onChange={(e) => {
+            setFilterType(e.target.value);
+            setPage(1);
+          }}
           className="px-4 py-2 border border-neutral-600 rounded-lg bg-neutral-900 text-neutral-100"
         >
           <option value="all">All Types</option>
@@ -180,14 +202,14 @@ export function DnsPage() {
                   Loading records...
                 </td>
               </tr>
-            ) : filteredRecords.length === 0 ? (
+            ) : dnsRecords.length === 0 ? (
               <tr>
                 <td colSpan={6} className="px-6 py-8 text-center text-neutral-400">
                   No DNS records found
                 </td>
               </tr>
             ) : (
-              filteredRecords.map((record) => (
+              dnsRecords.map((record) => (
                 <tr key={record.id} className="hover:bg-neutral-700/30">
                   <td className="px-6 py-4">
                     <span className={clsx(
@@ -205,11 +227,18 @@ export function DnsPage() {
                   <td className="px-6 py-4 font-mono text-sm text-neutral-100">{record.name}</td>
                   <td className="px-6 py-4 font-mono text-sm max-w-xs truncate text-neutral-300">{record.content}</td>
                   <td className="px-6 py-4">
-                    {record.proxied ? (
-                      <Cloud className="w-5 h-5 text-primary-500" />
-                    ) : (
-                      <Globe className="w-5 h-5 text-neutral-500" />
-                    )}
+                    <button
+                      onClick={() => proxiedMutation.mutate({ id: record.id, proxied: !record.proxied })}
+                      disabled={!record.proxiable || proxiedMutation.isPending}
+                      title={record.proxiable ? 'Toggle proxy' : 'This record type cannot be proxied'}
+                      className="disabled:cursor-not-allowed disabled:opacity-50"
+                    >
+                      {record.proxied ? (
+                        <Cloud className="w-5 h-5 text-primary-500" />
+                      ) : (
+                        <Globe className="w-5 h-5 text-neutral-500" />
+                      )}
+                    </button>
                   </td>
                   <td className="px-6 py-4 text-sm text-neutral-400">
                     {record.ttl === 1 ? 'Auto' : `${record.ttl}s`}
@@ -237,6 +266,29 @@ export function DnsPage() {
             )}
           </tbody>
         </table>
+        {totalPages > 1 && (
+          <div className="flex items-center justify-between px-4 py-3 border-t border-neutral-700/50">
+            <span className="text-sm text-neutral-400">
+              Page {page} of {totalPages}
+            </span>
+            <div className="flex gap-2">
+              <button
+                onClick={() => setPage((p) => p - 1)}
+                disabled={page <= 1}
+                className="p-1 text-neutral-400 hover:text-white disabled:opacity-40"
+              >
+                <ChevronLeft className="w-4 h-4" />
+              </button>
+              <button
+                onClick={() => setPage((p) => p + 1)}
+                disabled={page >= totalPages}
+                className="p-1 text-neutral-400 hover:text-white disabled:opacity-40"
+              >
+                <ChevronRight className="w-4 h-4" />
+              </button>
+            </div>
+          </div>
+        )}
       </div>
 
       {/* Add/Edit Modal */}
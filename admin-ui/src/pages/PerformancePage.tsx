@@ -31,6 +31,23 @@ export function PerformancePage() {
     onError: () => toast.error('Failed to update settings'),
   });
 
+  const { data: argoData } = useQuery({
+    queryKey: ['argo'],
+    queryFn: () => cloudflareApi.getArgo(),
+  });
+
+  const updateArgoMutation = useMutation({
+    mutationFn: (settings: { smart_routing?: boolean; tiered_caching?: boolean }) =>
+      cloudflareApi.updateArgo(settings),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['argo'] });
+      toast.success('Argo settings updated');
+    },
+    onError: (error: any) =>
+      toast.error(error.response?.data?.error?.message ?? 'Failed to update Argo settings'),
+  });
+
+  const argo = argoData?.data?.data;
   const settings = zoneSettings?.data?.data || [];
   const getSetting = (id: string) => {
     const setting = settings.find((s: any) => s.id === id);
@@ -224,21 +241,15 @@ export function PerformancePage() {
             <ToggleSetting
               label="Argo Smart Routing"
               description="Route traffic through fastest paths"
-              enabled={getSetting('argo')?.smart_routing === 'on'}
-              onChange={(enabled) => updateSetting('argo', {
-                ...getSetting('argo'),
-                smart_routing: enabled ? 'on' : 'off'
-              })}
+              enabled={argo?.smart_routing === true}
+              onChange={(enabled) => updateArgoMutation.mutate({ smart_routing: enabled })}
             />
 
             <ToggleSetting
               label="Argo Tiered Caching"
               description="Reduce origin requests with tiered caching"
-              enabled={getSetting('argo')?.tiered_caching === 'on'}
-              onChange={(enabled) => updateSetting('argo', {
-                ...getSetting('argo'),
-                tiered_caching: enabled ? 'on' : 'off'
-              })}
+              enabled={argo?.tiered_caching === true}
+              onChange={(enabled) => updateArgoMutation.mutate({ tiered_caching: enabled })}
             />
 
             <div className="pt-4 border-t border-neutral-700/50">
@@ -108,7 +108,7 @@ export function StreamPage() {
         return data;
       }
       const response = await cloudflareApi.listStreamVideos();
-      // API returns { data: { videos: [...] } }
+      // API returns { videos: [...], page, total_pages }
       const data = response.data?.data ?? response.data;
       return data;
     },
@@ -42,6 +42,7 @@ export const useCloudflareStore = create<CloudflareState>()(
           set({
             isConnected: response.data.data.connected,
             isLoading: false,
+            error: response.data.data.error ?? null,
           });
         } catch (error: any) {
           set({
@@ -90,7 +91,10 @@ export const useCloudflareStore = create<CloudflareState>()(
       purgeAllCache: async () => {
         set({ isLoading: true });
         try {
-          await api.post('/cloudflare/cache/purge/all');
+          const { data: confirmation } = await api.post('/cloudflare/cache/purge/all');
+          await api.post('/cloudflare/cache/purge/all', {
+            confirmation_token: confirmation.data.token,
+          });
           set({ isLoading: false });
         } catch (error: any) {
           set({ isLoading: false, error: error.message });
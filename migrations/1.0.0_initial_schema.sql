@@ -16,8 +16,8 @@ CREATE TABLE IF NOT EXISTS cloudflare_dns_records (
     updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
 );
 
-CREATE INDEX idx_dns_records_name ON cloudflare_dns_records(name);
-CREATE INDEX idx_dns_records_type ON cloudflare_dns_records(record_type);
+CREATE INDEX IF NOT EXISTS idx_dns_records_name ON cloudflare_dns_records(name);
+CREATE INDEX IF NOT EXISTS idx_dns_records_type ON cloudflare_dns_records(record_type);
 
 -- Cache Purge Events Log
 CREATE TABLE IF NOT EXISTS cloudflare_cache_events (
@@ -28,8 +28,8 @@ CREATE TABLE IF NOT EXISTS cloudflare_cache_events (
     created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
 );
 
-CREATE INDEX idx_cache_events_type ON cloudflare_cache_events(event_type);
-CREATE INDEX idx_cache_events_created ON cloudflare_cache_events(created_at);
+CREATE INDEX IF NOT EXISTS idx_cache_events_type ON cloudflare_cache_events(event_type);
+CREATE INDEX IF NOT EXISTS idx_cache_events_created ON cloudflare_cache_events(created_at);
 
 -- Security Events Log
 CREATE TABLE IF NOT EXISTS cloudflare_security_events (
@@ -43,9 +43,9 @@ CREATE TABLE IF NOT EXISTS cloudflare_security_events (
     created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
 );
 
-CREATE INDEX idx_security_events_type ON cloudflare_security_events(event_type);
-CREATE INDEX idx_security_events_ip ON cloudflare_security_events(ip_address);
-CREATE INDEX idx_security_events_created ON cloudflare_security_events(created_at);
+CREATE INDEX IF NOT EXISTS idx_security_events_type ON cloudflare_security_events(event_type);
+CREATE INDEX IF NOT EXISTS idx_security_events_ip ON cloudflare_security_events(ip_address);
+CREATE INDEX IF NOT EXISTS idx_security_events_created ON cloudflare_security_events(created_at);
 
 -- Workers Deployments
 CREATE TABLE IF NOT EXISTS cloudflare_workers (
@@ -94,7 +94,7 @@ CREATE TABLE IF NOT EXISTS cloudflare_analytics_snapshots (
     created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
 );
 
-CREATE INDEX idx_analytics_period ON cloudflare_analytics_snapshots(period_start, period_end);
+CREATE INDEX IF NOT EXISTS idx_analytics_period ON cloudflare_analytics_snapshots(period_start, period_end);
 
 -- Page Rules
 CREATE TABLE IF NOT EXISTS cloudflare_page_rules (
@@ -132,8 +132,8 @@ CREATE TABLE IF NOT EXISTS cloudflare_ip_access_rules (
     created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
 );
 
-CREATE INDEX idx_ip_rules_mode ON cloudflare_ip_access_rules(mode);
-CREATE INDEX idx_ip_rules_ip ON cloudflare_ip_access_rules(ip_address);
+CREATE INDEX IF NOT EXISTS idx_ip_rules_mode ON cloudflare_ip_access_rules(mode);
+CREATE INDEX IF NOT EXISTS idx_ip_rules_ip ON cloudflare_ip_access_rules(ip_address);
 
 -- SSL Certificates
 CREATE TABLE IF NOT EXISTS cloudflare_certificates (
@@ -0,0 +1,13 @@
+-- RustCloudflare Plugin - Cache Tag Registry
+-- Version: 1.1.0
+
+-- Cache tags attached to each piece of content, keyed as "<content_type>:<content_id>"
+CREATE TABLE IF NOT EXISTS cloudflare_cache_tags (
+    id SERIAL PRIMARY KEY,
+    content_key VARCHAR(255) NOT NULL,
+    tag VARCHAR(1024) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE (content_key, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_cache_tags_content_key ON cloudflare_cache_tags(content_key);
@@ -0,0 +1,14 @@
+-- RustCloudflare Plugin - Stream Webhook Events
+-- Version: 1.2.0
+
+-- Verified notifications received from the Stream webhook
+CREATE TABLE IF NOT EXISTS cloudflare_stream_events (
+    id SERIAL PRIMARY KEY,
+    video_uid VARCHAR(64) NOT NULL,
+    state VARCHAR(32),
+    details JSONB,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_stream_events_video ON cloudflare_stream_events(video_uid);
+CREATE INDEX IF NOT EXISTS idx_stream_events_created ON cloudflare_stream_events(created_at);
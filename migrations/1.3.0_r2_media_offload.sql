@@ -0,0 +1,15 @@
+-- RustCloudflare Plugin - R2 Media Offload
+-- Version: 1.3.0
+
+-- Media library attachments copied to R2, so public URLs can be mapped back
+CREATE TABLE IF NOT EXISTS cloudflare_r2_media (
+    attachment_id VARCHAR(255) PRIMARY KEY,
+    bucket VARCHAR(255) NOT NULL,
+    object_key VARCHAR(1024) NOT NULL,
+    local_path TEXT NOT NULL,
+    public_url TEXT,
+    size_bytes BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_r2_media_public_url ON cloudflare_r2_media(public_url);
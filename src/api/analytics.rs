@@ -7,6 +7,8 @@ use axum::{
 use serde::Deserialize;
 use std::sync::Arc;
 use crate::error::CloudflareResult;
+use crate::services::analytics::dashboard_hours;
+use crate::services::cache::CacheStatsBucket;
 use crate::services::CloudflareServices;
 
 #[derive(Debug, Deserialize)]
@@ -15,12 +17,19 @@ pub struct AnalyticsQuery {
     pub hours: Option<i32>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CacheTimeseriesQuery {
+    pub hours: Option<i32>,
+    #[serde(default)]
+    pub bucket: CacheStatsBucket,
+}
+
 /// Get dashboard analytics
 pub async fn get_analytics(
     State(services): State<Arc<CloudflareServices>>,
     Query(query): Query<AnalyticsQuery>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let hours = query.hours.unwrap_or(24);
+    let hours = dashboard_hours(query.hours.unwrap_or(24));
     let analytics = services.analytics.get_dashboard(hours).await?;
 
     Ok(Json(serde_json::json!({
@@ -34,7 +43,7 @@ pub async fn get_zone_analytics(
     State(services): State<Arc<CloudflareServices>>,
     Query(query): Query<AnalyticsQuery>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let hours = query.hours.unwrap_or(24);
+    let hours = dashboard_hours(query.hours.unwrap_or(24));
     let analytics = services.analytics.get_dashboard(hours).await?;
 
     Ok(Json(serde_json::json!({
@@ -46,6 +55,18 @@ pub async fn get_zone_analytics(
     })))
 }
 
+/// Drop cached analytics so the next request fetches fresh data
+pub async fn refresh_analytics(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    services.analytics.invalidate_analytics_cache().await;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Analytics cache cleared"
+    })))
+}
+
 /// Get traffic summary
 pub async fn get_traffic_summary(
     State(services): State<Arc<CloudflareServices>>,
@@ -75,7 +96,7 @@ pub async fn get_bandwidth_stats(
     State(services): State<Arc<CloudflareServices>>,
     Query(query): Query<AnalyticsQuery>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let hours = query.hours.unwrap_or(24);
+    let hours = dashboard_hours(query.hours.unwrap_or(24));
     let analytics = services.analytics.get_dashboard(hours).await?;
     let totals = analytics.totals.unwrap_or_default();
     let bandwidth = totals.bandwidth.unwrap_or_default();
@@ -104,7 +125,7 @@ pub async fn get_security_summary(
     State(services): State<Arc<CloudflareServices>>,
     Query(query): Query<AnalyticsQuery>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let hours = query.hours.unwrap_or(24);
+    let hours = dashboard_hours(query.hours.unwrap_or(24));
     let analytics = services.analytics.get_dashboard(hours).await?;
     let totals = analytics.totals.unwrap_or_default();
     let threats = totals.threats.unwrap_or_default();
@@ -125,7 +146,7 @@ pub async fn get_cache_analytics(
     State(services): State<Arc<CloudflareServices>>,
     Query(query): Query<AnalyticsQuery>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let hours = query.hours.unwrap_or(24);
+    let hours = dashboard_hours(query.hours.unwrap_or(24));
     let stats = services.cache.get_cache_stats(hours).await?;
 
     Ok(Json(serde_json::json!({
@@ -150,12 +171,30 @@ pub async fn get_cache_analytics(
     })))
 }
 
+/// Get cache hit ratio and bytes per time bucket, for charting
+pub async fn get_cache_timeseries(
+    State(services): State<Arc<CloudflareServices>>,
+    Query(query): Query<CacheTimeseriesQuery>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let hours = query.hours.unwrap_or(24);
+    let points = services.cache.get_cache_stats_timeseries(hours, query.bucket).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "period_hours": hours,
+            "bucket": query.bucket,
+            "points": points
+        }
+    })))
+}
+
 /// Get performance metrics
 pub async fn get_performance_metrics(
     State(services): State<Arc<CloudflareServices>>,
     Query(query): Query<AnalyticsQuery>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let hours = query.hours.unwrap_or(24);
+    let hours = dashboard_hours(query.hours.unwrap_or(24));
     let analytics = services.analytics.get_dashboard(hours).await?;
 
     Ok(Json(serde_json::json!({
@@ -187,3 +226,36 @@ fn format_bytes(bytes: i64) -> String {
         format!("{} B", bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::analytics::MAX_DASHBOARD_HOURS;
+    use crate::test_support::{lazy_pool, mock_client};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_period_hours_reports_the_clamped_window() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "viewer": { "zones": [] } },
+                "errors": null
+            })))
+            .mount(&server)
+            .await;
+        let services = Arc::new(CloudflareServices::new(mock_client(&server), lazy_pool()));
+        let query = |hours| Query(AnalyticsQuery { time_range: None, hours: Some(hours) });
+
+        let Json(body) = get_bandwidth_stats(State(services.clone()), query(10_000)).await.unwrap();
+        assert_eq!(body["data"]["period_hours"], MAX_DASHBOARD_HOURS);
+
+        let Json(body) = get_zone_analytics(State(services.clone()), query(0)).await.unwrap();
+        assert_eq!(body["data"]["period_hours"], 1);
+
+        let Json(body) = get_performance_metrics(State(services), query(48)).await.unwrap();
+        assert_eq!(body["data"]["period_hours"], 48);
+    }
+}
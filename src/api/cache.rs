@@ -2,11 +2,14 @@
 
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     Json,
 };
 use serde::Deserialize;
 use std::sync::Arc;
 use crate::error::CloudflareResult;
+use crate::models::{Page, Pagination};
+use crate::services::cache::{TieredCacheTopology, PURGE_ALL_CONFIRMATION_WINDOW};
 use crate::services::CloudflareServices;
 
 #[derive(Debug, Deserialize)]
@@ -14,11 +17,36 @@ pub struct PurgeUrlsRequest {
     pub urls: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PurgeUrlRequest {
+    pub url: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PurgeAllRequest {
+    pub confirmation_token: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PurgeTagsRequest {
     pub tags: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CacheReserveRequest {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TieredCacheRequest {
+    pub topology: TieredCacheTopology,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeHostsRequest {
+    pub hosts: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PurgePrefixRequest {
     pub prefix: String,
@@ -29,33 +57,107 @@ pub struct WarmCacheRequest {
     pub urls: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RespectOriginHeadersRequest {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeHistoryQuery {
+    pub event_type: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CacheStatsQuery {
     pub hours: Option<i32>,
 }
 
+/// `Idempotency-Key` header sent with a purge request, if any
+fn idempotency_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("Idempotency-Key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
 /// Purge cache by specific URLs
 pub async fn purge_cache(
     State(services): State<Arc<CloudflareServices>>,
+    headers: HeaderMap,
     Json(req): Json<PurgeUrlsRequest>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let result = services.cache.purge_urls(req.urls.clone()).await?;
+    let result = services
+        .cache
+        .purge_urls(req.urls.clone(), idempotency_key(&headers))
+        .await?;
+
+    let purged = req.urls.len().saturating_sub(result.skipped_urls.len());
 
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
             "id": result.id,
-            "purged_urls": req.urls.len()
+            "purged_urls": purged,
+            "skipped_urls": result.skipped_urls
         },
-        "message": format!("Successfully purged {} URLs", req.urls.len())
+        "message": if result.skipped_urls.is_empty() {
+            format!("Successfully purged {} URLs", purged)
+        } else {
+            format!(
+                "Purged {} URLs, skipped {} outside the zone",
+                purged,
+                result.skipped_urls.len()
+            )
+        }
     })))
 }
 
-/// Purge all cache
+/// Purge a single URL in the configured zone
+pub async fn purge_url(
+    State(services): State<Arc<CloudflareServices>>,
+    headers: HeaderMap,
+    Json(req): Json<PurgeUrlRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let url = req.url.trim();
+    let result = services.cache.purge_url(url, idempotency_key(&headers)).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "id": result.id
+        },
+        "message": format!("Successfully purged {}", url)
+    })))
+}
+
+/// Purge all cache.
+///
+/// Takes two calls: the first returns a confirmation token, and a second
+/// call quoting it within `PURGE_ALL_CONFIRMATION_WINDOW` does the purge.
 pub async fn purge_all(
     State(services): State<Arc<CloudflareServices>>,
+    headers: HeaderMap,
+    req: Option<Json<PurgeAllRequest>>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let result = services.cache.purge_all().await?;
+    let Json(req) = req.unwrap_or_default();
+    let Some(token) = req.confirmation_token.filter(|t| !t.is_empty()) else {
+        let token = services.cache.request_purge_all_confirmation();
+        return Ok(Json(serde_json::json!({
+            "success": true,
+            "data": {
+                "requires_confirmation": true,
+                "token": token,
+                "expires_in": PURGE_ALL_CONFIRMATION_WINDOW.as_secs()
+            },
+            "message": "Purging the entire cache needs confirmation; repeat the request with this token"
+        })));
+    };
+
+    let result = services
+        .cache
+        .purge_all_confirmed(&token, idempotency_key(&headers))
+        .await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
@@ -69,9 +171,13 @@ pub async fn purge_all(
 /// Purge cache by tags (Enterprise only)
 pub async fn purge_by_tags(
     State(services): State<Arc<CloudflareServices>>,
+    headers: HeaderMap,
     Json(req): Json<PurgeTagsRequest>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let result = services.cache.purge_tags(req.tags.clone()).await?;
+    let result = services
+        .cache
+        .purge_tags(req.tags.clone(), idempotency_key(&headers))
+        .await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
@@ -83,6 +189,27 @@ pub async fn purge_by_tags(
     })))
 }
 
+/// Purge everything cached for some hostnames (Enterprise only)
+pub async fn purge_by_hosts(
+    State(services): State<Arc<CloudflareServices>>,
+    headers: HeaderMap,
+    Json(req): Json<PurgeHostsRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let result = services
+        .cache
+        .purge_hosts(req.hosts.clone(), idempotency_key(&headers))
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "id": result.id,
+            "purged_hosts": req.hosts.len()
+        },
+        "message": format!("Successfully purged cache for {} hosts", req.hosts.len())
+    })))
+}
+
 /// Purge cache by prefix (Enterprise only)
 pub async fn purge_by_prefix(
     State(services): State<Arc<CloudflareServices>>,
@@ -128,6 +255,133 @@ pub async fn get_cache_status(
     })))
 }
 
+/// Get whether the edge honors origin cache headers
+pub async fn get_respect_origin_headers(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let enabled = services.cache.get_respect_origin_headers().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "enabled": enabled
+        }
+    })))
+}
+
+/// Get whether Cache Reserve is on
+pub async fn get_cache_reserve(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let enabled = services.cache.get_cache_reserve().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "enabled": enabled
+        }
+    })))
+}
+
+/// Turn Cache Reserve on or off (Business and Enterprise)
+pub async fn set_cache_reserve(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<CacheReserveRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let enabled = services.cache.set_cache_reserve(req.enabled).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "enabled": enabled
+        },
+        "message": if enabled { "Cache Reserve enabled" } else { "Cache Reserve disabled" }
+    })))
+}
+
+/// Get the tiered cache topology
+pub async fn get_tiered_cache(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let topology = services.cache.get_tiered_cache_topology().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "topology": topology
+        }
+    })))
+}
+
+/// Choose a tiered cache topology
+pub async fn set_tiered_cache(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<TieredCacheRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let topology = services.cache.set_tiered_cache_topology(req.topology).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "topology": topology
+        },
+        "message": format!("Tiered cache topology set to {}", topology.as_str())
+    })))
+}
+
+/// Get the URLs the last auto-purge dry run would have purged
+pub async fn get_last_dry_run(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let dry_run = services.cache.last_dry_run().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": dry_run
+    })))
+}
+
+/// Logged purge events, newest first
+pub async fn get_purge_history(
+    State(services): State<Arc<CloudflareServices>>,
+    pagination: Pagination,
+    Query(query): Query<PurgeHistoryQuery>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let event_type = query.event_type.as_deref().filter(|t| !t.is_empty());
+    let events = services
+        .cache
+        .list_purge_events(pagination.per_page as i64, pagination.offset() as i64, event_type)
+        .await?;
+    let total = services.cache.count_purge_events(event_type).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": Page::new(events, pagination, total as usize),
+        "total": total
+    })))
+}
+
+/// Make the edge honor origin `Cache-Control`/`Expires` instead of fixed edge TTLs
+pub async fn set_respect_origin_headers(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<RespectOriginHeadersRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let warnings = services.cache.set_respect_origin_headers(req.enabled).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "enabled": req.enabled
+        },
+        "warnings": warnings,
+        "message": if req.enabled {
+            "Edge cache now respects origin headers"
+        } else {
+            "Edge cache no longer respects origin headers"
+        }
+    })))
+}
+
 /// Warm cache by pre-fetching URLs
 pub async fn warm_cache(
     State(_services): State<Arc<CloudflareServices>>,
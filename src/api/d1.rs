@@ -2,12 +2,14 @@
 
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{header, StatusCode},
+    response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use crate::error::CloudflareResult;
 use crate::models::{D1Database, D1QueryResult};
 use crate::services::CloudflareServices;
 
@@ -23,6 +25,13 @@ pub struct CreateDatabaseRequest {
     pub name: String,
 }
 
+/// Delete database request
+#[derive(Debug, Deserialize)]
+pub struct DeleteDatabaseRequest {
+    /// Must match the database's name
+    pub confirm_name: String,
+}
+
 /// Query request
 #[derive(Debug, Deserialize)]
 pub struct QueryRequest {
@@ -35,6 +44,13 @@ pub struct BatchQueryRequest {
     pub statements: Vec<String>,
 }
 
+/// Import request
+#[derive(Debug, Deserialize)]
+pub struct ImportRequest {
+    /// SQL dump, e.g. from the export endpoint
+    pub sql: String,
+}
+
 /// Table schema response
 #[derive(Debug, Serialize)]
 pub struct TableSchemaResponse {
@@ -83,6 +99,19 @@ pub async fn create_database(
     Ok(Json(database))
 }
 
+/// Delete a database, confirmed by its name
+pub async fn delete_database(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(database_id): Path<String>,
+    Json(req): Json<DeleteDatabaseRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    services.d1.delete_database(&database_id, &req.confirm_name).await?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Database deleted"
+    })))
+}
+
 /// Execute a SQL query
 pub async fn execute_query(
     State(services): State<Arc<CloudflareServices>>,
@@ -113,6 +142,47 @@ pub async fn execute_batch(
     Ok(Json(results))
 }
 
+/// Export a database as an SQL dump file
+pub async fn export_database(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(database_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let dump = services
+        .d1
+        .export(&database_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/sql".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.sql\"", database_id),
+            ),
+        ],
+        dump,
+    ))
+}
+
+/// Restore an SQL dump into a database
+pub async fn import_database(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(database_id): Path<String>,
+    Json(req): Json<ImportRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let statements = services
+        .d1
+        .import(&database_id, &req.sql)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "statements": statements
+    })))
+}
+
 /// List tables in a database
 pub async fn list_tables(
     State(services): State<Arc<CloudflareServices>>,
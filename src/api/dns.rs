@@ -7,7 +7,7 @@ use axum::{
 use serde::Deserialize;
 use std::sync::Arc;
 use crate::error::CloudflareResult;
-use crate::models::{CreateDnsRecord, DnsListParams, UpdateDnsRecord};
+use crate::models::{CreateDnsRecord, DnsListParams, Pagination, UpdateDnsRecord};
 use crate::services::CloudflareServices;
 
 /// Query parameters for listing DNS records
@@ -17,8 +17,17 @@ pub struct ListDnsQuery {
     pub record_type: Option<String>,
     pub name: Option<String>,
     pub content: Option<String>,
-    pub page: Option<i32>,
-    pub per_page: Option<i32>,
+    /// `all` (default) or `any` of the filters must match
+    #[serde(rename = "match")]
+    pub match_type: Option<String>,
+}
+
+/// Query parameters for scanning a domain's existing records
+#[derive(Debug, Deserialize)]
+pub struct ScanDnsQuery {
+    pub domain: String,
+    /// Comma-separated record types to look up, e.g. `A,MX`; all probes if unset
+    pub types: Option<String>,
 }
 
 /// Import zone file request
@@ -27,25 +36,59 @@ pub struct ImportZoneRequest {
     pub zone_file: String,
 }
 
-/// List all DNS records
+/// Enable/disable DNSSEC request
+#[derive(Debug, Deserialize)]
+pub struct SetDnssecRequest {
+    pub enabled: bool,
+}
+
+/// Toggle proxy request
+#[derive(Debug, Deserialize)]
+pub struct SetProxiedRequest {
+    pub proxied: bool,
+}
+
+/// List a page of DNS records
 pub async fn list_records(
     State(services): State<Arc<CloudflareServices>>,
     Query(query): Query<ListDnsQuery>,
+    pagination: Pagination,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let params = DnsListParams {
+        record_type: query.record_type,
+        name: query.name,
+        content: query.content,
+        match_type: query.match_type,
+        ..Default::default()
+    };
+
+    let page = services.dns.list_page(params, pagination).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": page.items,
+        "total": page.total,
+        "page": page.page,
+        "per_page": page.per_page,
+        "total_pages": page.total_pages
+    })))
+}
+
+/// Search DNS records by name, type and content, returning every match across all pages
+pub async fn search_records(
+    State(services): State<Arc<CloudflareServices>>,
+    Query(query): Query<ListDnsQuery>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let params = if query.record_type.is_some() || query.name.is_some() || query.content.is_some() {
-        Some(DnsListParams {
+    let records = services
+        .dns
+        .search(DnsListParams {
             record_type: query.record_type,
             name: query.name,
             content: query.content,
-            page: query.page,
-            per_page: query.per_page,
+            match_type: query.match_type,
             ..Default::default()
         })
-    } else {
-        None
-    };
-
-    let records = services.dns.list(params).await?;
+        .await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
@@ -96,6 +139,25 @@ pub async fn update_record(
     })))
 }
 
+/// Turn the proxy on or off for a DNS record
+pub async fn set_proxied(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(id): Path<String>,
+    Json(req): Json<SetProxiedRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let record = services.dns.set_proxied(&id, req.proxied).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": record,
+        "message": format!(
+            "Proxy {} for {}",
+            if record.proxied { "enabled" } else { "disabled" },
+            record.name
+        )
+    })))
+}
+
 /// Delete a DNS record
 pub async fn delete_record(
     State(services): State<Arc<CloudflareServices>>,
@@ -133,6 +195,105 @@ pub async fn import_zone(
     })))
 }
 
+/// Validate a zone file and preview what an import would change, without writing
+pub async fn preview_import(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<ImportZoneRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let preview = services.dns.preview_import(&req.zone_file).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "valid": preview.errors.is_empty(),
+        "message": format!(
+            "{} to create, {} to update, {} unchanged, {} invalid",
+            preview.to_create, preview.to_update, preview.to_skip, preview.errors.len()
+        ),
+        "data": preview
+    })))
+}
+
+/// Preview what a sync would change in the local database
+pub async fn diff_records(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let diff = services.dns.diff().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "in_sync": diff.is_empty(),
+        "message": format!(
+            "{} added, {} removed, {} modified",
+            diff.added.len(), diff.removed.len(), diff.modified.len()
+        ),
+        "data": diff
+    })))
+}
+
+/// Suggest records to import from the domain's current nameservers
+pub async fn scan_existing(
+    State(services): State<Arc<CloudflareServices>>,
+    Query(query): Query<ScanDnsQuery>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let records = match query.types.as_deref() {
+        Some(types) => {
+            let types: Vec<String> = types
+                .split(',')
+                .map(|t| t.trim().to_uppercase())
+                .filter(|t| !t.is_empty())
+                .collect();
+            let probes = services.dns.scan_probes_for_types(&types)?;
+            services.dns.scan_existing_with(&query.domain, &probes).await?
+        }
+        None => services.dns.scan_existing(&query.domain).await?,
+    };
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Found {} records to import", records.len()),
+        "data": records
+    })))
+}
+
+/// Get the zone's DNSSEC status
+pub async fn get_dnssec(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let status = services.dns.get_dnssec().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "enabled": status.is_enabled(),
+        "ds_record": status.ds,
+        "data": status
+    })))
+}
+
+/// Enable or disable DNSSEC
+pub async fn set_dnssec(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<SetDnssecRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let status = services.dns.set_dnssec(req.enabled).await?;
+
+    let message = match (&status.ds, req.enabled) {
+        (Some(ds), true) => format!(
+            "DNSSEC enabled. Add this DS record at your domain registrar to complete setup: {}",
+            ds
+        ),
+        (None, true) => "DNSSEC enabled. The DS record will be available shortly.".to_string(),
+        (_, false) => "DNSSEC disabled. Remove the DS record at your domain registrar.".to_string(),
+    };
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "enabled": status.is_enabled(),
+        "ds_record": status.ds,
+        "data": status,
+        "message": message
+    })))
+}
+
 /// Sync DNS records from Cloudflare to local database
 pub async fn sync_records(
     State(services): State<Arc<CloudflareServices>>,
@@ -12,12 +12,20 @@ pub mod ssl;
 pub mod stream;
 pub mod rules;
 pub mod d1;
+pub mod pagination;
 
 use axum::{
+    extract::{DefaultBodyLimit, State},
+    http::header,
+    response::IntoResponse,
     routing::{get, post, put, delete, patch},
     Router,
 };
 use std::sync::Arc;
+use crate::circuit_breaker::CircuitState;
+use crate::error::CloudflareError;
+use crate::metrics::{Metrics, PROMETHEUS_CONTENT_TYPE};
+use crate::models::ConnectionStatus;
 use crate::services::CloudflareServices;
 
 /// Create the API router with all routes
@@ -26,6 +34,7 @@ pub fn create_router(services: Arc<CloudflareServices>) -> Router {
     Router::new()
         // Status & Connection
         .route("/status", get(get_status))
+        .route("/metrics", get(get_metrics))
         .route("/connection", get(oauth::get_connection_status))
 
         // OAuth / Authentication routes
@@ -35,36 +44,71 @@ pub fn create_router(services: Arc<CloudflareServices>) -> Router {
         .route("/auth/sso-complete", post(oauth::sso_complete))
         .route("/auth/verify-token", post(oauth::verify_token))
         .route("/auth/save-credentials", post(oauth::save_credentials))
+        .route("/auth/rotate-token", post(oauth::rotate_token))
+        .route("/auth/test-capabilities", post(oauth::test_capabilities))
         .route("/auth/disconnect", post(oauth::disconnect))
         .route("/auth/accounts", post(oauth::list_accounts))
         .route("/auth/zones", post(oauth::list_zones))
 
         // Cache routes
         .route("/cache/purge", post(cache::purge_cache))
+        .route("/cache/purge/url", post(cache::purge_url))
         .route("/cache/purge/all", post(cache::purge_all))
         .route("/cache/purge/tags", post(cache::purge_by_tags))
         .route("/cache/purge/prefix", post(cache::purge_by_prefix))
+        .route("/cache/purge/hosts", post(cache::purge_by_hosts))
         .route("/cache/status", get(cache::get_cache_status))
+        .route("/cache/respect-origin-headers", get(cache::get_respect_origin_headers))
+        .route("/cache/respect-origin-headers", put(cache::set_respect_origin_headers))
+        .route("/cache/reserve", get(cache::get_cache_reserve))
+        .route("/cache/reserve", put(cache::set_cache_reserve))
+        .route("/cache/tiered", get(cache::get_tiered_cache))
+        .route("/cache/tiered", put(cache::set_tiered_cache))
+        .route("/cache/dry-run/last", get(cache::get_last_dry_run))
+        .route("/cache/history", get(cache::get_purge_history))
 
         // DNS routes
         .route("/dns/records", get(dns::list_records))
         .route("/dns/records", post(dns::create_record))
+        .route("/dns/records/search", get(dns::search_records))
         .route("/dns/records/:id", get(dns::get_record))
         .route("/dns/records/:id", put(dns::update_record))
         .route("/dns/records/:id", delete(dns::delete_record))
+        .route("/dns/records/:id/proxied", patch(dns::set_proxied))
         .route("/dns/export", get(dns::export_zone))
+        .route("/dns/import/preview", post(dns::preview_import))
+        .route("/dns/diff", get(dns::diff_records))
+        .route("/dns/scan", get(dns::scan_existing))
+        .route("/dns/dnssec", get(dns::get_dnssec))
+        .route("/dns/dnssec", put(dns::set_dnssec))
         .route("/dns/sync", post(dns::sync_records))
 
         // SSL/TLS routes
         .route("/ssl/settings", get(ssl::get_ssl_settings))
         .route("/ssl/mode", put(ssl::update_ssl_mode))
+        .route("/ssl/min-tls", put(ssl::update_min_tls_version))
+        .route("/ssl/tls13", put(ssl::update_tls_1_3))
         .route("/ssl/certificates", get(ssl::list_certificates))
+        .route("/ssl/custom-hostnames", get(ssl::list_custom_hostnames))
+        .route("/ssl/custom-hostnames", post(ssl::create_custom_hostname))
+        .route("/ssl/custom-hostnames/:id", get(ssl::get_custom_hostname))
+        .route("/ssl/custom-hostnames/:id", delete(ssl::delete_custom_hostname))
 
         // Security routes
         .route("/security/level", get(security::get_security_level))
         .route("/security/level", put(security::set_security_level))
         .route("/security/under-attack", post(security::toggle_under_attack))
         .route("/security/waf/rules", get(security::list_waf_rules))
+        .route("/security/waf/managed", get(security::list_managed_rulesets))
+        .route("/security/waf/managed", post(security::deploy_managed_ruleset))
+        .route("/security/events", get(security::get_security_events))
+        .route("/security/events/stream", get(security::stream_security_events))
+        .route("/security/bots", get(security::get_bot_management))
+        .route("/security/bots", put(security::update_bot_management))
+        .route("/security/custom-rules", get(security::list_custom_rules))
+        .route("/security/custom-rules", post(security::create_custom_rule))
+        .route("/security/custom-rules/:id", put(security::update_custom_rule))
+        .route("/security/custom-rules/:id", delete(security::delete_custom_rule))
         .route("/security/firewall/rules", get(security::list_firewall_rules))
         .route("/security/firewall/rules", post(security::create_firewall_rule))
         .route("/security/firewall/rules/:id", delete(security::delete_firewall_rule))
@@ -72,18 +116,38 @@ pub fn create_router(services: Arc<CloudflareServices>) -> Router {
         .route("/security/ip-access/block", post(security::block_ip))
         .route("/security/ip-access/allow", post(security::allow_ip))
         .route("/security/ip-access/rules/:id", delete(security::delete_ip_access_rule))
+        .route("/security/export", get(security::export_rules))
+        .route("/security/import", post(security::import_rules))
+        .route("/security/ip-lists", get(security::list_ip_lists))
+        .route("/security/ip-lists", post(security::create_ip_list))
+        .route("/security/ip-lists/operations/:id", get(security::get_ip_list_operation))
+        .route("/security/ip-lists/:id/items", get(security::list_ip_list_items))
+        .route("/security/ip-lists/:id/items", post(security::add_ip_list_items))
+        .route("/security/ip-lists/:id/items", delete(security::remove_ip_list_items))
+        .route("/security/turnstile/verify", post(security::verify_turnstile))
+        .route("/security/turnstile/widgets", get(security::list_turnstile_widgets))
+        .route("/security/turnstile/widgets", post(security::create_turnstile_widget))
+        .route("/security/turnstile/widgets/:sitekey", delete(security::delete_turnstile_widget))
+        .route(
+            "/security/turnstile/widgets/:sitekey/rotate-secret",
+            post(security::rotate_turnstile_secret),
+        )
 
         // Page Rules routes
         .route("/rules/pages", get(rules::list_page_rules))
         .route("/rules/pages", post(rules::create_page_rule))
+        .route("/rules/pages/:id", get(rules::get_page_rule))
         .route("/rules/pages/:id", put(rules::update_page_rule))
         .route("/rules/pages/:id", delete(rules::delete_page_rule))
+        .route("/rules/bulk-redirects/import", post(rules::import_bulk_redirects))
 
         // Workers routes
         .route("/workers", get(workers::list_workers))
         .route("/workers", post(workers::deploy_worker))
         .route("/workers/:name", get(workers::get_worker))
         .route("/workers/:name", delete(workers::delete_worker))
+        .route("/workers/:name/settings", get(workers::get_worker_settings))
+        .route("/workers/:name/tail", get(workers::tail_worker))
         .route("/workers/routes", get(workers::list_routes))
         .route("/workers/routes", post(workers::create_route))
         .route("/workers/routes/:id", delete(workers::delete_route))
@@ -94,26 +158,45 @@ pub fn create_router(services: Arc<CloudflareServices>) -> Router {
         .route("/workers/kv/:namespace/values/:key", get(workers::get_kv_value))
         .route("/workers/kv/:namespace/values/:key", put(workers::set_kv_value))
         .route("/workers/kv/:namespace/values/:key", delete(workers::delete_kv_value))
+        .route("/workers/kv/:namespace/entries/:key", get(workers::get_kv_entry))
 
         // R2 Storage routes
         .route("/r2/buckets", get(r2::list_buckets))
         .route("/r2/buckets", post(r2::create_bucket))
         .route("/r2/buckets/:name", delete(r2::delete_bucket))
+        .route("/r2/buckets/:name/cors", get(r2::get_bucket_cors))
+        .route("/r2/buckets/:name/cors", put(r2::set_bucket_cors))
         .route("/r2/buckets/:name/objects", get(r2::list_objects))
-        .route("/r2/buckets/:name/objects", post(r2::upload_object))
+        // Uploads are streamed to R2 in parts, so large media isn't capped by the body limit
+        .route(
+            "/r2/buckets/:name/objects",
+            post(r2::upload_object).layer(DefaultBodyLimit::disable()),
+        )
         .route("/r2/buckets/:name/objects/*key", get(r2::get_object))
         .route("/r2/buckets/:name/objects/*key", delete(r2::delete_object))
+        // A wildcard must end the path, so downloads can't live under /objects/*key
+        .route("/r2/buckets/:name/download/*key", get(r2::download_object))
 
         // Stream routes
         .route("/stream/videos", get(stream::list_videos))
         .route("/stream/videos/search", get(stream::search_videos))
+        .route("/stream/videos/copy", post(stream::copy_video))
         .route("/stream/videos/:id", get(stream::get_video))
         .route("/stream/videos/:id", delete(stream::delete_video))
+        .route("/stream/videos/:id/captions", get(stream::list_captions))
+        .route("/stream/videos/:id/captions/:lang", put(stream::upload_caption))
+        .route("/stream/videos/:id/captions/:lang", delete(stream::delete_caption))
         .route("/stream/videos/:id/urls", get(stream::get_video_urls))
         .route("/stream/videos/:id/embed", get(stream::get_embed_code))
+        .route("/stream/videos/:id/signed-url", get(stream::get_signed_url))
         .route("/stream/stats", get(stream::get_stats))
+        .route("/stream/webhook", get(stream::get_webhook))
+        .route("/stream/webhook", put(stream::set_webhook))
+        .route("/stream/webhook", delete(stream::delete_webhook))
+        .route("/stream/webhook", post(stream::receive_webhook))
         .route("/stream/live-inputs", get(stream::list_live_inputs))
         .route("/stream/live-inputs", post(stream::create_live_input))
+        .route("/stream/live-inputs/:id", get(stream::get_live_input))
         .route("/stream/live-inputs/:id", delete(stream::delete_live_input))
         .route("/stream/live-inputs/:id/urls", get(stream::get_live_input_urls))
 
@@ -121,8 +204,11 @@ pub fn create_router(services: Arc<CloudflareServices>) -> Router {
         .route("/d1/databases", get(d1::list_databases))
         .route("/d1/databases", post(d1::create_database))
         .route("/d1/databases/:id", get(d1::get_database))
+        .route("/d1/databases/:id", delete(d1::delete_database))
         .route("/d1/databases/:id/query", post(d1::execute_query))
         .route("/d1/databases/:id/batch", post(d1::execute_batch))
+        .route("/d1/databases/:id/export", get(d1::export_database))
+        .route("/d1/databases/:id/import", post(d1::import_database))
         .route("/d1/databases/:id/tables", get(d1::list_tables))
         .route("/d1/databases/:id/tables/:table/schema", get(d1::get_table_schema))
 
@@ -130,26 +216,86 @@ pub fn create_router(services: Arc<CloudflareServices>) -> Router {
         .route("/analytics", get(analytics::get_analytics))
         .route("/analytics/traffic", get(analytics::get_traffic_summary))
         .route("/analytics/security", get(analytics::get_security_summary))
+        .route("/analytics/cache/timeseries", get(analytics::get_cache_timeseries))
+        .route("/analytics/refresh", post(analytics::refresh_analytics))
 
         // Settings routes
         .route("/settings", get(settings::get_settings))
         .route("/settings", put(settings::update_settings))
+        .route("/settings/features", get(settings::get_feature_flags))
+        .route("/settings/features", put(settings::update_feature_flags))
+        .route("/settings/export", get(settings::export_settings))
+        .route("/settings/import", post(settings::import_settings))
+        .route("/settings/under-attack", put(settings::update_under_attack_auto_settings))
         .route("/zone", get(settings::get_zone_info))
+        .route("/zone/plan", get(settings::get_zone_plan))
         .route("/zone/settings", get(settings::get_zone_settings))
         .route("/zone/settings", patch(settings::update_zone_settings))
         .route("/zone/development-mode", post(settings::toggle_dev_mode))
+        .route("/zone/pause", post(settings::pause_zone))
+        .route("/zone/performance/advanced", get(settings::get_advanced_performance))
+        .route("/zone/performance/advanced", patch(settings::update_advanced_performance))
+        .route("/performance/settings", get(settings::get_performance_settings))
+        .route("/performance/settings", patch(settings::update_performance_settings))
+        .route("/performance/images", get(settings::get_image_settings))
+        .route("/performance/images", patch(settings::update_image_settings))
+        .route("/performance/argo", get(settings::get_argo))
+        .route("/performance/argo", patch(settings::update_argo))
 
         // Add state to all routes
         .with_state(services)
 }
 
-/// Get Cloudflare status
-async fn get_status() -> axum::Json<serde_json::Value> {
+/// Plugin metrics in the Prometheus text format
+async fn get_metrics(State(services): State<Arc<CloudflareServices>>) -> impl IntoResponse {
+    let body = match &services.client {
+        Some(client) => client.metrics().render(&client.circuit_breakers().snapshot()),
+        None => Metrics::default().render(&Default::default()),
+    };
+    ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body)
+}
+
+/// Get Cloudflare connection status, including token scopes
+async fn get_status(
+    State(services): State<Arc<CloudflareServices>>,
+) -> axum::Json<serde_json::Value> {
+    let (mut status, circuits) = match &services.client {
+        Some(client) => (
+            client.connection_status().await,
+            client.circuit_breakers().snapshot(),
+        ),
+        None => (
+            ConnectionStatus {
+                error: Some(CloudflareError::NotConfigured.to_string()),
+                ..Default::default()
+            },
+            Default::default(),
+        ),
+    };
+
+    for (category, circuit) in &circuits {
+        if circuit.state != CircuitState::Closed {
+            status.warnings.push(format!(
+                "{} API circuit open after repeated Cloudflare failures",
+                category
+            ));
+        }
+    }
+
     axum::Json(serde_json::json!({
         "success": true,
         "data": {
-            "connected": true,
+            "connected": status.connected,
             "plugin_version": crate::VERSION,
+            "zone_name": status.zone_name,
+            "zone_status": status.zone_status,
+            "token_status": status.token_status,
+            "permission_groups": status.permission_groups,
+            "missing_scopes": status.missing_scopes,
+            "warnings": status.warnings,
+            "circuits": circuits,
+            "health": services.health.snapshot(),
+            "error": status.error,
         }
     }))
 }
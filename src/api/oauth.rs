@@ -9,8 +9,9 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 use crate::error::{CloudflareError, CloudflareResult};
+use crate::models::TokenPermission;
 use crate::services::{CloudflareServices, TokenResources};
-use tracing::{info, error};
+use tracing::{info, error, warn};
 
 /// OAuth callback query parameters
 #[derive(Debug, Deserialize)]
@@ -34,6 +35,8 @@ pub struct VerifyTokenResponse {
     pub valid: bool,
     pub message: Option<String>,
     pub resources: Option<TokenResources>,
+    /// `None` when the token can't read its own permissions
+    pub permissions: Option<Vec<TokenPermission>>,
 }
 
 /// Save credentials request
@@ -44,6 +47,12 @@ pub struct SaveCredentialsRequest {
     pub zone_id: String,
 }
 
+/// Rotate API token request
+#[derive(Debug, Deserialize)]
+pub struct RotateTokenRequest {
+    pub api_token: String,
+}
+
 /// SSO Connect request - completes the multi-account/zone flow by
 /// quoting a one-shot handoff ID issued during /auth/callback.
 ///
@@ -370,9 +379,18 @@ pub async fn verify_token(
             valid: false,
             message: Some("Invalid API token".to_string()),
             resources: None,
+            permissions: None,
         }));
     }
 
+    let permissions = match services.oauth.get_token_details(&req.api_token).await {
+        Ok(details) => Some(details.permissions),
+        Err(e) => {
+            warn!("Couldn't read API token permissions: {}", e);
+            None
+        }
+    };
+
     // Get available resources (accounts and zones)
     match services.oauth.get_token_resources(&req.api_token).await {
         Ok(resources) => Ok(Json(VerifyTokenResponse {
@@ -380,12 +398,14 @@ pub async fn verify_token(
             valid: true,
             message: Some("Token verified successfully".to_string()),
             resources: Some(resources),
+            permissions,
         })),
         Err(e) => Ok(Json(VerifyTokenResponse {
             success: true,
             valid: true,
             message: Some(format!("Token valid but couldn't fetch resources: {}", e)),
             resources: None,
+            permissions,
         })),
     }
 }
@@ -422,6 +442,53 @@ pub async fn save_credentials(
     }))
 }
 
+/// Rotate the API token used by the live connection
+pub async fn rotate_token(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<RotateTokenRequest>,
+) -> CloudflareResult<Json<ApiResponse<()>>> {
+    let client = services.client.as_ref().ok_or(CloudflareError::NotConfigured)?;
+
+    services.settings.rotate_token(client, &req.api_token).await?;
+    info!("API token rotated via admin API");
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(()),
+        error: None,
+    }))
+}
+
+/// Probe each feature area with the saved API token, on the live connection when there is one
+pub async fn test_capabilities(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let creds = services
+        .settings
+        .get_credentials()
+        .await?
+        .ok_or(CloudflareError::NotConfigured)?;
+    let client = match &services.client {
+        // Keep the live connection's base URL, user agent and timeouts
+        Some(client) => client.with_token(&creds.api_token)?,
+        None => crate::client::CloudflareClient::new(&crate::config::CloudflareConfig {
+            api_token: creds.api_token,
+            account_id: creds.account_id,
+            zone_id: creds.zone_id,
+            ..Default::default()
+        })?,
+    };
+
+    let checks = client.check_capabilities().await;
+    let all_ok = checks.values().all(|c| c.ok);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "all_ok": all_ok,
+        "data": checks
+    })))
+}
+
 /// Get connection status
 pub async fn get_connection_status(
     State(services): State<Arc<CloudflareServices>>,
@@ -0,0 +1,60 @@
+//! Query extractor for paginated list handlers
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::Deserialize;
+use crate::error::CloudflareError;
+use crate::models::Pagination;
+
+#[derive(Debug, Deserialize)]
+struct PaginationQuery {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+/// Reads `page` and `per_page` from the query string, see `Pagination::new`
+#[async_trait]
+impl<S> FromRequestParts<S> for Pagination
+where
+    S: Send + Sync,
+{
+    type Rejection = CloudflareError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<PaginationQuery>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| CloudflareError::ValidationError(e.body_text()))?;
+        Ok(Pagination::new(query.page, query.per_page))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DEFAULT_PER_PAGE, MAX_PER_PAGE};
+    use axum::http::Request;
+
+    async fn extract(uri: &str) -> Result<Pagination, CloudflareError> {
+        let (mut parts, _) = Request::builder().uri(uri).body(()).unwrap().into_parts();
+        Pagination::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_pagination_extractor() {
+        assert_eq!(
+            extract("/dns/records").await.unwrap(),
+            Pagination { page: 1, per_page: DEFAULT_PER_PAGE }
+        );
+        assert_eq!(
+            extract("/dns/records?type=A&page=2&per_page=500").await.unwrap(),
+            Pagination { page: 2, per_page: MAX_PER_PAGE }
+        );
+        assert!(matches!(
+            extract("/dns/records?page=two").await,
+            Err(CloudflareError::ValidationError(_))
+        ));
+    }
+}
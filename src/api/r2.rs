@@ -1,12 +1,16 @@
 //! R2 Storage API handlers
 
 use axum::{
+    body::Body,
     extract::{Path, Query, State, Multipart},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::sync::Arc;
 use crate::error::CloudflareResult;
+use crate::services::r2::{CorsRule, DEFAULT_PART_SIZE};
 use crate::services::CloudflareServices;
 
 #[derive(Debug, Deserialize)]
@@ -93,15 +97,18 @@ pub async fn list_objects(
     })))
 }
 
-/// Upload object to bucket
+/// Upload object to bucket.
+///
+/// The `file` field is streamed to R2 in parts as it arrives, so a `key`
+/// field must come before it; without one the file name is used, and a
+/// `key` sent after the file is rejected.
 pub async fn upload_object(
     State(services): State<Arc<CloudflareServices>>,
     Path(bucket): Path<String>,
     mut multipart: Multipart,
 ) -> CloudflareResult<Json<serde_json::Value>> {
     let mut key = String::new();
-    let mut data = Vec::new();
-    let mut content_type: Option<String> = None;
+    let mut uploaded: Option<u64> = None;
 
     while let Some(field) = multipart.next_field().await.map_err(|e| {
         crate::error::CloudflareError::R2Error(format!("Failed to read multipart: {}", e))
@@ -109,6 +116,12 @@ pub async fn upload_object(
         let name = field.name().unwrap_or_default().to_string();
 
         if name == "key" {
+            if uploaded.is_some() {
+                return Err(crate::error::CloudflareError::ValidationError(format!(
+                    "The key field must come before the file; it was already uploaded as '{}'",
+                    key
+                )));
+            }
             key = field.text().await.map_err(|e| {
                 crate::error::CloudflareError::R2Error(format!("Failed to read key: {}", e))
             })?;
@@ -118,10 +131,15 @@ pub async fn upload_object(
                     key = file_name.to_string();
                 }
             }
-            content_type = field.content_type().map(|s| s.to_string());
-            data = field.bytes().await.map_err(|e| {
-                crate::error::CloudflareError::R2Error(format!("Failed to read file: {}", e))
-            })?.to_vec();
+            if key.is_empty() {
+                break;
+            }
+            let content_type = field.content_type().map(|s| s.to_string());
+            let size = services
+                .r2
+                .upload_multipart(&bucket, &key, field, DEFAULT_PART_SIZE, content_type.as_deref())
+                .await?;
+            uploaded = Some(size);
         }
     }
 
@@ -132,21 +150,19 @@ pub async fn upload_object(
         })));
     }
 
-    if data.is_empty() {
+    let Some(size) = uploaded else {
         return Ok(Json(serde_json::json!({
             "success": false,
             "error": "No file data provided"
         })));
-    }
-
-    services.r2.upload(&bucket, &key, data.clone(), content_type.as_deref()).await?;
+    };
 
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
             "bucket": bucket,
             "key": key,
-            "size": data.len()
+            "size": size
         },
         "message": format!("Object '{}' uploaded successfully", key)
     })))
@@ -168,6 +184,60 @@ pub async fn get_object(
     })))
 }
 
+/// Stream an object's contents.
+///
+/// A `Range` header is forwarded to R2, in which case only that slice is
+/// returned with `206 Partial Content`.
+pub async fn download_object(
+    State(services): State<Arc<CloudflareServices>>,
+    Path((bucket, key)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> CloudflareResult<Response> {
+    let range = headers.get(header::RANGE).and_then(|v| v.to_str().ok());
+    let download = services.r2.download(&bucket, &key, range).await?;
+
+    let status = if download.content_range.is_some() {
+        StatusCode::PARTIAL_CONTENT
+    } else {
+        StatusCode::OK
+    };
+    let mut response = (status, Body::from_stream(download.body)).into_response();
+    let response_headers = response.headers_mut();
+
+    let content_type = download.content_type.as_deref().unwrap_or("application/octet-stream");
+    if let Ok(value) = HeaderValue::from_str(content_type) {
+        response_headers.insert(header::CONTENT_TYPE, value);
+    }
+    if let Some(length) = download.content_length {
+        response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+    }
+    if let Some(value) = download.content_range.and_then(|r| HeaderValue::from_str(&r).ok()) {
+        response_headers.insert(header::CONTENT_RANGE, value);
+    }
+    if let Some(value) = download.etag.and_then(|e| HeaderValue::from_str(&e).ok()) {
+        response_headers.insert(header::ETAG, value);
+    }
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Ok(value) = HeaderValue::from_str(&content_disposition(&key)) {
+        response_headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+
+    Ok(response)
+}
+
+/// `attachment` disposition named after the last segment of the key
+fn content_disposition(key: &str) -> String {
+    let name: String = key
+        .rsplit('/')
+        .next()
+        .unwrap_or(key)
+        .chars()
+        .map(|c| if c == '"' || c == '\\' || !c.is_ascii() || c.is_ascii_control() { '_' } else { c })
+        .collect();
+    let name = if name.is_empty() { "download".to_string() } else { name };
+    format!("attachment; filename=\"{}\"", name)
+}
+
 /// Get object download URL
 pub async fn get_object_url(
     State(_services): State<Arc<CloudflareServices>>,
@@ -204,32 +274,28 @@ pub async fn delete_object(
 
 /// Get bucket CORS configuration
 pub async fn get_bucket_cors(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Path(bucket): Path<String>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
+    let rules = services.r2.get_cors(&bucket).await?;
+
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
             "bucket": bucket,
-            "cors_rules": []
+            "cors_rules": rules
         }
     })))
 }
 
 /// Set bucket CORS configuration
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CorsRule {
-    pub allowed_origins: Vec<String>,
-    pub allowed_methods: Vec<String>,
-    pub allowed_headers: Option<Vec<String>>,
-    pub max_age_seconds: Option<u32>,
-}
-
 pub async fn set_bucket_cors(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Path(bucket): Path<String>,
     Json(rules): Json<Vec<CorsRule>>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
+    services.r2.put_cors(&bucket, &rules).await?;
+
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
@@ -1,113 +1,136 @@
 //! Page Rules API handlers
 
 use axum::{
-    extract::{Path, State},
+    extract::{Multipart, Path, State},
     Json,
 };
 use serde::Deserialize;
 use std::sync::Arc;
-use crate::error::CloudflareResult;
+use crate::error::{CloudflareError, CloudflareResult};
 use crate::models::{CreatePageRule, UpdatePageRule};
+use crate::services::rules::parse_redirects_csv;
 use crate::services::CloudflareServices;
 
-/// List all page rules
+/// List all page rules, with how many more the zone's plan allows
 pub async fn list_page_rules(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Would call client.list_page_rules().await?;
-    // Page rules are zone-level settings for URL pattern matching
+    let rules = services.rules.list_page_rules().await?;
+    let quota = services.rules.page_rule_quota(rules.len()).await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
-        "data": [],
-        "total": 0,
-        "available_quota": 3  // Free plan allows 3 page rules
+        "total": rules.len(),
+        "data": rules,
+        "available_quota": quota.remaining,
+        "quota": quota
     })))
 }
 
 /// Get a specific page rule
 pub async fn get_page_rule(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Path(id): Path<String>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
+    let rule = services.rules.get_page_rule(&id).await?;
+
     Ok(Json(serde_json::json!({
         "success": true,
-        "data": {
-            "id": id
-        }
+        "data": rule
     })))
 }
 
 /// Create a new page rule
 pub async fn create_page_rule(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Json(req): Json<CreatePageRule>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Validate targets
-    if req.targets.is_empty() {
-        return Ok(Json(serde_json::json!({
-            "success": false,
-            "error": "At least one target URL pattern is required"
-        })));
-    }
-
-    // Validate actions
-    if req.actions.is_empty() {
-        return Ok(Json(serde_json::json!({
-            "success": false,
-            "error": "At least one action is required"
-        })));
-    }
-
-    // Would call client.create_page_rule(req).await?;
+    let rule = services.rules.create_page_rule(req).await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
-        "data": {
-            "id": uuid::Uuid::new_v4().to_string(),
-            "targets": req.targets,
-            "actions": req.actions,
-            "status": req.status.unwrap_or_else(|| "active".to_string()),
-            "priority": req.priority.unwrap_or(1)
-        },
+        "data": rule,
         "message": "Page rule created successfully"
     })))
 }
 
 /// Update an existing page rule
 pub async fn update_page_rule(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Path(id): Path<String>,
     Json(req): Json<UpdatePageRule>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Would call client.update_page_rule(&id, req).await?;
+    let rule = services.rules.update_page_rule(&id, req).await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
-        "data": {
-            "id": id,
-            "targets": req.targets,
-            "actions": req.actions,
-            "status": req.status,
-            "priority": req.priority
-        },
+        "data": rule,
         "message": "Page rule updated successfully"
     })))
 }
 
 /// Delete a page rule
 pub async fn delete_page_rule(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Path(id): Path<String>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Would call client.delete_page_rule(&id).await?;
+    let deleted = services.rules.delete_page_rule(&id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": deleted,
+        "message": "Page rule deleted successfully"
+    })))
+}
+
+/// Import redirects from a CSV upload into a new Bulk Redirect list and turn them on.
+///
+/// Multipart fields: `name`, the list name, and `file`, a CSV of
+/// `source,target[,status[,preserve_query]]` rows.
+pub async fn import_bulk_redirects(
+    State(services): State<Arc<CloudflareServices>>,
+    mut multipart: Multipart,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let mut name = None;
+    let mut csv = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| CloudflareError::ValidationError(format!("Failed to read upload: {}", e)))?
+    {
+        match field.name().unwrap_or_default() {
+            "name" => name = Some(field.text().await.map_err(|e| {
+                CloudflareError::ValidationError(format!("Failed to read list name: {}", e))
+            })?),
+            "file" => csv = Some(field.text().await.map_err(|e| {
+                CloudflareError::ValidationError(format!("Failed to read CSV: {}", e))
+            })?),
+            _ => {}
+        }
+    }
+
+    let name = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).ok_or_else(|| {
+        CloudflareError::ValidationError("A list name is required".to_string())
+    })?;
+    let csv = csv.ok_or_else(|| CloudflareError::ValidationError("No CSV file provided".to_string()))?;
+
+    // Parse everything before creating anything on Cloudflare
+    let redirects = parse_redirects_csv(&csv)?;
+    let count = redirects.len();
+
+    let list = services.rules.create_bulk_redirect_list(&name).await?;
+    let operations = services.rules.add_redirects(&list.id, redirects).await?;
+    services.rules.enable_bulk_redirects(&list.id).await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
-            "id": id
+            "list": list,
+            "redirects": count,
+            "operations": operations
         },
-        "message": "Page rule deleted successfully"
+        "message": format!("Imported {} redirects into {}", count, name)
     })))
 }
 
@@ -2,12 +2,19 @@
 
 use axum::{
     extract::{Path, Query, State},
+    response::sse::{Event, KeepAlive, Sse},
     Json,
 };
+use futures::stream::{self, Stream};
 use serde::Deserialize;
+use std::convert::Infallible;
 use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
 use crate::error::CloudflareResult;
-use crate::models::CreateFirewallRule;
+use crate::models::{CreateFirewallRule, CreateIpListItem, CreateTurnstileWidget, UpdateBotManagement};
+use crate::services::rules_export::{RuleImportStatus, RulesExport, RulesImportMode};
+use crate::services::security_events::{FeedMessage, MAX_RECENT_EVENTS};
 use crate::services::CloudflareServices;
 
 #[derive(Debug, Deserialize)]
@@ -20,6 +27,62 @@ pub struct ToggleUnderAttackRequest {
     pub enabled: bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ImportRulesRequest {
+    pub export: RulesExport,
+    #[serde(default)]
+    pub mode: RulesImportMode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeployManagedRulesetRequest {
+    pub ruleset_id: String,
+    #[serde(default = "default_managed_action")]
+    pub action: String,
+}
+
+fn default_managed_action() -> String {
+    "default".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CustomRuleRequest {
+    pub expression: String,
+    pub action: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateTurnstileSecretRequest {
+    /// Revoke the old secret now instead of after the two hour grace period
+    #[serde(default)]
+    pub invalidate_immediately: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyTurnstileRequest {
+    /// Value of the `cf-turnstile-response` form field
+    pub token: String,
+    pub secret: String,
+    pub remote_ip: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateIpListRequest {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddIpListItemsRequest {
+    pub items: Vec<CreateIpListItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemoveIpListItemsRequest {
+    pub ids: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct IpAccessRequest {
     pub ip: String,
@@ -114,6 +177,123 @@ pub async fn list_waf_rules(
     })))
 }
 
+/// List Cloudflare Managed Rulesets and whether each is deployed
+pub async fn list_managed_rulesets(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let rulesets = services.security.list_managed_rulesets().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": rulesets,
+        "total": rulesets.len()
+    })))
+}
+
+/// Deploy a Cloudflare Managed Ruleset in the zone's WAF
+pub async fn deploy_managed_ruleset(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<DeployManagedRulesetRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let ruleset = services
+        .security
+        .deploy_managed_ruleset(&req.ruleset_id, &req.action)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": ruleset,
+        "message": format!("Managed ruleset {} deployed", req.ruleset_id)
+    })))
+}
+
+/// List custom WAF rules
+pub async fn list_custom_rules(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let rules = services.security.list_custom_rules().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": rules,
+        "total": rules.len()
+    })))
+}
+
+/// Create a custom WAF rule
+pub async fn create_custom_rule(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<CustomRuleRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let rule = services
+        .security
+        .create_custom_rule(&req.expression, &req.action, req.description)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": rule,
+        "message": "Custom rule created"
+    })))
+}
+
+/// Update a custom WAF rule
+pub async fn update_custom_rule(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(id): Path<String>,
+    Json(req): Json<CustomRuleRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let rule = services
+        .security
+        .update_custom_rule(&id, &req.expression, &req.action, req.description)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": rule,
+        "message": "Custom rule updated"
+    })))
+}
+
+/// Delete a custom WAF rule
+pub async fn delete_custom_rule(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(id): Path<String>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    services.security.delete_custom_rule(&id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Custom rule {} deleted", id)
+    })))
+}
+
+/// Get bot management settings and the protection the zone's plan offers
+pub async fn get_bot_management(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let bots = services.security.get_bot_management().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": bots
+    })))
+}
+
+/// Update bot management settings
+pub async fn update_bot_management(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<UpdateBotManagement>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let settings = services.security.update_bot_management(req).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": settings,
+        "message": "Bot management settings updated"
+    })))
+}
+
 /// Update WAF rule status
 pub async fn update_waf_rule(
     State(_services): State<Arc<CloudflareServices>>,
@@ -176,10 +356,11 @@ pub async fn update_firewall_rule(
 
 /// Delete a firewall rule
 pub async fn delete_firewall_rule(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Path(id): Path<String>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Would need to add delete_firewall_rule to security service
+    services.security.delete_firewall_rule(&id).await?;
+
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
@@ -248,10 +429,11 @@ pub async fn challenge_ip(
 
 /// Delete an IP access rule
 pub async fn delete_ip_access_rule(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Path(id): Path<String>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Would need to add delete_ip_access_rule to security service
+    services.security.delete_ip_access_rule(&id).await?;
+
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
@@ -261,24 +443,254 @@ pub async fn delete_ip_access_rule(
     })))
 }
 
-/// Get recent security events
+/// Export the zone's security rules as a portable document
+pub async fn export_rules(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let export = services.security.export_rules().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": export
+    })))
+}
+
+/// Recreate the rules of an export on this zone
+pub async fn import_rules(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(request): Json<ImportRulesRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let report = services.security.import_rules(&request.export, request.mode).await?;
+    let message = format!(
+        "Imported {} rules ({} skipped, {} failed)",
+        report.count(RuleImportStatus::Created),
+        report.count(RuleImportStatus::Skipped),
+        report.count(RuleImportStatus::Failed)
+    );
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": report,
+        "message": message
+    })))
+}
+
+/// List the account's IP Lists
+pub async fn list_ip_lists(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let lists = services.security.list_ip_lists().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": lists,
+        "total": lists.len()
+    })))
+}
+
+/// Create an IP List
+pub async fn create_ip_list(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<CreateIpListRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let list = services.security.create_ip_list(&req.name, req.description).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": list,
+        "message": format!("IP list {} created", list.name)
+    })))
+}
+
+/// List the items in an IP List
+pub async fn list_ip_list_items(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(id): Path<String>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let items = services.security.list_ip_list_items(&id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": items,
+        "total": items.len()
+    })))
+}
+
+/// Add items to an IP List
+pub async fn add_ip_list_items(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(id): Path<String>,
+    Json(req): Json<AddIpListItemsRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let count = req.items.len();
+    let operation = services.security.add_ip_list_items(&id, req.items).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": operation,
+        "message": format!("Adding {} items; poll the operation for completion", count)
+    })))
+}
+
+/// Remove items from an IP List
+pub async fn remove_ip_list_items(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(id): Path<String>,
+    Json(req): Json<RemoveIpListItemsRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let count = req.ids.len();
+    let operation = services.security.remove_ip_list_items(&id, req.ids).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": operation,
+        "message": format!("Removing {} items; poll the operation for completion", count)
+    })))
+}
+
+/// Status of an asynchronous IP List operation
+pub async fn get_ip_list_operation(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(id): Path<String>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let status = services.security.get_bulk_operation_status(&id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": status
+    })))
+}
+
+/// List Turnstile widgets, without their secrets
+pub async fn list_turnstile_widgets(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let widgets = services.security.list_turnstile_widgets().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": widgets,
+        "total": widgets.len()
+    })))
+}
+
+/// Create a Turnstile widget; the only response that includes its secret
+pub async fn create_turnstile_widget(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<CreateTurnstileWidget>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let widget = services.security.create_turnstile_widget(req).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": widget,
+        "message": format!("Turnstile widget {} created; copy the secret now, it won't be shown again", widget.name)
+    })))
+}
+
+/// Rotate a Turnstile widget's secret
+pub async fn rotate_turnstile_secret(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(sitekey): Path<String>,
+    Json(req): Json<RotateTurnstileSecretRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let widget = services
+        .security
+        .rotate_turnstile_secret(&sitekey, req.invalidate_immediately)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": widget,
+        "message": "Turnstile secret rotated"
+    })))
+}
+
+/// Delete a Turnstile widget
+pub async fn delete_turnstile_widget(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(sitekey): Path<String>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    services.security.delete_turnstile_widget(&sitekey).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Turnstile widget {} deleted", sitekey)
+    })))
+}
+
+/// Verify a Turnstile token for a theme or plugin form
+pub async fn verify_turnstile(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<VerifyTurnstileRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let verification = services
+        .security
+        .verify_turnstile(&req.token, req.remote_ip.as_deref(), &req.secret)
+        .await?;
+    let messages = verification.error_messages();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": verification,
+        "messages": messages
+    })))
+}
+
+/// Get recent security events, newest first
 pub async fn get_security_events(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Query(query): Query<SecurityEventsQuery>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
     let limit = query.limit.unwrap_or(100);
+    let events = services.security.get_recent_events(limit).await?;
 
-    // Security events would require analytics API integration
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
-            "events": [],
-            "limit": limit
-        },
-        "message": "Security events fetched (requires Cloudflare Analytics API)"
+            "events": events,
+            "limit": limit.min(MAX_RECENT_EVENTS)
+        }
     })))
 }
 
+/// Stream new firewall events as server-sent events.
+///
+/// Each event is a `security_event` carrying the event as JSON, with the ray
+/// ID as its ID. A `closed` event ends the stream when the zone's plan lacks
+/// the dataset.
+pub async fn stream_security_events(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let events = services.security.subscribe_events()?;
+    Ok(Sse::new(sse_events(events)).keep_alive(KeepAlive::default()))
+}
+
+/// Map feed messages to SSE events, ending after a `Closed` message
+fn sse_events(events: broadcast::Receiver<FeedMessage>) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(Some(events), |events| async move {
+        let mut events = events?;
+        loop {
+            let event = match events.recv().await {
+                Ok(FeedMessage::Event(event)) => Event::default()
+                    .event("security_event")
+                    .id(event.ray_id.clone())
+                    .json_data(&event)
+                    .unwrap_or_else(|_| Event::default().comment("unserializable event")),
+                Ok(FeedMessage::Closed(message)) => {
+                    return Some((Ok(Event::default().event("closed").data(message)), None));
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Security event stream fell behind, skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((Ok(event), Some(events)));
+        }
+    })
+}
+
 /// Helper function to get security level description
 fn get_security_level_description(level: &str) -> &'static str {
     match level {
@@ -291,3 +703,44 @@ fn get_security_level_description(level: &str) -> &'static str {
         _ => "Unknown security level"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SecurityEvent;
+    use axum::response::IntoResponse;
+
+    #[tokio::test]
+    async fn test_feed_messages_become_sse_events() {
+        let (tx, rx) = broadcast::channel(8);
+        tx.send(FeedMessage::Event(SecurityEvent {
+            ray_id: "8c1f2e3d4a5b6c7d".to_string(),
+            action: "managed_challenge".to_string(),
+            source: "firewallCustom".to_string(),
+            country: Some("US".to_string()),
+            host: Some("example.com".to_string()),
+            path: Some("/xmlrpc.php".to_string()),
+            rule_id: Some("rule-1".to_string()),
+            datetime: "2026-10-16T10:00:00Z".parse().unwrap(),
+        }))
+        .unwrap();
+        tx.send(FeedMessage::Closed("Firewall events aren't available on this zone's plan".to_string()))
+            .unwrap();
+        // Nothing after the close is sent
+        tx.send(FeedMessage::Closed("again".to_string())).unwrap();
+
+        let response = Sse::new(sse_events(rx)).into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        let frames: Vec<&str> = body.split("\n\n").filter(|f| !f.is_empty()).collect();
+        assert_eq!(frames.len(), 2, "{}", body);
+        assert!(frames[0].starts_with("event: security_event\n"));
+        assert!(frames[0].contains("id: 8c1f2e3d4a5b6c7d"));
+        let data = frames[0].lines().find_map(|l| l.strip_prefix("data: ")).unwrap();
+        let data: serde_json::Value = serde_json::from_str(data).unwrap();
+        assert_eq!(data["action"], "managed_challenge");
+        assert_eq!(data["path"], "/xmlrpc.php");
+        assert_eq!(frames[1], "event: closed\ndata: Firewall events aren't available on this zone's plan");
+    }
+}
@@ -1,11 +1,22 @@
 //! Settings API handlers
 
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
-use crate::error::CloudflareResult;
+use crate::config::CloudflareConfig;
+use crate::error::{CloudflareError, CloudflareResult};
+use crate::hooks::QuotaExhaustedAction;
+use crate::models::NormalizedZoneSettings;
+use crate::plan::Feature;
 use crate::services::CloudflareServices;
-use crate::services::settings::ExtendedPluginSettings;
+use crate::services::features::KNOWN_FEATURES;
+use crate::services::dns_scan::DnsProbe;
+use crate::services::performance::{ImageSettings, PerformanceSettings};
+use crate::services::settings::{zone_setting_updates, ExtendedPluginSettings, ZoneSettingOutcome};
 
 /// API response wrapper
 #[derive(Serialize)]
@@ -42,6 +53,25 @@ pub struct UpdateSettingsRequest {
 #[derive(Deserialize)]
 pub struct ToggleDevModeRequest {
     pub enabled: bool,
+    /// Minutes to stay on, defaults to the `development_mode_duration` setting
+    pub duration: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct PauseZoneRequest {
+    pub paused: bool,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateAdvancedPerformanceRequest {
+    pub crawler_hints: Option<bool>,
+    pub http2_to_origin: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateArgoRequest {
+    pub smart_routing: Option<bool>,
+    pub tiered_caching: Option<bool>,
 }
 
 #[derive(Deserialize)]
@@ -57,6 +87,9 @@ pub struct UpdateAutoPurgeRequest {
     pub auto_purge_archives: Option<bool>,
     pub auto_purge_custom_urls: Option<String>,
     pub auto_purge_delay_ms: Option<u32>,
+    pub auto_purge_on_quota_exhausted: Option<QuotaExhaustedAction>,
+    pub auto_purge_quota_pause_minutes: Option<u32>,
+    pub auto_purge_dry_run: Option<bool>,
 }
 
 #[derive(Deserialize)]
@@ -69,14 +102,26 @@ pub struct UpdateCacheWarmingRequest {
 pub struct UpdateNotificationsRequest {
     pub security_email_alerts: Option<bool>,
     pub security_slack_webhook: Option<String>,
+    pub security_webhook_alerts: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateUnderAttackAutoRequest {
+    pub under_attack_auto_enabled: Option<bool>,
+    pub under_attack_threshold: Option<u32>,
+    pub under_attack_window_minutes: Option<u32>,
+    pub under_attack_cooldown_minutes: Option<u32>,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateAdvancedRequest {
     pub development_mode_duration: Option<u32>,
     pub analytics_retention_days: Option<u32>,
+    pub analytics_cache_ttl_secs: Option<u64>,
     pub r2_default_bucket: Option<String>,
     pub workers_enabled: Option<bool>,
+    pub dns_scan_probes: Option<Vec<DnsProbe>>,
+    pub dns_scan_nameservers: Option<String>,
 }
 
 /// Get all plugin settings
@@ -97,6 +142,7 @@ pub async fn update_settings(
     Json(req): Json<ExtendedPluginSettings>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
     services.settings.update_extended_settings(&req).await?;
+    services.apply_extended_settings(&req);
 
     let updated = services.settings.get_extended_settings().await?;
 
@@ -138,6 +184,9 @@ pub async fn update_auto_purge_settings(
     if let Some(v) = req.auto_purge_archives { settings.auto_purge_archives = v; }
     if req.auto_purge_custom_urls.is_some() { settings.auto_purge_custom_urls = req.auto_purge_custom_urls; }
     if let Some(v) = req.auto_purge_delay_ms { settings.auto_purge_delay_ms = v; }
+    if let Some(v) = req.auto_purge_on_quota_exhausted { settings.auto_purge_on_quota_exhausted = v; }
+    if let Some(v) = req.auto_purge_quota_pause_minutes { settings.auto_purge_quota_pause_minutes = v; }
+    if let Some(v) = req.auto_purge_dry_run { settings.auto_purge_dry_run = v; }
 
     services.settings.update_extended_settings(&settings).await?;
 
@@ -155,6 +204,9 @@ pub async fn update_auto_purge_settings(
             "auto_purge_archives": settings.auto_purge_archives,
             "auto_purge_custom_urls": settings.auto_purge_custom_urls,
             "auto_purge_delay_ms": settings.auto_purge_delay_ms,
+            "auto_purge_on_quota_exhausted": settings.auto_purge_on_quota_exhausted,
+            "auto_purge_quota_pause_minutes": settings.auto_purge_quota_pause_minutes,
+            "auto_purge_dry_run": settings.auto_purge_dry_run,
         },
         "message": "Auto-purge settings updated"
     })))
@@ -191,6 +243,7 @@ pub async fn update_notification_settings(
 
     if let Some(v) = req.security_email_alerts { settings.security_email_alerts = v; }
     if req.security_slack_webhook.is_some() { settings.security_slack_webhook = req.security_slack_webhook; }
+    if let Some(v) = req.security_webhook_alerts { settings.security_webhook_alerts = v; }
 
     services.settings.update_extended_settings(&settings).await?;
 
@@ -199,11 +252,38 @@ pub async fn update_notification_settings(
         "data": {
             "security_email_alerts": settings.security_email_alerts,
             "security_slack_webhook": settings.security_slack_webhook,
+            "security_webhook_alerts": settings.security_webhook_alerts,
         },
         "message": "Notification settings updated"
     })))
 }
 
+/// Update the Under Attack auto-trigger settings
+pub async fn update_under_attack_auto_settings(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<UpdateUnderAttackAutoRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let mut settings = services.settings.get_extended_settings().await?;
+
+    if let Some(v) = req.under_attack_auto_enabled { settings.under_attack_auto_enabled = v; }
+    if let Some(v) = req.under_attack_threshold { settings.under_attack_threshold = v; }
+    if let Some(v) = req.under_attack_window_minutes { settings.under_attack_window_minutes = v; }
+    if let Some(v) = req.under_attack_cooldown_minutes { settings.under_attack_cooldown_minutes = v; }
+
+    services.settings.update_extended_settings(&settings).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "under_attack_auto_enabled": settings.under_attack_auto_enabled,
+            "under_attack_threshold": settings.under_attack_threshold,
+            "under_attack_window_minutes": settings.under_attack_window_minutes,
+            "under_attack_cooldown_minutes": settings.under_attack_cooldown_minutes,
+        },
+        "message": "Under Attack auto-trigger settings updated"
+    })))
+}
+
 /// Update advanced settings
 pub async fn update_advanced_settings(
     State(services): State<Arc<CloudflareServices>>,
@@ -213,51 +293,396 @@ pub async fn update_advanced_settings(
 
     if let Some(v) = req.development_mode_duration { settings.development_mode_duration = v; }
     if let Some(v) = req.analytics_retention_days { settings.analytics_retention_days = v; }
+    if let Some(v) = req.analytics_cache_ttl_secs { settings.analytics_cache_ttl_secs = v; }
     if req.r2_default_bucket.is_some() { settings.r2_default_bucket = req.r2_default_bucket; }
     if let Some(v) = req.workers_enabled { settings.workers_enabled = v; }
+    if let Some(v) = req.dns_scan_probes { settings.dns_scan_probes = v; }
+    if req.dns_scan_nameservers.is_some() { settings.dns_scan_nameservers = req.dns_scan_nameservers; }
 
     services.settings.update_extended_settings(&settings).await?;
+    services.apply_extended_settings(&settings);
 
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
             "development_mode_duration": settings.development_mode_duration,
             "analytics_retention_days": settings.analytics_retention_days,
+            "analytics_cache_ttl_secs": settings.analytics_cache_ttl_secs,
             "r2_default_bucket": settings.r2_default_bucket,
             "workers_enabled": settings.workers_enabled,
+            "dns_scan_probes": settings.dns_scan_probes,
+            "dns_scan_nameservers": settings.dns_scan_nameservers,
         },
         "message": "Advanced settings updated"
     })))
 }
 
+/// Get feature flags
+pub async fn get_feature_flags(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": services.features.snapshot()
+    })))
+}
+
+/// Toggle feature flags at runtime
+pub async fn update_feature_flags(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<HashMap<String, bool>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    if let Some(unknown) = req.keys().find(|f| !KNOWN_FEATURES.contains(&f.as_str())) {
+        return Err(CloudflareError::ValidationError(format!(
+            "Unknown feature flag: {}",
+            unknown
+        )));
+    }
+
+    for (feature, enabled) in &req {
+        services.features.set(feature, *enabled)?;
+    }
+
+    let flags = services.features.snapshot();
+    services.settings.save_feature_flags(&flags).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": flags,
+        "message": "Feature flags updated"
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateZoneSettingsQuery {
+    /// Roll back every change if any setting fails
+    #[serde(default)]
+    pub transactional: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ExportSettingsQuery {
+    /// Include the API token and other secrets instead of redacting them
+    #[serde(default)]
+    pub include_secrets: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ImportSettingsRequest {
+    /// A document from `GET /settings/export`
+    pub document: serde_json::Value,
+    /// Replace settings that already have a value
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Export every plugin setting for backup or migration
+pub async fn export_settings(
+    State(services): State<Arc<CloudflareServices>>,
+    Query(query): Query<ExportSettingsQuery>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let export = services.settings.export_all(query.include_secrets).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": export
+    })))
+}
+
+/// Import plugin settings from an export
+pub async fn import_settings(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<ImportSettingsRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let summary = services.settings.import_all(&req.document, req.overwrite).await?;
+
+    // Settings the running plugin keeps in memory
+    for (feature, enabled) in services.settings.get_feature_flags().await? {
+        if KNOWN_FEATURES.contains(&feature.as_str()) {
+            services.features.set(&feature, enabled)?;
+        }
+    }
+    let settings = services.settings.get_extended_settings().await?;
+    services.apply_extended_settings(&settings);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": summary,
+        "message": format!("Imported {} settings", summary.imported.len())
+    })))
+}
+
 /// Get zone info
 pub async fn get_zone_info(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let zone = services.zone.get_zone_info().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": zone
+    })))
+}
+
+/// Zone plan tier and which plan-gated features it includes
+pub async fn get_zone_plan(
+    State(services): State<Arc<CloudflareServices>>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Would fetch from Cloudflare API
+    let tier = services.zone.plan().await?;
+    let features: Vec<serde_json::Value> = Feature::ALL
+        .iter()
+        .map(|&feature| serde_json::json!({
+            "feature": feature,
+            "label": feature.label(),
+            "supported": tier.supports(feature),
+            "required_plan": feature.required_plan()
+        }))
+        .collect();
+
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
-            "id": "",
-            "name": "",
-            "status": "pending"
+            "tier": tier,
+            "features": features
         }
     })))
 }
 
-/// Get zone settings from Cloudflare (placeholder)
+/// Pause or unpause the zone
+pub async fn pause_zone(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<PauseZoneRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let zone = services.zone.pause_zone(req.paused).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": zone,
+        "message": if req.paused { "Zone paused" } else { "Zone unpaused" }
+    })))
+}
+
+/// Get the zone's live settings from Cloudflare
 pub async fn get_zone_settings(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    Ok(Json(serde_json::json!({ "success": true, "data": [] })))
+    let settings = services.zone.get_settings().await?;
+    let normalized = NormalizedZoneSettings::from_settings(&settings);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": settings,
+        "normalized": normalized,
+        "total": settings.len()
+    })))
 }
 
-/// Update zone settings on Cloudflare (placeholder)
+/// Push zone-level config toggles to Cloudflare.
+///
+/// The body holds `CloudflareConfig` fields (e.g. `{"brotli_compression": true,
+/// "min_tls_version": "1.2"}`); only the fields given are applied.
 pub async fn update_zone_settings(
-    State(_services): State<Arc<CloudflareServices>>,
-    Json(req): Json<serde_json::Value>,
+    State(services): State<Arc<CloudflareServices>>,
+    Query(query): Query<UpdateZoneSettingsQuery>,
+    Json(req): Json<serde_json::Map<String, serde_json::Value>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let mut merged = serde_json::to_value(CloudflareConfig::default())?;
+    for (field, value) in &req {
+        merged[field] = value.clone();
+    }
+    let config: CloudflareConfig = serde_json::from_value(merged)
+        .map_err(|e| CloudflareError::ValidationError(format!("Invalid zone settings: {}", e)))?;
+
+    let updates: Vec<_> = zone_setting_updates(&config)
+        .into_iter()
+        .filter(|u| req.contains_key(u.field))
+        .collect();
+
+    let unknown: Vec<&str> = req
+        .keys()
+        .map(String::as_str)
+        .filter(|field| !updates.iter().any(|u| u.field == *field))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(CloudflareError::ValidationError(format!(
+            "Not zone settings: {}",
+            unknown.join(", ")
+        )));
+    }
+
+    if query.transactional {
+        let transaction = services.zone.apply_setting_updates_transactional(updates).await?;
+        let message = if transaction.committed {
+            format!("{} applied", transaction.with_outcome(ZoneSettingOutcome::Applied).len())
+        } else {
+            format!(
+                "{} failed; {} rolled back, {} couldn't be rolled back",
+                transaction.with_outcome(ZoneSettingOutcome::Failed).join(", "),
+                transaction.with_outcome(ZoneSettingOutcome::RolledBack).len(),
+                transaction.with_outcome(ZoneSettingOutcome::RollbackFailed).len()
+            )
+        };
+
+        return Ok(Json(serde_json::json!({
+            "success": transaction.committed,
+            "message": message,
+            "data": transaction
+        })));
+    }
+
+    let results = services.settings.apply_zone_setting_updates(updates).await?;
+    let count = |outcome: ZoneSettingOutcome| results.iter().filter(|r| r.outcome == outcome).count();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!(
+            "{} applied, {} unchanged, {} skipped, {} failed",
+            count(ZoneSettingOutcome::Applied),
+            count(ZoneSettingOutcome::Unchanged),
+            count(ZoneSettingOutcome::Skipped),
+            count(ZoneSettingOutcome::Failed)
+        ),
+        "data": results
+    })))
+}
+
+/// Get Crawler Hints and HTTP/2-to-origin settings
+pub async fn get_advanced_performance(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let settings = services.cache.get_advanced_performance().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": settings
+    })))
+}
+
+/// Toggle Crawler Hints and/or HTTP/2 to origin
+pub async fn update_advanced_performance(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<UpdateAdvancedPerformanceRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    if req.crawler_hints.is_none() && req.http2_to_origin.is_none() {
+        return Err(CloudflareError::ValidationError(
+            "Specify crawler_hints and/or http2_to_origin".to_string(),
+        ));
+    }
+
+    let mut data = serde_json::Map::new();
+    if let Some(enabled) = req.crawler_hints {
+        let enabled = services.cache.set_crawler_hints(enabled).await?;
+        data.insert("crawler_hints".to_string(), serde_json::json!(enabled));
+    }
+    if let Some(enabled) = req.http2_to_origin {
+        let enabled = services.cache.set_http2_to_origin(enabled).await?;
+        data.insert("http2_to_origin".to_string(), serde_json::json!(enabled));
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": data,
+        "message": "Performance settings updated"
+    })))
+}
+
+/// Get the protocol and delivery performance toggles
+pub async fn get_performance_settings(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let settings = services.performance.get_settings().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": settings
+    })))
+}
+
+/// Change some of the performance toggles, reporting the outcome of each
+pub async fn update_performance_settings(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<PerformanceSettings>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    Ok(Json(serde_json::json!({ "success": true, "data": req })))
+    let results = services.performance.update_settings(&req).await?;
+    let count = |outcome: ZoneSettingOutcome| results.iter().filter(|r| r.outcome == outcome).count();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!(
+            "{} applied, {} unchanged, {} skipped, {} failed",
+            count(ZoneSettingOutcome::Applied),
+            count(ZoneSettingOutcome::Unchanged),
+            count(ZoneSettingOutcome::Skipped),
+            count(ZoneSettingOutcome::Failed)
+        ),
+        "data": results
+    })))
+}
+
+/// Get Polish, WebP, Mirage and Image Resizing settings
+pub async fn get_image_settings(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let settings = services.performance.get_image_settings().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": settings
+    })))
+}
+
+/// Change image optimization settings
+pub async fn update_image_settings(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<ImageSettings>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let updated = services.performance.update_image_settings(&req).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": updated,
+        "message": "Image optimization settings updated"
+    })))
+}
+
+/// Get Argo Smart Routing and Tiered Caching state
+pub async fn get_argo(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let argo = services.zone.get_argo().await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": argo
+    })))
+}
+
+/// Toggle Argo Smart Routing and/or Tiered Caching
+pub async fn update_argo(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<UpdateArgoRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    if req.smart_routing.is_none() && req.tiered_caching.is_none() {
+        return Err(CloudflareError::ValidationError(
+            "Specify smart_routing and/or tiered_caching".to_string(),
+        ));
+    }
+
+    let mut data = serde_json::Map::new();
+    if let Some(enabled) = req.smart_routing {
+        let enabled = services.zone.set_argo_smart_routing(enabled).await?;
+        data.insert("smart_routing".to_string(), serde_json::json!(enabled));
+    }
+    if let Some(enabled) = req.tiered_caching {
+        let enabled = services.zone.set_tiered_caching(enabled).await?;
+        data.insert("tiered_caching".to_string(), serde_json::json!(enabled));
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": data,
+        "message": "Argo settings updated"
+    })))
 }
 
 /// Toggle development mode
@@ -265,12 +690,14 @@ pub async fn toggle_dev_mode(
     State(services): State<Arc<CloudflareServices>>,
     Json(req): Json<ToggleDevModeRequest>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    services.settings.set_setting("development_mode", &serde_json::json!(req.enabled)).await?;
+    let status = services.zone.set_development_mode(req.enabled, req.duration).await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
-            "development_mode": req.enabled
+            "development_mode": status.enabled,
+            "duration_minutes": status.duration_minutes,
+            "expires_at": status.expires_at
         },
         "message": if req.enabled {
             "Development mode enabled"
@@ -279,3 +706,49 @@ pub async fn toggle_dev_mode(
         }
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_client, success};
+    use sqlx::postgres::PgPoolOptions;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer};
+
+    fn lazy_pool() -> sqlx::PgPool {
+        PgPoolOptions::new()
+            .connect_lazy("postgres://localhost:1/rustpress_test")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_zone_settings_returns_live_values() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings"))
+            .respond_with(success(serde_json::json!([
+                { "id": "brotli", "value": "on", "editable": true },
+                { "id": "ssl", "value": "strict", "editable": true },
+                { "id": "browser_cache_ttl", "value": 14400, "editable": true },
+                { "id": "0rtt", "value": "off", "editable": false }
+            ])))
+            .mount(&server)
+            .await;
+
+        let services = Arc::new(CloudflareServices::new(mock_client(&server), lazy_pool()));
+
+        let Json(body) = get_zone_settings(State(services)).await.unwrap();
+
+        assert_eq!(body["total"], 4);
+        assert_eq!(body["data"][1], serde_json::json!({ "id": "ssl", "value": "strict", "editable": true, "modified_on": null }));
+        assert_eq!(body["normalized"]["brotli"], true);
+        assert_eq!(body["normalized"]["ssl"], "strict");
+        assert_eq!(body["normalized"]["browser_cache_ttl"], 14400);
+        assert_eq!(body["normalized"]["zero_rtt"], false);
+        assert!(body["normalized"]["polish"].is_null());
+
+        let unconfigured = Arc::new(CloudflareServices::new_unconfigured(lazy_pool()));
+        let err = get_zone_settings(State(unconfigured)).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::NotConfigured));
+    }
+}
@@ -7,6 +7,7 @@ use axum::{
 use serde::Deserialize;
 use std::sync::Arc;
 use crate::error::CloudflareResult;
+use crate::models::CustomHostname;
 use crate::services::CloudflareServices;
 
 #[derive(Debug, Deserialize)]
@@ -14,11 +15,33 @@ pub struct UpdateSslModeRequest {
     pub mode: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateMinTlsRequest {
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTls13Request {
+    pub enabled: bool,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct OrderCertificateRequest {
     pub hosts: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomHostnameRequest {
+    pub hostname: String,
+    /// `http`, `txt` or `email`
+    #[serde(default = "default_ssl_method")]
+    pub ssl_method: String,
+}
+
+fn default_ssl_method() -> String {
+    "http".to_string()
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UploadCertificateRequest {
     pub certificate: String,
@@ -57,27 +80,50 @@ pub async fn get_ssl_status(
 
 /// Update SSL mode
 pub async fn update_ssl_mode(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Json(req): Json<UpdateSslModeRequest>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Validate SSL mode
-    let valid_modes = ["off", "flexible", "full", "strict"];
-    if !valid_modes.contains(&req.mode.as_str()) {
-        return Ok(Json(serde_json::json!({
-            "success": false,
-            "error": format!("Invalid SSL mode. Valid options: {:?}", valid_modes)
-        })));
-    }
+    let updated = services.ssl.set_ssl_mode(&req.mode).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "mode": updated.value,
+            "description": get_ssl_mode_description(&updated.value)
+        },
+        "message": format!("SSL mode set to {}", updated.value)
+    })))
+}
+
+/// Set the minimum TLS version
+pub async fn update_min_tls_version(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<UpdateMinTlsRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let updated = services.ssl.set_min_tls_version(&req.version).await?;
 
-    // Would call client.update_ssl_mode(&req.mode).await?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "min_tls_version": updated.value
+        },
+        "message": format!("Minimum TLS version set to {}", req.version)
+    })))
+}
+
+/// Enable or disable TLS 1.3
+pub async fn update_tls_1_3(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<UpdateTls13Request>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let updated = services.ssl.set_tls_1_3(req.enabled).await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
-            "mode": req.mode,
-            "description": get_ssl_mode_description(&req.mode)
+            "tls_1_3": updated.value
         },
-        "message": format!("SSL mode set to {}", req.mode)
+        "message": if req.enabled { "TLS 1.3 enabled" } else { "TLS 1.3 disabled" }
     })))
 }
 
@@ -162,6 +208,74 @@ pub async fn delete_certificate(
     })))
 }
 
+/// List custom hostnames with the validation records each still needs
+pub async fn list_custom_hostnames(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let hostnames = services.ssl.list_custom_hostnames().await?;
+    let data: Vec<_> = hostnames.iter().map(custom_hostname_json).collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": data,
+        "total": data.len()
+    })))
+}
+
+/// Add a custom hostname
+pub async fn create_custom_hostname(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<CreateCustomHostnameRequest>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let hostname = services
+        .ssl
+        .create_custom_hostname(&req.hostname, &req.ssl_method)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": custom_hostname_json(&hostname),
+        "message": format!(
+            "Custom hostname {} created. Publish the validation records to activate it.",
+            hostname.hostname
+        )
+    })))
+}
+
+/// Get a custom hostname
+pub async fn get_custom_hostname(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(id): Path<String>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let hostname = services.ssl.get_custom_hostname(&id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": custom_hostname_json(&hostname)
+    })))
+}
+
+/// Remove a custom hostname
+pub async fn delete_custom_hostname(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(id): Path<String>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let deleted = services.ssl.delete_custom_hostname(&id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": deleted,
+        "message": "Custom hostname deleted"
+    })))
+}
+
+/// A custom hostname along with its outstanding validation records
+fn custom_hostname_json(hostname: &CustomHostname) -> serde_json::Value {
+    let mut value = serde_json::json!(hostname);
+    value["dcv_records"] = serde_json::json!(hostname.dcv_records());
+    value
+}
+
 /// Helper function to get SSL mode description
 fn get_ssl_mode_description(mode: &str) -> &'static str {
     match mode {
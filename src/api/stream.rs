@@ -1,15 +1,24 @@
 //! Stream Video API endpoints
 
 use axum::{
+    body::Bytes,
     extract::{Path, Query, State},
+    http::HeaderMap,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::error::CloudflareResult;
-use crate::models::{StreamVideo, LiveInput, CreateLiveInput, StreamStats, LiveRecording};
-use crate::services::{CloudflareServices, EmbedOptions};
+use crate::models::{
+    StreamVideo, LiveInput, CreateLiveInput, StreamStats, LiveRecording, LiveLatencyMode, StreamWebhook,
+    StreamListParams, Page, Pagination,
+};
+use crate::services::{CloudflareServices, EmbedOptions, SignedUrlRestrictions};
+
+/// Signed URL lifetime when no `ttl` is given
+const DEFAULT_SIGNED_URL_TTL_SECS: u64 = 3600;
 
 /// List videos response
 #[derive(Debug, Serialize)]
@@ -17,6 +26,26 @@ pub struct ListVideosResponse {
     pub success: bool,
     pub videos: Vec<StreamVideo>,
     pub total: usize,
+    pub page: u32,
+    pub per_page: u32,
+    pub total_pages: u32,
+    /// Continuation token for the next page, if there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl ListVideosResponse {
+    fn new(page: Page<StreamVideo>, next_cursor: Option<String>) -> Self {
+        Self {
+            success: true,
+            videos: page.items,
+            total: page.total,
+            page: page.page,
+            per_page: page.per_page,
+            total_pages: page.total_pages,
+            next_cursor,
+        }
+    }
 }
 
 /// Video URLs response
@@ -47,6 +76,35 @@ pub struct EmbedCodeResponse {
     pub html: String,
 }
 
+/// Copy video from URL request
+#[derive(Debug, Deserialize)]
+pub struct CopyVideoRequest {
+    pub url: String,
+    pub name: Option<String>,
+}
+
+/// Webhook subscription request
+#[derive(Debug, Deserialize)]
+pub struct SetWebhookRequest {
+    pub notification_url: String,
+}
+
+/// Signed URL query params
+#[derive(Debug, Deserialize)]
+pub struct SignedUrlQuery {
+    /// Token lifetime in seconds, at most 24 hours
+    pub ttl: Option<u64>,
+    pub downloadable: Option<bool>,
+}
+
+/// Signed URL response
+#[derive(Debug, Serialize)]
+pub struct SignedUrlResponse {
+    pub token: String,
+    pub embed_url: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
 /// List live inputs response
 #[derive(Debug, Serialize)]
 pub struct ListLiveInputsResponse {
@@ -61,6 +119,11 @@ pub struct CreateLiveInputRequest {
     pub name: Option<String>,
     pub recording_mode: Option<String>,
     pub timeout_seconds: Option<i32>,
+    pub require_signed_urls: Option<bool>,
+    #[serde(default)]
+    pub latency_mode: LiveLatencyMode,
+    pub delete_recording_after_days: Option<u32>,
+    pub default_creator: Option<String>,
 }
 
 /// Live input URLs response
@@ -76,17 +139,14 @@ pub struct SearchQuery {
     pub q: String,
 }
 
-/// List all videos
+/// List a page of videos
 pub async fn list_videos(
     State(services): State<Arc<CloudflareServices>>,
+    Query(params): Query<StreamListParams>,
+    pagination: Pagination,
 ) -> CloudflareResult<Json<ListVideosResponse>> {
-    let videos = services.stream.list_videos().await?;
-    let total = videos.len();
-    Ok(Json(ListVideosResponse {
-        success: true,
-        videos,
-        total,
-    }))
+    let (page, next_cursor) = services.stream.list_videos_page(params, pagination).await?;
+    Ok(Json(ListVideosResponse::new(page, next_cursor)))
 }
 
 /// Get a video by ID
@@ -98,6 +158,16 @@ pub async fn get_video(
     Ok(Json(video))
 }
 
+/// Copy a video into Stream from a URL
+pub async fn copy_video(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<CopyVideoRequest>,
+) -> CloudflareResult<Json<StreamVideo>> {
+    let meta = req.name.map(|n| serde_json::json!({ "name": n }));
+    let video = services.stream.copy_from_url(&req.url, meta).await?;
+    Ok(Json(video))
+}
+
 /// Delete a video
 pub async fn delete_video(
     State(services): State<Arc<CloudflareServices>>,
@@ -110,6 +180,45 @@ pub async fn delete_video(
     })))
 }
 
+/// List caption tracks on a video
+pub async fn list_captions(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(video_id): Path<String>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let captions = services.stream.list_captions(&video_id).await?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": captions,
+        "total": captions.len()
+    })))
+}
+
+/// Upload WebVTT captions; the request body is the `.vtt` file
+pub async fn upload_caption(
+    State(services): State<Arc<CloudflareServices>>,
+    Path((video_id, language)): Path<(String, String)>,
+    body: String,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let caption = services.stream.upload_caption(&video_id, &language, &body).await?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": caption,
+        "message": format!("{} captions uploaded", language)
+    })))
+}
+
+/// Delete the caption track for a language
+pub async fn delete_caption(
+    State(services): State<Arc<CloudflareServices>>,
+    Path((video_id, language)): Path<(String, String)>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    services.stream.delete_caption(&video_id, &language).await?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("{} captions deleted", language)
+    })))
+}
+
 /// Get video URLs
 pub async fn get_video_urls(
     State(services): State<Arc<CloudflareServices>>,
@@ -123,6 +232,30 @@ pub async fn get_video_urls(
     }))
 }
 
+/// Create a signed embed URL for a video
+pub async fn get_signed_url(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(video_id): Path<String>,
+    Query(query): Query<SignedUrlQuery>,
+) -> CloudflareResult<Json<SignedUrlResponse>> {
+    let ttl = Duration::from_secs(query.ttl.unwrap_or(DEFAULT_SIGNED_URL_TTL_SECS));
+    let restrictions = SignedUrlRestrictions {
+        downloadable: query.downloadable.unwrap_or(false),
+        ..Default::default()
+    };
+
+    let token = services
+        .stream
+        .generate_signed_token(&video_id, ttl, restrictions)
+        .await?;
+
+    Ok(Json(SignedUrlResponse {
+        embed_url: services.stream.get_signed_embed_url(&token),
+        expires_at: chrono::Utc::now() + chrono::Duration::seconds(ttl.as_secs() as i64),
+        token,
+    }))
+}
+
 /// Generate embed code
 pub async fn get_embed_code(
     State(services): State<Arc<CloudflareServices>>,
@@ -169,14 +302,11 @@ pub async fn get_stats(
 pub async fn search_videos(
     State(services): State<Arc<CloudflareServices>>,
     Query(query): Query<SearchQuery>,
+    pagination: Pagination,
 ) -> CloudflareResult<Json<ListVideosResponse>> {
     let videos = services.stream.search_videos(&query.q).await?;
-    let total = videos.len();
-    Ok(Json(ListVideosResponse {
-        success: true,
-        videos,
-        total,
-    }))
+    let page = Page::from_vec(videos, pagination);
+    Ok(Json(ListVideosResponse::new(page, None)))
 }
 
 /// List live inputs
@@ -202,20 +332,33 @@ pub async fn create_live_input(
         recording: req.recording_mode.map(|mode| LiveRecording {
             mode,
             timeout_seconds: req.timeout_seconds,
-            require_signed_urls: None,
+            require_signed_urls: req.require_signed_urls,
         }),
-    };
+        prefer_low_latency: None,
+        delete_recording_after_days: req.delete_recording_after_days,
+        default_creator: req.default_creator,
+    }
+    .with_latency_mode(req.latency_mode);
 
     let live_input = services.stream.create_live_input(input).await?;
     Ok(Json(live_input))
 }
 
+/// Get a live input by ID
+pub async fn get_live_input(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(input_id): Path<String>,
+) -> CloudflareResult<Json<LiveInput>> {
+    let live_input = services.stream.get_live_input(&input_id).await?;
+    Ok(Json(live_input))
+}
+
 /// Delete live input
 pub async fn delete_live_input(
-    State(_services): State<Arc<CloudflareServices>>,
-    Path(_input_id): Path<String>,
+    State(services): State<Arc<CloudflareServices>>,
+    Path(input_id): Path<String>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Note: delete_live_input not yet implemented in client
+    services.stream.delete_live_input(&input_id).await?;
     Ok(Json(serde_json::json!({
         "success": true,
         "message": "Live input deleted"
@@ -227,15 +370,7 @@ pub async fn get_live_input_urls(
     State(services): State<Arc<CloudflareServices>>,
     Path(input_id): Path<String>,
 ) -> CloudflareResult<Json<LiveInputUrlsResponse>> {
-    let live_inputs = services.stream.list_live_inputs().await?;
-
-    let live_input = live_inputs
-        .into_iter()
-        .find(|li| li.uid == input_id)
-        .ok_or(crate::error::CloudflareError::NotFound(format!(
-            "Live input {} not found",
-            input_id
-        )))?;
+    let live_input = services.stream.get_live_input(&input_id).await?;
 
     Ok(Json(LiveInputUrlsResponse {
         rtmps_url: services.stream.get_rtmps_url(&live_input),
@@ -243,3 +378,66 @@ pub async fn get_live_input_urls(
         webrtc_url: services.stream.get_webrtc_url(&live_input),
     }))
 }
+
+/// Get the Stream webhook subscription
+pub async fn get_webhook(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<StreamWebhook>> {
+    let webhook = services.stream.get_webhook().await?;
+    Ok(Json(webhook))
+}
+
+/// Subscribe a URL to Stream notifications
+pub async fn set_webhook(
+    State(services): State<Arc<CloudflareServices>>,
+    Json(req): Json<SetWebhookRequest>,
+) -> CloudflareResult<Json<StreamWebhook>> {
+    let webhook = services.stream.set_webhook(&req.notification_url).await?;
+    Ok(Json(webhook))
+}
+
+/// Remove the Stream webhook subscription
+pub async fn delete_webhook(
+    State(services): State<Arc<CloudflareServices>>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    services.stream.delete_webhook().await?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Stream webhook removed"
+    })))
+}
+
+/// Receive a signed notification from Stream
+pub async fn receive_webhook(
+    State(services): State<Arc<CloudflareServices>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let signature = headers
+        .get("Webhook-Signature")
+        .and_then(|v| v.to_str().ok());
+    let uid = services.stream.handle_webhook(signature, &body).await?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "uid": uid
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_videos_response_keeps_videos_array() {
+        let page = Page::new(Vec::new(), Pagination { page: 2, per_page: 25 }, 60);
+        let json = serde_json::to_value(ListVideosResponse::new(page, Some("cursor".to_string()))).unwrap();
+
+        assert!(json["videos"].as_array().unwrap().is_empty());
+        assert_eq!(json["total"], 60);
+        assert_eq!(json["page"], 2);
+        assert_eq!(json["per_page"], 25);
+        assert_eq!(json["total_pages"], 3);
+        assert_eq!(json["next_cursor"], "cursor");
+        assert!(json.get("data").is_none());
+    }
+}
@@ -2,9 +2,13 @@
 
 use axum::{
     extract::{Path, Query, State},
+    response::sse::{Event, KeepAlive, Sse},
     Json,
 };
-use serde::{Deserialize, Serialize};
+use futures::stream::{self, Stream, StreamExt};
+use std::convert::Infallible;
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 use crate::error::CloudflareResult;
 use crate::services::CloudflareServices;
@@ -13,11 +17,15 @@ use crate::services::CloudflareServices;
 pub struct DeployWorkerRequest {
     pub name: String,
     pub script: String,
+    /// Only deploy if the live script still has this etag
+    pub expected_etag: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateWorkerRequest {
     pub script: String,
+    /// Only deploy if the live script still has this etag
+    pub expected_etag: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,6 +37,9 @@ pub struct CreateRouteRequest {
 #[derive(Debug, Deserialize)]
 pub struct CreateKvNamespaceRequest {
     pub title: String,
+    /// Create it even if another namespace already has this title
+    #[serde(default)]
+    pub allow_duplicate: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -45,6 +56,9 @@ pub struct ListKvKeysQuery {
 pub struct DeployTemplateRequest {
     pub template_id: String,
     pub name: String,
+    /// Values for the template's parameters
+    #[serde(default)]
+    pub params: HashMap<String, serde_json::Value>,
 }
 
 /// List all Workers
@@ -73,12 +87,28 @@ pub async fn get_worker(
     })))
 }
 
+/// Get a Worker's bindings and compatibility settings
+pub async fn get_worker_settings(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(name): Path<String>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let settings = services.workers.get_worker_settings(&name).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": settings
+    })))
+}
+
 /// Deploy a new Worker
 pub async fn deploy_worker(
     State(services): State<Arc<CloudflareServices>>,
     Json(req): Json<DeployWorkerRequest>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let worker = services.workers.deploy(&req.name, &req.script).await?;
+    let worker = match &req.expected_etag {
+        Some(etag) => services.workers.deploy_if_match(&req.name, &req.script, etag).await?,
+        None => services.workers.deploy(&req.name, &req.script).await?,
+    };
 
     Ok(Json(serde_json::json!({
         "success": true,
@@ -93,7 +123,10 @@ pub async fn update_worker(
     Path(name): Path<String>,
     Json(req): Json<UpdateWorkerRequest>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let worker = services.workers.deploy(&name, &req.script).await?;
+    let worker = match &req.expected_etag {
+        Some(etag) => services.workers.deploy_if_match(&name, &req.script, etag).await?,
+        None => services.workers.deploy(&name, &req.script).await?,
+    };
 
     Ok(Json(serde_json::json!({
         "success": true,
@@ -147,14 +180,15 @@ pub async fn create_route(
 
 /// Delete a Worker route
 pub async fn delete_route(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Path(id): Path<String>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Would need to add delete_route to workers service
+    let result = services.workers.delete_route(&id).await?;
+
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
-            "id": id
+            "id": result.id
         },
         "message": "Route deleted successfully"
     })))
@@ -178,7 +212,7 @@ pub async fn create_kv_namespace(
     State(services): State<Arc<CloudflareServices>>,
     Json(req): Json<CreateKvNamespaceRequest>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    let namespace = services.workers.create_kv_namespace(&req.title).await?;
+    let namespace = services.workers.create_kv_namespace(&req.title, req.allow_duplicate).await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
@@ -189,10 +223,11 @@ pub async fn create_kv_namespace(
 
 /// Delete a KV namespace
 pub async fn delete_kv_namespace(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Path(id): Path<String>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Would need to add delete_kv_namespace to workers service
+    services.workers.delete_kv_namespace(&id).await?;
+
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
@@ -233,6 +268,19 @@ pub async fn get_kv_value(
     })))
 }
 
+/// Get a KV value, typed as text, JSON or binary, with its metadata
+pub async fn get_kv_entry(
+    State(services): State<Arc<CloudflareServices>>,
+    Path((namespace, key)): Path<(String, String)>,
+) -> CloudflareResult<Json<serde_json::Value>> {
+    let entry = services.workers.get_kv_entry(&namespace, &key).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": entry
+    })))
+}
+
 /// Set a KV value
 pub async fn set_kv_value(
     State(services): State<Arc<CloudflareServices>>,
@@ -266,62 +314,44 @@ pub async fn delete_kv_value(
     })))
 }
 
+/// Stream a Worker's live logs as server-sent events.
+///
+/// The first `session` event describes the tail session; each trace event
+/// follows as a `log` event. The session is deleted when the client
+/// disconnects.
+pub async fn tail_worker(
+    State(services): State<Arc<CloudflareServices>>,
+    Path(name): Path<String>,
+) -> CloudflareResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let (session, events) = services.workers.open_tail(&name).await?;
+
+    let session = Event::default()
+        .event("session")
+        .data(serde_json::to_string(&session).unwrap_or_default());
+    let events = events.map(|event| {
+        Ok(match event {
+            Ok(trace) => Event::default().event("log").data(trace.to_string()),
+            Err(e) => Event::default().event("error").data(e.to_string()),
+        })
+    });
+
+    Ok(Sse::new(stream::once(async { Ok(session) }).chain(events)).keep_alive(KeepAlive::default()))
+}
+
 /// Deploy a Worker from template
 pub async fn deploy_template(
-    State(_services): State<Arc<CloudflareServices>>,
+    State(services): State<Arc<CloudflareServices>>,
     Json(req): Json<DeployTemplateRequest>,
 ) -> CloudflareResult<Json<serde_json::Value>> {
-    // Template deployment would fetch template script and deploy it
-    let templates = get_worker_templates();
-
-    let template = templates.iter().find(|t| t.id == req.template_id);
-
-    if template.is_none() {
-        return Ok(Json(serde_json::json!({
-            "success": false,
-            "error": format!("Template '{}' not found", req.template_id)
-        })));
-    }
+    let worker = services.workers.deploy_from_template(&req.template_id, &req.name, &req.params).await?;
 
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
+            "worker": worker,
             "name": req.name,
             "template_id": req.template_id
         },
-        "message": format!("Worker '{}' deployed from template", req.name)
+        "message": format!("Worker '{}' deployed from template '{}'", req.name, req.template_id)
     })))
 }
-
-/// Worker template definition
-#[derive(Debug, Serialize)]
-struct WorkerTemplate {
-    id: &'static str,
-    name: &'static str,
-    description: &'static str,
-}
-
-fn get_worker_templates() -> Vec<WorkerTemplate> {
-    vec![
-        WorkerTemplate {
-            id: "redirect",
-            name: "Redirect Worker",
-            description: "Redirect requests to a different URL",
-        },
-        WorkerTemplate {
-            id: "maintenance",
-            name: "Maintenance Mode",
-            description: "Display a maintenance page",
-        },
-        WorkerTemplate {
-            id: "ab-test",
-            name: "A/B Testing",
-            description: "Route traffic for A/B testing",
-        },
-        WorkerTemplate {
-            id: "edge-cache",
-            name: "Edge Caching",
-            description: "Custom caching logic at the edge",
-        },
-    ]
-}
@@ -0,0 +1,348 @@
+//! Circuit breakers for Cloudflare API calls
+//!
+//! During a Cloudflare outage every auto-purge and dashboard poll would
+//! otherwise wait out its own timeouts and retries. Each endpoint category
+//! (cache, dns, security, ...) gets a breaker that opens after repeated
+//! outage-type failures, fast-fails calls for a cooldown, then lets a single
+//! probe through to decide whether to close again.
+
+use crate::error::{CloudflareError, CloudflareResult};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Consecutive failures that open a circuit
+pub const FAILURE_THRESHOLD: u32 = 5;
+/// Failures further apart than this don't count as consecutive
+pub const FAILURE_WINDOW: Duration = Duration::from_secs(60);
+/// How long an open circuit fast-fails before probing again
+pub const OPEN_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// State of a single circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls go through
+    Closed,
+    /// Calls fail immediately until the cooldown passes
+    Open,
+    /// One probe call is allowed through to test the API
+    HalfOpen,
+}
+
+/// Circuit state as reported by the status endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitSnapshot {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Seconds until an open circuit lets a probe through
+    pub retry_in_secs: Option<u64>,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    failures: u32,
+    first_failure_at: Option<Instant>,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+impl Default for Circuit {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            failures: 0,
+            first_failure_at: None,
+            opened_at: None,
+            probe_in_flight: false,
+        }
+    }
+}
+
+/// Per-category circuit breakers, shared between clones of a client
+#[derive(Debug, Clone)]
+pub struct CircuitBreakers {
+    circuits: Arc<Mutex<HashMap<&'static str, Circuit>>>,
+    threshold: u32,
+    window: Duration,
+    cooldown: Duration,
+}
+
+impl Default for CircuitBreakers {
+    fn default() -> Self {
+        Self::new(FAILURE_THRESHOLD, FAILURE_WINDOW, OPEN_COOLDOWN)
+    }
+}
+
+impl CircuitBreakers {
+    pub fn new(threshold: u32, window: Duration, cooldown: Duration) -> Self {
+        Self {
+            circuits: Arc::new(Mutex::new(HashMap::new())),
+            threshold: threshold.max(1),
+            window,
+            cooldown,
+        }
+    }
+
+    /// Check a call to `endpoint` may go ahead, returning its category
+    pub fn before_call(&self, endpoint: &str) -> CloudflareResult<&'static str> {
+        let category = endpoint_category(endpoint);
+        self.before_call_at(category, Instant::now())?;
+        Ok(category)
+    }
+
+    /// Record the outcome of a call made after `before_call`, passing the result through
+    pub fn record<T>(&self, category: &'static str, result: CloudflareResult<T>) -> CloudflareResult<T> {
+        let failed = result.as_ref().err().is_some_and(is_outage);
+        self.record_at(category, failed, Instant::now());
+        result
+    }
+
+    fn before_call_at(&self, category: &'static str, now: Instant) -> CloudflareResult<()> {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        let circuit = circuits.entry(category).or_default();
+
+        match circuit.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let opened_at = circuit.opened_at.unwrap_or(now);
+                if now.duration_since(opened_at) >= self.cooldown {
+                    info!("Cloudflare {} API circuit half-open, probing", category);
+                    circuit.state = CircuitState::HalfOpen;
+                    circuit.probe_in_flight = true;
+                    circuit.opened_at = Some(now);
+                    Ok(())
+                } else {
+                    Err(open_error(category, self.cooldown - now.duration_since(opened_at)))
+                }
+            }
+            // Let another probe through if the last one never reported back
+            CircuitState::HalfOpen
+                if !circuit.probe_in_flight
+                    || circuit
+                        .opened_at
+                        .is_none_or(|probed_at| now.duration_since(probed_at) >= self.cooldown) =>
+            {
+                circuit.probe_in_flight = true;
+                circuit.opened_at = Some(now);
+                Ok(())
+            }
+            CircuitState::HalfOpen => Err(open_error(category, Duration::ZERO)),
+        }
+    }
+
+    fn record_at(&self, category: &'static str, failed: bool, now: Instant) {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        let circuit = circuits.entry(category).or_default();
+        circuit.probe_in_flight = false;
+
+        if !failed {
+            if circuit.state != CircuitState::Closed {
+                info!("Cloudflare {} API circuit closed", category);
+            }
+            *circuit = Circuit::default();
+            return;
+        }
+
+        match circuit.state {
+            CircuitState::HalfOpen => {
+                warn!("Cloudflare {} API probe failed, circuit open again", category);
+                circuit.state = CircuitState::Open;
+                circuit.opened_at = Some(now);
+            }
+            CircuitState::Open => circuit.opened_at = Some(now),
+            CircuitState::Closed => {
+                let in_window = circuit
+                    .first_failure_at
+                    .is_some_and(|first| now.duration_since(first) <= self.window);
+                if in_window {
+                    circuit.failures += 1;
+                } else {
+                    circuit.failures = 1;
+                    circuit.first_failure_at = Some(now);
+                }
+
+                if circuit.failures >= self.threshold {
+                    warn!(
+                        "Cloudflare {} API circuit open after {} consecutive failures",
+                        category, circuit.failures
+                    );
+                    circuit.state = CircuitState::Open;
+                    circuit.opened_at = Some(now);
+                }
+            }
+        }
+    }
+
+    /// State of a category's circuit
+    pub fn state(&self, category: &str) -> CircuitState {
+        self.circuits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(category)
+            .map(|c| c.state)
+            .unwrap_or(CircuitState::Closed)
+    }
+
+    /// State of every circuit that has seen a call
+    pub fn snapshot(&self) -> BTreeMap<String, CircuitSnapshot> {
+        let now = Instant::now();
+        self.circuits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(category, circuit)| {
+                let retry_in_secs = match (circuit.state, circuit.opened_at) {
+                    (CircuitState::Open, Some(opened_at)) => Some(
+                        self.cooldown
+                            .saturating_sub(now.duration_since(opened_at))
+                            .as_secs(),
+                    ),
+                    _ => None,
+                };
+                (
+                    category.to_string(),
+                    CircuitSnapshot {
+                        state: circuit.state,
+                        consecutive_failures: circuit.failures,
+                        retry_in_secs,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Every category `endpoint_category` returns
+pub const ENDPOINT_CATEGORIES: &[&str] = &[
+    "cache", "dns", "security", "workers", "kv", "r2", "d1", "stream", "analytics", "ssl", "user", "zone",
+];
+
+/// Group an API endpoint into the category its circuit is tracked under
+pub fn endpoint_category(endpoint: &str) -> &'static str {
+    let endpoint = endpoint.split('?').next().unwrap_or_default();
+    let has = |marker: &str| endpoint.contains(marker);
+
+    if has("/purge_cache") || has("/cache/") {
+        "cache"
+    } else if has("/dns_records") || has("/dnssec") {
+        "dns"
+    } else if has("/firewall") || has("/rulesets") || has("/rules/") || has("/waf") || has("/challenges")
+        || has("/bot_management")
+    {
+        "security"
+    } else if has("/workers/") {
+        "workers"
+    } else if has("/storage/kv") {
+        "kv"
+    } else if has("/r2/") {
+        "r2"
+    } else if has("/d1/") {
+        "d1"
+    } else if has("/stream") {
+        "stream"
+    } else if has("/analytics") {
+        "analytics"
+    } else if has("/ssl") || has("/certificates") || has("/custom_hostnames") {
+        "ssl"
+    } else if endpoint.starts_with("/user") {
+        "user"
+    } else {
+        "zone"
+    }
+}
+
+/// Errors that suggest Cloudflare itself is unreachable or failing, as
+/// opposed to rejecting this particular request
+fn is_outage(error: &CloudflareError) -> bool {
+    matches!(
+        error,
+        CloudflareError::NetworkError(_)
+            | CloudflareError::Timeout(_)
+            | CloudflareError::ServiceUnavailable(_)
+            | CloudflareError::Reqwest(_)
+    )
+}
+
+fn open_error(category: &str, retry_in: Duration) -> CloudflareError {
+    CloudflareError::ServiceUnavailable(format!(
+        "Cloudflare {} API circuit open after repeated failures; retrying in {}s",
+        category,
+        retry_in.as_secs()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_opens_probes_and_closes() {
+        let breakers = CircuitBreakers::new(3, Duration::from_secs(60), Duration::from_secs(30));
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        // Closed: failures below the threshold still go through
+        for secs in 0..2 {
+            breakers.before_call_at("cache", at(secs)).unwrap();
+            breakers.record_at("cache", true, at(secs));
+        }
+        assert_eq!(breakers.state("cache"), CircuitState::Closed);
+
+        // Third consecutive failure opens it
+        breakers.before_call_at("cache", at(2)).unwrap();
+        breakers.record_at("cache", true, at(2));
+        assert_eq!(breakers.state("cache"), CircuitState::Open);
+
+        // Open: fast-fails during the cooldown, other categories unaffected
+        let err = breakers.before_call_at("cache", at(10)).unwrap_err();
+        assert!(matches!(err, CloudflareError::ServiceUnavailable(ref m) if m.contains("cache API circuit open")));
+        breakers.before_call_at("dns", at(10)).unwrap();
+
+        // Half-open: one probe after the cooldown, everything else still fails
+        breakers.before_call_at("cache", at(32)).unwrap();
+        assert_eq!(breakers.state("cache"), CircuitState::HalfOpen);
+        assert!(breakers.before_call_at("cache", at(32)).is_err());
+
+        // A failed probe reopens the circuit
+        breakers.record_at("cache", true, at(33));
+        assert_eq!(breakers.state("cache"), CircuitState::Open);
+        assert!(breakers.before_call_at("cache", at(40)).is_err());
+
+        // A successful probe closes it
+        breakers.before_call_at("cache", at(63)).unwrap();
+        breakers.record_at("cache", false, at(63));
+        assert_eq!(breakers.state("cache"), CircuitState::Closed);
+        breakers.before_call_at("cache", at(64)).unwrap();
+    }
+
+    #[test]
+    fn test_spread_out_failures_do_not_open_circuit() {
+        let breakers = CircuitBreakers::new(2, Duration::from_secs(60), Duration::from_secs(30));
+        let start = Instant::now();
+
+        breakers.record_at("dns", true, start);
+        breakers.record_at("dns", true, start + Duration::from_secs(120));
+        assert_eq!(breakers.state("dns"), CircuitState::Closed);
+
+        // Non-outage errors count as the API being reachable
+        let result: CloudflareResult<()> = Err(CloudflareError::ValidationError("bad".to_string()));
+        assert!(breakers.record("dns", result).is_err());
+        assert_eq!(breakers.snapshot()["dns"].consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_endpoint_category() {
+        assert_eq!(endpoint_category("/zones/z/purge_cache"), "cache");
+        assert_eq!(endpoint_category("/zones/z/dns_records?page=2"), "dns");
+        assert_eq!(endpoint_category("/zones/z/firewall/access_rules/rules"), "security");
+        assert_eq!(endpoint_category("/zones/z/bot_management"), "security");
+        assert_eq!(endpoint_category("/accounts/a/workers/scripts/app"), "workers");
+        assert_eq!(endpoint_category("/accounts/a/storage/kv/namespaces"), "kv");
+        assert_eq!(endpoint_category("/user/tokens/verify"), "user");
+        assert_eq!(endpoint_category("/zones/z"), "zone");
+    }
+}
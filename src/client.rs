@@ -2,31 +2,60 @@
 //!
 //! HTTP client for communicating with the Cloudflare API
 
-use crate::config::CloudflareConfig;
+use crate::circuit_breaker::CircuitBreakers;
+use crate::config::{default_user_agent, CloudflareConfig, RequestTimeouts};
 use crate::error::{CloudflareError, CloudflareResult};
+use crate::metrics::Metrics;
 use crate::models::*;
+use crate::single_flight::SingleFlight;
+use crate::utils::redact_secrets;
 use reqwest::{header, Client, Response, StatusCode};
 use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::time::sleep;
-use tracing::{debug, error, warn};
+use tracing::{debug, error, info, warn};
 
 /// Default max retry attempts
 const MAX_RETRIES: u32 = 3;
 /// Base delay for exponential backoff (milliseconds)
 const BASE_DELAY_MS: u64 = 1000;
+/// Cloudflare error code returned when a phase has no entrypoint ruleset yet
+const NO_ENTRYPOINT_RULESET: i32 = 10003;
+/// Response header carrying Cloudflare's request ID
+const CF_RAY_HEADER: &str = "cf-ray";
 
-/// Base URL for Cloudflare API
-const API_BASE_URL: &str = "https://api.cloudflare.com/client/v4";
+/// Most KV namespaces the API returns per page
+const KV_NAMESPACES_PER_PAGE: i32 = 100;
+/// DNS records requested per page when walking every page of a listing
+const DNS_RECORDS_PER_PAGE: i32 = 1000;
+/// Request header carrying the plugin version
+pub const PLUGIN_VERSION_HEADER: &str = "x-plugin-version";
+
+/// Outcome of a GET, shared between identical requests made at the same time
+type SharedGet = Result<Arc<ApiResponse<serde_json::Value>>, Arc<CloudflareError>>;
 
 /// Cloudflare API client
 #[derive(Debug, Clone)]
 pub struct CloudflareClient {
-    client: Client,
-    #[allow(dead_code)]
-    api_token: String,
+    /// Shared between clones so a token rotation reaches every service
+    client: Arc<RwLock<Client>>,
+    base_url: String,
     account_id: String,
     zone_id: String,
+    /// Log requests and responses, see `CloudflareConfig::verbose_logging`
+    verbose: bool,
+    /// Sent with every request, see `CloudflareConfig::user_agent`
+    user_agent: String,
+    /// Applied per request according to the kind of operation
+    timeouts: RequestTimeouts,
+    /// Shared between clones so every service sees the same circuit state
+    breakers: CircuitBreakers,
+    /// Shared between clones so every call is counted once
+    metrics: Metrics,
+    /// GETs in flight, shared between clones using the same token
+    gets: SingleFlight<SharedGet>,
 }
 
 impl CloudflareClient {
@@ -34,10 +63,27 @@ impl CloudflareClient {
     pub fn new(config: &CloudflareConfig) -> CloudflareResult<Self> {
         config.validate()?;
 
-        let mut headers = header::HeaderMap::new();
+        let user_agent = config.user_agent();
+        Ok(Self {
+            client: Arc::new(RwLock::new(Self::build_http_client(&config.api_token, &user_agent)?)),
+            base_url: config.api_base_url().to_string(),
+            account_id: config.account_id.clone(),
+            zone_id: config.zone_id.clone(),
+            verbose: config.verbose_logging,
+            user_agent,
+            timeouts: config.request_timeouts(),
+            breakers: CircuitBreakers::default(),
+            metrics: Metrics::default(),
+            gets: SingleFlight::default(),
+        })
+    }
+
+    /// Build the HTTP client authenticating with the given token
+    fn build_http_client(api_token: &str, user_agent: &str) -> CloudflareResult<Client> {
+        let mut headers = identification_headers(user_agent);
         headers.insert(
             header::AUTHORIZATION,
-            header::HeaderValue::from_str(&format!("Bearer {}", config.api_token))
+            header::HeaderValue::from_str(&format!("Bearer {}", api_token))
                 .map_err(|_| CloudflareError::InvalidToken)?,
         );
         headers.insert(
@@ -45,21 +91,82 @@ impl CloudflareClient {
             header::HeaderValue::from_static("application/json"),
         );
 
-        let client = Client::builder()
+        // Overall timeouts are set per request, see `RequestTimeouts`
+        Client::builder()
             .default_headers(headers)
-            .timeout(Duration::from_secs(30))
             .connect_timeout(Duration::from_secs(10))
             .build()
-            .map_err(|e| CloudflareError::NetworkError(e.to_string()))?;
+            .map_err(|e| CloudflareError::NetworkError(e.to_string()))
+    }
 
+    /// Create a separate client for the same account and zone using another token
+    pub fn with_token(&self, api_token: &str) -> CloudflareResult<Self> {
         Ok(Self {
-            client,
-            api_token: config.api_token.clone(),
-            account_id: config.account_id.clone(),
-            zone_id: config.zone_id.clone(),
+            client: Arc::new(RwLock::new(Self::build_http_client(api_token, &self.user_agent)?)),
+            base_url: self.base_url.clone(),
+            account_id: self.account_id.clone(),
+            zone_id: self.zone_id.clone(),
+            verbose: self.verbose,
+            user_agent: self.user_agent.clone(),
+            timeouts: self.timeouts,
+            breakers: self.breakers.clone(),
+            metrics: self.metrics.clone(),
+            // Another token may see different results, so it never shares a GET
+            gets: SingleFlight::default(),
         })
     }
 
+    /// Swap the token used by this client and every clone sharing it
+    pub fn set_api_token(&self, api_token: &str) -> CloudflareResult<()> {
+        let client = Self::build_http_client(api_token, &self.user_agent)?;
+        *self.client.write().unwrap_or_else(|e| e.into_inner()) = client;
+        Ok(())
+    }
+
+    /// Current HTTP client
+    fn http(&self) -> Client {
+        self.client.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Point the client at a different API base URL (e.g. a mock server)
+    #[cfg(test)]
+    pub(crate) fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Use different per-request timeouts
+    #[cfg(test)]
+    pub(crate) fn with_timeouts(mut self, timeouts: RequestTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Per-request timeouts by kind of operation
+    pub fn timeouts(&self) -> RequestTimeouts {
+        self.timeouts
+    }
+
+    /// Get the API base URL requests are sent to
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// `User-Agent` sent with every request
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// Circuit breakers guarding calls to each API category
+    pub fn circuit_breakers(&self) -> &CircuitBreakers {
+        &self.breakers
+    }
+
+    /// Counters for API calls and purges made through this client
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
     /// Get the zone ID
     pub fn zone_id(&self) -> &str {
         &self.zone_id
@@ -72,8 +179,10 @@ impl CloudflareClient {
 
     /// Verify the connection to Cloudflare
     pub async fn verify_connection(&self) -> CloudflareResult<()> {
-        let url = format!("{}/user/tokens/verify", API_BASE_URL);
-        let response = self.client.get(&url).send().await?;
+        let url = format!("{}/user/tokens/verify", self.base_url);
+        self.log_request("GET", &url, None);
+        let response = self.http().get(&url).timeout(self.timeouts.read).send().await.map_err(request_error)?;
+        self.log_response(&response);
 
         if response.status().is_success() {
             debug!("Cloudflare connection verified");
@@ -88,6 +197,125 @@ impl CloudflareClient {
         }
     }
 
+    /// Check the token can use the permissions the plugin relies on.
+    ///
+    /// Returns `PermissionDenied` listing every permission the token lacks.
+    pub async fn verify_required_permissions(&self) -> CloudflareResult<()> {
+        self.verify_connection().await?;
+
+        let mut missing = Vec::new();
+        if self.get_zone().await.is_err() {
+            missing.push("Zone Read");
+        }
+        if self.get_zone_settings().await.is_err() {
+            missing.push("Zone Settings Read");
+        }
+        if self.list_dns_records(None).await.is_err() {
+            missing.push("DNS Read");
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(CloudflareError::PermissionDenied(format!(
+                "API token is missing required permissions: {}",
+                missing.join(", ")
+            )))
+        }
+    }
+
+    /// Probe each feature area with a cheap read, reporting each outcome
+    /// separately so one missing scope doesn't hide the others
+    pub async fn check_capabilities(&self) -> BTreeMap<&'static str, CapabilityCheck> {
+        let dns_params = DnsListParams { per_page: Some(5), ..Default::default() };
+        let (zone, dns, cache, workers, r2) = futures::join!(
+            self.get_zone(),
+            self.list_dns_records_page(Some(dns_params)),
+            self.get_zone_setting("cache_level"),
+            self.list_workers(),
+            self.list_r2_buckets(),
+        );
+
+        BTreeMap::from([
+            ("zone", capability_check(zone)),
+            ("dns", capability_check(dns)),
+            ("cache", capability_check(cache)),
+            ("workers", capability_check(workers)),
+            ("r2", capability_check(r2)),
+        ])
+    }
+
+    /// Verify the token and return its ID and status
+    pub async fn verify_token(&self) -> CloudflareResult<TokenVerification> {
+        let response: ApiResponse<TokenVerification> = self.get("/user/tokens/verify").await?;
+        response.result.ok_or(CloudflareError::AuthenticationError(
+            "Token verification failed".to_string(),
+        ))
+    }
+
+    /// Get an API token's details, including its policies.
+    ///
+    /// Needs the "API Tokens Read" permission, which many tokens don't have.
+    pub async fn get_token(&self, id: &str) -> CloudflareResult<ApiToken> {
+        let response: ApiResponse<ApiToken> = self.get(&format!("/user/tokens/{}", id)).await?;
+        response.result.ok_or(CloudflareError::NotFound(format!("API token {}", id)))
+    }
+
+    /// Report whether the token works, which zone it reaches, and which required scopes it lacks
+    pub async fn connection_status(&self) -> ConnectionStatus {
+        let verification = match self.verify_token().await {
+            Ok(verification) => verification,
+            Err(e) => {
+                return ConnectionStatus {
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                }
+            }
+        };
+
+        let mut status = ConnectionStatus {
+            connected: verification.status == "active",
+            token_status: Some(verification.status.clone()),
+            ..Default::default()
+        };
+
+        if !status.connected {
+            status
+                .warnings
+                .push(format!("API token status is '{}'", verification.status));
+        }
+
+        match self.get_zone().await {
+            Ok(zone) => {
+                status.zone_name = Some(zone.name);
+                status.zone_status = Some(zone.status);
+            }
+            Err(e) => status.warnings.push(format!("Could not load zone {}: {}", self.zone_id, e)),
+        }
+
+        match self.get_token(&verification.id).await {
+            Ok(token) => {
+                status.missing_scopes = token.missing_scopes();
+                status.permission_groups = Some(token.permission_groups());
+                if !status.missing_scopes.is_empty() {
+                    status.warnings.push(format!(
+                        "API token is missing required scopes: {}. Features that need them, such as cache purging or DNS changes, will fail.",
+                        status.missing_scopes.join(", ")
+                    ));
+                }
+            }
+            Err(e) => {
+                debug!("Could not read token permissions: {}", e);
+                status.warnings.push(
+                    "Could not read the token's permissions; grant \"API Tokens Read\" to check its scopes"
+                        .to_string(),
+                );
+            }
+        }
+
+        status
+    }
+
     /// Check if an error is retryable
     fn is_retryable_error(status: StatusCode) -> bool {
         matches!(
@@ -99,6 +327,17 @@ impl CloudflareClient {
         )
     }
 
+    /// Statuses meaning the request was turned away before it was applied.
+    /// A gateway error may arrive after the origin already acted on it.
+    fn is_unapplied_error(status: StatusCode) -> bool {
+        matches!(status, StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE)
+    }
+
+    /// Record a finished call with the circuit breaker and metrics
+    fn record<T>(&self, category: &'static str, result: CloudflareResult<T>) -> CloudflareResult<T> {
+        self.metrics.record_call(category, self.breakers.record(category, result))
+    }
+
     /// Calculate backoff delay with exponential increase and jitter
     fn calculate_backoff(attempt: u32) -> Duration {
         let base_delay = BASE_DELAY_MS * 2u64.pow(attempt);
@@ -106,14 +345,49 @@ impl CloudflareClient {
         Duration::from_millis(base_delay + jitter)
     }
 
-    /// Make a GET request with retry logic
+    /// Make a GET request, sharing the response with an identical GET
+    /// that is already in flight
     async fn get<T: DeserializeOwned>(&self, endpoint: &str) -> CloudflareResult<ApiResponse<T>> {
-        let url = format!("{}{}", API_BASE_URL, endpoint);
+        let client = self.clone();
+        let owned_endpoint = endpoint.to_string();
+        let shared = self
+            .gets
+            .run(&format!("GET {}{}", self.base_url, endpoint), move || async move {
+                client
+                    .get_uncoalesced::<serde_json::Value>(&owned_endpoint)
+                    .await
+                    .map(Arc::new)
+                    .map_err(Arc::new)
+            })
+            .await;
+
+        let response = shared.map_err(|e| e.duplicate())?;
+        let result = match &response.result {
+            Some(value) => Some(
+                T::deserialize(value)
+                    .map_err(|e| CloudflareError::Internal(format!("Failed to parse response: {}", e)))?,
+            ),
+            None => None,
+        };
+        Ok(ApiResponse {
+            success: response.success,
+            result,
+            errors: response.errors.clone(),
+            messages: response.messages.clone(),
+            result_info: response.result_info.clone(),
+        })
+    }
+
+    /// Make a GET request with retry logic
+    async fn get_uncoalesced<T: DeserializeOwned>(&self, endpoint: &str) -> CloudflareResult<ApiResponse<T>> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let category = self.breakers.before_call(endpoint)?;
 
         for attempt in 0..MAX_RETRIES {
             debug!("GET {} (attempt {})", url, attempt + 1);
+            self.log_request("GET", &url, None);
 
-            match self.client.get(&url).send().await {
+            match self.http().get(&url).timeout(self.timeouts.read).send().await {
                 Ok(response) => {
                     let status = response.status();
                     if Self::is_retryable_error(status) && attempt < MAX_RETRIES - 1 {
@@ -122,19 +396,22 @@ impl CloudflareClient {
                         sleep(delay).await;
                         continue;
                     }
-                    return self.handle_response(response).await;
+                    return self.record(category, self.handle_response(response).await);
                 }
-                Err(e) if attempt < MAX_RETRIES - 1 && e.is_timeout() => {
+                Err(e) if attempt < MAX_RETRIES - 1 && (e.is_timeout() || e.is_connect()) => {
                     let delay = Self::calculate_backoff(attempt);
-                    warn!("Timeout for GET {}, retrying in {:?}", url, delay);
+                    warn!("GET {} failed ({}), retrying in {:?}", url, e, delay);
                     sleep(delay).await;
                     continue;
                 }
-                Err(e) => return Err(e.into()),
+                Err(e) => return self.record(category, Err(request_error(e))),
             }
         }
 
-        Err(CloudflareError::NetworkError("Max retries exceeded".to_string()))
+        self.record(
+            category,
+            Err(CloudflareError::NetworkError("Max retries exceeded".to_string())),
+        )
     }
 
     /// Make a POST request with retry logic
@@ -143,13 +420,15 @@ impl CloudflareClient {
         endpoint: &str,
         body: &B,
     ) -> CloudflareResult<ApiResponse<T>> {
-        let url = format!("{}{}", API_BASE_URL, endpoint);
+        let url = format!("{}{}", self.base_url, endpoint);
         let body_json = serde_json::to_value(body).map_err(|e| CloudflareError::Internal(e.to_string()))?;
+        let category = self.breakers.before_call(endpoint)?;
 
         for attempt in 0..MAX_RETRIES {
             debug!("POST {} (attempt {})", url, attempt + 1);
+            self.log_request("POST", &url, Some(&body_json));
 
-            match self.client.post(&url).json(&body_json).send().await {
+            match self.http().post(&url).json(&body_json).timeout(self.timeouts.mutation).send().await {
                 Ok(response) => {
                     let status = response.status();
                     if Self::is_retryable_error(status) && attempt < MAX_RETRIES - 1 {
@@ -158,15 +437,65 @@ impl CloudflareClient {
                         sleep(delay).await;
                         continue;
                     }
-                    return self.handle_response(response).await;
+                    return self.record(category, self.handle_response(response).await);
+                }
+                Err(e) if attempt < MAX_RETRIES - 1 && (e.is_timeout() || e.is_connect()) => {
+                    let delay = Self::calculate_backoff(attempt);
+                    warn!("POST {} failed ({}), retrying in {:?}", url, e, delay);
+                    sleep(delay).await;
+                    continue;
+                }
+                Err(e) => return self.record(category, Err(request_error(e))),
+            }
+        }
+
+        self.record(
+            category,
+            Err(CloudflareError::NetworkError("Max retries exceeded".to_string())),
+        )
+    }
+
+    /// Send a multipart upload with retry logic.
+    ///
+    /// A `Form` is consumed by sending it, so `build_form` makes a fresh one
+    /// for every attempt. Uploads aren't idempotent, so only failures that
+    /// show nothing was applied are retried: 429 or 503, or a connection that
+    /// was never made. A 502, 504 or timeout may have landed and is returned
+    /// as-is.
+    async fn send_multipart(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        build_form: impl Fn() -> reqwest::multipart::Form,
+        decorate: impl Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    ) -> CloudflareResult<Response> {
+        for attempt in 0..MAX_RETRIES {
+            debug!("{} {} (multipart, attempt {})", method, url, attempt + 1);
+            self.log_request(method.as_str(), url, None);
+
+            let request = self
+                .http()
+                .request(method.clone(), url)
+                .multipart(build_form())
+                .timeout(self.timeouts.upload);
+            match decorate(request).send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    if Self::is_unapplied_error(status) && attempt < MAX_RETRIES - 1 {
+                        let delay = Self::calculate_backoff(attempt);
+                        warn!("Retryable error {} for {} {}, retrying upload in {:?}", status, method, url, delay);
+                        sleep(delay).await;
+                        continue;
+                    }
+                    return Ok(response);
                 }
-                Err(e) if attempt < MAX_RETRIES - 1 && e.is_timeout() => {
+                Err(e) if attempt < MAX_RETRIES - 1 && e.is_connect() => {
                     let delay = Self::calculate_backoff(attempt);
-                    warn!("Timeout for POST {}, retrying in {:?}", url, delay);
+                    warn!("{} {} failed ({}), retrying upload in {:?}", method, url, e, delay);
                     sleep(delay).await;
                     continue;
                 }
-                Err(e) => return Err(e.into()),
+                Err(e) => return Err(request_error(e)),
             }
         }
 
@@ -179,13 +508,15 @@ impl CloudflareClient {
         endpoint: &str,
         body: &B,
     ) -> CloudflareResult<ApiResponse<T>> {
-        let url = format!("{}{}", API_BASE_URL, endpoint);
+        let url = format!("{}{}", self.base_url, endpoint);
         let body_json = serde_json::to_value(body).map_err(|e| CloudflareError::Internal(e.to_string()))?;
+        let category = self.breakers.before_call(endpoint)?;
 
         for attempt in 0..MAX_RETRIES {
             debug!("PUT {} (attempt {})", url, attempt + 1);
+            self.log_request("PUT", &url, Some(&body_json));
 
-            match self.client.put(&url).json(&body_json).send().await {
+            match self.http().put(&url).json(&body_json).timeout(self.timeouts.mutation).send().await {
                 Ok(response) => {
                     let status = response.status();
                     if Self::is_retryable_error(status) && attempt < MAX_RETRIES - 1 {
@@ -194,19 +525,22 @@ impl CloudflareClient {
                         sleep(delay).await;
                         continue;
                     }
-                    return self.handle_response(response).await;
+                    return self.record(category, self.handle_response(response).await);
                 }
-                Err(e) if attempt < MAX_RETRIES - 1 && e.is_timeout() => {
+                Err(e) if attempt < MAX_RETRIES - 1 && (e.is_timeout() || e.is_connect()) => {
                     let delay = Self::calculate_backoff(attempt);
-                    warn!("Timeout for PUT {}, retrying in {:?}", url, delay);
+                    warn!("PUT {} failed ({}), retrying in {:?}", url, e, delay);
                     sleep(delay).await;
                     continue;
                 }
-                Err(e) => return Err(e.into()),
+                Err(e) => return self.record(category, Err(request_error(e))),
             }
         }
 
-        Err(CloudflareError::NetworkError("Max retries exceeded".to_string()))
+        self.record(
+            category,
+            Err(CloudflareError::NetworkError("Max retries exceeded".to_string())),
+        )
     }
 
     /// Make a PATCH request with retry logic
@@ -215,13 +549,15 @@ impl CloudflareClient {
         endpoint: &str,
         body: &B,
     ) -> CloudflareResult<ApiResponse<T>> {
-        let url = format!("{}{}", API_BASE_URL, endpoint);
+        let url = format!("{}{}", self.base_url, endpoint);
         let body_json = serde_json::to_value(body).map_err(|e| CloudflareError::Internal(e.to_string()))?;
+        let category = self.breakers.before_call(endpoint)?;
 
         for attempt in 0..MAX_RETRIES {
             debug!("PATCH {} (attempt {})", url, attempt + 1);
+            self.log_request("PATCH", &url, Some(&body_json));
 
-            match self.client.patch(&url).json(&body_json).send().await {
+            match self.http().patch(&url).json(&body_json).timeout(self.timeouts.mutation).send().await {
                 Ok(response) => {
                     let status = response.status();
                     if Self::is_retryable_error(status) && attempt < MAX_RETRIES - 1 {
@@ -230,29 +566,34 @@ impl CloudflareClient {
                         sleep(delay).await;
                         continue;
                     }
-                    return self.handle_response(response).await;
+                    return self.record(category, self.handle_response(response).await);
                 }
-                Err(e) if attempt < MAX_RETRIES - 1 && e.is_timeout() => {
+                Err(e) if attempt < MAX_RETRIES - 1 && (e.is_timeout() || e.is_connect()) => {
                     let delay = Self::calculate_backoff(attempt);
-                    warn!("Timeout for PATCH {}, retrying in {:?}", url, delay);
+                    warn!("PATCH {} failed ({}), retrying in {:?}", url, e, delay);
                     sleep(delay).await;
                     continue;
                 }
-                Err(e) => return Err(e.into()),
+                Err(e) => return self.record(category, Err(request_error(e))),
             }
         }
 
-        Err(CloudflareError::NetworkError("Max retries exceeded".to_string()))
+        self.record(
+            category,
+            Err(CloudflareError::NetworkError("Max retries exceeded".to_string())),
+        )
     }
 
     /// Make a DELETE request with retry logic
     async fn delete<T: DeserializeOwned>(&self, endpoint: &str) -> CloudflareResult<ApiResponse<T>> {
-        let url = format!("{}{}", API_BASE_URL, endpoint);
+        let url = format!("{}{}", self.base_url, endpoint);
+        let category = self.breakers.before_call(endpoint)?;
 
         for attempt in 0..MAX_RETRIES {
             debug!("DELETE {} (attempt {})", url, attempt + 1);
+            self.log_request("DELETE", &url, None);
 
-            match self.client.delete(&url).send().await {
+            match self.http().delete(&url).timeout(self.timeouts.mutation).send().await {
                 Ok(response) => {
                     let status = response.status();
                     if Self::is_retryable_error(status) && attempt < MAX_RETRIES - 1 {
@@ -261,19 +602,92 @@ impl CloudflareClient {
                         sleep(delay).await;
                         continue;
                     }
-                    return self.handle_response(response).await;
+                    return self.record(category, self.handle_response(response).await);
                 }
-                Err(e) if attempt < MAX_RETRIES - 1 && e.is_timeout() => {
+                Err(e) if attempt < MAX_RETRIES - 1 && (e.is_timeout() || e.is_connect()) => {
                     let delay = Self::calculate_backoff(attempt);
-                    warn!("Timeout for DELETE {}, retrying in {:?}", url, delay);
+                    warn!("DELETE {} failed ({}), retrying in {:?}", url, e, delay);
                     sleep(delay).await;
                     continue;
                 }
-                Err(e) => return Err(e.into()),
+                Err(e) => return self.record(category, Err(request_error(e))),
             }
         }
 
-        Err(CloudflareError::NetworkError("Max retries exceeded".to_string()))
+        self.record(
+            category,
+            Err(CloudflareError::NetworkError("Max retries exceeded".to_string())),
+        )
+    }
+
+    /// Make a DELETE request with a JSON body, with retry logic
+    async fn delete_with_body<T: DeserializeOwned, B: Serialize>(
+        &self,
+        endpoint: &str,
+        body: &B,
+    ) -> CloudflareResult<ApiResponse<T>> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let body_json = serde_json::to_value(body).map_err(|e| CloudflareError::Internal(e.to_string()))?;
+        let category = self.breakers.before_call(endpoint)?;
+
+        for attempt in 0..MAX_RETRIES {
+            debug!("DELETE {} (attempt {})", url, attempt + 1);
+            self.log_request("DELETE", &url, Some(&body_json));
+
+            match self.http().delete(&url).json(&body_json).timeout(self.timeouts.mutation).send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    if Self::is_retryable_error(status) && attempt < MAX_RETRIES - 1 {
+                        let delay = Self::calculate_backoff(attempt);
+                        warn!("Retryable error {} for DELETE {}, retrying in {:?}", status, url, delay);
+                        sleep(delay).await;
+                        continue;
+                    }
+                    return self.record(category, self.handle_response(response).await);
+                }
+                Err(e) if attempt < MAX_RETRIES - 1 && (e.is_timeout() || e.is_connect()) => {
+                    let delay = Self::calculate_backoff(attempt);
+                    warn!("DELETE {} failed ({}), retrying in {:?}", url, e, delay);
+                    sleep(delay).await;
+                    continue;
+                }
+                Err(e) => return self.record(category, Err(request_error(e))),
+            }
+        }
+
+        self.record(
+            category,
+            Err(CloudflareError::NetworkError("Max retries exceeded".to_string())),
+        )
+    }
+
+    /// Log an outgoing request when verbose logging is on
+    fn log_request(&self, method: &str, url: &str, body: Option<&serde_json::Value>) {
+        if !self.verbose {
+            return;
+        }
+        match body {
+            Some(body) => info!("--> {} {} {}", method, url, redact_secrets(body)),
+            None => info!("--> {} {}", method, url),
+        }
+    }
+
+    /// Log a response's status and `CF-Ray` when verbose logging is on, returning the ray
+    fn log_response(&self, response: &Response) -> Option<String> {
+        let ray = response
+            .headers()
+            .get(CF_RAY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        if self.verbose {
+            info!(
+                "<-- {} {} (CF-Ray: {})",
+                response.status(),
+                response.url(),
+                ray.as_deref().unwrap_or("none")
+            );
+        }
+        ray
     }
 
     /// Handle API response
@@ -282,30 +696,36 @@ impl CloudflareClient {
         response: Response,
     ) -> CloudflareResult<ApiResponse<T>> {
         let status = response.status();
+        let ray = self.log_response(&response);
 
         if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
             return Err(CloudflareError::RateLimitExceeded);
         }
 
-        let body = response.text().await?;
+        let body = response.text().await.map_err(request_error)?;
 
         let api_response: ApiResponse<T> = serde_json::from_str(&body).map_err(|e| {
             error!("Failed to parse response: {} - Body: {}", e, body);
-            CloudflareError::Internal(format!("Failed to parse response: {}", e))
+            if status.is_server_error() {
+                CloudflareError::ServiceUnavailable(format!("Cloudflare API returned {}", status))
+            } else {
+                CloudflareError::Internal(format!("Failed to parse response: {}", e))
+            }
         })?;
 
         if !api_response.success {
-            if let Some(errors) = &api_response.errors {
-                if let Some(first_error) = errors.first() {
-                    return Err(CloudflareError::ApiError {
-                        code: first_error.code,
-                        message: first_error.message.clone(),
-                    });
-                }
+            if self.verbose {
+                let errors = serde_json::to_value(&api_response.errors).unwrap_or_default();
+                info!("<-- errors: {}", redact_secrets(&errors));
             }
-            return Err(CloudflareError::ApiError {
-                code: 0,
-                message: "Unknown API error".to_string(),
+            let errors = api_response.errors.as_deref().unwrap_or_default();
+            return Err(match CloudflareError::from_api_errors(errors) {
+                CloudflareError::ApiError { message, .. }
+                    if status == StatusCode::NOT_FOUND && !errors.is_empty() =>
+                {
+                    CloudflareError::NotFound(message)
+                }
+                error => error.with_ray_id(ray),
             });
         }
 
@@ -324,6 +744,15 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::ZoneNotFound(self.zone_id.clone()))
     }
 
+    /// Pause or unpause the zone
+    pub async fn set_zone_paused(&self, paused: bool) -> CloudflareResult<Zone> {
+        let body = serde_json::json!({ "paused": paused });
+        let response: ApiResponse<Zone> = self
+            .patch(&format!("/zones/{}", self.zone_id), &body)
+            .await?;
+        response.result.ok_or(CloudflareError::ZoneNotFound(self.zone_id.clone()))
+    }
+
     /// Get zone settings
     pub async fn get_zone_settings(&self) -> CloudflareResult<Vec<ZoneSetting>> {
         let response: ApiResponse<Vec<ZoneSetting>> = self
@@ -332,6 +761,37 @@ impl CloudflareClient {
         Ok(response.result.unwrap_or_default())
     }
 
+    /// Get a single zone setting
+    pub async fn get_zone_setting(&self, setting_id: &str) -> CloudflareResult<ZoneSetting> {
+        let response: ApiResponse<ZoneSetting> = self
+            .get(&format!("/zones/{}/settings/{}", self.zone_id, setting_id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(setting_id.to_string()))
+    }
+
+    /// Whether Crawler Hints is enabled for the zone
+    pub async fn get_crawler_hints(&self) -> CloudflareResult<bool> {
+        let response: ApiResponse<serde_json::Value> = self
+            .get(&format!("/zones/{}/flags/products/cache/changes", self.zone_id))
+            .await?;
+        Ok(response
+            .result
+            .and_then(|r| r.get("crawlhints_enabled").and_then(|v| v.as_bool()))
+            .unwrap_or(false))
+    }
+
+    /// Enable or disable Crawler Hints
+    pub async fn set_crawler_hints(&self, enabled: bool) -> CloudflareResult<bool> {
+        let body = serde_json::json!({ "feature": "crawlhints_enabled", "value": enabled });
+        let response: ApiResponse<serde_json::Value> = self
+            .put(&format!("/zones/{}/flags/products/cache/changes", self.zone_id), &body)
+            .await?;
+        Ok(response
+            .result
+            .and_then(|r| r.get("crawlhints_enabled").and_then(|v| v.as_bool()))
+            .unwrap_or(enabled))
+    }
+
     /// Update zone setting
     pub async fn update_zone_setting(
         &self,
@@ -357,6 +817,40 @@ impl CloudflareClient {
         .await
     }
 
+    /// Get an Argo setting (`smart_routing` or `tiered_caching`)
+    pub async fn get_argo_setting(&self, feature: &str) -> CloudflareResult<ZoneSetting> {
+        let response: ApiResponse<ZoneSetting> = self
+            .get(&format!("/zones/{}/argo/{}", self.zone_id, feature))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(feature.to_string()))
+    }
+
+    /// Turn an Argo setting (`smart_routing` or `tiered_caching`) on or off
+    pub async fn set_argo_setting(&self, feature: &str, enabled: bool) -> CloudflareResult<ZoneSetting> {
+        let body = serde_json::json!({ "value": if enabled { "on" } else { "off" } });
+        let response: ApiResponse<ZoneSetting> = self
+            .patch(&format!("/zones/{}/argo/{}", self.zone_id, feature), &body)
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(feature.to_string()))
+    }
+
+    /// Get a zone cache setting (`cache_reserve`, `tiered_cache_smart_topology_enable`, ...)
+    pub async fn get_cache_setting(&self, setting: &str) -> CloudflareResult<ZoneSetting> {
+        let response: ApiResponse<ZoneSetting> = self
+            .get(&format!("/zones/{}/cache/{}", self.zone_id, setting))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(setting.to_string()))
+    }
+
+    /// Turn a zone cache setting on or off
+    pub async fn set_cache_setting(&self, setting: &str, enabled: bool) -> CloudflareResult<ZoneSetting> {
+        let body = serde_json::json!({ "value": if enabled { "on" } else { "off" } });
+        let response: ApiResponse<ZoneSetting> = self
+            .patch(&format!("/zones/{}/cache/{}", self.zone_id, setting), &body)
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(setting.to_string()))
+    }
+
     // =========================================================================
     // Cache Operations
     // =========================================================================
@@ -388,6 +882,15 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::CacheError("Purge failed".to_string()))
     }
 
+    /// Purge everything cached for the given hostnames
+    pub async fn purge_cache_by_hosts(&self, hosts: Vec<String>) -> CloudflareResult<PurgeResponse> {
+        let body = serde_json::json!({ "hosts": hosts });
+        let response: ApiResponse<PurgeResponse> = self
+            .post(&format!("/zones/{}/purge_cache", self.zone_id), &body)
+            .await?;
+        response.result.ok_or(CloudflareError::CacheError("Purge failed".to_string()))
+    }
+
     /// Purge cache by prefix
     pub async fn purge_cache_by_prefix(&self, prefixes: Vec<String>) -> CloudflareResult<PurgeResponse> {
         let body = serde_json::json!({ "prefixes": prefixes });
@@ -406,6 +909,14 @@ impl CloudflareClient {
         &self,
         params: Option<DnsListParams>,
     ) -> CloudflareResult<Vec<DnsRecord>> {
+        Ok(self.list_dns_records_page(params).await?.0)
+    }
+
+    /// List DNS records along with the upstream pagination info
+    pub async fn list_dns_records_page(
+        &self,
+        params: Option<DnsListParams>,
+    ) -> CloudflareResult<(Vec<DnsRecord>, Option<ResultInfo>)> {
         let mut endpoint = format!("/zones/{}/dns_records", self.zone_id);
 
         if let Some(p) = params {
@@ -416,7 +927,35 @@ impl CloudflareClient {
         }
 
         let response: ApiResponse<Vec<DnsRecord>> = self.get(&endpoint).await?;
-        Ok(response.result.unwrap_or_default())
+        Ok((response.result.unwrap_or_default(), response.result_info))
+    }
+
+    /// List every DNS record matching `params`, across all pages
+    pub async fn list_all_dns_records(&self, params: DnsListParams) -> CloudflareResult<Vec<DnsRecord>> {
+        let mut records = Vec::new();
+        let mut page = 1;
+        loop {
+            let (batch, info) = self
+                .list_dns_records_page(Some(DnsListParams {
+                    page: Some(page),
+                    per_page: Some(DNS_RECORDS_PER_PAGE),
+                    ..params.clone()
+                }))
+                .await?;
+            records.extend(batch);
+            if page >= info.map_or(1, |info| info.total_pages) {
+                return Ok(records);
+            }
+            page += 1;
+        }
+    }
+
+    /// Get a DNS record by ID
+    pub async fn get_dns_record(&self, id: &str) -> CloudflareResult<DnsRecord> {
+        let response: ApiResponse<DnsRecord> = self
+            .get(&format!("/zones/{}/dns_records/{}", self.zone_id, id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(format!("DNS record {}", id)))
     }
 
     /// Create DNS record
@@ -447,6 +986,26 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::DnsError("Delete failed".to_string()))
     }
 
+    /// Get the zone's DNSSEC status
+    pub async fn get_dnssec(&self) -> CloudflareResult<DnssecStatus> {
+        let response: ApiResponse<DnssecStatus> = self
+            .get(&format!("/zones/{}/dnssec", self.zone_id))
+            .await?;
+        response.result.ok_or(CloudflareError::DnsError("No DNSSEC status returned".to_string()))
+    }
+
+    /// Enable or disable DNSSEC for the zone
+    pub async fn set_dnssec(&self, enabled: bool) -> CloudflareResult<DnssecStatus> {
+        let status = if enabled { "active" } else { "disabled" };
+        let response: ApiResponse<DnssecStatus> = self
+            .patch(
+                &format!("/zones/{}/dnssec", self.zone_id),
+                &serde_json::json!({ "status": status }),
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::DnsError("DNSSEC update failed".to_string()))
+    }
+
     // =========================================================================
     // SSL/TLS Operations
     // =========================================================================
@@ -476,6 +1035,45 @@ impl CloudflareClient {
         Ok(response.result.unwrap_or_default())
     }
 
+    /// List custom hostnames
+    pub async fn list_custom_hostnames(&self) -> CloudflareResult<Vec<CustomHostname>> {
+        let response: ApiResponse<Vec<CustomHostname>> = self
+            .get(&format!("/zones/{}/custom_hostnames", self.zone_id))
+            .await?;
+        Ok(response.result.unwrap_or_default())
+    }
+
+    /// Create a custom hostname with a DV certificate validated by `ssl_method`
+    pub async fn create_custom_hostname(&self, hostname: &str, ssl_method: &str) -> CloudflareResult<CustomHostname> {
+        let body = CreateCustomHostname {
+            hostname: hostname.to_string(),
+            ssl: CreateCustomHostnameSsl {
+                method: ssl_method.to_string(),
+                ssl_type: "dv".to_string(),
+            },
+        };
+        let response: ApiResponse<CustomHostname> = self
+            .post(&format!("/zones/{}/custom_hostnames", self.zone_id), &body)
+            .await?;
+        response.result.ok_or(CloudflareError::SslError("Custom hostname creation failed".to_string()))
+    }
+
+    /// Get a custom hostname
+    pub async fn get_custom_hostname(&self, id: &str) -> CloudflareResult<CustomHostname> {
+        let response: ApiResponse<CustomHostname> = self
+            .get(&format!("/zones/{}/custom_hostnames/{}", self.zone_id, id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(format!("Custom hostname {}", id)))
+    }
+
+    /// Delete a custom hostname
+    pub async fn delete_custom_hostname(&self, id: &str) -> CloudflareResult<DeleteResponse> {
+        let response: ApiResponse<DeleteResponse> = self
+            .delete(&format!("/zones/{}/custom_hostnames/{}", self.zone_id, id))
+            .await?;
+        response.result.ok_or(CloudflareError::SslError("Custom hostname deletion failed".to_string()))
+    }
+
     // =========================================================================
     // Security / WAF Operations
     // =========================================================================
@@ -530,6 +1128,14 @@ impl CloudflareClient {
             .ok_or(CloudflareError::WafError("Create failed".to_string()))
     }
 
+    /// Delete firewall rule
+    pub async fn delete_firewall_rule(&self, id: &str) -> CloudflareResult<DeleteResponse> {
+        let response: ApiResponse<DeleteResponse> = self
+            .delete(&format!("/zones/{}/firewall/rules/{}", self.zone_id, id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(id.to_string()))
+    }
+
     /// List IP access rules
     pub async fn list_ip_access_rules(&self) -> CloudflareResult<Vec<IpAccessRule>> {
         let response: ApiResponse<Vec<IpAccessRule>> = self
@@ -552,6 +1158,14 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::WafError("Create failed".to_string()))
     }
 
+    /// Delete IP access rule
+    pub async fn delete_ip_access_rule(&self, id: &str) -> CloudflareResult<DeleteResponse> {
+        let response: ApiResponse<DeleteResponse> = self
+            .delete(&format!("/zones/{}/firewall/access_rules/rules/{}", self.zone_id, id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(id.to_string()))
+    }
+
     // =========================================================================
     // Page Rules Operations
     // =========================================================================
@@ -564,6 +1178,14 @@ impl CloudflareClient {
         Ok(response.result.unwrap_or_default())
     }
 
+    /// Get a page rule
+    pub async fn get_page_rule(&self, id: &str) -> CloudflareResult<PageRule> {
+        let response: ApiResponse<PageRule> = self
+            .get(&format!("/zones/{}/pagerules/{}", self.zone_id, id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(id.to_string()))
+    }
+
     /// Create page rule
     pub async fn create_page_rule(&self, rule: CreatePageRule) -> CloudflareResult<PageRule> {
         let response: ApiResponse<PageRule> = self
@@ -592,49 +1214,395 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::NotFound(id.to_string()))
     }
 
-    // =========================================================================
-    // Workers Operations
-    // =========================================================================
-
-    /// List Workers
-    pub async fn list_workers(&self) -> CloudflareResult<Vec<Worker>> {
-        let response: ApiResponse<Vec<Worker>> = self
-            .get(&format!("/accounts/{}/workers/scripts", self.account_id))
+    /// List the account's IP Lists
+    pub async fn list_ip_lists(&self) -> CloudflareResult<Vec<IpList>> {
+        let response: ApiResponse<Vec<IpList>> = self
+            .get(&format!("/accounts/{}/rules/lists", self.account_id))
             .await?;
         Ok(response.result.unwrap_or_default())
     }
 
-    /// Get Worker script
-    pub async fn get_worker(&self, name: &str) -> CloudflareResult<Worker> {
-        let response: ApiResponse<Worker> = self
-            .get(&format!(
-                "/accounts/{}/workers/scripts/{}",
-                self.account_id, name
-            ))
+    /// Create an IP List
+    pub async fn create_ip_list(&self, list: CreateIpList) -> CloudflareResult<IpList> {
+        let response: ApiResponse<IpList> = self
+            .post(&format!("/accounts/{}/rules/lists", self.account_id), &list)
             .await?;
-        response.result.ok_or(CloudflareError::WorkerError(format!(
-            "Worker '{}' not found",
-            name
-        )))
+        response.result.ok_or(CloudflareError::WafError("Create IP list failed".to_string()))
     }
 
-    /// Deploy Worker script
-    pub async fn deploy_worker(&self, name: &str, script: &str) -> CloudflareResult<Worker> {
-        // Workers API requires multipart form data for script upload
-        let url = format!(
-            "{}/accounts/{}/workers/scripts/{}",
-            API_BASE_URL, self.account_id, name
-        );
-
-        let form = reqwest::multipart::Form::new()
-            .text("script", script.to_string());
-
-        let response = self
-            .client
-            .put(&url)
-            .multipart(form)
-            .send()
+    /// Get a list by ID
+    pub async fn get_ip_list(&self, list_id: &str) -> CloudflareResult<IpList> {
+        let response: ApiResponse<IpList> = self
+            .get(&format!("/accounts/{}/rules/lists/{}", self.account_id, list_id))
             .await?;
+        response.result.ok_or(CloudflareError::NotFound(list_id.to_string()))
+    }
+
+    /// List the items in an IP List
+    pub async fn list_ip_list_items(&self, list_id: &str) -> CloudflareResult<Vec<IpListItem>> {
+        let response: ApiResponse<Vec<IpListItem>> = self
+            .get(&format!("/accounts/{}/rules/lists/{}/items", self.account_id, list_id))
+            .await?;
+        Ok(response.result.unwrap_or_default())
+    }
+
+    /// Append items to an IP List; Cloudflare applies them asynchronously
+    pub async fn add_ip_list_items(
+        &self,
+        list_id: &str,
+        items: &[CreateIpListItem],
+    ) -> CloudflareResult<BulkOperation> {
+        let response: ApiResponse<BulkOperation> = self
+            .post(
+                &format!("/accounts/{}/rules/lists/{}/items", self.account_id, list_id),
+                &items,
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::WafError("Add IP list items failed".to_string()))
+    }
+
+    /// Append redirects to a redirect list; Cloudflare applies them asynchronously
+    pub async fn add_redirect_list_items(
+        &self,
+        list_id: &str,
+        redirects: &[BulkRedirect],
+    ) -> CloudflareResult<BulkOperation> {
+        let items: Vec<_> = redirects
+            .iter()
+            .map(|redirect| serde_json::json!({ "redirect": redirect }))
+            .collect();
+        let response: ApiResponse<BulkOperation> = self
+            .post(
+                &format!("/accounts/{}/rules/lists/{}/items", self.account_id, list_id),
+                &items,
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::WafError("Add redirect list items failed".to_string()))
+    }
+
+    /// Remove items from an IP List by item ID; Cloudflare applies this asynchronously
+    pub async fn remove_ip_list_items(
+        &self,
+        list_id: &str,
+        item_ids: &[String],
+    ) -> CloudflareResult<BulkOperation> {
+        let body = serde_json::json!({
+            "items": item_ids.iter().map(|id| serde_json::json!({ "id": id })).collect::<Vec<_>>()
+        });
+        let response: ApiResponse<BulkOperation> = self
+            .delete_with_body(
+                &format!("/accounts/{}/rules/lists/{}/items", self.account_id, list_id),
+                &body,
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::WafError("Remove IP list items failed".to_string()))
+    }
+
+    /// Get the status of an asynchronous list operation
+    pub async fn get_bulk_operation_status(&self, operation_id: &str) -> CloudflareResult<BulkOperationStatus> {
+        let response: ApiResponse<BulkOperationStatus> = self
+            .get(&format!(
+                "/accounts/{}/rules/lists/bulk_operations/{}",
+                self.account_id, operation_id
+            ))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(format!("Bulk operation {}", operation_id)))
+    }
+
+    /// Get the zone's bot management settings
+    pub async fn get_bot_management(&self) -> CloudflareResult<BotManagement> {
+        let response: ApiResponse<BotManagement> = self
+            .get(&format!("/zones/{}/bot_management", self.zone_id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound("Bot management".to_string()))
+    }
+
+    /// Update the zone's bot management settings
+    pub async fn update_bot_management(&self, update: &UpdateBotManagement) -> CloudflareResult<BotManagement> {
+        let response: ApiResponse<BotManagement> = self
+            .put(&format!("/zones/{}/bot_management", self.zone_id), update)
+            .await?;
+        response.result.ok_or(CloudflareError::WafError("Bot management update failed".to_string()))
+    }
+
+    // =========================================================================
+    // Turnstile Operations
+    // =========================================================================
+
+    /// List Turnstile widgets
+    pub async fn list_turnstile_widgets(&self) -> CloudflareResult<Vec<TurnstileWidget>> {
+        let response: ApiResponse<Vec<TurnstileWidget>> = self
+            .get(&format!("/accounts/{}/challenges/widgets", self.account_id))
+            .await?;
+        Ok(response.result.unwrap_or_default())
+    }
+
+    /// Create a Turnstile widget; the response carries its secret
+    pub async fn create_turnstile_widget(&self, widget: &CreateTurnstileWidget) -> CloudflareResult<TurnstileWidget> {
+        let response: ApiResponse<TurnstileWidget> = self
+            .post(&format!("/accounts/{}/challenges/widgets", self.account_id), widget)
+            .await?;
+        response.result.ok_or(CloudflareError::WafError("Create Turnstile widget failed".to_string()))
+    }
+
+    /// Generate a new secret for a Turnstile widget.
+    ///
+    /// Unless `invalidate_immediately`, the old secret keeps working for two hours.
+    pub async fn rotate_turnstile_secret(
+        &self,
+        sitekey: &str,
+        invalidate_immediately: bool,
+    ) -> CloudflareResult<TurnstileWidget> {
+        let body = serde_json::json!({ "invalidate_immediately": invalidate_immediately });
+        let response: ApiResponse<TurnstileWidget> = self
+            .post(
+                &format!("/accounts/{}/challenges/widgets/{}/rotate_secret", self.account_id, sitekey),
+                &body,
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::WafError("Rotate Turnstile secret failed".to_string()))
+    }
+
+    /// Delete a Turnstile widget
+    pub async fn delete_turnstile_widget(&self, sitekey: &str) -> CloudflareResult<()> {
+        let _: ApiResponse<serde_json::Value> = self
+            .delete(&format!("/accounts/{}/challenges/widgets/{}", self.account_id, sitekey))
+            .await?;
+        Ok(())
+    }
+
+    // =========================================================================
+    // Rulesets Operations
+    // =========================================================================
+
+    /// Get the zone entrypoint ruleset for a phase
+    pub async fn get_phase_ruleset(&self, phase: &str) -> CloudflareResult<Ruleset> {
+        let response: ApiResponse<Ruleset> = self
+            .get(&format!(
+                "/zones/{}/rulesets/phases/{}/entrypoint",
+                self.zone_id, phase
+            ))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(phase.to_string()))
+    }
+
+    /// Zone entrypoint ruleset for a phase, `None` if it hasn't been created yet
+    pub async fn find_phase_ruleset(&self, phase: &str) -> CloudflareResult<Option<Ruleset>> {
+        match self.get_phase_ruleset(phase).await {
+            Ok(ruleset) => Ok(Some(ruleset)),
+            Err(CloudflareError::ApiError { code: NO_ENTRYPOINT_RULESET, .. })
+            | Err(CloudflareError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Rules of the zone entrypoint ruleset for a phase, empty if it hasn't been created yet
+    pub async fn get_phase_rules(&self, phase: &str) -> CloudflareResult<Vec<RulesetRule>> {
+        Ok(self.find_phase_ruleset(phase).await?.map(|r| r.rules).unwrap_or_default())
+    }
+
+    /// Add a rule to the end of a zone ruleset, leaving its other rules alone
+    pub async fn create_ruleset_rule(&self, ruleset_id: &str, rule: &RulesetRule) -> CloudflareResult<Ruleset> {
+        let response: ApiResponse<Ruleset> = self
+            .post(&format!("/zones/{}/rulesets/{}/rules", self.zone_id, ruleset_id), rule)
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(ruleset_id.to_string()))
+    }
+
+    /// Update one rule of a zone ruleset in place
+    pub async fn update_ruleset_rule(
+        &self,
+        ruleset_id: &str,
+        rule_id: &str,
+        rule: &RulesetRule,
+    ) -> CloudflareResult<Ruleset> {
+        let response: ApiResponse<Ruleset> = self
+            .patch(
+                &format!("/zones/{}/rulesets/{}/rules/{}", self.zone_id, ruleset_id, rule_id),
+                rule,
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(ruleset_id.to_string()))
+    }
+
+    /// Remove one rule from a zone ruleset
+    pub async fn delete_ruleset_rule(&self, ruleset_id: &str, rule_id: &str) -> CloudflareResult<Ruleset> {
+        let response: ApiResponse<Ruleset> = self
+            .delete(&format!("/zones/{}/rulesets/{}/rules/{}", self.zone_id, ruleset_id, rule_id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(ruleset_id.to_string()))
+    }
+
+    /// List the zone's rulesets, including the managed rulesets it can deploy
+    pub async fn list_zone_rulesets(&self) -> CloudflareResult<Vec<Ruleset>> {
+        let response: ApiResponse<Vec<Ruleset>> = self
+            .get(&format!("/zones/{}/rulesets", self.zone_id))
+            .await?;
+        Ok(response.result.unwrap_or_default())
+    }
+
+    /// Replace the rules of the zone entrypoint ruleset for a phase
+    pub async fn update_phase_ruleset(
+        &self,
+        phase: &str,
+        rules: Vec<RulesetRule>,
+    ) -> CloudflareResult<Ruleset> {
+        let body = serde_json::json!({ "rules": rules });
+        let response: ApiResponse<Ruleset> = self
+            .put(
+                &format!("/zones/{}/rulesets/phases/{}/entrypoint", self.zone_id, phase),
+                &body,
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(phase.to_string()))
+    }
+
+    /// Account entrypoint ruleset for a phase, `None` if it hasn't been created yet
+    pub async fn find_account_phase_ruleset(&self, phase: &str) -> CloudflareResult<Option<Ruleset>> {
+        let response: CloudflareResult<ApiResponse<Ruleset>> = self
+            .get(&format!(
+                "/accounts/{}/rulesets/phases/{}/entrypoint",
+                self.account_id, phase
+            ))
+            .await;
+        match response {
+            Ok(response) => Ok(response.result),
+            Err(CloudflareError::ApiError { code: NO_ENTRYPOINT_RULESET, .. })
+            | Err(CloudflareError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Rules of the account entrypoint ruleset for a phase, empty if it hasn't been created yet
+    pub async fn get_account_phase_rules(&self, phase: &str) -> CloudflareResult<Vec<RulesetRule>> {
+        Ok(self.find_account_phase_ruleset(phase).await?.map(|r| r.rules).unwrap_or_default())
+    }
+
+    /// Add a rule to the end of an account ruleset, leaving its other rules alone
+    pub async fn create_account_ruleset_rule(&self, ruleset_id: &str, rule: &RulesetRule) -> CloudflareResult<Ruleset> {
+        let response: ApiResponse<Ruleset> = self
+            .post(&format!("/accounts/{}/rulesets/{}/rules", self.account_id, ruleset_id), rule)
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(ruleset_id.to_string()))
+    }
+
+    /// Update one rule of an account ruleset in place
+    pub async fn update_account_ruleset_rule(
+        &self,
+        ruleset_id: &str,
+        rule_id: &str,
+        rule: &RulesetRule,
+    ) -> CloudflareResult<Ruleset> {
+        let response: ApiResponse<Ruleset> = self
+            .patch(
+                &format!("/accounts/{}/rulesets/{}/rules/{}", self.account_id, ruleset_id, rule_id),
+                rule,
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(ruleset_id.to_string()))
+    }
+
+    /// Replace the rules of the account entrypoint ruleset for a phase
+    pub async fn update_account_phase_ruleset(
+        &self,
+        phase: &str,
+        rules: Vec<RulesetRule>,
+    ) -> CloudflareResult<Ruleset> {
+        let body = serde_json::json!({ "rules": rules });
+        let response: ApiResponse<Ruleset> = self
+            .put(
+                &format!("/accounts/{}/rulesets/phases/{}/entrypoint", self.account_id, phase),
+                &body,
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(phase.to_string()))
+    }
+
+    // =========================================================================
+    // Workers Operations
+    // =========================================================================
+
+    /// List Workers
+    pub async fn list_workers(&self) -> CloudflareResult<Vec<Worker>> {
+        let response: ApiResponse<Vec<Worker>> = self
+            .get(&format!("/accounts/{}/workers/scripts", self.account_id))
+            .await?;
+        Ok(response.result.unwrap_or_default())
+    }
+
+    /// Get Worker script
+    pub async fn get_worker(&self, name: &str) -> CloudflareResult<Worker> {
+        let response: ApiResponse<Worker> = self
+            .get(&format!(
+                "/accounts/{}/workers/scripts/{}",
+                self.account_id, name
+            ))
+            .await?;
+        response.result.ok_or(CloudflareError::WorkerError(format!(
+            "Worker '{}' not found",
+            name
+        )))
+    }
+
+    /// Get Worker script settings, including bindings
+    pub async fn get_worker_settings(&self, name: &str) -> CloudflareResult<WorkerSettings> {
+        let response: ApiResponse<WorkerSettings> = self
+            .get(&format!(
+                "/accounts/{}/workers/scripts/{}/settings",
+                self.account_id, name
+            ))
+            .await?;
+        response.result.ok_or(CloudflareError::WorkerError(format!(
+            "Worker '{}' not found",
+            name
+        )))
+    }
+
+    /// Deploy Worker script
+    pub async fn deploy_worker(&self, name: &str, script: &str) -> CloudflareResult<Worker> {
+        self.upload_worker_script(name, script, None).await
+    }
+
+    /// Deploy Worker script only if the live script still has `expected_etag`.
+    ///
+    /// Returns `Conflict` if someone else deployed the Worker in the meantime.
+    pub async fn deploy_worker_if_match(
+        &self,
+        name: &str,
+        script: &str,
+        expected_etag: &str,
+    ) -> CloudflareResult<Worker> {
+        self.upload_worker_script(name, script, Some(expected_etag)).await
+    }
+
+    async fn upload_worker_script(
+        &self,
+        name: &str,
+        script: &str,
+        if_match: Option<&str>,
+    ) -> CloudflareResult<Worker> {
+        // Workers API requires multipart form data for script upload
+        let url = format!(
+            "{}/accounts/{}/workers/scripts/{}",
+            self.base_url, self.account_id, name
+        );
+        let build_form = || reqwest::multipart::Form::new().text("script", script.to_string());
+
+        let response = self
+            .send_multipart(reqwest::Method::PUT, &url, build_form, |request| match if_match {
+                Some(etag) => request.header(header::IF_MATCH, format!("\"{}\"", etag.trim_matches('"'))),
+                None => request,
+            })
+            .await?;
+
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            self.log_response(&response);
+            return Err(CloudflareError::Conflict(format!(
+                "Worker '{}' was changed since it was loaded (expected etag {}). Reload it and reapply your changes.",
+                name,
+                if_match.unwrap_or_default()
+            )));
+        }
 
         let api_response: ApiResponse<Worker> = self.handle_response(response).await?;
         api_response.result.ok_or(CloudflareError::WorkerError("Deploy failed".to_string()))
@@ -651,6 +1619,28 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::WorkerError("Delete failed".to_string()))
     }
 
+    /// Start a tail (live log) session for a Worker
+    pub async fn create_worker_tail(&self, script: &str) -> CloudflareResult<TailSession> {
+        let response: ApiResponse<TailSession> = self
+            .post(
+                &format!("/accounts/{}/workers/scripts/{}/tails", self.account_id, script),
+                &serde_json::json!({}),
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::WorkerError("Starting tail failed".to_string()))
+    }
+
+    /// End a tail session
+    pub async fn delete_worker_tail(&self, script: &str, id: &str) -> CloudflareResult<()> {
+        let _: ApiResponse<serde_json::Value> = self
+            .delete(&format!(
+                "/accounts/{}/workers/scripts/{}/tails/{}",
+                self.account_id, script, id
+            ))
+            .await?;
+        Ok(())
+    }
+
     /// List Worker routes
     pub async fn list_worker_routes(&self) -> CloudflareResult<Vec<WorkerRoute>> {
         let response: ApiResponse<Vec<WorkerRoute>> = self
@@ -675,19 +1665,36 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::WorkerError("Create route failed".to_string()))
     }
 
+    /// Delete Worker route
+    pub async fn delete_worker_route(&self, id: &str) -> CloudflareResult<DeleteResponse> {
+        let response: ApiResponse<DeleteResponse> = self
+            .delete(&format!("/zones/{}/workers/routes/{}", self.zone_id, id))
+            .await?;
+        response.result.ok_or(CloudflareError::WorkerError("Delete route failed".to_string()))
+    }
+
     // =========================================================================
     // Workers KV Operations
     // =========================================================================
 
-    /// List KV namespaces
+    /// List every KV namespace in the account, across all pages
     pub async fn list_kv_namespaces(&self) -> CloudflareResult<Vec<KvNamespace>> {
-        let response: ApiResponse<Vec<KvNamespace>> = self
-            .get(&format!(
-                "/accounts/{}/storage/kv/namespaces",
-                self.account_id
-            ))
-            .await?;
-        Ok(response.result.unwrap_or_default())
+        let mut namespaces = Vec::new();
+        let mut page = 1;
+        loop {
+            let response: ApiResponse<Vec<KvNamespace>> = self
+                .get(&format!(
+                    "/accounts/{}/storage/kv/namespaces?page={}&per_page={}",
+                    self.account_id, page, KV_NAMESPACES_PER_PAGE
+                ))
+                .await?;
+            let total_pages = response.result_info.as_ref().map_or(1, |info| info.total_pages);
+            namespaces.extend(response.result.unwrap_or_default());
+            if page >= total_pages {
+                return Ok(namespaces);
+            }
+            page += 1;
+        }
     }
 
     /// Create KV namespace
@@ -702,6 +1709,17 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::KvError("Create failed".to_string()))
     }
 
+    /// Delete KV namespace
+    pub async fn delete_kv_namespace(&self, id: &str) -> CloudflareResult<()> {
+        let _: ApiResponse<serde_json::Value> = self
+            .delete(&format!(
+                "/accounts/{}/storage/kv/namespaces/{}",
+                self.account_id, id
+            ))
+            .await?;
+        Ok(())
+    }
+
     /// List KV keys
     pub async fn list_kv_keys(&self, namespace_id: &str) -> CloudflareResult<Vec<KvKey>> {
         let response: ApiResponse<Vec<KvKey>> = self
@@ -717,18 +1735,54 @@ impl CloudflareClient {
     pub async fn get_kv_value(&self, namespace_id: &str, key: &str) -> CloudflareResult<String> {
         let url = format!(
             "{}/accounts/{}/storage/kv/namespaces/{}/values/{}",
-            API_BASE_URL, self.account_id, namespace_id, key
+            self.base_url, self.account_id, namespace_id, key
         );
 
-        let response = self.client.get(&url).send().await?;
+        self.log_request("GET", &url, None);
+        let response = self.http().get(&url).timeout(self.timeouts.read).send().await.map_err(request_error)?;
+        self.log_response(&response);
 
         if response.status().is_success() {
-            Ok(response.text().await?)
+            response.text().await.map_err(request_error)
         } else {
             Err(CloudflareError::KvError(format!("Key '{}' not found", key)))
         }
     }
 
+    /// Get a KV value, typed by the content type it comes back with
+    pub async fn get_kv_value_typed(&self, namespace_id: &str, key: &str) -> CloudflareResult<KvValue> {
+        let url = format!(
+            "{}/accounts/{}/storage/kv/namespaces/{}/values/{}",
+            self.base_url, self.account_id, namespace_id, key
+        );
+
+        self.log_request("GET", &url, None);
+        let response = self.http().get(&url).timeout(self.timeouts.read).send().await.map_err(request_error)?;
+        self.log_response(&response);
+
+        if !response.status().is_success() {
+            return Err(CloudflareError::KvError(format!("Key '{}' not found", key)));
+        }
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await.map_err(request_error)?;
+        Ok(KvValue::from_body(content_type.as_deref(), body.to_vec()))
+    }
+
+    /// Get the metadata stored with a KV key, `None` if it has none
+    pub async fn get_kv_metadata(&self, namespace_id: &str, key: &str) -> CloudflareResult<Option<serde_json::Value>> {
+        let response: ApiResponse<serde_json::Value> = self
+            .get(&format!(
+                "/accounts/{}/storage/kv/namespaces/{}/metadata/{}",
+                self.account_id, namespace_id, key
+            ))
+            .await?;
+        Ok(response.result.filter(|m| !m.is_null()))
+    }
+
     /// Set KV value
     pub async fn set_kv_value(
         &self,
@@ -738,10 +1792,12 @@ impl CloudflareClient {
     ) -> CloudflareResult<()> {
         let url = format!(
             "{}/accounts/{}/storage/kv/namespaces/{}/values/{}",
-            API_BASE_URL, self.account_id, namespace_id, key
+            self.base_url, self.account_id, namespace_id, key
         );
 
-        let response = self.client.put(&url).body(value.to_string()).send().await?;
+        self.log_request("PUT", &url, None);
+        let response = self.http().put(&url).body(value.to_string()).timeout(self.timeouts.mutation).send().await.map_err(request_error)?;
+        self.log_response(&response);
 
         if response.status().is_success() {
             Ok(())
@@ -754,10 +1810,12 @@ impl CloudflareClient {
     pub async fn delete_kv_value(&self, namespace_id: &str, key: &str) -> CloudflareResult<()> {
         let url = format!(
             "{}/accounts/{}/storage/kv/namespaces/{}/values/{}",
-            API_BASE_URL, self.account_id, namespace_id, key
+            self.base_url, self.account_id, namespace_id, key
         );
 
-        let response = self.client.delete(&url).send().await?;
+        self.log_request("DELETE", &url, None);
+        let response = self.http().delete(&url).timeout(self.timeouts.mutation).send().await.map_err(request_error)?;
+        self.log_response(&response);
 
         if response.status().is_success() {
             Ok(())
@@ -770,7 +1828,9 @@ impl CloudflareClient {
     // Analytics Operations
     // =========================================================================
 
-    /// Get zone analytics
+    /// Get zone analytics from the legacy dashboard endpoint
+    ///
+    /// Prefer `graphql`; this endpoint returns no data for many newer zones.
     pub async fn get_analytics(&self, since: &str, until: &str) -> CloudflareResult<Analytics> {
         let response: ApiResponse<Analytics> = self
             .get(&format!(
@@ -781,6 +1841,62 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::NotFound("Analytics".to_string()))
     }
 
+    /// Run a query against the GraphQL Analytics API
+    pub async fn graphql<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> CloudflareResult<T> {
+        let url = format!("{}/graphql", self.base_url);
+        let body = serde_json::json!({
+            "query": query,
+            "variables": variables
+        });
+
+        self.log_request("POST", &url, Some(&body));
+        let response = self.http().post(&url).json(&body).timeout(self.timeouts.read).send().await.map_err(request_error)?;
+        let ray = self.log_response(&response);
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(CloudflareError::RateLimitExceeded);
+        }
+
+        let body = response.text().await.map_err(request_error)?;
+        let graphql_response: GraphQlResponse<T> = serde_json::from_str(&body).map_err(|e| {
+            error!("Failed to parse GraphQL response: {} - Body: {}", e, body);
+            CloudflareError::Internal(format!("Failed to parse GraphQL response: {}", e))
+        })?;
+
+        if let Some(errors) = graphql_response.errors.filter(|e| !e.is_empty()) {
+            return Err(CloudflareError::ApiError {
+                code: 0,
+                message: errors
+                    .iter()
+                    .map(|e| e.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+                errors: Vec::new(),
+                ray_id: ray,
+            });
+        }
+
+        graphql_response
+            .data
+            .ok_or(CloudflareError::Internal("GraphQL response contained no data".to_string()))
+    }
+
+    // =========================================================================
+    // R2 Operations
+    // =========================================================================
+
+    /// List R2 buckets in the account
+    pub async fn list_r2_buckets(&self) -> CloudflareResult<Vec<R2Bucket>> {
+        let response: ApiResponse<R2BucketList> = self
+            .get(&format!("/accounts/{}/r2/buckets", self.account_id))
+            .await?;
+        Ok(response.result.map(|r| r.buckets).unwrap_or_default())
+    }
+
     // =========================================================================
     // D1 Database Operations
     // =========================================================================
@@ -802,6 +1918,17 @@ impl CloudflareClient {
         response.result.ok_or(CloudflareError::D1Error("Create failed".to_string()))
     }
 
+    /// Delete D1 database
+    pub async fn delete_d1_database(&self, database_id: &str) -> CloudflareResult<()> {
+        let _: ApiResponse<serde_json::Value> = self
+            .delete(&format!(
+                "/accounts/{}/d1/database/{}",
+                self.account_id, database_id
+            ))
+            .await?;
+        Ok(())
+    }
+
     /// Query D1 database
     pub async fn query_d1(
         &self,
@@ -824,6 +1951,92 @@ impl CloudflareClient {
             .ok_or(CloudflareError::D1Error("Query failed".to_string()))
     }
 
+    /// Start or poll an SQL export of a D1 database.
+    ///
+    /// Pass the `at_bookmark` from the previous call to poll an export already running.
+    pub async fn export_d1_database(
+        &self,
+        database_id: &str,
+        bookmark: Option<&str>,
+    ) -> CloudflareResult<D1ExportStatus> {
+        let mut body = serde_json::json!({ "output_format": "polling" });
+        if let Some(bookmark) = bookmark {
+            body["current_bookmark"] = serde_json::json!(bookmark);
+        }
+
+        let response: ApiResponse<D1ExportStatus> = self
+            .post(
+                &format!(
+                    "/accounts/{}/d1/database/{}/export",
+                    self.account_id, database_id
+                ),
+                &body,
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::D1Error("Export failed".to_string()))
+    }
+
+    /// Download a finished D1 export.
+    ///
+    /// The URL is pre-signed, so it is fetched without the API token.
+    pub async fn download_d1_export(&self, signed_url: &str) -> CloudflareResult<String> {
+        // The query string carries the signature, keep it out of the logs
+        let unsigned = signed_url.split('?').next().unwrap_or_default();
+        self.log_request("GET", unsigned, None);
+        let response = Client::new().get(signed_url).send().await.map_err(request_error)?;
+        self.log_response(&response);
+
+        if !response.status().is_success() {
+            return Err(CloudflareError::D1Error(format!(
+                "Export download failed with status {}",
+                response.status()
+            )));
+        }
+        response.text().await.map_err(request_error)
+    }
+
+    /// Run several statements against a D1 database in one request.
+    ///
+    /// D1 runs the statements as a single transaction and returns one result
+    /// per statement, in order.
+    pub async fn query_d1_batch(
+        &self,
+        database_id: &str,
+        statements: Vec<String>,
+    ) -> CloudflareResult<Vec<D1QueryResult>> {
+        let statements: Vec<&str> = statements
+            .iter()
+            .map(|sql| sql.trim().trim_end_matches(';').trim_end())
+            .filter(|sql| !sql.is_empty())
+            .collect();
+        if statements.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Separators go on their own line so a trailing `--` comment can't swallow them
+        let body = serde_json::json!({ "sql": statements.join("\n;\n") });
+        let response: ApiResponse<Vec<D1QueryResult>> = self
+            .post(
+                &format!(
+                    "/accounts/{}/d1/database/{}/query",
+                    self.account_id, database_id
+                ),
+                &body,
+            )
+            .await?;
+        let results = response.result.unwrap_or_default();
+
+        // A single entry may itself hold several statements, e.g. an SQL dump
+        if results.len() != statements.len() {
+            debug!(
+                "D1 batch of {} statements returned {} results",
+                statements.len(),
+                results.len()
+            );
+        }
+        Ok(results)
+    }
+
     // =========================================================================
     // Stream Operations
     // =========================================================================
@@ -836,6 +2049,35 @@ impl CloudflareClient {
         Ok(response.result.unwrap_or_default())
     }
 
+    /// List a page of Stream videos, filtered server-side
+    pub async fn list_stream_videos_paginated(
+        &self,
+        params: &StreamListParams,
+    ) -> CloudflareResult<StreamVideoPage> {
+        let (query, after_uid) = match params.cursor.as_deref() {
+            Some(cursor) => {
+                let (created, uid) = StreamListParams::parse_cursor(cursor).ok_or_else(|| {
+                    CloudflareError::ValidationError("Invalid Stream continuation token".to_string())
+                })?;
+                (params.resume_from(created), Some(uid))
+            }
+            None => (params.clone(), None),
+        };
+
+        let mut endpoint = format!("/accounts/{}/stream", self.account_id);
+        let query = serde_urlencoded::to_string(&query).unwrap_or_default();
+        if !query.is_empty() {
+            endpoint = format!("{}?{}", endpoint, query);
+        }
+
+        let response: ApiResponse<Vec<StreamVideo>> = self.get(&endpoint).await?;
+        Ok(StreamVideoPage::from_fetched(
+            response.result.unwrap_or_default(),
+            after_uid,
+            params.limit,
+        ))
+    }
+
     /// Get Stream video
     pub async fn get_stream_video(&self, video_id: &str) -> CloudflareResult<StreamVideo> {
         let response: ApiResponse<StreamVideo> = self
@@ -847,6 +2089,59 @@ impl CloudflareClient {
         )))
     }
 
+    /// List caption tracks on a Stream video
+    pub async fn list_stream_captions(&self, video_id: &str) -> CloudflareResult<Vec<StreamCaption>> {
+        let response: ApiResponse<Vec<StreamCaption>> = self
+            .get(&format!("/accounts/{}/stream/{}/captions", self.account_id, video_id))
+            .await?;
+        Ok(response.result.unwrap_or_default())
+    }
+
+    /// Upload a WebVTT caption track, replacing any existing one for `language`
+    pub async fn upload_stream_caption(
+        &self,
+        video_id: &str,
+        language: &str,
+        vtt: &str,
+    ) -> CloudflareResult<StreamCaption> {
+        // Captions are uploaded as a multipart `file` field
+        let url = format!(
+            "{}/accounts/{}/stream/{}/captions/{}",
+            self.base_url, self.account_id, video_id, language
+        );
+
+        let file = reqwest::multipart::Part::text(vtt.to_string())
+            .file_name(format!("{}.vtt", language))
+            .mime_str("text/vtt")?;
+        let form = reqwest::multipart::Form::new().part("file", file);
+
+        self.log_request("PUT", &url, None);
+        let response = self.http().put(&url).multipart(form).timeout(self.timeouts.upload).send().await.map_err(request_error)?;
+        let api_response: ApiResponse<StreamCaption> = self.handle_response(response).await?;
+        api_response
+            .result
+            .ok_or(CloudflareError::StreamError("Caption upload failed".to_string()))
+    }
+
+    /// Remove the caption track for `language` from a Stream video
+    pub async fn delete_stream_caption(&self, video_id: &str, language: &str) -> CloudflareResult<()> {
+        let _: ApiResponse<serde_json::Value> = self
+            .delete(&format!(
+                "/accounts/{}/stream/{}/captions/{}",
+                self.account_id, video_id, language
+            ))
+            .await?;
+        Ok(())
+    }
+
+    /// Copy a video into Stream from a URL
+    pub async fn copy_stream_video(&self, request: &StreamCopyRequest) -> CloudflareResult<StreamVideo> {
+        let response: ApiResponse<StreamVideo> = self
+            .post(&format!("/accounts/{}/stream/copy", self.account_id), request)
+            .await?;
+        response.result.ok_or(CloudflareError::StreamError("Copy failed".to_string()))
+    }
+
     /// Delete Stream video
     pub async fn delete_stream_video(&self, video_id: &str) -> CloudflareResult<()> {
         let _: ApiResponse<serde_json::Value> = self
@@ -863,6 +2158,25 @@ impl CloudflareClient {
         Ok(response.result.unwrap_or_default())
     }
 
+    /// Get a live input
+    pub async fn get_live_input(&self, input_id: &str) -> CloudflareResult<LiveInput> {
+        let response: ApiResponse<LiveInput> = self
+            .get(&format!("/accounts/{}/stream/live_inputs/{}", self.account_id, input_id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound(format!(
+            "Live input {} not found",
+            input_id
+        )))
+    }
+
+    /// Delete a live input
+    pub async fn delete_live_input(&self, input_id: &str) -> CloudflareResult<()> {
+        let _: ApiResponse<serde_json::Value> = self
+            .delete(&format!("/accounts/{}/stream/live_inputs/{}", self.account_id, input_id))
+            .await?;
+        Ok(())
+    }
+
     /// Create live input
     pub async fn create_live_input(&self, input: CreateLiveInput) -> CloudflareResult<LiveInput> {
         let response: ApiResponse<LiveInput> = self
@@ -873,4 +2187,808 @@ impl CloudflareClient {
             .await?;
         response.result.ok_or(CloudflareError::StreamError("Create failed".to_string()))
     }
+
+    /// Get the Stream webhook subscription
+    pub async fn get_stream_webhook(&self) -> CloudflareResult<StreamWebhook> {
+        let response: ApiResponse<StreamWebhook> = self
+            .get(&format!("/accounts/{}/stream/webhook", self.account_id))
+            .await?;
+        response.result.ok_or(CloudflareError::NotFound("Stream webhook".to_string()))
+    }
+
+    /// Subscribe a URL to Stream webhook notifications
+    pub async fn set_stream_webhook(&self, notification_url: &str) -> CloudflareResult<StreamWebhook> {
+        let response: ApiResponse<StreamWebhook> = self
+            .put(
+                &format!("/accounts/{}/stream/webhook", self.account_id),
+                &serde_json::json!({ "notificationUrl": notification_url }),
+            )
+            .await?;
+        response.result.ok_or(CloudflareError::StreamError("Webhook update failed".to_string()))
+    }
+
+    /// Remove the Stream webhook subscription
+    pub async fn delete_stream_webhook(&self) -> CloudflareResult<()> {
+        let _: ApiResponse<serde_json::Value> = self
+            .delete(&format!("/accounts/{}/stream/webhook", self.account_id))
+            .await?;
+        Ok(())
+    }
+
+    /// Create a signed URL token for a Stream video
+    pub async fn create_stream_token(
+        &self,
+        video_id: &str,
+        request: &StreamTokenRequest,
+    ) -> CloudflareResult<String> {
+        let response: ApiResponse<StreamToken> = self
+            .post(
+                &format!("/accounts/{}/stream/{}/token", self.account_id, video_id),
+                request,
+            )
+            .await?;
+        response
+            .result
+            .map(|t| t.token)
+            .ok_or(CloudflareError::StreamError("Token creation failed".to_string()))
+    }
+}
+
+/// Headers identifying the plugin on every request. A user agent that
+/// isn't a valid header value, which config validation rules out, falls
+/// back to the default.
+pub(crate) fn identification_headers(user_agent: &str) -> header::HeaderMap {
+    let user_agent = header::HeaderValue::from_str(user_agent)
+        .or_else(|_| header::HeaderValue::from_str(&default_user_agent()))
+        .expect("default user agent is a valid header value");
+
+    let mut headers = header::HeaderMap::new();
+    headers.insert(header::USER_AGENT, user_agent);
+    headers.insert(PLUGIN_VERSION_HEADER, header::HeaderValue::from_static(crate::VERSION));
+    headers
+}
+
+/// Error for a request that got no response, or whose body never arrived
+fn request_error(error: reqwest::Error) -> CloudflareError {
+    if error.is_timeout() {
+        CloudflareError::Timeout(error.to_string())
+    } else if error.is_connect() {
+        CloudflareError::NetworkError(error.to_string())
+    } else {
+        error.into()
+    }
+}
+
+/// Reduce a probe's result to whether it worked and why not
+fn capability_check<T>(result: CloudflareResult<T>) -> CapabilityCheck {
+    CapabilityCheck {
+        ok: result.is_ok(),
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{success, unshared_client};
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_requests_identify_the_plugin() {
+        let server = MockServer::start().await;
+        let expected = format!("RustCloudflare/{} (+https://rustpress.io)", crate::VERSION);
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/verify"))
+            .and(header("user-agent", expected.as_str()))
+            .and(header("x-plugin-version", crate::VERSION))
+            .respond_with(success(serde_json::json!({ "id": "t", "status": "active" })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = unshared_client(&server);
+        client.verify_connection().await.unwrap();
+        // Still sent after the token is swapped
+        client.set_api_token("rotated-token").unwrap();
+        client.verify_connection().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_override() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/verify"))
+            .and(header("user-agent", "AcmeCDN/2.0"))
+            .and(header("x-plugin-version", crate::VERSION))
+            .respond_with(success(serde_json::json!({ "id": "t", "status": "active" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = CloudflareConfig {
+            api_token: "test-token".to_string(),
+            account_id: "acct".to_string(),
+            zone_id: "zone".to_string(),
+            user_agent: Some("AcmeCDN/2.0".to_string()),
+            ..Default::default()
+        };
+        let client = CloudflareClient::new(&config).unwrap().with_base_url(&server.uri());
+        client.verify_connection().await.unwrap();
+
+        let config = CloudflareConfig { user_agent: Some("bad\nagent".to_string()), ..config };
+        assert!(matches!(CloudflareClient::new(&config), Err(CloudflareError::InvalidConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_list_stream_videos_paginated_sends_filters_and_cursor() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/stream"))
+            .and(query_param("search", "intro"))
+            .and(query_param("end", "2024-03-01T12:00:00Z"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "result": [
+                    { "uid": "last-seen", "created": "2024-03-01T12:00:00Z" },
+                    { "uid": "older", "created": "2024-02-01T12:00:00Z" }
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = unshared_client(&server);
+        let params = StreamListParams {
+            search: Some("intro".to_string()),
+            cursor: Some("2024-03-01T12:00:00Z|last-seen".to_string()),
+            ..Default::default()
+        };
+        let page = client.list_stream_videos_paginated(&params).await.unwrap();
+        assert_eq!(page.videos.len(), 1);
+        assert_eq!(page.videos[0].uid, "older");
+        assert!(page.next_cursor.is_none());
+
+        let params = StreamListParams { cursor: Some("bogus".to_string()), ..Default::default() };
+        assert!(matches!(
+            client.list_stream_videos_paginated(&params).await,
+            Err(CloudflareError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_query_d1_batch_sends_statements_together() {
+        use wiremock::matchers::body_json;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/d1/database/db-1/query"))
+            .and(body_json(serde_json::json!({
+                "sql": "INSERT INTO posts (title) VALUES ('a')\n;\nUPDATE posts SET title = 'b' -- rename\n;\nSELECT COUNT(*) AS n FROM posts"
+            })))
+            .respond_with(success(serde_json::json!([
+                { "results": [], "success": true, "meta": { "changes": 1, "last_row_id": 7, "duration": 0.2 } },
+                { "results": [], "success": true, "meta": { "changes": 3, "rows_written": 3 } },
+                { "results": [{ "n": 7 }], "success": true, "meta": { "rows_read": 7 } }
+            ])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = unshared_client(&server);
+        let results = client
+            .query_d1_batch(
+                "db-1",
+                vec![
+                    "INSERT INTO posts (title) VALUES ('a');".to_string(),
+                    "UPDATE posts SET title = 'b' -- rename\n".to_string(),
+                    "  ".to_string(),
+                    "SELECT COUNT(*) AS n FROM posts".to_string(),
+                ],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].meta.as_ref().unwrap().last_row_id, Some(7));
+        assert_eq!(results[1].meta.as_ref().unwrap().changes, Some(3));
+        assert_eq!(results[2].results, vec![serde_json::json!({ "n": 7 })]);
+
+        assert!(client.query_d1_batch("db-1", Vec::new()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_worker_route_calls_route_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/zones/zone/workers/routes/route-1"))
+            .respond_with(success(serde_json::json!({ "id": "route-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = unshared_client(&server).delete_worker_route("route-1").await.unwrap();
+        assert_eq!(result.id, "route-1");
+    }
+
+    #[tokio::test]
+    async fn test_delete_kv_namespace_calls_namespace_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/accounts/acct/storage/kv/namespaces/ns-1"))
+            .respond_with(success(serde_json::json!(null)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        unshared_client(&server).delete_kv_namespace("ns-1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_kv_namespace_surfaces_api_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/accounts/acct/storage/kv/namespaces/ns-1"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 10013, "message": "namespace is not empty" }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&server)
+            .await;
+
+        let err = unshared_client(&server).delete_kv_namespace("ns-1").await.unwrap_err();
+        assert!(matches!(err, CloudflareError::ApiError { code: 10013, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_zone_id_maps_to_zone_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 1003, "message": "Invalid or missing zone id." }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&server)
+            .await;
+
+        let err = unshared_client(&server).get_zone().await.unwrap_err();
+        assert!(matches!(err, CloudflareError::ZoneNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_delete_live_input_calls_live_input_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/accounts/acct/stream/live_inputs/input-1"))
+            .respond_with(success(serde_json::json!(null)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        unshared_client(&server).delete_live_input("input-1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_missing_live_input_maps_to_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/stream/live_inputs/missing"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 10003, "message": "Not Found" }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&server)
+            .await;
+
+        let err = unshared_client(&server).get_live_input("missing").await.unwrap_err();
+        assert!(matches!(err, CloudflareError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_configured_base_url_receives_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/client/v4/accounts/acct/stream"))
+            .respond_with(success(serde_json::json!([])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = CloudflareConfig {
+            api_token: "test-token".to_string(),
+            account_id: "acct".to_string(),
+            zone_id: "zone".to_string(),
+            api_base_url: format!("{}/client/v4/", server.uri()),
+            ..Default::default()
+        };
+        let client = CloudflareClient::new(&config).unwrap();
+        assert_eq!(client.base_url(), format!("{}/client/v4", server.uri()));
+        assert!(client.list_stream_videos().await.unwrap().is_empty());
+
+        for invalid in ["", "api.cloudflare.com/client/v4", "ftp://api.example.com"] {
+            let config = CloudflareConfig { api_base_url: invalid.to_string(), ..config.clone() };
+            assert!(matches!(
+                CloudflareClient::new(&config),
+                Err(CloudflareError::InvalidConfig(_))
+            ));
+        }
+    }
+
+    /// Collects everything written by a tracing subscriber
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verbose_logging_redacts_tokens_and_captures_ray() {
+        let token = "tok_6c1b9f0e4d2a8e7f";
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/tokens"))
+            .respond_with(
+                ResponseTemplate::new(400)
+                    .insert_header("CF-Ray", "8a1b2c3d4e5f6789-LHR")
+                    .set_body_json(serde_json::json!({
+                        "success": false,
+                        "errors": [{ "code": 1001, "message": "Invalid policy" }],
+                        "messages": [],
+                        "result": null
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        let config = CloudflareConfig {
+            api_token: token.to_string(),
+            account_id: "acct".to_string(),
+            zone_id: "zone".to_string(),
+            verbose_logging: true,
+            ..Default::default()
+        };
+        let client = CloudflareClient::new(&config).unwrap().with_base_url(&server.uri());
+
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let err = client
+            .post::<serde_json::Value, _>(
+                "/accounts/acct/tokens",
+                &serde_json::json!({ "name": "deploy", "value": { "api_token": token, "client_secret": token } }),
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.ray_id(), Some("8a1b2c3d4e5f6789-LHR"));
+        assert!(err.to_string().contains("8a1b2c3d4e5f6789-LHR"));
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("--> POST"));
+        assert!(output.contains("8a1b2c3d4e5f6789-LHR"));
+        assert!(output.contains("Invalid policy"));
+        assert!(output.contains(crate::utils::REDACTED));
+        assert!(!output.contains(token), "token leaked into logs:\n{}", output);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_worker_if_match_sends_etag() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/accounts/acct/workers/scripts/edge"))
+            .and(header("If-Match", "\"etag-1\""))
+            .respond_with(success(serde_json::json!({ "id": "edge", "etag": "etag-2" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let worker = unshared_client(&server)
+            .deploy_worker_if_match("edge", "export default {}", "etag-1")
+            .await
+            .unwrap();
+        assert_eq!(worker.etag.as_deref(), Some("etag-2"));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_worker_retries_503_with_rebuilt_form() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/accounts/acct/workers/scripts/edge"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/accounts/acct/workers/scripts/edge"))
+            .respond_with(success(serde_json::json!({ "id": "edge", "etag": "etag-2" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let worker = unshared_client(&server).deploy_worker("edge", "export default {}").await.unwrap();
+        assert_eq!(worker.id, "edge");
+
+        // Each attempt carried the whole script, not an emptied form
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        for request in requests {
+            let body = String::from_utf8_lossy(&request.body).into_owned();
+            assert!(body.contains("name=\"script\""));
+            assert!(body.contains("export default {}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deploy_worker_does_not_retry_gateway_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/accounts/acct/workers/scripts/edge"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // The upload may have been applied, so it isn't sent again
+        assert!(unshared_client(&server).deploy_worker("edge", "export default {}").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deploy_worker_etag_mismatch_is_conflict() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/accounts/acct/workers/scripts/edge"))
+            .respond_with(ResponseTemplate::new(412))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let err = unshared_client(&server)
+            .deploy_worker_if_match("edge", "export default {}", "stale")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::Conflict(_)));
+        assert_eq!(err.status_code(), axum::http::StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_connection_status_warns_about_missing_scopes() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/verify"))
+            .respond_with(success(serde_json::json!({ "id": "tok-1", "status": "active" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/tok-1"))
+            .respond_with(success(serde_json::json!({
+                "id": "tok-1",
+                "name": "RustPress",
+                "status": "active",
+                "policies": [{
+                    "id": "pol-1",
+                    "effect": "allow",
+                    "resources": { "com.cloudflare.api.account.zone.zone": "*" },
+                    "permission_groups": [
+                        { "id": "g1", "name": "Zone Settings Write" },
+                        { "id": "g2", "name": "DNS Read" }
+                    ]
+                }, {
+                    "id": "pol-2",
+                    "effect": "deny",
+                    "resources": {},
+                    "permission_groups": [{ "id": "g3", "name": "DNS Write" }]
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let status = unshared_client(&server).connection_status().await;
+
+        assert!(status.connected);
+        assert_eq!(status.missing_scopes, vec!["cache_purge", "dns:edit"]);
+        assert_eq!(
+            status.permission_groups,
+            Some(vec!["DNS Read".to_string(), "Zone Settings Write".to_string()])
+        );
+        assert!(status
+            .warnings
+            .iter()
+            .any(|w| w.contains("missing required scopes: cache_purge, dns:edit")));
+        // The zone endpoint isn't mocked, so that shows up as a warning rather than an error
+        assert!(status.zone_name.is_none());
+        assert!(status.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_add_ip_list_items_sends_bulk_body() {
+        use wiremock::matchers::body_json;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/rules/lists/list-1/items"))
+            .and(body_json(serde_json::json!([
+                { "ip": "192.0.2.1", "comment": "scraper" },
+                { "ip": "2001:db8::/48" }
+            ])))
+            .respond_with(success(serde_json::json!({ "operation_id": "op-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let items = vec![
+            CreateIpListItem { ip: "192.0.2.1".to_string(), comment: Some("scraper".to_string()) },
+            CreateIpListItem { ip: "2001:db8::/48".to_string(), comment: None },
+        ];
+        let operation = unshared_client(&server).add_ip_list_items("list-1", &items).await.unwrap();
+        assert_eq!(operation.operation_id, "op-1");
+    }
+
+    #[tokio::test]
+    async fn test_create_turnstile_widget_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/challenges/widgets"))
+            .and(body_json(serde_json::json!({
+                "name": "Contact form",
+                "domains": ["example.com"],
+                "mode": "managed"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "result": {
+                    "sitekey": "0x4AAAAAAA",
+                    "secret": "0x4AAAAAAA-secret",
+                    "name": "Contact form",
+                    "domains": ["example.com"],
+                    "mode": "managed",
+                    "created_on": "2024-03-01T12:00:00Z",
+                    "modified_on": "2024-03-01T12:00:00Z"
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let widget = unshared_client(&server)
+            .create_turnstile_widget(&CreateTurnstileWidget {
+                name: "Contact form".to_string(),
+                domains: vec!["example.com".to_string()],
+                mode: "managed".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(widget.sitekey, "0x4AAAAAAA");
+        assert_eq!(widget.secret.as_deref(), Some("0x4AAAAAAA-secret"));
+    }
+
+    #[tokio::test]
+    async fn test_check_capabilities_reports_each_probe() {
+        let server = MockServer::start().await;
+        let denied = || {
+            ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 10000, "message": "Authentication error" }],
+                "messages": [],
+                "result": null
+            }))
+        };
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .respond_with(success(serde_json::json!({
+                "id": "zone", "name": "example.com", "status": "active", "paused": false,
+                "development_mode": 0, "type": "full", "name_servers": []
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/dns_records"))
+            .and(query_param("per_page", "5"))
+            .respond_with(success(serde_json::json!([])))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings/cache_level"))
+            .respond_with(success(serde_json::json!({ "id": "cache_level", "value": "aggressive", "editable": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/workers/scripts"))
+            .respond_with(denied())
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/r2/buckets"))
+            .respond_with(denied())
+            .mount(&server)
+            .await;
+
+        let checks = unshared_client(&server).check_capabilities().await;
+
+        assert_eq!(checks.keys().copied().collect::<Vec<_>>(), vec!["cache", "dns", "r2", "workers", "zone"]);
+        for ok_probe in ["zone", "dns", "cache"] {
+            assert!(checks[ok_probe].ok, "{} should pass", ok_probe);
+            assert!(checks[ok_probe].error.is_none());
+        }
+        for failed_probe in ["workers", "r2"] {
+            assert!(!checks[failed_probe].ok, "{} should fail", failed_probe);
+            assert!(checks[failed_probe].error.is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_identical_gets_share_one_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "success": true,
+                        "errors": [],
+                        "messages": [],
+                        "result": [{ "id": "brotli", "value": "on", "editable": true }]
+                    }))
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = unshared_client(&server);
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.get_zone_settings().await })
+            })
+            .collect();
+        for handle in handles {
+            let settings = handle.await.unwrap().unwrap();
+            assert_eq!(settings[0].id, "brotli");
+        }
+        server.verify().await;
+
+        // Once finished, the next GET goes upstream again
+        server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .set_body_json(serde_json::json!({
+                        "success": false,
+                        "errors": [{ "code": 9109, "message": "Unauthorized to access requested resource" }],
+                        "messages": [],
+                        "result": null
+                    }))
+                    .set_delay(Duration::from_millis(100)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Failures reach every waiter too
+        let (a, b) = tokio::join!(client.get_zone_settings(), client.get_zone_settings());
+        assert!(matches!(a, Err(CloudflareError::PermissionDenied(_))));
+        assert!(matches!(b, Err(CloudflareError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_slow_read_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "success": true, "errors": [], "messages": [], "result": null }))
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "success": true, "errors": [], "messages": [],
+                        "result": {
+                            "id": "zone", "name": "example.com", "status": "active", "paused": true,
+                            "development_mode": 0, "type": "full", "name_servers": []
+                        }
+                    }))
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = unshared_client(&server).with_timeouts(RequestTimeouts {
+            read: Duration::from_millis(50),
+            mutation: Duration::from_secs(5),
+            upload: Duration::from_secs(5),
+        });
+
+        let err = client.get_zone().await.unwrap_err();
+        assert!(matches!(err, CloudflareError::Timeout(_)), "{:?}", err);
+        // Mutations have their own, longer timeout
+        assert!(client.set_zone_paused(true).await.unwrap().paused);
+    }
+
+    #[tokio::test]
+    async fn test_raw_paths_map_timeouts_and_connect_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/storage/kv/namespaces/ns/values/slow"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("value").set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let client = unshared_client(&server).with_timeouts(RequestTimeouts {
+            read: Duration::from_millis(50),
+            ..RequestTimeouts::default()
+        });
+        let err = client.get_kv_value("ns", "slow").await.unwrap_err();
+        assert!(matches!(err, CloudflareError::Timeout(_)), "{:?}", err);
+        assert_eq!(err.status_code(), axum::http::StatusCode::SERVICE_UNAVAILABLE);
+
+        // Nothing listens on port 1
+        let unreachable = unshared_client(&server).with_base_url("http://127.0.0.1:1");
+        let err = unreachable.set_kv_value("ns", "key", "value").await.unwrap_err();
+        assert!(matches!(err, CloudflareError::NetworkError(_)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_get_kv_value_typed_by_content_type() {
+        let server = MockServer::start().await;
+        let value_path = |key: &str| path(format!("/accounts/acct/storage/kv/namespaces/ns/values/{}", key));
+        Mock::given(method("GET"))
+            .and(value_path("config"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(r#"{"ttl":60,"paths":["/"]}"#, "application/json"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(value_path("greeting"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("hello", "application/octet-stream"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(value_path("logo"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(vec![0x89, b'P', b'N', b'G', 0xff], "image/png"))
+            .mount(&server)
+            .await;
+
+        let client = unshared_client(&server);
+        assert_eq!(
+            client.get_kv_value_typed("ns", "config").await.unwrap(),
+            KvValue::Json(serde_json::json!({ "ttl": 60, "paths": ["/"] }))
+        );
+        assert_eq!(client.get_kv_value_typed("ns", "greeting").await.unwrap(), KvValue::Text("hello".to_string()));
+        assert_eq!(client.get_kv_value_typed("ns", "logo").await.unwrap(), KvValue::Binary("iVBOR/8=".to_string()));
+
+        // The raw getter still returns the JSON as a string
+        assert_eq!(client.get_kv_value("ns", "config").await.unwrap(), r#"{"ttl":60,"paths":["/"]}"#);
+        assert_eq!(
+            serde_json::to_value(KvValue::Binary("iVBOR/8=".to_string())).unwrap(),
+            serde_json::json!({ "type": "binary", "value": "iVBOR/8=" })
+        );
+    }
 }
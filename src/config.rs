@@ -2,6 +2,10 @@
 
 use crate::error::{CloudflareError, CloudflareResult};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Public Cloudflare API endpoint
+pub const DEFAULT_API_BASE_URL: &str = "https://api.cloudflare.com/client/v4";
 
 /// Main configuration for Cloudflare integration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +15,26 @@ pub struct CloudflareConfig {
     pub account_id: String,
     pub zone_id: String,
     pub email: Option<String>,
+    /// API endpoint, e.g. an API gateway in front of Cloudflare or a mock server in tests
+    #[serde(default = "default_api_base_url")]
+    pub api_base_url: String,
+    /// Log every API request and response, with credentials redacted
+    #[serde(default)]
+    pub verbose_logging: bool,
+    /// `User-Agent` sent to Cloudflare instead of the RustCloudflare one,
+    /// e.g. for white-labeled builds
+    pub user_agent: Option<String>,
+
+    // Request Timeouts (seconds)
+    /// Reads such as listing records or loading settings
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+    /// Creates, updates, deletes and purges
+    #[serde(default = "default_mutation_timeout_secs")]
+    pub mutation_timeout_secs: u64,
+    /// Worker deploys and R2/Stream uploads
+    #[serde(default = "default_upload_timeout_secs")]
+    pub upload_timeout_secs: u64,
 
     // CDN Settings
     #[serde(default = "default_true")]
@@ -120,6 +144,26 @@ pub struct CloudflareConfig {
     pub dns_management: bool,
     #[serde(default)]
     pub auto_dns_sync: bool,
+
+    // Cloudflare Access
+    /// Zero Trust team domain, e.g. `myteam` or `myteam.cloudflareaccess.com`
+    pub access_team_domain: Option<String>,
+    /// Application Audience (AUD) tag of the Access application protecting the admin
+    pub access_audience: Option<String>,
+}
+
+/// How long a request may take, by the kind of operation it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTimeouts {
+    pub read: Duration,
+    pub mutation: Duration,
+    pub upload: Duration,
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        CloudflareConfig::default().request_timeouts()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -180,6 +224,27 @@ fn default_cache_level() -> CacheLevel {
     CacheLevel::Aggressive
 }
 
+/// `User-Agent` identifying this plugin and its version
+pub fn default_user_agent() -> String {
+    format!("RustCloudflare/{} (+https://rustpress.io)", crate::VERSION)
+}
+
+fn default_api_base_url() -> String {
+    DEFAULT_API_BASE_URL.to_string()
+}
+
+fn default_read_timeout_secs() -> u64 {
+    15
+}
+
+fn default_mutation_timeout_secs() -> u64 {
+    30
+}
+
+fn default_upload_timeout_secs() -> u64 {
+    300
+}
+
 fn default_browser_cache_ttl() -> u32 {
     14400 // 4 hours
 }
@@ -253,6 +318,14 @@ impl CloudflareConfig {
             zone_id: std::env::var("CLOUDFLARE_ZONE_ID")
                 .map_err(|_| CloudflareError::MissingConfig("CLOUDFLARE_ZONE_ID".to_string()))?,
             email: std::env::var("CLOUDFLARE_EMAIL").ok(),
+            api_base_url: std::env::var("CLOUDFLARE_API_BASE_URL")
+                .unwrap_or_else(|_| default_api_base_url()),
+            verbose_logging: std::env::var("CLOUDFLARE_VERBOSE_LOGGING")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            user_agent: std::env::var("CLOUDFLARE_USER_AGENT").ok(),
+            access_team_domain: std::env::var("CLOUDFLARE_ACCESS_TEAM_DOMAIN").ok(),
+            access_audience: std::env::var("CLOUDFLARE_ACCESS_AUD").ok(),
             ..Default::default()
         })
     }
@@ -277,6 +350,38 @@ impl CloudflareConfig {
             ));
         }
 
+        match url::Url::parse(&self.api_base_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => {}
+            _ => {
+                return Err(CloudflareError::InvalidConfig(format!(
+                    "API base URL must be an http(s) URL, got '{}'",
+                    self.api_base_url
+                )));
+            }
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            if user_agent.trim().is_empty() || user_agent.chars().any(|c| c.is_control()) {
+                return Err(CloudflareError::InvalidConfig(format!(
+                    "User agent must be non-empty printable text, got '{}'",
+                    user_agent.escape_debug()
+                )));
+            }
+        }
+
+        for (field, secs) in [
+            ("read_timeout_secs", self.read_timeout_secs),
+            ("mutation_timeout_secs", self.mutation_timeout_secs),
+            ("upload_timeout_secs", self.upload_timeout_secs),
+        ] {
+            if secs == 0 {
+                return Err(CloudflareError::InvalidConfig(format!(
+                    "{} must be greater than 0",
+                    field
+                )));
+            }
+        }
+
         // Validate R2 config if enabled
         if self.r2_enabled {
             if self.r2_bucket.is_none() {
@@ -295,12 +400,33 @@ impl CloudflareConfig {
             }
         }
 
+        // Access JWTs can only be checked with both the team and the audience
+        if self.access_team_domain.is_some() != self.access_audience.is_some() {
+            return Err(CloudflareError::InvalidConfig(
+                "Cloudflare Access needs both a team domain and an application audience".to_string(),
+            ));
+        }
+
         Ok(())
     }
 
-    /// Get the Cloudflare API base URL
+    /// Get the Cloudflare API base URL, without a trailing slash
     pub fn api_base_url(&self) -> &str {
-        "https://api.cloudflare.com/client/v4"
+        self.api_base_url.trim_end_matches('/')
+    }
+
+    /// `User-Agent` to send: the configured override, or the default
+    pub fn user_agent(&self) -> String {
+        self.user_agent.clone().unwrap_or_else(default_user_agent)
+    }
+
+    /// Per-request timeouts for each class of operation
+    pub fn request_timeouts(&self) -> RequestTimeouts {
+        RequestTimeouts {
+            read: Duration::from_secs(self.read_timeout_secs),
+            mutation: Duration::from_secs(self.mutation_timeout_secs),
+            upload: Duration::from_secs(self.upload_timeout_secs),
+        }
     }
 
     /// Get the R2 endpoint URL
@@ -319,6 +445,12 @@ impl Default for CloudflareConfig {
             account_id: String::new(),
             zone_id: String::new(),
             email: None,
+            api_base_url: default_api_base_url(),
+            verbose_logging: false,
+            user_agent: None,
+            read_timeout_secs: default_read_timeout_secs(),
+            mutation_timeout_secs: default_mutation_timeout_secs(),
+            upload_timeout_secs: default_upload_timeout_secs(),
             cdn_enabled: true,
             auto_minify: default_auto_minify(),
             brotli_compression: true,
@@ -365,6 +497,8 @@ impl Default for CloudflareConfig {
             analytics_token: None,
             dns_management: true,
             auto_dns_sync: false,
+            access_team_domain: None,
+            access_audience: None,
         }
     }
 }
@@ -5,9 +5,47 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use serde::Serialize;
 use serde_json::json;
 use thiserror::Error;
 
+/// Cloudflare error code returned when the plan's cache purge quota is used up
+pub const PURGE_QUOTA_EXCEEDED_CODE: i32 = 1134;
+
+/// Cloudflare error code for a missing or malformed zone identifier
+pub const INVALID_ZONE_ID_CODE: i32 = 1003;
+
+/// Cloudflare error code for a failed token authentication
+pub const AUTHENTICATION_ERROR_CODE: i32 = 10000;
+
+/// Cloudflare error code for a token lacking access to the resource
+pub const UNAUTHORIZED_CODE: i32 = 9109;
+
+/// Cloudflare error codes for a DNS record that already exists
+pub const RECORD_EXISTS_CODES: &[i32] = &[81044, 81053, 81057, 81058];
+
+/// One error from a Cloudflare API response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiErrorDetail {
+    pub code: i32,
+    pub message: String,
+}
+
+/// Flatten API errors and their `error_chain`s, depth first
+pub fn flatten_api_errors(errors: &[crate::models::ApiError]) -> Vec<ApiErrorDetail> {
+    let mut flat = Vec::new();
+    for error in errors {
+        flat.push(ApiErrorDetail {
+            code: error.code,
+            message: error.message.clone(),
+        });
+        if let Some(chain) = &error.error_chain {
+            flat.extend(flatten_api_errors(chain));
+        }
+    }
+    flat
+}
+
 /// Main error type for Cloudflare operations
 #[derive(Error, Debug)]
 pub enum CloudflareError {
@@ -32,8 +70,15 @@ pub enum CloudflareError {
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
-    #[error("API error: {message} (code: {code})")]
-    ApiError { code: i32, message: String },
+    #[error("API error: {message} (code: {code}){}{}", other_errors(.errors), ray_suffix(.ray_id))]
+    ApiError {
+        code: i32,
+        message: String,
+        /// Every error Cloudflare returned, including the first and any `error_chain`
+        errors: Vec<ApiErrorDetail>,
+        /// `CF-Ray` of the failed request, for support tickets
+        ray_id: Option<String>,
+    },
 
     #[error("Network error: {0}")]
     NetworkError(String),
@@ -86,7 +131,8 @@ pub enum CloudflareError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
-    #[error("Plugin not configured - Cloudflare credentials required")]
+    /// No API client yet, because no Cloudflare account has been connected
+    #[error("Cloudflare is not connected. Please connect your Cloudflare account in the plugin settings.")]
     NotConfigured,
 
     #[error("Internal error: {0}")]
@@ -112,6 +158,75 @@ impl From<CloudflareError> for rustpress_core::Error {
 }
 
 impl CloudflareError {
+    /// Map an error returned by the Cloudflare API to the most specific variant
+    pub fn from_api_error(code: i32, message: String) -> Self {
+        match code {
+            INVALID_ZONE_ID_CODE => Self::ZoneNotFound(format!(
+                "{} - check that the configured zone_id matches the zone in your Cloudflare dashboard",
+                message
+            )),
+            AUTHENTICATION_ERROR_CODE => Self::AuthenticationError(format!(
+                "{} - the API token is invalid, expired or revoked",
+                message
+            )),
+            UNAUTHORIZED_CODE => Self::PermissionDenied(format!(
+                "{} - grant the API token access to this zone or account",
+                message
+            )),
+            code if RECORD_EXISTS_CODES.contains(&code) => Self::Conflict(format!(
+                "{} - edit the existing DNS record instead of creating a new one",
+                message
+            )),
+            _ => Self::ApiError {
+                errors: vec![ApiErrorDetail { code, message: message.clone() }],
+                code,
+                message,
+                ray_id: None,
+            },
+        }
+    }
+
+    /// Map every error in an API response; the first one decides the variant
+    pub fn from_api_errors(errors: &[crate::models::ApiError]) -> Self {
+        let flat = flatten_api_errors(errors);
+        let Some(first) = flat.first() else {
+            return Self::ApiError {
+                code: 0,
+                message: "Unknown API error".to_string(),
+                errors: Vec::new(),
+                ray_id: None,
+            };
+        };
+
+        match Self::from_api_error(first.code, first.message.clone()) {
+            Self::ApiError { code, message, ray_id, .. } => Self::ApiError {
+                code,
+                message,
+                errors: flat,
+                ray_id,
+            },
+            other => other,
+        }
+    }
+
+    /// Attach the `CF-Ray` of the request that failed
+    pub fn with_ray_id(mut self, ray: Option<String>) -> Self {
+        if let Self::ApiError { ray_id, .. } = &mut self {
+            if ray.is_some() {
+                *ray_id = ray;
+            }
+        }
+        self
+    }
+
+    /// `CF-Ray` of the request that failed, if known
+    pub fn ray_id(&self) -> Option<&str> {
+        match self {
+            Self::ApiError { ray_id, .. } => ray_id.as_deref(),
+            _ => None,
+        }
+    }
+
     /// Get HTTP status code for this error
     pub fn status_code(&self) -> StatusCode {
         match self {
@@ -121,7 +236,7 @@ impl CloudflareError {
             Self::ValidationError(_) | Self::InvalidConfig(_) | Self::MissingConfig(_) | Self::ConfigError(_) => {
                 StatusCode::BAD_REQUEST
             }
-            Self::NotConfigured => StatusCode::SERVICE_UNAVAILABLE,
+            Self::NotConfigured => StatusCode::CONFLICT,
             Self::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
             Self::Conflict(_) => StatusCode::CONFLICT,
             Self::ServiceUnavailable(_) | Self::Timeout(_) => StatusCode::SERVICE_UNAVAILABLE,
@@ -176,22 +291,179 @@ impl CloudflareError {
             Self::Anyhow(_) => "INTERNAL_ERROR",
         }
     }
+
+    /// Whether this error means the zone has no purge quota left
+    pub fn is_purge_quota_exceeded(&self) -> bool {
+        match self {
+            Self::ApiError { code, message, .. } => {
+                *code == PURGE_QUOTA_EXCEEDED_CODE || message.to_lowercase().contains("purge quota")
+            }
+            _ => false,
+        }
+    }
+
+    /// Copy of this error for handing the same failure to several callers.
+    /// Wrapped library errors, which can't be cloned, keep their message
+    /// under the closest matching variant.
+    pub fn duplicate(&self) -> Self {
+        match self {
+            Self::AuthenticationError(m) => Self::AuthenticationError(m.clone()),
+            Self::InvalidToken => Self::InvalidToken,
+            Self::MissingConfig(m) => Self::MissingConfig(m.clone()),
+            Self::InvalidConfig(m) => Self::InvalidConfig(m.clone()),
+            Self::ZoneNotFound(m) => Self::ZoneNotFound(m.clone()),
+            Self::NotFound(m) => Self::NotFound(m.clone()),
+            Self::RateLimitExceeded => Self::RateLimitExceeded,
+            Self::ApiError { code, message, errors, ray_id } => Self::ApiError {
+                code: *code,
+                message: message.clone(),
+                errors: errors.clone(),
+                ray_id: ray_id.clone(),
+            },
+            Self::NetworkError(m) => Self::NetworkError(m.clone()),
+            Self::Timeout(m) => Self::Timeout(m.clone()),
+            Self::DatabaseError(m) => Self::DatabaseError(m.clone()),
+            Self::ValidationError(m) => Self::ValidationError(m.clone()),
+            Self::PermissionDenied(m) => Self::PermissionDenied(m.clone()),
+            Self::Conflict(m) => Self::Conflict(m.clone()),
+            Self::ServiceUnavailable(m) => Self::ServiceUnavailable(m.clone()),
+            Self::WorkerError(m) => Self::WorkerError(m.clone()),
+            Self::R2Error(m) => Self::R2Error(m.clone()),
+            Self::D1Error(m) => Self::D1Error(m.clone()),
+            Self::StreamError(m) => Self::StreamError(m.clone()),
+            Self::KvError(m) => Self::KvError(m.clone()),
+            Self::DnsError(m) => Self::DnsError(m.clone()),
+            Self::SslError(m) => Self::SslError(m.clone()),
+            Self::WafError(m) => Self::WafError(m.clone()),
+            Self::CacheError(m) => Self::CacheError(m.clone()),
+            Self::ConfigError(m) => Self::ConfigError(m.clone()),
+            Self::NotConfigured => Self::NotConfigured,
+            Self::Internal(m) => Self::Internal(m.clone()),
+            Self::Reqwest(e) if e.is_timeout() => Self::Timeout(e.to_string()),
+            Self::Reqwest(e) => Self::NetworkError(e.to_string()),
+            Self::SerdeJson(e) => Self::Internal(e.to_string()),
+            Self::Sqlx(e) => Self::DatabaseError(e.to_string()),
+            Self::Anyhow(e) => Self::Internal(e.to_string()),
+        }
+    }
+
+    /// Whether this error means the feature needs an Enterprise plan
+    pub fn is_enterprise_only(&self) -> bool {
+        match self {
+            Self::ApiError { message, .. } | Self::PermissionDenied(message) => {
+                message.to_lowercase().contains("enterprise")
+            }
+            _ => false,
+        }
+    }
 }
 
 impl IntoResponse for CloudflareError {
     fn into_response(self) -> Response {
         let status = self.status_code();
+        let mut error = json!({
+            "code": self.error_code(),
+            "message": self.to_string(),
+        });
+        if let Self::ApiError { errors, .. } = &self {
+            if !errors.is_empty() {
+                error["errors"] = json!(errors);
+            }
+        }
+        if let Some(ray_id) = self.ray_id() {
+            error["ray_id"] = json!(ray_id);
+        }
         let body = Json(json!({
             "success": false,
-            "error": {
-                "code": self.error_code(),
-                "message": self.to_string(),
-            }
+            "error": error,
         }));
 
         (status, body).into_response()
     }
 }
 
+fn other_errors(errors: &[ApiErrorDetail]) -> String {
+    errors
+        .iter()
+        .skip(1)
+        .map(|e| format!("; {} (code: {})", e.message, e.code))
+        .collect()
+}
+
+fn ray_suffix(ray_id: &Option<String>) -> String {
+    ray_id
+        .as_ref()
+        .map(|ray| format!(" [CF-Ray: {}]", ray))
+        .unwrap_or_default()
+}
+
 /// Result type alias for Cloudflare operations
 pub type CloudflareResult<T> = Result<T, CloudflareError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_error_codes_map_to_specific_variants() {
+        assert!(matches!(
+            CloudflareError::from_api_error(1003, "Invalid or missing zone id.".to_string()),
+            CloudflareError::ZoneNotFound(_)
+        ));
+        assert!(matches!(
+            CloudflareError::from_api_error(9109, "Unauthorized to access requested resource".to_string()),
+            CloudflareError::PermissionDenied(_)
+        ));
+        assert!(matches!(
+            CloudflareError::from_api_error(81044, "Record already exists.".to_string()),
+            CloudflareError::Conflict(_)
+        ));
+        assert!(matches!(
+            CloudflareError::from_api_error(PURGE_QUOTA_EXCEEDED_CODE, "Purge quota exceeded".to_string()),
+            CloudflareError::ApiError { code: PURGE_QUOTA_EXCEEDED_CODE, .. }
+        ));
+
+        let err = CloudflareError::from_api_error(1003, "Invalid or missing zone id.".to_string());
+        assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+        assert!(err.to_string().contains("zone_id"));
+    }
+
+    #[tokio::test]
+    async fn test_multiple_api_errors_are_all_surfaced() {
+        let errors: Vec<crate::models::ApiError> = serde_json::from_value(json!([
+            {
+                "code": 1004,
+                "message": "DNS Validation Error",
+                "error_chain": [
+                    { "code": 9005, "message": "Content for A record is invalid." },
+                    { "code": 9021, "message": "TTL must be between 60 and 86400 seconds, or 1 for Automatic." }
+                ]
+            },
+            { "code": 9000, "message": "Name is required." }
+        ]))
+        .unwrap();
+
+        let err = CloudflareError::from_api_errors(&errors);
+        let text = err.to_string();
+        for message in [
+            "DNS Validation Error",
+            "Content for A record is invalid.",
+            "TTL must be between 60 and 86400 seconds",
+            "Name is required.",
+        ] {
+            assert!(text.contains(message), "{} missing from {}", message, text);
+        }
+        assert!(matches!(err, CloudflareError::ApiError { code: 1004, .. }));
+
+        let response = err.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let codes: Vec<i64> = body["error"]["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["code"].as_i64().unwrap())
+            .collect();
+        assert_eq!(codes, vec![1004, 9005, 9021, 9000]);
+    }
+}
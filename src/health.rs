@@ -0,0 +1,236 @@
+//! Connection health checks
+//!
+//! A site configured after boot, or whose token stops working, would
+//! otherwise stay in whatever state it started in until a restart. The
+//! health check periodically connects once credentials appear and verifies
+//! an existing connection, backing off while checks keep failing.
+
+use crate::error::{CloudflareError, CloudflareResult};
+use crate::services::CloudflareCredentials;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How often the connection is checked while healthy or unconfigured
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Longest wait between checks while they keep failing
+pub const MAX_HEALTH_BACKOFF: Duration = Duration::from_secs(15 * 60);
+
+/// Overall connection health of the plugin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    /// No credentials stored yet
+    Unconfigured,
+    /// Connected and the token verifies
+    Healthy,
+    /// Credentials exist but connecting or verifying fails
+    Degraded,
+}
+
+/// Health as reported by the status endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSnapshot {
+    pub state: HealthState,
+    pub consecutive_failures: u32,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl Default for HealthSnapshot {
+    fn default() -> Self {
+        Self {
+            state: HealthState::Unconfigured,
+            consecutive_failures: 0,
+            last_checked_at: None,
+            last_error: None,
+        }
+    }
+}
+
+/// Health shared between the plugin, its background task and the API
+#[derive(Debug, Clone, Default)]
+pub struct PluginHealth {
+    inner: Arc<Mutex<HealthSnapshot>>,
+}
+
+impl PluginHealth {
+    pub fn snapshot(&self) -> HealthSnapshot {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn record_success(&self) {
+        let mut health = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if health.state == HealthState::Degraded {
+            info!("Cloudflare connection recovered");
+        }
+        *health = HealthSnapshot {
+            state: HealthState::Healthy,
+            last_checked_at: Some(Utc::now()),
+            ..Default::default()
+        };
+    }
+
+    pub fn record_failure(&self, error: &CloudflareError) {
+        let mut health = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        health.state = HealthState::Degraded;
+        health.consecutive_failures += 1;
+        health.last_checked_at = Some(Utc::now());
+        health.last_error = Some(error.to_string());
+    }
+
+    pub fn mark_unconfigured(&self) {
+        *self.inner.lock().unwrap_or_else(|e| e.into_inner()) = HealthSnapshot {
+            last_checked_at: Some(Utc::now()),
+            ..Default::default()
+        };
+    }
+}
+
+/// What the health check needs from the plugin
+#[async_trait]
+pub trait HealthTarget: Send + Sync {
+    /// Whether a client is currently set up
+    async fn is_connected(&self) -> bool;
+    /// Credentials saved in settings, if any
+    async fn stored_credentials(&self) -> CloudflareResult<Option<CloudflareCredentials>>;
+    /// Set up and verify a client from saved credentials
+    async fn connect(&self, credentials: CloudflareCredentials) -> CloudflareResult<()>;
+    /// Verify the current client's token still works
+    async fn verify(&self) -> CloudflareResult<()>;
+}
+
+/// Run one health check, returning how long to wait before the next one
+pub async fn check_health(target: &dyn HealthTarget, health: &PluginHealth) -> Duration {
+    let result = if target.is_connected().await {
+        target.verify().await
+    } else {
+        match target.stored_credentials().await {
+            Ok(Some(credentials)) => {
+                info!("Cloudflare credentials found, connecting");
+                target.connect(credentials).await
+            }
+            Ok(None) => {
+                health.mark_unconfigured();
+                return HEALTH_CHECK_INTERVAL;
+            }
+            Err(e) => Err(e),
+        }
+    };
+
+    match result {
+        Ok(()) => health.record_success(),
+        Err(e) => {
+            warn!("Cloudflare health check failed: {}", e);
+            health.record_failure(&e);
+        }
+    }
+
+    next_check_delay(health.snapshot().consecutive_failures)
+}
+
+/// Wait before the next check: the normal interval, doubling per failure
+/// up to `MAX_HEALTH_BACKOFF`
+pub fn next_check_delay(consecutive_failures: u32) -> Duration {
+    HEALTH_CHECK_INTERVAL
+        .saturating_mul(1 << consecutive_failures.min(16))
+        .min(MAX_HEALTH_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    /// Settings held in memory, with a client that connects on demand
+    #[derive(Default)]
+    struct FakePlugin {
+        credentials: Mutex<Option<CloudflareCredentials>>,
+        connected: AtomicBool,
+        token_valid: AtomicBool,
+        connects: AtomicU32,
+    }
+
+    #[async_trait]
+    impl HealthTarget for FakePlugin {
+        async fn is_connected(&self) -> bool {
+            self.connected.load(Ordering::SeqCst)
+        }
+
+        async fn stored_credentials(&self) -> CloudflareResult<Option<CloudflareCredentials>> {
+            Ok(self.credentials.lock().unwrap().clone())
+        }
+
+        async fn connect(&self, _: CloudflareCredentials) -> CloudflareResult<()> {
+            self.connects.fetch_add(1, Ordering::SeqCst);
+            self.verify().await?;
+            self.connected.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn verify(&self) -> CloudflareResult<()> {
+            if self.token_valid.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err(CloudflareError::AuthenticationError("Token verification failed".to_string()))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_connects_once_credentials_appear() {
+        let plugin = FakePlugin::default();
+        let health = PluginHealth::default();
+
+        // Nothing saved yet: stay unconfigured and keep checking at the normal pace
+        assert_eq!(check_health(&plugin, &health).await, HEALTH_CHECK_INTERVAL);
+        assert_eq!(health.snapshot().state, HealthState::Unconfigured);
+        assert_eq!(plugin.connects.load(Ordering::SeqCst), 0);
+
+        // Credentials saved from the dashboard after boot
+        *plugin.credentials.lock().unwrap() = Some(CloudflareCredentials {
+            api_token: "token".to_string(),
+            account_id: "acct".to_string(),
+            zone_id: "zone".to_string(),
+        });
+        plugin.token_valid.store(true, Ordering::SeqCst);
+
+        assert_eq!(check_health(&plugin, &health).await, HEALTH_CHECK_INTERVAL);
+        assert!(plugin.connected.load(Ordering::SeqCst));
+        assert_eq!(plugin.connects.load(Ordering::SeqCst), 1);
+        assert_eq!(health.snapshot().state, HealthState::Healthy);
+
+        // Already connected: verify instead of connecting again
+        check_health(&plugin, &health).await;
+        assert_eq!(plugin.connects.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failing_verification_degrades_and_backs_off() {
+        let plugin = FakePlugin::default();
+        plugin.connected.store(true, Ordering::SeqCst);
+        let health = PluginHealth::default();
+
+        assert_eq!(check_health(&plugin, &health).await, HEALTH_CHECK_INTERVAL * 2);
+        assert_eq!(check_health(&plugin, &health).await, HEALTH_CHECK_INTERVAL * 4);
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.state, HealthState::Degraded);
+        assert_eq!(snapshot.consecutive_failures, 2);
+        assert!(snapshot.last_error.unwrap().contains("Token verification failed"));
+
+        plugin.token_valid.store(true, Ordering::SeqCst);
+        assert_eq!(check_health(&plugin, &health).await, HEALTH_CHECK_INTERVAL);
+        assert_eq!(health.snapshot().state, HealthState::Healthy);
+    }
+
+    #[test]
+    fn test_next_check_delay_is_capped() {
+        assert_eq!(next_check_delay(0), HEALTH_CHECK_INTERVAL);
+        assert_eq!(next_check_delay(3), HEALTH_CHECK_INTERVAL * 8);
+        assert_eq!(next_check_delay(10), MAX_HEALTH_BACKOFF);
+        assert_eq!(next_check_delay(u32::MAX), MAX_HEALTH_BACKOFF);
+    }
+}
@@ -3,14 +3,37 @@
 //! This module provides hooks that automatically purge Cloudflare cache
 //! when content changes in RustPress, ensuring visitors always see fresh content.
 
+mod purge_queue;
+
+pub use purge_queue::{PendingPurges, PurgeExecutor, PurgeQueue, MAX_URLS_PER_PURGE};
+
 use crate::error::{CloudflareError, CloudflareResult};
-use crate::services::CloudflareServices;
+use crate::services::cache::{IdempotencyCache, PURGE_IDEMPOTENCY_WINDOW};
+use crate::services::{features, CloudflareServices, NotificationEvent, SharedServices};
+use crate::utils::normalize_purge_url;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
+/// Events for the same content and action within this many seconds share an idempotency key
+const EVENT_KEY_BUCKET_SECS: i64 = 5;
+
+/// What auto-purge does when the zone's purge quota is used up
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaExhaustedAction {
+    /// Purge the affected URLs individually instead
+    #[default]
+    FallbackToUrls,
+    /// Pause auto-purge for `quota_pause_minutes`
+    DisableAutoPurge,
+}
+
 /// Auto-purge configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AutoPurgeConfig {
@@ -38,8 +61,21 @@ pub struct AutoPurgeConfig {
     pub purge_archives: bool,
     /// Custom URLs to always purge (comma-separated patterns)
     pub custom_purge_urls: Option<String>,
-    /// Delay before purging (milliseconds) - allows batching
+    /// Debounce window before queued purges are flushed (milliseconds)
     pub purge_delay_ms: u32,
+    /// What to do when the purge quota is exhausted
+    #[serde(default)]
+    pub on_quota_exhausted: QuotaExhaustedAction,
+    /// How long auto-purge stays paused after the quota is exhausted
+    #[serde(default = "default_quota_pause_minutes")]
+    pub quota_pause_minutes: u32,
+    /// Log what would be purged without purging anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+pub(crate) fn default_quota_pause_minutes() -> u32 {
+    60
 }
 
 impl AutoPurgeConfig {
@@ -59,6 +95,9 @@ impl AutoPurgeConfig {
             purge_archives: true,
             custom_purge_urls: None,
             purge_delay_ms: 500, // Small delay to batch rapid changes
+            on_quota_exhausted: QuotaExhaustedAction::FallbackToUrls,
+            quota_pause_minutes: default_quota_pause_minutes(),
+            dry_run: false,
         }
     }
 }
@@ -148,6 +187,15 @@ pub struct ContentChangeEvent {
     pub related_urls: Vec<String>,
     /// User who made the change
     pub user_id: Option<String>,
+    /// When the content was published, used for dated archive URLs
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+    /// Status before the change (e.g. `draft`, `publish`), if known
+    #[serde(default)]
+    pub previous_status: Option<String>,
+    /// Status after the change, if known
+    #[serde(default)]
+    pub status: Option<String>,
     /// Timestamp of the event
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
@@ -163,6 +211,9 @@ impl ContentChangeEvent {
             title: None,
             related_urls: Vec::new(),
             user_id: None,
+            published_at: None,
+            previous_status: None,
+            status: None,
             timestamp: chrono::Utc::now(),
         }
     }
@@ -196,34 +247,138 @@ impl ContentChangeEvent {
         self.user_id = Some(user_id.into());
         self
     }
+
+    pub fn with_published_at(mut self, published_at: DateTime<Utc>) -> Self {
+        self.published_at = Some(published_at);
+        self
+    }
+
+    pub fn with_status_change(mut self, previous: impl Into<String>, current: impl Into<String>) -> Self {
+        self.previous_status = Some(previous.into());
+        self.status = Some(current.into());
+        self
+    }
+
+    /// Whether the content was or becomes publicly visible.
+    ///
+    /// A status that isn't known is assumed to be public so that events without
+    /// status information keep purging.
+    pub fn affects_public_content(&self) -> bool {
+        let was_public = self.previous_status.as_deref().map(is_public_status);
+        let is_public = match (&self.status, &self.action) {
+            (Some(status), _) => Some(is_public_status(status)),
+            (None, EventAction::Published) => Some(true),
+            (None, EventAction::Unpublished | EventAction::Trashed | EventAction::Deleted) => Some(false),
+            (None, _) => None,
+        };
+
+        was_public.unwrap_or(true) || is_public.unwrap_or(true)
+    }
+
+    /// Key identifying this change, shared by redeliveries of the same event.
+    ///
+    /// Built from the content type, ID (or URL), action and the event time
+    /// rounded down to `EVENT_KEY_BUCKET_SECS`.
+    pub fn idempotency_key(&self) -> String {
+        let target = self
+            .content_id
+            .as_deref()
+            .or(self.url.as_deref())
+            .or(self.title.as_deref())
+            .unwrap_or_default();
+        format!(
+            "{}:{}:{}:{}",
+            self.content_type,
+            target,
+            self.action,
+            self.timestamp.timestamp().div_euclid(EVENT_KEY_BUCKET_SECS)
+        )
+    }
+}
+
+/// Whether content with this status is visible to visitors
+fn is_public_status(status: &str) -> bool {
+    matches!(status.to_ascii_lowercase().as_str(), "publish" | "published" | "public")
 }
 
 /// Auto-purge hooks manager
 pub struct AutoPurgeHooks {
-    services: Option<Arc<CloudflareServices>>,
+    services: SharedServices,
     config: RwLock<AutoPurgeConfig>,
     db: PgPool,
     site_url: String,
+    paused_until: RwLock<Option<DateTime<Utc>>>,
+    queue: Arc<PurgeQueue>,
+    /// Idempotency keys of recently handled events
+    recent_events: IdempotencyCache<()>,
+}
+
+/// How to recover from a failed auto-purge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PurgeFallback {
+    /// Purge the affected URLs individually
+    PurgeUrls,
+    /// Pause auto-purge until the given time
+    Pause(DateTime<Utc>),
+}
+
+/// Decide how to recover from a purge error, if it was caused by an exhausted quota.
+///
+/// Falling back to URLs is only possible when the failed purge wasn't already a URL purge.
+pub fn quota_fallback(
+    config: &AutoPurgeConfig,
+    error: &CloudflareError,
+    can_purge_urls: bool,
+    now: DateTime<Utc>,
+) -> Option<PurgeFallback> {
+    if !error.is_purge_quota_exceeded() {
+        return None;
+    }
+
+    match config.on_quota_exhausted {
+        QuotaExhaustedAction::FallbackToUrls if can_purge_urls => Some(PurgeFallback::PurgeUrls),
+        QuotaExhaustedAction::FallbackToUrls => None,
+        QuotaExhaustedAction::DisableAutoPurge => Some(PurgeFallback::Pause(
+            now + Duration::minutes(config.quota_pause_minutes as i64),
+        )),
+    }
 }
 
 impl AutoPurgeHooks {
     /// Create a new hooks manager
     pub fn new(db: PgPool, site_url: String) -> Self {
+        let config = AutoPurgeConfig::new();
+        let queue = Arc::new(PurgeQueue::new(purge_debounce(&config)));
+
         Self {
-            services: None,
-            config: RwLock::new(AutoPurgeConfig::new()),
+            services: SharedServices::default(),
+            config: RwLock::new(config),
             db,
             site_url,
+            paused_until: RwLock::new(None),
+            queue,
+            recent_events: IdempotencyCache::new(PURGE_IDEMPOTENCY_WINDOW),
         }
     }
 
     /// Set the Cloudflare services instance
     pub fn set_services(&mut self, services: Arc<CloudflareServices>) {
-        self.services = Some(services);
+        self.services = Arc::new(RwLock::new(Some(services)));
+    }
+
+    /// Follow the plugin's services, picking up replacements made on reconnect
+    pub fn share_services(&mut self, services: SharedServices) {
+        self.services = services;
+    }
+
+    /// Services as of now
+    async fn services(&self) -> Option<Arc<CloudflareServices>> {
+        self.services.read().await.clone()
     }
 
     /// Update the auto-purge configuration
     pub async fn update_config(&self, config: AutoPurgeConfig) {
+        self.queue.set_debounce(purge_debounce(&config));
         *self.config.write().await = config;
     }
 
@@ -232,6 +387,80 @@ impl AutoPurgeHooks {
         self.config.read().await.clone()
     }
 
+    /// Time until which auto-purge is paused after exhausting the purge quota
+    pub async fn paused_until(&self) -> Option<DateTime<Utc>> {
+        *self.paused_until.read().await
+    }
+
+    /// Resume auto-purge after a quota pause
+    pub async fn resume(&self) {
+        *self.paused_until.write().await = None;
+        info!("Auto-purge resumed");
+    }
+
+    /// The queue that batches purges from content events
+    pub fn queue(&self) -> &Arc<PurgeQueue> {
+        &self.queue
+    }
+
+    /// Start flushing queued purges in the background
+    pub fn start_purge_queue(self: &Arc<Self>) -> JoinHandle<()> {
+        let executor: Arc<dyn PurgeExecutor> = Arc::clone(self) as Arc<dyn PurgeExecutor>;
+        tokio::spawn(Arc::clone(&self.queue).run(executor))
+    }
+
+    /// Flush queued purges before shutdown.
+    ///
+    /// Purges that don't go through within `timeout` are saved and re-queued by
+    /// `restore_pending` on the next start.
+    pub async fn drain(&self, timeout: std::time::Duration) -> CloudflareResult<()> {
+        match self.queue.drain(self, timeout).await {
+            Some(unflushed) => {
+                warn!("Saving {} unflushed purge URLs for the next start", unflushed.urls.len());
+                self.save_pending(&unflushed).await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Re-queue purges saved by `drain`
+    pub async fn restore_pending(&self) -> CloudflareResult<()> {
+        let result: Option<(Option<serde_json::Value>,)> = sqlx::query_as(
+            r#"DELETE FROM cloudflare_settings WHERE key = 'auto_purge_pending' RETURNING value"#,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        if let Some((Some(value),)) = result {
+            if let Ok(pending) = serde_json::from_value::<PendingPurges>(value) {
+                info!("Restoring {} purge URLs saved at shutdown", pending.urls.len());
+                self.queue.restore(pending).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn save_pending(&self, pending: &PendingPurges) -> CloudflareResult<()> {
+        let value = serde_json::to_value(pending)
+            .map_err(|e| CloudflareError::Internal(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO cloudflare_settings (key, value, updated_at)
+            VALUES ('auto_purge_pending', $1, NOW())
+            ON CONFLICT (key) DO UPDATE SET value = $1, updated_at = NOW()
+            "#,
+        )
+        .bind(&value)
+        .execute(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
     /// Load configuration from database
     pub async fn load_config(&self) -> CloudflareResult<()> {
         let result: Option<(Option<serde_json::Value>,)> = sqlx::query_as(
@@ -243,7 +472,7 @@ impl AutoPurgeHooks {
 
         if let Some((Some(value),)) = result {
             if let Ok(config) = serde_json::from_value::<AutoPurgeConfig>(value) {
-                *self.config.write().await = config;
+                self.update_config(config).await;
             }
         }
 
@@ -280,6 +509,21 @@ impl AutoPurgeHooks {
             return Ok(());
         }
 
+        let services = self.services().await;
+        if let Some(services) = &services {
+            if !services.features.is_enabled(features::AUTO_PURGE) {
+                debug!("Auto-purge disabled by feature flag, skipping event: {:?}", event);
+                return Ok(());
+            }
+        }
+
+        if let Some(until) = self.paused_until().await {
+            if Utc::now() < until {
+                debug!("Auto-purge paused until {}, skipping event: {:?}", until, event);
+                return Ok(());
+            }
+        }
+
         // Check if we should handle this content type
         let should_purge = match event.content_type {
             ContentType::Post => config.on_post_update,
@@ -298,101 +542,230 @@ impl AutoPurgeHooks {
             return Ok(());
         }
 
+        if !event.affects_public_content() {
+            debug!(
+                "Skipping auto-purge for non-public {} change ({:?} -> {:?})",
+                event.content_type, event.previous_status, event.status
+            );
+            return Ok(());
+        }
+
+        // Event buses may redeliver; handle each change once. A failed
+        // attempt gives the key back so the redelivery is handled.
+        let key = event.idempotency_key();
+        if !self.recent_events.insert_new(&key, ()) {
+            debug!("Skipping duplicate content change event {}", key);
+            return Ok(());
+        }
+        let result = self.purge_for_event(&event, &config, services).await;
+        if result.is_err() {
+            self.recent_events.take(&key);
+        }
+        result
+    }
+
+    /// Log a deduplicated event and queue the purges it calls for
+    async fn purge_for_event(
+        &self,
+        event: &ContentChangeEvent,
+        config: &AutoPurgeConfig,
+        services: Option<Arc<CloudflareServices>>,
+    ) -> CloudflareResult<()> {
         // Log the event
-        self.log_event(&event).await?;
+        self.log_event(event).await?;
 
-        // Get the services
-        let services = match &self.services {
-            Some(s) => s,
-            None => {
-                warn!("Cloudflare services not configured, skipping auto-purge");
-                return Ok(());
-            }
+        if config.dry_run {
+            let mut urls = self.collect_urls_to_purge(event, config).await;
+            urls.sort();
+            urls.dedup();
+            info!(
+                "Dry run: would purge {} due to {} {}: {:?}",
+                if config.purge_entire_site { "entire site" } else { "URLs" },
+                event.content_type,
+                event.action,
+                urls
+            );
+            return self.log_dry_run(event, config, urls).await;
+        }
+
+        let Some(services) = services else {
+            warn!("Cloudflare services not configured, skipping auto-purge");
+            return Ok(());
         };
 
-        // Apply delay if configured
-        if config.purge_delay_ms > 0 {
-            tokio::time::sleep(tokio::time::Duration::from_millis(config.purge_delay_ms as u64)).await;
+        // Tagged content can be purged precisely by cache tag
+        let mut tags_purged = false;
+        if !config.purge_entire_site {
+            if let Some(id) = &event.content_id {
+                if services.features.is_enabled(features::CACHE_TAG_PURGE) {
+                    match services.cache.purge_content_tags(&event.content_type.to_string(), id).await {
+                        Ok(Some(_)) => {
+                            info!("Purged cache tags for {} {}", event.content_type, id);
+                            tags_purged = true;
+                        }
+                        Ok(None) => {}
+                        Err(e) => warn!("Cache tag purge failed, purging URLs instead: {}", e),
+                    }
+                }
+            }
         }
 
-        // Purge entire site or specific URLs
+        let mut urls_to_purge = self.collect_urls_to_purge(event, config).await;
+        urls_to_purge.sort();
+        urls_to_purge.dedup();
+
+        // The tags covered the content itself; the homepage, archives and
+        // other listings it appears on still go through the queue
+        if tags_purged {
+            let content_urls: Vec<String> = event
+                .url
+                .iter()
+                .flat_map(|url| normalize_purge_url(&self.site_url, url))
+                .collect();
+            urls_to_purge.retain(|url| !content_urls.contains(url));
+        }
+
+        // Purge entire site or specific URLs; the queue batches both until the debounce window passes
         if config.purge_entire_site {
-            info!("Auto-purging entire site cache due to {} {}", event.content_type, event.action);
-            services.cache.purge_all().await?;
+            info!("Queueing entire site purge due to {} {}", event.content_type, event.action);
+            self.queue.enqueue_purge_all(urls_to_purge).await;
         } else {
-            // Collect URLs to purge
-            let mut urls_to_purge = self.collect_urls_to_purge(&event, &config).await;
-
             if urls_to_purge.is_empty() {
                 debug!("No URLs to purge for event");
                 return Ok(());
             }
 
-            // Deduplicate
-            urls_to_purge.sort();
-            urls_to_purge.dedup();
-
             info!(
-                "Auto-purging {} URLs due to {} {}",
+                "Queueing {} URLs for purge due to {} {}",
                 urls_to_purge.len(),
                 event.content_type,
                 event.action
             );
+            self.queue.enqueue(urls_to_purge).await;
+        }
+
+        Ok(())
+    }
 
-            // Cloudflare allows up to 30 URLs per purge request
-            for chunk in urls_to_purge.chunks(30) {
-                services.cache.purge_urls(chunk.to_vec()).await?;
+    /// Apply the configured quota fallback, or return the original error
+    async fn recover_from_purge_error(
+        &self,
+        services: &CloudflareServices,
+        config: &AutoPurgeConfig,
+        error: CloudflareError,
+        fallback_urls: Vec<String>,
+    ) -> CloudflareResult<()> {
+        match quota_fallback(config, &error, !fallback_urls.is_empty(), Utc::now()) {
+            None => Err(error),
+            Some(PurgeFallback::PurgeUrls) => {
+                warn!(
+                    "Purge quota exhausted, falling back to purging {} URLs",
+                    fallback_urls.len()
+                );
+                for chunk in fallback_urls.chunks(MAX_URLS_PER_PURGE) {
+                    services.cache.purge_urls(chunk.to_vec(), None).await?;
+                }
+                Ok(())
+            }
+            Some(PurgeFallback::Pause(until)) => {
+                *self.paused_until.write().await = Some(until);
+                warn!("Purge quota exhausted, auto-purge paused until {}", until);
+                services.notifications.notify_in_background(NotificationEvent::AutoPurgePaused {
+                    paused_until: until,
+                    reason: error.to_string(),
+                });
+                self.log_quota_pause(until, &error).await
             }
         }
+    }
+
+    /// Record an auto-purge pause so it shows up in the purge log
+    async fn log_quota_pause(&self, until: DateTime<Utc>, error: &CloudflareError) -> CloudflareResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cloudflare_cache_events (event_type, details, created_at)
+            VALUES ('auto_purge_paused', $1, NOW())
+            "#,
+        )
+        .bind(serde_json::json!({
+            "reason": error.to_string(),
+            "paused_until": until,
+        }))
+        .execute(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Record what a dry run would have purged
+    async fn log_dry_run(
+        &self,
+        event: &ContentChangeEvent,
+        config: &AutoPurgeConfig,
+        urls: Vec<String>,
+    ) -> CloudflareResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cloudflare_cache_events (event_type, details, created_at)
+            VALUES ('dry_run_purge', $1, NOW())
+            "#,
+        )
+        .bind(serde_json::json!({
+            "content_type": event.content_type,
+            "action": event.action,
+            "content_id": event.content_id,
+            "purge_entire_site": config.purge_entire_site,
+            "urls": urls,
+        }))
+        .execute(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
 
         Ok(())
     }
 
     /// Collect URLs to purge based on the event
     async fn collect_urls_to_purge(&self, event: &ContentChangeEvent, config: &AutoPurgeConfig) -> Vec<String> {
-        let mut urls = Vec::new();
-        let site_url = self.site_url.trim_end_matches('/');
+        let mut paths: Vec<String> = Vec::new();
 
         // Add the content URL if available
         if let Some(url) = &event.url {
-            urls.push(url.clone());
-            // Also add with trailing slash
-            if !url.ends_with('/') {
-                urls.push(format!("{}/", url));
-            }
+            paths.push(url.clone());
         }
 
         // Add related URLs
-        urls.extend(event.related_urls.clone());
+        paths.extend(event.related_urls.clone());
 
         // Add homepage if configured
         if config.always_purge_homepage {
-            urls.push(format!("{}/", site_url));
-            urls.push(site_url.to_string());
+            paths.push("/".to_string());
         }
 
         // Add archive pages if configured
         if config.purge_archives {
             match event.content_type {
                 ContentType::Post => {
-                    urls.push(format!("{}/blog/", site_url));
-                    urls.push(format!("{}/blog", site_url));
-                    urls.push(format!("{}/posts/", site_url));
-                    urls.push(format!("{}/posts", site_url));
-                    // Year/month archives could be added based on post date
+                    paths.push("/blog".to_string());
+                    paths.push("/posts".to_string());
+                    if let Some(published_at) = event.published_at {
+                        let (year, month, day) =
+                            (published_at.year(), published_at.month(), published_at.day());
+                        paths.push(format!("/{}", year));
+                        paths.push(format!("/{}/{:02}", year, month));
+                        paths.push(format!("/{}/{:02}/{:02}", year, month, day));
+                    }
                 }
                 ContentType::Category => {
-                    urls.push(format!("{}/category/", site_url));
+                    paths.push("/category".to_string());
                     if let Some(slug) = &event.slug {
-                        urls.push(format!("{}/category/{}/", site_url, slug));
-                        urls.push(format!("{}/category/{}", site_url, slug));
+                        paths.push(format!("/category/{}", slug));
                     }
                 }
                 ContentType::Tag => {
-                    urls.push(format!("{}/tag/", site_url));
+                    paths.push("/tag".to_string());
                     if let Some(slug) = &event.slug {
-                        urls.push(format!("{}/tag/{}/", site_url, slug));
-                        urls.push(format!("{}/tag/{}", site_url, slug));
+                        paths.push(format!("/tag/{}", slug));
                     }
                 }
                 _ => {}
@@ -401,25 +774,25 @@ impl AutoPurgeHooks {
 
         // Add custom URLs if configured
         if let Some(custom) = &config.custom_purge_urls {
-            for pattern in custom.split(',') {
-                let pattern = pattern.trim();
-                if !pattern.is_empty() {
-                    if pattern.starts_with("http") {
-                        urls.push(pattern.to_string());
-                    } else {
-                        urls.push(format!("{}{}", site_url, pattern));
-                    }
-                }
-            }
+            paths.extend(
+                custom
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(str::to_string),
+            );
         }
 
         // Add sitemap URLs (good practice to purge these)
-        urls.push(format!("{}/sitemap.xml", site_url));
-        urls.push(format!("{}/sitemap_index.xml", site_url));
-        urls.push(format!("{}/feed/", site_url));
-        urls.push(format!("{}/rss/", site_url));
+        paths.push("/sitemap.xml".to_string());
+        paths.push("/sitemap_index.xml".to_string());
+        paths.push("/feed".to_string());
+        paths.push("/rss".to_string());
 
-        urls
+        paths
+            .iter()
+            .flat_map(|path| normalize_purge_url(&self.site_url, path))
+            .collect()
     }
 
     /// Log a purge event to the database
@@ -443,6 +816,32 @@ impl AutoPurgeHooks {
     }
 }
 
+#[async_trait]
+impl PurgeExecutor for AutoPurgeHooks {
+    async fn purge_urls(&self, urls: Vec<String>) -> CloudflareResult<()> {
+        let services = self.services().await.ok_or(CloudflareError::NotConfigured)?;
+        if let Err(e) = services.cache.purge_urls(urls, None).await {
+            let config = self.config.read().await.clone();
+            return self.recover_from_purge_error(&services, &config, e, Vec::new()).await;
+        }
+        Ok(())
+    }
+
+    async fn purge_all(&self, fallback_urls: Vec<String>) -> CloudflareResult<()> {
+        let services = self.services().await.ok_or(CloudflareError::NotConfigured)?;
+        if let Err(e) = services.cache.purge_all(None).await {
+            let config = self.config.read().await.clone();
+            return self.recover_from_purge_error(&services, &config, e, fallback_urls).await;
+        }
+        Ok(())
+    }
+}
+
+/// Debounce window for the purge queue
+fn purge_debounce(config: &AutoPurgeConfig) -> std::time::Duration {
+    std::time::Duration::from_millis(config.purge_delay_ms as u64)
+}
+
 // Convenience functions for creating events
 impl ContentChangeEvent {
     /// Create a post published event
@@ -499,6 +898,8 @@ impl ContentChangeEvent {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{lazy_pool, mock_client, success};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[test]
     fn test_auto_purge_config_defaults() {
@@ -508,6 +909,29 @@ mod tests {
         assert!(config.always_purge_homepage);
     }
 
+    #[test]
+    fn test_event_idempotency_key_dedupes_redeliveries_only() {
+        let event = ContentChangeEvent::post_updated("42", "https://example.com/hello", "Hello");
+        let key = event.idempotency_key();
+        assert!(key.starts_with("post:42:updated:"));
+
+        let hooks_events: IdempotencyCache<()> = IdempotencyCache::new(PURGE_IDEMPOTENCY_WINDOW);
+        assert!(hooks_events.insert_new(&key, ()));
+
+        // A redelivery carries the original timestamp, so it's recognised
+        let redelivered: ContentChangeEvent =
+            serde_json::from_value(serde_json::to_value(&event).unwrap()).unwrap();
+        assert!(!hooks_events.insert_new(&redelivered.idempotency_key(), ()));
+
+        // A later edit of the same post is a new change
+        let mut later = event.clone();
+        later.timestamp = event.timestamp + Duration::seconds(EVENT_KEY_BUCKET_SECS);
+        assert!(hooks_events.insert_new(&later.idempotency_key(), ()));
+
+        let published = ContentChangeEvent { action: EventAction::Published, ..event };
+        assert_ne!(published.idempotency_key(), key);
+    }
+
     #[test]
     fn test_content_change_event_builder() {
         let event = ContentChangeEvent::post_published("123", "https://example.com/post/123", "Test Post");
@@ -516,4 +940,283 @@ mod tests {
         assert_eq!(event.content_id, Some("123".to_string()));
         assert_eq!(event.url, Some("https://example.com/post/123".to_string()));
     }
+
+    #[test]
+    fn test_quota_error_triggers_configured_fallback() {
+        let quota_error = CloudflareError::ApiError {
+            code: crate::error::PURGE_QUOTA_EXCEEDED_CODE,
+            message: "Purge quota exceeded".to_string(),
+            errors: Vec::new(),
+            ray_id: None,
+        };
+        let other_error = CloudflareError::ApiError {
+            code: 1012,
+            message: "Invalid request".to_string(),
+            errors: Vec::new(),
+            ray_id: None,
+        };
+        let now = Utc::now();
+
+        let mut config = AutoPurgeConfig::new();
+        assert_eq!(
+            quota_fallback(&config, &quota_error, true, now),
+            Some(PurgeFallback::PurgeUrls)
+        );
+        assert_eq!(quota_fallback(&config, &quota_error, false, now), None);
+        assert_eq!(quota_fallback(&config, &other_error, true, now), None);
+
+        config.on_quota_exhausted = QuotaExhaustedAction::DisableAutoPurge;
+        config.quota_pause_minutes = 30;
+        assert_eq!(
+            quota_fallback(&config, &quota_error, false, now),
+            Some(PurgeFallback::Pause(now + Duration::minutes(30)))
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_dry_run_logs_urls_without_purging() {
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let services = Arc::new(CloudflareServices::new(mock_client(&server), pool.clone()));
+
+        let mut hooks = AutoPurgeHooks::new(pool.clone(), "https://example.com".to_string());
+        hooks.set_services(services);
+        hooks
+            .update_config(AutoPurgeConfig {
+                dry_run: true,
+                custom_purge_urls: Some("/landing".to_string()),
+                ..AutoPurgeConfig::new()
+            })
+            .await;
+
+        let content_id = uuid::Uuid::new_v4().simple().to_string();
+        let event = ContentChangeEvent::post_updated(&content_id, "https://example.com/hello", "Hello");
+        hooks.handle_event(event).await.unwrap();
+        hooks.queue().flush(&hooks).await.unwrap();
+
+        let (details,): (serde_json::Value,) = sqlx::query_as(
+            "SELECT details FROM cloudflare_cache_events \
+             WHERE event_type = 'dry_run_purge' AND details->>'content_id' = $1",
+        )
+        .bind(&content_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query("DELETE FROM cloudflare_cache_events WHERE details->>'content_id' = $1")
+            .bind(&content_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let urls = details["urls"].as_array().unwrap();
+        assert!(urls.contains(&serde_json::json!("https://example.com/hello")));
+        assert!(urls.contains(&serde_json::json!("https://example.com/landing")));
+        assert_eq!(hooks.queue().pending_urls().await, 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_tag_purge_still_queues_listing_urls() {
+        #[derive(Default)]
+        struct RecordingExecutor {
+            urls: tokio::sync::Mutex<Vec<String>>,
+        }
+
+        #[async_trait]
+        impl PurgeExecutor for RecordingExecutor {
+            async fn purge_urls(&self, urls: Vec<String>) -> CloudflareResult<()> {
+                self.urls.lock().await.extend(urls);
+                Ok(())
+            }
+
+            async fn purge_all(&self, _fallback_urls: Vec<String>) -> CloudflareResult<()> {
+                Ok(())
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path("/zones/zone/purge_cache"))
+            .respond_with(success(serde_json::json!({ "id": "purge-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let services = Arc::new(CloudflareServices::new(mock_client(&server), pool.clone()));
+
+        let content_id = uuid::Uuid::new_v4().simple().to_string();
+        let tags = crate::services::cache::content_cache_tags("post", &content_id, &[]);
+        services.cache.register_content_tags("post", &content_id, &tags).await.unwrap();
+
+        let mut hooks = AutoPurgeHooks::new(pool.clone(), "https://example.com".to_string());
+        hooks.set_services(services);
+        let event = ContentChangeEvent::post_updated(&content_id, "https://example.com/hello", "Hello");
+        hooks.handle_event(event).await.unwrap();
+
+        let executor = RecordingExecutor::default();
+        hooks.queue().flush(&executor).await.unwrap();
+
+        sqlx::query("DELETE FROM cloudflare_cache_tags WHERE content_key = $1")
+            .bind(crate::services::cache::content_cache_key("post", &content_id))
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM cloudflare_cache_events WHERE details->>'content_id' = $1 OR details->'tags' ? $2")
+            .bind(&content_id)
+            .bind(&tags[0])
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let urls = executor.urls.lock().await;
+        assert!(urls.contains(&"https://example.com/".to_string()));
+        assert!(urls.contains(&"https://example.com/sitemap.xml".to_string()));
+        assert!(!urls.iter().any(|url| url.starts_with("https://example.com/hello")));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_shared_services_are_read_when_purging() {
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path("/zones/zone"))
+            .respond_with(success(serde_json::json!({
+                "id": "zone", "name": "example.com", "status": "active", "paused": false,
+                "development_mode": 0, "type": "full", "name_servers": []
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path("/zones/zone/purge_cache"))
+            .respond_with(success(serde_json::json!({ "id": "purge-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let shared = SharedServices::default();
+        let mut hooks = AutoPurgeHooks::new(pool.clone(), "https://example.com".to_string());
+        hooks.share_services(Arc::clone(&shared));
+
+        let url = format!("https://example.com/{}", uuid::Uuid::new_v4().simple());
+        let err = hooks.purge_urls(vec![url.clone()]).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::NotConfigured));
+
+        // Connecting after the hooks were registered
+        *shared.write().await = Some(Arc::new(CloudflareServices::new(mock_client(&server), pool.clone())));
+        hooks.purge_urls(vec![url.clone()]).await.unwrap();
+
+        sqlx::query("DELETE FROM cloudflare_cache_events WHERE details->'urls' ? $1")
+            .bind(&url)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_post_archives_include_dated_urls() {
+        let hooks = AutoPurgeHooks::new(lazy_pool(), "https://example.com".to_string());
+        let published_at = DateTime::parse_from_rfc3339("2024-06-05T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let event = ContentChangeEvent::post_published("1", "https://example.com/hello", "Hello")
+            .with_published_at(published_at);
+
+        let urls = hooks.collect_urls_to_purge(&event, &AutoPurgeConfig::new()).await;
+        for expected in [
+            "https://example.com/2024",
+            "https://example.com/2024/",
+            "https://example.com/2024/06",
+            "https://example.com/2024/06/",
+            "https://example.com/2024/06/05",
+            "https://example.com/2024/06/05/",
+        ] {
+            assert!(urls.contains(&expected.to_string()), "missing {}", expected);
+        }
+
+        let config = AutoPurgeConfig { purge_archives: false, ..AutoPurgeConfig::new() };
+        let urls = hooks.collect_urls_to_purge(&event, &config).await;
+        assert!(!urls.iter().any(|u| u.contains("/2024")));
+    }
+
+    #[test]
+    fn test_only_public_status_transitions_purge() {
+        let draft_saved = ContentChangeEvent::new(ContentType::Post, EventAction::Updated)
+            .with_status_change("draft", "draft");
+        assert!(!draft_saved.affects_public_content());
+
+        let published = ContentChangeEvent::new(ContentType::Post, EventAction::Published)
+            .with_status_change("draft", "publish");
+        assert!(published.affects_public_content());
+
+        let unpublished = ContentChangeEvent::new(ContentType::Post, EventAction::Unpublished)
+            .with_status_change("publish", "draft");
+        assert!(unpublished.affects_public_content());
+
+        let mut hidden_trashed = ContentChangeEvent::new(ContentType::Post, EventAction::Trashed);
+        hidden_trashed.previous_status = Some("private".to_string());
+        assert!(!hidden_trashed.affects_public_content());
+
+        // Without status information events keep purging
+        assert!(ContentChangeEvent::post_updated("1", "https://example.com/a", "A").affects_public_content());
+        assert!(ContentChangeEvent::media_deleted("1").affects_public_content());
+    }
+
+    #[tokio::test]
+    async fn test_draft_to_draft_does_not_queue_purge() {
+        // Nothing listens on this port, so logging the event would fail
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost:1/rustpress_test")
+            .unwrap();
+        let hooks = AutoPurgeHooks::new(pool, "https://example.com".to_string());
+        let event = ContentChangeEvent::post_updated("1", "https://example.com/hello", "Hello")
+            .with_status_change("draft", "draft");
+
+        hooks.handle_event(event).await.unwrap();
+        assert_eq!(hooks.queue().pending_urls().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_event_is_handled_on_redelivery() {
+        // Nothing listens on this port, so logging the event fails
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_millis(100))
+            .connect_lazy("postgres://localhost:1/rustpress_test")
+            .unwrap();
+        let hooks = AutoPurgeHooks::new(pool, "https://example.com".to_string());
+        let event = ContentChangeEvent::post_updated("1", "https://example.com/hello", "Hello");
+
+        assert!(hooks.handle_event(event.clone()).await.is_err());
+        // Not skipped as a duplicate of the failed attempt
+        assert!(hooks.handle_event(event).await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_unflushed_purges_survive_restart() {
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+
+        // Without services every purge fails, so draining has to save the queue
+        let hooks = AutoPurgeHooks::new(pool.clone(), "https://example.com".to_string());
+        hooks.queue().enqueue(vec!["https://example.com/a".to_string()]).await;
+        hooks.drain(std::time::Duration::from_secs(1)).await.unwrap();
+        assert_eq!(hooks.queue().pending_urls().await, 0);
+
+        let restarted = AutoPurgeHooks::new(pool, "https://example.com".to_string());
+        restarted.restore_pending().await.unwrap();
+        assert_eq!(restarted.queue().pending_urls().await, 1);
+
+        // Saved purges are only restored once
+        restarted.restore_pending().await.unwrap();
+        assert_eq!(restarted.queue().pending_urls().await, 1);
+    }
 }
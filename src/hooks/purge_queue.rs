@@ -0,0 +1,279 @@
+//! Debounced purge queue
+//!
+//! Collects URLs from rapid content changes and purges them in a single
+//! batch once the debounce window has passed.
+
+use crate::error::CloudflareResult;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+use tracing::{debug, error, warn};
+
+/// Maximum URLs Cloudflare accepts in a single purge request
+pub const MAX_URLS_PER_PURGE: usize = 30;
+
+/// Performs the purges flushed from a `PurgeQueue`
+#[async_trait]
+pub trait PurgeExecutor: Send + Sync {
+    /// Purge a batch of at most `MAX_URLS_PER_PURGE` URLs
+    async fn purge_urls(&self, urls: Vec<String>) -> CloudflareResult<()>;
+
+    /// Purge the entire site; `fallback_urls` are the URLs queued alongside it
+    async fn purge_all(&self, fallback_urls: Vec<String>) -> CloudflareResult<()>;
+}
+
+/// Purges waiting in a `PurgeQueue`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingPurges {
+    pub urls: BTreeSet<String>,
+    pub purge_all: bool,
+}
+
+impl PendingPurges {
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty() && !self.purge_all
+    }
+}
+
+/// Queue that batches purge requests within a debounce window
+pub struct PurgeQueue {
+    pending: Mutex<PendingPurges>,
+    notify: Notify,
+    debounce_ms: AtomicU64,
+}
+
+impl PurgeQueue {
+    pub fn new(debounce: Duration) -> Self {
+        Self {
+            pending: Mutex::new(PendingPurges::default()),
+            notify: Notify::new(),
+            debounce_ms: AtomicU64::new(debounce.as_millis() as u64),
+        }
+    }
+
+    /// Change how long the queue waits for more URLs before flushing
+    pub fn set_debounce(&self, debounce: Duration) {
+        self.debounce_ms.store(debounce.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms.load(Ordering::Relaxed))
+    }
+
+    /// Add URLs to the next batch
+    pub async fn enqueue(&self, urls: Vec<String>) {
+        if urls.is_empty() {
+            return;
+        }
+        self.pending.lock().await.urls.extend(urls);
+        self.notify.notify_one();
+    }
+
+    /// Replace pending URL batches with a full-site purge.
+    ///
+    /// Queued URLs are kept only as the fallback for a failed full purge.
+    pub async fn enqueue_purge_all(&self, fallback_urls: Vec<String>) {
+        let mut pending = self.pending.lock().await;
+        pending.purge_all = true;
+        pending.urls.extend(fallback_urls);
+        drop(pending);
+        self.notify.notify_one();
+    }
+
+    /// Number of distinct URLs waiting to be purged
+    pub async fn pending_urls(&self) -> usize {
+        self.pending.lock().await.urls.len()
+    }
+
+    /// Put purges back in the queue, e.g. ones saved before a restart
+    pub async fn restore(&self, purges: PendingPurges) {
+        if purges.is_empty() {
+            return;
+        }
+        let mut pending = self.pending.lock().await;
+        pending.purge_all |= purges.purge_all;
+        pending.urls.extend(purges.urls);
+        drop(pending);
+        self.notify.notify_one();
+    }
+
+    /// Purge everything queued so far
+    pub async fn flush(&self, executor: &dyn PurgeExecutor) -> CloudflareResult<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().await);
+        execute(pending, executor).await
+    }
+
+    /// Flush without waiting for the debounce window, giving up after `timeout`.
+    ///
+    /// Returns the purges that may not have gone through so they can be saved.
+    pub async fn drain(&self, executor: &dyn PurgeExecutor, timeout: Duration) -> Option<PendingPurges> {
+        let pending = std::mem::take(&mut *self.pending.lock().await);
+        if pending.is_empty() {
+            return None;
+        }
+
+        match tokio::time::timeout(timeout, execute(pending.clone(), executor)).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => {
+                warn!("Draining purge queue failed: {}", e);
+                Some(pending)
+            }
+            Err(_) => {
+                warn!("Draining purge queue timed out after {:?}", timeout);
+                Some(pending)
+            }
+        }
+    }
+
+    /// Flush the queue whenever URLs arrive, after waiting out the debounce window
+    pub async fn run(self: Arc<Self>, executor: Arc<dyn PurgeExecutor>) {
+        loop {
+            self.notify.notified().await;
+            tokio::time::sleep(self.debounce()).await;
+
+            if let Err(e) = self.flush(executor.as_ref()).await {
+                error!("Queued cache purge failed: {}", e);
+            }
+        }
+    }
+}
+
+async fn execute(pending: PendingPurges, executor: &dyn PurgeExecutor) -> CloudflareResult<()> {
+    let urls: Vec<String> = pending.urls.into_iter().collect();
+
+    if pending.purge_all {
+        debug!("Flushing full-site purge");
+        return executor.purge_all(urls).await;
+    }
+
+    if urls.is_empty() {
+        return Ok(());
+    }
+
+    debug!("Flushing {} queued URLs", urls.len());
+    for chunk in urls.chunks(MAX_URLS_PER_PURGE) {
+        executor.purge_urls(chunk.to_vec()).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingExecutor {
+        url_calls: Mutex<Vec<Vec<String>>>,
+        purge_all_calls: Mutex<usize>,
+    }
+
+    #[async_trait]
+    impl PurgeExecutor for RecordingExecutor {
+        async fn purge_urls(&self, urls: Vec<String>) -> CloudflareResult<()> {
+            self.url_calls.lock().await.push(urls);
+            Ok(())
+        }
+
+        async fn purge_all(&self, _fallback_urls: Vec<String>) -> CloudflareResult<()> {
+            *self.purge_all_calls.lock().await += 1;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rapid_events_flush_as_single_deduplicated_purge() {
+        let queue = Arc::new(PurgeQueue::new(Duration::from_millis(50)));
+        let executor = Arc::new(RecordingExecutor::default());
+        let runner = tokio::spawn(Arc::clone(&queue).run(executor.clone()));
+
+        for i in 0..10 {
+            queue
+                .enqueue(vec![
+                    format!("https://example.com/post/{}", i % 3),
+                    "https://example.com/".to_string(),
+                ])
+                .await;
+        }
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        runner.abort();
+
+        let calls = executor.url_calls.lock().await;
+        assert_eq!(calls.len(), 1);
+        assert_eq!(
+            calls[0],
+            vec![
+                "https://example.com/".to_string(),
+                "https://example.com/post/0".to_string(),
+                "https://example.com/post/1".to_string(),
+                "https://example.com/post/2".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_purge_all_cancels_pending_url_batches() {
+        let queue = PurgeQueue::new(Duration::from_millis(0));
+        let executor = RecordingExecutor::default();
+
+        queue.enqueue(vec!["https://example.com/a".to_string()]).await;
+        queue.enqueue_purge_all(Vec::new()).await;
+        queue.flush(&executor).await.unwrap();
+
+        assert!(executor.url_calls.lock().await.is_empty());
+        assert_eq!(*executor.purge_all_calls.lock().await, 1);
+        assert_eq!(queue.pending_urls().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_flushes_pending_purges_without_waiting() {
+        let queue = Arc::new(PurgeQueue::new(Duration::from_secs(60)));
+        let executor = Arc::new(RecordingExecutor::default());
+        let runner = tokio::spawn(Arc::clone(&queue).run(executor.clone()));
+
+        queue.enqueue(vec!["https://example.com/a".to_string()]).await;
+        let unflushed = queue.drain(executor.as_ref(), Duration::from_secs(1)).await;
+        runner.abort();
+
+        assert_eq!(unflushed, None);
+        assert_eq!(
+            *executor.url_calls.lock().await,
+            vec![vec!["https://example.com/a".to_string()]]
+        );
+        assert_eq!(queue.pending_urls().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_returns_purges_that_time_out() {
+        struct StuckExecutor;
+
+        #[async_trait]
+        impl PurgeExecutor for StuckExecutor {
+            async fn purge_urls(&self, _urls: Vec<String>) -> CloudflareResult<()> {
+                std::future::pending().await
+            }
+
+            async fn purge_all(&self, _fallback_urls: Vec<String>) -> CloudflareResult<()> {
+                std::future::pending().await
+            }
+        }
+
+        let queue = PurgeQueue::new(Duration::from_secs(60));
+        queue.enqueue(vec!["https://example.com/a".to_string()]).await;
+
+        let unflushed = queue
+            .drain(&StuckExecutor, Duration::from_millis(20))
+            .await
+            .unwrap();
+        assert_eq!(unflushed.urls.len(), 1);
+        assert!(!unflushed.purge_all);
+
+        queue.restore(unflushed).await;
+        assert_eq!(queue.pending_urls().await, 1);
+    }
+}
@@ -17,15 +17,25 @@
 //! - **Analytics**: Traffic, performance, and security analytics
 
 pub mod api;
+pub mod circuit_breaker;
 pub mod client;
 pub mod config;
 pub mod error;
+pub mod health;
 pub mod hooks;
+pub mod metrics;
 pub mod middleware;
 pub mod models;
+pub mod plan;
 pub mod services;
+pub mod single_flight;
+pub mod upgrade;
+pub mod utils;
 pub mod workers;
 
+#[cfg(test)]
+mod test_support;
+
 use async_trait::async_trait;
 use axum::Router;
 use rustpress_core::{
@@ -40,12 +50,21 @@ use rustpress_plugins::lifecycle::{
 };
 use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::{info, warn};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
 
 use crate::client::CloudflareClient;
 use crate::config::CloudflareConfig;
-use crate::services::CloudflareServices;
+use crate::health::{HealthSnapshot, HealthTarget, PluginHealth, HEALTH_CHECK_INTERVAL};
+use crate::hooks::AutoPurgeHooks;
+use crate::services::cache::content_cache_tags;
+use crate::services::r2::R2MediaObject;
+use crate::services::security_events::SecurityEventFeed;
+use crate::services::under_attack::{UnderAttackMonitor, UnderAttackThresholds, UNDER_ATTACK_POLL_INTERVAL};
+use crate::services::zone::DevModeTimer;
+use crate::services::{features, CloudflareCredentials, CloudflareServices, SharedServices};
 
 /// Current plugin version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -53,14 +72,112 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Plugin identifier
 pub const PLUGIN_ID: &str = "rustcloudflare";
 
+/// How often old analytics events are cleaned up
+const ANALYTICS_CLEANUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long shutdown waits for queued purges to go out
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Client and services, shared with the health check task so it can
+/// connect once credentials are saved
+#[derive(Clone, Default)]
+struct Connection {
+    config: Arc<RwLock<Option<CloudflareConfig>>>,
+    client: Arc<RwLock<Option<Arc<CloudflareClient>>>>,
+    services: SharedServices,
+    db_pool: Arc<RwLock<Option<PgPool>>>,
+    health: PluginHealth,
+    /// Development mode switch-off, kept here so it survives reconnects
+    dev_mode_timer: Arc<DevModeTimer>,
+    /// Live security events, kept here so subscribers survive reconnects
+    security_feed: SecurityEventFeed,
+}
+
+impl Connection {
+    /// Hand state that outlives any one set of services to new services
+    fn share_state(&self, services: &mut CloudflareServices) {
+        services.health = self.health.clone();
+        services.zone.set_dev_mode_timer(Arc::clone(&self.dev_mode_timer));
+        services.security.set_event_feed(self.security_feed.clone());
+    }
+
+    /// Build, verify and store a client from settings
+    async fn connect_with_settings(&self, settings: serde_json::Value) -> CloudflareResult<()> {
+        let config = CloudflareConfig::from_settings(settings)?;
+
+        let pool = self.db_pool.read().await.clone()
+            .ok_or_else(|| error::CloudflareError::NotConfigured)?;
+
+        // Create the Cloudflare API client
+        let client = Arc::new(CloudflareClient::new(&config)?);
+
+        // Verify connection
+        client.verify_connection().await?;
+
+        // Create services layer
+        let mut services = CloudflareServices::new(Arc::clone(&client), pool);
+        self.share_state(&mut services);
+        if let Err(e) = services.r2.init_s3_client(&config).await {
+            warn!("R2 not initialized: {}", e);
+        }
+        let services = Arc::new(services);
+        if let Err(e) = services.load_feature_flags().await {
+            warn!("Failed to load feature flags: {}", e);
+        }
+        if let Err(e) = services.load_extended_settings().await {
+            warn!("Failed to load the stored settings: {}", e);
+        }
+        // Reschedule any pending switch-off with the new client
+        if let Err(e) = services.zone.resume_development_mode_timer().await {
+            warn!("Failed to resume the development mode timer: {}", e);
+        }
+
+        // Store in plugin state
+        *self.config.write().await = Some(config);
+        *self.client.write().await = Some(client);
+        *self.services.write().await = Some(services);
+        self.health.record_success();
+
+        info!("Cloudflare client initialized from settings");
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HealthTarget for Connection {
+    async fn is_connected(&self) -> bool {
+        self.client.read().await.is_some()
+    }
+
+    async fn stored_credentials(&self) -> CloudflareResult<Option<CloudflareCredentials>> {
+        let services = self.services.read().await.clone()
+            .ok_or(error::CloudflareError::NotConfigured)?;
+        services.settings.get_credentials().await
+    }
+
+    async fn connect(&self, credentials: CloudflareCredentials) -> CloudflareResult<()> {
+        self.connect_with_settings(serde_json::json!({
+            "api_token": credentials.api_token,
+            "account_id": credentials.account_id,
+            "zone_id": credentials.zone_id,
+        }))
+        .await
+    }
+
+    async fn verify(&self) -> CloudflareResult<()> {
+        let client = self.client.read().await.clone()
+            .ok_or(error::CloudflareError::NotConfigured)?;
+        client.verify_connection().await
+    }
+}
+
 /// Main RustCloudflare plugin struct
 pub struct RustCloudflarePlugin {
     info: PluginInfo,
     state: RwLock<PluginState>,
-    config: RwLock<Option<CloudflareConfig>>,
-    client: RwLock<Option<Arc<CloudflareClient>>>,
-    services: RwLock<Option<Arc<CloudflareServices>>>,
-    db_pool: RwLock<Option<PgPool>>,
+    connection: Connection,
+    background_tasks: RwLock<Vec<JoinHandle<()>>>,
+    auto_purge: RwLock<Option<Arc<AutoPurgeHooks>>>,
 }
 
 impl RustCloudflarePlugin {
@@ -91,16 +208,20 @@ impl RustCloudflarePlugin {
                 ],
             },
             state: RwLock::new(PluginState::Inactive),
-            config: RwLock::new(None),
-            client: RwLock::new(None),
-            services: RwLock::new(None),
-            db_pool: RwLock::new(None),
+            connection: Connection::default(),
+            background_tasks: RwLock::new(Vec::new()),
+            auto_purge: RwLock::new(None),
         }
     }
 
     /// Initialize the plugin with a database pool
     pub async fn init_with_pool(&self, pool: PgPool) -> CloudflareResult<()> {
-        *self.db_pool.write().await = Some(pool.clone());
+        *self.connection.db_pool.write().await = Some(pool.clone());
+
+        let applied = upgrade::run_pending(&pool, &upgrade::parse_version(VERSION)?).await?;
+        if !applied.is_empty() {
+            info!("Applied {} pending upgrade steps: {:?}", applied.len(), applied);
+        }
 
         // Try to load configuration from environment
         match CloudflareConfig::from_env() {
@@ -112,23 +233,44 @@ impl RustCloudflarePlugin {
                 client.verify_connection().await?;
 
                 // Create services layer
-                let services = Arc::new(CloudflareServices::new(
-                    Arc::clone(&client),
-                    pool,
-                ));
+                let mut services = CloudflareServices::new(Arc::clone(&client), pool);
+                self.connection.share_state(&mut services);
+                if let Err(e) = services.r2.init_s3_client(&config).await {
+                    warn!("R2 not initialized: {}", e);
+                }
+                let services = Arc::new(services);
+
+                if let Err(e) = services.load_feature_flags().await {
+                    warn!("Failed to load feature flags: {}", e);
+                }
+                if let Err(e) = services.zone.resume_development_mode_timer().await {
+                    warn!("Failed to resume the development mode timer: {}", e);
+                }
+                if let Err(e) = services.load_extended_settings().await {
+                    warn!("Failed to load the stored settings: {}", e);
+                }
 
                 // Store in plugin state
-                *self.config.write().await = Some(config);
-                *self.client.write().await = Some(client);
-                *self.services.write().await = Some(services);
+                *self.connection.config.write().await = Some(config);
+                *self.connection.client.write().await = Some(client);
+                *self.connection.services.write().await = Some(services);
+                self.connection.health.record_success();
 
                 info!("Cloudflare client initialized from environment");
             }
             Err(e) => {
                 warn!("Cloudflare not configured from environment: {}", e);
                 // Create unconfigured services for settings management
-                let services = Arc::new(CloudflareServices::new_unconfigured(pool));
-                *self.services.write().await = Some(services);
+                let mut services = CloudflareServices::new_unconfigured(pool);
+                self.connection.share_state(&mut services);
+                let services = Arc::new(services);
+                if let Err(e) = services.load_feature_flags().await {
+                    warn!("Failed to load feature flags: {}", e);
+                }
+                if let Err(e) = services.load_extended_settings().await {
+                    warn!("Failed to load the stored settings: {}", e);
+                }
+                *self.connection.services.write().await = Some(services);
             }
         }
 
@@ -137,57 +279,214 @@ impl RustCloudflarePlugin {
 
     /// Initialize client with stored settings
     pub async fn init_with_settings(&self, settings: serde_json::Value) -> CloudflareResult<()> {
-        let config = CloudflareConfig::from_settings(settings)?;
-
-        let pool = self.db_pool.read().await.clone()
-            .ok_or_else(|| error::CloudflareError::NotConfigured)?;
-
-        // Create the Cloudflare API client
-        let client = Arc::new(CloudflareClient::new(&config)?);
-
-        // Verify connection
-        client.verify_connection().await?;
-
-        // Create services layer
-        let services = Arc::new(CloudflareServices::new(
-            Arc::clone(&client),
-            pool,
-        ));
-
-        // Store in plugin state
-        *self.config.write().await = Some(config);
-        *self.client.write().await = Some(client);
-        *self.services.write().await = Some(services);
-
-        info!("Cloudflare client initialized from settings");
-        Ok(())
+        self.connection.connect_with_settings(settings).await
     }
 
     /// Get the Cloudflare client
     pub async fn client(&self) -> Option<Arc<CloudflareClient>> {
-        self.client.read().await.clone()
+        self.connection.client.read().await.clone()
     }
 
     /// Get the services layer
     pub async fn services(&self) -> Option<Arc<CloudflareServices>> {
-        self.services.read().await.clone()
+        self.connection.services.read().await.clone()
     }
 
     /// Get the current configuration
     pub async fn config(&self) -> Option<CloudflareConfig> {
-        self.config.read().await.clone()
+        self.connection.config.read().await.clone()
+    }
+
+    /// Get the auto-purge hooks, if enabled
+    pub async fn auto_purge(&self) -> Option<Arc<AutoPurgeHooks>> {
+        self.auto_purge.read().await.clone()
+    }
+
+    /// Set up auto-purge hooks for the site and start their purge queue
+    pub async fn enable_auto_purge(&self, site_url: String) -> CloudflareResult<Arc<AutoPurgeHooks>> {
+        let pool = self.connection.db_pool.read().await.clone()
+            .ok_or(error::CloudflareError::NotConfigured)?;
+        let services = self.services().await
+            .ok_or(error::CloudflareError::NotConfigured)?;
+
+        let config = services.settings.get_auto_purge_config().await?;
+
+        let mut hooks = AutoPurgeHooks::new(pool, site_url);
+        hooks.share_services(Arc::clone(&self.connection.services));
+        hooks.update_config(config).await;
+
+        if let Err(e) = hooks.restore_pending().await {
+            warn!("Failed to restore purges saved at shutdown: {}", e);
+        }
+
+        let hooks = Arc::new(hooks);
+        let queue = hooks.start_purge_queue();
+        self.background_tasks.write().await.push(queue);
+        *self.auto_purge.write().await = Some(Arc::clone(&hooks));
+
+        info!("Auto-purge hooks enabled");
+        Ok(hooks)
+    }
+
+    /// Handle a content render: record the cache tags the response carries.
+    ///
+    /// Returns the `Cache-Tag` header value to add to the response, or `None`
+    /// when purging by cache tag is switched off. Cloudflare strips the header
+    /// before the response reaches visitors.
+    pub async fn on_content_rendered(
+        &self,
+        content_type: &str,
+        content_id: &str,
+        extra_tags: &[String],
+    ) -> Option<String> {
+        let services = self.services().await?;
+        if !services.features.is_enabled(features::CACHE_TAG_PURGE) {
+            return None;
+        }
+
+        // Send the tags even if recording them fails; content without
+        // recorded tags is purged by URL instead
+        let tags = content_cache_tags(content_type, content_id, extra_tags);
+        if let Err(e) = services.cache.register_content_tags(content_type, content_id, &tags).await {
+            warn!("Failed to record cache tags for {} {}: {}", content_type, content_id, e);
+        }
+        Some(tags.join(","))
+    }
+
+    /// Handle a media library upload: copy the file to R2 when R2 is
+    /// configured and the media offload flag is on.
+    ///
+    /// Returns where the file was offloaded to; `None` means keep serving the
+    /// local copy.
+    pub async fn on_media_uploaded(&self, attachment_id: &str, local_path: &std::path::Path) -> Option<R2MediaObject> {
+        let services = self.services().await?;
+        services.r2.offload_attachment(attachment_id, local_path).await
+    }
+
+    /// Rewrite an attachment's URL to its R2 public URL once it has been
+    /// offloaded; other attachments keep `url`
+    pub async fn rewrite_attachment_url(&self, attachment_id: &str, url: &str) -> String {
+        match self.services().await {
+            Some(services) => services.r2.attachment_url(attachment_id, url).await,
+            None => url.to_string(),
+        }
+    }
+
+    /// Handle a media library deletion: remove the attachment's R2 copy.
+    ///
+    /// Runs even with the offload flag off, so copies made earlier are cleaned up.
+    pub async fn on_media_deleted(&self, attachment_id: &str) {
+        let Some(services) = self.services().await else {
+            return;
+        };
+        if !services.r2.media_offload_enabled() {
+            return;
+        }
+        if let Err(e) = services.r2.remove_attachment(attachment_id).await {
+            error!("Failed to remove R2 copy of attachment {}: {}", attachment_id, e);
+        }
     }
 
     /// Get the API router for this plugin
     /// This can be mounted at /api/plugins/rustcloudflare
     pub async fn api_router(&self) -> Option<Router> {
-        let services = self.services.read().await.clone()?;
+        let services = self.connection.services.read().await.clone()?;
         Some(api::create_router(services))
     }
 
     /// Check if the plugin is configured
     pub async fn is_configured(&self) -> bool {
-        self.config.read().await.is_some()
+        self.connection.config.read().await.is_some()
+    }
+
+    /// Current connection health, updated by the health check task
+    pub fn health(&self) -> HealthSnapshot {
+        self.connection.health.snapshot()
+    }
+
+    /// Start periodic maintenance tasks
+    pub async fn start_background_tasks(&self) {
+        let connection = self.connection.clone();
+        let health_check = tokio::spawn(async move {
+            let mut delay = HEALTH_CHECK_INTERVAL;
+            loop {
+                tokio::time::sleep(delay).await;
+                delay = health::check_health(&connection, &connection.health).await;
+            }
+        });
+        self.background_tasks.write().await.push(health_check);
+
+        // Reconnecting replaces the services, so each tick uses the current ones
+        let shared = Arc::clone(&self.connection.services);
+        let under_attack = tokio::spawn(async move {
+            let mut monitor: Option<UnderAttackMonitor> = None;
+            let mut interval = tokio::time::interval(UNDER_ATTACK_POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(services) = shared.read().await.clone() else {
+                    continue;
+                };
+                let monitor = match &mut monitor {
+                    Some(monitor) => monitor,
+                    None => match services.security.load_under_attack_state().await {
+                        Ok(state) => monitor.insert(state),
+                        Err(e) => {
+                            warn!("Failed to load Under Attack auto-trigger state: {}", e);
+                            continue;
+                        }
+                    },
+                };
+                let settings = match services.settings.get_extended_settings().await {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        warn!("Failed to load Under Attack auto-trigger settings: {}", e);
+                        continue;
+                    }
+                };
+                // Turning the auto-trigger off hands Under Attack mode back to the admin
+                if !settings.under_attack_auto_enabled || !services.features.is_enabled(features::UNDER_ATTACK_AUTO) {
+                    services.security.release_under_attack(monitor).await;
+                    continue;
+                }
+                let thresholds = UnderAttackThresholds::from(&settings);
+                if let Err(e) = services.security.check_threat_rate(monitor, &thresholds).await {
+                    warn!("Under Attack auto-trigger check failed: {}", e);
+                }
+            }
+        });
+
+        let shared = Arc::clone(&self.connection.services);
+        let cleanup = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ANALYTICS_CLEANUP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(services) = shared.read().await.clone() else {
+                    continue;
+                };
+                if let Err(e) = services.analytics.cleanup_old_events().await {
+                    error!("Analytics retention cleanup failed: {}", e);
+                }
+            }
+        });
+
+        self.background_tasks.write().await.extend([under_attack, cleanup]);
+        info!("Background tasks started");
+    }
+
+    /// Flush queued purges, saving any that can't be sent in time
+    pub async fn drain_pending_work(&self) {
+        if let Some(hooks) = self.auto_purge().await {
+            if let Err(e) = hooks.drain(SHUTDOWN_DRAIN_TIMEOUT).await {
+                error!("Failed to drain queued purges: {}", e);
+            }
+        }
+    }
+
+    /// Stop all periodic maintenance tasks
+    pub async fn stop_background_tasks(&self) {
+        for task in self.background_tasks.write().await.drain(..) {
+            task.abort();
+        }
     }
 }
 
@@ -208,7 +507,7 @@ impl Plugin for RustCloudflarePlugin {
 
         *self.state.write().await = PluginState::Activating;
 
-        // Note: Database migrations should be run via the migration system
+        // Note: Database migrations are applied by init_with_pool and on_upgrade
         // API routes are exposed via api_router() method
 
         *self.state.write().await = PluginState::Active;
@@ -223,9 +522,12 @@ impl Plugin for RustCloudflarePlugin {
         *self.state.write().await = PluginState::Deactivating;
 
         // Cleanup resources
-        *self.client.write().await = None;
-        *self.services.write().await = None;
-        *self.config.write().await = None;
+        self.drain_pending_work().await;
+        self.stop_background_tasks().await;
+        *self.auto_purge.write().await = None;
+        *self.connection.client.write().await = None;
+        *self.connection.services.write().await = None;
+        *self.connection.config.write().await = None;
 
         *self.state.write().await = PluginState::Inactive;
         info!("RustCloudflare plugin deactivated");
@@ -235,11 +537,14 @@ impl Plugin for RustCloudflarePlugin {
 
     async fn on_startup(&self, _ctx: &AppContext) -> Result<()> {
         info!("RustCloudflare plugin starting up");
+        self.start_background_tasks().await;
         Ok(())
     }
 
     async fn on_shutdown(&self, _ctx: &AppContext) -> Result<()> {
         info!("RustCloudflare plugin shutting down");
+        self.drain_pending_work().await;
+        self.stop_background_tasks().await;
         Ok(())
     }
 
@@ -292,6 +597,16 @@ impl LifecycleHook for RustCloudflarePlugin {
             "RustCloudflare lifecycle: upgrading from {} to {}",
             context.from_version, context.to_version
         );
+
+        let Some(pool) = self.connection.db_pool.read().await.clone() else {
+            warn!("No database pool, skipping upgrade steps");
+            return Ok(());
+        };
+        let from = upgrade::parse_version(&context.from_version.to_string()).map_err(hook_error)?;
+        let to = upgrade::parse_version(&context.to_version.to_string()).map_err(hook_error)?;
+
+        let applied = upgrade::run_upgrade(&pool, &from, &to).await.map_err(hook_error)?;
+        info!("Applied {} upgrade steps: {:?}", applied.len(), applied);
         Ok(())
     }
 
@@ -320,6 +635,11 @@ impl LifecycleHook for RustCloudflarePlugin {
     }
 }
 
+/// Report a failed lifecycle step to the plugin host
+fn hook_error(error: error::CloudflareError) -> HookError {
+    HookError(error.to_string())
+}
+
 /// Plugin entry point - called by RustPress plugin loader
 #[no_mangle]
 #[allow(improper_ctypes_definitions)]
@@ -0,0 +1,171 @@
+//! Prometheus metrics
+//!
+//! Lightweight counters for Cloudflare API calls and cache purges, shared
+//! between clones of a client and rendered in the Prometheus text format by
+//! the `/metrics` endpoint. Labels are limited to endpoint categories so the
+//! number of series stays small.
+
+use crate::circuit_breaker::{CircuitSnapshot, CircuitState, ENDPOINT_CATEGORIES};
+use crate::error::{CloudflareError, CloudflareResult};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Content type of the Prometheus text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+const METRIC_PREFIX: &str = "rustpress_cloudflare";
+
+#[derive(Debug, Default)]
+struct Counters {
+    api_calls: [AtomicU64; ENDPOINT_CATEGORIES.len()],
+    api_errors: [AtomicU64; ENDPOINT_CATEGORIES.len()],
+    rate_limit_hits: AtomicU64,
+    purges: AtomicU64,
+    purge_failures: AtomicU64,
+}
+
+/// Plugin metrics, shared between clones of a client
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    counters: Arc<Counters>,
+}
+
+impl Metrics {
+    /// Count a finished API call in `category`, passing the result through
+    pub fn record_call<T>(&self, category: &str, result: CloudflareResult<T>) -> CloudflareResult<T> {
+        if let Some(index) = ENDPOINT_CATEGORIES.iter().position(|c| *c == category) {
+            self.counters.api_calls[index].fetch_add(1, Ordering::Relaxed);
+            if result.is_err() {
+                self.counters.api_errors[index].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        if matches!(result, Err(CloudflareError::RateLimitExceeded)) {
+            self.counters.rate_limit_hits.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Count a cache purge, passing the result through
+    pub fn record_purge<T>(&self, result: CloudflareResult<T>) -> CloudflareResult<T> {
+        self.counters.purges.fetch_add(1, Ordering::Relaxed);
+        if result.is_err() {
+            self.counters.purge_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Purges attempted so far
+    pub fn purges(&self) -> u64 {
+        self.counters.purges.load(Ordering::Relaxed)
+    }
+
+    /// Render the counters and circuit states in the Prometheus text format
+    pub fn render(&self, circuits: &BTreeMap<String, CircuitSnapshot>) -> String {
+        let counters = &self.counters;
+        let mut out = String::new();
+
+        let per_category = |out: &mut String, name: &str, help: &str, values: &[AtomicU64]| {
+            header(out, name, help, "counter");
+            for (category, value) in ENDPOINT_CATEGORIES.iter().zip(values) {
+                let _ = writeln!(
+                    out,
+                    "{}_{}{{category=\"{}\"}} {}",
+                    METRIC_PREFIX,
+                    name,
+                    category,
+                    value.load(Ordering::Relaxed)
+                );
+            }
+        };
+        per_category(&mut out, "api_calls_total", "Cloudflare API calls by endpoint category", &counters.api_calls);
+        per_category(&mut out, "api_errors_total", "Failed Cloudflare API calls by endpoint category", &counters.api_errors);
+
+        for (name, help, value) in [
+            ("rate_limit_hits_total", "Cloudflare API calls that ended rate limited", &counters.rate_limit_hits),
+            ("purges_total", "Cache purges attempted", &counters.purges),
+            ("purge_failures_total", "Cache purges that failed", &counters.purge_failures),
+        ] {
+            header(&mut out, name, help, "counter");
+            let _ = writeln!(out, "{}_{} {}", METRIC_PREFIX, name, value.load(Ordering::Relaxed));
+        }
+
+        header(&mut out, "circuit_state", "Circuit breaker state: 0 closed, 1 half-open, 2 open", "gauge");
+        for category in ENDPOINT_CATEGORIES {
+            let state = circuits.get(*category).map(|c| c.state).unwrap_or(CircuitState::Closed);
+            let value = match state {
+                CircuitState::Closed => 0,
+                CircuitState::HalfOpen => 1,
+                CircuitState::Open => 2,
+            };
+            let _ = writeln!(out, "{}_circuit_state{{category=\"{}\"}} {}", METRIC_PREFIX, category, value);
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {}_{} {}", METRIC_PREFIX, name, help);
+    let _ = writeln!(out, "# TYPE {}_{} {}", METRIC_PREFIX, name, kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse exposition text into `series -> value`, checking every sample
+    /// line is preceded by HELP and TYPE lines for its metric
+    fn parse_exposition(text: &str) -> BTreeMap<String, f64> {
+        let mut typed = Vec::new();
+        let mut samples = BTreeMap::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let mut parts = rest.split(' ');
+                typed.push(parts.next().unwrap().to_string());
+                assert!(matches!(parts.next(), Some("counter" | "gauge")), "{}", line);
+            } else if !line.starts_with("# HELP ") {
+                let (series, value) = line.rsplit_once(' ').unwrap_or_else(|| panic!("bad sample: {}", line));
+                let name = series.split('{').next().unwrap();
+                assert!(typed.iter().any(|t| t == name), "no TYPE for {}", name);
+                assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'), "{}", name);
+                samples.insert(series.to_string(), value.parse().unwrap());
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_render_counts_calls_purges_and_circuits() {
+        let metrics = Metrics::default();
+        metrics.record_call("cache", Ok(())).unwrap();
+        let _ = metrics.record_call::<()>("cache", Err(CloudflareError::RateLimitExceeded));
+        metrics.record_call("dns", Ok(())).unwrap();
+        metrics.record_purge(Ok(())).unwrap();
+        let _ = metrics.record_purge::<()>(Err(CloudflareError::Timeout("slow".to_string())));
+
+        let circuits = BTreeMap::from([(
+            "dns".to_string(),
+            CircuitSnapshot { state: CircuitState::Open, consecutive_failures: 5, retry_in_secs: Some(10) },
+        )]);
+        let samples = parse_exposition(&metrics.render(&circuits));
+
+        assert_eq!(samples[r#"rustpress_cloudflare_api_calls_total{category="cache"}"#], 2.0);
+        assert_eq!(samples[r#"rustpress_cloudflare_api_errors_total{category="cache"}"#], 1.0);
+        assert_eq!(samples[r#"rustpress_cloudflare_api_calls_total{category="dns"}"#], 1.0);
+        assert_eq!(samples[r#"rustpress_cloudflare_api_calls_total{category="r2"}"#], 0.0);
+        assert_eq!(samples["rustpress_cloudflare_rate_limit_hits_total"], 1.0);
+        assert_eq!(samples["rustpress_cloudflare_purges_total"], 2.0);
+        assert_eq!(samples["rustpress_cloudflare_purge_failures_total"], 1.0);
+        assert_eq!(samples[r#"rustpress_cloudflare_circuit_state{category="dns"}"#], 2.0);
+        assert_eq!(samples[r#"rustpress_cloudflare_circuit_state{category="cache"}"#], 0.0);
+    }
+
+    #[test]
+    fn test_clones_share_counters() {
+        let metrics = Metrics::default();
+        metrics.clone().record_purge(Ok(())).unwrap();
+        assert_eq!(metrics.purges(), 1);
+    }
+}
@@ -1,10 +1,17 @@
 //! Middleware for RustCloudflare
 
+pub mod access;
+
+pub use access::{AccessIdentity, AccessJwtVerifier, ACCESS_JWT_HEADER};
+
+use crate::error::CloudflareError;
 use axum::{
     body::Body,
+    extract::State,
     http::{Request, Response},
     middleware::Next,
 };
+use std::sync::Arc;
 
 /// Add Cloudflare-specific headers to responses
 pub async fn cloudflare_headers(
@@ -25,3 +32,61 @@ pub async fn cache_control(
     // Add cache control headers based on route
     response
 }
+
+/// Only let through requests carrying a valid Cloudflare Access token,
+/// adding the [`AccessIdentity`] it asserts to the request's extensions.
+///
+/// Missing or invalid tokens get a 401. Apply it with
+/// `axum::middleware::from_fn_with_state(verifier, require_access_jwt)`.
+pub async fn require_access_jwt(
+    State(verifier): State<Arc<AccessJwtVerifier>>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Result<Response<Body>, CloudflareError> {
+    let token = request
+        .headers()
+        .get(ACCESS_JWT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| CloudflareError::AuthenticationError("Missing Cloudflare Access token".to_string()))?;
+
+    let identity = verifier.verify(token).await?;
+    request.extensions_mut().insert(identity);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::access::tests::{certs_server, sign, TEST_AUDIENCE};
+    use super::*;
+    use axum::{http::StatusCode, routing::get, Extension, Router};
+    use tower::Service;
+
+    #[tokio::test]
+    async fn test_require_access_jwt() {
+        let server = certs_server().await;
+        let verifier = Arc::new(AccessJwtVerifier::new(&server.uri(), TEST_AUDIENCE));
+        let mut app = Router::new()
+            .route("/", get(|Extension(identity): Extension<AccessIdentity>| async move { identity.email.unwrap() }))
+            .layer(axum::middleware::from_fn_with_state(verifier, require_access_jwt));
+
+        let request = |token: Option<String>| {
+            let mut builder = Request::builder().uri("/");
+            if let Some(token) = token {
+                builder = builder.header(ACCESS_JWT_HEADER, token);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().call(request(Some(sign(&server.uri(), TEST_AUDIENCE, 300)))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"admin@example.com");
+
+        let response = app.clone().call(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let expired = sign(&server.uri(), TEST_AUDIENCE, -3600);
+        let response = app.call(request(Some(expired))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
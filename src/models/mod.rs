@@ -42,6 +42,77 @@ pub struct ResultInfo {
     pub total_pages: i32,
 }
 
+/// Items per page when the caller doesn't say
+pub const DEFAULT_PER_PAGE: u32 = 20;
+/// Most items a single admin API page returns
+pub const MAX_PER_PAGE: u32 = 100;
+
+/// Which page of a list to return, 1-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self { page: 1, per_page: DEFAULT_PER_PAGE }
+    }
+}
+
+impl Pagination {
+    /// Build from raw query values, defaulting missing ones and clamping
+    /// `page` to at least 1 and `per_page` to 1..=`MAX_PER_PAGE`
+    pub fn new(page: Option<i64>, per_page: Option<i64>) -> Self {
+        Self {
+            page: page.unwrap_or(1).clamp(1, u32::MAX as i64) as u32,
+            per_page: per_page
+                .unwrap_or(DEFAULT_PER_PAGE as i64)
+                .clamp(1, MAX_PER_PAGE as i64) as u32,
+        }
+    }
+
+    /// Number of items before this page
+    pub fn offset(&self) -> usize {
+        (self.page as usize - 1).saturating_mul(self.per_page as usize)
+    }
+}
+
+/// One page of a list, as returned by the admin API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: usize,
+    pub total_pages: u32,
+}
+
+impl<T> Page<T> {
+    /// Wrap a page already fetched from upstream, out of `total` items
+    pub fn new(items: Vec<T>, pagination: Pagination, total: usize) -> Self {
+        let total_pages = total.div_ceil(pagination.per_page as usize).min(u32::MAX as usize) as u32;
+        Self {
+            items,
+            page: pagination.page,
+            per_page: pagination.per_page,
+            total,
+            total_pages,
+        }
+    }
+
+    /// Cut the requested page out of a full list
+    pub fn from_vec(all: Vec<T>, pagination: Pagination) -> Self {
+        let total = all.len();
+        let items = all
+            .into_iter()
+            .skip(pagination.offset())
+            .take(pagination.per_page as usize)
+            .collect();
+        Self::new(items, pagination, total)
+    }
+}
+
 /// Delete response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeleteResponse {
@@ -52,6 +123,9 @@ pub struct DeleteResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PurgeResponse {
     pub id: String,
+    /// URLs left out of the purge because they aren't in the zone
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_urls: Vec<String>,
 }
 
 // ============================================================================
@@ -88,6 +162,16 @@ pub struct Plan {
     pub frequency: String,
     pub is_subscribed: bool,
     pub can_subscribe: bool,
+    /// `free`, `pro`, `business` or `enterprise`
+    pub legacy_id: Option<String>,
+}
+
+impl Plan {
+    /// Whether this is the Free plan
+    pub fn is_free(&self) -> bool {
+        self.legacy_id.as_deref() == Some("free")
+            || (self.legacy_id.is_none() && self.name.to_lowercase().starts_with("free"))
+    }
 }
 
 /// Zone setting
@@ -99,6 +183,250 @@ pub struct ZoneSetting {
     pub modified_on: Option<DateTime<Utc>>,
 }
 
+/// Commonly used zone settings in typed form; `None` when the zone doesn't report one
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NormalizedZoneSettings {
+    pub always_online: Option<bool>,
+    pub always_use_https: Option<bool>,
+    pub automatic_https_rewrites: Option<bool>,
+    pub brotli: Option<bool>,
+    pub browser_cache_ttl: Option<i64>,
+    pub browser_check: Option<bool>,
+    pub cache_level: Option<String>,
+    pub challenge_ttl: Option<i64>,
+    pub development_mode: Option<bool>,
+    pub early_hints: Option<bool>,
+    pub http3: Option<bool>,
+    pub ipv6: Option<bool>,
+    pub min_tls_version: Option<String>,
+    pub mirage: Option<bool>,
+    pub opportunistic_encryption: Option<bool>,
+    pub polish: Option<String>,
+    pub rocket_loader: Option<bool>,
+    pub security_level: Option<String>,
+    pub ssl: Option<String>,
+    pub webp: Option<bool>,
+    pub websockets: Option<bool>,
+    pub zero_rtt: Option<bool>,
+}
+
+impl NormalizedZoneSettings {
+    /// Pick the known settings out of a zone's settings list
+    pub fn from_settings(settings: &[ZoneSetting]) -> Self {
+        let value = |id: &str| settings.iter().find(|s| s.id == id).map(|s| &s.value);
+        let on_off = |id: &str| value(id).and_then(|v| v.as_str()).map(|v| v == "on");
+        let string = |id: &str| value(id).and_then(|v| v.as_str()).map(str::to_string);
+        let number = |id: &str| value(id).and_then(|v| v.as_i64());
+
+        Self {
+            always_online: on_off("always_online"),
+            always_use_https: on_off("always_use_https"),
+            automatic_https_rewrites: on_off("automatic_https_rewrites"),
+            brotli: on_off("brotli"),
+            browser_cache_ttl: number("browser_cache_ttl"),
+            browser_check: on_off("browser_check"),
+            cache_level: string("cache_level"),
+            challenge_ttl: number("challenge_ttl"),
+            development_mode: on_off("development_mode"),
+            early_hints: on_off("early_hints"),
+            http3: on_off("http3"),
+            ipv6: on_off("ipv6"),
+            min_tls_version: string("min_tls_version"),
+            mirage: on_off("mirage"),
+            opportunistic_encryption: on_off("opportunistic_encryption"),
+            polish: string("polish"),
+            rocket_loader: on_off("rocket_loader"),
+            security_level: string("security_level"),
+            ssl: string("ssl"),
+            webp: on_off("webp"),
+            websockets: on_off("websockets"),
+            zero_rtt: on_off("0rtt"),
+        }
+    }
+}
+
+// ============================================================================
+// API Token Types
+// ============================================================================
+
+/// Scopes the plugin needs, with the permission groups that grant each one
+pub const REQUIRED_TOKEN_SCOPES: &[(&str, &[&str])] = &[
+    ("zone:edit", &["Zone Write", "Zone Settings Write"]),
+    ("cache_purge", &["Cache Purge"]),
+    ("dns:edit", &["DNS Write"]),
+];
+
+/// Result of `/user/tokens/verify`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenVerification {
+    pub id: String,
+    pub status: String,
+    pub not_before: Option<DateTime<Utc>>,
+    pub expires_on: Option<DateTime<Utc>>,
+}
+
+/// An API token and the policies attached to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub status: String,
+    #[serde(default)]
+    pub policies: Vec<TokenPolicy>,
+}
+
+/// A policy granting (or denying) permission groups on a set of resources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenPolicy {
+    pub id: Option<String>,
+    pub effect: String,
+    #[serde(default)]
+    pub permission_groups: Vec<PermissionGroup>,
+    #[serde(default)]
+    pub resources: serde_json::Value,
+}
+
+/// A named set of permissions, e.g. "DNS Write"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionGroup {
+    pub id: String,
+    pub name: String,
+}
+
+/// Permission groups the admin UI explains by ID, as (id, resource, scope)
+pub const WELL_KNOWN_PERMISSION_GROUPS: &[(&str, &str, PermissionScope)] = &[
+    ("c8fed203ed3043cba015a93ad1616f1f", "Zone", PermissionScope::Read),
+    ("e6d2666161e84845a636613608cee8d5", "Zone", PermissionScope::Write),
+    ("517b21aee92c4d89936c976ba6e4be55", "Zone Settings", PermissionScope::Read),
+    ("3030687196b94b638145a3953da2b699", "Zone Settings", PermissionScope::Write),
+    ("82e64a83756745bbbb1c9c2701bf816b", "DNS", PermissionScope::Read),
+    ("4755a26eedb94da69e1066d98aa820be", "DNS", PermissionScope::Write),
+    ("e17beae8b8cb423a99b1730f21238bed", "Cache", PermissionScope::Purge),
+    ("c03055bc037c4ea9afb9a9f104b7b721", "SSL and Certificates", PermissionScope::Write),
+    ("43137f8d07884d3198dc0ee77ca6e79b", "Firewall Services", PermissionScope::Write),
+    ("e086da7e2179491d91ee5f35b3ca210a", "Workers Scripts", PermissionScope::Write),
+    ("f7f0eda5697f475c90846e879bab8666", "Workers KV Storage", PermissionScope::Write),
+];
+
+/// What a permission group allows on its resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionScope {
+    Read,
+    Write,
+    Purge,
+    /// A group whose name doesn't end in a scope we recognise
+    Other,
+}
+
+impl PermissionScope {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Read => "Read",
+            Self::Write => "Write",
+            Self::Purge => "Purge",
+            Self::Other => "",
+        }
+    }
+}
+
+/// A permission group resolved into what it grants access to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenPermission {
+    /// Permission group ID
+    pub id: String,
+    /// Friendly name, e.g. "DNS Write"
+    pub name: String,
+    /// What the permission covers, e.g. "DNS"
+    pub resource: String,
+    pub scope: PermissionScope,
+}
+
+impl PermissionGroup {
+    /// Resolve the group by its well-known ID, falling back to splitting
+    /// the name Cloudflare sent (e.g. "Access: Apps and Policies Edit")
+    pub fn permission(&self) -> TokenPermission {
+        let (resource, scope) = match WELL_KNOWN_PERMISSION_GROUPS.iter().find(|(id, _, _)| *id == self.id) {
+            Some((_, resource, scope)) => (resource.to_string(), *scope),
+            None => {
+                let (resource, last) = self.name.rsplit_once(' ').unwrap_or((&self.name, ""));
+                match last {
+                    "Read" => (resource.to_string(), PermissionScope::Read),
+                    "Write" | "Edit" => (resource.to_string(), PermissionScope::Write),
+                    "Purge" => (resource.to_string(), PermissionScope::Purge),
+                    _ => (self.name.clone(), PermissionScope::Other),
+                }
+            }
+        };
+
+        let name = match scope {
+            PermissionScope::Other => resource.clone(),
+            scope => format!("{} {}", resource, scope.label()),
+        };
+        TokenPermission { id: self.id.clone(), name, resource, scope }
+    }
+}
+
+impl ApiToken {
+    /// Permissions granted by the token's allowed policies
+    pub fn permissions(&self) -> Vec<TokenPermission> {
+        let mut permissions: Vec<TokenPermission> = self
+            .policies
+            .iter()
+            .filter(|p| p.effect == "allow")
+            .flat_map(|p| p.permission_groups.iter().map(PermissionGroup::permission))
+            .collect();
+        permissions.sort_by(|a, b| a.name.cmp(&b.name));
+        permissions.dedup_by(|a, b| a.id == b.id);
+        permissions
+    }
+
+    /// Names of the permission groups the token is allowed
+    pub fn permission_groups(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .policies
+            .iter()
+            .filter(|p| p.effect == "allow")
+            .flat_map(|p| p.permission_groups.iter().map(|g| g.name.clone()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Required scopes that none of the token's allowed permission groups grant
+    pub fn missing_scopes(&self) -> Vec<String> {
+        let granted = self.permission_groups();
+        REQUIRED_TOKEN_SCOPES
+            .iter()
+            .filter(|(_, groups)| !groups.iter().any(|g| granted.iter().any(|name| name == g)))
+            .map(|(scope, _)| scope.to_string())
+            .collect()
+    }
+}
+
+/// Outcome of probing one capability with a cheap read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityCheck {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Connection health reported to the admin UI
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionStatus {
+    pub connected: bool,
+    pub token_status: Option<String>,
+    pub zone_name: Option<String>,
+    pub zone_status: Option<String>,
+    /// `None` when the token isn't allowed to read its own permissions
+    pub permission_groups: Option<Vec<String>>,
+    pub missing_scopes: Vec<String>,
+    pub warnings: Vec<String>,
+    pub error: Option<String>,
+}
+
 // ============================================================================
 // DNS Types
 // ============================================================================
@@ -188,6 +516,32 @@ pub struct DnsListParams {
     pub match_type: Option<String>,
 }
 
+/// DNSSEC state of a zone.
+///
+/// Once enabled, `ds` holds the DS record that must be added at the domain's
+/// registrar; DNSSEC stays `pending` until it is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnssecStatus {
+    pub status: String,
+    pub ds: Option<String>,
+    pub digest: Option<String>,
+    pub digest_type: Option<String>,
+    pub digest_algorithm: Option<String>,
+    pub algorithm: Option<String>,
+    pub key_tag: Option<i32>,
+    pub key_type: Option<String>,
+    pub flags: Option<i32>,
+    pub public_key: Option<String>,
+    pub modified_on: Option<DateTime<Utc>>,
+}
+
+impl DnssecStatus {
+    /// Whether DNSSEC has been switched on, even if the registrar hasn't picked up the DS record yet
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.status.as_str(), "active" | "pending")
+    }
+}
+
 // ============================================================================
 // SSL/TLS Types
 // ============================================================================
@@ -227,6 +581,115 @@ pub struct CertificateDetails {
     pub expires_on: Option<DateTime<Utc>>,
 }
 
+/// Ways Cloudflare can validate control of a custom hostname for its certificate
+pub const CUSTOM_HOSTNAME_SSL_METHODS: &[&str] = &["http", "txt", "email"];
+
+/// Custom hostname (SSL for SaaS)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomHostname {
+    pub id: String,
+    pub hostname: String,
+    pub ssl: Option<CustomHostnameSsl>,
+    pub status: Option<String>,
+    #[serde(default)]
+    pub verification_errors: Vec<String>,
+    /// TXT record proving ownership of the hostname
+    pub ownership_verification: Option<OwnershipVerification>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Certificate state of a custom hostname
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomHostnameSsl {
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub ssl_type: Option<String>,
+    pub method: Option<String>,
+    pub status: Option<String>,
+    #[serde(default)]
+    pub validation_records: Vec<SslValidationRecord>,
+    #[serde(default)]
+    pub validation_errors: Vec<serde_json::Value>,
+}
+
+/// A domain control validation record for a custom hostname certificate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SslValidationRecord {
+    pub txt_name: Option<String>,
+    pub txt_value: Option<String>,
+    pub http_url: Option<String>,
+    pub http_body: Option<String>,
+    pub emails: Option<Vec<String>>,
+}
+
+/// Hostname ownership verification record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnershipVerification {
+    #[serde(rename = "type")]
+    pub record_type: Option<String>,
+    pub name: Option<String>,
+    pub value: Option<String>,
+}
+
+/// Something the customer has to publish before a custom hostname goes active
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DcvRecord {
+    /// `TXT`, `HTTP` or `EMAIL`
+    #[serde(rename = "type")]
+    pub record_type: String,
+    /// Record name, URL to serve, or email address
+    pub name: String,
+    /// Record value or file body; empty for email validation
+    pub value: String,
+}
+
+impl CustomHostname {
+    /// Ownership and certificate validation records still to be published
+    pub fn dcv_records(&self) -> Vec<DcvRecord> {
+        let mut records = Vec::new();
+        let record = |record_type: &str, name: &str, value: &str| DcvRecord {
+            record_type: record_type.to_string(),
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+
+        if let Some(OwnershipVerification { name: Some(name), value: Some(value), .. }) =
+            &self.ownership_verification
+        {
+            records.push(record("TXT", name, value));
+        }
+
+        for validation in self.ssl.iter().flat_map(|ssl| &ssl.validation_records) {
+            if let (Some(name), Some(value)) = (&validation.txt_name, &validation.txt_value) {
+                records.push(record("TXT", name, value));
+            }
+            if let (Some(url), Some(body)) = (&validation.http_url, &validation.http_body) {
+                records.push(record("HTTP", url, body));
+            }
+            for email in validation.emails.iter().flatten() {
+                records.push(record("EMAIL", email, ""));
+            }
+        }
+
+        records
+    }
+}
+
+/// Custom hostname creation request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCustomHostname {
+    pub hostname: String,
+    pub ssl: CreateCustomHostnameSsl,
+}
+
+/// Certificate options for a new custom hostname
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCustomHostnameSsl {
+    pub method: String,
+    #[serde(rename = "type")]
+    pub ssl_type: String,
+}
+
 // ============================================================================
 // Security / WAF Types
 // ============================================================================
@@ -276,6 +739,9 @@ pub struct CreateFirewallRule {
     pub paused: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub priority: Option<i32>,
+    /// Features a `bypass` rule skips
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub products: Option<Vec<String>>,
 }
 
 /// Create firewall filter
@@ -323,6 +789,229 @@ pub struct CreateIpAccessRule {
     pub notes: Option<String>,
 }
 
+/// Account-level list of IPs that firewall expressions can reference as `$name`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpList {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub kind: String,
+    #[serde(default)]
+    pub num_items: i64,
+    #[serde(default)]
+    pub num_referencing_filters: i64,
+    pub created_on: Option<DateTime<Utc>>,
+    pub modified_on: Option<DateTime<Utc>>,
+}
+
+/// Create IP list request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateIpList {
+    pub name: String,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Entry in an IP list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpListItem {
+    pub id: String,
+    pub ip: Option<String>,
+    pub comment: Option<String>,
+    pub created_on: Option<DateTime<Utc>>,
+    pub modified_on: Option<DateTime<Utc>>,
+}
+
+/// IP or CIDR to add to an IP list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateIpListItem {
+    pub ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// A redirect in a Bulk Redirect list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkRedirect {
+    pub source_url: String,
+    pub target_url: String,
+    /// 301, 302, 307 or 308
+    pub status_code: u16,
+    #[serde(default)]
+    pub preserve_query_string: bool,
+}
+
+/// Handle for an asynchronous list operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkOperation {
+    pub operation_id: String,
+}
+
+/// Progress of an asynchronous list operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkOperationStatus {
+    pub id: String,
+    /// `pending`, `running`, `completed` or `failed`
+    pub status: String,
+    pub error: Option<String>,
+    pub completed: Option<DateTime<Utc>>,
+}
+
+/// A request a firewall feature acted on, from the `firewallEventsAdaptive` dataset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecurityEvent {
+    pub ray_id: String,
+    /// `block`, `managed_challenge`, `log`, ...
+    pub action: String,
+    /// Feature that acted: `firewallCustom`, `firewallManaged`, `botFight`, ...
+    pub source: String,
+    /// Two-letter country code of the client
+    pub country: Option<String>,
+    pub host: Option<String>,
+    pub path: Option<String>,
+    pub rule_id: Option<String>,
+    pub datetime: DateTime<Utc>,
+}
+
+/// Actions Super Bot Fight Mode can take on a class of bots
+pub const SUPER_BOT_FIGHT_ACTIONS: &[&str] = &["allow", "block", "managed_challenge"];
+
+/// Bot protection the zone's plan offers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BotProtectionMode {
+    /// Free plans: a single on/off toggle
+    BotFightMode,
+    /// Paid plans: an action per class of bot
+    SuperBotFightMode,
+}
+
+/// Zone bot management settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BotManagement {
+    /// Bot Fight Mode, the Free plan toggle
+    #[serde(default)]
+    pub fight_mode: bool,
+    /// Inject the JavaScript detection snippet
+    pub enable_js: Option<bool>,
+    pub sbfm_definitely_automated: Option<String>,
+    pub sbfm_likely_automated: Option<String>,
+    pub sbfm_verified_bots: Option<String>,
+    pub sbfm_static_resource_protection: Option<bool>,
+    /// Let WordPress's own loopback and cron requests through
+    pub optimize_wordpress: Option<bool>,
+    pub using_latest_model: Option<bool>,
+}
+
+/// Bot management settings with the protection the zone's plan offers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BotProtection {
+    pub mode: BotProtectionMode,
+    #[serde(flatten)]
+    pub settings: BotManagement,
+}
+
+/// Changes to a zone's bot management settings; unset fields are left alone
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateBotManagement {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fight_mode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_js: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sbfm_definitely_automated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sbfm_likely_automated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sbfm_verified_bots: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sbfm_static_resource_protection: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimize_wordpress: Option<bool>,
+}
+
+impl UpdateBotManagement {
+    /// Whether any Super Bot Fight Mode setting is being changed
+    pub fn changes_super_bot_fight_mode(&self) -> bool {
+        self.sbfm_definitely_automated.is_some()
+            || self.sbfm_likely_automated.is_some()
+            || self.sbfm_verified_bots.is_some()
+            || self.sbfm_static_resource_protection.is_some()
+            || self.optimize_wordpress.is_some()
+    }
+}
+
+// ============================================================================
+// Turnstile Types
+// ============================================================================
+
+/// Widget modes Turnstile accepts
+pub const TURNSTILE_MODES: &[&str] = &["managed", "non-interactive", "invisible"];
+
+/// Turnstile widget.
+///
+/// Cloudflare only includes `secret` when a widget is created or its secret
+/// rotated; list and get responses leave it out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnstileWidget {
+    pub sitekey: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub domains: Vec<String>,
+    /// `managed`, `non-interactive` or `invisible`
+    pub mode: String,
+    pub created_on: Option<DateTime<Utc>>,
+    pub modified_on: Option<DateTime<Utc>>,
+}
+
+/// Create Turnstile widget request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTurnstileWidget {
+    pub name: String,
+    pub domains: Vec<String>,
+    pub mode: String,
+}
+
+/// Result of verifying a Turnstile token with siteverify
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnstileVerification {
+    pub success: bool,
+    /// When the challenge was solved
+    pub challenge_ts: Option<DateTime<Utc>>,
+    /// Hostname of the site the challenge was solved on
+    pub hostname: Option<String>,
+    #[serde(default, alias = "error-codes")]
+    pub error_codes: Vec<String>,
+    /// Action name set on the widget, if any
+    pub action: Option<String>,
+    /// Customer data set on the widget, if any
+    pub cdata: Option<String>,
+}
+
+impl TurnstileVerification {
+    /// Readable explanation of each error code
+    pub fn error_messages(&self) -> Vec<&'static str> {
+        self.error_codes.iter().map(|code| turnstile_error_message(code)).collect()
+    }
+}
+
+/// Explain a siteverify error code
+pub fn turnstile_error_message(code: &str) -> &'static str {
+    match code {
+        "missing-input-secret" => "The Turnstile secret was not sent",
+        "invalid-input-secret" => "The Turnstile secret is invalid or belongs to another widget",
+        "missing-input-response" => "The Turnstile token was not sent",
+        "invalid-input-response" => "The Turnstile token is invalid or has expired",
+        "timeout-or-duplicate" => "The Turnstile token has already been used or is too old; solve the challenge again",
+        "bad-request" => "The verification request was malformed",
+        "internal-error" => "Turnstile had an internal error; try again",
+        _ => "Turnstile verification failed",
+    }
+}
+
 // ============================================================================
 // Page Rules Types
 // ============================================================================
@@ -383,6 +1072,60 @@ pub struct UpdatePageRule {
     pub status: Option<String>,
 }
 
+// ============================================================================
+// Rulesets Types
+// ============================================================================
+
+/// Ruleset (cache rules, custom rules, etc.)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub kind: Option<String>,
+    pub phase: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<RulesetRule>,
+    pub version: Option<String>,
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+/// Rule within a ruleset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulesetRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action_parameters: Option<serde_json::Value>,
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Counting characteristics and limits of an `http_ratelimit` rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ratelimit: Option<serde_json::Value>,
+    /// Fields not modeled above (`ref`, `logging`, ...), kept so a rule read
+    /// from Cloudflare is written back unchanged
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A Cloudflare Managed Ruleset available to the zone's WAF
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManagedRuleset {
+    pub id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub version: Option<String>,
+    pub last_updated: Option<DateTime<Utc>>,
+    /// Whether the zone's managed WAF phase executes this ruleset
+    pub deployed: bool,
+    /// Action override applied when deployed, `None` for the ruleset's defaults
+    pub action: Option<String>,
+}
+
 // ============================================================================
 // Workers Types
 // ============================================================================
@@ -400,6 +1143,37 @@ pub struct Worker {
     pub compatibility_flags: Option<Vec<String>>,
 }
 
+/// Live log session for a Worker, read over a WebSocket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TailSession {
+    pub id: String,
+    /// WebSocket URL streaming the Worker's trace events
+    pub url: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Worker script settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerSettings {
+    #[serde(default)]
+    pub bindings: Vec<WorkerBinding>,
+    pub compatibility_date: Option<String>,
+    #[serde(default)]
+    pub compatibility_flags: Vec<String>,
+    pub usage_model: Option<String>,
+}
+
+/// Worker binding (KV, R2, D1, secret, plain text, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerBinding {
+    #[serde(rename = "type")]
+    pub binding_type: String,
+    pub name: String,
+    /// Type-specific fields, e.g. `namespace_id` or `bucket_name`
+    #[serde(flatten)]
+    pub details: serde_json::Map<String, serde_json::Value>,
+}
+
 /// Worker route
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerRoute {
@@ -428,6 +1202,53 @@ pub struct KvKey {
     pub metadata: Option<serde_json::Value>,
 }
 
+/// A KV value, typed by the content type Cloudflare returns it with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum KvValue {
+    Text(String),
+    /// Parsed, so it isn't encoded twice in a JSON response
+    Json(serde_json::Value),
+    /// Base64 encoded
+    Binary(String),
+}
+
+impl KvValue {
+    /// Type a value from its body and `Content-Type`.
+    ///
+    /// JSON content types are parsed, falling back to text if the body isn't
+    /// JSON. Values stored without a content type come back as
+    /// `application/octet-stream`, so anything else is text when the body is
+    /// valid UTF-8 and binary otherwise.
+    pub fn from_body(content_type: Option<&str>, body: Vec<u8>) -> Self {
+        use base64::Engine;
+
+        let mime = content_type
+            .and_then(|ct| ct.split(';').next())
+            .map(|ct| ct.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        let is_json = mime == "application/json" || mime.ends_with("+json");
+
+        if is_json {
+            if let Ok(value) = serde_json::from_slice(&body) {
+                return Self::Json(value);
+            }
+        }
+        match String::from_utf8(body) {
+            Ok(text) => Self::Text(text),
+            Err(e) => Self::Binary(base64::engine::general_purpose::STANDARD.encode(e.into_bytes())),
+        }
+    }
+}
+
+/// A KV value together with its metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvEntry {
+    pub key: String,
+    pub value: KvValue,
+    pub metadata: Option<serde_json::Value>,
+}
+
 // ============================================================================
 // R2 Storage Types
 // ============================================================================
@@ -440,6 +1261,13 @@ pub struct R2Bucket {
     pub location: Option<String>,
 }
 
+/// Bucket list returned by the R2 API
+#[derive(Debug, Clone, Deserialize)]
+pub struct R2BucketList {
+    #[serde(default)]
+    pub buckets: Vec<R2Bucket>,
+}
+
 /// R2 object
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct R2Object {
@@ -495,6 +1323,26 @@ pub struct D1QueryMeta {
     pub rows_written: Option<i64>,
 }
 
+/// Progress of a D1 export, returned by each poll of the export endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct D1ExportStatus {
+    /// Pass back as `current_bookmark` to poll the same export
+    pub at_bookmark: Option<String>,
+    /// `active`, `complete` or `error`
+    pub status: Option<String>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<String>,
+    pub result: Option<D1ExportResult>,
+}
+
+/// Location of a finished D1 export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct D1ExportResult {
+    pub filename: Option<String>,
+    pub signed_url: Option<String>,
+}
+
 // ============================================================================
 // Stream Types
 // ============================================================================
@@ -504,7 +1352,9 @@ pub struct D1QueryMeta {
 pub struct StreamVideo {
     pub uid: String,
     pub thumbnail: Option<String>,
+    #[serde(alias = "thumbnailTimestampPct")]
     pub thumbnail_timestamp_pct: Option<f64>,
+    #[serde(alias = "readyToStream", default)]
     pub ready_to_stream: bool,
     pub status: Option<StreamStatus>,
     pub meta: Option<serde_json::Value>,
@@ -524,11 +1374,145 @@ pub struct StreamVideo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamStatus {
     pub state: String,
+    #[serde(alias = "pctComplete", default, deserialize_with = "deserialize_pct_complete")]
     pub pct_complete: Option<f64>,
+    #[serde(alias = "errorReasonCode")]
     pub error_reason_code: Option<String>,
+    #[serde(alias = "errorReasonText")]
     pub error_reason_text: Option<String>,
 }
 
+/// Stream reports progress as a string such as `"42.500000"`
+fn deserialize_pct_complete<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::Number(n)) => Ok(n.as_f64()),
+        Some(serde_json::Value::String(s)) if s.is_empty() => Ok(None),
+        Some(serde_json::Value::String(s)) => s.parse().map(Some).map_err(serde::de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+/// Request to copy a video into Stream from a URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamCopyRequest {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Value>,
+}
+
+/// Caption track on a Stream video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamCaption {
+    /// BCP-47 language tag, e.g. `en` or `pt-BR`
+    pub language: String,
+    /// Display name shown in the player's caption menu
+    pub label: Option<String>,
+    /// Whether Stream generated the captions rather than them being uploaded
+    pub generated: Option<bool>,
+    pub status: Option<String>,
+}
+
+/// Most videos Stream returns from a single list request
+pub const STREAM_LIST_MAX_RESULTS: usize = 1000;
+
+/// Filters and position for a page of Stream videos.
+///
+/// Everything except `cursor` and `limit` is sent to Cloudflare as a query
+/// parameter. `cursor` is the continuation token from a previous page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamListParams {
+    /// Match against video names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    /// Processing state, e.g. `ready`, `inprogress` or `error`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creator: Option<String>,
+    /// Oldest first instead of newest first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asc: Option<bool>,
+    /// Only videos created at or after this time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    /// Only videos created at or before this time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    /// Videos per page, at most `STREAM_LIST_MAX_RESULTS`
+    #[serde(skip_serializing)]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing)]
+    pub cursor: Option<String>,
+}
+
+impl StreamListParams {
+    /// Continuation token resuming after `video`: its upload time and uid
+    pub fn cursor_after(video: &StreamVideo) -> Option<String> {
+        video
+            .created
+            .map(|created| format!("{}|{}", created.to_rfc3339(), video.uid))
+    }
+
+    /// Split a continuation token into the upload time and uid of the last video seen
+    pub fn parse_cursor(cursor: &str) -> Option<(DateTime<Utc>, &str)> {
+        let (created, uid) = cursor.split_once('|')?;
+        let created = DateTime::parse_from_rfc3339(created).ok()?.with_timezone(&Utc);
+        Some((created, uid))
+    }
+
+    /// The same filters narrowed to videos uploaded at or past `created`,
+    /// in whichever direction the listing runs
+    pub fn resume_from(&self, created: DateTime<Utc>) -> Self {
+        let mut params = self.clone();
+        if params.asc == Some(true) {
+            params.start = Some(created);
+        } else {
+            params.end = Some(created);
+        }
+        params.cursor = None;
+        params
+    }
+}
+
+/// A page of Stream videos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamVideoPage {
+    pub videos: Vec<StreamVideo>,
+    /// Pass back as `cursor` to fetch the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl StreamVideoPage {
+    /// Build a page from one list response.
+    ///
+    /// `after_uid` is the last video of the previous page, which Stream
+    /// returns again since `start`/`end` are inclusive.
+    pub fn from_fetched(fetched: Vec<StreamVideo>, after_uid: Option<&str>, limit: Option<usize>) -> Self {
+        let more_upstream = fetched.len() >= STREAM_LIST_MAX_RESULTS;
+        let limit = limit
+            .unwrap_or(STREAM_LIST_MAX_RESULTS)
+            .clamp(1, STREAM_LIST_MAX_RESULTS);
+
+        let mut videos: Vec<StreamVideo> = fetched
+            .into_iter()
+            .filter(|v| Some(v.uid.as_str()) != after_uid)
+            .collect();
+        let truncated = videos.len() > limit;
+        videos.truncate(limit);
+
+        let next_cursor = if truncated || more_upstream {
+            videos.last().and_then(StreamListParams::cursor_after)
+        } else {
+            None
+        };
+
+        Self { videos, next_cursor }
+    }
+}
+
 /// Stream playback URLs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamPlayback {
@@ -610,17 +1594,107 @@ pub struct LiveInputCurrent {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LiveRecording {
     pub mode: String,
+    #[serde(rename = "timeoutSeconds", alias = "timeout_seconds", skip_serializing_if = "Option::is_none")]
     pub timeout_seconds: Option<i32>,
+    #[serde(rename = "requireSignedURLs", alias = "require_signed_urls", skip_serializing_if = "Option::is_none")]
     pub require_signed_urls: Option<bool>,
 }
 
+/// Playback latency mode for a live input
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveLatencyMode {
+    /// Standard HLS/DASH latency
+    #[default]
+    Standard,
+    /// Prefer low-latency HLS playback
+    LowLatency,
+    /// Keep the recording so viewers can seek back through the live stream
+    Dvr,
+}
+
 /// Create live input request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CreateLiveInput {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recording: Option<LiveRecording>,
+    #[serde(rename = "preferLowLatency", skip_serializing_if = "Option::is_none")]
+    pub prefer_low_latency: Option<bool>,
+    #[serde(rename = "deleteRecordingAfterDays", skip_serializing_if = "Option::is_none")]
+    pub delete_recording_after_days: Option<u32>,
+    #[serde(rename = "defaultCreator", skip_serializing_if = "Option::is_none")]
+    pub default_creator: Option<String>,
+}
+
+impl CreateLiveInput {
+    /// Apply a latency mode.
+    ///
+    /// DVR needs the stream recorded, so it turns on automatic recording if none was requested.
+    pub fn with_latency_mode(mut self, mode: LiveLatencyMode) -> Self {
+        match mode {
+            LiveLatencyMode::Standard => self.prefer_low_latency = None,
+            LiveLatencyMode::LowLatency => self.prefer_low_latency = Some(true),
+            LiveLatencyMode::Dvr => {
+                self.prefer_low_latency = Some(false);
+                let recording = self.recording.get_or_insert_with(|| LiveRecording {
+                    mode: "automatic".to_string(),
+                    timeout_seconds: None,
+                    require_signed_urls: None,
+                });
+                if recording.mode == "off" {
+                    recording.mode = "automatic".to_string();
+                }
+            }
+        }
+        self
+    }
+}
+
+/// Stream webhook subscription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamWebhook {
+    #[serde(rename = "notificationUrl")]
+    pub notification_url: String,
+    pub modified: Option<DateTime<Utc>>,
+    /// Signing secret for incoming notifications; never sent back to the admin UI
+    #[serde(skip_serializing)]
+    pub secret: Option<String>,
+}
+
+/// Request body for a Stream signed URL token
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StreamTokenRequest {
+    /// Unix timestamp after which the token is rejected
+    pub exp: i64,
+    /// Unix timestamp before which the token is rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloadable: Option<bool>,
+    #[serde(rename = "accessRules", skip_serializing_if = "Vec::is_empty", default)]
+    pub access_rules: Vec<StreamAccessRule>,
+}
+
+/// Access rule embedded in a signed token, evaluated in order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StreamAccessRule {
+    /// `ip.geoip.country`, `ip.src` or `any`
+    #[serde(rename = "type")]
+    pub rule_type: String,
+    /// `allow` or `block`
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip: Option<Vec<String>>,
+}
+
+/// Signed URL token created by Stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamToken {
+    pub token: String,
 }
 
 // ============================================================================
@@ -697,6 +1771,20 @@ pub struct AnalyticsUniques {
     pub all: i64,
 }
 
+/// GraphQL Analytics API response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphQlResponse<T> {
+    pub data: Option<T>,
+    pub errors: Option<Vec<GraphQlError>>,
+}
+
+/// GraphQL error details
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphQlError {
+    pub message: String,
+    pub path: Option<Vec<serde_json::Value>>,
+}
+
 /// Analytics timeseries
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalyticsTimeseries {
@@ -708,3 +1796,254 @@ pub struct AnalyticsTimeseries {
     pub pageviews: Option<AnalyticsPageviews>,
     pub uniques: Option<AnalyticsUniques>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turnstile_verification_deserializes() {
+        let success: TurnstileVerification = serde_json::from_value(serde_json::json!({
+            "success": true,
+            "challenge_ts": "2024-03-01T12:00:00.000Z",
+            "hostname": "example.com",
+            "error-codes": [],
+            "action": "login",
+            "cdata": "session-42"
+        }))
+        .unwrap();
+        assert!(success.success);
+        assert_eq!(success.hostname.as_deref(), Some("example.com"));
+        assert_eq!(success.action.as_deref(), Some("login"));
+        assert_eq!(success.cdata.as_deref(), Some("session-42"));
+        assert!(success.error_messages().is_empty());
+
+        let failure: TurnstileVerification = serde_json::from_value(serde_json::json!({
+            "success": false,
+            "error-codes": ["timeout-or-duplicate", "invalid-input-response"]
+        }))
+        .unwrap();
+        assert!(!failure.success);
+        assert!(failure.challenge_ts.is_none());
+        assert_eq!(failure.error_codes, vec!["timeout-or-duplicate", "invalid-input-response"]);
+        assert!(failure.error_messages()[0].contains("already been used"));
+        assert!(failure.error_messages()[1].contains("invalid or has expired"));
+    }
+
+    #[test]
+    fn test_pagination_defaults_and_clamps() {
+        assert_eq!(Pagination::new(None, None), Pagination { page: 1, per_page: DEFAULT_PER_PAGE });
+        assert_eq!(Pagination::new(Some(3), Some(50)), Pagination { page: 3, per_page: 50 });
+        assert_eq!(Pagination::new(Some(0), Some(1000)), Pagination { page: 1, per_page: MAX_PER_PAGE });
+        assert_eq!(Pagination::new(Some(-2), Some(0)), Pagination { page: 1, per_page: 1 });
+        assert_eq!(Pagination::new(Some(3), Some(50)).offset(), 100);
+    }
+
+    #[test]
+    fn test_page_from_vec() {
+        let page = Page::from_vec((1..=45).collect::<Vec<_>>(), Pagination::new(Some(3), Some(20)));
+        assert_eq!(page.items, vec![41, 42, 43, 44, 45]);
+        assert_eq!((page.page, page.per_page, page.total, page.total_pages), (3, 20, 45, 3));
+
+        let past_end = Page::from_vec(vec![1, 2], Pagination::new(Some(5), None));
+        assert!(past_end.items.is_empty());
+        assert_eq!(past_end.total_pages, 1);
+        assert_eq!(Page::<u8>::from_vec(Vec::new(), Pagination::default()).total_pages, 0);
+    }
+
+    #[test]
+    fn test_create_live_input_serializes_recording_options() {
+        let input = CreateLiveInput {
+            meta: Some(serde_json::json!({ "name": "Weekly show" })),
+            recording: Some(LiveRecording {
+                mode: "off".to_string(),
+                timeout_seconds: Some(60),
+                require_signed_urls: Some(true),
+            }),
+            delete_recording_after_days: Some(30),
+            default_creator: Some("editor-7".to_string()),
+            ..Default::default()
+        }
+        .with_latency_mode(LiveLatencyMode::Dvr);
+
+        let body = serde_json::to_value(&input).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "meta": { "name": "Weekly show" },
+                "recording": {
+                    "mode": "automatic",
+                    "timeoutSeconds": 60,
+                    "requireSignedURLs": true
+                },
+                "preferLowLatency": false,
+                "deleteRecordingAfterDays": 30,
+                "defaultCreator": "editor-7"
+            })
+        );
+
+        let low_latency = CreateLiveInput::default().with_latency_mode(LiveLatencyMode::LowLatency);
+        assert_eq!(
+            serde_json::to_value(&low_latency).unwrap(),
+            serde_json::json!({ "preferLowLatency": true })
+        );
+    }
+
+    #[test]
+    fn test_dnssec_enable_response_deserializes_ds_record() {
+        let status: DnssecStatus = serde_json::from_value(serde_json::json!({
+            "algorithm": "13",
+            "digest": "48E939042E82C22542CB377B580DFDC52A361CEFDC72E7F9107E2B6BD9306A45",
+            "digest_algorithm": "SHA256",
+            "digest_type": "2",
+            "ds": "example.com. 3600 IN DS 16953 13 2 48E939042E82C22542CB377B580DFDC52A361CEFDC72E7F9107E2B6BD9306A45",
+            "flags": 257,
+            "key_tag": 16953,
+            "key_type": "ECDSAP256SHA256",
+            "modified_on": "2014-01-01T05:20:00Z",
+            "public_key": "oXiGYrSTO+LSCJ3mohc8EP+CzF9KxBj8/ydXJ22pKuZP3VAC3/Md/k7xZfz470CoRyZJ6gV6vml07IC3d8xqhA==",
+            "status": "pending"
+        }))
+        .unwrap();
+
+        assert!(status.is_enabled());
+        assert_eq!(status.key_tag, Some(16953));
+        assert!(status.ds.unwrap().starts_with("example.com. 3600 IN DS 16953 13 2"));
+
+        let disabled: DnssecStatus =
+            serde_json::from_value(serde_json::json!({ "status": "disabled" })).unwrap();
+        assert!(!disabled.is_enabled());
+        assert!(disabled.ds.is_none());
+    }
+
+    fn video(uid: &str, created: &str) -> StreamVideo {
+        serde_json::from_value(serde_json::json!({ "uid": uid, "created": created })).unwrap()
+    }
+
+    #[test]
+    fn test_stream_list_params_query_serialization() {
+        assert_eq!(serde_urlencoded::to_string(StreamListParams::default()).unwrap(), "");
+
+        let params = StreamListParams {
+            search: Some("launch party".to_string()),
+            status: Some("ready".to_string()),
+            limit: Some(25),
+            cursor: Some("ignored".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_urlencoded::to_string(&params).unwrap(),
+            "search=launch+party&status=ready"
+        );
+
+        // Resuming moves `end` back for newest-first listings and `start` forward for oldest-first
+        let (created, uid) = StreamListParams::parse_cursor("2024-03-01T12:00:00+00:00|abc123").unwrap();
+        assert_eq!(uid, "abc123");
+        assert_eq!(
+            serde_urlencoded::to_string(params.resume_from(created)).unwrap(),
+            "search=launch+party&status=ready&end=2024-03-01T12%3A00%3A00Z"
+        );
+        let asc = StreamListParams { asc: Some(true), ..Default::default() };
+        assert_eq!(
+            serde_urlencoded::to_string(asc.resume_from(created)).unwrap(),
+            "asc=true&start=2024-03-01T12%3A00%3A00Z"
+        );
+
+        assert!(StreamListParams::parse_cursor("not-a-cursor").is_none());
+        assert!(StreamListParams::parse_cursor("yesterday|abc123").is_none());
+    }
+
+    #[test]
+    fn test_stream_video_page_cursor() {
+        let fetched = vec![
+            video("seen", "2024-03-03T00:00:00Z"),
+            video("b", "2024-03-02T00:00:00Z"),
+            video("c", "2024-03-01T00:00:00Z"),
+        ];
+
+        // The previous page's last video is dropped, and a truncated page gets a cursor
+        let page = StreamVideoPage::from_fetched(fetched.clone(), Some("seen"), Some(1));
+        assert_eq!(page.videos.len(), 1);
+        assert_eq!(page.videos[0].uid, "b");
+        let cursor = page.next_cursor.unwrap();
+        assert_eq!(StreamListParams::parse_cursor(&cursor).unwrap().1, "b");
+
+        // Fewer results than Stream's cap means this is the last page
+        let page = StreamVideoPage::from_fetched(fetched, None, None);
+        assert_eq!(page.videos.len(), 3);
+        assert!(page.next_cursor.is_none());
+
+        let full: Vec<StreamVideo> = (0..STREAM_LIST_MAX_RESULTS)
+            .map(|i| video(&format!("v{}", i), "2024-03-01T00:00:00Z"))
+            .collect();
+        let page = StreamVideoPage::from_fetched(full, None, None);
+        assert!(page.next_cursor.unwrap().ends_with("|v999"));
+    }
+
+    #[test]
+    fn test_permission_group_resolves_well_known_ids() {
+        // Known IDs win over whatever name came with them
+        let group = PermissionGroup {
+            id: "4755a26eedb94da69e1066d98aa820be".to_string(),
+            name: "dns_records:edit".to_string(),
+        };
+        assert_eq!(
+            group.permission(),
+            TokenPermission {
+                id: "4755a26eedb94da69e1066d98aa820be".to_string(),
+                name: "DNS Write".to_string(),
+                resource: "DNS".to_string(),
+                scope: PermissionScope::Write,
+            }
+        );
+
+        let purge = PermissionGroup { id: "e17beae8b8cb423a99b1730f21238bed".to_string(), name: String::new() };
+        assert_eq!(purge.permission().name, "Cache Purge");
+        assert_eq!(purge.permission().scope, PermissionScope::Purge);
+    }
+
+    #[test]
+    fn test_permission_group_falls_back_to_name() {
+        let permission = |name: &str| PermissionGroup { id: "unknown".to_string(), name: name.to_string() }.permission();
+
+        let edit = permission("Access: Apps and Policies Edit");
+        assert_eq!((edit.resource.as_str(), edit.scope), ("Access: Apps and Policies", PermissionScope::Write));
+        assert_eq!(edit.name, "Access: Apps and Policies Write");
+
+        let read = permission("Analytics Read");
+        assert_eq!((read.resource.as_str(), read.scope), ("Analytics", PermissionScope::Read));
+
+        let other = permission("Billing");
+        assert_eq!((other.name.as_str(), other.scope), ("Billing", PermissionScope::Other));
+    }
+
+    #[test]
+    fn test_api_token_permissions_skip_denied_policies() {
+        let token: ApiToken = serde_json::from_value(serde_json::json!({
+            "id": "tok",
+            "name": "RustPress",
+            "status": "active",
+            "policies": [
+                {
+                    "effect": "allow",
+                    "permission_groups": [
+                        { "id": "e17beae8b8cb423a99b1730f21238bed", "name": "Cache Purge" },
+                        { "id": "4755a26eedb94da69e1066d98aa820be", "name": "DNS Write" }
+                    ]
+                },
+                {
+                    "effect": "allow",
+                    "permission_groups": [{ "id": "4755a26eedb94da69e1066d98aa820be", "name": "DNS Write" }]
+                },
+                {
+                    "effect": "deny",
+                    "permission_groups": [{ "id": "e6d2666161e84845a636613608cee8d5", "name": "Zone Write" }]
+                }
+            ]
+        }))
+        .unwrap();
+
+        let names: Vec<String> = token.permissions().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["Cache Purge", "DNS Write"]);
+    }
+}
@@ -0,0 +1,315 @@
+//! Plan-gated features
+//!
+//! Cloudflare rejects calls for features the zone's plan doesn't include with
+//! error codes and messages that mean little to an admin. `map_plan_error`
+//! recognizes those and explains which plan the feature needs instead.
+//! `PlanTier::supports` answers the same question up front, so callers can
+//! refuse an operation before Cloudflare does.
+
+use crate::error::CloudflareError;
+use crate::models::Plan;
+use serde::Serialize;
+use tracing::warn;
+
+/// Cloudflare error codes meaning the zone isn't entitled to a feature.
+///
+/// Only codes Cloudflare reserves for entitlement belong here; generic codes
+/// such as 1007 (invalid value) are reused for unrelated validation errors.
+pub const PLAN_UPGRADE_CODES: &[i32] = &[
+    // Purge by tag outside Enterprise
+    1107,
+];
+
+/// Error text Cloudflare uses for a feature outside the zone's plan
+const PLAN_UPGRADE_MARKERS: &[&str] = &[
+    "not entitled",
+    "entitlement",
+    "upgrade your plan",
+    "requires an active subscription",
+    "only available on",
+    "not available on your plan",
+    "not available for your plan",
+];
+
+/// Error text Cloudflare uses when a zone lacks the Argo add-on, which is
+/// billed separately from the plan
+const ARGO_SUBSCRIPTION_MARKERS: &[&str] = &["subscri", "billing"];
+
+/// A feature only some plans include
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    ArgoSmartRouting,
+    TieredCaching,
+    TagPurge,
+    HostPurge,
+    SuperBotFightMode,
+    WafManagedRules,
+    RateLimiting,
+    CustomCertificates,
+    CacheReserve,
+    CustomTieredCacheTopology,
+}
+
+impl Feature {
+    /// Name shown to admins
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ArgoSmartRouting => "Argo Smart Routing",
+            Self::TieredCaching => "Tiered Caching",
+            Self::TagPurge => "Purge by cache tag",
+            Self::HostPurge => "Purge by hostname",
+            Self::SuperBotFightMode => "Super Bot Fight Mode",
+            Self::WafManagedRules => "WAF Managed Rules",
+            Self::RateLimiting => "Advanced Rate Limiting",
+            Self::CustomCertificates => "Custom SSL certificates",
+            Self::CacheReserve => "Cache Reserve",
+            Self::CustomTieredCacheTopology => "Custom tiered cache topology",
+        }
+    }
+
+    /// Plan or subscription the feature needs
+    pub fn required_plan(self) -> &'static str {
+        match self {
+            Self::ArgoSmartRouting => "an Argo subscription",
+            Self::TieredCaching => "a Free plan or higher",
+            Self::TagPurge | Self::HostPurge | Self::RateLimiting | Self::CustomTieredCacheTopology => {
+                "an Enterprise plan"
+            }
+            Self::SuperBotFightMode | Self::WafManagedRules => "a Pro plan or higher",
+            Self::CustomCertificates | Self::CacheReserve => "a Business plan or higher",
+        }
+    }
+
+    /// Error text that, for this feature alone, means it isn't paid for
+    fn upgrade_markers(self) -> &'static [&'static str] {
+        match self {
+            Self::ArgoSmartRouting => ARGO_SUBSCRIPTION_MARKERS,
+            _ => &[],
+        }
+    }
+
+    /// Lowest plan tier that includes the feature.
+    ///
+    /// Argo Smart Routing is a paid add-on any plan can subscribe to, so
+    /// whether a zone has it is only known from the API's answer. Generic
+    /// tiered caching comes with every plan.
+    pub fn minimum_tier(self) -> PlanTier {
+        match self {
+            Self::ArgoSmartRouting | Self::TieredCaching => PlanTier::Free,
+            Self::SuperBotFightMode | Self::WafManagedRules => PlanTier::Pro,
+            Self::CustomCertificates | Self::CacheReserve => PlanTier::Business,
+            Self::TagPurge | Self::HostPurge | Self::RateLimiting | Self::CustomTieredCacheTopology => {
+                PlanTier::Enterprise
+            }
+        }
+    }
+
+    pub const ALL: [Feature; 10] = [
+        Self::ArgoSmartRouting,
+        Self::TieredCaching,
+        Self::TagPurge,
+        Self::HostPurge,
+        Self::SuperBotFightMode,
+        Self::WafManagedRules,
+        Self::RateLimiting,
+        Self::CustomCertificates,
+        Self::CacheReserve,
+        Self::CustomTieredCacheTopology,
+    ];
+}
+
+/// Cloudflare zone plan, ordered from least to most capable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanTier {
+    Free,
+    Pro,
+    Business,
+    Enterprise,
+}
+
+impl PlanTier {
+    /// Tier for a zone's plan.
+    ///
+    /// Uses `legacy_id` when present, including reseller ids such as
+    /// `partners_ent` whose tier is the last segment, and falls back to the
+    /// plan name ("Pro Website", "Enterprise"). Anything unrecognized is
+    /// treated as Enterprise, leaving Cloudflare to refuse what the plan
+    /// lacks rather than hiding features the zone may well have.
+    pub fn from_plan(plan: &Plan) -> Self {
+        plan.legacy_id
+            .as_deref()
+            .and_then(Self::from_legacy_id)
+            .or_else(|| plan.name.split_whitespace().next().and_then(Self::parse))
+            .unwrap_or_else(|| {
+                warn!(
+                    "Unrecognized Cloudflare plan '{}' ({:?}), not restricting features",
+                    plan.name, plan.legacy_id
+                );
+                Self::Enterprise
+            })
+    }
+
+    /// Tier for a legacy plan id like `pro` or `partners_ent`
+    fn from_legacy_id(legacy_id: &str) -> Option<Self> {
+        match legacy_id.rsplit('_').next()? {
+            "ent" => Some(Self::Enterprise),
+            "biz" => Some(Self::Business),
+            tier => Self::parse(tier),
+        }
+    }
+
+    /// Tier named by a plan identifier such as `business`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "free" => Some(Self::Free),
+            "pro" => Some(Self::Pro),
+            "business" => Some(Self::Business),
+            "enterprise" => Some(Self::Enterprise),
+            _ => None,
+        }
+    }
+
+    /// Whether zones on this tier can use `feature`
+    pub fn supports(self, feature: Feature) -> bool {
+        self >= feature.minimum_tier()
+    }
+}
+
+/// `PermissionDenied` explaining that the zone's plan lacks `feature`
+pub fn plan_upgrade_error(feature: Feature) -> CloudflareError {
+    CloudflareError::PermissionDenied(format!(
+        "{} isn't included in this zone's plan; it needs {}. Upgrade in the Cloudflare dashboard, then try again.",
+        feature.label(),
+        feature.required_plan()
+    ))
+}
+
+/// Whether an error means the zone's plan doesn't include `feature`
+pub fn is_plan_error(error: &CloudflareError, feature: Feature) -> bool {
+    match error {
+        CloudflareError::ApiError { code, message, errors, .. } => {
+            PLAN_UPGRADE_CODES.contains(code)
+                || errors.iter().any(|e| PLAN_UPGRADE_CODES.contains(&e.code))
+                || std::iter::once(message)
+                    .chain(errors.iter().map(|e| &e.message))
+                    .any(|m| {
+                        let m = m.to_lowercase();
+                        PLAN_UPGRADE_MARKERS
+                            .iter()
+                            .chain(feature.upgrade_markers())
+                            .any(|marker| m.contains(marker))
+                    })
+        }
+        _ => false,
+    }
+}
+
+/// Turn an "upgrade required" API error for `feature` into `PermissionDenied`
+/// naming the plan it needs; other errors pass through untouched
+pub fn map_plan_error(error: CloudflareError, feature: Feature) -> CloudflareError {
+    if is_plan_error(&error, feature) {
+        plan_upgrade_error(feature)
+    } else {
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ApiError;
+
+    fn api_error(code: i32, message: &str) -> CloudflareError {
+        CloudflareError::from_api_errors(&[ApiError {
+            code,
+            message: message.to_string(),
+            error_chain: None,
+        }])
+    }
+
+    #[test]
+    fn test_upgrade_codes_become_permission_denied() {
+        let err = map_plan_error(
+            api_error(1107, "Purge by tag is only available on Enterprise plans"),
+            Feature::TagPurge,
+        );
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m)
+            if m.starts_with("Purge by cache tag isn't included") && m.contains("an Enterprise plan")));
+
+        // Argo is billed separately, so billing errors mean it isn't subscribed
+        let err = map_plan_error(
+            api_error(1007, "Argo Smart Routing requires a billing profile"),
+            Feature::ArgoSmartRouting,
+        );
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m) if m.contains("an Argo subscription")));
+
+        // Recognized by message when the code is unfamiliar
+        let err = map_plan_error(api_error(1004, "zone is not entitled to this feature"), Feature::SuperBotFightMode);
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m) if m.contains("a Pro plan")));
+    }
+
+    #[test]
+    fn test_other_errors_pass_through() {
+        let err = map_plan_error(api_error(1004, "DNS validation error"), Feature::TagPurge);
+        assert!(matches!(err, CloudflareError::ApiError { code: 1004, .. }));
+
+        // 1007 is also Cloudflare's generic invalid-value code
+        let err = map_plan_error(api_error(1007, "Invalid value for zone setting"), Feature::TieredCaching);
+        assert!(matches!(err, CloudflareError::ApiError { code: 1007, .. }));
+
+        // Subscription wording only means a missing add-on for Argo
+        let err = map_plan_error(api_error(1004, "Webhook subscription not found"), Feature::TagPurge);
+        assert!(matches!(err, CloudflareError::ApiError { code: 1004, .. }));
+
+        let err = map_plan_error(CloudflareError::RateLimitExceeded, Feature::RateLimiting);
+        assert!(matches!(err, CloudflareError::RateLimitExceeded));
+    }
+
+    fn plan(name: &str, legacy_id: Option<&str>) -> Plan {
+        Plan {
+            id: "plan-id".to_string(),
+            name: name.to_string(),
+            price: 0.0,
+            currency: "USD".to_string(),
+            frequency: "monthly".to_string(),
+            is_subscribed: true,
+            can_subscribe: false,
+            legacy_id: legacy_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_plan_strings_map_to_tiers() {
+        assert_eq!(PlanTier::from_plan(&plan("Free Website", Some("free"))), PlanTier::Free);
+        assert_eq!(PlanTier::from_plan(&plan("Pro Website", Some("pro"))), PlanTier::Pro);
+        assert_eq!(PlanTier::from_plan(&plan("Business Website", Some("business"))), PlanTier::Business);
+        assert_eq!(PlanTier::from_plan(&plan("Enterprise Website", Some("enterprise"))), PlanTier::Enterprise);
+
+        // Name is used when there's no legacy ID
+        assert_eq!(PlanTier::from_plan(&plan("Business Website", None)), PlanTier::Business);
+        assert_eq!(PlanTier::from_plan(&plan("Enterprise", None)), PlanTier::Enterprise);
+        // Reseller ids carry the tier in their last segment
+        assert_eq!(PlanTier::from_plan(&plan("Partners Plan", Some("partners_ent"))), PlanTier::Enterprise);
+        assert_eq!(PlanTier::from_plan(&plan("Partners Plan", Some("partners_pro"))), PlanTier::Pro);
+        assert_eq!(PlanTier::from_plan(&plan("Partners Plan", Some("partners_biz"))), PlanTier::Business);
+        // Unrecognized plans aren't restricted
+        assert_eq!(PlanTier::from_plan(&plan("Custom Plan", Some("custom_deal"))), PlanTier::Enterprise);
+    }
+
+    #[test]
+    fn test_tier_feature_support() {
+        assert!(!PlanTier::Free.supports(Feature::WafManagedRules));
+        assert!(PlanTier::Pro.supports(Feature::WafManagedRules));
+        assert!(!PlanTier::Pro.supports(Feature::CustomCertificates));
+        assert!(PlanTier::Business.supports(Feature::CustomCertificates));
+        assert!(!PlanTier::Business.supports(Feature::TagPurge));
+        assert!(PlanTier::Enterprise.supports(Feature::TagPurge));
+        assert!(PlanTier::Enterprise.supports(Feature::RateLimiting));
+        assert!(PlanTier::Free.supports(Feature::ArgoSmartRouting));
+
+        assert!(matches!(plan_upgrade_error(Feature::CustomCertificates), CloudflareError::PermissionDenied(ref m)
+            if m.contains("a Business plan or higher")));
+    }
+}
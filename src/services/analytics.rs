@@ -1,44 +1,183 @@
 //! Analytics service
 
 use crate::client::CloudflareClient;
+use crate::services::features::{FeatureFlags, ANALYTICS_CLEANUP};
+use crate::services::settings::SettingsService;
 use crate::error::{CloudflareError, CloudflareResult};
-use crate::models::Analytics;
+use crate::models::{
+    Analytics, AnalyticsBandwidth, AnalyticsPageviews, AnalyticsRequests, AnalyticsSsl,
+    AnalyticsThreats, AnalyticsTimeseries, AnalyticsTotals, AnalyticsUniques,
+};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use chrono::{Duration, Utc};
+use std::time::Instant;
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::{Mutex, RwLock};
+use tracing::info;
+
+/// Default lifetime of cached analytics responses
+const DEFAULT_CACHE_TTL_SECS: u64 = 300;
+
+/// Longest dashboard window, in hours. Requested windows are clamped to
+/// `1..=MAX_DASHBOARD_HOURS`, which also bounds the number of cache keys.
+pub const MAX_DASHBOARD_HOURS: i32 = 720;
+
+/// The window `get_dashboard` actually covers for `hours`
+pub fn dashboard_hours(hours: i32) -> i32 {
+    hours.clamp(1, MAX_DASHBOARD_HOURS)
+}
+
+/// Hourly HTTP request totals for a zone
+const ZONE_ANALYTICS_QUERY: &str = r#"
+query ZoneAnalytics($zoneTag: string, $since: Time, $until: Time) {
+  viewer {
+    zones(filter: { zoneTag: $zoneTag }) {
+      httpRequests1hGroups(
+        limit: 1000
+        filter: { datetime_geq: $since, datetime_lt: $until }
+        orderBy: [datetime_ASC]
+      ) {
+        dimensions { datetime }
+        sum {
+          requests
+          cachedRequests
+          bytes
+          cachedBytes
+          threats
+          pageViews
+          encryptedRequests
+          encryptedBytes
+        }
+        uniq { uniques }
+      }
+    }
+  }
+}
+"#;
 
 pub struct AnalyticsService {
     client: Option<Arc<CloudflareClient>>,
-    #[allow(dead_code)]
     db: PgPool,
+    cache: AnalyticsCache<(&'static str, i32), Analytics>,
+    features: FeatureFlags,
 }
 
 impl AnalyticsService {
     pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
-        Self { client: Some(client), db }
+        Self {
+            client: Some(client),
+            db,
+            cache: AnalyticsCache::new(std::time::Duration::from_secs(DEFAULT_CACHE_TTL_SECS)),
+            features: FeatureFlags::default(),
+        }
     }
 
     /// Create without a configured client (for initial setup)
     pub fn new_unconfigured(db: PgPool) -> Self {
-        Self { client: None, db }
+        Self {
+            client: None,
+            db,
+            cache: AnalyticsCache::new(std::time::Duration::from_secs(DEFAULT_CACHE_TTL_SECS)),
+            features: FeatureFlags::default(),
+        }
+    }
+
+    /// Use shared feature flags
+    pub fn with_features(mut self, features: FeatureFlags) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Set how long analytics responses are served from cache
+    pub fn set_cache_ttl(&self, ttl: std::time::Duration) {
+        self.cache.set_ttl(ttl);
+    }
+
+    /// Drop all cached analytics so the next request goes upstream
+    pub async fn invalidate_analytics_cache(&self) {
+        self.cache.invalidate().await;
     }
 
     /// Get the client or return an error if not configured
     fn get_client(&self) -> CloudflareResult<&CloudflareClient> {
         self.client.as_ref()
             .map(|c| c.as_ref())
-            .ok_or_else(|| CloudflareError::ConfigError("Cloudflare not configured. Please connect your account.".to_string()))
+            .ok_or(CloudflareError::NotConfigured)
+    }
+
+    /// Run a query against the GraphQL Analytics API
+    pub async fn query_graphql<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> CloudflareResult<T> {
+        let client = self.get_client()?;
+        client.graphql(query, variables).await
     }
 
     pub async fn get_dashboard(&self, hours: i32) -> CloudflareResult<Analytics> {
+        let hours = dashboard_hours(hours);
+        self.cache
+            .get_or_fetch(("dashboard", hours), || self.fetch_dashboard(hours))
+            .await
+    }
+
+    async fn fetch_dashboard(&self, hours: i32) -> CloudflareResult<Analytics> {
         let client = self.get_client()?;
-        let since = Utc::now() - Duration::hours(hours as i64);
         let until = Utc::now();
+        let since = until - Duration::hours(hours as i64);
+
+        let variables = serde_json::json!({
+            "zoneTag": client.zone_id(),
+            "since": since.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            "until": until.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        });
+
+        let data: ZoneAnalyticsData = self.query_graphql(ZONE_ANALYTICS_QUERY, variables).await?;
+        Ok(data.into_analytics())
+    }
+
+    /// Delete logged events older than the configured retention window
+    pub async fn cleanup_old_events(&self) -> CloudflareResult<u64> {
+        if !self.features.is_enabled(ANALYTICS_CLEANUP) {
+            info!("Analytics cleanup disabled by feature flag");
+            return Ok(0);
+        }
+
+        let settings = SettingsService::new(self.db.clone()).get_extended_settings().await?;
+        let removed = self.cleanup_events_older_than(settings.analytics_retention_days).await?;
+
+        info!(
+            "Removed {} analytics events older than {} days",
+            removed, settings.analytics_retention_days
+        );
+        Ok(removed)
+    }
+
+    /// Delete cache events and analytics snapshots older than `days`
+    pub async fn cleanup_events_older_than(&self, days: u32) -> CloudflareResult<u64> {
+        let days = days as i32;
+        let mut removed = 0;
+
+        for query in [
+            "DELETE FROM cloudflare_cache_events WHERE created_at < NOW() - ($1 * INTERVAL '1 day')",
+            "DELETE FROM cloudflare_analytics_snapshots WHERE created_at < NOW() - ($1 * INTERVAL '1 day')",
+        ] {
+            let result = sqlx::query(query)
+                .bind(days)
+                .execute(&self.db)
+                .await
+                .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+            removed += result.rows_affected();
+        }
 
-        client.get_analytics(
-            &since.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
-            &until.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
-        ).await
+        Ok(removed)
     }
 
     pub async fn get_traffic_summary(&self) -> CloudflareResult<TrafficSummary> {
@@ -64,6 +203,248 @@ impl AnalyticsService {
     }
 }
 
+/// TTL cache for analytics responses.
+///
+/// Concurrent misses for the same key share a single upstream fetch: the
+/// first caller fetches while the others wait and then read its result.
+/// Expired entries are dropped whenever a new one is stored, and a key's
+/// fetch lock is dropped once nobody is waiting on it.
+pub struct AnalyticsCache<K, V> {
+    ttl_ms: AtomicU64,
+    entries: RwLock<HashMap<K, (Instant, V)>>,
+    in_flight: Mutex<HashMap<K, Arc<Mutex<()>>>>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> AnalyticsCache<K, V> {
+    pub fn new(ttl: std::time::Duration) -> Self {
+        Self {
+            ttl_ms: AtomicU64::new(ttl.as_millis() as u64),
+            entries: RwLock::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_ttl(&self, ttl: std::time::Duration) {
+        self.ttl_ms.store(ttl.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub async fn invalidate(&self) {
+        self.entries.write().await.clear();
+    }
+
+    /// Return the cached value for `key` if fresh, otherwise run `fetch` and cache its result
+    pub async fn get_or_fetch<F, Fut>(&self, key: K, fetch: F) -> CloudflareResult<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = CloudflareResult<V>>,
+    {
+        if let Some(value) = self.get_fresh(&key).await {
+            return Ok(value);
+        }
+
+        let key_lock = {
+            let mut in_flight = self.in_flight.lock().await;
+            Arc::clone(in_flight.entry(key.clone()).or_default())
+        };
+        let _guard = key_lock.lock().await;
+
+        // Another caller may have filled the entry while we waited
+        let result = match self.get_fresh(&key).await {
+            Some(value) => Ok(value),
+            None => self.fetch_and_store(&key, fetch).await,
+        };
+
+        // Only this call and the map hold the lock when nobody else is waiting
+        let mut in_flight = self.in_flight.lock().await;
+        if Arc::strong_count(&key_lock) <= 2 {
+            in_flight.remove(&key);
+        }
+
+        result
+    }
+
+    async fn fetch_and_store<F, Fut>(&self, key: &K, fetch: F) -> CloudflareResult<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = CloudflareResult<V>>,
+    {
+        let value = fetch().await?;
+        let ttl = self.ttl();
+        let mut entries = self.entries.write().await;
+        entries.retain(|_, (fetched_at, _)| fetched_at.elapsed() < ttl);
+        entries.insert(key.clone(), (Instant::now(), value.clone()));
+        Ok(value)
+    }
+
+    fn ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.ttl_ms.load(Ordering::Relaxed))
+    }
+
+    async fn get_fresh(&self, key: &K) -> Option<V> {
+        let ttl = self.ttl();
+        self.entries
+            .read()
+            .await
+            .get(key)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < ttl)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+/// `viewer` payload of the zone analytics GraphQL query
+#[derive(Debug, Clone, Deserialize)]
+pub struct ZoneAnalyticsData {
+    viewer: GraphQlViewer,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GraphQlViewer {
+    zones: Vec<GraphQlZone>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GraphQlZone {
+    #[serde(rename = "httpRequests1hGroups", default)]
+    http_requests: Vec<HttpRequestsGroup>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct HttpRequestsGroup {
+    dimensions: HttpRequestsDimensions,
+    #[serde(default)]
+    sum: HttpRequestsSum,
+    uniq: Option<HttpRequestsUniq>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct HttpRequestsDimensions {
+    datetime: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct HttpRequestsSum {
+    requests: i64,
+    cached_requests: i64,
+    bytes: i64,
+    cached_bytes: i64,
+    threats: i64,
+    page_views: i64,
+    encrypted_requests: i64,
+    encrypted_bytes: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct HttpRequestsUniq {
+    uniques: i64,
+}
+
+impl HttpRequestsSum {
+    fn add(&mut self, other: &HttpRequestsSum) {
+        self.requests += other.requests;
+        self.cached_requests += other.cached_requests;
+        self.bytes += other.bytes;
+        self.cached_bytes += other.cached_bytes;
+        self.threats += other.threats;
+        self.page_views += other.page_views;
+        self.encrypted_requests += other.encrypted_requests;
+        self.encrypted_bytes += other.encrypted_bytes;
+    }
+
+    fn requests(&self) -> AnalyticsRequests {
+        AnalyticsRequests {
+            all: self.requests,
+            cached: self.cached_requests,
+            uncached: self.requests - self.cached_requests,
+            content_type: None,
+            country: None,
+            ssl: Some(AnalyticsSsl {
+                encrypted: self.encrypted_requests,
+                unencrypted: self.requests - self.encrypted_requests,
+            }),
+            ssl_protocols: None,
+            http_status: None,
+        }
+    }
+
+    fn bandwidth(&self) -> AnalyticsBandwidth {
+        AnalyticsBandwidth {
+            all: self.bytes,
+            cached: self.cached_bytes,
+            uncached: self.bytes - self.cached_bytes,
+            content_type: None,
+            country: None,
+            ssl: Some(AnalyticsSsl {
+                encrypted: self.encrypted_bytes,
+                unencrypted: self.bytes - self.encrypted_bytes,
+            }),
+        }
+    }
+
+    fn threats(&self) -> AnalyticsThreats {
+        AnalyticsThreats {
+            all: self.threats,
+            country: None,
+            threat_type: None,
+        }
+    }
+
+    fn pageviews(&self) -> AnalyticsPageviews {
+        AnalyticsPageviews {
+            all: self.page_views,
+            search_engine: None,
+        }
+    }
+}
+
+impl ZoneAnalyticsData {
+    /// Map hourly groups into the dashboard analytics shape.
+    ///
+    /// Unique visitors are summed across hours, so a visitor seen in several
+    /// hours is counted more than once.
+    pub fn into_analytics(self) -> Analytics {
+        let groups: Vec<HttpRequestsGroup> = self
+            .viewer
+            .zones
+            .into_iter()
+            .flat_map(|zone| zone.http_requests)
+            .collect();
+
+        let mut total = HttpRequestsSum::default();
+        let mut uniques = 0;
+
+        let timeseries = groups
+            .iter()
+            .map(|group| {
+                total.add(&group.sum);
+                let group_uniques = group.uniq.as_ref().map(|u| u.uniques).unwrap_or(0);
+                uniques += group_uniques;
+
+                AnalyticsTimeseries {
+                    since: group.dimensions.datetime,
+                    until: group.dimensions.datetime + Duration::hours(1),
+                    requests: Some(group.sum.requests()),
+                    bandwidth: Some(group.sum.bandwidth()),
+                    threats: Some(group.sum.threats()),
+                    pageviews: Some(group.sum.pageviews()),
+                    uniques: Some(AnalyticsUniques { all: group_uniques }),
+                }
+            })
+            .collect();
+
+        Analytics {
+            totals: Some(AnalyticsTotals {
+                requests: Some(total.requests()),
+                bandwidth: Some(total.bandwidth()),
+                threats: Some(total.threats()),
+                pageviews: Some(total.pageviews()),
+                uniques: Some(AnalyticsUniques { all: uniques }),
+            }),
+            timeseries: Some(timeseries),
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TrafficSummary {
     pub total_requests: i64,
@@ -84,3 +465,178 @@ impl Default for crate::models::AnalyticsThreats {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GraphQlResponse;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_graphql_response_maps_into_totals() {
+        let body = serde_json::json!({
+            "data": {
+                "viewer": {
+                    "zones": [{
+                        "httpRequests1hGroups": [
+                            {
+                                "dimensions": { "datetime": "2024-01-01T00:00:00Z" },
+                                "sum": {
+                                    "requests": 100,
+                                    "cachedRequests": 60,
+                                    "bytes": 5000,
+                                    "cachedBytes": 3000,
+                                    "threats": 2,
+                                    "pageViews": 40,
+                                    "encryptedRequests": 90,
+                                    "encryptedBytes": 4500
+                                },
+                                "uniq": { "uniques": 10 }
+                            },
+                            {
+                                "dimensions": { "datetime": "2024-01-01T01:00:00Z" },
+                                "sum": {
+                                    "requests": 50,
+                                    "cachedRequests": 20,
+                                    "bytes": 1000,
+                                    "cachedBytes": 500,
+                                    "threats": 1,
+                                    "pageViews": 15,
+                                    "encryptedRequests": 50,
+                                    "encryptedBytes": 1000
+                                },
+                                "uniq": { "uniques": 5 }
+                            }
+                        ]
+                    }]
+                }
+            },
+            "errors": null
+        });
+
+        let response: GraphQlResponse<ZoneAnalyticsData> = serde_json::from_value(body).unwrap();
+        let analytics = response.data.unwrap().into_analytics();
+        let totals: AnalyticsTotals = analytics.totals.unwrap();
+
+        let requests = totals.requests.unwrap();
+        assert_eq!(requests.all, 150);
+        assert_eq!(requests.cached, 80);
+        assert_eq!(requests.uncached, 70);
+
+        let bandwidth = totals.bandwidth.unwrap();
+        assert_eq!(bandwidth.all, 6000);
+        assert_eq!(bandwidth.cached, 3500);
+
+        assert_eq!(totals.threats.unwrap().all, 3);
+        assert_eq!(totals.pageviews.unwrap().all, 55);
+        assert_eq!(totals.uniques.unwrap().all, 15);
+        assert_eq!(analytics.timeseries.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_analytics_cache_expires_after_ttl() {
+        let cache: AnalyticsCache<&str, i32> =
+            AnalyticsCache::new(std::time::Duration::from_millis(50));
+        let calls = AtomicUsize::new(0);
+        let fetch = || async {
+            Ok(calls.fetch_add(1, Ordering::SeqCst) as i32)
+        };
+
+        assert_eq!(cache.get_or_fetch("dashboard", fetch).await.unwrap(), 0);
+        assert_eq!(cache.get_or_fetch("dashboard", fetch).await.unwrap(), 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(std::time::Duration::from_millis(80)).await;
+
+        assert_eq!(cache.get_or_fetch("dashboard", fetch).await.unwrap(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        cache.invalidate().await;
+        assert_eq!(cache.get_or_fetch("dashboard", fetch).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_analytics_cache_evicts_expired_entries() {
+        let cache: AnalyticsCache<i32, i32> = AnalyticsCache::new(std::time::Duration::from_millis(50));
+        for hours in 0..100 {
+            cache.get_or_fetch(hours, || async move { Ok(hours) }).await.unwrap();
+        }
+        assert_eq!(cache.entries.read().await.len(), 100);
+        assert!(cache.in_flight.lock().await.is_empty());
+
+        tokio::time::sleep(std::time::Duration::from_millis(80)).await;
+
+        cache.get_or_fetch(100, || async { Ok(100) }).await.unwrap();
+        assert_eq!(cache.entries.read().await.len(), 1);
+
+        let failed = cache.get_or_fetch(101, || async { Err(CloudflareError::NotConfigured) }).await;
+        assert!(failed.is_err());
+        assert!(cache.in_flight.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_analytics_cache_single_flight() {
+        let cache: Arc<AnalyticsCache<&str, i32>> =
+            Arc::new(AnalyticsCache::new(std::time::Duration::from_secs(60)));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                let calls = Arc::clone(&calls);
+                tokio::spawn(async move {
+                    cache
+                        .get_or_fetch("dashboard", || async move {
+                            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            Ok(42)
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), 42);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(cache.in_flight.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_cleanup_removes_only_old_events() {
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let service = AnalyticsService::new_unconfigured(pool.clone());
+        let marker = format!("cleanup_test_{}", &uuid::Uuid::new_v4().simple().to_string()[..12]);
+
+        for age_days in [45, 31, 1, 0] {
+            sqlx::query(
+                "INSERT INTO cloudflare_cache_events (event_type, created_at) \
+                 VALUES ($1, NOW() - ($2 * INTERVAL '1 day'))",
+            )
+            .bind(&marker)
+            .bind(age_days)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let removed = service.cleanup_events_older_than(30).await.unwrap();
+        assert!(removed >= 2);
+
+        let (remaining,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM cloudflare_cache_events WHERE event_type = $1")
+                .bind(&marker)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(remaining, 2);
+
+        sqlx::query("DELETE FROM cloudflare_cache_events WHERE event_type = $1")
+            .bind(&marker)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
@@ -2,86 +2,710 @@
 
 use crate::client::CloudflareClient;
 use crate::error::{CloudflareError, CloudflareResult};
-use crate::models::PurgeResponse;
+use crate::hooks::ContentChangeEvent;
+use crate::models::{PurgeResponse, RulesetRule};
+use crate::plan::{map_plan_error, Feature};
+use crate::services::notifications::{NotificationEvent, NotificationService};
+use crate::services::zone::PlanLookup;
+use crate::utils::normalize_purge_url;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
-use std::sync::Arc;
-use tracing::info;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Ruleset phase holding the zone's cache rules
+const CACHE_SETTINGS_PHASE: &str = "http_request_cache_settings";
+
+/// Zone setting controlling the HTTP version used to connect to the origin
+const ORIGIN_MAX_HTTP_VERSION: &str = "origin_max_http_version";
+
+/// Cache setting storing cached content in R2 once it leaves the edge
+const CACHE_RESERVE: &str = "cache_reserve";
+
+/// Argo setting turning tiered caching on
+const TIERED_CACHING: &str = "tiered_caching";
+
+/// Cache setting letting Cloudflare pick upper tiers for tiered caching
+const SMART_TOPOLOGY: &str = "tiered_cache_smart_topology_enable";
+
+/// Description identifying the cache rule managed by this plugin
+const RESPECT_ORIGIN_RULE_DESCRIPTION: &str = "RustPress: respect origin cache headers";
+/// Separates the managed rule's description from the ids of the rules it disabled
+const DISABLED_RULES_MARKER: &str = " (disabled rules: ";
+
+/// How long an idempotency key is remembered after a purge
+pub const PURGE_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(300);
+
+/// How long a purge-everything confirmation token stays valid
+pub const PURGE_ALL_CONFIRMATION_WINDOW: Duration = Duration::from_secs(60);
+
+/// How long a rendered content's tags are remembered before they're written again
+pub const TAG_REGISTRATION_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Requests and bytes in a time window from the adaptive dataset, grouped
+/// by cache status and a time dimension substituted for `{dimension}`, at
+/// most `{limit}` groups
+const CACHE_TIMESERIES_QUERY: &str = r#"
+query CacheTimeseries($zoneTag: string, $since: Time, $until: Time) {
+  viewer {
+    zones(filter: { zoneTag: $zoneTag }) {
+      httpRequestsAdaptiveGroups(
+        limit: {limit}
+        filter: { datetime_geq: $since, datetime_lt: $until }
+      ) {
+        count
+        dimensions { ts: {dimension} cacheStatus }
+        sum { edgeResponseBytes }
+      }
+    }
+  }
+}
+"#;
+
+/// Values remembered by key for a limited window, used to make repeated
+/// purge requests (e.g. a redelivered event) a no-op
+#[derive(Debug)]
+pub struct IdempotencyCache<T> {
+    entries: Mutex<HashMap<String, (Instant, T)>>,
+    window: Duration,
+}
+
+impl<T: Clone> IdempotencyCache<T> {
+    pub fn new(window: Duration) -> Self {
+        Self { entries: Mutex::new(HashMap::new()), window }
+    }
+
+    /// Value stored for `key`, if it was stored within the window
+    pub fn get(&self, key: &str) -> Option<T> {
+        self.get_at(key, Instant::now())
+    }
+
+    /// Remember `value` for `key`
+    pub fn insert(&self, key: &str, value: T) {
+        self.insert_at(key, value, Instant::now());
+    }
+
+    /// Remember `value` for `key` unless the key was already seen within the window.
+    ///
+    /// Returns whether the value was stored.
+    pub fn insert_new(&self, key: &str, value: T) -> bool {
+        self.insert_new_at(key, value, Instant::now())
+    }
+
+    /// Remove and return the value stored for `key` within the window
+    pub fn take(&self, key: &str) -> Option<T> {
+        self.take_at(key, Instant::now())
+    }
+
+    fn take_at(&self, key: &str, now: Instant) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        self.prune(&mut entries, now);
+        entries.remove(key).map(|(_, value)| value)
+    }
+
+    fn get_at(&self, key: &str, now: Instant) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        self.prune(&mut entries, now);
+        entries.get(key).map(|(_, value)| value.clone())
+    }
+
+    fn insert_at(&self, key: &str, value: T, now: Instant) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        self.prune(&mut entries, now);
+        entries.insert(key.to_string(), (now, value));
+    }
+
+    fn insert_new_at(&self, key: &str, value: T, now: Instant) -> bool {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        self.prune(&mut entries, now);
+        if entries.contains_key(key) {
+            return false;
+        }
+        entries.insert(key.to_string(), (now, value));
+        true
+    }
+
+    fn prune(&self, entries: &mut HashMap<String, (Instant, T)>, now: Instant) {
+        entries.retain(|_, (stored_at, _)| now.duration_since(*stored_at) < self.window);
+    }
+}
 
 /// Cache management service
 pub struct CacheService {
     client: Option<Arc<CloudflareClient>>,
     db: PgPool,
+    notifications: Option<Arc<NotificationService>>,
+    /// Results of recent purges made with an idempotency key
+    recent_purges: IdempotencyCache<PurgeResponse>,
+    /// Zone name, fetched on the first URL purge
+    zone_name: Mutex<Option<String>>,
+    /// Outstanding single-use tokens for confirming a purge of everything
+    purge_all_confirmations: IdempotencyCache<()>,
+    /// Content tags recently written to the registry
+    registered_tags: IdempotencyCache<()>,
+    /// Zone plan tier, for refusing plan-gated settings up front
+    plan: PlanLookup,
 }
 
 impl CacheService {
     pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
-        Self { client: Some(client), db }
+        Self {
+            plan: PlanLookup::new(Some(Arc::clone(&client))),
+            client: Some(client),
+            db,
+            notifications: None,
+            recent_purges: IdempotencyCache::new(PURGE_IDEMPOTENCY_WINDOW),
+            zone_name: Mutex::new(None),
+            purge_all_confirmations: IdempotencyCache::new(PURGE_ALL_CONFIRMATION_WINDOW),
+            registered_tags: IdempotencyCache::new(TAG_REGISTRATION_WINDOW),
+        }
     }
 
     /// Create without a configured client (for initial setup)
     pub fn new_unconfigured(db: PgPool) -> Self {
-        Self { client: None, db }
+        Self {
+            client: None,
+            db,
+            notifications: None,
+            recent_purges: IdempotencyCache::new(PURGE_IDEMPOTENCY_WINDOW),
+            zone_name: Mutex::new(None),
+            purge_all_confirmations: IdempotencyCache::new(PURGE_ALL_CONFIRMATION_WINDOW),
+            registered_tags: IdempotencyCache::new(TAG_REGISTRATION_WINDOW),
+            plan: PlanLookup::default(),
+        }
+    }
+
+    /// Share another service's plan lookup, and with it the cached tier
+    pub fn with_plan_lookup(mut self, plan: PlanLookup) -> Self {
+        self.plan = plan;
+        self
+    }
+
+    /// Send webhook notifications for full-cache purges
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
     }
 
     /// Get the client or return an error if not configured
     fn get_client(&self) -> CloudflareResult<&CloudflareClient> {
         self.client.as_ref()
             .map(|c| c.as_ref())
-            .ok_or_else(|| CloudflareError::ConfigError("Cloudflare not configured. Please connect your account.".to_string()))
+            .ok_or(CloudflareError::NotConfigured)
     }
 
-    /// Purge entire cache
-    pub async fn purge_all(&self) -> CloudflareResult<PurgeResponse> {
+    /// Result of an earlier purge made with the same idempotency key
+    fn prior_purge(&self, operation: &str, idempotency_key: Option<&str>) -> Option<PurgeResponse> {
+        let key = format!("{}:{}", operation, idempotency_key?);
+        let prior = self.recent_purges.get(&key);
+        if prior.is_some() {
+            debug!("Skipping repeated {} with idempotency key {}", operation, key);
+        }
+        prior
+    }
+
+    fn remember_purge(&self, operation: &str, idempotency_key: Option<&str>, result: &PurgeResponse) {
+        if let Some(key) = idempotency_key {
+            self.recent_purges.insert(&format!("{}:{}", operation, key), result.clone());
+        }
+    }
+
+    /// Purge entire cache.
+    ///
+    /// A repeated `idempotency_key` within `PURGE_IDEMPOTENCY_WINDOW` returns
+    /// the earlier result without purging again; the same goes for the other
+    /// purge methods.
+    pub async fn purge_all(&self, idempotency_key: Option<&str>) -> CloudflareResult<PurgeResponse> {
+        if let Some(prior) = self.prior_purge("purge_all", idempotency_key) {
+            return Ok(prior);
+        }
         let client = self.get_client()?;
         info!("Purging all cache for zone {}", client.zone_id());
-        let result = client.purge_all_cache().await?;
+        let result = client.metrics().record_purge(client.purge_all_cache().await)?;
+        self.remember_purge("purge_all", idempotency_key, &result);
         self.log_purge_event("purge_all", None).await?;
+        if let Some(notifications) = &self.notifications {
+            notifications.notify_in_background(NotificationEvent::CachePurgedAll {
+                zone_id: client.zone_id().to_string(),
+            });
+        }
         Ok(result)
     }
 
-    /// Purge cache by URLs
-    pub async fn purge_urls(&self, urls: Vec<String>) -> CloudflareResult<PurgeResponse> {
+    /// Name of the configured zone, fetched once and cached
+    async fn zone_name(&self) -> CloudflareResult<String> {
+        if let Some(name) = self.zone_name.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+            return Ok(name);
+        }
+        let name = self.get_client()?.get_zone().await?.name;
+        *self.zone_name.lock().unwrap_or_else(|e| e.into_inner()) = Some(name.clone());
+        Ok(name)
+    }
+
+    /// Issue a single-use token that `purge_all_confirmed` accepts for
+    /// `PURGE_ALL_CONFIRMATION_WINDOW`
+    pub fn request_purge_all_confirmation(&self) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        self.purge_all_confirmations.insert(&token, ());
+        token
+    }
+
+    /// Purge everything, provided `token` came from `request_purge_all_confirmation`
+    /// and hasn't expired or been used yet
+    pub async fn purge_all_confirmed(
+        &self,
+        token: &str,
+        idempotency_key: Option<&str>,
+    ) -> CloudflareResult<PurgeResponse> {
+        if self.purge_all_confirmations.take(token).is_none() {
+            return Err(CloudflareError::ValidationError(
+                "Purge confirmation token is invalid, expired or already used".to_string(),
+            ));
+        }
+        self.purge_all(idempotency_key).await
+    }
+
+    /// Purge cache by URLs.
+    ///
+    /// Cloudflare silently ignores URLs outside the zone, so those are left
+    /// out and returned in `skipped_urls` instead. Fails if none are left.
+    pub async fn purge_urls(
+        &self,
+        urls: Vec<String>,
+        idempotency_key: Option<&str>,
+    ) -> CloudflareResult<PurgeResponse> {
+        if let Some(prior) = self.prior_purge("purge_urls", idempotency_key) {
+            return Ok(prior);
+        }
         let client = self.get_client()?;
+        let zone_name = self.zone_name().await?;
+        let (urls, skipped_urls) = partition_zone_urls(urls, &zone_name);
+        if !skipped_urls.is_empty() {
+            warn!("Skipping {} URLs outside zone {}: {:?}", skipped_urls.len(), zone_name, skipped_urls);
+        }
+        if urls.is_empty() {
+            return Err(CloudflareError::ValidationError(format!(
+                "None of the URLs are in zone {}: {}",
+                zone_name,
+                skipped_urls.join(", ")
+            )));
+        }
+
         info!("Purging {} URLs from cache", urls.len());
-        let result = client.purge_cache_by_urls(urls.clone()).await?;
-        self.log_purge_event("purge_urls", Some(serde_json::json!({ "urls": urls })))
-            .await?;
+        let mut result = client.metrics().record_purge(client.purge_cache_by_urls(urls.clone()).await)?;
+        result.skipped_urls = skipped_urls;
+        self.remember_purge("purge_urls", idempotency_key, &result);
+        let mut details = serde_json::json!({ "urls": urls });
+        if !result.skipped_urls.is_empty() {
+            details["skipped_urls"] = serde_json::json!(result.skipped_urls);
+        }
+        self.log_purge_event("purge_urls", Some(details)).await?;
         Ok(result)
     }
 
+    /// Purge a single absolute URL, which must belong to the configured zone
+    pub async fn purge_url(&self, url: &str, idempotency_key: Option<&str>) -> CloudflareResult<PurgeResponse> {
+        check_url_in_zone(url, &self.zone_name().await?)?;
+        self.purge_urls(vec![url.to_string()], idempotency_key).await
+    }
+
     /// Purge cache by tags
-    pub async fn purge_tags(&self, tags: Vec<String>) -> CloudflareResult<PurgeResponse> {
+    pub async fn purge_tags(
+        &self,
+        tags: Vec<String>,
+        idempotency_key: Option<&str>,
+    ) -> CloudflareResult<PurgeResponse> {
+        if let Some(prior) = self.prior_purge("purge_tags", idempotency_key) {
+            return Ok(prior);
+        }
         let client = self.get_client()?;
         info!("Purging cache by tags: {:?}", tags);
-        let result = client.purge_cache_by_tags(tags.clone()).await?;
+        let result = client.metrics().record_purge(
+            client
+                .purge_cache_by_tags(tags.clone())
+                .await
+                .map_err(|e| map_plan_error(e, Feature::TagPurge)),
+        )?;
+        self.remember_purge("purge_tags", idempotency_key, &result);
         self.log_purge_event("purge_tags", Some(serde_json::json!({ "tags": tags })))
             .await?;
         Ok(result)
     }
 
+    /// Purge everything cached for some hostnames (Enterprise only).
+    ///
+    /// Each host must be the zone's apex or one of its subdomains; if any
+    /// isn't, nothing is purged.
+    pub async fn purge_hosts(
+        &self,
+        hosts: Vec<String>,
+        idempotency_key: Option<&str>,
+    ) -> CloudflareResult<PurgeResponse> {
+        if let Some(prior) = self.prior_purge("purge_hosts", idempotency_key) {
+            return Ok(prior);
+        }
+        if hosts.is_empty() {
+            return Err(CloudflareError::ValidationError("No hostnames to purge".to_string()));
+        }
+        let client = self.get_client()?;
+        let zone_name = self.zone_name().await?;
+        let mut checked = Vec::with_capacity(hosts.len());
+        let mut rejected = Vec::new();
+        for host in &hosts {
+            match check_host_in_zone(host, &zone_name) {
+                Ok(host) => checked.push(host),
+                Err(CloudflareError::ValidationError(reason)) => rejected.push(reason),
+                Err(e) => return Err(e),
+            }
+        }
+        if !rejected.is_empty() {
+            return Err(CloudflareError::ValidationError(rejected.join("; ")));
+        }
+        let hosts = checked;
+
+        info!("Purging cache by hosts: {:?}", hosts);
+        let result = client.metrics().record_purge(
+            client
+                .purge_cache_by_hosts(hosts.clone())
+                .await
+                .map_err(|e| map_plan_error(e, Feature::HostPurge)),
+        )?;
+        self.remember_purge("purge_hosts", idempotency_key, &result);
+        self.log_purge_event("purge_hosts", Some(serde_json::json!({ "hosts": hosts })))
+            .await?;
+        Ok(result)
+    }
+
     /// Purge cache by prefix
     pub async fn purge_prefix(&self, prefixes: Vec<String>) -> CloudflareResult<PurgeResponse> {
         let client = self.get_client()?;
         info!("Purging cache by prefixes: {:?}", prefixes);
-        let result = client.purge_cache_by_prefix(prefixes.clone()).await?;
+        let result = client.metrics().record_purge(client.purge_cache_by_prefix(prefixes.clone()).await)?;
         self.log_purge_event("purge_prefix", Some(serde_json::json!({ "prefixes": prefixes })))
             .await?;
         Ok(result)
     }
 
+    /// Record that responses for a piece of content carry a cache tag
+    pub async fn register_tag(&self, content_key: &str, tag: &str) -> CloudflareResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cloudflare_cache_tags (content_key, tag, created_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (content_key, tag) DO NOTHING
+            "#,
+        )
+        .bind(content_key)
+        .bind(tag)
+        .execute(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Record the cache tags a piece of content is rendered with.
+    ///
+    /// Tags already written within `TAG_REGISTRATION_WINDOW` aren't written
+    /// again, so this is cheap to call on every render.
+    pub async fn register_content_tags(
+        &self,
+        content_type: &str,
+        content_id: &str,
+        tags: &[String],
+    ) -> CloudflareResult<()> {
+        let content_key = content_cache_key(content_type, content_id);
+        for tag in tags {
+            let key = format!("{}|{}", content_key, tag);
+            if self.registered_tags.get(&key).is_some() {
+                continue;
+            }
+            self.register_tag(&content_key, tag).await?;
+            self.registered_tags.insert(&key, ());
+        }
+        Ok(())
+    }
+
+    /// Cache tags registered for a piece of content
+    pub async fn content_tags(&self, content_key: &str) -> CloudflareResult<Vec<String>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"SELECT tag FROM cloudflare_cache_tags WHERE content_key = $1 ORDER BY tag"#,
+        )
+        .bind(content_key)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(rows.into_iter().map(|(tag,)| tag).collect())
+    }
+
+    /// Purge the cache tags registered for a piece of content.
+    ///
+    /// Returns `None` when the content has no tags, or the zone can't purge by
+    /// tag because it isn't on an Enterprise plan; callers should purge URLs instead.
+    pub async fn purge_content_tags(
+        &self,
+        content_type: &str,
+        content_id: &str,
+    ) -> CloudflareResult<Option<PurgeResponse>> {
+        let tags = self.content_tags(&content_cache_key(content_type, content_id)).await?;
+        if tags.is_empty() {
+            return Ok(None);
+        }
+
+        match self.purge_tags(tags, None).await {
+            Ok(result) => Ok(Some(result)),
+            Err(e) if e.is_enterprise_only() => {
+                warn!("Purge by cache tag unavailable ({}), falling back to URL purge", e);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Most recent auto-purge dry run, with the URLs it would have purged
+    pub async fn last_dry_run(&self) -> CloudflareResult<Option<serde_json::Value>> {
+        let row: Option<(Option<serde_json::Value>, Option<DateTime<Utc>>)> = sqlx::query_as(
+            r#"
+            SELECT details, created_at FROM cloudflare_cache_events
+            WHERE event_type = 'dry_run_purge'
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(row.map(|(details, created_at)| {
+            let mut result = details.unwrap_or_else(|| serde_json::json!({}));
+            result["created_at"] = serde_json::json!(created_at);
+            result
+        }))
+    }
+
+    /// Logged purge events, newest first, optionally only those of one `event_type`
+    pub async fn list_purge_events(
+        &self,
+        limit: i64,
+        offset: i64,
+        event_type_filter: Option<&str>,
+    ) -> CloudflareResult<Vec<PurgeEvent>> {
+        let rows: Vec<PurgeEventRow> = sqlx::query_as(
+            r#"
+            SELECT id, event_type, details, created_at FROM cloudflare_cache_events
+            WHERE $1::TEXT IS NULL OR event_type = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(event_type_filter)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, event_type, details, created_at)| PurgeEvent {
+                id,
+                details: details.map(|d| PurgeEventDetails::parse(&event_type, d)),
+                event_type,
+                created_at,
+            })
+            .collect())
+    }
+
+    /// Number of logged purge events matching `event_type_filter`
+    pub async fn count_purge_events(&self, event_type_filter: Option<&str>) -> CloudflareResult<i64> {
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM cloudflare_cache_events WHERE $1::TEXT IS NULL OR event_type = $1",
+        )
+        .bind(event_type_filter)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(count)
+    }
+
     /// Auto-purge on content update
     pub async fn auto_purge_post(&self, post_url: &str) -> CloudflareResult<()> {
         // Purge the post URL and related URLs
-        let urls_to_purge = vec![
-            post_url.to_string(),
-            format!("{}/", post_url),
-            // Also purge homepage and archive pages
-        ];
+        let urls_to_purge = normalize_purge_url(post_url, "");
+        // Also purge homepage and archive pages
 
-        self.purge_urls(urls_to_purge).await?;
+        self.purge_urls(urls_to_purge, None).await?;
         Ok(())
     }
 
+    /// Whether the cache rule honoring origin `Cache-Control`/`Expires` headers is active
+    pub async fn get_respect_origin_headers(&self) -> CloudflareResult<bool> {
+        let rules = self.get_cache_rules().await?;
+        Ok(rules
+            .iter()
+            .any(|r| is_respect_origin_rule(r) && r.enabled != Some(false)))
+    }
+
+    /// Enable or disable the cache rule honoring origin cache headers.
+    ///
+    /// Enabling it disables any cache rules that force a fixed edge TTL, since
+    /// the two are mutually exclusive; disabling it switches those rules back
+    /// on. Returns a warning for each rule changed.
+    pub async fn set_respect_origin_headers(&self, enabled: bool) -> CloudflareResult<Vec<String>> {
+        let client = self.get_client()?;
+        let rules = self.get_cache_rules().await?;
+        let (rules, warnings) = apply_respect_origin_headers(rules, enabled);
+
+        for warning in &warnings {
+            warn!("{}", warning);
+        }
+
+        client.update_phase_ruleset(CACHE_SETTINGS_PHASE, rules).await?;
+        info!("Respect origin cache headers {}", if enabled { "enabled" } else { "disabled" });
+
+        Ok(warnings)
+    }
+
+    /// Crawler Hints and HTTP/2-to-origin state
+    pub async fn get_advanced_performance(&self) -> CloudflareResult<AdvancedPerformanceSettings> {
+        let client = self.get_client()?;
+        let crawler_hints = client.get_crawler_hints().await?;
+        let origin_version = client.get_zone_setting(ORIGIN_MAX_HTTP_VERSION).await?;
+
+        Ok(AdvancedPerformanceSettings {
+            crawler_hints,
+            http2_to_origin: origin_version.value.as_str() == Some(origin_http_version(true)),
+        })
+    }
+
+    /// Enable or disable Crawler Hints
+    pub async fn set_crawler_hints(&self, enabled: bool) -> CloudflareResult<bool> {
+        let client = self.get_client()?;
+        let enabled = client.set_crawler_hints(enabled).await?;
+        info!("Crawler Hints {}", if enabled { "enabled" } else { "disabled" });
+        Ok(enabled)
+    }
+
+    /// Enable or disable HTTP/2 connections to the origin.
+    ///
+    /// Fails with `PermissionDenied` if the zone's plan doesn't allow changing it.
+    pub async fn set_http2_to_origin(&self, enabled: bool) -> CloudflareResult<bool> {
+        let client = self.get_client()?;
+        let current = client.get_zone_setting(ORIGIN_MAX_HTTP_VERSION).await?;
+        if !current.editable {
+            return Err(CloudflareError::PermissionDenied(
+                "HTTP/2 to Origin is not available on this zone's plan".to_string(),
+            ));
+        }
+
+        let updated = client
+            .update_zone_setting(ORIGIN_MAX_HTTP_VERSION, serde_json::json!(origin_http_version(enabled)))
+            .await?;
+        info!("HTTP/2 to Origin {}", if enabled { "enabled" } else { "disabled" });
+        Ok(updated.value.as_str() == Some(origin_http_version(true)))
+    }
+
+    /// Whether Cache Reserve is on
+    pub async fn get_cache_reserve(&self) -> CloudflareResult<bool> {
+        let setting = self.get_client()?.get_cache_setting(CACHE_RESERVE).await?;
+        Ok(setting.value == "on")
+    }
+
+    /// Turn Cache Reserve on or off. Turning it on needs a Business plan or higher.
+    pub async fn set_cache_reserve(&self, enabled: bool) -> CloudflareResult<bool> {
+        let client = self.get_client()?;
+        if enabled {
+            self.plan.require_feature(Feature::CacheReserve).await?;
+        }
+
+        let setting = client
+            .set_cache_setting(CACHE_RESERVE, enabled)
+            .await
+            .map_err(|e| map_plan_error(e, Feature::CacheReserve))?;
+        info!("Cache Reserve {}", if enabled { "enabled" } else { "disabled" });
+        Ok(setting.value == "on")
+    }
+
+    /// Current tiered cache topology.
+    ///
+    /// A custom topology can't be told apart from generic through the API,
+    /// so it reads back as generic.
+    pub async fn get_tiered_cache_topology(&self) -> CloudflareResult<TieredCacheTopology> {
+        let client = self.get_client()?;
+        let (tiered, smart) = futures::try_join!(
+            client.get_argo_setting(TIERED_CACHING),
+            client.get_cache_setting(SMART_TOPOLOGY),
+        )?;
+
+        Ok(match (tiered.value == "on", smart.value == "on") {
+            (false, _) => TieredCacheTopology::Off,
+            (true, true) => TieredCacheTopology::Smart,
+            (true, false) => TieredCacheTopology::Generic,
+        })
+    }
+
+    /// Switch tiered caching to `topology`.
+    ///
+    /// Smart topology is layered on tiered caching, so tiered caching is
+    /// turned on before it and off after it. A custom topology is one
+    /// Cloudflare sets up for Enterprise zones; choosing it turns tiered
+    /// caching on without smart topology so the custom one applies.
+    pub async fn set_tiered_cache_topology(&self, topology: TieredCacheTopology) -> CloudflareResult<TieredCacheTopology> {
+        let client = self.get_client()?;
+        if topology == TieredCacheTopology::Custom {
+            self.plan.require_feature(Feature::CustomTieredCacheTopology).await?;
+        }
+
+        let set_tiered = |enabled| async move {
+            client
+                .set_argo_setting(TIERED_CACHING, enabled)
+                .await
+                .map_err(|e| map_plan_error(e, Feature::TieredCaching))
+        };
+        let set_smart = |enabled| async move {
+            client
+                .set_cache_setting(SMART_TOPOLOGY, enabled)
+                .await
+                .map_err(|e| map_plan_error(e, Feature::TieredCaching))
+        };
+
+        match topology {
+            TieredCacheTopology::Off => {
+                set_smart(false).await?;
+                set_tiered(false).await?;
+            }
+            TieredCacheTopology::Generic | TieredCacheTopology::Custom => {
+                set_tiered(true).await?;
+                set_smart(false).await?;
+            }
+            TieredCacheTopology::Smart => {
+                set_tiered(true).await?;
+                set_smart(true).await?;
+            }
+        }
+
+        info!("Tiered cache topology set to {}", topology.as_str());
+        Ok(topology)
+    }
+
+    /// Current zone cache rules, empty if none have been created yet.
+    ///
+    /// Cloudflare answers 404 for a zone without a cache rules entrypoint,
+    /// which the client reports as `NotFound`; that means no rules rather
+    /// than a failure.
+    async fn get_cache_rules(&self) -> CloudflareResult<Vec<RulesetRule>> {
+        let client = self.get_client()?;
+        Ok(client
+            .find_phase_ruleset(CACHE_SETTINGS_PHASE)
+            .await?
+            .map(|ruleset| ruleset.rules)
+            .unwrap_or_default())
+    }
+
     /// Log purge event to database
     async fn log_purge_event(
         &self,
@@ -135,6 +759,506 @@ impl CacheService {
             },
         })
     }
+
+    /// Cache hit ratio and bytes over the last `hours`, one point per `bucket`.
+    ///
+    /// The adaptive dataset only answers queries spanning a day or less and
+    /// returns a limited number of groups, so the window is split into chunks
+    /// short enough for every group to fit (see `timeseries_chunk`), fetched
+    /// concurrently and merged; a bucket straddling two chunks is summed.
+    pub async fn get_cache_stats_timeseries(
+        &self,
+        hours: i32,
+        bucket: CacheStatsBucket,
+    ) -> CloudflareResult<Vec<CacheStatsPoint>> {
+        if !(1..=MAX_CACHE_TIMESERIES_HOURS).contains(&hours) {
+            return Err(CloudflareError::ValidationError(format!(
+                "hours must be between 1 and {}",
+                MAX_CACHE_TIMESERIES_HOURS
+            )));
+        }
+        if hours as i64 * 3600 / bucket.seconds() > MAX_CACHE_TIMESERIES_POINTS {
+            return Err(CloudflareError::ValidationError(format!(
+                "{} hours of {} buckets is more than {} points; use a larger bucket",
+                hours,
+                bucket.as_str(),
+                MAX_CACHE_TIMESERIES_POINTS
+            )));
+        }
+        let client = self.get_client()?;
+        let query = CACHE_TIMESERIES_QUERY
+            .replace("{dimension}", bucket.dimension())
+            .replace("{limit}", &CACHE_TIMESERIES_GROUP_LIMIT.to_string());
+
+        let until = Utc::now();
+        let since = until - chrono::Duration::hours(hours as i64);
+        let chunk = timeseries_chunk(bucket);
+        let chunks = (0..)
+            .map(|i| since + chunk * i)
+            .take_while(|start| *start < until)
+            .map(|start| {
+                let end = (start + chunk).min(until);
+                let variables = serde_json::json!({
+                    "zoneTag": client.zone_id(),
+                    "since": start.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                    "until": end.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                });
+                client.graphql::<CacheTimeseriesData>(&query, variables)
+            });
+        let results = futures::future::try_join_all(chunks).await?;
+
+        let mut groups = Vec::new();
+        for data in results {
+            let before = groups.len();
+            groups.extend(data.into_groups());
+            if (groups.len() - before) as i64 >= CACHE_TIMESERIES_GROUP_LIMIT {
+                warn!(
+                    "Cache timeseries chunk reached the {} group limit; some buckets may be short",
+                    CACHE_TIMESERIES_GROUP_LIMIT
+                );
+            }
+        }
+        Ok(cache_stats_points(groups))
+    }
+}
+
+/// How requests that miss the edge reach the origin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TieredCacheTopology {
+    /// Every edge data center goes to the origin itself
+    Off,
+    /// Any of Cloudflare's data centers can act as an upper tier
+    Generic,
+    /// Cloudflare picks the upper tiers closest to the origin
+    Smart,
+    /// Upper tiers chosen with Cloudflare for an Enterprise zone
+    Custom,
+}
+
+impl TieredCacheTopology {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Generic => "generic",
+            Self::Smart => "smart",
+            Self::Custom => "custom",
+        }
+    }
+}
+
+/// Longest window a cache timeseries can cover
+pub const MAX_CACHE_TIMESERIES_HOURS: i32 = 24 * 7;
+
+/// Most points a cache timeseries may return
+pub const MAX_CACHE_TIMESERIES_POINTS: i64 = 2000;
+
+/// Most groups the adaptive dataset returns for one query
+const CACHE_TIMESERIES_GROUP_LIMIT: i64 = 10_000;
+
+/// Distinct cache statuses a bucket can be split into, with room to spare
+const MAX_CACHE_STATUSES: i64 = 16;
+
+/// Longest span one timeseries query may cover: a day, or less when a day
+/// of `bucket`s could be split into more groups than one query returns.
+/// Always a whole number of buckets.
+fn timeseries_chunk(bucket: CacheStatsBucket) -> chrono::Duration {
+    let buckets = (CACHE_TIMESERIES_GROUP_LIMIT / MAX_CACHE_STATUSES).min(24 * 3600 / bucket.seconds());
+    chrono::Duration::seconds(buckets * bucket.seconds())
+}
+
+/// Cache statuses counted as served from cache
+const CACHED_STATUSES: &[&str] = &["hit", "stale", "updating", "revalidated"];
+
+/// Width of the time buckets in a cache timeseries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatsBucket {
+    Minute,
+    FiveMinutes,
+    FifteenMinutes,
+    #[default]
+    Hour,
+    Day,
+}
+
+impl CacheStatsBucket {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Minute => "minute",
+            Self::FiveMinutes => "five_minutes",
+            Self::FifteenMinutes => "fifteen_minutes",
+            Self::Hour => "hour",
+            Self::Day => "day",
+        }
+    }
+
+    pub fn seconds(self) -> i64 {
+        match self {
+            Self::Minute => 60,
+            Self::FiveMinutes => 5 * 60,
+            Self::FifteenMinutes => 15 * 60,
+            Self::Hour => 3600,
+            Self::Day => 24 * 3600,
+        }
+    }
+
+    /// Adaptive dataset dimension the bucket groups by
+    fn dimension(self) -> &'static str {
+        match self {
+            Self::Minute => "datetimeMinute",
+            Self::FiveMinutes => "datetimeFiveMinutes",
+            Self::FifteenMinutes => "datetimeFifteenMinutes",
+            Self::Hour => "datetimeHour",
+            Self::Day => "date",
+        }
+    }
+}
+
+/// One bucket of a cache timeseries
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct CacheStatsPoint {
+    /// Start of the bucket
+    pub ts: DateTime<Utc>,
+    /// Percentage of requests served from cache
+    pub hit_ratio: f64,
+    pub bytes_cached: i64,
+    pub bytes_total: i64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CacheTimeseriesData {
+    viewer: CacheTimeseriesViewer,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CacheTimeseriesViewer {
+    zones: Vec<CacheTimeseriesZone>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CacheTimeseriesZone {
+    #[serde(default, rename = "httpRequestsAdaptiveGroups")]
+    groups: Vec<CacheTimeseriesGroup>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CacheTimeseriesGroup {
+    count: i64,
+    dimensions: CacheTimeseriesDimensions,
+    sum: CacheTimeseriesSum,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheTimeseriesDimensions {
+    ts: String,
+    cache_status: String,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheTimeseriesSum {
+    #[serde(default)]
+    edge_response_bytes: i64,
+}
+
+impl CacheTimeseriesData {
+    fn into_groups(self) -> impl Iterator<Item = CacheTimeseriesGroup> {
+        self.viewer.zones.into_iter().flat_map(|zone| zone.groups)
+    }
+}
+
+/// Fold groups keyed by bucket and cache status into one point per bucket,
+/// oldest first. Groups with an unreadable timestamp are dropped.
+fn cache_stats_points(groups: impl IntoIterator<Item = CacheTimeseriesGroup>) -> Vec<CacheStatsPoint> {
+    // (requests, cached requests, bytes, cached bytes)
+    let mut buckets: std::collections::BTreeMap<DateTime<Utc>, (i64, i64, i64, i64)> = Default::default();
+    for group in groups {
+        let Some(ts) = parse_bucket_start(&group.dimensions.ts) else {
+            warn!("Skipping cache timeseries group with timestamp {:?}", group.dimensions.ts);
+            continue;
+        };
+        let bucket = buckets.entry(ts).or_default();
+        let bytes = group.sum.edge_response_bytes;
+        bucket.0 += group.count;
+        bucket.2 += bytes;
+        if CACHED_STATUSES.contains(&group.dimensions.cache_status.as_str()) {
+            bucket.1 += group.count;
+            bucket.3 += bytes;
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(ts, (requests, cached, bytes_total, bytes_cached))| CacheStatsPoint {
+            ts,
+            hit_ratio: if requests > 0 { (cached as f64 / requests as f64) * 100.0 } else { 0.0 },
+            bytes_cached,
+            bytes_total,
+        })
+        .collect()
+}
+
+/// Bucket start as returned by the API: a timestamp, or a date for daily buckets
+fn parse_bucket_start(ts: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(ts)
+        .map(|ts| ts.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(ts, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|midnight| midnight.and_utc())
+        })
+}
+
+/// Check `url` is an absolute http(s) URL on `zone_name` or one of its subdomains
+pub fn check_url_in_zone(url: &str, zone_name: &str) -> CloudflareResult<()> {
+    let parsed = url::Url::parse(url)
+        .map_err(|_| CloudflareError::ValidationError(format!("Invalid URL: {}", url)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(CloudflareError::ValidationError(format!("URL must use http or https: {}", url)));
+    }
+
+    let host = parsed.host_str().unwrap_or_default();
+    if !host_in_zone(host, zone_name) {
+        return Err(CloudflareError::ValidationError(format!(
+            "URL {} is not in zone {}",
+            url,
+            zone_name.trim_end_matches('.').to_lowercase()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check a hostname to purge is the zone's apex or one of its subdomains,
+/// returning it lowercased. Schemes, paths and ports aren't accepted.
+pub fn check_host_in_zone(host: &str, zone_name: &str) -> CloudflareResult<String> {
+    let host = host.trim().trim_end_matches('.').to_lowercase();
+    let valid = !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty() && label.len() <= 63 && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if !valid {
+        return Err(CloudflareError::ValidationError(format!("Invalid hostname: {}", host)));
+    }
+    if !host_in_zone(&host, zone_name) {
+        return Err(CloudflareError::ValidationError(format!(
+            "Host {} is not in zone {}",
+            host,
+            zone_name.trim_end_matches('.').to_lowercase()
+        )));
+    }
+
+    Ok(host)
+}
+
+/// Whether a lowercase host is the zone's apex or one of its subdomains
+fn host_in_zone(host: &str, zone_name: &str) -> bool {
+    let host = host.trim_end_matches('.');
+    let zone = zone_name.trim_end_matches('.').to_lowercase();
+    host == zone || host.strip_suffix(&zone).is_some_and(|sub| sub.ends_with('.'))
+}
+
+/// Split URLs into those in `zone_name` and those that aren't
+pub fn partition_zone_urls(urls: Vec<String>, zone_name: &str) -> (Vec<String>, Vec<String>) {
+    urls.into_iter()
+        .partition(|url| check_url_in_zone(url, zone_name).is_ok())
+}
+
+/// Registry key for a piece of content, e.g. `post:42`
+pub fn content_cache_key(content_type: &str, content_id: &str) -> String {
+    format!("{}:{}", content_type, content_id)
+}
+
+/// Default cache tag to send in the `Cache-Tag` header for a piece of content
+pub fn content_cache_tag(content_type: &str, content_id: &str) -> String {
+    format!("rp-{}-{}", content_type, content_id)
+}
+
+/// Tags to send in the `Cache-Tag` header for a piece of content: its
+/// default tag followed by `extra_tags`, without blanks or duplicates
+pub fn content_cache_tags(content_type: &str, content_id: &str, extra_tags: &[String]) -> Vec<String> {
+    let mut tags = vec![content_cache_tag(content_type, content_id)];
+    for tag in extra_tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Cache rule that makes the edge honor origin `Cache-Control`/`Expires` headers.
+///
+/// The ids of the fixed-TTL rules it switched off are kept in its description
+/// so they can be switched back on when the rule is removed.
+pub fn respect_origin_headers_rule(disabled_rule_ids: &[String]) -> RulesetRule {
+    let description = if disabled_rule_ids.is_empty() {
+        RESPECT_ORIGIN_RULE_DESCRIPTION.to_string()
+    } else {
+        format!(
+            "{}{}{})",
+            RESPECT_ORIGIN_RULE_DESCRIPTION,
+            DISABLED_RULES_MARKER,
+            disabled_rule_ids.join(",")
+        )
+    };
+
+    RulesetRule {
+        id: None,
+        action: "set_cache_settings".to_string(),
+        action_parameters: Some(serde_json::json!({
+            "cache": true,
+            "edge_ttl": { "mode": "respect_origin" },
+            "browser_ttl": { "mode": "respect_origin" }
+        })),
+        expression: "true".to_string(),
+        description: Some(description),
+        enabled: Some(true),
+        ratelimit: None,
+        extra: Default::default(),
+    }
+}
+
+/// Add or remove the respect-origin rule.
+///
+/// Enabling it disables fixed edge TTL rules; disabling it re-enables the
+/// rules it disabled. Every other rule is left as it was.
+pub fn apply_respect_origin_headers(
+    rules: Vec<RulesetRule>,
+    enabled: bool,
+) -> (Vec<RulesetRule>, Vec<String>) {
+    let mut warnings = Vec::new();
+    let (managed, mut rules): (Vec<RulesetRule>, Vec<RulesetRule>) =
+        rules.into_iter().partition(is_respect_origin_rule);
+    let mut disabled_ids: Vec<String> = managed.iter().flat_map(disabled_rule_ids).collect();
+    let label = |rule: &RulesetRule| rule.description.clone().unwrap_or_else(|| rule.expression.clone());
+
+    if enabled {
+        for rule in rules.iter_mut() {
+            if rule.enabled != Some(false) && overrides_edge_ttl(rule) {
+                rule.enabled = Some(false);
+                disabled_ids.extend(rule.id.clone());
+                warnings.push(format!(
+                    "Disabled cache rule '{}' because it sets a fixed edge TTL",
+                    label(rule)
+                ));
+            }
+        }
+        disabled_ids.sort();
+        disabled_ids.dedup();
+        rules.push(respect_origin_headers_rule(&disabled_ids));
+    } else {
+        for rule in rules.iter_mut() {
+            let was_disabled = rule.id.as_ref().is_some_and(|id| disabled_ids.contains(id));
+            if was_disabled && rule.enabled == Some(false) {
+                rule.enabled = Some(true);
+                warnings.push(format!(
+                    "Re-enabled cache rule '{}' now that origin cache headers are no longer respected",
+                    label(rule)
+                ));
+            }
+        }
+    }
+
+    (rules, warnings)
+}
+
+fn is_respect_origin_rule(rule: &RulesetRule) -> bool {
+    rule.description
+        .as_deref()
+        .is_some_and(|d| d.starts_with(RESPECT_ORIGIN_RULE_DESCRIPTION))
+}
+
+/// Ids of the rules the respect-origin rule disabled, read from its description
+fn disabled_rule_ids(rule: &RulesetRule) -> Vec<String> {
+    rule.description
+        .as_deref()
+        .and_then(|d| d.split_once(DISABLED_RULES_MARKER))
+        .map(|(_, ids)| ids.trim_end_matches(')').split(',').filter(|id| !id.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn overrides_edge_ttl(rule: &RulesetRule) -> bool {
+    rule.action_parameters
+        .as_ref()
+        .and_then(|p| p.pointer("/edge_ttl/mode"))
+        .and_then(|m| m.as_str())
+        == Some("override_origin")
+}
+
+/// `origin_max_http_version` value for HTTP/2 to origin on or off
+fn origin_http_version(http2: bool) -> &'static str {
+    if http2 { "2" } else { "1" }
+}
+
+/// Lesser-used performance toggles
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AdvancedPerformanceSettings {
+    pub crawler_hints: bool,
+    pub http2_to_origin: bool,
+}
+
+type PurgeEventRow = (i32, String, Option<serde_json::Value>, Option<DateTime<Utc>>);
+
+/// A logged purge, as shown in the purge history
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PurgeEvent {
+    pub id: i32,
+    pub event_type: String,
+    pub details: Option<PurgeEventDetails>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Stored purge event details, parsed according to the event type
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(untagged)]
+pub enum PurgeEventDetails {
+    Urls { urls: Vec<String> },
+    Tags { tags: Vec<String> },
+    Prefixes { prefixes: Vec<String> },
+    Hosts { hosts: Vec<String> },
+    Content(Box<ContentChangeEvent>),
+    /// Details of other events, or ones that don't match their event type
+    Other(serde_json::Value),
+}
+
+impl PurgeEventDetails {
+    /// Parse the `details` column of an event, keeping it as-is if it doesn't fit
+    pub fn parse(event_type: &str, details: serde_json::Value) -> Self {
+        #[derive(serde::Deserialize)]
+        struct Urls {
+            urls: Vec<String>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Tags {
+            tags: Vec<String>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Prefixes {
+            prefixes: Vec<String>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Hosts {
+            hosts: Vec<String>,
+        }
+
+        let parsed = match event_type {
+            "purge_urls" => serde_json::from_value::<Urls>(details.clone()).map(|d| Self::Urls { urls: d.urls }),
+            "purge_tags" => serde_json::from_value::<Tags>(details.clone()).map(|d| Self::Tags { tags: d.tags }),
+            "purge_prefix" => serde_json::from_value::<Prefixes>(details.clone())
+                .map(|d| Self::Prefixes { prefixes: d.prefixes }),
+            "purge_hosts" => serde_json::from_value::<Hosts>(details.clone()).map(|d| Self::Hosts { hosts: d.hosts }),
+            "auto_purge_paused" => return Self::Other(details),
+            t if t.starts_with("auto_purge_") => serde_json::from_value(details.clone()).map(|e| Self::Content(Box::new(e))),
+            _ => return Self::Other(details),
+        };
+
+        parsed.unwrap_or(Self::Other(details))
+    }
 }
 
 /// Cache statistics
@@ -189,3 +1313,979 @@ impl Default for crate::models::AnalyticsBandwidth {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_client, mock_service, success};
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn tagged_service(server: &MockServer, content_id: &str) -> (CacheService, PgPool) {
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let service = CacheService::new(mock_client(server), pool.clone());
+
+        let key = content_cache_key("post", content_id);
+        service.register_tag(&key, &content_cache_tag("post", content_id)).await.unwrap();
+        service.register_tag(&key, "rp-archive").await.unwrap();
+        service.register_tag(&key, "rp-archive").await.unwrap();
+
+        (service, pool)
+    }
+
+    #[test]
+    fn test_idempotency_cache_window() {
+        let cache: IdempotencyCache<String> = IdempotencyCache::new(Duration::from_secs(60));
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        cache.insert_at("purge_urls:evt-1", "purge-1".to_string(), at(0));
+        assert_eq!(cache.get_at("purge_urls:evt-1", at(30)), Some("purge-1".to_string()));
+        assert!(!cache.insert_new_at("purge_urls:evt-1", "purge-2".to_string(), at(59)));
+        assert_eq!(cache.get_at("purge_urls:evt-2", at(30)), None);
+
+        // Once the window has passed the same key may purge again
+        assert_eq!(cache.get_at("purge_urls:evt-1", at(60)), None);
+        assert!(cache.insert_new_at("purge_urls:evt-1", "purge-3".to_string(), at(61)));
+        assert_eq!(cache.get_at("purge_urls:evt-1", at(62)), Some("purge-3".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_idempotency_key_skips_purge() {
+        let server = MockServer::start().await;
+        let service = mock_service(&server, CacheService::new);
+        service.recent_purges.insert(
+            "purge_all:evt-1",
+            PurgeResponse { id: "purge-1".to_string(), skipped_urls: Vec::new() },
+        );
+
+        // Answered from the earlier purge without calling Cloudflare or the database
+        let result = service.purge_all(Some("evt-1")).await.unwrap();
+        assert_eq!(result.id, "purge-1");
+
+        // Keys are scoped to the kind of purge
+        assert!(service.prior_purge("purge_urls", Some("evt-1")).is_none());
+        assert!(service.prior_purge("purge_all", None).is_none());
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_set_crawler_hints_payload() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/zones/zone/flags/products/cache/changes"))
+            .and(body_json(serde_json::json!({ "feature": "crawlhints_enabled", "value": true })))
+            .respond_with(success(serde_json::json!({ "crawlhints_enabled": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert!(mock_service(&server, CacheService::new).set_crawler_hints(true).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_set_http2_to_origin_payload_and_plan_gate() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings/origin_max_http_version"))
+            .respond_with(success(serde_json::json!({
+                "id": "origin_max_http_version", "value": "1", "editable": true
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/origin_max_http_version"))
+            .and(body_json(serde_json::json!({ "value": "2" })))
+            .respond_with(success(serde_json::json!({
+                "id": "origin_max_http_version", "value": "2", "editable": true
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, CacheService::new);
+        assert!(service.set_http2_to_origin(true).await.unwrap());
+
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings/origin_max_http_version"))
+            .respond_with(success(serde_json::json!({
+                "id": "origin_max_http_version", "value": "1", "editable": false
+            })))
+            .mount(&server)
+            .await;
+
+        let err = service.set_http2_to_origin(true).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::PermissionDenied(_)));
+    }
+
+    async fn cleanup(pool: &PgPool, content_id: &str) {
+        sqlx::query("DELETE FROM cloudflare_cache_tags WHERE content_key = $1")
+            .bind(content_cache_key("post", content_id))
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_purge_content_tags_purges_registered_tags() {
+        let server = MockServer::start().await;
+        let content_id = uuid::Uuid::new_v4().simple().to_string();
+
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .and(body_json(serde_json::json!({
+                "tags": ["rp-archive", content_cache_tag("post", &content_id)]
+            })))
+            .respond_with(success(serde_json::json!({ "id": "purge-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let (service, pool) = tagged_service(&server, &content_id).await;
+        let result = service.purge_content_tags("post", &content_id).await.unwrap();
+        cleanup(&pool, &content_id).await;
+
+        assert_eq!(result.unwrap().id, "purge-1");
+        assert!(service.purge_content_tags("post", "untagged").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_rendered_content_tags_are_purged() {
+        let server = MockServer::start().await;
+        let content_id = uuid::Uuid::new_v4().simple().to_string();
+        let default_tag = content_cache_tag("post", &content_id);
+
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .and(body_json(serde_json::json!({ "tags": [default_tag.clone(), "rp-sidebar"] })))
+            .respond_with(success(serde_json::json!({ "id": "purge-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let service = CacheService::new(mock_client(&server), pool.clone());
+
+        let extra = vec!["rp-sidebar".to_string(), default_tag.clone(), " ".to_string()];
+        let tags = content_cache_tags("post", &content_id, &extra);
+        service.register_content_tags("post", &content_id, &tags).await.unwrap();
+        // Rendering again is served from memory
+        service.register_content_tags("post", &content_id, &tags).await.unwrap();
+
+        let result = service.purge_content_tags("post", &content_id).await.unwrap();
+        cleanup(&pool, &content_id).await;
+
+        assert_eq!(tags, vec![default_tag, "rp-sidebar".to_string()]);
+        assert_eq!(result.unwrap().id, "purge-1");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_purge_content_tags_falls_back_without_enterprise() {
+        let server = MockServer::start().await;
+        let content_id = uuid::Uuid::new_v4().simple().to_string();
+
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 1107, "message": "Purge by tag is only available on Enterprise plans" }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&server)
+            .await;
+
+        let (service, pool) = tagged_service(&server, &content_id).await;
+        let result = service.purge_content_tags("post", &content_id).await;
+        cleanup(&pool, &content_id).await;
+
+        assert!(result.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_respect_origin_headers_ruleset() {
+        let fixed_ttl = RulesetRule {
+            id: Some("rule-1".to_string()),
+            action: "set_cache_settings".to_string(),
+            action_parameters: Some(serde_json::json!({
+                "cache": true,
+                "edge_ttl": { "mode": "override_origin", "default": 7200 }
+            })),
+            expression: "(http.request.uri.path wildcard \"/blog/*\")".to_string(),
+            description: Some("Blog edge TTL".to_string()),
+            enabled: Some(true),
+            ratelimit: None,
+            extra: Default::default(),
+        };
+
+        let (rules, warnings) = apply_respect_origin_headers(vec![fixed_ttl], true);
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].enabled, Some(false));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("Blog edge TTL"));
+
+        let rule = serde_json::to_value(&rules[1]).unwrap();
+        assert_eq!(rule["action"], "set_cache_settings");
+        assert_eq!(rule["expression"], "true");
+        assert_eq!(rule["action_parameters"]["edge_ttl"]["mode"], "respect_origin");
+        assert_eq!(rule["action_parameters"]["browser_ttl"]["mode"], "respect_origin");
+        assert!(rule.get("id").is_none());
+
+        assert_eq!(rule["description"], format!("{} (disabled rules: rule-1)", RESPECT_ORIGIN_RULE_DESCRIPTION));
+
+        let (rules, warnings) = apply_respect_origin_headers(rules, false);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].enabled, Some(true));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("Re-enabled cache rule 'Blog edge TTL'"));
+    }
+
+    #[tokio::test]
+    async fn test_disabling_respect_origin_headers_restores_rules_unchanged() {
+        let server = MockServer::start().await;
+        let fixed_ttl = |enabled: bool| {
+            serde_json::json!({
+                "id": "rule-1",
+                "ref": "blog-ttl",
+                "action": "set_cache_settings",
+                "action_parameters": { "cache": true, "edge_ttl": { "mode": "override_origin", "default": 7200 } },
+                "expression": "(http.request.uri.path wildcard \"/blog/*\")",
+                "description": "Blog edge TTL",
+                "enabled": enabled,
+                "logging": { "enabled": true }
+            })
+        };
+        let bypass = serde_json::json!({
+            "id": "rule-2",
+            "action": "set_cache_settings",
+            "action_parameters": { "cache": false },
+            "expression": "(http.request.uri.path wildcard \"/admin/*\")",
+            "enabled": false
+        });
+        let managed = serde_json::json!({
+            "id": "rule-3",
+            "action": "set_cache_settings",
+            "action_parameters": { "cache": true, "edge_ttl": { "mode": "respect_origin" } },
+            "expression": "true",
+            "description": format!("{} (disabled rules: rule-1)", RESPECT_ORIGIN_RULE_DESCRIPTION),
+            "enabled": true
+        });
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/rulesets/phases/http_request_cache_settings/entrypoint"))
+            .respond_with(success(serde_json::json!({
+                "id": "entry",
+                "phase": CACHE_SETTINGS_PHASE,
+                "rules": [fixed_ttl(false), bypass, managed]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/zones/zone/rulesets/phases/http_request_cache_settings/entrypoint"))
+            .respond_with(success(serde_json::json!({ "id": "entry", "phase": CACHE_SETTINGS_PHASE, "rules": [] })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, CacheService::new);
+        let warnings = service.set_respect_origin_headers(false).await.unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        let requests = server.received_requests().await.unwrap();
+        let put = requests.iter().find(|r| r.method.to_string() == "PUT").unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&put.body).unwrap();
+        // The rule it disabled is back on with its other fields intact; the
+        // rule the admin disabled stays off
+        assert_eq!(body["rules"], serde_json::json!([fixed_ttl(true), bypass]));
+    }
+
+    #[tokio::test]
+    async fn test_respect_origin_headers_without_cache_rules_entrypoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/rulesets/phases/http_request_cache_settings/entrypoint"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 10003, "message": "could not find entrypoint ruleset in the http_request_cache_settings phase" }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/zones/zone/rulesets/phases/http_request_cache_settings/entrypoint"))
+            .respond_with(success(serde_json::json!({ "id": "entry", "phase": CACHE_SETTINGS_PHASE, "rules": [] })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, CacheService::new);
+        assert!(!service.get_respect_origin_headers().await.unwrap());
+        assert!(service.set_respect_origin_headers(true).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_purge_event_details_parse_by_event_type() {
+        let urls = PurgeEventDetails::parse("purge_urls", serde_json::json!({ "urls": ["https://example.com/a"] }));
+        assert!(matches!(urls, PurgeEventDetails::Urls { ref urls } if urls == &["https://example.com/a"]));
+
+        let tags = PurgeEventDetails::parse("purge_tags", serde_json::json!({ "tags": ["rp-post-1"] }));
+        assert!(matches!(tags, PurgeEventDetails::Tags { .. }));
+
+        let hosts = PurgeEventDetails::parse("purge_hosts", serde_json::json!({ "hosts": ["shop.example.com"] }));
+        assert!(matches!(hosts, PurgeEventDetails::Hosts { ref hosts } if hosts == &["shop.example.com"]));
+
+        let content = PurgeEventDetails::parse(
+            "auto_purge_post",
+            serde_json::json!({
+                "content_type": "post",
+                "action": "updated",
+                "content_id": "42",
+                "url": "https://example.com/hello",
+                "slug": "hello",
+                "title": null,
+                "related_urls": [],
+                "user_id": null,
+                "timestamp": "2026-01-01T00:00:00Z"
+            }),
+        );
+        assert!(matches!(content, PurgeEventDetails::Content(ref e) if e.content_id.as_deref() == Some("42")));
+
+        // Unknown or mismatched details are kept as stored
+        let paused = serde_json::json!({ "reason": "quota", "paused_until": "2026-01-01T00:00:00Z" });
+        assert!(matches!(PurgeEventDetails::parse("auto_purge_paused", paused.clone()), PurgeEventDetails::Other(ref v) if v == &paused));
+        let odd = serde_json::json!({ "urls": "not-a-list" });
+        assert!(matches!(PurgeEventDetails::parse("purge_urls", odd.clone()), PurgeEventDetails::Other(ref v) if v == &odd));
+
+        // Serialized back in the stored shape
+        assert_eq!(serde_json::to_value(&urls).unwrap(), serde_json::json!({ "urls": ["https://example.com/a"] }));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_list_purge_events_filters_and_paginates() {
+        let server = MockServer::start().await;
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let client = mock_client(&server);
+        let service = CacheService::new(client, pool.clone());
+        let suffix = &uuid::Uuid::new_v4().simple().to_string()[..12];
+        let (kept, other) = (format!("hist_a_{}", suffix), format!("hist_b_{}", suffix));
+
+        for (i, event_type) in [&kept, &other, &kept, &kept].iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO cloudflare_cache_events (event_type, details, created_at) \
+                 VALUES ($1, $2, NOW() - ($3 * INTERVAL '1 minute'))",
+            )
+            .bind(event_type)
+            .bind(serde_json::json!({ "n": i }))
+            .bind(10 - i as i32)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(service.count_purge_events(Some(&kept)).await.unwrap(), 3);
+        assert_eq!(service.count_purge_events(Some(&other)).await.unwrap(), 1);
+
+        // Newest first, only the filtered type
+        let first = service.list_purge_events(2, 0, Some(&kept)).await.unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|e| e.event_type == kept));
+        let order: Vec<_> = first
+            .iter()
+            .map(|e| match &e.details {
+                Some(PurgeEventDetails::Other(v)) => v["n"].as_i64().unwrap(),
+                other => panic!("unexpected details: {:?}", other),
+            })
+            .collect();
+        assert_eq!(order, vec![3, 2]);
+
+        let second = service.list_purge_events(2, 2, Some(&kept)).await.unwrap();
+        assert_eq!(second.len(), 1);
+        assert!(matches!(&second[0].details, Some(PurgeEventDetails::Other(v)) if v["n"] == 0));
+
+        // Without a filter every type counts
+        assert!(service.count_purge_events(None).await.unwrap() >= 4);
+
+        sqlx::query("DELETE FROM cloudflare_cache_events WHERE event_type IN ($1, $2)")
+            .bind(&kept)
+            .bind(&other)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    async fn mount_zone(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .respond_with(success(serde_json::json!({
+                "id": "zone", "name": "example.com", "status": "active", "paused": false,
+                "development_mode": 0, "type": "full", "name_servers": []
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[test]
+    fn test_partition_zone_urls() {
+        let (in_zone, skipped) = partition_zone_urls(
+            vec![
+                "https://example.com/".to_string(),
+                "https://typo-example.com/post".to_string(),
+                "https://cdn.example.com/app.css".to_string(),
+                "example.com/relative".to_string(),
+            ],
+            "example.com",
+        );
+        assert_eq!(in_zone, vec!["https://example.com/", "https://cdn.example.com/app.css"]);
+        assert_eq!(skipped, vec!["https://typo-example.com/post", "example.com/relative"]);
+    }
+
+    #[tokio::test]
+    async fn test_purge_urls_outside_zone_fail_without_purging() {
+        let server = MockServer::start().await;
+        mount_zone(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .respond_with(success(serde_json::json!({ "id": "purge-1" })))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, CacheService::new);
+        for _ in 0..2 {
+            let err = service
+                .purge_urls(vec!["https://exmaple.com/".to_string()], None)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("exmaple.com")));
+        }
+        // Zone name fetched once (checked by `expect(1)` on drop)
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_purge_urls_skips_urls_outside_zone() {
+        let server = MockServer::start().await;
+        mount_zone(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .and(body_json(serde_json::json!({
+                "files": ["https://example.com/a", "https://www.example.com/b"]
+            })))
+            .respond_with(success(serde_json::json!({ "id": "purge-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let service = CacheService::new(mock_client(&server), pool);
+        let result = service
+            .purge_urls(
+                vec![
+                    "https://example.com/a".to_string(),
+                    "https://other.org/a".to_string(),
+                    "https://www.example.com/b".to_string(),
+                ],
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.id, "purge-1");
+        assert_eq!(result.skipped_urls, vec!["https://other.org/a"]);
+    }
+
+    #[test]
+    fn test_check_url_in_zone() {
+        check_url_in_zone("https://example.com/blog/hello", "example.com").unwrap();
+        check_url_in_zone("http://WWW.Example.com/", "example.com.").unwrap();
+
+        for url in [
+            "https://example.org/blog/hello",
+            "https://notexample.com/",
+            "https://example.com.evil.net/",
+            "/blog/hello",
+            "ftp://example.com/file",
+        ] {
+            assert!(
+                matches!(check_url_in_zone(url, "example.com"), Err(CloudflareError::ValidationError(_))),
+                "{} should be rejected",
+                url
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_purge_url_rejects_foreign_domain() {
+        let server = MockServer::start().await;
+        mount_zone(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .respond_with(success(serde_json::json!({ "id": "purge-1" })))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let err = mock_service(&server, CacheService::new)
+            .purge_url("https://other-site.net/stale-page", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("not in zone example.com")));
+    }
+
+    #[test]
+    fn test_idempotency_cache_take_is_single_use() {
+        let cache: IdempotencyCache<()> = IdempotencyCache::new(Duration::from_secs(60));
+        let start = Instant::now();
+
+        cache.insert_at("a", (), start);
+        cache.insert_at("b", (), start);
+        assert!(cache.take_at("a", start + Duration::from_secs(10)).is_some());
+        assert!(cache.take_at("a", start + Duration::from_secs(11)).is_none());
+        assert!(cache.take_at("b", start + Duration::from_secs(60)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_purge_all_confirmation_rejects_unknown_and_reused_tokens() {
+        let server = MockServer::start().await;
+        let service = mock_service(&server, CacheService::new);
+
+        let err = service.purge_all_confirmed("made-up", None).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(_)));
+
+        // A valid token is consumed even when the purge itself is answered from
+        // an earlier idempotent result
+        service.recent_purges.insert(
+            "purge_all:evt-1",
+            PurgeResponse { id: "purge-1".to_string(), skipped_urls: Vec::new() },
+        );
+        let token = service.request_purge_all_confirmation();
+        assert_eq!(service.purge_all_confirmed(&token, Some("evt-1")).await.unwrap().id, "purge-1");
+        let err = service.purge_all_confirmed(&token, Some("evt-1")).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(_)));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_purge_metrics_count_failed_purge() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 1134, "message": "Purge quota exceeded" }],
+                "messages": [],
+                "result": null
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, CacheService::new);
+        assert!(service.purge_tags(vec!["post-1".to_string()], None).await.is_err());
+
+        let client = service.get_client().unwrap();
+        let rendered = client.metrics().render(&client.circuit_breakers().snapshot());
+        let lines: Vec<&str> = rendered.lines().collect();
+        for expected in [
+            "rustpress_cloudflare_purges_total 1",
+            "rustpress_cloudflare_purge_failures_total 1",
+            r#"rustpress_cloudflare_api_calls_total{category="cache"} 1"#,
+            r#"rustpress_cloudflare_api_errors_total{category="cache"} 1"#,
+        ] {
+            assert!(lines.contains(&expected), "missing {} in\n{}", expected, rendered);
+        }
+    }
+
+    #[test]
+    fn test_check_host_in_zone() {
+        assert_eq!(check_host_in_zone("example.com", "example.com").unwrap(), "example.com");
+        assert_eq!(check_host_in_zone(" Shop.Example.com. ", "example.com").unwrap(), "shop.example.com");
+
+        for host in [
+            "example.org",
+            "notexample.com",
+            "example.com.evil.net",
+            "https://example.com",
+            "example.com/blog",
+            "example.com:8443",
+            "*.example.com",
+            "",
+        ] {
+            assert!(
+                matches!(check_host_in_zone(host, "example.com"), Err(CloudflareError::ValidationError(_))),
+                "{} should be rejected",
+                host
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_purge_hosts_rejects_hosts_outside_zone() {
+        let server = MockServer::start().await;
+        mount_zone(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .respond_with(success(serde_json::json!({ "id": "purge-1" })))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let err = mock_service(&server, CacheService::new)
+            .purge_hosts(vec!["shop.example.com".to_string(), "other-site.net".to_string()], None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("other-site.net")));
+    }
+
+    #[tokio::test]
+    async fn test_purge_hosts_explains_enterprise_requirement() {
+        let server = MockServer::start().await;
+        mount_zone(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .and(body_json(serde_json::json!({ "hosts": ["shop.example.com", "example.com"] })))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 1107, "message": "Purge by host is only available on Enterprise plans" }],
+                "messages": [],
+                "result": null
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let err = mock_service(&server, CacheService::new)
+            .purge_hosts(vec!["Shop.example.com".to_string(), "example.com".to_string()], None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m)
+            if m.starts_with("Purge by hostname isn't included") && m.contains("an Enterprise plan")));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_purge_hosts_request_body() {
+        let server = MockServer::start().await;
+        mount_zone(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .and(body_json(serde_json::json!({ "hosts": ["shop.example.com", "www.example.com"] })))
+            .respond_with(success(serde_json::json!({ "id": "purge-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let service = CacheService::new(mock_client(&server), pool);
+        let result = service
+            .purge_hosts(vec!["shop.example.com".to_string(), "www.example.com".to_string()], None)
+            .await
+            .unwrap();
+        assert_eq!(result.id, "purge-1");
+    }
+
+    fn adaptive_group(ts: &str, cache_status: &str, count: i64, bytes: i64) -> serde_json::Value {
+        serde_json::json!({
+            "count": count,
+            "dimensions": { "ts": ts, "cacheStatus": cache_status },
+            "sum": { "edgeResponseBytes": bytes }
+        })
+    }
+
+    #[test]
+    fn test_grouped_graphql_response_maps_into_points() {
+        let body = serde_json::json!({
+            "data": {
+                "viewer": {
+                    "zones": [{
+                        "httpRequestsAdaptiveGroups": [
+                            adaptive_group("2024-01-01T01:00:00Z", "miss", 30, 3000),
+                            adaptive_group("2024-01-01T00:00:00Z", "hit", 60, 4000),
+                            adaptive_group("2024-01-01T00:00:00Z", "miss", 20, 5000),
+                            adaptive_group("2024-01-01T00:00:00Z", "revalidated", 10, 1000),
+                            adaptive_group("2024-01-01T00:00:00Z", "dynamic", 10, 0),
+                            adaptive_group("2024-01-01T01:00:00Z", "stale", 10, 1000),
+                            adaptive_group("yesterday", "hit", 1, 1)
+                        ]
+                    }]
+                }
+            },
+            "errors": null
+        });
+
+        let response: crate::models::GraphQlResponse<CacheTimeseriesData> = serde_json::from_value(body).unwrap();
+        let points = cache_stats_points(response.data.unwrap().into_groups());
+
+        assert_eq!(
+            points,
+            vec![
+                CacheStatsPoint {
+                    ts: "2024-01-01T00:00:00Z".parse().unwrap(),
+                    hit_ratio: 70.0,
+                    bytes_cached: 5000,
+                    bytes_total: 10000,
+                },
+                CacheStatsPoint {
+                    ts: "2024-01-01T01:00:00Z".parse().unwrap(),
+                    hit_ratio: 25.0,
+                    bytes_cached: 1000,
+                    bytes_total: 4000,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_daily_buckets_start_at_midnight() {
+        let points = cache_stats_points(vec![CacheTimeseriesGroup {
+            count: 0,
+            dimensions: CacheTimeseriesDimensions { ts: "2024-03-05".to_string(), cache_status: "hit".to_string() },
+            sum: CacheTimeseriesSum::default(),
+        }]);
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].ts, "2024-03-05T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(points[0].hit_ratio, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_cache_timeseries_fetches_day_chunks_and_merges_them() {
+        let server = MockServer::start().await;
+        // Both chunks report the bucket the window boundary falls in
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {
+                    "viewer": {
+                        "zones": [{
+                            "httpRequestsAdaptiveGroups": [
+                                adaptive_group("2024-01-01T00:00:00Z", "hit", 3, 300),
+                                adaptive_group("2024-01-01T00:00:00Z", "miss", 1, 100)
+                            ]
+                        }]
+                    }
+                },
+                "errors": null
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let points = mock_service(&server, CacheService::new)
+            .get_cache_stats_timeseries(48, CacheStatsBucket::Hour)
+            .await
+            .unwrap();
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].bytes_total, 800);
+        assert_eq!(points[0].bytes_cached, 600);
+        assert_eq!(points[0].hit_ratio, 75.0);
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body["query"].as_str().unwrap().contains("ts: datetimeHour cacheStatus"));
+    }
+
+    #[test]
+    fn test_minute_buckets_are_fetched_in_shorter_chunks() {
+        let minute = timeseries_chunk(CacheStatsBucket::Minute);
+        assert!(minute < chrono::Duration::days(1));
+        assert_eq!(minute.num_seconds() % 60, 0);
+        // Every bucket in a chunk fits in one response, whatever its cache statuses
+        assert!(minute.num_minutes() * MAX_CACHE_STATUSES <= CACHE_TIMESERIES_GROUP_LIMIT);
+
+        assert_eq!(timeseries_chunk(CacheStatsBucket::FiveMinutes), chrono::Duration::days(1));
+        assert_eq!(timeseries_chunk(CacheStatsBucket::Hour), chrono::Duration::days(1));
+        assert_eq!(timeseries_chunk(CacheStatsBucket::Day), chrono::Duration::days(1));
+    }
+
+    #[tokio::test]
+    async fn test_cache_timeseries_rejects_too_many_points() {
+        let server = MockServer::start().await;
+        let service = mock_service(&server, CacheService::new);
+
+        for (hours, bucket) in [(0, CacheStatsBucket::Hour), (169, CacheStatsBucket::Hour), (48, CacheStatsBucket::Minute)] {
+            assert!(matches!(
+                service.get_cache_stats_timeseries(hours, bucket).await,
+                Err(CloudflareError::ValidationError(_))
+            ));
+        }
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    async fn mount_zone_on_plan(server: &MockServer, plan: &str) {
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .respond_with(success(serde_json::json!({
+                "id": "zone", "name": "example.com", "status": "active", "paused": false,
+                "development_mode": 0, "type": "full", "name_servers": [],
+                "plan": {
+                    "id": "plan", "name": plan, "price": 0.0, "currency": "USD", "frequency": "monthly",
+                    "is_subscribed": true, "can_subscribe": false, "legacy_id": plan
+                }
+            })))
+            .mount(server)
+            .await;
+    }
+
+    fn setting(id: &str, value: &str) -> ResponseTemplate {
+        success(serde_json::json!({ "id": id, "value": value, "editable": true }))
+    }
+
+    #[tokio::test]
+    async fn test_set_cache_reserve_request_body() {
+        let server = MockServer::start().await;
+        mount_zone_on_plan(&server, "business").await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/cache/cache_reserve"))
+            .and(body_json(serde_json::json!({ "value": "on" })))
+            .respond_with(setting("cache_reserve", "on"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert!(mock_service(&server, CacheService::new).set_cache_reserve(true).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cache_reserve_is_gated_on_plan() {
+        let server = MockServer::start().await;
+        mount_zone_on_plan(&server, "pro").await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/cache/cache_reserve"))
+            .and(body_json(serde_json::json!({ "value": "on" })))
+            .respond_with(setting("cache_reserve", "on"))
+            .expect(0)
+            .mount(&server)
+            .await;
+        // Turning it off is never refused
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/cache/cache_reserve"))
+            .and(body_json(serde_json::json!({ "value": "off" })))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 1004, "message": "Cache Reserve requires an active subscription" }],
+                "messages": [],
+                "result": null
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, CacheService::new);
+        let err = service.set_cache_reserve(true).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m)
+            if m.starts_with("Cache Reserve isn't included") && m.contains("a Business plan")));
+
+        // An entitlement error from Cloudflare is explained the same way
+        let err = service.set_cache_reserve(false).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m) if m.starts_with("Cache Reserve isn't included")));
+    }
+
+    #[tokio::test]
+    async fn test_smart_topology_turns_on_tiered_caching_first() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/argo/tiered_caching"))
+            .and(body_json(serde_json::json!({ "value": "on" })))
+            .respond_with(setting("tiered_caching", "on"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/cache/tiered_cache_smart_topology_enable"))
+            .and(body_json(serde_json::json!({ "value": "on" })))
+            .respond_with(setting("tiered_cache_smart_topology_enable", "on"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let topology = mock_service(&server, CacheService::new)
+            .set_tiered_cache_topology(TieredCacheTopology::Smart)
+            .await
+            .unwrap();
+        assert_eq!(topology, TieredCacheTopology::Smart);
+
+        let paths: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.url.path().to_string())
+            .collect();
+        assert_eq!(paths, vec!["/zones/zone/argo/tiered_caching", "/zones/zone/cache/tiered_cache_smart_topology_enable"]);
+    }
+
+    #[tokio::test]
+    async fn test_custom_topology_needs_enterprise() {
+        let server = MockServer::start().await;
+        mount_zone_on_plan(&server, "business").await;
+        Mock::given(method("PATCH"))
+            .respond_with(setting("tiered_caching", "on"))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let err = mock_service(&server, CacheService::new)
+            .set_tiered_cache_topology(TieredCacheTopology::Custom)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m)
+            if m.starts_with("Custom tiered cache topology isn't included") && m.contains("an Enterprise plan")));
+    }
+
+    #[tokio::test]
+    async fn test_plan_lookup_is_shared_with_zone_service() {
+        let server = MockServer::start().await;
+        mount_zone_on_plan(&server, "business").await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/cache/cache_reserve"))
+            .respond_with(setting("cache_reserve", "on"))
+            .mount(&server)
+            .await;
+
+        let zone = mock_service(&server, crate::services::zone::ZoneService::new);
+        let service = mock_service(&server, CacheService::new).with_plan_lookup(zone.plan_lookup());
+
+        zone.plan().await.unwrap();
+        service.set_cache_reserve(true).await.unwrap();
+        service.set_cache_reserve(true).await.unwrap();
+
+        let zone_fetches = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.url.path() == "/zones/zone")
+            .count();
+        assert_eq!(zone_fetches, 1);
+    }
+
+    #[tokio::test]
+    async fn test_tiered_caching_errors_do_not_mention_argo() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/argo/tiered_caching"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 1004, "message": "Invalid subscription state" }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&server)
+            .await;
+
+        let err = mock_service(&server, CacheService::new)
+            .set_tiered_cache_topology(TieredCacheTopology::Generic)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ApiError { code: 1004, .. }));
+    }
+}
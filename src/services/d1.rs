@@ -4,11 +4,18 @@
 
 use crate::client::CloudflareClient;
 use crate::error::{CloudflareError, CloudflareResult};
-use crate::models::{D1Database, D1QueryResult};
+use crate::models::{D1Database, D1ExportStatus, D1QueryResult};
 use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
 use tracing::{debug, info};
 
+/// How often a running export is polled
+const EXPORT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long an export may run before giving up
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(300);
+
 /// D1 Database service
 pub struct D1Service {
     client: Option<Arc<CloudflareClient>>,
@@ -64,6 +71,26 @@ impl D1Service {
         Ok(database)
     }
 
+    /// Delete a D1 database and all its data.
+    ///
+    /// `confirm_name` must match the database's name, so a stale or mistyped
+    /// ID can't delete the wrong database.
+    pub async fn delete_database(&self, database_id: &str, confirm_name: &str) -> CloudflareResult<()> {
+        let database = self.get_database(database_id).await?;
+        if confirm_name.trim() != database.name {
+            return Err(CloudflareError::ValidationError(format!(
+                "Type the database name '{}' to confirm deletion",
+                database.name
+            )));
+        }
+
+        let client = self.client()?;
+        info!("Deleting D1 database: {} ({})", database.name, database_id);
+        client.delete_d1_database(database_id).await?;
+        info!("D1 database deleted: {}", database_id);
+        Ok(())
+    }
+
     /// Execute a SQL query on a D1 database
     pub async fn execute_query(
         &self,
@@ -80,18 +107,73 @@ impl D1Service {
         Ok(result)
     }
 
-    /// Execute multiple SQL statements in a batch
+    /// Execute multiple SQL statements in one request, as a single transaction.
+    ///
+    /// Results are returned in statement order.
     pub async fn execute_batch(
         &self,
         database_id: &str,
         statements: Vec<String>,
     ) -> CloudflareResult<Vec<D1QueryResult>> {
-        let mut results = Vec::new();
-        for sql in statements {
-            let result = self.execute_query(database_id, &sql).await?;
-            results.push(result);
+        let client = self.client()?;
+        debug!("Executing {} SQL statements on D1 database {}", statements.len(), database_id);
+        client.query_d1_batch(database_id, statements).await
+    }
+
+    /// Export a database as an SQL dump
+    pub async fn export(&self, database_id: &str) -> CloudflareResult<String> {
+        self.export_with(database_id, EXPORT_POLL_INTERVAL, EXPORT_TIMEOUT).await
+    }
+
+    async fn export_with(
+        &self,
+        database_id: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> CloudflareResult<String> {
+        let client = self.client()?;
+        let deadline = Instant::now() + timeout;
+        let mut bookmark: Option<String> = None;
+
+        info!("Exporting D1 database {}", database_id);
+        loop {
+            let status = client.export_d1_database(database_id, bookmark.as_deref()).await?;
+            match export_step(&status)? {
+                ExportStep::Download(url) => {
+                    let dump = client.download_d1_export(&url).await?;
+                    info!("D1 database {} exported ({} bytes)", database_id, dump.len());
+                    return Ok(dump);
+                }
+                ExportStep::Poll(next) => {
+                    if next.is_some() {
+                        bookmark = next;
+                    }
+                }
+            }
+
+            if Instant::now() + poll_interval > deadline {
+                return Err(CloudflareError::Timeout(format!(
+                    "Export of D1 database {} did not finish within {}s",
+                    database_id,
+                    timeout.as_secs()
+                )));
+            }
+            sleep(poll_interval).await;
         }
-        Ok(results)
+    }
+
+    /// Restore an SQL dump into a database, running it as one batch.
+    ///
+    /// Returns the number of statements executed.
+    pub async fn import(&self, database_id: &str, sql: &str) -> CloudflareResult<usize> {
+        if sql.trim().is_empty() {
+            return Err(CloudflareError::ValidationError("SQL dump is empty".to_string()));
+        }
+
+        info!("Importing SQL dump into D1 database {}", database_id);
+        let results = self.execute_batch(database_id, vec![sql.to_string()]).await?;
+        info!("Imported {} statements into D1 database {}", results.len(), database_id);
+        Ok(results.len())
     }
 
     /// Create a table in a D1 database
@@ -252,3 +334,161 @@ impl D1Service {
         self.execute_query(database_id, &sql).await
     }
 }
+
+/// What to do after one poll of an export
+#[derive(Debug, PartialEq, Eq)]
+enum ExportStep {
+    /// Still running; poll again, with the new bookmark if one was returned
+    Poll(Option<String>),
+    /// Finished; download the dump from this URL
+    Download(String),
+}
+
+fn export_step(status: &D1ExportStatus) -> CloudflareResult<ExportStep> {
+    match status.status.as_deref() {
+        Some("complete") => status
+            .result
+            .as_ref()
+            .and_then(|r| r.signed_url.clone())
+            .map(ExportStep::Download)
+            .ok_or_else(|| CloudflareError::D1Error("Export finished without a download URL".to_string())),
+        Some("error") => Err(CloudflareError::D1Error(format!(
+            "Export failed: {}",
+            status.error.as_deref().unwrap_or("unknown error")
+        ))),
+        _ => Ok(ExportStep::Poll(status.at_bookmark.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_service, success};
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn export_status(value: serde_json::Value) -> D1ExportStatus {
+        serde_json::from_value(value).unwrap()
+    }
+
+    async fn mount_database_list(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/d1/database"))
+            .respond_with(success(serde_json::json!([{ "uuid": "db-1", "name": "blog", "version": "production" }])))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_delete_database_refuses_name_mismatch() {
+        let server = MockServer::start().await;
+        mount_database_list(&server).await;
+        Mock::given(method("DELETE"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, D1Service::new);
+        for confirm in ["", "db-1", "Blog", "blog-old"] {
+            assert!(matches!(
+                service.delete_database("db-1", confirm).await,
+                Err(CloudflareError::ValidationError(ref m)) if m.contains("'blog'")
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_database_with_matching_name() {
+        let server = MockServer::start().await;
+        mount_database_list(&server).await;
+        Mock::given(method("DELETE"))
+            .and(path("/accounts/acct/d1/database/db-1"))
+            .respond_with(success(serde_json::json!(null)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        mock_service(&server, D1Service::new).delete_database("db-1", "blog").await.unwrap();
+    }
+
+    #[test]
+    fn test_export_step_transitions() {
+        let active = export_status(serde_json::json!({ "status": "active", "at_bookmark": "b1" }));
+        assert_eq!(export_step(&active).unwrap(), ExportStep::Poll(Some("b1".to_string())));
+
+        // Nothing reported yet still means keep polling
+        let starting = export_status(serde_json::json!({}));
+        assert_eq!(export_step(&starting).unwrap(), ExportStep::Poll(None));
+
+        let complete = export_status(serde_json::json!({
+            "status": "complete",
+            "at_bookmark": "b2",
+            "result": { "filename": "db.sql", "signed_url": "https://r2.example/db.sql?sig=1" }
+        }));
+        assert_eq!(
+            export_step(&complete).unwrap(),
+            ExportStep::Download("https://r2.example/db.sql?sig=1".to_string())
+        );
+
+        let no_url = export_status(serde_json::json!({ "status": "complete", "result": {} }));
+        assert!(matches!(export_step(&no_url), Err(CloudflareError::D1Error(_))));
+
+        let failed = export_status(serde_json::json!({ "status": "error", "error": "database too large" }));
+        assert!(matches!(
+            export_step(&failed),
+            Err(CloudflareError::D1Error(ref m)) if m.contains("database too large")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_export_polls_until_complete_then_downloads() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/d1/database/db-1/export"))
+            .and(body_json(serde_json::json!({ "output_format": "polling" })))
+            .respond_with(success(serde_json::json!({ "status": "active", "at_bookmark": "b1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/d1/database/db-1/export"))
+            .and(body_json(serde_json::json!({ "output_format": "polling", "current_bookmark": "b1" })))
+            .respond_with(success(serde_json::json!({
+                "status": "complete",
+                "at_bookmark": "b1",
+                "result": { "filename": "db-1.sql", "signed_url": format!("{}/dumps/db-1.sql?sig=abc", server.uri()) }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/dumps/db-1.sql"))
+            .and(query_param("sig", "abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("CREATE TABLE posts (id INTEGER);\n"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dump = mock_service(&server, D1Service::new)
+            .export_with("db-1", Duration::from_millis(5), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(dump, "CREATE TABLE posts (id INTEGER);\n");
+    }
+
+    #[tokio::test]
+    async fn test_export_times_out_while_active() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/d1/database/db-1/export"))
+            .respond_with(success(serde_json::json!({ "status": "active", "at_bookmark": "b1" })))
+            .mount(&server)
+            .await;
+
+        let result = mock_service(&server, D1Service::new)
+            .export_with("db-1", Duration::from_millis(10), Duration::from_millis(35))
+            .await;
+        assert!(matches!(result, Err(CloudflareError::Timeout(_))));
+    }
+}
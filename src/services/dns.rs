@@ -2,32 +2,138 @@
 
 use crate::client::CloudflareClient;
 use crate::error::{CloudflareError, CloudflareResult};
-use crate::models::{CreateDnsRecord, DnsListParams, DnsRecord, UpdateDnsRecord, DeleteResponse};
+use crate::services::dns_scan::{default_probes, scan_records, DnsProbe, DnsResolver, DohResolver, NameserverResolver};
+use crate::models::{
+    CreateDnsRecord, DnsListParams, DnsRecord, DnssecStatus, UpdateDnsRecord, DeleteResponse, Page,
+    Pagination, ResultInfo,
+};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
-use std::sync::Arc;
-use tracing::info;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// TTL value Cloudflare treats as "automatic"
+pub const AUTO_TTL: i32 = 1;
+/// Shortest explicit TTL outside Enterprise plans, in seconds
+pub const MIN_TTL: i32 = 60;
+/// Longest TTL Cloudflare accepts, in seconds
+pub const MAX_TTL: i32 = 86_400;
+/// Record types that can be proxied through Cloudflare
+const PROXIABLE_TYPES: &[&str] = &["A", "AAAA", "CNAME"];
+
+/// Records written to the local table at once during a full sync
+pub const SYNC_CONCURRENCY: usize = 8;
+/// Records fetched per page during a full sync
+const SYNC_PAGE_SIZE: i32 = 500;
+/// First wait after a rate-limited page fetch, doubled on each retry
+const SYNC_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(2);
+/// Rate-limited retries of a single page before the sync gives up
+const SYNC_RATE_LIMIT_RETRIES: u32 = 5;
+
+/// A `cloudflare_dns_records` row: cloudflare_id, type, name, content, proxied, ttl, priority
+type LocalDnsRow = (String, String, String, String, Option<bool>, Option<i32>, Option<i32>);
 
 /// DNS management service
 pub struct DnsService {
     client: Option<Arc<CloudflareClient>>,
     db: PgPool,
+    /// Finds the nameservers a scan queries
+    recursive: Arc<dyn DnsResolver>,
+    /// Queried instead of the domain's nameservers, if set
+    resolver: Option<Arc<dyn DnsResolver>>,
+    scan: RwLock<ScanSettings>,
+}
+
+/// What `scan_existing` looks up and where
+#[derive(Debug, Clone)]
+struct ScanSettings {
+    probes: Vec<DnsProbe>,
+    /// Nameservers to ask instead of the domain's own
+    nameservers: Vec<String>,
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        Self { probes: default_probes(), nameservers: Vec::new() }
+    }
 }
 
 impl DnsService {
     pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
-        Self { client: Some(client), db }
+        Self {
+            client: Some(client),
+            db,
+            recursive: Arc::new(DohResolver::default()),
+            resolver: None,
+            scan: RwLock::default(),
+        }
     }
 
     /// Create without a configured client (for initial setup)
     pub fn new_unconfigured(db: PgPool) -> Self {
-        Self { client: None, db }
+        Self {
+            client: None,
+            db,
+            recursive: Arc::new(DohResolver::default()),
+            resolver: None,
+            scan: RwLock::default(),
+        }
+    }
+
+    /// Answer `scan_existing` lookups from `resolver` instead of the domain's nameservers
+    pub fn with_resolver(mut self, resolver: Arc<dyn DnsResolver>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// Look up these names and types in `scan_existing` instead of the defaults
+    pub fn with_scan_probes(self, probes: Vec<DnsProbe>) -> Self {
+        self.set_scan_settings(probes, Vec::new());
+        self
+    }
+
+    /// Names and types `scan_existing` looks up, and the nameservers it asks
+    /// instead of the domain's own (none to use the domain's)
+    pub fn set_scan_settings(&self, probes: Vec<DnsProbe>, nameservers: Vec<String>) {
+        *self.scan.write().unwrap_or_else(|e| e.into_inner()) = ScanSettings { probes, nameservers };
+    }
+
+    /// Names and types `scan_existing` looks up
+    pub fn scan_probes(&self) -> Vec<DnsProbe> {
+        self.scan_settings().probes
+    }
+
+    /// Probes for the given record types, failing on a type no probe looks up
+    pub fn scan_probes_for_types(&self, types: &[String]) -> CloudflareResult<Vec<DnsProbe>> {
+        let probes = self.scan_probes();
+        let unknown: Vec<&str> = types
+            .iter()
+            .filter(|t| !probes.iter().any(|p| &p.record_type == *t))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(CloudflareError::ValidationError(format!(
+                "Unknown record types to scan: {}",
+                unknown.join(", ")
+            )));
+        }
+        Ok(probes.into_iter().filter(|p| types.contains(&p.record_type)).collect())
+    }
+
+    fn scan_settings(&self) -> ScanSettings {
+        self.scan.read().unwrap_or_else(|e| e.into_inner()).clone()
     }
 
     /// Get the client or return an error if not configured
     fn get_client(&self) -> CloudflareResult<&CloudflareClient> {
         self.client.as_ref()
             .map(|c| c.as_ref())
-            .ok_or_else(|| CloudflareError::ConfigError("Cloudflare not configured. Please connect your account.".to_string()))
+            .ok_or(CloudflareError::NotConfigured)
     }
 
     /// List all DNS records
@@ -36,19 +142,46 @@ impl DnsService {
         client.list_dns_records(params).await
     }
 
+    /// Search DNS records, filtering by name, type and content on Cloudflare's side.
+    ///
+    /// `match_type` is `all` (the default) or `any` of the filters. Returns every
+    /// matching record, walking all pages; any `page`/`per_page` is ignored.
+    pub async fn search(&self, params: DnsListParams) -> CloudflareResult<Vec<DnsRecord>> {
+        let client = self.get_client()?;
+        client.list_all_dns_records(search_params(params)?).await
+    }
+
+    /// List one page of DNS records, fetching that page from Cloudflare
+    pub async fn list_page(&self, params: DnsListParams, pagination: Pagination) -> CloudflareResult<Page<DnsRecord>> {
+        let client = self.get_client()?;
+        let params = DnsListParams {
+            page: Some(pagination.page as i32),
+            per_page: Some(pagination.per_page as i32),
+            ..search_params(params)?
+        };
+
+        let (records, info) = client.list_dns_records_page(Some(params)).await?;
+        let total = match info {
+            Some(info) => info.total_count.max(0) as usize,
+            None => pagination.offset() + records.len(),
+        };
+        Ok(Page::new(records, pagination, total))
+    }
+
     /// Get a DNS record by ID
     pub async fn get(&self, id: &str) -> CloudflareResult<DnsRecord> {
         let client = self.get_client()?;
-        let records = client.list_dns_records(None).await?;
-        records
-            .into_iter()
-            .find(|r| r.id == id)
-            .ok_or(CloudflareError::NotFound(format!("DNS record {}", id)))
+        client.get_dns_record(id).await
     }
 
     /// Create a new DNS record
-    pub async fn create(&self, record: CreateDnsRecord) -> CloudflareResult<DnsRecord> {
+    pub async fn create(&self, mut record: CreateDnsRecord) -> CloudflareResult<DnsRecord> {
+        record.ttl = normalize_ttl(&record.record_type, record.ttl, record.proxied)?;
         let client = self.get_client()?;
+        let zone = client.get_zone().await?;
+        let existing = client.list_dns_records(None).await?;
+        validate_record(&record, &zone.name, &existing)?;
+
         info!("Creating DNS record: {} -> {}", record.name, record.content);
         let result = client.create_dns_record(record).await?;
         self.sync_to_local(&result).await?;
@@ -56,7 +189,8 @@ impl DnsService {
     }
 
     /// Update a DNS record
-    pub async fn update(&self, id: &str, record: UpdateDnsRecord) -> CloudflareResult<DnsRecord> {
+    pub async fn update(&self, id: &str, mut record: UpdateDnsRecord) -> CloudflareResult<DnsRecord> {
+        record.ttl = normalize_ttl(&record.record_type, record.ttl, record.proxied)?;
         let client = self.get_client()?;
         info!("Updating DNS record {}: {} -> {}", id, record.name, record.content);
         let result = client.update_dns_record(id, record).await?;
@@ -64,6 +198,31 @@ impl DnsService {
         Ok(result)
     }
 
+    /// Turn Cloudflare's proxy (orange cloud) on or off for a record, keeping
+    /// the rest of it as is. Enabling the proxy switches the TTL to automatic.
+    pub async fn set_proxied(&self, id: &str, proxied: bool) -> CloudflareResult<DnsRecord> {
+        let record = self.get(id).await?;
+        if !record.proxiable {
+            return Err(CloudflareError::ValidationError(format!(
+                "{} record {} cannot be proxied through Cloudflare",
+                record.record_type, record.name
+            )));
+        }
+        if record.proxied == proxied {
+            return Ok(record);
+        }
+
+        let update = UpdateDnsRecord {
+            record_type: record.record_type,
+            name: record.name,
+            content: record.content,
+            ttl: Some(if proxied { AUTO_TTL } else { record.ttl }),
+            proxied: Some(proxied),
+            priority: record.priority,
+        };
+        self.update(id, update).await
+    }
+
     /// Delete a DNS record
     pub async fn delete(&self, id: &str) -> CloudflareResult<DeleteResponse> {
         let client = self.get_client()?;
@@ -115,32 +274,69 @@ impl DnsService {
         Ok(())
     }
 
-    /// Full sync from Cloudflare to local
+    /// Suggest records to import for `domain` from what its current DNS
+    /// host serves, without creating anything. Works before the plugin is
+    /// connected to Cloudflare.
+    pub async fn scan_existing(&self, domain: &str) -> CloudflareResult<Vec<CreateDnsRecord>> {
+        self.scan_existing_with(domain, &self.scan_probes()).await
+    }
+
+    /// `scan_existing` with a specific probe list
+    pub async fn scan_existing_with(&self, domain: &str, probes: &[DnsProbe]) -> CloudflareResult<Vec<CreateDnsRecord>> {
+        let records = match &self.resolver {
+            Some(resolver) => scan_records(resolver.as_ref(), domain, probes).await?,
+            None => {
+                let nameservers = self.scan_settings().nameservers;
+                let resolver = NameserverResolver::for_domain(self.recursive.as_ref(), domain, &nameservers).await?;
+                scan_records(&resolver, domain, probes).await?
+            }
+        };
+        info!("DNS scan of {} found {} records", domain, records.len());
+        Ok(records)
+    }
+
+    /// Get the zone's DNSSEC status
+    pub async fn get_dnssec(&self) -> CloudflareResult<DnssecStatus> {
+        let client = self.get_client()?;
+        client.get_dnssec().await
+    }
+
+    /// Enable or disable DNSSEC
+    pub async fn set_dnssec(&self, enabled: bool) -> CloudflareResult<DnssecStatus> {
+        let client = self.get_client()?;
+        info!("{} DNSSEC", if enabled { "Enabling" } else { "Disabling" });
+        client.set_dnssec(enabled).await
+    }
+
+    /// Full sync from Cloudflare to local.
+    ///
+    /// Records are fetched page by page and written `SYNC_CONCURRENCY` at a
+    /// time; a rate-limited page fetch backs off and is retried.
     pub async fn full_sync(&self) -> CloudflareResult<SyncResult> {
         let client = self.get_client()?;
         info!("Starting full DNS sync from Cloudflare");
 
-        let records = client.list_dns_records(None).await?;
-        let mut synced = 0;
-        let mut errors = 0;
-
-        for record in &records {
-            match self.sync_to_local(record).await {
-                Ok(_) => synced += 1,
-                Err(e) => {
-                    tracing::warn!("Failed to sync record {}: {}", record.id, e);
-                    errors += 1;
+        let result = sync_records(
+            |page| {
+                client.list_dns_records_page(Some(DnsListParams {
+                    page: Some(page as i32),
+                    per_page: Some(SYNC_PAGE_SIZE),
+                    ..Default::default()
+                }))
+            },
+            |record| async move {
+                let result = self.sync_to_local(&record).await;
+                if let Err(e) = &result {
+                    warn!("Failed to sync record {}: {}", record.id, e);
                 }
-            }
-        }
-
-        info!("DNS sync complete: {} synced, {} errors", synced, errors);
+                result
+            },
+            SYNC_RATE_LIMIT_BACKOFF,
+        )
+        .await?;
 
-        Ok(SyncResult {
-            total: records.len(),
-            synced,
-            errors,
-        })
+        info!("DNS sync complete: {} synced, {} errors", result.synced, result.errors);
+        Ok(result)
     }
 
     /// Export DNS records as zone file format
@@ -171,6 +367,66 @@ impl DnsService {
 
         Ok(output)
     }
+
+    /// Parse a zone file and report what an import would do, without writing anything
+    pub async fn preview_import(&self, zone_file: &str) -> CloudflareResult<ImportPreview> {
+        let client = self.get_client()?;
+        let zone = client.get_zone().await?;
+        let existing = client.list_dns_records(None).await?;
+
+        let (parsed, errors) = parse_zone_file(zone_file, &zone.name);
+        let preview = ImportPreview::build(parsed, errors, &existing);
+
+        info!(
+            "DNS import preview: {} to create, {} to update, {} unchanged, {} errors",
+            preview.to_create, preview.to_update, preview.to_skip, preview.errors.len()
+        );
+
+        Ok(preview)
+    }
+
+    /// Compare the records on Cloudflare with the local copy, without changing either
+    pub async fn diff(&self) -> CloudflareResult<DnsDiff> {
+        let client = self.get_client()?;
+        let remote = client.list_dns_records(None).await?;
+        let local = self.list_local().await?;
+
+        let diff = DnsDiff::build(&remote, &local);
+
+        info!(
+            "DNS diff: {} added, {} removed, {} modified",
+            diff.added.len(), diff.removed.len(), diff.modified.len()
+        );
+
+        Ok(diff)
+    }
+
+    /// Load the records stored in the local database
+    async fn list_local(&self) -> CloudflareResult<Vec<DnsDiffRecord>> {
+        let rows: Vec<LocalDnsRow> = sqlx::query_as(
+            r#"
+            SELECT cloudflare_id, record_type, name, content, proxied, ttl, priority
+            FROM cloudflare_dns_records
+            ORDER BY name, record_type
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, record_type, name, content, proxied, ttl, priority)| DnsDiffRecord {
+                id,
+                record_type,
+                name,
+                content,
+                proxied: proxied.unwrap_or(false),
+                ttl: ttl.unwrap_or(1),
+                priority,
+            })
+            .collect())
+    }
 }
 
 /// Sync result
@@ -180,3 +436,1092 @@ pub struct SyncResult {
     pub synced: usize,
     pub errors: usize,
 }
+
+/// Fetch every page with `fetch_page` and store each record with `upsert`,
+/// running up to `SYNC_CONCURRENCY` upserts at once.
+///
+/// A page fetch that hits the rate limit waits `backoff` (doubling, with
+/// jitter) and is retried; failed upserts are counted, not fatal.
+async fn sync_records<P, PF, U, UF>(fetch_page: P, upsert: U, backoff: Duration) -> CloudflareResult<SyncResult>
+where
+    P: Fn(u32) -> PF,
+    PF: Future<Output = CloudflareResult<(Vec<DnsRecord>, Option<ResultInfo>)>>,
+    U: Fn(DnsRecord) -> UF,
+    UF: Future<Output = CloudflareResult<()>>,
+{
+    let mut result = SyncResult { total: 0, synced: 0, errors: 0 };
+    let mut page = 1;
+
+    loop {
+        let mut retries = 0;
+        let (records, info) = loop {
+            match fetch_page(page).await {
+                Err(CloudflareError::RateLimitExceeded) if retries < SYNC_RATE_LIMIT_RETRIES => {
+                    let delay = backoff * 2u32.pow(retries) + jitter(backoff);
+                    warn!("DNS sync rate limited on page {}, retrying in {:?}", page, delay);
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                other => break other?,
+            }
+        };
+
+        result.total += records.len();
+        let outcomes: Vec<_> = stream::iter(records)
+            .map(&upsert)
+            .buffer_unordered(SYNC_CONCURRENCY)
+            .collect()
+            .await;
+        for outcome in outcomes {
+            match outcome {
+                Ok(()) => result.synced += 1,
+                Err(_) => result.errors += 1,
+            }
+        }
+
+        match info {
+            Some(info) if (page as i32) < info.total_pages => page += 1,
+            _ => break,
+        }
+    }
+
+    Ok(result)
+}
+
+/// Random extra wait of up to half of `base`, so retries don't line up
+fn jitter(base: Duration) -> Duration {
+    base.mul_f64(rand::random::<f64>() / 2.0)
+}
+
+/// A record successfully parsed from a zone file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedZoneRecord {
+    pub line: usize,
+    pub record: CreateDnsRecord,
+}
+
+/// A zone file line that could not be parsed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneParseError {
+    pub line: usize,
+    pub content: String,
+    pub message: String,
+}
+
+/// What an import would do with a parsed record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    Create,
+    Update,
+    Skip,
+}
+
+/// A single parsed record and the action an import would take for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPreviewEntry {
+    pub line: usize,
+    pub action: ImportAction,
+    pub record: CreateDnsRecord,
+    pub existing_id: Option<String>,
+}
+
+/// Result of previewing a zone file import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub records: Vec<ImportPreviewEntry>,
+    pub errors: Vec<ZoneParseError>,
+    pub to_create: usize,
+    pub to_update: usize,
+    pub to_skip: usize,
+}
+
+impl ImportPreview {
+    /// Classify parsed records against the records already on the zone
+    pub fn build(
+        parsed: Vec<ParsedZoneRecord>,
+        errors: Vec<ZoneParseError>,
+        existing: &[DnsRecord],
+    ) -> Self {
+        let records: Vec<ImportPreviewEntry> = parsed
+            .into_iter()
+            .map(|p| {
+                let (action, existing_id) = classify_import_record(&p.record, existing);
+                ImportPreviewEntry {
+                    line: p.line,
+                    action,
+                    record: p.record,
+                    existing_id,
+                }
+            })
+            .collect();
+
+        let count = |action: ImportAction| records.iter().filter(|r| r.action == action).count();
+
+        Self {
+            to_create: count(ImportAction::Create),
+            to_update: count(ImportAction::Update),
+            to_skip: count(ImportAction::Skip),
+            records,
+            errors,
+        }
+    }
+}
+
+/// The fields of a DNS record that a sync compares
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsDiffRecord {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub name: String,
+    pub content: String,
+    pub proxied: bool,
+    pub ttl: i32,
+    pub priority: Option<i32>,
+}
+
+impl From<&DnsRecord> for DnsDiffRecord {
+    fn from(record: &DnsRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            record_type: record.record_type.clone(),
+            name: record.name.clone(),
+            content: record.content.clone(),
+            proxied: record.proxied,
+            ttl: record.ttl,
+            priority: record.priority,
+        }
+    }
+}
+
+/// A record whose local copy differs from Cloudflare
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsRecordChange {
+    pub before: DnsDiffRecord,
+    pub after: DnsDiffRecord,
+}
+
+/// Differences between the records on Cloudflare and the local database.
+///
+/// `added` records exist only on Cloudflare, `removed` records exist only
+/// locally, and `modified` records changed on Cloudflare since the last sync
+/// (`before` is the local copy, `after` the Cloudflare record).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DnsDiff {
+    pub added: Vec<DnsDiffRecord>,
+    pub removed: Vec<DnsDiffRecord>,
+    pub modified: Vec<DnsRecordChange>,
+}
+
+impl DnsDiff {
+    /// Match records by Cloudflare ID and compare type, name, content, proxied, TTL and priority
+    pub fn build(remote: &[DnsRecord], local: &[DnsDiffRecord]) -> Self {
+        let local_by_id: HashMap<&str, &DnsDiffRecord> =
+            local.iter().map(|r| (r.id.as_str(), r)).collect();
+        let remote_ids: HashSet<&str> = remote.iter().map(|r| r.id.as_str()).collect();
+
+        let mut diff = Self::default();
+
+        for record in remote {
+            let after = DnsDiffRecord::from(record);
+            match local_by_id.get(record.id.as_str()) {
+                None => diff.added.push(after),
+                Some(before) if **before != after => diff.modified.push(DnsRecordChange {
+                    before: (*before).clone(),
+                    after,
+                }),
+                Some(_) => {}
+            }
+        }
+
+        diff.removed = local
+            .iter()
+            .filter(|r| !remote_ids.contains(r.id.as_str()))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    /// Whether local and Cloudflare records already match
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Decide whether a parsed record would be created, update an existing record, or be skipped
+fn classify_import_record(
+    record: &CreateDnsRecord,
+    existing: &[DnsRecord],
+) -> (ImportAction, Option<String>) {
+    let same_name_and_type = |r: &&DnsRecord| {
+        r.record_type.eq_ignore_ascii_case(&record.record_type)
+            && r.name.eq_ignore_ascii_case(&record.name)
+    };
+
+    if let Some(current) = existing
+        .iter()
+        .filter(same_name_and_type)
+        .find(|r| r.content.eq_ignore_ascii_case(&record.content))
+    {
+        let ttl_matches = record.ttl.is_none_or(|ttl| ttl == current.ttl);
+        let proxied_matches = record.proxied.is_none_or(|p| p == current.proxied);
+        let priority_matches = record.priority.is_none() || record.priority == current.priority;
+
+        return if ttl_matches && proxied_matches && priority_matches {
+            (ImportAction::Skip, Some(current.id.clone()))
+        } else {
+            (ImportAction::Update, Some(current.id.clone()))
+        };
+    }
+
+    // Only one CNAME may exist per name, so a different target replaces it
+    if record.record_type.eq_ignore_ascii_case("CNAME") {
+        if let Some(current) = existing.iter().find(same_name_and_type) {
+            return (ImportAction::Update, Some(current.id.clone()));
+        }
+    }
+
+    (ImportAction::Create, None)
+}
+
+/// Check a new record against the zone before sending it to Cloudflare.
+///
+/// Catches mistakes the API would otherwise reject with less helpful errors:
+/// misplaced wildcards, an apex CNAME that can't be flattened, a CNAME sharing
+/// its name with other records, exact duplicates, and MX/SRV records without
+/// a priority. `record.name` may be relative to `zone_name` or `@`.
+pub fn validate_record(
+    record: &CreateDnsRecord,
+    zone_name: &str,
+    existing: &[DnsRecord],
+) -> CloudflareResult<()> {
+    let invalid = |message: String| Err(CloudflareError::ValidationError(message));
+
+    let zone = zone_name.trim_end_matches('.').to_lowercase();
+    let name = record_fqdn(&record.name, &zone);
+    let record_type = record.record_type.to_uppercase();
+
+    if name.split('.').skip(1).any(|label| label.contains('*'))
+        || name.split('.').next().is_some_and(|label| label.contains('*') && label != "*")
+    {
+        return invalid(format!(
+            "Invalid wildcard name '{}': '*' must be the entire leftmost label",
+            record.name
+        ));
+    }
+
+    if matches!(record_type.as_str(), "MX" | "SRV") && record.priority.is_none() {
+        return invalid(format!("{} records require a priority", record_type));
+    }
+
+    if record_type == "CNAME" && name == zone {
+        let target = record.content.trim_end_matches('.').to_lowercase();
+        if target.is_empty() || target.parse::<std::net::IpAddr>().is_ok() {
+            return invalid(format!(
+                "CNAME at the zone apex must point to a hostname to be flattened, not '{}'",
+                record.content
+            ));
+        }
+        if target == zone {
+            return invalid("CNAME at the zone apex cannot point to the apex itself".to_string());
+        }
+    }
+
+    let same_name: Vec<&DnsRecord> = existing
+        .iter()
+        .filter(|r| r.name.eq_ignore_ascii_case(&name))
+        .collect();
+
+    if same_name.iter().any(|r| {
+        r.record_type.eq_ignore_ascii_case(&record_type)
+            && r.content.trim_end_matches('.').eq_ignore_ascii_case(record.content.trim_end_matches('.'))
+    }) {
+        return invalid(format!(
+            "A {} record for {} with content '{}' already exists",
+            record_type, name, record.content
+        ));
+    }
+
+    if record_type == "CNAME" {
+        if let Some(conflict) = same_name.first() {
+            return invalid(format!(
+                "Cannot create a CNAME for {}: there is already a {} record with that name",
+                name, conflict.record_type
+            ));
+        }
+    } else if same_name.iter().any(|r| r.record_type.eq_ignore_ascii_case("CNAME")) {
+        return invalid(format!(
+            "Cannot create a {} record for {}: a CNAME already exists for that name",
+            record_type, name
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check a record's TTL against its proxy status, returning the TTL to send.
+///
+/// Proxied records always use the automatic TTL, so a missing TTL becomes
+/// `AUTO_TTL` and any other explicit value is rejected. Unproxied TTLs other
+/// than `AUTO_TTL` are clamped to `MIN_TTL..=MAX_TTL`. Only A, AAAA and
+/// CNAME records can be proxied.
+pub fn normalize_ttl(record_type: &str, ttl: Option<i32>, proxied: Option<bool>) -> CloudflareResult<Option<i32>> {
+    if proxied != Some(true) {
+        return Ok(ttl.map(|ttl| if ttl == AUTO_TTL { ttl } else { ttl.clamp(MIN_TTL, MAX_TTL) }));
+    }
+
+    let record_type = record_type.to_uppercase();
+    if !PROXIABLE_TYPES.contains(&record_type.as_str()) {
+        return Err(CloudflareError::ValidationError(format!(
+            "{} records cannot be proxied; only {} records can",
+            record_type,
+            PROXIABLE_TYPES.join(", ")
+        )));
+    }
+
+    match ttl {
+        None | Some(AUTO_TTL) => Ok(Some(AUTO_TTL)),
+        Some(ttl) => Err(CloudflareError::ValidationError(format!(
+            "Proxied records must use an automatic TTL ({}), not {}",
+            AUTO_TTL, ttl
+        ))),
+    }
+}
+
+/// Drop empty search filters, uppercase the record type and check the match mode
+fn search_params(params: DnsListParams) -> CloudflareResult<DnsListParams> {
+    let filter = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let match_type = filter(params.match_type).map(|m| m.to_lowercase());
+    if let Some(m) = match_type.as_deref().filter(|m| !matches!(*m, "all" | "any")) {
+        return Err(CloudflareError::ValidationError(format!(
+            "Invalid match mode '{}', expected all or any",
+            m
+        )));
+    }
+
+    Ok(DnsListParams {
+        name: filter(params.name),
+        record_type: filter(params.record_type).map(|t| t.to_uppercase()),
+        content: filter(params.content),
+        match_type,
+        ..params
+    })
+}
+
+/// Fully-qualified, lowercase form of a record name that may be relative to the zone
+fn record_fqdn(name: &str, zone: &str) -> String {
+    let name = name.trim_end_matches('.').to_lowercase();
+    if name == "@" || name.is_empty() {
+        zone.to_string()
+    } else if name == zone || name.ends_with(&format!(".{}", zone)) {
+        name
+    } else {
+        format!("{}.{}", name, zone)
+    }
+}
+
+/// Parse a BIND-style zone file into records that can be created on the zone.
+///
+/// Lines that fail to parse are collected as errors instead of aborting, so one
+/// bad record doesn't hide the rest of the file. SOA records are ignored since
+/// Cloudflare manages them. A `; proxied` comment (as written by
+/// `export_zone_file`) marks the record as proxied.
+pub fn parse_zone_file(
+    zone_file: &str,
+    zone_name: &str,
+) -> (Vec<ParsedZoneRecord>, Vec<ZoneParseError>) {
+    let mut origin = zone_name.trim_end_matches('.').to_lowercase();
+    let mut default_ttl: Option<i32> = None;
+    let mut last_owner: Option<String> = None;
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+
+    // Join records spanning multiple lines with parentheses
+    let mut entries: Vec<(usize, String, String, bool)> = Vec::new();
+    let mut pending: Option<(usize, String, String, bool)> = None;
+
+    for (idx, raw) in zone_file.lines().enumerate() {
+        let (data, comment) = split_zone_comment(raw);
+        let comment = comment.unwrap_or_default();
+
+        if let Some((line, original, mut joined, indented)) = pending.take() {
+            joined.push(' ');
+            joined.push_str(data);
+            if data.contains(')') {
+                entries.push((line, original, joined.replace(['(', ')'], " "), indented));
+            } else {
+                pending = Some((line, original, joined, indented));
+            }
+            continue;
+        }
+
+        if data.trim().is_empty() {
+            continue;
+        }
+
+        let indented = data.starts_with(' ') || data.starts_with('\t');
+        let data = if comment.to_lowercase().contains("proxied") {
+            format!("{} ;proxied", data)
+        } else {
+            data.to_string()
+        };
+
+        if data.contains('(') && !data.contains(')') {
+            pending = Some((idx + 1, raw.to_string(), data, indented));
+        } else {
+            entries.push((idx + 1, raw.to_string(), data.replace(['(', ')'], " "), indented));
+        }
+    }
+
+    if let Some((line, original, _, _)) = pending {
+        errors.push(ZoneParseError {
+            line,
+            content: original,
+            message: "Unterminated parenthesis".to_string(),
+        });
+    }
+
+    for (line, original, data, indented) in entries {
+        let proxied = data.ends_with(" ;proxied");
+        let data = data.trim_end_matches(" ;proxied");
+        let mut tokens = tokenize_zone_line(data);
+
+        if tokens.is_empty() {
+            continue;
+        }
+
+        let error = |message: String| ZoneParseError {
+            line,
+            content: original.clone(),
+            message,
+        };
+
+        // Directives
+        match tokens[0].to_uppercase().as_str() {
+            "$ORIGIN" => {
+                match tokens.get(1) {
+                    Some(o) => origin = o.trim_end_matches('.').to_lowercase(),
+                    None => errors.push(error("$ORIGIN requires a domain".to_string())),
+                }
+                continue;
+            }
+            "$TTL" => {
+                match tokens.get(1).and_then(|t| t.parse::<i32>().ok()) {
+                    Some(ttl) => default_ttl = Some(ttl),
+                    None => errors.push(error("$TTL requires a numeric value".to_string())),
+                }
+                continue;
+            }
+            d if d.starts_with('$') => {
+                errors.push(error(format!("Unsupported directive {}", tokens[0])));
+                continue;
+            }
+            _ => {}
+        }
+
+        let owner = if indented {
+            match &last_owner {
+                Some(owner) => owner.clone(),
+                None => {
+                    errors.push(error("Record has no owner name".to_string()));
+                    continue;
+                }
+            }
+        } else {
+            let owner = qualify_zone_name(&tokens.remove(0), &origin);
+            last_owner = Some(owner.clone());
+            owner
+        };
+
+        // Optional TTL and class, in either order
+        let mut ttl = None;
+        while let Some(token) = tokens.first() {
+            if let Ok(value) = token.parse::<i32>() {
+                ttl = Some(value);
+                tokens.remove(0);
+            } else if matches!(token.to_uppercase().as_str(), "IN" | "CH" | "HS") {
+                tokens.remove(0);
+            } else {
+                break;
+            }
+        }
+
+        if tokens.is_empty() {
+            errors.push(error("Missing record type".to_string()));
+            continue;
+        }
+
+        let record_type = tokens.remove(0).to_uppercase();
+        let rdata = tokens;
+
+        let parsed = match record_type.as_str() {
+            "SOA" => continue,
+            "A" => match rdata.first().map(|v| v.parse::<Ipv4Addr>()) {
+                Some(Ok(ip)) => Ok((ip.to_string(), None)),
+                _ => Err(format!("Invalid IPv4 address '{}'", rdata.join(" "))),
+            },
+            "AAAA" => match rdata.first().map(|v| v.parse::<Ipv6Addr>()) {
+                Some(Ok(ip)) => Ok((ip.to_string(), None)),
+                _ => Err(format!("Invalid IPv6 address '{}'", rdata.join(" "))),
+            },
+            "CNAME" | "NS" | "PTR" => match rdata.first() {
+                Some(target) => Ok((qualify_zone_name(target, &origin), None)),
+                None => Err(format!("{} record requires a target", record_type)),
+            },
+            "MX" => match (rdata.first().map(|p| p.parse::<i32>()), rdata.get(1)) {
+                (Some(Ok(priority)), Some(target)) => {
+                    Ok((qualify_zone_name(target, &origin), Some(priority)))
+                }
+                _ => Err("MX record requires a numeric priority and a target".to_string()),
+            },
+            "TXT" => {
+                if rdata.is_empty() {
+                    Err("TXT record requires a value".to_string())
+                } else if rdata.iter().all(|t| t.starts_with('"')) {
+                    Ok((rdata.iter().map(|t| t.trim_matches('"')).collect::<String>(), None))
+                } else {
+                    Ok((rdata.join(" "), None))
+                }
+            }
+            "CAA" => {
+                if rdata.len() < 3 {
+                    Err("CAA record requires flags, tag and value".to_string())
+                } else {
+                    Ok((rdata.join(" "), None))
+                }
+            }
+            other => Err(format!("Unsupported record type {}", other)),
+        };
+
+        match parsed {
+            Ok((content, priority)) => records.push(ParsedZoneRecord {
+                line,
+                record: CreateDnsRecord {
+                    record_type,
+                    name: owner,
+                    content,
+                    ttl: ttl.or(default_ttl),
+                    proxied: if proxied { Some(true) } else { None },
+                    priority,
+                },
+            }),
+            Err(message) => errors.push(error(message)),
+        }
+    }
+
+    (records, errors)
+}
+
+/// Split a zone file line into data and comment, ignoring `;` inside quotes
+fn split_zone_comment(line: &str) -> (&str, Option<&str>) {
+    let mut in_quotes = false;
+    for (i, ch) in line.char_indices() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => return (&line[..i], Some(&line[i + 1..])),
+            _ => {}
+        }
+    }
+    (line, None)
+}
+
+/// Split a zone file line on whitespace, keeping quoted strings (with quotes) as one token
+fn tokenize_zone_line(data: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for ch in data.chars() {
+        match ch {
+            '"' => {
+                current.push(ch);
+                in_quotes = !in_quotes;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// Expand a zone file name relative to the origin
+fn qualify_zone_name(name: &str, origin: &str) -> String {
+    if name == "@" {
+        origin.to_string()
+    } else if let Some(absolute) = name.strip_suffix('.') {
+        absolute.to_lowercase()
+    } else {
+        format!("{}.{}", name.to_lowercase(), origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{lazy_pool, mock_client, mock_service, success};
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parse_zone_file_reports_invalid_record_and_keeps_valid_ones() {
+        let zone_file = "\
+$ORIGIN example.com.
+$TTL 3600
+@\tIN\tA\t192.0.2.1 ; proxied
+www\t300\tIN\tCNAME\texample.com.
+bad\tIN\tA\tnot-an-ip
+@\tIN\tMX\t10 mail
+@\tIN\tTXT\t\"v=spf1 include:_spf.example.com ~all\"
+";
+        let (records, errors) = parse_zone_file(zone_file, "example.com");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 5);
+        assert!(errors[0].message.contains("Invalid IPv4"));
+
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].record.name, "example.com");
+        assert_eq!(records[0].record.proxied, Some(true));
+        assert_eq!(records[0].record.ttl, Some(3600));
+        assert_eq!(records[1].record.content, "example.com");
+        assert_eq!(records[1].record.ttl, Some(300));
+        assert_eq!(records[2].record.content, "mail.example.com");
+        assert_eq!(records[2].record.priority, Some(10));
+        assert_eq!(records[3].record.content, "v=spf1 include:_spf.example.com ~all");
+    }
+
+    #[tokio::test]
+    async fn test_scan_probes_for_types_rejects_types_without_probes() {
+        let service = DnsService::new_unconfigured(lazy_pool())
+            .with_scan_probes(vec![DnsProbe::new("@", "A"), DnsProbe::new("www", "CNAME"), DnsProbe::new("@", "TXT")]);
+
+        let probes = service.scan_probes_for_types(&["CNAME".to_string(), "A".to_string()]).unwrap();
+        assert_eq!(probes, vec![DnsProbe::new("@", "A"), DnsProbe::new("www", "CNAME")]);
+
+        let err = service
+            .scan_probes_for_types(&["A".to_string(), "SRV".to_string(), "MX".to_string()])
+            .unwrap_err();
+        match err {
+            CloudflareError::ValidationError(message) => assert!(message.ends_with("SRV, MX"), "{}", message),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    fn remote_record(id: &str, name: &str, content: &str) -> DnsRecord {
+        DnsRecord {
+            id: id.to_string(),
+            record_type: "A".to_string(),
+            name: name.to_string(),
+            content: content.to_string(),
+            proxiable: true,
+            proxied: true,
+            ttl: 1,
+            locked: false,
+            zone_id: "zone-1".to_string(),
+            zone_name: "example.com".to_string(),
+            created_on: None,
+            modified_on: None,
+            priority: None,
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_dns_diff_reports_added_modified_and_removed_records() {
+        let remote = vec![
+            remote_record("rec-1", "example.com", "192.0.2.1"),
+            remote_record("rec-2", "www.example.com", "192.0.2.20"),
+            remote_record("rec-4", "new.example.com", "192.0.2.4"),
+        ];
+        let local: Vec<DnsDiffRecord> = vec![
+            DnsDiffRecord::from(&remote[0]),
+            DnsDiffRecord::from(&remote_record("rec-2", "www.example.com", "192.0.2.2")),
+            DnsDiffRecord::from(&remote_record("rec-3", "old.example.com", "192.0.2.3")),
+        ];
+
+        let diff = DnsDiff::build(&remote, &local);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].id, "rec-4");
+
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].before.content, "192.0.2.2");
+        assert_eq!(diff.modified[0].after.content, "192.0.2.20");
+
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].id, "rec-3");
+
+        assert!(DnsDiff::build(&remote[..1], &local[..1]).is_empty());
+    }
+
+    fn new_record(record_type: &str, name: &str, content: &str) -> CreateDnsRecord {
+        CreateDnsRecord {
+            record_type: record_type.to_string(),
+            name: name.to_string(),
+            content: content.to_string(),
+            ttl: None,
+            proxied: None,
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn test_import_preview_skips_matches_updates_changes_and_creates_new_records() {
+        let existing = vec![
+            remote_record("rec-1", "example.com", "192.0.2.1"),
+            remote_record("rec-2", "www.example.com", "192.0.2.2"),
+        ];
+        let parsed = vec![
+            ParsedZoneRecord { line: 1, record: new_record("A", "EXAMPLE.com", "192.0.2.1") },
+            ParsedZoneRecord {
+                line: 2,
+                record: CreateDnsRecord { ttl: Some(300), ..new_record("A", "www.example.com", "192.0.2.2") },
+            },
+            ParsedZoneRecord { line: 3, record: new_record("A", "api.example.com", "192.0.2.3") },
+        ];
+
+        let preview = ImportPreview::build(parsed, Vec::new(), &existing);
+
+        let actions: Vec<(usize, ImportAction, Option<&str>)> = preview
+            .records
+            .iter()
+            .map(|r| (r.line, r.action, r.existing_id.as_deref()))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (1, ImportAction::Skip, Some("rec-1")),
+                (2, ImportAction::Update, Some("rec-2")),
+                (3, ImportAction::Create, None),
+            ]
+        );
+        assert_eq!((preview.to_skip, preview.to_update, preview.to_create), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_validate_record_rejects_unflattenable_apex_cname() {
+        let err = validate_record(&new_record("CNAME", "@", "192.0.2.1"), "example.com", &[])
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("flattened")));
+
+        let err = validate_record(&new_record("CNAME", "example.com", "example.com."), "example.com", &[])
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("apex itself")));
+
+        validate_record(&new_record("CNAME", "@", "app.herokudns.com"), "example.com", &[]).unwrap();
+    }
+
+    #[test]
+    fn test_validate_record_rejects_a_and_cname_collisions() {
+        let existing = vec![remote_record("rec-1", "www.example.com", "192.0.2.1")];
+
+        let err = validate_record(&new_record("CNAME", "www", "example.com"), "example.com", &existing)
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("already a A record")));
+
+        let mut cname = remote_record("rec-2", "blog.example.com", "example.com");
+        cname.record_type = "CNAME".to_string();
+        let err = validate_record(&new_record("A", "blog", "192.0.2.2"), "example.com", &[cname])
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("CNAME already exists")));
+
+        let err = validate_record(&new_record("A", "www.example.com", "192.0.2.1"), "example.com", &existing)
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("already exists")));
+
+        // Round-robin A records are fine
+        validate_record(&new_record("A", "www", "192.0.2.9"), "example.com", &existing).unwrap();
+
+        assert!(validate_record(&new_record("MX", "@", "mail.example.com"), "example.com", &[]).is_err());
+        assert!(validate_record(&new_record("A", "foo.*", "192.0.2.1"), "example.com", &[]).is_err());
+        validate_record(&new_record("A", "*", "192.0.2.1"), "example.com", &[]).unwrap();
+    }
+
+    #[test]
+    fn test_normalize_ttl_for_proxied_records() {
+        assert_eq!(normalize_ttl("A", None, Some(true)).unwrap(), Some(AUTO_TTL));
+        assert_eq!(normalize_ttl("cname", Some(1), Some(true)).unwrap(), Some(AUTO_TTL));
+
+        let err = normalize_ttl("A", Some(300), Some(true)).unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("automatic TTL")));
+    }
+
+    #[test]
+    fn test_normalize_ttl_clamps_unproxied_records() {
+        assert_eq!(normalize_ttl("A", None, None).unwrap(), None);
+        assert_eq!(normalize_ttl("A", Some(1), Some(false)).unwrap(), Some(AUTO_TTL));
+        assert_eq!(normalize_ttl("TXT", Some(30), None).unwrap(), Some(MIN_TTL));
+        assert_eq!(normalize_ttl("MX", Some(3600), Some(false)).unwrap(), Some(3600));
+        assert_eq!(normalize_ttl("A", Some(604_800), Some(false)).unwrap(), Some(MAX_TTL));
+    }
+
+    #[test]
+    fn test_normalize_ttl_rejects_unproxiable_types() {
+        for record_type in ["MX", "TXT", "NS"] {
+            let err = normalize_ttl(record_type, None, Some(true)).unwrap_err();
+            assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("cannot be proxied")));
+        }
+        assert!(normalize_ttl("TXT", None, Some(false)).is_ok());
+    }
+
+    fn page_info(page: i32, total_pages: i32) -> Option<ResultInfo> {
+        Some(ResultInfo { page, per_page: 10, count: 10, total_count: total_pages * 10, total_pages })
+    }
+
+    #[tokio::test]
+    async fn test_sync_records_caps_concurrent_upserts() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+
+        let result = sync_records(
+            |page| async move {
+                let records = (0..10)
+                    .map(|i| remote_record(&format!("{}-{}", page, i), "example.com", "192.0.2.1"))
+                    .collect();
+                Ok((records, page_info(page as i32, 3)))
+            },
+            |record| {
+                let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    if record.id.ends_with("-9") {
+                        Err(CloudflareError::DatabaseError("boom".to_string()))
+                    } else {
+                        Ok(())
+                    }
+                }
+            },
+            Duration::from_millis(1),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.total, 30);
+        assert_eq!(result.synced, 27);
+        assert_eq!(result.errors, 3);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), SYNC_CONCURRENCY);
+    }
+
+    #[tokio::test]
+    async fn test_sync_records_backs_off_when_rate_limited() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let calls = AtomicU32::new(0);
+        let backoff = Duration::from_millis(40);
+        let started = std::time::Instant::now();
+
+        let result = sync_records(
+            |page| {
+                let attempt = calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if attempt == 0 {
+                        Err(CloudflareError::RateLimitExceeded)
+                    } else {
+                        Ok((vec![remote_record("a", "example.com", "192.0.2.1")], page_info(page as i32, 1)))
+                    }
+                }
+            },
+            |_| async { Ok(()) },
+            backoff,
+        )
+        .await
+        .unwrap();
+
+        assert!(started.elapsed() >= backoff);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!((result.total, result.synced, result.errors), (1, 1, 0));
+
+        // Other upstream errors still fail the sync straight away
+        let err = sync_records(
+            |_| async { Err::<(Vec<DnsRecord>, Option<ResultInfo>), _>(CloudflareError::NotFound("zone".to_string())) },
+            |_| async { Ok(()) },
+            backoff,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, CloudflareError::NotFound(_)));
+    }
+
+    fn record_json(id: &str, record_type: &str, proxiable: bool, proxied: bool, ttl: i32) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "type": record_type, "name": "www.example.com", "content": "203.0.113.10",
+            "proxiable": proxiable, "proxied": proxied, "ttl": ttl, "locked": false,
+            "zone_id": "zone", "zone_name": "example.com"
+        })
+    }
+
+    /// Serve `records` from the list endpoint and each from its by-ID endpoint
+    async fn mount_records(server: &MockServer, records: serde_json::Value) {
+        for record in records.as_array().unwrap() {
+            Mock::given(method("GET"))
+                .and(path(format!("/zones/zone/dns_records/{}", record["id"].as_str().unwrap())))
+                .respond_with(success(record.clone()))
+                .mount(server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/dns_records"))
+            .respond_with(success(records))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_get_uses_record_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/dns_records/rec-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "errors": [], "messages": [],
+                "result": record_json("rec-1", "A", true, false, 3600)
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/dns_records/missing"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 7003, "message": "Could not route to /zones/zone/dns_records/missing" }],
+                "messages": [], "result": null
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/dns_records"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let dns = mock_service(&server, DnsService::new);
+        assert_eq!(dns.get("rec-1").await.unwrap().id, "rec-1");
+        assert!(matches!(dns.get("missing").await, Err(CloudflareError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_search_forwards_filters() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/dns_records"))
+            .and(query_param("content", "203.0.113.10"))
+            .and(query_param("type", "A"))
+            .and(query_param("match", "any"))
+            .respond_with(success(serde_json::json!([record_json("rec-1", "A", true, false, 3600)])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dns = mock_service(&server, DnsService::new);
+        let records = dns
+            .search(DnsListParams {
+                content: Some(" 203.0.113.10 ".to_string()),
+                record_type: Some("a".to_string()),
+                name: Some("".to_string()),
+                match_type: Some("any".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+
+        let requests = server.received_requests().await.unwrap();
+        assert!(!requests[0].url.query().unwrap().contains("name="));
+
+        let err = dns
+            .search(DnsListParams { match_type: Some("some".to_string()), ..Default::default() })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_search_returns_matches_from_every_page() {
+        let server = MockServer::start().await;
+        for (page, id) in [("1", "rec-1"), ("2", "rec-2")] {
+            Mock::given(method("GET"))
+                .and(path("/zones/zone/dns_records"))
+                .and(query_param("type", "A"))
+                .and(query_param("page", page))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true, "errors": [], "messages": [],
+                    "result": [record_json(id, "A", true, false, 3600)],
+                    "result_info": { "page": 1, "per_page": 1, "count": 1, "total_count": 2, "total_pages": 2 }
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let dns = mock_service(&server, DnsService::new);
+        let records = dns
+            .search(DnsListParams { record_type: Some("A".to_string()), page: Some(2), ..Default::default() })
+            .await
+            .unwrap();
+        let ids: Vec<_> = records.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["rec-1", "rec-2"]);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_set_proxied_updates_fetched_record() {
+        let server = MockServer::start().await;
+        mount_records(&server, serde_json::json!([record_json("rec-1", "A", true, false, 3600)])).await;
+        Mock::given(method("PUT"))
+            .and(path("/zones/zone/dns_records/rec-1"))
+            .and(body_json(serde_json::json!({
+                "type": "A", "name": "www.example.com", "content": "203.0.113.10",
+                "ttl": AUTO_TTL, "proxied": true
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "errors": [], "messages": [],
+                "result": record_json("rec-1", "A", true, true, AUTO_TTL)
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let record = DnsService::new(mock_client(&server), db).set_proxied("rec-1", true).await.unwrap();
+        assert!(record.proxied);
+        assert_eq!(record.ttl, AUTO_TTL);
+    }
+
+    #[tokio::test]
+    async fn test_set_proxied_rejects_unproxiable_and_skips_no_ops() {
+        let server = MockServer::start().await;
+        mount_records(
+            &server,
+            serde_json::json!([
+                record_json("mx-1", "MX", false, false, 3600),
+                record_json("rec-1", "A", true, true, AUTO_TTL)
+            ]),
+        )
+        .await;
+        let dns = mock_service(&server, DnsService::new);
+
+        let err = dns.set_proxied("mx-1", true).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("cannot be proxied")));
+
+        // Already proxied: returned as is without an update
+        assert!(dns.set_proxied("rec-1", true).await.unwrap().proxied);
+        let requests = server.received_requests().await.unwrap();
+        assert!(requests.iter().all(|r| r.method == wiremock::http::Method::Get));
+    }
+}
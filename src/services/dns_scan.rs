@@ -0,0 +1,809 @@
+//! Discover a domain's existing DNS records
+//!
+//! Before a zone is proxied its records still live at the previous DNS
+//! host. Scanning looks up a list of common names and record types on the
+//! domain's authoritative nameservers and turns the answers into records
+//! that could be created on Cloudflare, so onboarding doesn't start from an
+//! empty zone.
+
+use crate::error::{CloudflareError, CloudflareResult};
+use crate::models::CreateDnsRecord;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tracing::warn;
+
+/// Public DNS-over-HTTPS endpoint answering in JSON, used to find a domain's
+/// nameservers and their addresses
+pub const DEFAULT_DOH_ENDPOINT: &str = "https://cloudflare-dns.com/dns-query";
+
+/// How long a single lookup may take
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Port nameservers answer on
+const DNS_PORT: u16 = 53;
+
+/// UDP response size advertised through EDNS, so long TXT records rarely
+/// need the TCP retry
+const EDNS_UDP_SIZE: u16 = 4096;
+
+/// Record types a scan can look up
+pub const SCAN_RECORD_TYPES: &[&str] = &["A", "AAAA", "CNAME", "MX", "TXT"];
+
+/// One record in a resolver's answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsAnswer {
+    /// Owner name, with or without the trailing dot
+    pub name: String,
+    /// `A`, `AAAA`, `CNAME`, `MX`, `TXT`, ...
+    pub record_type: String,
+    /// Record data in presentation format, e.g. `10 mail.example.com.` for MX
+    pub data: String,
+}
+
+/// Looks up DNS records
+#[async_trait]
+pub trait DnsResolver: Send + Sync {
+    /// Answers for `name` and `record_type`, including any CNAME followed to
+    /// reach them; empty if the name doesn't exist
+    async fn lookup(&self, name: &str, record_type: &str) -> CloudflareResult<Vec<DnsAnswer>>;
+}
+
+/// Resolver using the JSON flavour of DNS over HTTPS
+pub struct DohResolver {
+    http: reqwest::Client,
+    endpoint: String,
+}
+
+impl DohResolver {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: endpoint.to_string(),
+        }
+    }
+}
+
+impl Default for DohResolver {
+    fn default() -> Self {
+        Self::new(DEFAULT_DOH_ENDPOINT)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohAnswer {
+    name: String,
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+/// DNS RCODE for a name that doesn't exist
+const NXDOMAIN: u32 = 3;
+
+#[async_trait]
+impl DnsResolver for DohResolver {
+    async fn lookup(&self, name: &str, record_type: &str) -> CloudflareResult<Vec<DnsAnswer>> {
+        let response = self
+            .http
+            .get(&self.endpoint)
+            .query(&[("name", name), ("type", record_type)])
+            .header(reqwest::header::ACCEPT, "application/dns-json")
+            .timeout(LOOKUP_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| CloudflareError::NetworkError(format!("DNS lookup of {} failed: {}", name, e)))?;
+        if !response.status().is_success() {
+            return Err(CloudflareError::NetworkError(format!(
+                "DNS lookup of {} failed with HTTP {}",
+                name,
+                response.status()
+            )));
+        }
+
+        let body: DohResponse = response
+            .json()
+            .await
+            .map_err(|e| CloudflareError::Internal(format!("Invalid DNS-over-HTTPS response: {}", e)))?;
+        match body.status {
+            0 | NXDOMAIN => {}
+            rcode => {
+                return Err(CloudflareError::NetworkError(format!(
+                    "DNS lookup of {} {} failed with RCODE {}",
+                    name, record_type, rcode
+                )))
+            }
+        }
+
+        Ok(body
+            .answer
+            .into_iter()
+            .filter_map(|answer| {
+                Some(DnsAnswer {
+                    record_type: record_type_name(answer.record_type)?.to_string(),
+                    name: answer.name,
+                    data: answer.data,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Wire codes of the record types a scan understands
+const RECORD_TYPES: &[(u16, &str)] = &[(1, "A"), (2, "NS"), (5, "CNAME"), (15, "MX"), (16, "TXT"), (28, "AAAA")];
+
+/// Name of the record types a scan understands
+fn record_type_name(code: u16) -> Option<&'static str> {
+    RECORD_TYPES.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+}
+
+fn record_type_code(name: &str) -> Option<u16> {
+    RECORD_TYPES.iter().find(|(_, n)| *n == name).map(|(code, _)| *code)
+}
+
+/// Resolver asking a domain's authoritative nameservers directly, so the
+/// answers are what the DNS host serves right now rather than what a public
+/// resolver has cached
+pub struct NameserverResolver {
+    servers: Vec<SocketAddr>,
+}
+
+impl NameserverResolver {
+    pub fn new(servers: Vec<SocketAddr>) -> Self {
+        Self { servers }
+    }
+
+    /// Resolver for `domain`'s nameservers, or for `nameservers` (host names
+    /// or IP addresses) when any are given, e.g. the previous host's once the
+    /// registrar already points at Cloudflare. NS records and nameserver
+    /// addresses are looked up through `recursive`.
+    pub async fn for_domain(
+        recursive: &dyn DnsResolver,
+        domain: &str,
+        nameservers: &[String],
+    ) -> CloudflareResult<Self> {
+        let domain = normalize_domain(domain)?;
+        let hosts: Vec<String> = if nameservers.is_empty() {
+            recursive
+                .lookup(&domain, "NS")
+                .await?
+                .into_iter()
+                .filter(|a| a.record_type == "NS" && same_name(&a.name, &domain))
+                .map(|a| trim_dot(a.data.trim()).to_string())
+                .collect()
+        } else {
+            nameservers.to_vec()
+        };
+
+        let mut servers = Vec::new();
+        for host in &hosts {
+            if let Ok(ip) = host.parse::<IpAddr>() {
+                servers.push(SocketAddr::new(ip, DNS_PORT));
+                continue;
+            }
+            match recursive.lookup(host, "A").await {
+                Ok(answers) => servers.extend(
+                    answers
+                        .iter()
+                        .filter(|a| a.record_type == "A")
+                        .filter_map(|a| a.data.trim().parse::<IpAddr>().ok())
+                        .map(|ip| SocketAddr::new(ip, DNS_PORT)),
+                ),
+                Err(e) => warn!("Couldn't resolve nameserver {}: {}", host, e),
+            }
+        }
+
+        if servers.is_empty() {
+            return Err(CloudflareError::ValidationError(format!(
+                "No nameservers found for {}; set the nameservers to scan in the DNS scan settings",
+                domain
+            )));
+        }
+        Ok(Self::new(servers))
+    }
+}
+
+#[async_trait]
+impl DnsResolver for NameserverResolver {
+    async fn lookup(&self, name: &str, record_type: &str) -> CloudflareResult<Vec<DnsAnswer>> {
+        let qtype = record_type_code(record_type)
+            .ok_or_else(|| CloudflareError::ValidationError(format!("Unsupported record type {}", record_type)))?;
+
+        // Nameservers of a zone serve the same data, so the first answer wins
+        let mut last_error = None;
+        for server in &self.servers {
+            match tokio::time::timeout(LOOKUP_TIMEOUT, query_nameserver(*server, name, qtype)).await {
+                Ok(Ok(answers)) => return Ok(answers),
+                Ok(Err(e)) => last_error = Some(e),
+                Err(_) => {
+                    last_error = Some(CloudflareError::NetworkError(format!(
+                        "DNS lookup of {} on {} timed out",
+                        name, server
+                    )))
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| CloudflareError::NetworkError("No nameservers to query".to_string())))
+    }
+}
+
+/// Ask `server` for `name`, over UDP and again over TCP if the answer was truncated
+async fn query_nameserver(server: SocketAddr, name: &str, qtype: u16) -> CloudflareResult<Vec<DnsAnswer>> {
+    let network_error = |e: std::io::Error| CloudflareError::NetworkError(format!("DNS lookup of {} failed: {}", name, e));
+    let id = rand::random::<u16>();
+    let query = encode_query(id, name, qtype)?;
+
+    let local: SocketAddr = if server.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(local).await.map_err(network_error)?;
+    socket.connect(server).await.map_err(network_error)?;
+    socket.send(&query).await.map_err(network_error)?;
+    let mut buf = vec![0u8; EDNS_UDP_SIZE as usize];
+    let response = loop {
+        let len = socket.recv(&mut buf).await.map_err(network_error)?;
+        // Ignore stray datagrams answering some other query
+        if len >= 2 && buf[..2] == id.to_be_bytes() {
+            break buf[..len].to_vec();
+        }
+    };
+
+    let truncated = response.len() > 2 && response[2] & 0x02 != 0;
+    let response = if truncated {
+        let mut stream = TcpStream::connect(server).await.map_err(network_error)?;
+        let mut framed = (query.len() as u16).to_be_bytes().to_vec();
+        framed.extend_from_slice(&query);
+        stream.write_all(&framed).await.map_err(network_error)?;
+        let len = stream.read_u16().await.map_err(network_error)?;
+        let mut response = vec![0u8; len as usize];
+        stream.read_exact(&mut response).await.map_err(network_error)?;
+        response
+    } else {
+        response
+    };
+
+    decode_response(id, name, &response)
+}
+
+/// Non-recursive query for `name` with an EDNS record raising the UDP size
+fn encode_query(id: u16, name: &str, qtype: u16) -> CloudflareResult<Vec<u8>> {
+    let mut query = Vec::with_capacity(64);
+    query.extend_from_slice(&id.to_be_bytes());
+    // Flags, then one question and one additional (OPT) record
+    query.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 1]);
+    for label in trim_dot(name).split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(CloudflareError::ValidationError(format!("Invalid DNS name: {}", name)));
+        }
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&qtype.to_be_bytes());
+    query.extend_from_slice(&1u16.to_be_bytes());
+    // OPT: root name, type 41, class carries the UDP size, no flags or options
+    query.push(0);
+    query.extend_from_slice(&41u16.to_be_bytes());
+    query.extend_from_slice(&EDNS_UDP_SIZE.to_be_bytes());
+    query.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    Ok(query)
+}
+
+/// Answers in a nameserver's response, in the presentation format the
+/// DNS-over-HTTPS JSON API uses
+fn decode_response(id: u16, name: &str, message: &[u8]) -> CloudflareResult<Vec<DnsAnswer>> {
+    let malformed = || CloudflareError::Internal(format!("Malformed DNS response for {}", name));
+    let u16_at = |offset: usize| -> CloudflareResult<u16> {
+        message
+            .get(offset..offset + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(malformed)
+    };
+
+    if message.len() < 12 || u16_at(0)? != id || message[2] & 0x80 == 0 {
+        return Err(malformed());
+    }
+    match message[3] & 0x0f {
+        0 | 3 => {}
+        rcode => {
+            return Err(CloudflareError::NetworkError(format!(
+                "DNS lookup of {} failed with RCODE {}",
+                name, rcode
+            )))
+        }
+    }
+
+    let questions = u16_at(4)?;
+    let answers = u16_at(6)?;
+    let mut offset = 12;
+    for _ in 0..questions {
+        offset = read_name(message, offset).ok_or_else(malformed)?.1 + 4;
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        let (owner, next) = read_name(message, offset).ok_or_else(malformed)?;
+        let rtype = u16_at(next)?;
+        let rdlength = u16_at(next + 8)? as usize;
+        let start = next + 10;
+        let rdata = message.get(start..start + rdlength).ok_or_else(malformed)?;
+        offset = start + rdlength;
+
+        let Some(record_type) = record_type_name(rtype) else {
+            continue;
+        };
+        let data = match record_type {
+            "A" => <[u8; 4]>::try_from(rdata).map(|b| Ipv4Addr::from(b).to_string()).ok(),
+            "AAAA" => <[u8; 16]>::try_from(rdata).map(|b| Ipv6Addr::from(b).to_string()).ok(),
+            "CNAME" | "NS" => read_name(message, start).map(|(target, _)| format!("{}.", target)),
+            "MX" => {
+                let preference = u16_at(start)?;
+                read_name(message, start + 2).map(|(host, _)| format!("{} {}.", preference, host))
+            }
+            _ => Some(txt_presentation(rdata)),
+        };
+        let data = data.ok_or_else(malformed)?;
+        records.push(DnsAnswer {
+            name: format!("{}.", owner),
+            record_type: record_type.to_string(),
+            data,
+        });
+    }
+
+    Ok(records)
+}
+
+/// Name starting at `offset`, following compression pointers, and the
+/// offset just past it
+fn read_name(message: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    // Bounds pointer loops in a hostile response
+    for _ in 0..128 {
+        let len = *message.get(offset)? as usize;
+        match len {
+            0 => return Some((labels.join("."), end.unwrap_or(offset + 1))),
+            len if len & 0xc0 == 0xc0 => {
+                let pointer = ((len & 0x3f) << 8) | *message.get(offset + 1)? as usize;
+                end.get_or_insert(offset + 2);
+                offset = pointer;
+            }
+            len => {
+                let label = message.get(offset + 1..offset + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                offset += 1 + len;
+            }
+        }
+    }
+    None
+}
+
+/// TXT character-strings quoted the way DNS-over-HTTPS returns them
+fn txt_presentation(rdata: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut rest = rdata;
+    while let Some((&len, tail)) = rest.split_first() {
+        let len = (len as usize).min(tail.len());
+        let text = String::from_utf8_lossy(&tail[..len]).replace('"', "\\\"");
+        parts.push(format!("\"{}\"", text));
+        rest = &tail[len..];
+    }
+    parts.join(" ")
+}
+
+/// A name and record type to look up, relative to the scanned domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsProbe {
+    /// Subdomain label, or `@` for the domain itself
+    pub name: String,
+    pub record_type: String,
+}
+
+impl DnsProbe {
+    pub fn new(name: &str, record_type: &str) -> Self {
+        Self { name: name.to_string(), record_type: record_type.to_string() }
+    }
+
+    /// Fully qualified name for `domain`
+    fn fqdn(&self, domain: &str) -> String {
+        match self.name.as_str() {
+            "@" | "" => domain.to_string(),
+            name => format!("{}.{}", name, domain),
+        }
+    }
+}
+
+/// Check probes before they're saved as the scan list
+pub fn validate_probes(probes: &[DnsProbe]) -> CloudflareResult<()> {
+    if probes.is_empty() {
+        return Err(CloudflareError::ValidationError("dns_scan_probes must not be empty".to_string()));
+    }
+    for probe in probes {
+        if !SCAN_RECORD_TYPES.contains(&probe.record_type.as_str()) {
+            return Err(CloudflareError::ValidationError(format!(
+                "dns_scan_probes record type '{}' must be one of: {}",
+                probe.record_type,
+                SCAN_RECORD_TYPES.join(", ")
+            )));
+        }
+        let valid_name = probe.name == "@"
+            || probe.name.split('.').all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            });
+        if !valid_name {
+            return Err(CloudflareError::ValidationError(format!(
+                "dns_scan_probes name '{}' must be @ or a subdomain",
+                probe.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Nameservers in a comma-separated list of host names or IP addresses
+pub fn parse_nameservers(list: &str) -> CloudflareResult<Vec<String>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|ns| !ns.is_empty())
+        .map(|ns| match ns.parse::<IpAddr>() {
+            Ok(ip) => Ok(ip.to_string()),
+            Err(_) => normalize_domain(ns).map_err(|_| {
+                CloudflareError::ValidationError(format!("dns_scan_nameservers entry '{}' is not a host name or IP", ns))
+            }),
+        })
+        .collect()
+}
+
+/// Names and types most sites have records for
+pub fn default_probes() -> Vec<DnsProbe> {
+    let mut probes: Vec<DnsProbe> = ["A", "AAAA", "MX", "TXT"].iter().map(|t| DnsProbe::new("@", t)).collect();
+    for name in ["www", "mail", "blog", "shop", "api", "cdn", "ftp", "webmail"] {
+        probes.extend(["CNAME", "A", "AAAA"].iter().map(|t| DnsProbe::new(name, t)));
+    }
+    probes.push(DnsProbe::new("_dmarc", "TXT"));
+    probes
+}
+
+/// Look up every probe for `domain` and suggest the records to create.
+///
+/// A name answering with a CNAME only gets the CNAME, since it can't have
+/// other records alongside. Records are suggested unproxied with automatic
+/// TTL; a failed lookup is logged and skipped.
+pub async fn scan_records(
+    resolver: &dyn DnsResolver,
+    domain: &str,
+    probes: &[DnsProbe],
+) -> CloudflareResult<Vec<CreateDnsRecord>> {
+    let domain = normalize_domain(domain)?;
+
+    let lookups = probes.iter().map(|probe| {
+        let fqdn = probe.fqdn(&domain);
+        async move {
+            match resolver.lookup(&fqdn, &probe.record_type).await {
+                Ok(answers) => (fqdn, probe, answers),
+                Err(e) => {
+                    warn!("Skipping {} {} in DNS scan: {}", fqdn, probe.record_type, e);
+                    (fqdn, probe, Vec::new())
+                }
+            }
+        }
+    });
+    let results = futures::future::join_all(lookups).await;
+
+    // Answers owned by the probed name itself, not records reached through a CNAME
+    let owned: Vec<(&DnsProbe, Vec<DnsAnswer>)> = results
+        .into_iter()
+        .map(|(fqdn, probe, answers)| {
+            let answers = answers.into_iter().filter(|a| same_name(&a.name, &fqdn)).collect();
+            (probe, answers)
+        })
+        .collect();
+    let aliased: HashSet<String> = owned
+        .iter()
+        .flat_map(|(_, answers)| answers)
+        .filter(|a| a.record_type == "CNAME")
+        .map(|a| trim_dot(&a.name).to_lowercase())
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut records = Vec::new();
+    for (probe, answers) in owned {
+        for answer in answers {
+            let name = trim_dot(&answer.name).to_lowercase();
+            let wanted = if aliased.contains(&name) {
+                answer.record_type == "CNAME"
+            } else {
+                answer.record_type == probe.record_type
+            };
+            if !wanted {
+                continue;
+            }
+            let Some(record) = suggested_record(&answer) else {
+                warn!("Skipping unreadable {} answer for {}: {}", answer.record_type, name, answer.data);
+                continue;
+            };
+            if seen.insert((record.record_type.clone(), record.name.clone(), record.content.clone())) {
+                records.push(record);
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+/// Record to create for an answer, or `None` if its data can't be read
+fn suggested_record(answer: &DnsAnswer) -> Option<CreateDnsRecord> {
+    let (content, priority) = match answer.record_type.as_str() {
+        "MX" => {
+            let (priority, host) = answer.data.trim().split_once(char::is_whitespace)?;
+            (trim_dot(host.trim()).to_string(), Some(priority.parse().ok()?))
+        }
+        "CNAME" => (trim_dot(answer.data.trim()).to_string(), None),
+        "TXT" => (unquote_txt(&answer.data), None),
+        _ => (answer.data.trim().to_string(), None),
+    };
+
+    Some(CreateDnsRecord {
+        record_type: answer.record_type.clone(),
+        name: trim_dot(&answer.name).to_lowercase(),
+        content,
+        ttl: None,
+        proxied: None,
+        priority,
+    })
+}
+
+/// Join the quoted character-strings of a TXT record, e.g. long SPF or DKIM values
+fn unquote_txt(data: &str) -> String {
+    let data = data.trim();
+    if !data.starts_with('"') {
+        return data.to_string();
+    }
+
+    let mut text = String::with_capacity(data.len());
+    let mut quoted = false;
+    let mut chars = data.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => text.extend(chars.next()),
+            c if quoted => text.push(c),
+            // Whitespace between character-strings
+            _ => {}
+        }
+    }
+    text
+}
+
+fn normalize_domain(domain: &str) -> CloudflareResult<String> {
+    let domain = trim_dot(domain.trim()).to_lowercase();
+    let valid = domain.contains('.')
+        && domain.len() <= 253
+        && domain.split('.').all(|label| {
+            !label.is_empty() && label.len() <= 63 && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if valid {
+        Ok(domain)
+    } else {
+        Err(CloudflareError::ValidationError(format!("Invalid domain: {}", domain)))
+    }
+}
+
+fn trim_dot(name: &str) -> &str {
+    name.strip_suffix('.').unwrap_or(name)
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    trim_dot(a).eq_ignore_ascii_case(trim_dot(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use wiremock::matchers::{header, method, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Resolver answering from a fixed table
+    #[derive(Default)]
+    struct StaticResolver {
+        answers: HashMap<(String, String), Vec<DnsAnswer>>,
+    }
+
+    impl StaticResolver {
+        fn answer(mut self, query: (&str, &str), answers: &[(&str, &str, &str)]) -> Self {
+            self.answers.insert(
+                (query.0.to_string(), query.1.to_string()),
+                answers
+                    .iter()
+                    .map(|(name, record_type, data)| DnsAnswer {
+                        name: name.to_string(),
+                        record_type: record_type.to_string(),
+                        data: data.to_string(),
+                    })
+                    .collect(),
+            );
+            self
+        }
+    }
+
+    #[async_trait]
+    impl DnsResolver for StaticResolver {
+        async fn lookup(&self, name: &str, record_type: &str) -> CloudflareResult<Vec<DnsAnswer>> {
+            if name == "broken.example.com" {
+                return Err(CloudflareError::NetworkError("SERVFAIL".to_string()));
+            }
+            Ok(self.answers.get(&(name.to_string(), record_type.to_string())).cloned().unwrap_or_default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_suggests_records_from_resolver() {
+        let resolver = StaticResolver::default()
+            .answer(("example.com", "A"), &[("example.com.", "A", "192.0.2.10")])
+            .answer(("example.com", "MX"), &[("example.com.", "MX", "10 mail.example.com.")])
+            .answer(
+                ("example.com", "TXT"),
+                &[("example.com.", "TXT", "\"v=spf1 include:_spf.example.net \" \"~all\"")],
+            )
+            // www is an alias: the A lookup follows it, but only the CNAME belongs to www
+            .answer(
+                ("www.example.com", "A"),
+                &[("www.example.com.", "CNAME", "example.com."), ("example.com.", "A", "192.0.2.10")],
+            )
+            .answer(("www.example.com", "CNAME"), &[("www.example.com.", "CNAME", "example.com.")]);
+        let probes = vec![
+            DnsProbe::new("@", "A"),
+            DnsProbe::new("@", "MX"),
+            DnsProbe::new("@", "TXT"),
+            DnsProbe::new("www", "CNAME"),
+            DnsProbe::new("www", "A"),
+            DnsProbe::new("broken", "A"),
+        ];
+
+        let records = scan_records(&resolver, "Example.com.", &probes).await.unwrap();
+
+        let summary: Vec<(&str, &str, &str, Option<i32>)> = records
+            .iter()
+            .map(|r| (r.record_type.as_str(), r.name.as_str(), r.content.as_str(), r.priority))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("A", "example.com", "192.0.2.10", None),
+                ("MX", "example.com", "mail.example.com", Some(10)),
+                ("TXT", "example.com", "v=spf1 include:_spf.example.net ~all", None),
+                ("CNAME", "www.example.com", "example.com", None),
+            ]
+        );
+        assert!(records.iter().all(|r| r.proxied.is_none() && r.ttl.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_scan_rejects_invalid_domain() {
+        let err = scan_records(&StaticResolver::default(), "https://example.com", &default_probes())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(_)));
+    }
+
+    /// Nameserver answering one query with `records`, each `(type, rdata)`
+    /// owned by the queried name
+    async fn nameserver(records: Vec<(u16, Vec<u8>)>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+            let query = &buf[..len];
+            // Header and question from the query, minus its OPT record
+            let question_end = read_name(query, 12).unwrap().1 + 4;
+            let mut response = query[..question_end].to_vec();
+            response[2] = 0x84;
+            response[6..8].copy_from_slice(&(records.len() as u16).to_be_bytes());
+            response[10..12].copy_from_slice(&[0, 0]);
+            for (rtype, rdata) in &records {
+                // Owner is a pointer to the question name
+                response.extend_from_slice(&[0xc0, 12]);
+                response.extend_from_slice(&rtype.to_be_bytes());
+                response.extend_from_slice(&[0, 1, 0, 0, 1, 44]);
+                response.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+                response.extend_from_slice(rdata);
+            }
+            socket.send_to(&response, peer).await.unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_nameserver_resolver_reads_wire_answers() {
+        let mx = [&[0, 10, 4][..], b"mail", &[0xc0, 12]].concat();
+        let txt = [&[11][..], b"v=spf1 ~all", &[8], br#"say "hi""#].concat();
+        let server = nameserver(vec![(15, mx), (16, txt), (46, vec![1, 2, 3])]).await;
+
+        let answers = NameserverResolver::new(vec![server]).lookup("example.com", "MX").await.unwrap();
+
+        assert_eq!(
+            answers,
+            vec![
+                DnsAnswer {
+                    name: "example.com.".to_string(),
+                    record_type: "MX".to_string(),
+                    data: "10 mail.example.com.".to_string(),
+                },
+                DnsAnswer {
+                    name: "example.com.".to_string(),
+                    record_type: "TXT".to_string(),
+                    data: r#""v=spf1 ~all" "say \"hi\"""#.to_string(),
+                },
+            ]
+        );
+        assert_eq!(unquote_txt(&answers[1].data), r#"v=spf1 ~allsay "hi""#);
+    }
+
+    #[tokio::test]
+    async fn test_nameserver_resolver_finds_domain_nameservers() {
+        let recursive = StaticResolver::default()
+            .answer(
+                ("example.com", "NS"),
+                &[("example.com.", "NS", "ns1.oldhost.net."), ("example.com.", "NS", "ns2.oldhost.net.")],
+            )
+            .answer(("ns1.oldhost.net", "A"), &[("ns1.oldhost.net.", "A", "192.0.2.53")]);
+
+        let resolver = NameserverResolver::for_domain(&recursive, "example.com", &[]).await.unwrap();
+        assert_eq!(resolver.servers, vec!["192.0.2.53:53".parse::<SocketAddr>().unwrap()]);
+
+        // Configured nameservers replace the NS lookup
+        let configured = vec!["198.51.100.1".to_string()];
+        let resolver = NameserverResolver::for_domain(&recursive, "example.com", &configured).await.unwrap();
+        assert_eq!(resolver.servers, vec!["198.51.100.1:53".parse::<SocketAddr>().unwrap()]);
+
+        let err = NameserverResolver::for_domain(&recursive, "example.org", &[]).await.err().unwrap();
+        assert!(matches!(err, CloudflareError::ValidationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_doh_resolver_parses_json_answers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("name", "example.com"))
+            .and(query_param("type", "MX"))
+            .and(header("accept", "application/dns-json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "Status": 0,
+                "Answer": [
+                    { "name": "example.com.", "type": 15, "TTL": 300, "data": "10 mail.example.com." },
+                    { "name": "example.com.", "type": 46, "TTL": 300, "data": "MX 13 2 300 ..." }
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let answers = DohResolver::new(&server.uri()).lookup("example.com", "MX").await.unwrap();
+        assert_eq!(
+            answers,
+            vec![DnsAnswer {
+                name: "example.com.".to_string(),
+                record_type: "MX".to_string(),
+                data: "10 mail.example.com.".to_string(),
+            }]
+        );
+    }
+}
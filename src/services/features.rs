@@ -0,0 +1,103 @@
+//! Runtime feature flags
+//!
+//! Lets operators switch off individual features without deactivating the
+//! plugin. Flags that were never set are enabled.
+
+use crate::error::{CloudflareError, CloudflareResult};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+/// Auto-purge of cache on content changes
+pub const AUTO_PURGE: &str = "auto_purge";
+/// Purging tagged content by cache tag instead of URL
+pub const CACHE_TAG_PURGE: &str = "cache_tag_purge";
+/// Daily cleanup of old analytics events
+pub const ANALYTICS_CLEANUP: &str = "analytics_cleanup";
+/// Copying media library uploads to R2 and serving them from there
+pub const R2_MEDIA_OFFLOAD: &str = "r2_media_offload";
+/// Switching Under Attack mode on and off automatically as threats spike
+pub const UNDER_ATTACK_AUTO: &str = "under_attack_auto";
+
+/// All flags that can be toggled
+pub const KNOWN_FEATURES: &[&str] = &[
+    AUTO_PURGE,
+    CACHE_TAG_PURGE,
+    ANALYTICS_CLEANUP,
+    R2_MEDIA_OFFLOAD,
+    UNDER_ATTACK_AUTO,
+];
+
+/// Feature flags shared by the services that consult them
+#[derive(Debug, Clone, Default)]
+pub struct FeatureFlags {
+    flags: Arc<RwLock<HashMap<String, bool>>>,
+}
+
+impl FeatureFlags {
+    /// Whether a feature is enabled
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        self.flags
+            .read()
+            .map(|flags| flags.get(feature).copied().unwrap_or(true))
+            .unwrap_or(true)
+    }
+
+    /// Enable or disable a feature
+    pub fn set(&self, feature: &str, enabled: bool) -> CloudflareResult<()> {
+        if !KNOWN_FEATURES.contains(&feature) {
+            return Err(CloudflareError::ValidationError(format!(
+                "Unknown feature flag: {}",
+                feature
+            )));
+        }
+
+        self.flags
+            .write()
+            .map_err(|_| CloudflareError::Internal("Feature flags lock poisoned".to_string()))?
+            .insert(feature.to_string(), enabled);
+        Ok(())
+    }
+
+    /// Replace all flags, e.g. with the ones stored in settings
+    pub fn replace(&self, flags: HashMap<String, bool>) {
+        if let Ok(mut current) = self.flags.write() {
+            *current = flags;
+        }
+    }
+
+    /// State of every known flag
+    pub fn snapshot(&self) -> BTreeMap<String, bool> {
+        KNOWN_FEATURES
+            .iter()
+            .map(|feature| (feature.to_string(), self.is_enabled(feature)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::analytics::AnalyticsService;
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn test_disabled_flag_skips_analytics_cleanup() {
+        // Nothing listens on this port, so touching the database would fail
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost:1/rustpress_test")
+            .unwrap();
+        let features = FeatureFlags::default();
+        let analytics = AnalyticsService::new_unconfigured(pool).with_features(features.clone());
+
+        features.set(ANALYTICS_CLEANUP, false).unwrap();
+        assert_eq!(analytics.cleanup_old_events().await.unwrap(), 0);
+
+        assert!(features.set("not_a_feature", false).is_err());
+        assert!(features.is_enabled(AUTO_PURGE));
+        assert_eq!(features.snapshot().get(ANALYTICS_CLEANUP), Some(&false));
+
+        features.set(R2_MEDIA_OFFLOAD, false).unwrap();
+        features.set(UNDER_ATTACK_AUTO, false).unwrap();
+        assert_eq!(features.snapshot().get(UNDER_ATTACK_AUTO), Some(&false));
+    }
+}
@@ -3,9 +3,17 @@
 //! Business logic and orchestration for Cloudflare operations
 
 pub mod cache;
+pub mod features;
+pub mod notifications;
 pub mod dns;
+pub mod dns_scan;
+pub mod ssl;
 pub mod security;
+pub mod security_events;
+pub mod rules_export;
+pub mod under_attack;
 pub mod workers;
+pub mod worker_tail;
 pub mod r2;
 pub mod d1;
 pub mod stream;
@@ -13,21 +21,35 @@ pub mod analytics;
 pub mod settings;
 pub mod oauth;
 pub mod sso_handoff;
+pub mod zone;
+pub mod performance;
+pub mod rules;
 
 use crate::client::CloudflareClient;
+use crate::health::PluginHealth;
 use sqlx::PgPool;
 use std::sync::Arc;
 
 pub use settings::{SettingsService, CloudflareCredentials, PluginSettings};
-pub use oauth::{OAuthService, OAuthConfig, TokenResources};
+pub use features::FeatureFlags;
+pub use notifications::{NotificationEvent, NotificationService};
+pub use oauth::{OAuthService, OAuthConfig, TokenDetails, TokenResources};
 pub use d1::D1Service;
-pub use stream::{StreamService, EmbedOptions};
+pub use stream::{StreamService, EmbedOptions, SignedUrlRestrictions, MAX_SIGNED_URL_TTL};
 pub use sso_handoff::SsoHandoffStore;
 
+/// Services handed to long-lived tasks and hooks, which read it each time
+/// they run since reconnecting replaces the services
+pub type SharedServices = Arc<tokio::sync::RwLock<Option<Arc<CloudflareServices>>>>;
+
 /// Main services container
 pub struct CloudflareServices {
+    pub zone: zone::ZoneService,
+    pub performance: performance::PerformanceService,
+    pub rules: rules::RulesService,
     pub cache: cache::CacheService,
     pub dns: dns::DnsService,
+    pub ssl: ssl::SslService,
     pub security: security::SecurityService,
     pub workers: workers::WorkersService,
     pub r2: r2::R2Service,
@@ -37,23 +59,47 @@ pub struct CloudflareServices {
     pub settings: settings::SettingsService,
     pub oauth: oauth::OAuthService,
     pub sso_handoff: SsoHandoffStore,
+    pub notifications: Arc<NotificationService>,
+    /// Runtime feature flags consulted by services and hooks
+    pub features: FeatureFlags,
+    /// Live API client, shared with the services above
+    pub client: Option<Arc<CloudflareClient>>,
+    /// Connection health, kept up to date by the plugin's health check
+    pub health: PluginHealth,
 }
 
 impl CloudflareServices {
     /// Create a new services container
     pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
+        let features = FeatureFlags::default();
+        let notifications = Arc::new(NotificationService::new(db.clone()));
+        let zone = zone::ZoneService::new(Arc::clone(&client), db.clone());
         Self {
-            cache: cache::CacheService::new(Arc::clone(&client), db.clone()),
+            cache: cache::CacheService::new(Arc::clone(&client), db.clone())
+                .with_plan_lookup(zone.plan_lookup())
+                .with_notifications(Arc::clone(&notifications)),
+            zone,
+            performance: performance::PerformanceService::new(Arc::clone(&client), db.clone()),
+            rules: rules::RulesService::new(Arc::clone(&client), db.clone()),
             dns: dns::DnsService::new(Arc::clone(&client), db.clone()),
-            security: security::SecurityService::new(Arc::clone(&client), db.clone()),
+            ssl: ssl::SslService::new(Arc::clone(&client), db.clone()),
+            security: security::SecurityService::new(Arc::clone(&client), db.clone())
+                .with_notifications(Arc::clone(&notifications)),
             workers: workers::WorkersService::new(Arc::clone(&client), db.clone()),
-            r2: r2::R2Service::new(Arc::clone(&client), db.clone()),
+            r2: r2::R2Service::new(Arc::clone(&client), db.clone()).with_features(features.clone()),
             d1: d1::D1Service::new(Arc::clone(&client), db.clone()),
             stream: stream::StreamService::new(Arc::clone(&client), db.clone()),
-            analytics: analytics::AnalyticsService::new(Arc::clone(&client), db.clone()),
-            settings: settings::SettingsService::new(db.clone()),
-            oauth: oauth::OAuthService::new(),
+            analytics: analytics::AnalyticsService::new(Arc::clone(&client), db.clone())
+                .with_features(features.clone()),
+            settings: settings::SettingsService::new(db.clone()).with_client(Arc::clone(&client)),
+            oauth: oauth::OAuthService::new()
+                .with_base_url(client.base_url())
+                .with_user_agent(client.user_agent()),
             sso_handoff: SsoHandoffStore::new(),
+            notifications,
+            features,
+            client: Some(client),
+            health: PluginHealth::default(),
         }
     }
 
@@ -61,18 +107,99 @@ impl CloudflareServices {
     pub fn new_unconfigured(db: PgPool) -> Self {
         // Create a dummy client for services that need it
         // These services will fail gracefully if called without proper config
+        let features = FeatureFlags::default();
+        let notifications = Arc::new(NotificationService::new(db.clone()));
         Self {
-            cache: cache::CacheService::new_unconfigured(db.clone()),
+            zone: zone::ZoneService::new_unconfigured(db.clone()),
+            performance: performance::PerformanceService::new_unconfigured(db.clone()),
+            rules: rules::RulesService::new_unconfigured(db.clone()),
+            cache: cache::CacheService::new_unconfigured(db.clone())
+                .with_notifications(Arc::clone(&notifications)),
             dns: dns::DnsService::new_unconfigured(db.clone()),
-            security: security::SecurityService::new_unconfigured(db.clone()),
+            ssl: ssl::SslService::new_unconfigured(db.clone()),
+            security: security::SecurityService::new_unconfigured(db.clone())
+                .with_notifications(Arc::clone(&notifications)),
             workers: workers::WorkersService::new_unconfigured(db.clone()),
-            r2: r2::R2Service::new_unconfigured(db.clone()),
+            r2: r2::R2Service::new_unconfigured(db.clone()).with_features(features.clone()),
             d1: d1::D1Service::new_unconfigured(db.clone()),
             stream: stream::StreamService::new_unconfigured(db.clone()),
-            analytics: analytics::AnalyticsService::new_unconfigured(db.clone()),
+            analytics: analytics::AnalyticsService::new_unconfigured(db.clone())
+                .with_features(features.clone()),
             settings: settings::SettingsService::new(db.clone()),
             oauth: oauth::OAuthService::new(),
             sso_handoff: SsoHandoffStore::new(),
+            notifications,
+            features,
+            client: None,
+            health: PluginHealth::default(),
         }
     }
+
+    /// Load the stored feature flags into the shared flags
+    pub async fn load_feature_flags(&self) -> crate::error::CloudflareResult<()> {
+        let flags = self.settings.get_feature_flags().await?;
+        self.features.replace(flags);
+        Ok(())
+    }
+
+    /// Apply the stored settings services keep in memory
+    pub async fn load_extended_settings(&self) -> crate::error::CloudflareResult<()> {
+        let settings = self.settings.get_extended_settings().await?;
+        self.apply_extended_settings(&settings);
+        Ok(())
+    }
+
+    /// Hand the analytics cache TTL and DNS scan settings to their services
+    pub fn apply_extended_settings(&self, settings: &settings::ExtendedPluginSettings) {
+        self.analytics
+            .set_cache_ttl(std::time::Duration::from_secs(settings.analytics_cache_ttl_secs));
+
+        let nameservers = settings
+            .dns_scan_nameservers
+            .as_deref()
+            .map(dns_scan::parse_nameservers)
+            .transpose()
+            .unwrap_or_else(|e| {
+                tracing::warn!("Ignoring the stored DNS scan nameservers: {}", e);
+                None
+            });
+        self.dns
+            .set_scan_settings(settings.dns_scan_probes.clone(), nameservers.unwrap_or_default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{CloudflareError, CloudflareResult};
+    use crate::test_support::lazy_pool;
+    use axum::http::StatusCode;
+
+    fn assert_not_configured<T>(service: &str, result: CloudflareResult<T>) {
+        let err = result.err().unwrap_or_else(|| panic!("{} succeeded without a client", service));
+        assert!(matches!(err, CloudflareError::NotConfigured), "{}: {:?}", service, err);
+        assert_eq!(err.error_code(), "NOT_CONFIGURED", "{}", service);
+        assert_eq!(err.status_code(), StatusCode::CONFLICT, "{}", service);
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured_services_report_not_configured() {
+        let services = CloudflareServices::new_unconfigured(lazy_pool());
+
+        assert_not_configured("zone", services.zone.get_zone_info().await);
+        assert_not_configured("performance", services.performance.get_settings().await);
+        assert_not_configured("rules", services.rules.list_page_rules().await);
+        assert_not_configured("cache", services.cache.purge_all(None).await);
+        assert_not_configured("dns", services.dns.list(None).await);
+        assert_not_configured("ssl", services.ssl.list_custom_hostnames().await);
+        assert_not_configured("security", services.security.get_security_level().await);
+        assert_not_configured("workers", services.workers.list_workers().await);
+        assert_not_configured("d1", services.d1.list_databases().await);
+        assert_not_configured("stream", services.stream.list_videos().await);
+        assert_not_configured(
+            "analytics",
+            services.analytics.query_graphql::<serde_json::Value>("{}", serde_json::json!({})).await,
+        );
+        assert_not_configured("settings", services.settings.get_zone_settings().await);
+    }
 }
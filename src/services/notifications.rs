@@ -0,0 +1,246 @@
+//! Webhook notifications for security events and purges
+
+use crate::error::{CloudflareError, CloudflareResult};
+use crate::services::settings::SettingsService;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// How long a webhook may take before it's abandoned
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Events that can be sent to the notification webhook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotificationEvent {
+    /// Under Attack mode was switched on or off
+    UnderAttackToggled { enabled: bool },
+    /// The auto-trigger switched Under Attack mode on or off after seeing
+    /// `threats` threats in the last `window_minutes`
+    UnderAttackAutoToggled { enabled: bool, threats: u64, window_minutes: u32 },
+    /// The entire zone cache was purged
+    CachePurgedAll { zone_id: String },
+    /// The purge quota ran out and auto-purge was paused until `paused_until`
+    AutoPurgePaused { paused_until: DateTime<Utc>, reason: String },
+}
+
+impl NotificationEvent {
+    /// Short machine-readable event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnderAttackToggled { .. } => "under_attack_toggled",
+            Self::UnderAttackAutoToggled { .. } => "under_attack_auto_toggled",
+            Self::CachePurgedAll { .. } => "cache_purged_all",
+            Self::AutoPurgePaused { .. } => "auto_purge_paused",
+        }
+    }
+
+    /// Human-readable message
+    pub fn message(&self) -> String {
+        match self {
+            Self::UnderAttackToggled { enabled: true } => {
+                ":rotating_light: Under Attack mode has been *enabled*".to_string()
+            }
+            Self::UnderAttackToggled { enabled: false } => {
+                ":white_check_mark: Under Attack mode has been *disabled*".to_string()
+            }
+            Self::UnderAttackAutoToggled { enabled: true, threats, window_minutes } => format!(
+                ":rotating_light: Under Attack mode was *enabled* automatically after {} threats in {} minutes",
+                threats, window_minutes
+            ),
+            Self::UnderAttackAutoToggled { enabled: false, threats, window_minutes } => format!(
+                ":white_check_mark: Under Attack mode was *disabled* automatically; threats fell to {} in {} minutes",
+                threats, window_minutes
+            ),
+            Self::CachePurgedAll { zone_id } => {
+                format!(":broom: The entire Cloudflare cache was purged for zone `{}`", zone_id)
+            }
+            Self::AutoPurgePaused { paused_until, reason } => format!(
+                ":pause_button: Auto-purge is paused until {} because the purge quota is exhausted: {}",
+                paused_until.format("%Y-%m-%d %H:%M UTC"),
+                reason
+            ),
+        }
+    }
+}
+
+/// Body for a Slack or Discord incoming webhook
+pub fn chat_webhook_body(webhook_url: &str, text: &str) -> serde_json::Value {
+    if is_discord_webhook(webhook_url) {
+        serde_json::json!({ "content": text })
+    } else {
+        serde_json::json!({ "text": format!("[RustCloudflare] {}", text) })
+    }
+}
+
+/// Discord webhooks live under `/api/webhooks/` and take `content` instead of `text`
+fn is_discord_webhook(url: &str) -> bool {
+    url.contains("/api/webhooks/")
+}
+
+/// Whether a URL is a Slack or Discord incoming webhook
+fn is_chat_webhook(url: &str) -> bool {
+    url.contains("hooks.slack.com") || is_discord_webhook(url)
+}
+
+/// Posts notifications to the configured webhook
+pub struct NotificationService {
+    http: reqwest::Client,
+    db: PgPool,
+}
+
+impl NotificationService {
+    pub fn new(db: PgPool) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { http, db }
+    }
+
+    /// Post a formatted message to a Slack or Discord webhook
+    pub async fn notify_slack(&self, webhook_url: &str, event: &NotificationEvent) -> CloudflareResult<()> {
+        let body = chat_webhook_body(webhook_url, &event.message());
+        self.notify_generic(webhook_url, &body).await
+    }
+
+    /// Post arbitrary JSON to a webhook
+    pub async fn notify_generic(&self, url: &str, body: &serde_json::Value) -> CloudflareResult<()> {
+        let response = self
+            .http
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| CloudflareError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(CloudflareError::ServiceUnavailable(format!(
+                "Webhook returned {}",
+                response.status()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Send an event to `url`, logging instead of failing if delivery fails
+    pub async fn send(&self, url: &str, event: &NotificationEvent) {
+        let result = if is_chat_webhook(url) {
+            self.notify_slack(url, event).await
+        } else {
+            let body = serde_json::json!({
+                "event": event.name(),
+                "message": event.message(),
+                "timestamp": Utc::now(),
+            });
+            self.notify_generic(url, &body).await
+        };
+
+        if let Err(e) = result {
+            warn!("Failed to send {} notification: {}", event.name(), e);
+        }
+    }
+
+    /// Send an event to the configured webhook, if webhook alerts are enabled
+    pub async fn notify(&self, event: NotificationEvent) {
+        let settings = match SettingsService::new(self.db.clone()).get_extended_settings().await {
+            Ok(settings) => settings,
+            Err(e) => {
+                warn!("Failed to load notification settings: {}", e);
+                return;
+            }
+        };
+
+        match (settings.security_webhook_alerts, settings.security_slack_webhook) {
+            (true, Some(url)) if !url.is_empty() => self.send(&url, &event).await,
+            _ => debug!("Webhook alerts disabled, not sending {}", event.name()),
+        }
+    }
+
+    /// Send an event in the background so a slow webhook doesn't hold up the
+    /// action that triggered it
+    pub fn notify_in_background(self: &Arc<Self>, event: NotificationEvent) {
+        let notifications = Arc::clone(self);
+        tokio::spawn(async move { notifications.notify(event).await });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lazy_pool;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn service() -> NotificationService {
+        NotificationService::new(lazy_pool())
+    }
+
+    #[test]
+    fn test_webhook_message_formatting() {
+        let event = NotificationEvent::UnderAttackToggled { enabled: true };
+        assert_eq!(
+            chat_webhook_body("https://hooks.slack.com/services/T0/B0/x", &event.message()),
+            serde_json::json!({ "text": "[RustCloudflare] :rotating_light: Under Attack mode has been *enabled*" })
+        );
+
+        let event = NotificationEvent::CachePurgedAll { zone_id: "zone-1".to_string() };
+        assert_eq!(
+            chat_webhook_body("https://discord.com/api/webhooks/1/abc", &event.message()),
+            serde_json::json!({ "content": ":broom: The entire Cloudflare cache was purged for zone `zone-1`" })
+        );
+    }
+
+    #[test]
+    fn test_auto_purge_paused_message_names_deadline_and_reason() {
+        let event = NotificationEvent::AutoPurgePaused {
+            paused_until: "2026-10-16T14:30:00Z".parse().unwrap(),
+            reason: "Rate limited".to_string(),
+        };
+        assert_eq!(event.name(), "auto_purge_paused");
+        assert_eq!(
+            event.message(),
+            ":pause_button: Auto-purge is paused until 2026-10-16 14:30 UTC because the purge quota is exhausted: Rate limited"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notify_slack_posts_formatted_message() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/webhooks/1/abc"))
+            .and(body_json(serde_json::json!({
+                "content": ":white_check_mark: Under Attack mode has been *disabled*"
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/api/webhooks/1/abc", server.uri());
+        service()
+            .notify_slack(&url, &NotificationEvent::UnderAttackToggled { enabled: false })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_webhook_failure_does_not_propagate() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let service = service();
+        let event = NotificationEvent::CachePurgedAll { zone_id: "zone-1".to_string() };
+        let url = format!("{}/hook", server.uri());
+
+        assert!(service.notify_generic(&url, &serde_json::json!({})).await.is_err());
+        // `send` only logs the failure
+        service.send(&url, &event).await;
+    }
+}
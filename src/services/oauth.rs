@@ -1,6 +1,10 @@
 //! Cloudflare OAuth service for SSO authentication
 
+use crate::client::identification_headers;
+use crate::config::{default_user_agent, DEFAULT_API_BASE_URL};
 use crate::error::{CloudflareError, CloudflareResult};
+use crate::models::{ApiToken, TokenPermission, TokenVerification};
+use chrono::{DateTime, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -88,35 +92,40 @@ pub struct ZonePlan {
 pub struct OAuthService {
     client: Client,
     config: Option<OAuthConfig>,
+    base_url: String,
 }
 
 impl OAuthService {
     /// Create a new OAuth service
     pub fn new() -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
-
         Self {
-            client,
+            client: http_client(&default_user_agent()),
             config: None,
+            base_url: DEFAULT_API_BASE_URL.to_string(),
         }
     }
 
     /// Create with OAuth config
     pub fn with_config(config: OAuthConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
-
         Self {
-            client,
+            client: http_client(&default_user_agent()),
             config: Some(config),
+            base_url: DEFAULT_API_BASE_URL.to_string(),
         }
     }
 
+    /// Send API requests to a different base URL
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Identify requests with a different `User-Agent`
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.client = http_client(user_agent);
+        self
+    }
+
     /// Generate OAuth authorization URL
     pub fn get_auth_url(&self, state: &str) -> CloudflareResult<String> {
         let config = self.config.as_ref().ok_or_else(|| {
@@ -140,7 +149,7 @@ impl OAuthService {
         })?;
 
         let response = self.client
-            .post("https://api.cloudflare.com/client/v4/oauth/token")
+            .post(format!("{}/oauth/token", self.base_url))
             .form(&[
                 ("grant_type", "authorization_code"),
                 ("code", code),
@@ -170,7 +179,7 @@ impl OAuthService {
     /// Get user info using access token
     pub async fn get_user_info(&self, access_token: &str) -> CloudflareResult<CloudflareUserInfo> {
         let response = self.client
-            .get("https://api.cloudflare.com/client/v4/user")
+            .get(format!("{}/user", self.base_url))
             .bearer_auth(access_token)
             .send()
             .await
@@ -196,7 +205,7 @@ impl OAuthService {
     /// List user's accounts
     pub async fn list_accounts(&self, access_token: &str) -> CloudflareResult<Vec<CloudflareAccount>> {
         let response = self.client
-            .get("https://api.cloudflare.com/client/v4/accounts")
+            .get(format!("{}/accounts", self.base_url))
             .bearer_auth(access_token)
             .send()
             .await
@@ -225,7 +234,7 @@ impl OAuthService {
         access_token: &str,
         account_id: Option<&str>
     ) -> CloudflareResult<Vec<CloudflareZoneInfo>> {
-        let mut url = "https://api.cloudflare.com/client/v4/zones".to_string();
+        let mut url = format!("{}/zones", self.base_url);
 
         if let Some(account) = account_id {
             url = format!("{}?account.id={}", url, account);
@@ -258,7 +267,7 @@ impl OAuthService {
     /// Verify an API token
     pub async fn verify_token(&self, api_token: &str) -> CloudflareResult<bool> {
         let response = self.client
-            .get("https://api.cloudflare.com/client/v4/user/tokens/verify")
+            .get(format!("{}/user/tokens/verify", self.base_url))
             .bearer_auth(api_token)
             .send()
             .await
@@ -279,6 +288,57 @@ impl OAuthService {
         Ok(verify.success)
     }
 
+    /// Look up an API token's status and the permissions its policies grant.
+    ///
+    /// Reading the policies needs the "API Tokens Read" permission on the
+    /// token itself; without it this returns a `PermissionDenied` error.
+    pub async fn get_token_details(&self, api_token: &str) -> CloudflareResult<TokenDetails> {
+        #[derive(Deserialize)]
+        struct Envelope<T> {
+            result: T,
+        }
+
+        let response = self.client
+            .get(format!("{}/user/tokens/verify", self.base_url))
+            .bearer_auth(api_token)
+            .send()
+            .await
+            .map_err(|e| CloudflareError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(CloudflareError::AuthenticationError(
+                "Failed to verify API token".to_string()
+            ));
+        }
+
+        let verification: Envelope<TokenVerification> = response.json().await
+            .map_err(|e| CloudflareError::Internal(format!("Failed to parse verify response: {}", e)))?;
+        let verification = verification.result;
+
+        let response = self.client
+            .get(format!("{}/user/tokens/{}", self.base_url, verification.id))
+            .bearer_auth(api_token)
+            .send()
+            .await
+            .map_err(|e| CloudflareError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(CloudflareError::PermissionDenied(
+                "API token is not allowed to read its own permissions (needs API Tokens Read)".to_string()
+            ));
+        }
+
+        let token: Envelope<ApiToken> = response.json().await
+            .map_err(|e| CloudflareError::Internal(format!("Failed to parse token details: {}", e)))?;
+
+        Ok(TokenDetails {
+            id: verification.id,
+            status: verification.status,
+            expires_on: verification.expires_on,
+            permissions: token.result.permissions(),
+        })
+    }
+
     /// Get accounts and zones using API token (for manual token entry)
     pub async fn get_token_resources(&self, api_token: &str) -> CloudflareResult<TokenResources> {
         // Get user info
@@ -306,8 +366,104 @@ pub struct TokenResources {
     pub zones: Vec<CloudflareZoneInfo>,
 }
 
+/// An API token's status and what it is allowed to do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenDetails {
+    pub id: String,
+    pub status: String,
+    pub expires_on: Option<DateTime<Utc>>,
+    pub permissions: Vec<TokenPermission>,
+}
+
+/// HTTP client for OAuth and token requests, identifying the plugin
+fn http_client(user_agent: &str) -> Client {
+    Client::builder()
+        .default_headers(identification_headers(user_agent))
+        .timeout(Duration::from_secs(30))
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
 impl Default for OAuthService {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PermissionScope;
+    use crate::test_support::success;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_get_token_details_resolves_permissions() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/verify"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(success(serde_json::json!({ "id": "tok", "status": "active" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/tok"))
+            .respond_with(success(serde_json::json!({
+                "id": "tok",
+                "name": "RustPress",
+                "status": "active",
+                "policies": [{
+                    "effect": "allow",
+                    "permission_groups": [
+                        { "id": "e17beae8b8cb423a99b1730f21238bed", "name": "Cache Purge" },
+                        { "id": "82e64a83756745bbbb1c9c2701bf816b", "name": "DNS Read" }
+                    ]
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let details = OAuthService::new().with_base_url(&server.uri()).get_token_details("secret").await.unwrap();
+        assert_eq!(details.status, "active");
+        let permissions: Vec<(&str, PermissionScope)> =
+            details.permissions.iter().map(|p| (p.resource.as_str(), p.scope)).collect();
+        assert_eq!(permissions, vec![("Cache", PermissionScope::Purge), ("DNS", PermissionScope::Read)]);
+    }
+
+    #[tokio::test]
+    async fn test_get_token_details_without_tokens_read() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/verify"))
+            .respond_with(success(serde_json::json!({ "id": "tok", "status": "active" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/tok"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let err = OAuthService::new().with_base_url(&server.uri()).get_token_details("secret").await.unwrap_err();
+        assert!(matches!(err, CloudflareError::PermissionDenied(_)));
+    }
+
+    #[tokio::test]
+    async fn test_requests_carry_user_agent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/verify"))
+            .and(header("user-agent", "AcmeCDN/2.0"))
+            .and(header("x-plugin-version", crate::VERSION))
+            .respond_with(success(serde_json::json!({ "id": "tok", "status": "active" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let oauth = OAuthService::new().with_base_url(&server.uri()).with_user_agent("AcmeCDN/2.0");
+        assert!(oauth.verify_token("secret").await.unwrap());
+    }
+}
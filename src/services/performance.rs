@@ -0,0 +1,488 @@
+//! Performance service
+//!
+//! Protocol and delivery toggles (HTTP/2, HTTP/3, 0-RTT, WebSockets, Early
+//! Hints, Brotli, Rocket Loader) and image optimization (Polish, WebP, Mirage,
+//! Image Resizing)
+
+use crate::client::CloudflareClient;
+use crate::config::PolishMode;
+use crate::error::{CloudflareError, CloudflareResult};
+use crate::models::ZoneSetting;
+use crate::services::settings::{SettingsService, ZoneSettingResult, ZoneSettingUpdate};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::info;
+
+/// An on/off performance setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PerformanceToggle {
+    Http2,
+    Http3,
+    ZeroRtt,
+    Websockets,
+    EarlyHints,
+    BrotliCompression,
+    RocketLoader,
+}
+
+impl PerformanceToggle {
+    pub const ALL: [Self; 7] = [
+        Self::Http2,
+        Self::Http3,
+        Self::ZeroRtt,
+        Self::Websockets,
+        Self::EarlyHints,
+        Self::BrotliCompression,
+        Self::RocketLoader,
+    ];
+
+    /// `CloudflareConfig` field the toggle comes from
+    pub fn field(self) -> &'static str {
+        match self {
+            Self::Http2 => "http2",
+            Self::Http3 => "http3",
+            Self::ZeroRtt => "zero_rtt",
+            Self::Websockets => "websockets",
+            Self::EarlyHints => "early_hints",
+            Self::BrotliCompression => "brotli_compression",
+            Self::RocketLoader => "rocket_loader",
+        }
+    }
+
+    /// Cloudflare zone setting ID
+    pub fn setting_id(self) -> &'static str {
+        match self {
+            Self::Http2 => "http2",
+            Self::Http3 => "http3",
+            Self::ZeroRtt => "0rtt",
+            Self::Websockets => "websockets",
+            Self::EarlyHints => "early_hints",
+            Self::BrotliCompression => "brotli",
+            Self::RocketLoader => "rocket_loader",
+        }
+    }
+}
+
+/// Performance toggles; `None` for settings the zone's plan doesn't have.
+///
+/// Also the body of a partial update, where `None` leaves a setting alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PerformanceSettings {
+    pub http2: Option<bool>,
+    pub http3: Option<bool>,
+    pub zero_rtt: Option<bool>,
+    pub websockets: Option<bool>,
+    pub early_hints: Option<bool>,
+    pub brotli_compression: Option<bool>,
+    pub rocket_loader: Option<bool>,
+}
+
+impl PerformanceSettings {
+    /// Read the toggles out of the zone's settings
+    pub fn from_zone_settings(settings: &[ZoneSetting]) -> Self {
+        let mut performance = Self::default();
+        for toggle in PerformanceToggle::ALL {
+            let value = settings
+                .iter()
+                .find(|s| s.id == toggle.setting_id())
+                .and_then(|s| parse_on_off(&s.value));
+            *performance.toggle_mut(toggle) = value;
+        }
+        performance
+    }
+
+    pub fn get(&self, toggle: PerformanceToggle) -> Option<bool> {
+        match toggle {
+            PerformanceToggle::Http2 => self.http2,
+            PerformanceToggle::Http3 => self.http3,
+            PerformanceToggle::ZeroRtt => self.zero_rtt,
+            PerformanceToggle::Websockets => self.websockets,
+            PerformanceToggle::EarlyHints => self.early_hints,
+            PerformanceToggle::BrotliCompression => self.brotli_compression,
+            PerformanceToggle::RocketLoader => self.rocket_loader,
+        }
+    }
+
+    fn toggle_mut(&mut self, toggle: PerformanceToggle) -> &mut Option<bool> {
+        match toggle {
+            PerformanceToggle::Http2 => &mut self.http2,
+            PerformanceToggle::Http3 => &mut self.http3,
+            PerformanceToggle::ZeroRtt => &mut self.zero_rtt,
+            PerformanceToggle::Websockets => &mut self.websockets,
+            PerformanceToggle::EarlyHints => &mut self.early_hints,
+            PerformanceToggle::BrotliCompression => &mut self.brotli_compression,
+            PerformanceToggle::RocketLoader => &mut self.rocket_loader,
+        }
+    }
+
+    /// Zone setting updates for the toggles that are set
+    pub fn updates(&self) -> Vec<ZoneSettingUpdate> {
+        PerformanceToggle::ALL
+            .into_iter()
+            .filter_map(|toggle| {
+                self.get(toggle).map(|enabled| ZoneSettingUpdate {
+                    field: toggle.field(),
+                    setting_id: toggle.setting_id(),
+                    value: on_off(enabled),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Image optimization settings; `None` for settings the zone's plan doesn't have.
+///
+/// Also the body of a partial update, where `None` leaves a setting alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImageSettings {
+    pub polish: Option<PolishMode>,
+    pub webp: Option<bool>,
+    pub mirage: Option<bool>,
+    pub image_resizing: Option<bool>,
+}
+
+impl ImageSettings {
+    /// Read the image settings out of the zone's settings
+    pub fn from_zone_settings(settings: &[ZoneSetting]) -> Self {
+        let value = |id: &str| settings.iter().find(|s| s.id == id).map(|s| &s.value);
+        Self {
+            polish: value("polish").and_then(|v| serde_json::from_value(v.clone()).ok()),
+            webp: value("webp").and_then(parse_on_off),
+            mirage: value("mirage").and_then(parse_on_off),
+            image_resizing: value("image_resizing").and_then(parse_on_off),
+        }
+    }
+}
+
+/// WebP conversion is part of Polish, so it needs Polish to be on
+pub fn check_webp_allowed(polish: PolishMode) -> CloudflareResult<()> {
+    if polish == PolishMode::Off {
+        return Err(CloudflareError::ValidationError(
+            "WebP only applies to images Polish optimizes; set polish to lossless or lossy first".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Cloudflare's value for an on/off setting
+pub fn on_off(enabled: bool) -> serde_json::Value {
+    serde_json::json!(if enabled { "on" } else { "off" })
+}
+
+/// Read an on/off setting value
+pub fn parse_on_off(value: &serde_json::Value) -> Option<bool> {
+    match value {
+        serde_json::Value::String(s) if s == "on" => Some(true),
+        serde_json::Value::String(s) if s == "off" => Some(false),
+        serde_json::Value::Bool(b) => Some(*b),
+        _ => None,
+    }
+}
+
+/// Performance service
+pub struct PerformanceService {
+    client: Option<Arc<CloudflareClient>>,
+    db: PgPool,
+}
+
+impl PerformanceService {
+    /// Create a new performance service
+    pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
+        Self {
+            client: Some(client),
+            db,
+        }
+    }
+
+    /// Create a new performance service without a client
+    pub fn new_unconfigured(db: PgPool) -> Self {
+        Self { client: None, db }
+    }
+
+    /// Get the client or return an error if not configured
+    fn get_client(&self) -> CloudflareResult<&Arc<CloudflareClient>> {
+        self.client.as_ref().ok_or(CloudflareError::NotConfigured)
+    }
+
+    /// Current performance toggles
+    pub async fn get_settings(&self) -> CloudflareResult<PerformanceSettings> {
+        let settings = self.get_client()?.get_zone_settings().await?;
+        Ok(PerformanceSettings::from_zone_settings(&settings))
+    }
+
+    /// Current state of one toggle, `None` if the zone's plan doesn't have it
+    pub async fn get(&self, toggle: PerformanceToggle) -> CloudflareResult<Option<bool>> {
+        Ok(self.get_settings().await?.get(toggle))
+    }
+
+    /// Turn a single toggle on or off
+    pub async fn set(&self, toggle: PerformanceToggle, enabled: bool) -> CloudflareResult<bool> {
+        self.set_on_off(toggle.setting_id(), enabled).await
+    }
+
+    async fn set_on_off(&self, setting_id: &str, enabled: bool) -> CloudflareResult<bool> {
+        let setting = self
+            .get_client()?
+            .update_zone_setting(setting_id, on_off(enabled))
+            .await?;
+        info!("{} {}", setting_id, if enabled { "enabled" } else { "disabled" });
+        Ok(parse_on_off(&setting.value).unwrap_or(enabled))
+    }
+
+    /// Apply the toggles set in `update`, collecting a result for each.
+    ///
+    /// Settings the zone's plan doesn't have are skipped rather than failing
+    /// the batch.
+    pub async fn update_settings(&self, update: &PerformanceSettings) -> CloudflareResult<Vec<ZoneSettingResult>> {
+        let updates = update.updates();
+        if updates.is_empty() {
+            return Err(CloudflareError::ValidationError(
+                "Specify at least one performance setting".to_string(),
+            ));
+        }
+
+        SettingsService::new(self.db.clone())
+            .with_client(Arc::clone(self.get_client()?))
+            .apply_zone_setting_updates(updates)
+            .await
+    }
+
+    /// Current image optimization settings
+    pub async fn get_image_settings(&self) -> CloudflareResult<ImageSettings> {
+        let settings = self.get_client()?.get_zone_settings().await?;
+        Ok(ImageSettings::from_zone_settings(&settings))
+    }
+
+    /// Set how Polish compresses images
+    pub async fn set_polish(&self, mode: PolishMode) -> CloudflareResult<PolishMode> {
+        let setting = self
+            .get_client()?
+            .update_zone_setting("polish", serde_json::to_value(mode)?)
+            .await?;
+        info!("Polish set to {:?}", mode);
+        Ok(serde_json::from_value(setting.value).unwrap_or(mode))
+    }
+
+    /// Turn WebP conversion on or off; turning it on needs Polish to be on
+    pub async fn set_webp(&self, enabled: bool) -> CloudflareResult<bool> {
+        if enabled {
+            check_webp_allowed(self.current_polish().await?)?;
+        }
+        self.set_on_off("webp", enabled).await
+    }
+
+    /// Turn Mirage on or off
+    pub async fn set_mirage(&self, enabled: bool) -> CloudflareResult<bool> {
+        self.set_on_off("mirage", enabled).await
+    }
+
+    /// Turn Image Resizing on or off
+    pub async fn set_image_resizing(&self, enabled: bool) -> CloudflareResult<bool> {
+        self.set_on_off("image_resizing", enabled).await
+    }
+
+    async fn current_polish(&self) -> CloudflareResult<PolishMode> {
+        let setting = self.get_client()?.get_zone_setting("polish").await?;
+        Ok(serde_json::from_value(setting.value).unwrap_or(PolishMode::Off))
+    }
+
+    /// Apply the image settings set in `update`.
+    ///
+    /// Polish goes first so WebP can be turned on in the same request; WebP is
+    /// checked against the Polish mode it will run with before anything is sent.
+    pub async fn update_image_settings(&self, update: &ImageSettings) -> CloudflareResult<ImageSettings> {
+        if update == &ImageSettings::default() {
+            return Err(CloudflareError::ValidationError(
+                "Specify polish, webp, mirage and/or image_resizing".to_string(),
+            ));
+        }
+        if update.webp == Some(true) {
+            let polish = match update.polish {
+                Some(mode) => mode,
+                None => self.current_polish().await?,
+            };
+            check_webp_allowed(polish)?;
+        }
+
+        let mut updated = ImageSettings::default();
+        if let Some(mode) = update.polish {
+            updated.polish = Some(self.set_polish(mode).await?);
+        }
+        if let Some(enabled) = update.webp {
+            updated.webp = Some(self.set_on_off("webp", enabled).await?);
+        }
+        if let Some(enabled) = update.mirage {
+            updated.mirage = Some(self.set_mirage(enabled).await?);
+        }
+        if let Some(enabled) = update.image_resizing {
+            updated.image_resizing = Some(self.set_image_resizing(enabled).await?);
+        }
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CloudflareConfig;
+    use crate::services::settings::{zone_setting_updates, ZoneSettingOutcome};
+    use crate::test_support::{mock_service, success};
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer};
+
+    #[test]
+    fn test_toggles_map_to_zone_setting_ids() {
+        let ids: Vec<_> = PerformanceToggle::ALL.iter().map(|t| (t.field(), t.setting_id())).collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("http2", "http2"),
+                ("http3", "http3"),
+                ("zero_rtt", "0rtt"),
+                ("websockets", "websockets"),
+                ("early_hints", "early_hints"),
+                ("brotli_compression", "brotli"),
+                ("rocket_loader", "rocket_loader"),
+            ]
+        );
+
+        // Same mapping the zone-settings batch uses for the config fields
+        let config_updates = zone_setting_updates(&CloudflareConfig::default());
+        for toggle in PerformanceToggle::ALL {
+            let update = config_updates.iter().find(|u| u.field == toggle.field()).unwrap();
+            assert_eq!(update.setting_id, toggle.setting_id());
+        }
+    }
+
+    #[test]
+    fn test_on_off_coercion() {
+        assert_eq!(on_off(true), json!("on"));
+        assert_eq!(on_off(false), json!("off"));
+        assert_eq!(parse_on_off(&json!("on")), Some(true));
+        assert_eq!(parse_on_off(&json!("off")), Some(false));
+        assert_eq!(parse_on_off(&json!(true)), Some(true));
+        assert_eq!(parse_on_off(&json!("lossy")), None);
+        assert_eq!(parse_on_off(&json!(null)), None);
+
+        let update = PerformanceSettings { http3: Some(true), zero_rtt: Some(false), ..Default::default() };
+        let values: Vec<_> = update.updates().into_iter().map(|u| (u.setting_id, u.value)).collect();
+        assert_eq!(values, vec![("http3", json!("on")), ("0rtt", json!("off"))]);
+
+        assert!(serde_json::from_value::<PerformanceSettings>(json!({ "http4": true })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_settings_skips_settings_missing_from_plan() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings"))
+            .respond_with(success(json!([
+                { "id": "http3", "value": "off", "editable": true },
+                { "id": "brotli", "value": "on", "editable": true }
+            ])))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/http3"))
+            .and(body_json(json!({ "value": "on" })))
+            .respond_with(success(json!({ "id": "http3", "value": "on", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let performance = mock_service(&server, PerformanceService::new);
+
+        let current = performance.get_settings().await.unwrap();
+        assert_eq!(current.http3, Some(false));
+        assert_eq!(current.brotli_compression, Some(true));
+        assert_eq!(current.early_hints, None);
+
+        let results = performance
+            .update_settings(&PerformanceSettings {
+                http3: Some(true),
+                early_hints: Some(true),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let outcome = |id: &str| results.iter().find(|r| r.setting_id == id).unwrap().outcome;
+        assert_eq!(outcome("http3"), ZoneSettingOutcome::Applied);
+        assert_eq!(outcome("early_hints"), ZoneSettingOutcome::Skipped);
+
+        assert!(matches!(
+            performance.update_settings(&PerformanceSettings::default()).await,
+            Err(CloudflareError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_polish_mode_serialization() {
+        assert_eq!(serde_json::to_value(PolishMode::Off).unwrap(), json!("off"));
+        assert_eq!(serde_json::to_value(PolishMode::Lossless).unwrap(), json!("lossless"));
+        assert_eq!(serde_json::to_value(PolishMode::Lossy).unwrap(), json!("lossy"));
+
+        let update: ImageSettings = serde_json::from_value(json!({ "polish": "lossless", "webp": true })).unwrap();
+        assert_eq!(update.polish, Some(PolishMode::Lossless));
+        assert!(serde_json::from_value::<ImageSettings>(json!({ "polish": "maximum" })).is_err());
+
+        let current = ImageSettings::from_zone_settings(&[
+            ZoneSetting { id: "polish".to_string(), value: json!("lossy"), editable: true, modified_on: None },
+            ZoneSetting { id: "webp".to_string(), value: json!("off"), editable: true, modified_on: None },
+        ]);
+        assert_eq!(current.polish, Some(PolishMode::Lossy));
+        assert_eq!(current.webp, Some(false));
+        assert_eq!(current.mirage, None);
+    }
+
+    #[tokio::test]
+    async fn test_webp_requires_polish() {
+        assert!(check_webp_allowed(PolishMode::Lossless).is_ok());
+        assert!(matches!(check_webp_allowed(PolishMode::Off), Err(CloudflareError::ValidationError(_))));
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings/polish"))
+            .respond_with(success(json!({ "id": "polish", "value": "off", "editable": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/webp"))
+            .respond_with(success(json!({ "id": "webp", "value": "on", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/polish"))
+            .and(body_json(json!({ "value": "lossy" })))
+            .respond_with(success(json!({ "id": "polish", "value": "lossy", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Polish is off, so WebP alone is refused before anything is sent
+        let performance = mock_service(&server, PerformanceService::new);
+        assert!(matches!(performance.set_webp(true).await, Err(CloudflareError::ValidationError(_))));
+        let webp_only = ImageSettings { webp: Some(true), ..Default::default() };
+        assert!(matches!(
+            performance.update_image_settings(&webp_only).await,
+            Err(CloudflareError::ValidationError(_))
+        ));
+
+        // Turning Polish on in the same update lets WebP through
+        let updated = performance
+            .update_image_settings(&ImageSettings {
+                polish: Some(PolishMode::Lossy),
+                webp: Some(true),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(updated.polish, Some(PolishMode::Lossy));
+        assert_eq!(updated.webp, Some(true));
+    }
+}
@@ -3,26 +3,75 @@
 use crate::client::CloudflareClient;
 use crate::config::CloudflareConfig;
 use crate::error::{CloudflareError, CloudflareResult};
+use crate::services::features::{FeatureFlags, R2_MEDIA_OFFLOAD};
+use async_trait::async_trait;
+use aws_sdk_s3::config::timeout::TimeoutConfig;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, CorsConfiguration};
 use aws_sdk_s3::Client as S3Client;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
 use sqlx::PgPool;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tracing::{debug, info, warn};
+
+/// Default size of each part in a multipart upload
+pub const DEFAULT_PART_SIZE: usize = 16 * 1024 * 1024;
+/// Smallest part S3 accepts, other than the last one
+pub const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+/// Most parts S3 allows in a single upload
+const MAX_PARTS: i32 = 10_000;
+/// Methods R2 allows in a CORS rule
+const CORS_METHODS: &[&str] = &["GET", "PUT", "POST", "DELETE", "HEAD"];
+/// Size of each read when streaming a local file to R2
+const FILE_READ_CHUNK: usize = 1024 * 1024;
 
 pub struct R2Service {
     #[allow(dead_code)]
     client: Option<Arc<CloudflareClient>>,
-    #[allow(dead_code)]
     db: PgPool,
     s3_client: Option<S3Client>,
+    /// Bucket media library uploads are copied to
+    media_bucket: Option<String>,
+    /// Public URL the media bucket is served from
+    public_url: Option<String>,
+    features: FeatureFlags,
 }
 
 impl R2Service {
     pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
-        Self { client: Some(client), db, s3_client: None }
+        Self {
+            client: Some(client),
+            db,
+            s3_client: None,
+            media_bucket: None,
+            public_url: None,
+            features: FeatureFlags::default(),
+        }
     }
 
     /// Create without a configured client (for initial setup)
     pub fn new_unconfigured(db: PgPool) -> Self {
-        Self { client: None, db, s3_client: None }
+        Self {
+            client: None,
+            db,
+            s3_client: None,
+            media_bucket: None,
+            public_url: None,
+            features: FeatureFlags::default(),
+        }
+    }
+
+    /// Use shared feature flags
+    pub fn with_features(mut self, features: FeatureFlags) -> Self {
+        self.features = features;
+        self
     }
 
     /// Get the S3 client or return an error if not initialized
@@ -41,26 +90,27 @@ impl R2Service {
         let secret_key = config.r2_secret_access_key.as_ref()
             .ok_or(CloudflareError::MissingConfig("r2_secret_access_key".into()))?;
 
-        let creds = aws_credential_types::Credentials::new(
-            access_key, secret_key, None, None, "rustcloudflare"
-        );
-
-        let s3_config = aws_sdk_s3::Config::builder()
-            .endpoint_url(config.r2_endpoint())
-            .credentials_provider(creds)
-            .region(aws_sdk_s3::config::Region::new("auto"))
-            .force_path_style(true)
-            .build();
-
-        self.s3_client = Some(S3Client::from_conf(s3_config));
+        self.s3_client = Some(build_s3_client(
+            &config.r2_endpoint(),
+            access_key,
+            secret_key,
+            config.request_timeouts().upload,
+        ));
+        self.media_bucket = config.r2_bucket.clone();
+        self.public_url = config.r2_public_url.clone();
         Ok(())
     }
 
+    /// Whether a media bucket is configured for offloaded attachments
+    pub fn media_offload_enabled(&self) -> bool {
+        self.s3_client.is_some() && self.media_bucket.is_some()
+    }
+
     pub async fn list_buckets(&self) -> CloudflareResult<Vec<String>> {
         let client = self.get_s3_client()?;
 
         let result = client.list_buckets().send().await
-            .map_err(|e| CloudflareError::R2Error(e.to_string()))?;
+            .map_err(sdk_error)?;
 
         Ok(result.buckets().iter().filter_map(|b| b.name().map(|s| s.to_string())).collect())
     }
@@ -74,7 +124,7 @@ impl R2Service {
         }
 
         let result = req.send().await
-            .map_err(|e| CloudflareError::R2Error(e.to_string()))?;
+            .map_err(sdk_error)?;
 
         Ok(result.contents().iter().map(|obj| R2Object {
             key: obj.key().unwrap_or_default().to_string(),
@@ -97,11 +147,42 @@ impl R2Service {
         }
 
         req.send().await
-            .map_err(|e| CloudflareError::R2Error(e.to_string()))?;
+            .map_err(sdk_error)?;
 
         Ok(())
     }
 
+    /// Upload a stream of bytes without holding the whole object in memory.
+    ///
+    /// The stream is cut into `part_size` parts and sent with the S3
+    /// multipart API; anything shorter than one part is sent as a single
+    /// object. A failed upload is aborted so no orphaned parts are left
+    /// behind. Returns the number of bytes uploaded.
+    pub async fn upload_multipart<S, E>(
+        &self,
+        bucket: &str,
+        key: &str,
+        stream: S,
+        part_size: usize,
+        content_type: Option<&str>,
+    ) -> CloudflareResult<u64>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: std::fmt::Display,
+    {
+        if part_size < MIN_PART_SIZE {
+            return Err(CloudflareError::ValidationError(format!(
+                "Part size must be at least {} bytes",
+                MIN_PART_SIZE
+            )));
+        }
+
+        let client = self.get_s3_client()?;
+        PartUpload::new(client, bucket, key, part_size, content_type)
+            .run(stream)
+            .await
+    }
+
     pub async fn delete(&self, bucket: &str, key: &str) -> CloudflareResult<()> {
         let client = self.get_s3_client()?;
 
@@ -109,8 +190,240 @@ impl R2Service {
             .bucket(bucket)
             .key(key)
             .send().await
+            .map_err(sdk_error)?;
+
+        Ok(())
+    }
+
+    /// Stream an object's body without buffering it.
+    ///
+    /// `range` is passed to R2 as-is (e.g. `bytes=0-1023`); R2 then returns
+    /// only that slice and `content_range` is set on the result.
+    pub async fn download(&self, bucket: &str, key: &str, range: Option<&str>) -> CloudflareResult<R2Download> {
+        let client = self.get_s3_client()?;
+
+        let result = client.get_object()
+            .bucket(bucket)
+            .key(key)
+            .set_range(range.map(str::to_string))
+            .send().await
+            .map_err(|e| match e.as_service_error() {
+                Some(err) if err.is_no_such_key() => {
+                    CloudflareError::NotFound(format!("Object '{}' not found in bucket '{}'", key, bucket))
+                }
+                _ => sdk_error(e),
+            })?;
+
+        let content_type = result.content_type().map(str::to_string);
+        let content_length = result.content_length().filter(|len| *len >= 0);
+        let content_range = result.content_range().map(str::to_string);
+        let etag = result.e_tag().map(str::to_string);
+        let body = futures::stream::unfold(result.body, |mut body| async move {
+            body.try_next()
+                .await
+                .map_err(|e| CloudflareError::R2Error(format!("Failed to read object body: {}", e)))
+                .transpose()
+                .map(|chunk| (chunk, body))
+        })
+        .boxed();
+
+        Ok(R2Download { body, content_type, content_length, content_range, etag })
+    }
+
+    /// CORS rules currently applied to a bucket
+    pub async fn get_cors(&self, bucket: &str) -> CloudflareResult<Vec<CorsRule>> {
+        let client = self.get_s3_client()?;
+
+        match client.get_bucket_cors().bucket(bucket).send().await {
+            Ok(result) => Ok(result.cors_rules().iter().map(CorsRule::from).collect()),
+            Err(e) if e.code() == Some("NoSuchCORSConfiguration") => Ok(Vec::new()),
+            Err(e) => Err(sdk_error(e)),
+        }
+    }
+
+    /// Replace a bucket's CORS rules; an empty list removes CORS entirely
+    pub async fn put_cors(&self, bucket: &str, rules: &[CorsRule]) -> CloudflareResult<()> {
+        validate_cors_rules(rules)?;
+        let client = self.get_s3_client()?;
+
+        if rules.is_empty() {
+            info!("Removing CORS configuration from bucket {}", bucket);
+            client.delete_bucket_cors().bucket(bucket).send().await
+                .map_err(sdk_error)?;
+            return Ok(());
+        }
+
+        let configuration = CorsConfiguration::builder()
+            .set_cors_rules(Some(
+                rules.iter().map(CorsRule::to_s3).collect::<CloudflareResult<Vec<_>>>()?,
+            ))
+            .build()
             .map_err(|e| CloudflareError::R2Error(e.to_string()))?;
 
+        info!("Applying {} CORS rules to bucket {}", rules.len(), bucket);
+        client.put_bucket_cors()
+            .bucket(bucket)
+            .cors_configuration(configuration)
+            .send().await
+            .map_err(sdk_error)?;
+
+        Ok(())
+    }
+
+    /// Copy a local media file to `key` in the media bucket, returning its size
+    pub async fn offload_media(&self, local_path: &Path, key: &str) -> CloudflareResult<u64> {
+        let bucket = self.media_bucket.as_deref()
+            .ok_or_else(|| CloudflareError::MissingConfig("r2_bucket".into()))?;
+
+        let file = tokio::fs::File::open(local_path).await
+            .map_err(|e| CloudflareError::R2Error(format!("Failed to open {}: {}", local_path.display(), e)))?;
+
+        let size = self
+            .upload_multipart(bucket, key, file_chunks(file), DEFAULT_PART_SIZE, media_content_type(local_path))
+            .await?;
+
+        debug!("Offloaded {} to R2 as {}/{} ({} bytes)", local_path.display(), bucket, key, size);
+        Ok(size)
+    }
+
+    /// Offload a newly uploaded attachment and remember where it went.
+    ///
+    /// Failures are logged and return `None`; the local file is never
+    /// touched, so the site keeps serving it as before.
+    pub async fn offload_attachment(&self, attachment_id: &str, local_path: &Path) -> Option<R2MediaObject> {
+        if !self.media_offload_enabled() || !self.features.is_enabled(R2_MEDIA_OFFLOAD) {
+            return None;
+        }
+
+        let key = media_object_key(attachment_id, local_path, Utc::now());
+        let result = async {
+            let size = self.offload_media(local_path, &key).await?;
+            let object = R2MediaObject {
+                attachment_id: attachment_id.to_string(),
+                bucket: self.media_bucket.clone().unwrap_or_default(),
+                public_url: self.media_public_url(&key),
+                object_key: key,
+                local_path: local_path.display().to_string(),
+                size_bytes: size as i64,
+            };
+            self.record_media_object(&object).await?;
+            Ok::<_, CloudflareError>(object)
+        }
+        .await;
+
+        match result {
+            Ok(object) => {
+                info!("Offloaded attachment {} to R2 as {}", attachment_id, object.object_key);
+                Some(object)
+            }
+            Err(e) => {
+                warn!("Failed to offload attachment {} to R2, keeping local copy: {}", attachment_id, e);
+                None
+            }
+        }
+    }
+
+    /// Remove a deleted attachment's R2 copy, returning whether it had one
+    pub async fn remove_attachment(&self, attachment_id: &str) -> CloudflareResult<bool> {
+        let Some(object) = self.media_object(attachment_id).await? else {
+            return Ok(false);
+        };
+
+        self.delete(&object.bucket, &object.object_key).await?;
+        sqlx::query("DELETE FROM cloudflare_r2_media WHERE attachment_id = $1")
+            .bind(attachment_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        info!("Removed R2 copy of attachment {}", attachment_id);
+        Ok(true)
+    }
+
+    /// URL to serve an attachment from: its R2 public URL once it has been
+    /// offloaded, otherwise `local_url`.
+    ///
+    /// Switching the offload flag off serves every attachment locally again;
+    /// the local files are never removed.
+    pub async fn attachment_url(&self, attachment_id: &str, local_url: &str) -> String {
+        if self.public_url.is_none() || !self.features.is_enabled(R2_MEDIA_OFFLOAD) {
+            return local_url.to_string();
+        }
+        match self.media_object(attachment_id).await {
+            Ok(object) => object.and_then(|o| o.public_url).unwrap_or_else(|| local_url.to_string()),
+            Err(e) => {
+                warn!("Failed to look up R2 copy of attachment {}: {}", attachment_id, e);
+                local_url.to_string()
+            }
+        }
+    }
+
+    /// Public URL for an object in the media bucket
+    pub fn media_public_url(&self, key: &str) -> Option<String> {
+        self.public_url
+            .as_deref()
+            .map(|base| format!("{}/{}", base.trim_end_matches('/'), key))
+    }
+
+    /// Where an attachment was offloaded to, if it was
+    pub async fn media_object(&self, attachment_id: &str) -> CloudflareResult<Option<R2MediaObject>> {
+        let row: Option<MediaRow> = sqlx::query_as(
+            r#"
+            SELECT attachment_id, bucket, object_key, local_path, public_url, size_bytes
+            FROM cloudflare_r2_media
+            WHERE attachment_id = $1
+            "#,
+        )
+        .bind(attachment_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(row.map(R2MediaObject::from))
+    }
+
+    /// The offloaded attachment served at a public URL, to rewrite it back
+    pub async fn media_object_by_url(&self, public_url: &str) -> CloudflareResult<Option<R2MediaObject>> {
+        let row: Option<MediaRow> = sqlx::query_as(
+            r#"
+            SELECT attachment_id, bucket, object_key, local_path, public_url, size_bytes
+            FROM cloudflare_r2_media
+            WHERE public_url = $1
+            "#,
+        )
+        .bind(public_url)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(row.map(R2MediaObject::from))
+    }
+
+    async fn record_media_object(&self, object: &R2MediaObject) -> CloudflareResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cloudflare_r2_media
+                (attachment_id, bucket, object_key, local_path, public_url, size_bytes)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (attachment_id) DO UPDATE SET
+                bucket = EXCLUDED.bucket,
+                object_key = EXCLUDED.object_key,
+                local_path = EXCLUDED.local_path,
+                public_url = EXCLUDED.public_url,
+                size_bytes = EXCLUDED.size_bytes,
+                created_at = NOW()
+            "#,
+        )
+        .bind(&object.attachment_id)
+        .bind(&object.bucket)
+        .bind(&object.object_key)
+        .bind(&object.local_path)
+        .bind(&object.public_url)
+        .bind(object.size_bytes)
+        .execute(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
         Ok(())
     }
 
@@ -127,3 +440,822 @@ pub struct R2Object {
     pub etag: Option<String>,
     pub last_modified: Option<String>,
 }
+
+/// An object body being streamed from R2
+pub struct R2Download {
+    pub body: BoxStream<'static, CloudflareResult<Bytes>>,
+    pub content_type: Option<String>,
+    pub content_length: Option<i64>,
+    /// Set when only part of the object was requested
+    pub content_range: Option<String>,
+    pub etag: Option<String>,
+}
+
+/// A media library attachment copied to R2
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct R2MediaObject {
+    pub attachment_id: String,
+    pub bucket: String,
+    pub object_key: String,
+    /// Original file, left in place after offloading
+    pub local_path: String,
+    pub public_url: Option<String>,
+    pub size_bytes: i64,
+}
+
+type MediaRow = (String, String, String, String, Option<String>, i64);
+
+impl From<MediaRow> for R2MediaObject {
+    fn from((attachment_id, bucket, object_key, local_path, public_url, size_bytes): MediaRow) -> Self {
+        Self { attachment_id, bucket, object_key, local_path, public_url, size_bytes }
+    }
+}
+
+/// R2 key for an attachment: `media/<year>/<month>/<attachment id>/<file name>`.
+///
+/// The attachment id keeps keys unique when files share a name, and the file
+/// name is reduced to URL-safe characters.
+pub fn media_object_key(attachment_id: &str, local_path: &Path, uploaded_at: DateTime<Utc>) -> String {
+    let safe = |value: &str| -> String {
+        let mut out = String::with_capacity(value.len());
+        for c in value.chars() {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                out.push(c);
+            } else if !out.ends_with('-') {
+                out.push('-');
+            }
+        }
+        out.trim_matches(|c| c == '-' || c == '.').to_string()
+    };
+
+    let file_name = local_path
+        .file_name()
+        .map(|name| safe(&name.to_string_lossy()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "file".to_string());
+    let id = safe(attachment_id);
+
+    format!("media/{}/{}/{}", uploaded_at.format("%Y/%m"), id, file_name)
+}
+
+/// Content type for common media library files
+fn media_content_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "txt" => "text/plain",
+        _ => return None,
+    })
+}
+
+/// Read a file as a stream of chunks
+fn file_chunks(file: tokio::fs::File) -> impl Stream<Item = std::io::Result<Bytes>> + Unpin {
+    Box::pin(futures::stream::try_unfold(file, |mut file| async move {
+        let mut buf = BytesMut::with_capacity(FILE_READ_CHUNK);
+        let read = file.read_buf(&mut buf).await?;
+        Ok((read > 0).then(|| (buf.freeze(), file)))
+    }))
+}
+
+/// A bucket CORS rule
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CorsRule {
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Option<Vec<String>>,
+    #[serde(default)]
+    pub expose_headers: Option<Vec<String>>,
+    pub max_age_seconds: Option<u32>,
+}
+
+impl CorsRule {
+    fn to_s3(&self) -> CloudflareResult<aws_sdk_s3::types::CorsRule> {
+        aws_sdk_s3::types::CorsRule::builder()
+            .set_allowed_origins(Some(self.allowed_origins.clone()))
+            .set_allowed_methods(Some(
+                self.allowed_methods.iter().map(|m| m.trim().to_ascii_uppercase()).collect(),
+            ))
+            .set_allowed_headers(self.allowed_headers.clone())
+            .set_expose_headers(self.expose_headers.clone())
+            .set_max_age_seconds(self.max_age_seconds.map(|s| s.min(i32::MAX as u32) as i32))
+            .build()
+            .map_err(|e| CloudflareError::R2Error(e.to_string()))
+    }
+}
+
+impl From<&aws_sdk_s3::types::CorsRule> for CorsRule {
+    fn from(rule: &aws_sdk_s3::types::CorsRule) -> Self {
+        let optional = |values: &[String]| (!values.is_empty()).then(|| values.to_vec());
+        Self {
+            allowed_origins: rule.allowed_origins().to_vec(),
+            allowed_methods: rule.allowed_methods().to_vec(),
+            allowed_headers: optional(rule.allowed_headers()),
+            expose_headers: optional(rule.expose_headers()),
+            max_age_seconds: rule.max_age_seconds().and_then(|s| u32::try_from(s).ok()),
+        }
+    }
+}
+
+/// Check every rule names at least one origin and only methods R2 supports
+fn validate_cors_rules(rules: &[CorsRule]) -> CloudflareResult<()> {
+    for (i, rule) in rules.iter().enumerate() {
+        let position = i + 1;
+        if rule.allowed_origins.is_empty() || rule.allowed_origins.iter().any(|o| o.trim().is_empty()) {
+            return Err(CloudflareError::ValidationError(format!(
+                "CORS rule {} needs at least one non-empty origin",
+                position
+            )));
+        }
+        if rule.allowed_methods.is_empty() {
+            return Err(CloudflareError::ValidationError(format!(
+                "CORS rule {} needs at least one method",
+                position
+            )));
+        }
+        if let Some(method) = rule
+            .allowed_methods
+            .iter()
+            .find(|m| !CORS_METHODS.contains(&m.trim().to_ascii_uppercase().as_str()))
+        {
+            return Err(CloudflareError::ValidationError(format!(
+                "CORS rule {} has unsupported method '{}'; allowed: {}",
+                position,
+                method,
+                CORS_METHODS.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// S3 client for an R2 endpoint. Every call gets `timeout`, the upload
+/// timeout, since object transfers dominate what it does.
+fn build_s3_client(endpoint: &str, access_key: &str, secret_key: &str, timeout: Duration) -> S3Client {
+    let creds = aws_credential_types::Credentials::new(
+        access_key, secret_key, None, None, "rustcloudflare"
+    );
+
+    let s3_config = aws_sdk_s3::Config::builder()
+        .endpoint_url(endpoint)
+        .credentials_provider(creds)
+        .region(aws_sdk_s3::config::Region::new("auto"))
+        .force_path_style(true)
+        .sleep_impl(TokioSleep)
+        .timeout_config(TimeoutConfig::builder().operation_attempt_timeout(timeout).build())
+        .build();
+
+    S3Client::from_conf(s3_config)
+}
+
+/// Timer for the SDK's retries and timeouts; the `rt-tokio` feature that
+/// would provide one is left off with the SDK's other defaults
+#[derive(Debug)]
+struct TokioSleep;
+
+impl aws_sdk_s3::config::AsyncSleep for TokioSleep {
+    fn sleep(&self, duration: Duration) -> aws_sdk_s3::config::Sleep {
+        aws_sdk_s3::config::Sleep::new(tokio::time::sleep(duration))
+    }
+}
+
+/// Map an S3 SDK error, keeping timeouts and unreachable endpoints apart
+/// from errors R2 returned
+fn sdk_error<E, R>(error: SdkError<E, R>) -> CloudflareError
+where
+    E: std::error::Error + 'static,
+    R: std::fmt::Debug,
+{
+    match &error {
+        SdkError::TimeoutError(_) => CloudflareError::Timeout(error.to_string()),
+        SdkError::DispatchFailure(failure) if failure.is_timeout() => CloudflareError::Timeout(error.to_string()),
+        SdkError::DispatchFailure(failure) if failure.is_io() => CloudflareError::NetworkError(error.to_string()),
+        _ => CloudflareError::R2Error(error.to_string()),
+    }
+}
+
+/// Object storage calls used to upload a file in parts
+#[async_trait]
+pub trait MultipartTarget: Send + Sync {
+    /// Upload a whole object in one request
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Bytes,
+        content_type: Option<&str>,
+    ) -> CloudflareResult<()>;
+
+    /// Start a multipart upload, returning its upload ID
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        content_type: Option<&str>,
+    ) -> CloudflareResult<String>;
+
+    /// Upload one part, returning its ETag
+    async fn upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Bytes,
+    ) -> CloudflareResult<String>;
+
+    /// Assemble the uploaded parts into the final object
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<(i32, String)>,
+    ) -> CloudflareResult<()>;
+
+    /// Discard an unfinished upload and its parts
+    async fn abort_multipart_upload(&self, bucket: &str, key: &str, upload_id: &str) -> CloudflareResult<()>;
+}
+
+#[async_trait]
+impl MultipartTarget for S3Client {
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Bytes,
+        content_type: Option<&str>,
+    ) -> CloudflareResult<()> {
+        self.put_object()
+            .bucket(bucket)
+            .key(key)
+            .body(ByteStream::from(body))
+            .set_content_type(content_type.map(str::to_string))
+            .send()
+            .await
+            .map_err(sdk_error)?;
+        Ok(())
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        content_type: Option<&str>,
+    ) -> CloudflareResult<String> {
+        let result = self
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .set_content_type(content_type.map(str::to_string))
+            .send()
+            .await
+            .map_err(sdk_error)?;
+        result
+            .upload_id()
+            .map(str::to_string)
+            .ok_or_else(|| CloudflareError::R2Error("Multipart upload returned no upload ID".to_string()))
+    }
+
+    async fn upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Bytes,
+    ) -> CloudflareResult<String> {
+        let result = self
+            .upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(sdk_error)?;
+        result
+            .e_tag()
+            .map(str::to_string)
+            .ok_or_else(|| CloudflareError::R2Error(format!("Part {} returned no ETag", part_number)))
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<(i32, String)>,
+    ) -> CloudflareResult<()> {
+        let parts = parts
+            .into_iter()
+            .map(|(part_number, etag)| CompletedPart::builder().part_number(part_number).e_tag(etag).build())
+            .collect();
+        self.complete_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+            .send()
+            .await
+            .map_err(sdk_error)?;
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(&self, bucket: &str, key: &str, upload_id: &str) -> CloudflareResult<()> {
+        self.abort_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await
+            .map_err(sdk_error)?;
+        Ok(())
+    }
+}
+
+/// A single streamed upload, split into parts as the bytes arrive
+struct PartUpload<'a, T: MultipartTarget + ?Sized> {
+    target: &'a T,
+    bucket: &'a str,
+    key: &'a str,
+    part_size: usize,
+    content_type: Option<&'a str>,
+    /// Set once the first full part is ready
+    upload_id: Option<String>,
+    parts: Vec<(i32, String)>,
+}
+
+impl<'a, T: MultipartTarget + ?Sized> PartUpload<'a, T> {
+    fn new(target: &'a T, bucket: &'a str, key: &'a str, part_size: usize, content_type: Option<&'a str>) -> Self {
+        Self {
+            target,
+            bucket,
+            key,
+            part_size,
+            content_type,
+            upload_id: None,
+            parts: Vec::new(),
+        }
+    }
+
+    /// Upload the stream, aborting the multipart upload if anything fails
+    async fn run<S, E>(mut self, stream: S) -> CloudflareResult<u64>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: std::fmt::Display,
+    {
+        let result = self.write(stream).await;
+
+        if let (Err(e), Some(upload_id)) = (&result, &self.upload_id) {
+            warn!("Aborting multipart upload of {}/{}: {}", self.bucket, self.key, e);
+            if let Err(abort_error) = self
+                .target
+                .abort_multipart_upload(self.bucket, self.key, upload_id)
+                .await
+            {
+                warn!("Failed to abort multipart upload {}: {}", upload_id, abort_error);
+            }
+        }
+
+        result
+    }
+
+    async fn write<S, E>(&mut self, mut stream: S) -> CloudflareResult<u64>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: std::fmt::Display,
+    {
+        let mut buffer = BytesMut::new();
+        let mut total: u64 = 0;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk
+                .map_err(|e| CloudflareError::R2Error(format!("Failed to read upload body: {}", e)))?;
+            total += chunk.len() as u64;
+            buffer.extend_from_slice(&chunk);
+
+            while buffer.len() >= self.part_size {
+                let part = buffer.split_to(self.part_size).freeze();
+                self.send_part(part).await?;
+            }
+        }
+
+        if total == 0 {
+            return Err(CloudflareError::ValidationError("No file data provided".to_string()));
+        }
+
+        // Smaller than one part: a plain upload is a single request
+        let Some(upload_id) = self.upload_id.clone() else {
+            self.target
+                .put_object(self.bucket, self.key, buffer.freeze(), self.content_type)
+                .await?;
+            return Ok(total);
+        };
+
+        if !buffer.is_empty() {
+            self.send_part(buffer.freeze()).await?;
+        }
+        self.target
+            .complete_multipart_upload(self.bucket, self.key, &upload_id, self.parts.clone())
+            .await?;
+        info!(
+            "Uploaded {}/{} in {} parts ({} bytes)",
+            self.bucket,
+            self.key,
+            self.parts.len(),
+            total
+        );
+        Ok(total)
+    }
+
+    async fn send_part(&mut self, body: Bytes) -> CloudflareResult<()> {
+        let upload_id = match &self.upload_id {
+            Some(id) => id.clone(),
+            None => {
+                let id = self
+                    .target
+                    .create_multipart_upload(self.bucket, self.key, self.content_type)
+                    .await?;
+                self.upload_id = Some(id.clone());
+                id
+            }
+        };
+
+        let part_number = self.parts.len() as i32 + 1;
+        if part_number > MAX_PARTS {
+            return Err(CloudflareError::R2Error(format!(
+                "Upload needs more than {} parts; use a larger part size",
+                MAX_PARTS
+            )));
+        }
+
+        debug!("Uploading part {} of {}/{} ({} bytes)", part_number, self.bucket, self.key, body.len());
+        let etag = self
+            .target
+            .upload_part(self.bucket, self.key, &upload_id, part_number, body)
+            .await?;
+        self.parts.push((part_number, etag));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RequestTimeouts;
+    use crate::test_support::lazy_pool;
+    use std::sync::Mutex;
+
+    /// Records calls, failing `upload_part` for one part number
+    #[derive(Default)]
+    struct RecordingTarget {
+        calls: Mutex<Vec<String>>,
+        fail_part: Option<i32>,
+    }
+
+    impl RecordingTarget {
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        fn record(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+
+    #[async_trait]
+    impl MultipartTarget for RecordingTarget {
+        async fn put_object(&self, _: &str, key: &str, body: Bytes, _: Option<&str>) -> CloudflareResult<()> {
+            self.record(format!("put {} {}", key, body.len()));
+            Ok(())
+        }
+
+        async fn create_multipart_upload(&self, _: &str, key: &str, _: Option<&str>) -> CloudflareResult<String> {
+            self.record(format!("create {}", key));
+            Ok("upload-1".to_string())
+        }
+
+        async fn upload_part(&self, _: &str, _: &str, _: &str, part_number: i32, body: Bytes) -> CloudflareResult<String> {
+            self.record(format!("part {} {}", part_number, body.len()));
+            if self.fail_part == Some(part_number) {
+                return Err(CloudflareError::R2Error("connection reset".to_string()));
+            }
+            Ok(format!("etag-{}", part_number))
+        }
+
+        async fn complete_multipart_upload(
+            &self,
+            _: &str,
+            _: &str,
+            upload_id: &str,
+            parts: Vec<(i32, String)>,
+        ) -> CloudflareResult<()> {
+            let etags: Vec<String> = parts.into_iter().map(|(n, etag)| format!("{}={}", n, etag)).collect();
+            self.record(format!("complete {} {}", upload_id, etags.join(",")));
+            Ok(())
+        }
+
+        async fn abort_multipart_upload(&self, _: &str, _: &str, upload_id: &str) -> CloudflareResult<()> {
+            self.record(format!("abort {}", upload_id));
+            Ok(())
+        }
+    }
+
+    fn chunks(sizes: &[usize]) -> impl Stream<Item = Result<Bytes, String>> + Unpin {
+        futures::stream::iter(sizes.iter().map(|&n| Ok(Bytes::from(vec![b'x'; n]))).collect::<Vec<_>>())
+    }
+
+    #[tokio::test]
+    async fn test_stream_is_split_into_parts() {
+        let target = RecordingTarget::default();
+        let size = PartUpload::new(&target, "media", "video.mp4", 4, Some("video/mp4"))
+            .run(chunks(&[3, 3, 4]))
+            .await
+            .unwrap();
+
+        assert_eq!(size, 10);
+        assert_eq!(
+            target.calls(),
+            vec![
+                "create video.mp4",
+                "part 1 4",
+                "part 2 4",
+                "part 3 2",
+                "complete upload-1 1=etag-1,2=etag-2,3=etag-3",
+            ]
+        );
+
+        // Less than one part goes up as a single object
+        let target = RecordingTarget::default();
+        PartUpload::new(&target, "media", "logo.png", 4, None)
+            .run(chunks(&[1, 2]))
+            .await
+            .unwrap();
+        assert_eq!(target.calls(), vec!["put logo.png 3"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_part_aborts_upload() {
+        let target = RecordingTarget { fail_part: Some(2), ..Default::default() };
+        let result = PartUpload::new(&target, "media", "video.mp4", 4, None)
+            .run(chunks(&[12]))
+            .await;
+
+        assert!(matches!(result, Err(CloudflareError::R2Error(ref m)) if m == "connection reset"));
+        assert_eq!(
+            target.calls(),
+            vec!["create video.mp4", "part 1 4", "part 2 4", "abort upload-1"]
+        );
+
+        // A broken client connection aborts too
+        let target = RecordingTarget::default();
+        let stream = futures::stream::iter(vec![
+            Ok(Bytes::from_static(b"abcdef")),
+            Err("client disconnected".to_string()),
+        ]);
+        let result = PartUpload::new(&target, "media", "video.mp4", 4, None).run(stream).await;
+        assert!(result.is_err());
+        assert_eq!(target.calls(), vec!["create video.mp4", "part 1 4", "abort upload-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_upload_multipart_rejects_small_part_size() {
+        let service = R2Service::new_unconfigured(lazy_pool());
+
+        let result = service
+            .upload_multipart("media", "video.mp4", chunks(&[1]), MIN_PART_SIZE - 1, None)
+            .await;
+        assert!(matches!(result, Err(CloudflareError::ValidationError(_))));
+    }
+
+    fn rule(origins: &[&str], methods: &[&str]) -> CorsRule {
+        CorsRule {
+            allowed_origins: origins.iter().map(|o| o.to_string()).collect(),
+            allowed_methods: methods.iter().map(|m| m.to_string()).collect(),
+            allowed_headers: None,
+            expose_headers: None,
+            max_age_seconds: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_cors_rules() {
+        assert!(validate_cors_rules(&[rule(&["https://example.com"], &["get", "HEAD"])]).is_ok());
+        assert!(validate_cors_rules(&[]).is_ok());
+
+        let err = validate_cors_rules(&[rule(&["*"], &["GET"]), rule(&["*"], &["PATCH"])]).unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("rule 2") && m.contains("PATCH")));
+        assert!(validate_cors_rules(&[rule(&[], &["GET"])]).is_err());
+        assert!(validate_cors_rules(&[rule(&[" "], &["GET"])]).is_err());
+        assert!(validate_cors_rules(&[rule(&["*"], &[])]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_put_cors_sends_s3_xml() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/media/"))
+            .and(query_param("cors", ""))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut service = R2Service::new_unconfigured(lazy_pool());
+        service.s3_client = Some(build_s3_client(&server.uri(), "key", "secret", RequestTimeouts::default().upload));
+
+        let rules = vec![
+            CorsRule {
+                allowed_headers: Some(vec!["Content-Type".to_string()]),
+                expose_headers: Some(vec!["ETag".to_string()]),
+                max_age_seconds: Some(3600),
+                ..rule(&["https://example.com", "https://www.example.com"], &["get", "PUT"])
+            },
+            rule(&["*"], &["HEAD"]),
+        ];
+        service.put_cors("media", &rules).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body = String::from_utf8(requests[0].body.clone()).unwrap();
+        assert!(body.starts_with("<CORSConfiguration"));
+        assert!(body.contains(concat!(
+            "<CORSRule>",
+            "<AllowedHeader>Content-Type</AllowedHeader>",
+            "<AllowedMethod>GET</AllowedMethod>",
+            "<AllowedMethod>PUT</AllowedMethod>",
+            "<AllowedOrigin>https://example.com</AllowedOrigin>",
+            "<AllowedOrigin>https://www.example.com</AllowedOrigin>",
+            "<ExposeHeader>ETag</ExposeHeader>",
+            "<MaxAgeSeconds>3600</MaxAgeSeconds>",
+            "</CORSRule>",
+            "<CORSRule>",
+            "<AllowedMethod>HEAD</AllowedMethod>",
+            "<AllowedOrigin>*</AllowedOrigin>",
+            "</CORSRule>",
+        )), "unexpected body: {}", body);
+        assert!(body.ends_with("</CORSConfiguration>"));
+
+        // Invalid rules never reach the bucket
+        assert!(service.put_cors("media", &[rule(&["*"], &["TRACE"])]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_download_forwards_range_and_content_type() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/media/videos/intro.mp4"))
+            .and(header("range", "bytes=0-3"))
+            .respond_with(
+                ResponseTemplate::new(206)
+                    .insert_header("content-type", "video/mp4")
+                    .insert_header("content-range", "bytes 0-3/10")
+                    .insert_header("etag", "\"abc\"")
+                    .set_body_bytes(b"\x00\x01\x02\x03".to_vec()),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/media/missing.txt"))
+            .respond_with(ResponseTemplate::new(404).set_body_string(concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>",
+                "<Error><Code>NoSuchKey</Code><Message>The specified key does not exist.</Message></Error>",
+            )))
+            .mount(&server)
+            .await;
+
+        let mut service = R2Service::new_unconfigured(lazy_pool());
+        service.s3_client = Some(build_s3_client(&server.uri(), "key", "secret", RequestTimeouts::default().upload));
+
+        let download = service.download("media", "videos/intro.mp4", Some("bytes=0-3")).await.unwrap();
+        assert_eq!(download.content_type.as_deref(), Some("video/mp4"));
+        assert_eq!(download.content_length, Some(4));
+        assert_eq!(download.content_range.as_deref(), Some("bytes 0-3/10"));
+        assert_eq!(download.etag.as_deref(), Some("\"abc\""));
+
+        let chunks: Vec<Bytes> = download.body.map(|chunk| chunk.unwrap()).collect().await;
+        assert_eq!(chunks.concat(), vec![0, 1, 2, 3]);
+
+        let err = service.download("media", "missing.txt", None).await.err().unwrap();
+        assert!(matches!(err, CloudflareError::NotFound(_)), "unexpected error: {:?}", err);
+    }
+
+    #[test]
+    fn test_media_object_key() {
+        let uploaded_at = DateTime::parse_from_rfc3339("2024-03-09T12:00:00Z").unwrap().with_timezone(&Utc);
+
+        assert_eq!(
+            media_object_key("42", Path::new("/var/www/uploads/Team Photo (1).JPG"), uploaded_at),
+            "media/2024/03/42/Team-Photo-1-.JPG"
+        );
+        assert_eq!(
+            media_object_key("a1/b2", Path::new("uploads/résumé.pdf"), uploaded_at),
+            "media/2024/03/a1-b2/r-sum-.pdf"
+        );
+        assert_eq!(media_object_key("7", Path::new("/"), uploaded_at), "media/2024/03/7/file");
+        assert_eq!(media_content_type(Path::new("logo.PNG")), Some("image/png"));
+        assert_eq!(media_content_type(Path::new("notes")), None);
+    }
+
+    #[tokio::test]
+    async fn test_offload_attachment_keeps_local_copy_on_failure() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let mut service = R2Service::new_unconfigured(lazy_pool());
+        assert!(!service.media_offload_enabled());
+        service.s3_client = Some(build_s3_client(&server.uri(), "key", "secret", RequestTimeouts::default().upload));
+        service.media_bucket = Some("media".to_string());
+
+        let dir = std::env::temp_dir().join(format!("r2-offload-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let local = dir.join("logo.png");
+        std::fs::write(&local, b"png bytes").unwrap();
+
+        assert!(service.offload_attachment("42", &local).await.is_none());
+        assert_eq!(std::fs::read(&local).unwrap(), b"png bytes");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_media_mapping_round_trip() {
+        use wiremock::matchers::{method, path_regex};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path_regex("^/media/media/.+/logo.png$"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path_regex("^/media/media/.+/logo.png$"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let mut service = R2Service::new_unconfigured(pool);
+        service.s3_client = Some(build_s3_client(&server.uri(), "key", "secret", RequestTimeouts::default().upload));
+        service.media_bucket = Some("media".to_string());
+        service.public_url = Some("https://cdn.example.com/".to_string());
+
+        let attachment_id = uuid::Uuid::new_v4().simple().to_string();
+        let dir = std::env::temp_dir().join(format!("r2-offload-{}", attachment_id));
+        std::fs::create_dir_all(&dir).unwrap();
+        let local = dir.join("logo.png");
+        std::fs::write(&local, b"png bytes").unwrap();
+
+        let object = service.offload_attachment(&attachment_id, &local).await.unwrap();
+        assert_eq!(object.size_bytes, 9);
+        let public_url = object.public_url.clone().unwrap();
+        assert_eq!(public_url, format!("https://cdn.example.com/{}", object.object_key));
+
+        assert_eq!(service.media_object(&attachment_id).await.unwrap(), Some(object.clone()));
+        assert_eq!(service.media_object_by_url(&public_url).await.unwrap(), Some(object));
+        assert_eq!(service.attachment_url(&attachment_id, "/uploads/logo.png").await, public_url);
+
+        // Switching the flag off serves the local copy again
+        let features = FeatureFlags::default();
+        features.set(R2_MEDIA_OFFLOAD, false).unwrap();
+        let service = service.with_features(features.clone());
+        assert_eq!(service.attachment_url(&attachment_id, "/uploads/logo.png").await, "/uploads/logo.png");
+        features.set(R2_MEDIA_OFFLOAD, true).unwrap();
+
+        assert!(service.remove_attachment(&attachment_id).await.unwrap());
+        assert!(service.media_object(&attachment_id).await.unwrap().is_none());
+        assert_eq!(service.attachment_url(&attachment_id, "/uploads/logo.png").await, "/uploads/logo.png");
+        assert!(!service.remove_attachment(&attachment_id).await.unwrap());
+
+        assert!(local.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
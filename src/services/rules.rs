@@ -0,0 +1,657 @@
+//! Rules service
+//!
+//! Page rules, the zone's page rule quota and Bulk Redirects
+
+use crate::client::CloudflareClient;
+use crate::error::{CloudflareError, CloudflareResult};
+use crate::models::{
+    BulkOperation, BulkRedirect, CreateIpList, CreatePageRule, DeleteResponse, IpList, PageRule, PageRuleAction,
+    PageRuleTarget, Plan, Ruleset, RulesetRule, UpdatePageRule,
+};
+use crate::services::security::validate_list_name;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::info;
+
+/// Page rules each plan includes
+pub const PAGE_RULE_LIMITS: &[(&str, usize)] = &[("free", 3), ("pro", 20), ("business", 50), ("enterprise", 125)];
+
+/// Page rules a zone on `plan` may have; the Free allowance when the plan is unknown
+pub fn page_rule_limit(plan: Option<&Plan>) -> usize {
+    plan.and_then(|p| p.legacy_id.as_deref())
+        .and_then(|id| PAGE_RULE_LIMITS.iter().find(|(plan, _)| *plan == id))
+        .map(|(_, limit)| *limit)
+        .unwrap_or(PAGE_RULE_LIMITS[0].1)
+}
+
+/// Account ruleset phase Bulk Redirect rules run in
+pub const BULK_REDIRECT_PHASE: &str = "http_request_redirect";
+/// Redirects sent per list items request
+pub const REDIRECT_ITEMS_CHUNK: usize = 1000;
+/// Status codes a bulk redirect can use
+pub const REDIRECT_STATUS_CODES: &[u16] = &[301, 302, 307, 308];
+
+/// How much of the zone's page rule allowance is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PageRuleQuota {
+    pub limit: usize,
+    pub used: usize,
+    pub remaining: usize,
+}
+
+/// Rules service
+pub struct RulesService {
+    client: Option<Arc<CloudflareClient>>,
+    #[allow(dead_code)]
+    db: PgPool,
+}
+
+impl RulesService {
+    /// Create a new rules service
+    pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
+        Self {
+            client: Some(client),
+            db,
+        }
+    }
+
+    /// Create a new rules service without a client
+    pub fn new_unconfigured(db: PgPool) -> Self {
+        Self { client: None, db }
+    }
+
+    /// Get the client or return an error if not configured
+    fn get_client(&self) -> CloudflareResult<&CloudflareClient> {
+        self.client.as_ref()
+            .map(|c| c.as_ref())
+            .ok_or(CloudflareError::NotConfigured)
+    }
+
+    /// List the zone's page rules
+    pub async fn list_page_rules(&self) -> CloudflareResult<Vec<PageRule>> {
+        self.get_client()?.list_page_rules().await
+    }
+
+    /// Get a page rule
+    pub async fn get_page_rule(&self, id: &str) -> CloudflareResult<PageRule> {
+        self.get_client()?.get_page_rule(id).await
+    }
+
+    /// Create a page rule
+    pub async fn create_page_rule(&self, rule: CreatePageRule) -> CloudflareResult<PageRule> {
+        validate_page_rule(&rule.targets, &rule.actions)?;
+        let created = self.get_client()?.create_page_rule(rule).await?;
+        info!("Created page rule {}", created.id);
+        Ok(created)
+    }
+
+    /// Replace a page rule's targets and actions
+    pub async fn update_page_rule(&self, id: &str, rule: UpdatePageRule) -> CloudflareResult<PageRule> {
+        validate_page_rule(&rule.targets, &rule.actions)?;
+        let updated = self.get_client()?.update_page_rule(id, rule).await?;
+        info!("Updated page rule {}", id);
+        Ok(updated)
+    }
+
+    /// Delete a page rule
+    pub async fn delete_page_rule(&self, id: &str) -> CloudflareResult<DeleteResponse> {
+        let deleted = self.get_client()?.delete_page_rule(id).await?;
+        info!("Deleted page rule {}", id);
+        Ok(deleted)
+    }
+
+    /// The zone's page rule allowance given `used` existing rules
+    pub async fn page_rule_quota(&self, used: usize) -> CloudflareResult<PageRuleQuota> {
+        let plan = self.get_client()?.get_zone().await?.plan;
+        let limit = page_rule_limit(plan.as_ref());
+        Ok(PageRuleQuota {
+            limit,
+            used,
+            remaining: limit.saturating_sub(used),
+        })
+    }
+
+    /// Create an account list to hold bulk redirects
+    pub async fn create_bulk_redirect_list(&self, name: &str) -> CloudflareResult<IpList> {
+        validate_list_name(name)?;
+        let list = self
+            .get_client()?
+            .create_ip_list(CreateIpList {
+                name: name.to_string(),
+                kind: "redirect".to_string(),
+                description: Some("Imported redirects".to_string()),
+            })
+            .await?;
+        info!("Created bulk redirect list {} ({})", list.name, list.id);
+        Ok(list)
+    }
+
+    /// Add redirects to a redirect list, in chunks of `REDIRECT_ITEMS_CHUNK`.
+    ///
+    /// Every redirect is checked before the first chunk is sent. Cloudflare
+    /// applies each chunk asynchronously; the returned operations can be
+    /// polled for completion.
+    pub async fn add_redirects(&self, list_id: &str, redirects: Vec<BulkRedirect>) -> CloudflareResult<Vec<BulkOperation>> {
+        let client = self.get_client()?;
+        validate_redirects(&redirects)?;
+
+        let mut operations = Vec::new();
+        for chunk in redirects.chunks(REDIRECT_ITEMS_CHUNK) {
+            operations.push(client.add_redirect_list_items(list_id, chunk).await?);
+        }
+        info!("Queued {} redirects for list {} in {} requests", redirects.len(), list_id, operations.len());
+        Ok(operations)
+    }
+
+    /// Turn on the redirects in a list with an account-level redirect rule.
+    ///
+    /// Replaces an existing rule for the same list, and leaves the phase's
+    /// other rules alone.
+    pub async fn enable_bulk_redirects(&self, list_id: &str) -> CloudflareResult<Ruleset> {
+        let client = self.get_client()?;
+        let list = client.get_ip_list(list_id).await?;
+        if list.kind != "redirect" {
+            return Err(CloudflareError::ValidationError(format!(
+                "List '{}' is a {} list, not a redirect list",
+                list.name, list.kind
+            )));
+        }
+
+        // Only this list's rule is written, so the phase's other rules, and
+        // any fields of theirs this plugin doesn't model, are left untouched
+        let rule = bulk_redirect_rule(&list.name);
+        let ruleset = match client.find_account_phase_ruleset(BULK_REDIRECT_PHASE).await? {
+            Some(ruleset) => {
+                let existing = ruleset
+                    .rules
+                    .iter()
+                    .find(|r| r.expression == rule.expression)
+                    .and_then(|r| r.id.clone());
+                match existing {
+                    Some(rule_id) => client.update_account_ruleset_rule(&ruleset.id, &rule_id, &rule).await?,
+                    None => client.create_account_ruleset_rule(&ruleset.id, &rule).await?,
+                }
+            }
+            // The first rule creates the phase's entrypoint ruleset
+            None => client.update_account_phase_ruleset(BULK_REDIRECT_PHASE, vec![rule]).await?,
+        };
+        info!("Enabled bulk redirects from list {}", list.name);
+        Ok(ruleset)
+    }
+}
+
+/// Redirect rule that applies every redirect in the named list
+fn bulk_redirect_rule(list_name: &str) -> RulesetRule {
+    RulesetRule {
+        id: None,
+        action: "redirect".to_string(),
+        action_parameters: Some(serde_json::json!({
+            "from_list": { "name": list_name, "key": "http.request.full_uri" }
+        })),
+        expression: format!("http.request.full_uri in ${}", list_name),
+        description: Some(format!("Bulk redirects from {}", list_name)),
+        enabled: Some(true),
+        ratelimit: None,
+        extra: Default::default(),
+    }
+}
+
+/// Check a redirect source: a URL, with or without its scheme
+fn is_valid_source_url(source: &str) -> bool {
+    let with_scheme = if source.contains("://") {
+        source.to_string()
+    } else {
+        format!("https://{}", source)
+    };
+    is_valid_target_url(&with_scheme)
+}
+
+/// Check a redirect target: an absolute http(s) URL
+fn is_valid_target_url(target: &str) -> bool {
+    url::Url::parse(target)
+        .map(|u| matches!(u.scheme(), "http" | "https") && u.host_str().is_some_and(|h| h.contains('.')))
+        .unwrap_or(false)
+}
+
+/// Check every redirect, reporting all problems at once
+pub fn validate_redirects(redirects: &[BulkRedirect]) -> CloudflareResult<()> {
+    if redirects.is_empty() {
+        return Err(CloudflareError::ValidationError("No redirects to add".to_string()));
+    }
+
+    let mut problems = Vec::new();
+    let mut sources = std::collections::HashSet::new();
+    for redirect in redirects {
+        if let Some(problem) = redirect_problem(redirect) {
+            problems.push(format!("{}: {}", redirect.source_url, problem));
+        } else if !sources.insert(redirect.source_url.as_str()) {
+            problems.push(format!("{}: duplicate source URL", redirect.source_url));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(CloudflareError::ValidationError(format!("Invalid redirects: {}", summarize(&problems))))
+    }
+}
+
+fn redirect_problem(redirect: &BulkRedirect) -> Option<String> {
+    if !is_valid_source_url(&redirect.source_url) {
+        Some("source is not a valid URL".to_string())
+    } else if !is_valid_target_url(&redirect.target_url) {
+        Some(format!("target '{}' is not a valid http(s) URL", redirect.target_url))
+    } else if !REDIRECT_STATUS_CODES.contains(&redirect.status_code) {
+        Some(format!("status {} is not one of {:?}", redirect.status_code, REDIRECT_STATUS_CODES))
+    } else {
+        None
+    }
+}
+
+/// The first few problems, and how many more there are
+fn summarize(problems: &[String]) -> String {
+    const SHOWN: usize = 10;
+    let mut summary = problems.iter().take(SHOWN).cloned().collect::<Vec<_>>().join("; ");
+    if problems.len() > SHOWN {
+        summary.push_str(&format!("; and {} more", problems.len() - SHOWN));
+    }
+    summary
+}
+
+/// Parse a redirects CSV with `source,target[,status[,preserve_query]]` rows.
+///
+/// A header row, blank lines and `#` comments are ignored. Status defaults to
+/// 301 and `preserve_query` (true/false, yes/no or 1/0) to false. Rows that
+/// can't be read are reported by line number.
+pub fn parse_redirects_csv(csv: &str) -> CloudflareResult<Vec<BulkRedirect>> {
+    let mut redirects = Vec::new();
+    let mut problems = Vec::new();
+    let mut first_row = true;
+
+    for (index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = split_csv_line(line);
+        let is_header = first_row && ["source", "source_url"].iter().any(|h| fields[0].eq_ignore_ascii_case(h));
+        first_row = false;
+        if is_header {
+            continue;
+        }
+
+        match redirect_from_fields(&fields) {
+            Ok(redirect) => redirects.push(redirect),
+            Err(problem) => problems.push(format!("line {}: {}", index + 1, problem)),
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(CloudflareError::ValidationError(format!(
+            "Couldn't read redirects CSV: {}",
+            summarize(&problems)
+        )));
+    }
+    validate_redirects(&redirects)?;
+    Ok(redirects)
+}
+
+fn redirect_from_fields(fields: &[String]) -> Result<BulkRedirect, String> {
+    if !(2..=4).contains(&fields.len()) {
+        return Err(format!("expected 2 to 4 columns, found {}", fields.len()));
+    }
+    let field = |i: usize| fields.get(i).map(|f| f.trim()).unwrap_or_default();
+
+    let status_code = match field(2) {
+        "" => 301,
+        status => status.parse().map_err(|_| format!("invalid status '{}'", status))?,
+    };
+    let preserve_query_string = match field(3).to_ascii_lowercase().as_str() {
+        "" | "false" | "no" | "0" => false,
+        "true" | "yes" | "1" => true,
+        other => return Err(format!("invalid preserve_query '{}'", other)),
+    };
+
+    let redirect = BulkRedirect {
+        source_url: field(0).to_string(),
+        target_url: field(1).to_string(),
+        status_code,
+        preserve_query_string,
+    };
+    match redirect_problem(&redirect) {
+        Some(problem) => Err(problem),
+        None => Ok(redirect),
+    }
+}
+
+/// Split a CSV line on commas outside double quotes; `""` inside quotes is a quote
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+fn validate_page_rule(targets: &[PageRuleTarget], actions: &[PageRuleAction]) -> CloudflareResult<()> {
+    if targets.is_empty() {
+        return Err(CloudflareError::ValidationError(
+            "At least one target URL pattern is required".to_string(),
+        ));
+    }
+    if actions.is_empty() {
+        return Err(CloudflareError::ValidationError("At least one action is required".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PageRuleConstraint;
+    use crate::test_support::{mock_service, success};
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn page_rule_json(id: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "targets": [{
+                "target": "url",
+                "constraint": { "operator": "matches", "value": "example.com/wp-admin/*" }
+            }],
+            "actions": [{ "id": "cache_level", "value": "bypass" }],
+            "priority": 1,
+            "status": "active"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_and_list_page_rules_call_the_api() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/pagerules"))
+            .and(body_json(json!({
+                "targets": [{
+                    "target": "url",
+                    "constraint": { "operator": "matches", "value": "example.com/wp-admin/*" }
+                }],
+                "actions": [{ "id": "cache_level", "value": "bypass" }],
+                "status": "active"
+            })))
+            .respond_with(success(page_rule_json("pr-1")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/pagerules"))
+            .respond_with(success(json!([page_rule_json("pr-1")])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rules = mock_service(&server, RulesService::new);
+        let created = rules
+            .create_page_rule(CreatePageRule {
+                targets: vec![PageRuleTarget {
+                    target: "url".to_string(),
+                    constraint: PageRuleConstraint {
+                        operator: "matches".to_string(),
+                        value: "example.com/wp-admin/*".to_string(),
+                    },
+                }],
+                actions: vec![PageRuleAction { id: "cache_level".to_string(), value: Some(json!("bypass")) }],
+                priority: None,
+                status: Some("active".to_string()),
+            })
+            .await
+            .unwrap();
+        assert_eq!(created.id, "pr-1");
+
+        let listed = rules.list_page_rules().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].actions[0].id, "cache_level");
+
+        // Rules without targets or actions never reach the API
+        let err = rules
+            .create_page_rule(CreatePageRule { targets: vec![], actions: vec![], priority: None, status: None })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_page_rule_quota_follows_plan() {
+        let plan = |legacy_id: Option<&str>| Plan {
+            id: "plan".to_string(),
+            name: "Plan".to_string(),
+            price: 0.0,
+            currency: "USD".to_string(),
+            frequency: "monthly".to_string(),
+            is_subscribed: true,
+            can_subscribe: false,
+            legacy_id: legacy_id.map(str::to_string),
+        };
+        assert_eq!(page_rule_limit(Some(&plan(Some("free")))), 3);
+        assert_eq!(page_rule_limit(Some(&plan(Some("business")))), 50);
+        assert_eq!(page_rule_limit(Some(&plan(None))), 3);
+        assert_eq!(page_rule_limit(None), 3);
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .respond_with(success(json!({
+                "id": "zone",
+                "name": "example.com",
+                "status": "active",
+                "paused": false,
+                "type": "full",
+                "development_mode": 0,
+                "name_servers": [],
+                "original_name_servers": null,
+                "plan": {
+                    "id": "plan-pro",
+                    "name": "Pro Website",
+                    "price": 20.0,
+                    "currency": "USD",
+                    "frequency": "monthly",
+                    "is_subscribed": true,
+                    "can_subscribe": false,
+                    "legacy_id": "pro"
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let quota = mock_service(&server, RulesService::new).page_rule_quota(22).await.unwrap();
+        assert_eq!(quota, PageRuleQuota { limit: 20, used: 22, remaining: 0 });
+    }
+
+    #[test]
+    fn test_parse_redirects_csv() {
+        let csv = "source,target,status,preserve_query\n\
+                   # old blog\n\
+                   example.com/old-post,https://example.com/new-post\n\
+                   \n\
+                   https://example.com/shop,https://shop.example.com/,302,yes\n\
+                   \"example.com/a,b\",\"https://example.com/a-b\",308,0\n";
+        let redirects = parse_redirects_csv(csv).unwrap();
+        assert_eq!(
+            redirects,
+            vec![
+                BulkRedirect {
+                    source_url: "example.com/old-post".to_string(),
+                    target_url: "https://example.com/new-post".to_string(),
+                    status_code: 301,
+                    preserve_query_string: false,
+                },
+                BulkRedirect {
+                    source_url: "https://example.com/shop".to_string(),
+                    target_url: "https://shop.example.com/".to_string(),
+                    status_code: 302,
+                    preserve_query_string: true,
+                },
+                BulkRedirect {
+                    source_url: "example.com/a,b".to_string(),
+                    target_url: "https://example.com/a-b".to_string(),
+                    status_code: 308,
+                    preserve_query_string: false,
+                },
+            ]
+        );
+
+        // Every bad row is reported with its line number
+        let err = parse_redirects_csv(
+            "example.com/a,/relative\nexample.com/b,https://example.com/b,303\nexample.com/c\nexample.com/d,https://example.com/d,301,maybe\n",
+        )
+        .unwrap_err();
+        match err {
+            CloudflareError::ValidationError(message) => {
+                for line in ["line 1:", "line 2:", "line 3:", "line 4:"] {
+                    assert!(message.contains(line), "{}", message);
+                }
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        assert!(parse_redirects_csv("source,target\n").is_err());
+        assert!(parse_redirects_csv("example.com/a,https://example.com/1\nexample.com/a,https://example.com/2\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_add_redirects_submits_in_chunks() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/rules/lists/list-1/items"))
+            .respond_with(success(json!({ "operation_id": "op" })))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let redirects: Vec<_> = (0..REDIRECT_ITEMS_CHUNK * 2 + 1)
+            .map(|i| BulkRedirect {
+                source_url: format!("example.com/old/{}", i),
+                target_url: format!("https://example.com/new/{}", i),
+                status_code: 301,
+                preserve_query_string: false,
+            })
+            .collect();
+        let operations = mock_service(&server, RulesService::new).add_redirects("list-1", redirects).await.unwrap();
+        assert_eq!(operations.len(), 3);
+
+        let requests = server.received_requests().await.unwrap();
+        let sizes: Vec<usize> = requests
+            .iter()
+            .map(|r| serde_json::from_slice::<Vec<serde_json::Value>>(&r.body).unwrap().len())
+            .collect();
+        assert_eq!(sizes, vec![REDIRECT_ITEMS_CHUNK, REDIRECT_ITEMS_CHUNK, 1]);
+
+        let first: Vec<serde_json::Value> = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            first[0],
+            json!({ "redirect": {
+                "source_url": "example.com/old/0",
+                "target_url": "https://example.com/new/0",
+                "status_code": 301,
+                "preserve_query_string": false
+            }})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enable_bulk_redirects_adds_account_rule() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/rules/lists/list-1"))
+            .respond_with(success(json!({ "id": "list-1", "name": "old_site", "kind": "redirect" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/rulesets/phases/http_request_redirect/entrypoint"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+                "success": false,
+                "errors": [{ "code": 10003, "message": "could not find entrypoint ruleset" }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/accounts/acct/rulesets/phases/http_request_redirect/entrypoint"))
+            .and(body_json(json!({ "rules": [{
+                "action": "redirect",
+                "action_parameters": { "from_list": { "name": "old_site", "key": "http.request.full_uri" } },
+                "expression": "http.request.full_uri in $old_site",
+                "description": "Bulk redirects from old_site",
+                "enabled": true
+            }]})))
+            .respond_with(success(json!({ "id": "rs-1", "name": "default", "kind": "root", "phase": "http_request_redirect", "rules": [] })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        mock_service(&server, RulesService::new).enable_bulk_redirects("list-1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_enable_bulk_redirects_updates_only_the_list_rule() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/rules/lists/list-1"))
+            .respond_with(success(json!({ "id": "list-1", "name": "old_site", "kind": "redirect" })))
+            .mount(&server)
+            .await;
+        let other = json!({
+            "id": "rule-other",
+            "action": "redirect",
+            "expression": "http.request.full_uri in $campaigns",
+            "logging": { "enabled": false }
+        });
+        let current = json!({
+            "id": "rule-old-site",
+            "action": "redirect",
+            "expression": "http.request.full_uri in $old_site",
+            "enabled": false
+        });
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/rulesets/phases/http_request_redirect/entrypoint"))
+            .respond_with(success(json!({
+                "id": "rs-1", "name": "default", "kind": "root", "phase": "http_request_redirect",
+                "rules": [other, current]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/accounts/acct/rulesets/rs-1/rules/rule-old-site"))
+            .and(body_json(json!({
+                "action": "redirect",
+                "action_parameters": { "from_list": { "name": "old_site", "key": "http.request.full_uri" } },
+                "expression": "http.request.full_uri in $old_site",
+                "description": "Bulk redirects from old_site",
+                "enabled": true
+            })))
+            .respond_with(success(json!({ "id": "rs-1", "name": "default", "kind": "root", "phase": "http_request_redirect", "rules": [] })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        mock_service(&server, RulesService::new).enable_bulk_redirects("list-1").await.unwrap();
+    }
+}
@@ -0,0 +1,350 @@
+//! Portable security rule exports
+//!
+//! An export captures a zone's firewall rules, custom WAF rules, IP access
+//! rules and rate limiting rules without the ids and timestamps Cloudflare
+//! assigns, so it can be imported into another zone. Source ids are kept
+//! alongside each rule only to report what they became on the target zone.
+
+use crate::models::{
+    CreateFirewallFilter, CreateFirewallRule, CreateIpAccessRule, FirewallRule, IpAccessRule, IpConfiguration,
+    RulesetRule,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Version written to new exports; imports of newer versions are refused
+pub const RULES_EXPORT_VERSION: u32 = 1;
+
+/// A zone's security rules in a form another zone can import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulesExport {
+    pub version: u32,
+    /// Zone the rules were exported from
+    pub source_zone_id: String,
+    pub exported_at: DateTime<Utc>,
+    #[serde(default)]
+    pub firewall_rules: Vec<ExportedFirewallRule>,
+    /// Rules of the `http_request_firewall_custom` phase, in the order they run
+    #[serde(default)]
+    pub custom_rules: Vec<RulesetRule>,
+    #[serde(default)]
+    pub ip_access_rules: Vec<ExportedIpAccessRule>,
+    /// Rules of the `http_ratelimit` phase, in the order they run
+    #[serde(default)]
+    pub rate_limit_rules: Vec<RulesetRule>,
+}
+
+/// Firewall rule with its filter inlined
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedFirewallRule {
+    /// Id on the source zone
+    pub id: String,
+    pub action: String,
+    pub expression: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub paused: bool,
+    pub priority: Option<i32>,
+    pub products: Option<Vec<String>>,
+}
+
+impl From<FirewallRule> for ExportedFirewallRule {
+    fn from(rule: FirewallRule) -> Self {
+        Self {
+            id: rule.id,
+            action: rule.action,
+            expression: rule.filter.expression,
+            description: rule.description,
+            paused: rule.paused,
+            priority: rule.priority,
+            products: rule.products,
+        }
+    }
+}
+
+impl ExportedFirewallRule {
+    /// Request recreating the rule, with a new filter
+    pub fn to_create(&self) -> CreateFirewallRule {
+        CreateFirewallRule {
+            action: self.action.clone(),
+            filter: CreateFirewallFilter {
+                expression: self.expression.clone(),
+                description: self.description.clone(),
+            },
+            description: self.description.clone(),
+            paused: Some(self.paused),
+            priority: self.priority,
+            products: self.products.clone(),
+        }
+    }
+}
+
+/// IP access rule without its zone scope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedIpAccessRule {
+    /// Id on the source zone
+    pub id: String,
+    pub mode: String,
+    pub target: String,
+    pub value: String,
+    pub notes: Option<String>,
+}
+
+impl From<IpAccessRule> for ExportedIpAccessRule {
+    fn from(rule: IpAccessRule) -> Self {
+        Self {
+            id: rule.id,
+            mode: rule.mode,
+            target: rule.configuration.target,
+            value: rule.configuration.value,
+            notes: rule.notes,
+        }
+    }
+}
+
+impl ExportedIpAccessRule {
+    pub fn to_create(&self) -> CreateIpAccessRule {
+        CreateIpAccessRule {
+            mode: self.mode.clone(),
+            configuration: IpConfiguration {
+                target: self.target.clone(),
+                value: self.value.clone(),
+            },
+            notes: self.notes.clone(),
+        }
+    }
+}
+
+/// What an import does with the rules already on the target zone
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RulesImportMode {
+    /// Keep existing rules, skipping imported ones that duplicate them
+    #[default]
+    Merge,
+    /// Remove existing rules of each kind once the imported ones are in place
+    Replace,
+}
+
+/// Kind of rule in an import report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleKind {
+    Firewall,
+    Custom,
+    IpAccess,
+    RateLimit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleImportStatus {
+    Created,
+    /// An identical rule already exists on the target zone
+    Skipped,
+    Failed,
+}
+
+/// Outcome of importing one rule
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleImportResult {
+    pub kind: RuleKind,
+    /// Id on the source zone
+    pub source_id: Option<String>,
+    /// Id on the target zone, if created or already there
+    pub id: Option<String>,
+    pub status: RuleImportStatus,
+    pub error: Option<String>,
+}
+
+impl RuleImportResult {
+    pub fn created(kind: RuleKind, source_id: Option<String>, id: Option<String>) -> Self {
+        Self { kind, source_id, id, status: RuleImportStatus::Created, error: None }
+    }
+
+    /// `id` is the existing rule the imported one duplicates
+    pub fn skipped(kind: RuleKind, source_id: Option<String>, id: Option<String>) -> Self {
+        Self { kind, source_id, id, status: RuleImportStatus::Skipped, error: None }
+    }
+
+    pub fn failed(kind: RuleKind, source_id: Option<String>, error: impl ToString) -> Self {
+        Self { kind, source_id, id: None, status: RuleImportStatus::Failed, error: Some(error.to_string()) }
+    }
+}
+
+/// Per-rule results of an import and the ids they were remapped to
+#[derive(Debug, Clone, Serialize)]
+pub struct RulesImportReport {
+    pub mode: RulesImportMode,
+    pub results: Vec<RuleImportResult>,
+    /// Source zone ids mapped to their target zone equivalents
+    pub id_map: BTreeMap<String, String>,
+}
+
+impl RulesImportReport {
+    pub fn new(mode: RulesImportMode, source_zone_id: &str, target_zone_id: &str) -> Self {
+        let mut id_map = BTreeMap::new();
+        id_map.insert(source_zone_id.to_string(), target_zone_id.to_string());
+        Self { mode, results: Vec::new(), id_map }
+    }
+
+    pub fn push(&mut self, result: RuleImportResult) {
+        if let (Some(source_id), Some(id)) = (&result.source_id, &result.id) {
+            self.id_map.insert(source_id.clone(), id.clone());
+        }
+        self.results.push(result);
+    }
+
+    pub fn count(&self, status: RuleImportStatus) -> usize {
+        self.results.iter().filter(|r| r.status == status).count()
+    }
+}
+
+/// Ruleset rule as it should be sent to the target zone: without its id,
+/// and with any source zone or rule ids in its parameters remapped
+pub fn portable_ruleset_rule(rule: &RulesetRule, id_map: &BTreeMap<String, String>) -> RulesetRule {
+    RulesetRule {
+        id: None,
+        action_parameters: rule.action_parameters.as_ref().map(|params| remap_ids(params, id_map)),
+        ..rule.clone()
+    }
+}
+
+/// Replace string values and object keys that are known source ids
+pub fn remap_ids(value: &serde_json::Value, id_map: &BTreeMap<String, String>) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::String(s) => Value::String(id_map.get(s).cloned().unwrap_or_else(|| s.clone())),
+        Value::Array(items) => Value::Array(items.iter().map(|v| remap_ids(v, id_map)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (id_map.get(k).cloned().unwrap_or_else(|| k.clone()), remap_ids(v, id_map)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Where an imported rule ends up among the rules sent for its phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RulePlacement {
+    /// Sent as a new rule at this index
+    New(usize),
+    /// Skipped as a duplicate of the rule at this index
+    Duplicate(usize),
+}
+
+/// Rules to send for a phase, and where each imported rule ends up in them.
+///
+/// Merging appends imported rules after the existing ones; replacing sends
+/// only the imported rules.
+pub fn plan_ruleset_import(
+    existing: Vec<RulesetRule>,
+    imported: &[RulesetRule],
+    mode: RulesImportMode,
+    id_map: &BTreeMap<String, String>,
+) -> (Vec<RulesetRule>, Vec<RulePlacement>) {
+    let mut rules = match mode {
+        RulesImportMode::Merge => existing,
+        RulesImportMode::Replace => Vec::new(),
+    };
+
+    let positions = imported
+        .iter()
+        .map(|rule| {
+            let rule = portable_ruleset_rule(rule, id_map);
+            if let Some(i) = rules.iter().position(|r| same_ruleset_rule(r, &rule)) {
+                return RulePlacement::Duplicate(i);
+            }
+            rules.push(rule);
+            RulePlacement::New(rules.len() - 1)
+        })
+        .collect();
+
+    (rules, positions)
+}
+
+fn same_ruleset_rule(a: &RulesetRule, b: &RulesetRule) -> bool {
+    a.action == b.action && a.expression.trim() == b.expression.trim()
+}
+
+/// The target zone's equivalent of the firewall rule, if it has one
+pub fn find_firewall_rule<'a>(existing: &'a [FirewallRule], rule: &ExportedFirewallRule) -> Option<&'a FirewallRule> {
+    existing
+        .iter()
+        .find(|r| r.action == rule.action && r.filter.expression.trim() == rule.expression.trim())
+}
+
+/// The target zone's access rule for the IP, range, ASN or country, if it
+/// has one; Cloudflare allows only one per target
+pub fn find_ip_access_rule<'a>(existing: &'a [IpAccessRule], rule: &ExportedIpAccessRule) -> Option<&'a IpAccessRule> {
+    existing
+        .iter()
+        .find(|r| r.configuration.target == rule.target && r.configuration.value == rule.value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, action: &str, expression: &str) -> RulesetRule {
+        RulesetRule {
+            id: Some(id.to_string()),
+            action: action.to_string(),
+            action_parameters: None,
+            expression: expression.to_string(),
+            description: None,
+            enabled: Some(true),
+            ratelimit: None,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_remap_ids_in_values_and_keys() {
+        let id_map = BTreeMap::from([("old-rule".to_string(), "new-rule".to_string())]);
+        let params = serde_json::json!({
+            "ruleset": "current",
+            "rules": { "old-rule": ["old-rule", "other"] }
+        });
+
+        assert_eq!(
+            remap_ids(&params, &id_map),
+            serde_json::json!({
+                "ruleset": "current",
+                "rules": { "new-rule": ["new-rule", "other"] }
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_appends_and_skips_duplicates() {
+        let existing = vec![rule("a", "block", "ip.src eq 192.0.2.1")];
+        let imported = vec![
+            rule("x", "block", " ip.src eq 192.0.2.1 "),
+            rule("y", "managed_challenge", "cf.threat_score gt 10"),
+        ];
+
+        let (rules, positions) = plan_ruleset_import(existing, &imported, RulesImportMode::Merge, &BTreeMap::new());
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].id.as_deref(), Some("a"));
+        assert_eq!(rules[1].id, None);
+        assert_eq!(positions, vec![RulePlacement::Duplicate(0), RulePlacement::New(1)]);
+    }
+
+    #[test]
+    fn test_replace_sends_only_imported_rules() {
+        let existing = vec![rule("a", "block", "ip.src eq 192.0.2.1")];
+        let imported = vec![rule("x", "block", "ip.src eq 192.0.2.1"), rule("y", "log", "true")];
+
+        let (rules, positions) = plan_ruleset_import(existing, &imported, RulesImportMode::Replace, &BTreeMap::new());
+
+        assert_eq!(rules.len(), 2);
+        assert!(rules.iter().all(|r| r.id.is_none()));
+        assert_eq!(positions, vec![RulePlacement::New(0), RulePlacement::New(1)]);
+    }
+}
@@ -3,30 +3,103 @@
 use crate::client::CloudflareClient;
 use crate::error::{CloudflareError, CloudflareResult};
 use crate::models::*;
+use crate::plan::{map_plan_error, Feature};
+use crate::services::notifications::{NotificationEvent, NotificationService};
+use crate::services::rules_export::{
+    find_firewall_rule, find_ip_access_rule, plan_ruleset_import, ExportedFirewallRule, ExportedIpAccessRule,
+    RuleImportResult, RuleImportStatus, RuleKind, RulePlacement, RulesExport, RulesImportMode, RulesImportReport,
+    RULES_EXPORT_VERSION,
+};
+use crate::services::security_events::{
+    fetch_firewall_events, FeedMessage, SecurityEventFeed, MAX_RECENT_EVENTS, RECENT_EVENTS_WINDOW,
+};
+use crate::services::under_attack::{
+    fetch_threat_count, UnderAttackMonitor, UnderAttackThresholds, UnderAttackTransition, DEFAULT_SECURITY_LEVEL,
+    UNDER_ATTACK_LEVEL,
+};
+use chrono::Utc;
 use sqlx::PgPool;
+use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Ruleset phase that executes Cloudflare Managed Rulesets
+const MANAGED_WAF_PHASE: &str = "http_request_firewall_managed";
+
+/// Actions a managed ruleset can be deployed with; `default` keeps each rule's own action
+pub const MANAGED_RULESET_ACTIONS: &[&str] = &["default", "block", "managed_challenge", "js_challenge", "log"];
+
+/// Ruleset phase holding the zone's custom WAF rules
+const CUSTOM_WAF_PHASE: &str = "http_request_firewall_custom";
+
+/// Actions a custom WAF rule can take
+pub const CUSTOM_RULE_ACTIONS: &[&str] = &["block", "challenge", "js_challenge", "managed_challenge", "skip", "log"];
+
+/// Ruleset phase holding the zone's rate limiting rules
+const RATE_LIMIT_PHASE: &str = "http_ratelimit";
+
+/// Setting holding the Under Attack auto-trigger's state
+const UNDER_ATTACK_STATE_SETTING: &str = "under_attack_auto_state";
+
+/// Turnstile token verification endpoint
+pub const TURNSTILE_SITEVERIFY_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+/// Longest token siteverify accepts
+const TURNSTILE_TOKEN_MAX_LEN: usize = 2048;
+/// How long to wait for siteverify before giving up
+const TURNSTILE_VERIFY_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct SecurityService {
     client: Option<Arc<CloudflareClient>>,
-    #[allow(dead_code)]
     db: PgPool,
+    notifications: Option<Arc<NotificationService>>,
+    siteverify_url: String,
+    events: SecurityEventFeed,
 }
 
 impl SecurityService {
     pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
-        Self { client: Some(client), db }
+        Self {
+            client: Some(client),
+            db,
+            notifications: None,
+            siteverify_url: TURNSTILE_SITEVERIFY_URL.to_string(),
+            events: SecurityEventFeed::default(),
+        }
     }
 
     /// Create without a configured client (for initial setup)
     pub fn new_unconfigured(db: PgPool) -> Self {
-        Self { client: None, db }
+        Self {
+            client: None,
+            db,
+            notifications: None,
+            siteverify_url: TURNSTILE_SITEVERIFY_URL.to_string(),
+            events: SecurityEventFeed::default(),
+        }
+    }
+
+    /// Use a feed that outlives this service, so subscribers keep receiving
+    /// events after the plugin reconnects
+    pub fn set_event_feed(&mut self, feed: SecurityEventFeed) {
+        if let Some(client) = &self.client {
+            feed.set_client(Arc::clone(client));
+        }
+        self.events = feed;
+    }
+
+    /// Send webhook notifications for security events
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
     }
 
     /// Get the client or return an error if not configured
     fn get_client(&self) -> CloudflareResult<&CloudflareClient> {
         self.client.as_ref()
             .map(|c| c.as_ref())
-            .ok_or_else(|| CloudflareError::ConfigError("Cloudflare not configured. Please connect your account.".to_string()))
+            .ok_or(CloudflareError::NotConfigured)
     }
 
     pub async fn get_security_level(&self) -> CloudflareResult<String> {
@@ -42,16 +115,355 @@ impl SecurityService {
     }
 
     pub async fn toggle_under_attack(&self, enabled: bool) -> CloudflareResult<()> {
+        let level = if enabled { UNDER_ATTACK_LEVEL } else { DEFAULT_SECURITY_LEVEL };
+        self.switch_security_level(level, NotificationEvent::UnderAttackToggled { enabled }).await
+    }
+
+    async fn switch_security_level(&self, level: &str, event: NotificationEvent) -> CloudflareResult<()> {
         let client = self.get_client()?;
-        client.toggle_under_attack_mode(enabled).await?;
+        client.set_security_level(level).await?;
+        if let Some(notifications) = &self.notifications {
+            notifications.notify_in_background(event);
+        }
         Ok(())
     }
 
+    /// Count recent threats once and let `monitor` decide whether Under
+    /// Attack mode should change, switching it and recording the change.
+    ///
+    /// If Under Attack mode is already on when a spike starts, an admin
+    /// turned it on and the monitor leaves it to them. Otherwise the current
+    /// security level is kept in `monitor` and restored on disengaging. A
+    /// failed switch leaves `monitor` as it was, so the next poll tries again.
+    pub async fn check_threat_rate(
+        &self,
+        monitor: &mut UnderAttackMonitor,
+        thresholds: &UnderAttackThresholds,
+    ) -> CloudflareResult<Option<UnderAttackTransition>> {
+        let client = self.get_client()?;
+        let now = chrono::Utc::now();
+        let threats = fetch_threat_count(client, now - thresholds.window).await?;
+
+        let before = monitor.clone();
+        let Some(transition) = monitor.observe(threats, now, thresholds) else {
+            if *monitor != before {
+                self.save_under_attack_state(monitor).await;
+            }
+            return Ok(None);
+        };
+
+        let level = match transition {
+            UnderAttackTransition::Engaged => match self.get_security_level().await {
+                Ok(level) if level == UNDER_ATTACK_LEVEL => {
+                    monitor.release();
+                    if *monitor != before {
+                        self.save_under_attack_state(monitor).await;
+                    }
+                    return Ok(None);
+                }
+                Ok(level) => {
+                    monitor.set_prior_level(level);
+                    UNDER_ATTACK_LEVEL
+                }
+                Err(e) => {
+                    *monitor = before;
+                    return Err(e);
+                }
+            },
+            UnderAttackTransition::Disengaged => before.prior_level().unwrap_or(DEFAULT_SECURITY_LEVEL),
+        };
+
+        let window_minutes = thresholds.window.num_minutes() as u32;
+        let event = NotificationEvent::UnderAttackAutoToggled {
+            enabled: transition.enabled(),
+            threats,
+            window_minutes,
+        };
+        if let Err(e) = self.switch_security_level(level, event).await {
+            *monitor = before;
+            return Err(e);
+        }
+        self.save_under_attack_state(monitor).await;
+
+        info!(
+            "Under Attack mode {} automatically ({} threats in {} minutes)",
+            if transition.enabled() { "enabled" } else { "disabled" },
+            threats,
+            window_minutes
+        );
+        if let Err(e) = self.record_under_attack_transition(transition, threats, thresholds).await {
+            warn!("Failed to record Under Attack auto-trigger event: {}", e);
+        }
+        Ok(Some(transition))
+    }
+
+    /// Auto-trigger state saved by the last check, so a restart doesn't
+    /// forget an engagement or the security level to restore
+    pub async fn load_under_attack_state(&self) -> CloudflareResult<UnderAttackMonitor> {
+        let row: Option<(Option<serde_json::Value>,)> =
+            sqlx::query_as(r#"SELECT value FROM cloudflare_settings WHERE key = $1"#)
+                .bind(UNDER_ATTACK_STATE_SETTING)
+                .fetch_optional(&self.db)
+                .await
+                .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        match row.and_then(|(value,)| value) {
+            Some(value) => serde_json::from_value(value).map_err(|e| CloudflareError::ConfigError(e.to_string())),
+            None => Ok(UnderAttackMonitor::default()),
+        }
+    }
+
+    /// Forget an engagement, handing Under Attack mode back to the admin
+    pub async fn release_under_attack(&self, monitor: &mut UnderAttackMonitor) {
+        if *monitor != UnderAttackMonitor::default() {
+            monitor.release();
+            self.save_under_attack_state(monitor).await;
+        }
+    }
+
+    async fn save_under_attack_state(&self, monitor: &UnderAttackMonitor) {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO cloudflare_settings (key, value, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (key) DO UPDATE SET value = $2, updated_at = NOW()
+            "#,
+        )
+        .bind(UNDER_ATTACK_STATE_SETTING)
+        .bind(serde_json::json!(monitor))
+        .execute(&self.db)
+        .await;
+
+        if let Err(e) = result {
+            warn!("Failed to save Under Attack auto-trigger state: {}", e);
+        }
+    }
+
+    /// Log an auto-trigger change to the security events table
+    async fn record_under_attack_transition(
+        &self,
+        transition: UnderAttackTransition,
+        threats: u64,
+        thresholds: &UnderAttackThresholds,
+    ) -> CloudflareResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cloudflare_security_events (event_type, action, details, created_at)
+            VALUES ('under_attack_auto', $1, $2, NOW())
+            "#,
+        )
+        .bind(if transition.enabled() { "enabled" } else { "disabled" })
+        .bind(serde_json::json!({
+            "transition": transition,
+            "threats": threats,
+            "threshold": thresholds.threshold,
+            "window_minutes": thresholds.window.num_minutes(),
+            "cooldown_minutes": thresholds.cooldown.num_minutes(),
+        }))
+        .execute(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Up to `limit` (at most 1000) firewall events from the last 24 hours, newest first.
+    ///
+    /// Fails with `PermissionDenied` if the zone's plan lacks the dataset.
+    pub async fn get_recent_events(&self, limit: u32) -> CloudflareResult<Vec<SecurityEvent>> {
+        let client = self.get_client()?;
+        let limit = limit.clamp(1, MAX_RECENT_EVENTS);
+        fetch_firewall_events(client, chrono::Utc::now() - RECENT_EVENTS_WINDOW, limit).await
+    }
+
+    /// Receive firewall events as they happen.
+    ///
+    /// Subscribers share one poller, which stops once all of them have dropped
+    /// their receivers. If the zone's plan lacks the dataset the feed sends
+    /// `FeedMessage::Closed` and ends.
+    pub fn subscribe_events(&self) -> CloudflareResult<broadcast::Receiver<FeedMessage>> {
+        let client = self.client.clone().ok_or(CloudflareError::NotConfigured)?;
+        Ok(self.events.subscribe(client))
+    }
+
     pub async fn list_waf_rules(&self) -> CloudflareResult<Vec<WafRule>> {
         let client = self.get_client()?;
         client.list_waf_rules().await
     }
 
+    /// Managed rulesets available for the WAF, and whether each is deployed
+    pub async fn list_managed_rulesets(&self) -> CloudflareResult<Vec<ManagedRuleset>> {
+        let client = self.get_client()?;
+        let rulesets = client.list_zone_rulesets().await?;
+        let deployed = client.get_phase_rules(MANAGED_WAF_PHASE).await?;
+
+        Ok(rulesets
+            .into_iter()
+            .filter(|r| r.kind.as_deref() == Some("managed") && r.phase.as_deref() == Some(MANAGED_WAF_PHASE))
+            .map(|r| {
+                let rule = deployed.iter().find(|rule| executes_ruleset(rule, &r.id));
+                ManagedRuleset {
+                    deployed: rule.is_some_and(|rule| rule.enabled != Some(false)),
+                    action: rule.and_then(override_action),
+                    id: r.id,
+                    name: r.name,
+                    description: r.description,
+                    version: r.version,
+                    last_updated: r.last_updated,
+                }
+            })
+            .collect())
+    }
+
+    /// Execute a managed ruleset from the zone's managed WAF phase.
+    ///
+    /// Replaces the existing execute rule for the ruleset if there is one, and
+    /// leaves the phase's other rules alone.
+    pub async fn deploy_managed_ruleset(&self, ruleset_id: &str, action: &str) -> CloudflareResult<Ruleset> {
+        let client = self.get_client()?;
+        let rule = managed_execute_rule(ruleset_id, action)?;
+        let rules = client.get_phase_rules(MANAGED_WAF_PHASE).await?;
+
+        let ruleset = client
+            .update_phase_ruleset(MANAGED_WAF_PHASE, upsert_execute_rule(rules, rule))
+            .await
+            .map_err(|e| map_plan_error(e, Feature::WafManagedRules))?;
+        info!("Deployed managed ruleset {} with action {}", ruleset_id, action);
+        Ok(ruleset)
+    }
+
+    /// Custom WAF rules, in the order they run
+    pub async fn list_custom_rules(&self) -> CloudflareResult<Vec<RulesetRule>> {
+        let client = self.get_client()?;
+        client.get_phase_rules(CUSTOM_WAF_PHASE).await
+    }
+
+    /// Add a custom WAF rule after the existing ones.
+    ///
+    /// Rules are written one at a time through the ruleset's rule endpoints,
+    /// so changes made elsewhere to the other rules are never overwritten.
+    pub async fn create_custom_rule(
+        &self,
+        expression: &str,
+        action: &str,
+        description: Option<String>,
+    ) -> CloudflareResult<RulesetRule> {
+        let client = self.get_client()?;
+        let rule = custom_rule(expression, action, description)?;
+        let ruleset = match client.find_phase_ruleset(CUSTOM_WAF_PHASE).await? {
+            Some(ruleset) => client.create_ruleset_rule(&ruleset.id, &rule).await?,
+            // The first rule creates the phase's entrypoint ruleset
+            None => client.update_phase_ruleset(CUSTOM_WAF_PHASE, vec![rule.clone()]).await?,
+        };
+        info!("Created custom WAF rule with action {}", action);
+        Ok(ruleset.rules.into_iter().last().unwrap_or(rule))
+    }
+
+    /// Change a custom WAF rule's expression, action and description, keeping its place
+    pub async fn update_custom_rule(
+        &self,
+        id: &str,
+        expression: &str,
+        action: &str,
+        description: Option<String>,
+    ) -> CloudflareResult<RulesetRule> {
+        let client = self.get_client()?;
+        let rule = custom_rule(expression, action, description)?;
+        let (ruleset, existing) = self.find_custom_rule(id).await?;
+        let rule = RulesetRule { enabled: existing.enabled, ..rule };
+
+        let ruleset = client.update_ruleset_rule(&ruleset.id, id, &rule).await?;
+        info!("Updated custom WAF rule {}", id);
+        ruleset
+            .rules
+            .into_iter()
+            .find(|r| r.id.as_deref() == Some(id))
+            .ok_or_else(|| CloudflareError::NotFound(format!("Custom rule {}", id)))
+    }
+
+    /// Remove a custom WAF rule
+    pub async fn delete_custom_rule(&self, id: &str) -> CloudflareResult<()> {
+        let client = self.get_client()?;
+        let (ruleset, _) = self.find_custom_rule(id).await?;
+        client.delete_ruleset_rule(&ruleset.id, id).await?;
+        info!("Deleted custom WAF rule {}", id);
+        Ok(())
+    }
+
+    /// Custom WAF entrypoint ruleset and its rule `id`
+    async fn find_custom_rule(&self, id: &str) -> CloudflareResult<(Ruleset, RulesetRule)> {
+        let not_found = || CloudflareError::NotFound(format!("Custom rule {}", id));
+        let ruleset = self
+            .get_client()?
+            .find_phase_ruleset(CUSTOM_WAF_PHASE)
+            .await?
+            .ok_or_else(not_found)?;
+        let rule = ruleset
+            .rules
+            .iter()
+            .find(|r| r.id.as_deref() == Some(id))
+            .cloned()
+            .ok_or_else(not_found)?;
+        Ok((ruleset, rule))
+    }
+
+    /// Bot management settings, and whether the zone's plan offers Bot Fight
+    /// Mode or Super Bot Fight Mode
+    pub async fn get_bot_management(&self) -> CloudflareResult<BotProtection> {
+        let client = self.get_client()?;
+        let settings = client.get_bot_management().await?;
+        let mode = bot_protection_mode(client.get_zone().await?.plan.as_ref());
+        Ok(BotProtection { mode, settings })
+    }
+
+    /// Turn Bot Fight Mode on or off
+    pub async fn set_bot_fight_mode(&self, enabled: bool) -> CloudflareResult<BotManagement> {
+        self.update_bot_management(UpdateBotManagement {
+            fight_mode: Some(enabled),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Change bot management settings.
+    ///
+    /// Super Bot Fight Mode settings need a paid plan; on a Free zone they
+    /// fail with `PermissionDenied` before anything is sent.
+    pub async fn update_bot_management(&self, update: UpdateBotManagement) -> CloudflareResult<BotManagement> {
+        let client = self.get_client()?;
+        for (field, action) in [
+            ("sbfm_definitely_automated", &update.sbfm_definitely_automated),
+            ("sbfm_likely_automated", &update.sbfm_likely_automated),
+            ("sbfm_verified_bots", &update.sbfm_verified_bots),
+        ] {
+            if let Some(action) = action.as_deref().filter(|a| !SUPER_BOT_FIGHT_ACTIONS.contains(a)) {
+                return Err(CloudflareError::ValidationError(format!(
+                    "Invalid {} action '{}', expected one of: {}",
+                    field,
+                    action,
+                    SUPER_BOT_FIGHT_ACTIONS.join(", ")
+                )));
+            }
+        }
+
+        if update.changes_super_bot_fight_mode() {
+            let plan = client.get_zone().await?.plan;
+            if bot_protection_mode(plan.as_ref()) == BotProtectionMode::BotFightMode {
+                return Err(CloudflareError::PermissionDenied(format!(
+                    "Super Bot Fight Mode needs a Pro, Business or Enterprise plan; this zone is on {}. Use Bot Fight Mode instead.",
+                    plan.map(|p| p.name).unwrap_or_else(|| "the Free plan".to_string())
+                )));
+            }
+        }
+
+        let updated = client
+            .update_bot_management(&update)
+            .await
+            .map_err(|e| map_plan_error(e, Feature::SuperBotFightMode))?;
+        info!("Updated bot management settings");
+        Ok(updated)
+    }
+
     pub async fn list_firewall_rules(&self) -> CloudflareResult<Vec<FirewallRule>> {
         let client = self.get_client()?;
         client.list_firewall_rules().await
@@ -62,6 +474,156 @@ impl SecurityService {
         client.create_firewall_rule(rule).await
     }
 
+    pub async fn delete_firewall_rule(&self, id: &str) -> CloudflareResult<()> {
+        let client = self.get_client()?;
+        client.delete_firewall_rule(id).await?;
+        info!("Deleted firewall rule {}", id);
+        Ok(())
+    }
+
+    /// List the account's IP Lists
+    pub async fn list_ip_lists(&self) -> CloudflareResult<Vec<IpList>> {
+        let client = self.get_client()?;
+        client.list_ip_lists().await
+    }
+
+    /// Create an IP List that firewall expressions can reference as `$name`
+    pub async fn create_ip_list(&self, name: &str, description: Option<String>) -> CloudflareResult<IpList> {
+        let client = self.get_client()?;
+        validate_list_name(name)?;
+        client
+            .create_ip_list(CreateIpList {
+                name: name.to_string(),
+                kind: "ip".to_string(),
+                description,
+            })
+            .await
+    }
+
+    /// List the items in an IP List
+    pub async fn list_ip_list_items(&self, list_id: &str) -> CloudflareResult<Vec<IpListItem>> {
+        let client = self.get_client()?;
+        client.list_ip_list_items(list_id).await
+    }
+
+    /// Add IPs or CIDRs to an IP List, rejecting the whole batch if any item is invalid.
+    ///
+    /// Returns the operation to poll with `get_bulk_operation_status`.
+    pub async fn add_ip_list_items(
+        &self,
+        list_id: &str,
+        items: Vec<CreateIpListItem>,
+    ) -> CloudflareResult<BulkOperation> {
+        let client = self.get_client()?;
+        validate_ip_list_items(&items)?;
+        let operation = client.add_ip_list_items(list_id, &items).await?;
+        info!("Adding {} items to IP list {} (operation {})", items.len(), list_id, operation.operation_id);
+        Ok(operation)
+    }
+
+    /// Remove items from an IP List by item ID
+    pub async fn remove_ip_list_items(&self, list_id: &str, item_ids: Vec<String>) -> CloudflareResult<BulkOperation> {
+        let client = self.get_client()?;
+        if item_ids.is_empty() {
+            return Err(CloudflareError::ValidationError("No IP list items to remove".to_string()));
+        }
+        let operation = client.remove_ip_list_items(list_id, &item_ids).await?;
+        info!("Removing {} items from IP list {} (operation {})", item_ids.len(), list_id, operation.operation_id);
+        Ok(operation)
+    }
+
+    /// Status of an asynchronous IP List operation
+    pub async fn get_bulk_operation_status(&self, operation_id: &str) -> CloudflareResult<BulkOperationStatus> {
+        let client = self.get_client()?;
+        client.get_bulk_operation_status(operation_id).await
+    }
+
+    /// List Turnstile widgets, never including their secrets
+    pub async fn list_turnstile_widgets(&self) -> CloudflareResult<Vec<TurnstileWidget>> {
+        let client = self.get_client()?;
+        let widgets = client.list_turnstile_widgets().await?;
+        Ok(widgets
+            .into_iter()
+            .map(|widget| TurnstileWidget { secret: None, ..widget })
+            .collect())
+    }
+
+    /// Create a Turnstile widget, returning it with its secret
+    pub async fn create_turnstile_widget(&self, widget: CreateTurnstileWidget) -> CloudflareResult<TurnstileWidget> {
+        let client = self.get_client()?;
+        validate_turnstile_widget(&widget)?;
+        let created = client.create_turnstile_widget(&widget).await?;
+        info!("Created Turnstile widget {} ({})", created.name, created.sitekey);
+        Ok(created)
+    }
+
+    /// Rotate a Turnstile widget's secret, returning it with the new secret
+    pub async fn rotate_turnstile_secret(
+        &self,
+        sitekey: &str,
+        invalidate_immediately: bool,
+    ) -> CloudflareResult<TurnstileWidget> {
+        let client = self.get_client()?;
+        let widget = client.rotate_turnstile_secret(sitekey, invalidate_immediately).await?;
+        info!("Rotated secret for Turnstile widget {}", sitekey);
+        Ok(widget)
+    }
+
+    /// Delete a Turnstile widget
+    pub async fn delete_turnstile_widget(&self, sitekey: &str) -> CloudflareResult<()> {
+        let client = self.get_client()?;
+        client.delete_turnstile_widget(sitekey).await?;
+        info!("Deleted Turnstile widget {}", sitekey);
+        Ok(())
+    }
+
+    /// Verify a Turnstile token submitted with a form.
+    ///
+    /// Doesn't need a connected account, only the widget's secret. A token
+    /// Turnstile rejects is an `Ok` result with `success: false`; errors are
+    /// reserved for bad input and siteverify being unreachable.
+    pub async fn verify_turnstile(
+        &self,
+        token: &str,
+        remote_ip: Option<&str>,
+        secret: &str,
+    ) -> CloudflareResult<TurnstileVerification> {
+        let token = token.trim();
+        if token.is_empty() || token.len() > TURNSTILE_TOKEN_MAX_LEN {
+            return Err(CloudflareError::ValidationError(format!(
+                "Turnstile token must be 1 to {} characters",
+                TURNSTILE_TOKEN_MAX_LEN
+            )));
+        }
+        if secret.trim().is_empty() {
+            return Err(CloudflareError::ValidationError("Turnstile secret is required".to_string()));
+        }
+
+        let mut form = vec![("secret", secret.trim()), ("response", token)];
+        if let Some(ip) = remote_ip.filter(|ip| !ip.is_empty()) {
+            form.push(("remoteip", ip));
+        }
+
+        let response = reqwest::Client::new()
+            .post(&self.siteverify_url)
+            .timeout(TURNSTILE_VERIFY_TIMEOUT)
+            .form(&form)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(CloudflareError::ServiceUnavailable(format!(
+                "Turnstile siteverify returned {}",
+                response.status()
+            )));
+        }
+
+        let verification: TurnstileVerification = response.json().await?;
+        if !verification.success {
+            info!("Turnstile token rejected: {}", verification.error_codes.join(", "));
+        }
+        Ok(verification)
+    }
+
     pub async fn list_ip_access_rules(&self) -> CloudflareResult<Vec<IpAccessRule>> {
         let client = self.get_client()?;
         client.list_ip_access_rules().await
@@ -90,4 +652,1241 @@ impl SecurityService {
             notes: note.map(|s| s.to_string()),
         }).await
     }
+
+    pub async fn delete_ip_access_rule(&self, id: &str) -> CloudflareResult<()> {
+        let client = self.get_client()?;
+        client.delete_ip_access_rule(id).await?;
+        info!("Deleted IP access rule {}", id);
+        Ok(())
+    }
+
+    /// The zone's firewall, custom WAF, IP access and rate limiting rules as
+    /// a document another zone can import. Access rules inherited from the
+    /// account or user are left out.
+    pub async fn export_rules(&self) -> CloudflareResult<RulesExport> {
+        let client = self.get_client()?;
+        let (firewall_rules, custom_rules, ip_access_rules, rate_limit_rules) = futures::try_join!(
+            client.list_firewall_rules(),
+            client.get_phase_rules(CUSTOM_WAF_PHASE),
+            client.list_ip_access_rules(),
+            client.get_phase_rules(RATE_LIMIT_PHASE),
+        )?;
+
+        Ok(RulesExport {
+            version: RULES_EXPORT_VERSION,
+            source_zone_id: client.zone_id().to_string(),
+            exported_at: Utc::now(),
+            firewall_rules: firewall_rules.into_iter().map(Into::into).collect(),
+            custom_rules,
+            ip_access_rules: ip_access_rules
+                .into_iter()
+                .filter(|rule| rule.scope.scope_type == "zone")
+                .map(Into::into)
+                .collect(),
+            rate_limit_rules,
+        })
+    }
+
+    /// Recreate exported rules on this zone, reporting what happened to each.
+    ///
+    /// Firewall and IP access rules are created one at a time, so a rule the
+    /// zone rejects doesn't stop the others; custom and rate limiting rules
+    /// are written per phase and succeed or fail together. Source zone ids,
+    /// and ids of rules already imported, are remapped in rule parameters.
+    pub async fn import_rules(
+        &self,
+        export: &RulesExport,
+        mode: RulesImportMode,
+    ) -> CloudflareResult<RulesImportReport> {
+        let client = self.get_client()?;
+        if export.version > RULES_EXPORT_VERSION {
+            return Err(CloudflareError::ValidationError(format!(
+                "Unsupported rules export version {}, expected {} or older",
+                export.version, RULES_EXPORT_VERSION
+            )));
+        }
+        let mut report = RulesImportReport::new(mode, &export.source_zone_id, client.zone_id());
+
+        import_firewall_rules(client, &export.firewall_rules, &mut report).await;
+        import_ip_access_rules(client, &export.ip_access_rules, &mut report).await;
+        import_phase_rules(client, CUSTOM_WAF_PHASE, RuleKind::Custom, &export.custom_rules, &mut report).await;
+        import_phase_rules(client, RATE_LIMIT_PHASE, RuleKind::RateLimit, &export.rate_limit_rules, &mut report).await;
+
+        info!(
+            "Imported security rules from zone {}: {} created, {} skipped, {} failed",
+            export.source_zone_id,
+            report.count(RuleImportStatus::Created),
+            report.count(RuleImportStatus::Skipped),
+            report.count(RuleImportStatus::Failed)
+        );
+        Ok(report)
+    }
+}
+
+/// Create imported firewall rules and record each one's outcome.
+///
+/// Replacing removes the zone's other rules only once every imported rule is
+/// in place, so a failed create never leaves the zone with fewer rules.
+async fn import_firewall_rules(
+    client: &CloudflareClient,
+    imported: &[ExportedFirewallRule],
+    report: &mut RulesImportReport,
+) {
+    let kind = RuleKind::Firewall;
+    let mut rules = match client.list_firewall_rules().await {
+        Ok(rules) => rules,
+        Err(e) => return fail_import(report, kind, imported.iter().map(|r| Some(r.id.clone())), &e),
+    };
+
+    let mut kept = Vec::new();
+    let mut all_imported = true;
+    for rule in imported {
+        let source_id = Some(rule.id.clone());
+        if let Some(existing) = find_firewall_rule(&rules, rule) {
+            kept.push(existing.id.clone());
+            report.push(RuleImportResult::skipped(kind, source_id, Some(existing.id.clone())));
+            continue;
+        }
+        match client.create_firewall_rule(rule.to_create()).await {
+            Ok(created) => {
+                kept.push(created.id.clone());
+                report.push(RuleImportResult::created(kind, source_id, Some(created.id.clone())));
+                rules.push(created);
+            }
+            Err(e) => {
+                all_imported = false;
+                report.push(RuleImportResult::failed(kind, source_id, e));
+            }
+        }
+    }
+
+    if report.mode != RulesImportMode::Replace {
+        return;
+    }
+    if !all_imported {
+        warn!("Keeping the zone's existing firewall rules since some imported rules failed");
+        return;
+    }
+    for rule in rules.iter().filter(|rule| !kept.contains(&rule.id)) {
+        if let Err(e) = client.delete_firewall_rule(&rule.id).await {
+            report.push(RuleImportResult::failed(kind, None, format!("Failed to remove rule {}: {}", rule.id, e)));
+        }
+    }
+}
+
+/// Create imported IP access rules and record each one's outcome.
+///
+/// Like firewall rules, replacing removes the zone's other rules only once
+/// every imported rule is in place. Account-level rules are never removed.
+async fn import_ip_access_rules(
+    client: &CloudflareClient,
+    imported: &[ExportedIpAccessRule],
+    report: &mut RulesImportReport,
+) {
+    let kind = RuleKind::IpAccess;
+    let mut rules = match client.list_ip_access_rules().await {
+        Ok(rules) => rules,
+        Err(e) => return fail_import(report, kind, imported.iter().map(|r| Some(r.id.clone())), &e),
+    };
+
+    let mut kept = Vec::new();
+    let mut all_imported = true;
+    for rule in imported {
+        let source_id = Some(rule.id.clone());
+        if let Some(existing) = find_ip_access_rule(&rules, rule) {
+            kept.push(existing.id.clone());
+            report.push(RuleImportResult::skipped(kind, source_id, Some(existing.id.clone())));
+            continue;
+        }
+        match client.create_ip_access_rule(rule.to_create()).await {
+            Ok(created) => {
+                kept.push(created.id.clone());
+                report.push(RuleImportResult::created(kind, source_id, Some(created.id.clone())));
+                rules.push(created);
+            }
+            Err(e) => {
+                all_imported = false;
+                report.push(RuleImportResult::failed(kind, source_id, e));
+            }
+        }
+    }
+
+    if report.mode != RulesImportMode::Replace {
+        return;
+    }
+    if !all_imported {
+        warn!("Keeping the zone's existing IP access rules since some imported rules failed");
+        return;
+    }
+    let stale = rules
+        .iter()
+        .filter(|rule| rule.scope.scope_type == "zone" && !kept.contains(&rule.id));
+    for rule in stale {
+        if let Err(e) = client.delete_ip_access_rule(&rule.id).await {
+            report.push(RuleImportResult::failed(kind, None, format!("Failed to remove rule {}: {}", rule.id, e)));
+        }
+    }
+}
+
+/// Write imported rules to a ruleset phase and record each one's outcome
+async fn import_phase_rules(
+    client: &CloudflareClient,
+    phase: &str,
+    kind: RuleKind,
+    imported: &[RulesetRule],
+    report: &mut RulesImportReport,
+) {
+    let existing = match client.get_phase_rules(phase).await {
+        Ok(rules) => rules,
+        Err(e) => return fail_import(report, kind, imported.iter().map(|r| r.id.clone()), &e),
+    };
+    if imported.is_empty() && (report.mode == RulesImportMode::Merge || existing.is_empty()) {
+        return;
+    }
+
+    let (rules, placements) = plan_ruleset_import(existing, imported, report.mode, &report.id_map);
+    let written = if report.mode == RulesImportMode::Merge
+        && placements.iter().all(|p| matches!(p, RulePlacement::Duplicate(_)))
+    {
+        Ok(Vec::new())
+    } else {
+        client.update_phase_ruleset(phase, rules.clone()).await.map(|ruleset| ruleset.rules)
+    };
+    let written = written.map_err(|e| match kind {
+        RuleKind::RateLimit => map_plan_error(e, Feature::RateLimiting),
+        _ => e,
+    });
+    // Rules already on the zone keep their ids when nothing is written
+    let target_id = |i: usize| {
+        written
+            .as_ref()
+            .ok()
+            .and_then(|rules| rules.get(i))
+            .or_else(|| rules.get(i))
+            .and_then(|rule| rule.id.clone())
+    };
+
+    for (rule, placement) in imported.iter().zip(placements) {
+        let source_id = rule.id.clone();
+        let result = match (placement, &written) {
+            (RulePlacement::Duplicate(i), _) => RuleImportResult::skipped(kind, source_id, target_id(i)),
+            (RulePlacement::New(i), Ok(_)) => RuleImportResult::created(kind, source_id, target_id(i)),
+            (RulePlacement::New(_), Err(e)) => RuleImportResult::failed(kind, source_id, e),
+        };
+        report.push(result);
+    }
+}
+
+/// Record every rule of a kind as failed when the zone's rules can't be read
+fn fail_import(
+    report: &mut RulesImportReport,
+    kind: RuleKind,
+    source_ids: impl Iterator<Item = Option<String>>,
+    error: &CloudflareError,
+) {
+    warn!("Failed to read the zone's {:?} rules for import: {}", kind, error);
+    for source_id in source_ids {
+        report.push(RuleImportResult::failed(kind, source_id, error));
+    }
+}
+
+/// Check a Turnstile widget has a name, at least one domain and a known mode
+fn validate_turnstile_widget(widget: &CreateTurnstileWidget) -> CloudflareResult<()> {
+    let name = widget.name.trim();
+    if name.is_empty() || name.len() > 254 {
+        return Err(CloudflareError::ValidationError(
+            "Turnstile widget name must be 1 to 254 characters".to_string(),
+        ));
+    }
+    if widget.domains.is_empty() || widget.domains.iter().any(|d| d.trim().is_empty()) {
+        return Err(CloudflareError::ValidationError(
+            "Turnstile widgets need at least one domain".to_string(),
+        ));
+    }
+    if !TURNSTILE_MODES.contains(&widget.mode.as_str()) {
+        return Err(CloudflareError::ValidationError(format!(
+            "Invalid Turnstile mode '{}': use one of {}",
+            widget.mode,
+            TURNSTILE_MODES.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// List names may only use lowercase letters, digits and underscores
+pub(crate) fn validate_list_name(name: &str) -> CloudflareResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 50
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(CloudflareError::ValidationError(format!(
+            "Invalid list name '{}': use up to 50 lowercase letters, digits and underscores",
+            name
+        )))
+    }
+}
+
+/// Check every item is an IP address or a CIDR range Cloudflare accepts
+/// (IPv4 /8 to /32, IPv6 /12 to /64), reporting all invalid items at once
+pub fn validate_ip_list_items(items: &[CreateIpListItem]) -> CloudflareResult<()> {
+    if items.is_empty() {
+        return Err(CloudflareError::ValidationError("No IP list items to add".to_string()));
+    }
+
+    let invalid: Vec<&str> = items
+        .iter()
+        .map(|item| item.ip.as_str())
+        .filter(|ip| !is_valid_ip_or_cidr(ip))
+        .collect();
+
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(CloudflareError::ValidationError(format!(
+            "Invalid IP address or CIDR: {}",
+            invalid.join(", ")
+        )))
+    }
+}
+
+fn is_valid_ip_or_cidr(value: &str) -> bool {
+    let Some((addr, prefix)) = value.split_once('/') else {
+        return value.parse::<IpAddr>().is_ok();
+    };
+
+    match (addr.parse::<IpAddr>(), prefix.parse::<u8>()) {
+        (Ok(IpAddr::V4(_)), Ok(prefix)) => (8..=32).contains(&prefix),
+        (Ok(IpAddr::V6(_)), Ok(prefix)) => (12..=64).contains(&prefix),
+        _ => false,
+    }
+}
+
+/// Rule executing a managed ruleset, overriding every rule's action unless `action` is `default`
+pub fn managed_execute_rule(ruleset_id: &str, action: &str) -> CloudflareResult<RulesetRule> {
+    if ruleset_id.trim().is_empty() {
+        return Err(CloudflareError::ValidationError("Managed ruleset ID is required".to_string()));
+    }
+    if !MANAGED_RULESET_ACTIONS.contains(&action) {
+        return Err(CloudflareError::ValidationError(format!(
+            "Invalid managed ruleset action '{}', expected one of: {}",
+            action,
+            MANAGED_RULESET_ACTIONS.join(", ")
+        )));
+    }
+
+    let mut action_parameters = serde_json::json!({ "id": ruleset_id });
+    if action != "default" {
+        action_parameters["overrides"] = serde_json::json!({ "action": action });
+    }
+
+    Ok(RulesetRule {
+        id: None,
+        action: "execute".to_string(),
+        action_parameters: Some(action_parameters),
+        expression: "true".to_string(),
+        description: Some(format!("Execute managed ruleset {}", ruleset_id)),
+        enabled: Some(true),
+        ratelimit: None,
+        extra: Default::default(),
+    })
+}
+
+/// Bot protection a plan offers; zones whose plan is unknown are assumed to be on Free
+fn bot_protection_mode(plan: Option<&Plan>) -> BotProtectionMode {
+    match plan {
+        Some(plan) if !plan.is_free() => BotProtectionMode::SuperBotFightMode,
+        _ => BotProtectionMode::BotFightMode,
+    }
+}
+
+/// Custom WAF rule running `action` on requests matching `expression`.
+///
+/// `skip` rules skip the rest of the custom rules.
+pub fn custom_rule(expression: &str, action: &str, description: Option<String>) -> CloudflareResult<RulesetRule> {
+    let expression = expression.trim();
+    if expression.is_empty() {
+        return Err(CloudflareError::ValidationError("Rule expression is required".to_string()));
+    }
+    if !CUSTOM_RULE_ACTIONS.contains(&action) {
+        return Err(CloudflareError::ValidationError(format!(
+            "Invalid custom rule action '{}', expected one of: {}",
+            action,
+            CUSTOM_RULE_ACTIONS.join(", ")
+        )));
+    }
+
+    Ok(RulesetRule {
+        id: None,
+        action: action.to_string(),
+        action_parameters: (action == "skip").then(|| serde_json::json!({ "ruleset": "current" })),
+        expression: expression.to_string(),
+        description: description.map(|d| d.trim().to_string()).filter(|d| !d.is_empty()),
+        enabled: Some(true),
+        ratelimit: None,
+        extra: Default::default(),
+    })
+}
+
+/// Replace the rule executing the same ruleset, or append it
+fn upsert_execute_rule(mut rules: Vec<RulesetRule>, rule: RulesetRule) -> Vec<RulesetRule> {
+    let ruleset_id = rule
+        .action_parameters
+        .as_ref()
+        .and_then(|p| p.get("id"))
+        .and_then(|id| id.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    match rules.iter_mut().find(|r| executes_ruleset(r, &ruleset_id)) {
+        Some(existing) => {
+            *existing = RulesetRule { id: existing.id.clone(), ..rule };
+        }
+        None => rules.push(rule),
+    }
+    rules
+}
+
+/// Whether a rule executes the given ruleset
+fn executes_ruleset(rule: &RulesetRule, ruleset_id: &str) -> bool {
+    rule.action == "execute"
+        && rule
+            .action_parameters
+            .as_ref()
+            .and_then(|p| p.get("id"))
+            .and_then(|id| id.as_str())
+            == Some(ruleset_id)
+}
+
+/// Action override of an execute rule
+fn override_action(rule: &RulesetRule) -> Option<String> {
+    rule.action_parameters
+        .as_ref()?
+        .get("overrides")?
+        .get("action")?
+        .as_str()
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{lazy_pool, mock_client, mock_service, success, zone_client};
+
+    const MANAGED_RULESET: &str = "efb7b8c949ac4650a09736fc376e9aee";
+
+    #[test]
+    fn test_managed_execute_rule_body() {
+        let rule = managed_execute_rule(MANAGED_RULESET, "log").unwrap();
+        assert_eq!(
+            serde_json::to_value(&rule).unwrap(),
+            serde_json::json!({
+                "action": "execute",
+                "action_parameters": {
+                    "id": MANAGED_RULESET,
+                    "overrides": { "action": "log" }
+                },
+                "expression": "true",
+                "description": format!("Execute managed ruleset {}", MANAGED_RULESET),
+                "enabled": true
+            })
+        );
+
+        let rule = managed_execute_rule(MANAGED_RULESET, "default").unwrap();
+        assert_eq!(rule.action_parameters, Some(serde_json::json!({ "id": MANAGED_RULESET })));
+
+        assert!(managed_execute_rule(MANAGED_RULESET, "explode").is_err());
+        assert!(managed_execute_rule("", "block").is_err());
+    }
+
+    #[test]
+    fn test_deploying_again_replaces_existing_execute_rule() {
+        let mut existing = managed_execute_rule(MANAGED_RULESET, "log").unwrap();
+        existing.id = Some("rule-1".to_string());
+        let other = managed_execute_rule("4814384a9e5d4991b9815dcfc25d2f1f", "default").unwrap();
+
+        let rules = upsert_execute_rule(
+            vec![existing, other],
+            managed_execute_rule(MANAGED_RULESET, "block").unwrap(),
+        );
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].id.as_deref(), Some("rule-1"));
+        assert_eq!(override_action(&rules[0]).as_deref(), Some("block"));
+        assert_eq!(override_action(&rules[1]), None);
+    }
+
+    fn ruleset_response(rules: serde_json::Value) -> wiremock::ResponseTemplate {
+        success(serde_json::json!({ "id": "entry", "phase": CUSTOM_WAF_PHASE, "rules": rules }))
+    }
+
+    fn zone_on_plan(legacy_id: &str, name: &str) -> wiremock::ResponseTemplate {
+        success(serde_json::json!({
+            "id": "zone", "name": "example.com", "status": "active", "paused": false,
+            "development_mode": 0, "type": "full", "name_servers": [],
+            "plan": {
+                "id": "plan-id", "name": name, "price": 0, "currency": "USD", "frequency": "",
+                "is_subscribed": true, "can_subscribe": false, "legacy_id": legacy_id
+            }
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_get_bot_management_reports_settings_and_mode() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/bot_management"))
+            .respond_with(success(serde_json::json!({
+                "enable_js": true,
+                "fight_mode": false,
+                "sbfm_definitely_automated": "block",
+                "sbfm_likely_automated": "managed_challenge",
+                "sbfm_verified_bots": "allow",
+                "sbfm_static_resource_protection": false,
+                "optimize_wordpress": true,
+                "using_latest_model": true
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .respond_with(zone_on_plan("pro", "Pro Website"))
+            .mount(&server)
+            .await;
+
+        let bots = mock_service(&server, SecurityService::new).get_bot_management().await.unwrap();
+        assert_eq!(bots.mode, BotProtectionMode::SuperBotFightMode);
+        assert!(!bots.settings.fight_mode);
+        assert_eq!(bots.settings.sbfm_likely_automated.as_deref(), Some("managed_challenge"));
+        assert_eq!(bots.settings.optimize_wordpress, Some(true));
+
+        let json = serde_json::to_value(&bots).unwrap();
+        assert_eq!(json["mode"], "super_bot_fight_mode");
+        assert_eq!(json["sbfm_definitely_automated"], "block");
+    }
+
+    #[tokio::test]
+    async fn test_super_bot_fight_settings_need_a_paid_plan() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .respond_with(zone_on_plan("free", "Free Website"))
+            .mount(&server)
+            .await;
+        // Only the Bot Fight Mode toggle reaches Cloudflare
+        Mock::given(method("PUT"))
+            .and(path("/zones/zone/bot_management"))
+            .and(body_json(serde_json::json!({ "fight_mode": true })))
+            .respond_with(success(serde_json::json!({ "fight_mode": true, "enable_js": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let security = mock_service(&server, SecurityService::new);
+        let err = security
+            .update_bot_management(UpdateBotManagement {
+                sbfm_definitely_automated: Some("block".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m) if m.contains("Free Website")), "{:?}", err);
+
+        let err = security
+            .update_bot_management(UpdateBotManagement {
+                sbfm_verified_bots: Some("tarpit".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(_)));
+
+        assert!(security.set_bot_fight_mode(true).await.unwrap().fight_mode);
+    }
+
+    #[test]
+    fn test_custom_rule_validation() {
+        let rule = custom_rule(" cf.client.bot ", "skip", Some(" ".to_string())).unwrap();
+        assert_eq!(rule.expression, "cf.client.bot");
+        assert_eq!(rule.action_parameters, Some(serde_json::json!({ "ruleset": "current" })));
+        assert_eq!(rule.description, None);
+
+        assert!(custom_rule("   ", "block", None).is_err());
+        assert!(custom_rule("ip.src eq 192.0.2.1", "execute", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_custom_rule_appends_to_entrypoint() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        let existing = serde_json::json!({
+            "id": "rule-1",
+            "action": "log",
+            "expression": "http.request.uri.path contains \"/wp-login\"",
+            "enabled": true
+        });
+        let created = serde_json::json!({
+            "action": "block",
+            "expression": "ip.src.country eq \"T1\"",
+            "description": "Block Tor",
+            "enabled": true
+        });
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/rulesets/phases/http_request_firewall_custom/entrypoint"))
+            .respond_with(ruleset_response(serde_json::json!([existing])))
+            .mount(&server)
+            .await;
+        let mut stored = created.clone();
+        stored["id"] = serde_json::json!("rule-2");
+        // Only the new rule is sent, so the existing one can't be clobbered
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/rulesets/entry/rules"))
+            .and(body_json(&created))
+            .respond_with(ruleset_response(serde_json::json!([existing, stored])))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .respond_with(ruleset_response(serde_json::json!([])))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let rule = mock_service(&server, SecurityService::new)
+            .create_custom_rule("ip.src.country eq \"T1\"", "block", Some("Block Tor".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(rule.id.as_deref(), Some("rule-2"));
+        assert_eq!(rule.action, "block");
+    }
+
+    #[tokio::test]
+    async fn test_update_custom_rule_preserves_other_rules() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        let first = serde_json::json!({ "id": "rule-1", "action": "log", "expression": "true", "enabled": true });
+        let last = serde_json::json!({
+            "id": "rule-3",
+            "action": "skip",
+            "action_parameters": { "ruleset": "current" },
+            "expression": "cf.client.bot",
+            "enabled": true
+        });
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/rulesets/phases/http_request_firewall_custom/entrypoint"))
+            .respond_with(ruleset_response(serde_json::json!([
+                first,
+                { "id": "rule-2", "action": "block", "expression": "ip.src eq 192.0.2.1", "enabled": false },
+                last
+            ])))
+            .mount(&server)
+            .await;
+        // The disabled rule stays disabled and in place
+        let updated = serde_json::json!({
+            "action": "managed_challenge",
+            "expression": "ip.src in {192.0.2.0/24}",
+            "description": "Challenge range",
+            "enabled": false
+        });
+        let mut stored = updated.clone();
+        stored["id"] = serde_json::json!("rule-2");
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/rulesets/entry/rules/rule-2"))
+            .and(body_json(&updated))
+            .respond_with(ruleset_response(serde_json::json!([first, stored, last])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let security = mock_service(&server, SecurityService::new);
+        let rule = security
+            .update_custom_rule(
+                "rule-2",
+                "ip.src in {192.0.2.0/24}",
+                "managed_challenge",
+                Some("Challenge range".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(rule.action, "managed_challenge");
+        assert_eq!(rule.enabled, Some(false));
+
+        let err = security.update_custom_rule("rule-9", "true", "log", None).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_delete_custom_rule_removes_only_that_rule() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        let first = serde_json::json!({ "id": "rule-1", "action": "log", "expression": "true", "enabled": true });
+        let second = serde_json::json!({ "id": "rule-2", "action": "block", "expression": "cf.client.bot" });
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/rulesets/phases/http_request_firewall_custom/entrypoint"))
+            .respond_with(ruleset_response(serde_json::json!([first, second])))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/zones/zone/rulesets/entry/rules/rule-1"))
+            .respond_with(ruleset_response(serde_json::json!([second])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let security = mock_service(&server, SecurityService::new);
+        security.delete_custom_rule("rule-1").await.unwrap();
+        assert!(matches!(
+            security.delete_custom_rule("rule-9").await,
+            Err(CloudflareError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_ip_list_items() {
+        let items = |ips: &[&str]| -> Vec<CreateIpListItem> {
+            ips.iter()
+                .map(|ip| CreateIpListItem { ip: ip.to_string(), comment: None })
+                .collect()
+        };
+
+        validate_ip_list_items(&items(&["192.0.2.1", "198.51.100.0/24", "2001:db8::1", "2001:db8::/48"]))
+            .unwrap();
+
+        let err = validate_ip_list_items(&items(&["192.0.2.1", "300.1.1.1", "10.0.0.0/4", "2001:db8::/128", "host"]))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CloudflareError::ValidationError(ref m)
+                if m == "Invalid IP address or CIDR: 300.1.1.1, 10.0.0.0/4, 2001:db8::/128, host"
+        ));
+
+        assert!(validate_ip_list_items(&[]).is_err());
+        assert!(validate_list_name("bad_actors").is_ok());
+        assert!(validate_list_name("Bad Actors").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_turnstile_list_never_includes_secrets() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/challenges/widgets"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "result": [{
+                    "sitekey": "0x4AAAAAAA",
+                    "secret": "0x4AAAAAAA-secret",
+                    "name": "Contact form",
+                    "domains": ["example.com"],
+                    "mode": "managed"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, SecurityService::new);
+
+        let widgets = service.list_turnstile_widgets().await.unwrap();
+        assert_eq!(widgets[0].sitekey, "0x4AAAAAAA");
+        assert!(widgets[0].secret.is_none());
+        assert!(!serde_json::to_string(&widgets).unwrap().contains("secret"));
+    }
+
+    #[test]
+    fn test_validate_turnstile_widget() {
+        let widget = |name: &str, domains: &[&str], mode: &str| CreateTurnstileWidget {
+            name: name.to_string(),
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            mode: mode.to_string(),
+        };
+
+        validate_turnstile_widget(&widget("Contact form", &["example.com"], "invisible")).unwrap();
+        assert!(validate_turnstile_widget(&widget(" ", &["example.com"], "managed")).is_err());
+        assert!(validate_turnstile_widget(&widget("Login", &[], "managed")).is_err());
+        assert!(validate_turnstile_widget(&widget("Login", &["example.com"], "captcha")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_turnstile_posts_form() {
+        use wiremock::matchers::{body_string, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string("secret=widget-secret&response=token-1&remoteip=203.0.113.7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": false,
+                "error-codes": ["timeout-or-duplicate"]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut service = SecurityService::new_unconfigured(lazy_pool());
+        service.siteverify_url = server.uri();
+
+        let result = service
+            .verify_turnstile("token-1", Some("203.0.113.7"), "widget-secret")
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error_codes, vec!["timeout-or-duplicate"]);
+
+        assert!(matches!(
+            service.verify_turnstile(" ", None, "widget-secret").await,
+            Err(CloudflareError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_export_import_round_trip_remaps_ids() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let firewall_rule = |id: &str| {
+            serde_json::json!({
+                "id": id,
+                "paused": false,
+                "description": "Block bad bots",
+                "action": "block",
+                "filter": { "id": format!("filter-{}", id), "expression": "cf.client.bot", "paused": false },
+            })
+        };
+        let ip_rule = |id: &str, scope: &str| {
+            serde_json::json!({
+                "id": id,
+                "mode": "block",
+                "allowed_modes": ["block", "challenge"],
+                "notes": "Abuse",
+                "configuration": { "target": "ip", "value": "198.51.100.4" },
+                "scope": { "id": "scope", "type": scope }
+            })
+        };
+        let tor_rule = serde_json::json!({
+            "action": "managed_challenge",
+            "expression": "ip.src.country eq \"T1\"",
+            "enabled": true
+        });
+        let login_rule = serde_json::json!({
+            "action": "block",
+            "expression": "http.request.uri.path eq \"/wp-login.php\"",
+            "enabled": true
+        });
+        let rate_limit_rule = serde_json::json!({
+            "action": "block",
+            "expression": "http.request.uri.path eq \"/xmlrpc.php\"",
+            "enabled": true,
+            "ratelimit": {
+                "characteristics": ["cf.colo.id", "ip.src"],
+                "period": 60,
+                "requests_per_period": 20,
+                "mitigation_timeout": 600
+            }
+        });
+        let with_id = |rule: &serde_json::Value, id: &str| {
+            let mut rule = rule.clone();
+            rule["id"] = serde_json::json!(id);
+            rule
+        };
+
+        // Source zone
+        let source = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone-a/firewall/rules"))
+            .respond_with(success(serde_json::json!([firewall_rule("fw-a")])))
+            .mount(&source)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone-a/firewall/access_rules/rules"))
+            .respond_with(success(serde_json::json!([ip_rule("ip-a", "zone"), ip_rule("ip-account", "account")])))
+            .mount(&source)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone-a/rulesets/phases/http_request_firewall_custom/entrypoint"))
+            .respond_with(ruleset_response(serde_json::json!([
+                with_id(&tor_rule, "custom-a1"),
+                with_id(&login_rule, "custom-a2")
+            ])))
+            .mount(&source)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone-a/rulesets/phases/http_ratelimit/entrypoint"))
+            .respond_with(ruleset_response(serde_json::json!([with_id(&rate_limit_rule, "rl-a")])))
+            .mount(&source)
+            .await;
+
+        let export = SecurityService::new(zone_client(&source, "zone-a"), lazy_pool()).export_rules().await.unwrap();
+        assert_eq!(export.source_zone_id, "zone-a");
+        assert_eq!(export.ip_access_rules.len(), 1);
+        let document = serde_json::to_string(&export).unwrap();
+        let export: RulesExport = serde_json::from_str(&document).unwrap();
+
+        // Target zone, which already has the Tor rule
+        let target = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone-b/firewall/rules"))
+            .respond_with(success(serde_json::json!([])))
+            .mount(&target)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone-b/firewall/rules"))
+            .and(body_json(serde_json::json!([{
+                "action": "block",
+                "filter": { "expression": "cf.client.bot", "description": "Block bad bots" },
+                "description": "Block bad bots",
+                "paused": false
+            }])))
+            .respond_with(success(serde_json::json!([firewall_rule("fw-b")])))
+            .expect(1)
+            .mount(&target)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone-b/firewall/access_rules/rules"))
+            .respond_with(success(serde_json::json!([])))
+            .mount(&target)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone-b/firewall/access_rules/rules"))
+            .and(body_json(serde_json::json!({
+                "mode": "block",
+                "configuration": { "target": "ip", "value": "198.51.100.4" },
+                "notes": "Abuse"
+            })))
+            .respond_with(success(ip_rule("ip-b", "zone")))
+            .expect(1)
+            .mount(&target)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone-b/rulesets/phases/http_request_firewall_custom/entrypoint"))
+            .respond_with(ruleset_response(serde_json::json!([with_id(&tor_rule, "custom-b1")])))
+            .mount(&target)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/zones/zone-b/rulesets/phases/http_request_firewall_custom/entrypoint"))
+            .and(body_json(serde_json::json!({ "rules": [with_id(&tor_rule, "custom-b1"), login_rule] })))
+            .respond_with(ruleset_response(serde_json::json!([
+                with_id(&tor_rule, "custom-b1"),
+                with_id(&login_rule, "custom-b2")
+            ])))
+            .expect(1)
+            .mount(&target)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone-b/rulesets/phases/http_ratelimit/entrypoint"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 10003, "message": "could not find entrypoint ruleset" }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&target)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/zones/zone-b/rulesets/phases/http_ratelimit/entrypoint"))
+            .and(body_json(serde_json::json!({ "rules": [rate_limit_rule] })))
+            .respond_with(ruleset_response(serde_json::json!([with_id(&rate_limit_rule, "rl-b")])))
+            .expect(1)
+            .mount(&target)
+            .await;
+
+        let report = SecurityService::new(zone_client(&target, "zone-b"), lazy_pool())
+            .import_rules(&export, RulesImportMode::Merge)
+            .await
+            .unwrap();
+
+        assert_eq!(report.count(RuleImportStatus::Created), 4);
+        assert_eq!(report.count(RuleImportStatus::Skipped), 1);
+        assert_eq!(report.count(RuleImportStatus::Failed), 0);
+        let skipped = report.results.iter().find(|r| r.status == RuleImportStatus::Skipped).unwrap();
+        assert_eq!(skipped.source_id.as_deref(), Some("custom-a1"));
+        assert_eq!(skipped.id.as_deref(), Some("custom-b1"));
+
+        let remapped: Vec<(&str, &str)> = report.id_map.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(
+            remapped,
+            vec![
+                ("custom-a1", "custom-b1"),
+                ("custom-a2", "custom-b2"),
+                ("fw-a", "fw-b"),
+                ("ip-a", "ip-b"),
+                ("rl-a", "rl-b"),
+                ("zone-a", "zone-b"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_refuses_newer_export_versions() {
+        let server = wiremock::MockServer::start().await;
+        let export = RulesExport {
+            version: RULES_EXPORT_VERSION + 1,
+            source_zone_id: "zone-a".to_string(),
+            exported_at: Utc::now(),
+            firewall_rules: Vec::new(),
+            custom_rules: Vec::new(),
+            ip_access_rules: Vec::new(),
+            rate_limit_rules: Vec::new(),
+        };
+
+        assert!(matches!(
+            mock_service(&server, SecurityService::new).import_rules(&export, RulesImportMode::Replace).await,
+            Err(CloudflareError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replace_import_keeps_existing_rules_when_a_create_fails() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let firewall_rule = |id: &str, expression: &str| {
+            serde_json::json!({
+                "id": id,
+                "paused": false,
+                "action": "block",
+                "filter": { "id": format!("filter-{}", id), "expression": expression, "paused": false },
+            })
+        };
+        let exported = |id: &str, expression: &str| ExportedFirewallRule {
+            id: id.to_string(),
+            action: "block".to_string(),
+            expression: expression.to_string(),
+            description: None,
+            paused: false,
+            priority: None,
+            products: None,
+        };
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/firewall/rules"))
+            .respond_with(success(serde_json::json!([firewall_rule("fw-keep", "cf.client.bot"), firewall_rule("fw-old", "ip.src eq 192.0.2.1")])))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/firewall/rules"))
+            .and(body_string_contains("cf.threat_score"))
+            .respond_with(success(serde_json::json!([firewall_rule("fw-new", "cf.threat_score gt 10")])))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/firewall/rules"))
+            .and(body_string_contains("invalid"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 10014, "message": "filter expression is invalid" }],
+                "messages": [],
+                "result": null
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/firewall/access_rules/rules"))
+            .respond_with(success(serde_json::json!([])))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/rulesets/phases/http_request_firewall_custom/entrypoint"))
+            .respond_with(success(serde_json::json!({ "id": "custom", "name": "custom", "kind": "zone",
+                        "phase": "http_request_firewall_custom", "rules": [] })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/rulesets/phases/http_ratelimit/entrypoint"))
+            .respond_with(success(serde_json::json!({ "id": "ratelimit", "name": "ratelimit", "kind": "zone",
+                        "phase": "http_ratelimit", "rules": [] })))
+            .mount(&server)
+            .await;
+
+        let export = RulesExport {
+            version: RULES_EXPORT_VERSION,
+            source_zone_id: "zone-a".to_string(),
+            exported_at: Utc::now(),
+            firewall_rules: vec![
+                exported("fw-a1", "cf.client.bot"),
+                exported("fw-a2", "cf.threat_score gt 10"),
+                exported("fw-a3", "invalid expression"),
+            ],
+            custom_rules: Vec::new(),
+            ip_access_rules: Vec::new(),
+            rate_limit_rules: Vec::new(),
+        };
+
+        let report = mock_service(&server, SecurityService::new)
+            .import_rules(&export, RulesImportMode::Replace)
+            .await
+            .unwrap();
+
+        assert_eq!(report.count(RuleImportStatus::Created), 1);
+        assert_eq!(report.count(RuleImportStatus::Skipped), 1);
+        assert_eq!(report.count(RuleImportStatus::Failed), 1);
+        let failed = report.results.iter().find(|r| r.status == RuleImportStatus::Failed).unwrap();
+        assert_eq!(failed.source_id.as_deref(), Some("fw-a3"));
+        // The identical rule already on the zone stands in for the skipped one
+        assert_eq!(report.id_map.get("fw-a1").map(String::as_str), Some("fw-keep"));
+        assert_eq!(report.id_map.get("fw-a2").map(String::as_str), Some("fw-new"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_trigger_restores_prior_security_level() {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let threats = |count: u64| {
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "viewer": { "zones": [{ "httpRequests1mGroups": [{ "sum": { "threats": count } }] }] } },
+                "errors": null
+            }))
+        };
+        let level = |value: &str| {
+            success(serde_json::json!({ "id": "security_level", "value": value, "editable": true }))
+        };
+
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(threats(900))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST")).and(path("/graphql")).respond_with(threats(0)).mount(&server).await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings/security_level"))
+            .respond_with(level("high"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/security_level"))
+            .and(body_json(serde_json::json!({ "value": "under_attack" })))
+            .respond_with(level("under_attack"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/security_level"))
+            .and(body_json(serde_json::json!({ "value": "high" })))
+            .respond_with(level("high"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, SecurityService::new);
+        let thresholds = UnderAttackThresholds {
+            threshold: 500,
+            window: chrono::Duration::minutes(5),
+            cooldown: chrono::Duration::zero(),
+        };
+        let mut monitor = UnderAttackMonitor::default();
+
+        let engaged = service.check_threat_rate(&mut monitor, &thresholds).await.unwrap();
+        assert_eq!(engaged, Some(UnderAttackTransition::Engaged));
+        assert_eq!(monitor.prior_level(), Some("high"));
+
+        let disengaged = service.check_threat_rate(&mut monitor, &thresholds).await.unwrap();
+        assert_eq!(disengaged, Some(UnderAttackTransition::Disengaged));
+    }
+
+    #[tokio::test]
+    async fn test_toggle_under_attack_does_not_wait_for_notifications() {
+        use sqlx::postgres::PgPoolOptions;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer};
+
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/security_level"))
+            .respond_with(success(serde_json::json!({ "id": "security_level", "value": "under_attack", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Loading the webhook settings hangs until the acquire timeout
+        let unreachable = PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_secs(30))
+            .connect_lazy("postgres://localhost:1/rustpress_test")
+            .unwrap();
+        let service = mock_service(&server, SecurityService::new)
+            .with_notifications(Arc::new(NotificationService::new(unreachable)));
+
+        tokio::time::timeout(std::time::Duration::from_secs(2), service.toggle_under_attack(true))
+            .await
+            .expect("toggle waited for the notification")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_auto_trigger_state_survives_restart() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "viewer": { "zones": [{ "httpRequests1mGroups": [{ "sum": { "threats": 900 } }] }] } },
+                "errors": null
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(wiremock::matchers::path("/zones/zone/settings/security_level"))
+            .respond_with(success(serde_json::json!({ "id": "security_level", "value": "low", "editable": true })))
+            .mount(&server)
+            .await;
+
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let service = SecurityService::new(mock_client(&server), pool.clone());
+        let thresholds = UnderAttackThresholds {
+            threshold: 500,
+            window: chrono::Duration::minutes(5),
+            cooldown: chrono::Duration::minutes(15),
+        };
+
+        let mut monitor = UnderAttackMonitor::default();
+        service.check_threat_rate(&mut monitor, &thresholds).await.unwrap();
+
+        // A restarted plugin picks up where the last one left off
+        let mut restored = service.load_under_attack_state().await.unwrap();
+        assert_eq!(restored, monitor);
+        assert!(restored.is_engaged());
+        assert_eq!(restored.prior_level(), Some("low"));
+
+        service.release_under_attack(&mut restored).await;
+        let released = service.load_under_attack_state().await.unwrap();
+
+        sqlx::query("DELETE FROM cloudflare_settings WHERE key = $1")
+            .bind(UNDER_ATTACK_STATE_SETTING)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM cloudflare_security_events WHERE event_type = 'under_attack_auto' AND details->>'threats' = '900'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(!released.is_engaged());
+    }
 }
@@ -0,0 +1,453 @@
+//! Live security events
+//!
+//! Polls the GraphQL `firewallEventsAdaptive` dataset while at least one
+//! admin is watching and fans new events out to every subscriber. Polling
+//! starts with the first subscriber and stops once the last one is gone.
+
+use crate::client::CloudflareClient;
+use crate::error::{CloudflareError, CloudflareResult};
+use crate::models::SecurityEvent;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// How often the dataset is polled while anyone is subscribed
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Most events fetched per poll
+pub const MAX_EVENTS_PER_POLL: u32 = 100;
+/// Most events a single listing returns
+pub const MAX_RECENT_EVENTS: u32 = 1000;
+/// How far back a listing of recent events looks
+pub const RECENT_EVENTS_WINDOW: chrono::Duration = chrono::Duration::hours(24);
+/// Ray IDs remembered for deduplication
+const SEEN_RAY_IDS: usize = 1000;
+/// Messages buffered for a subscriber that falls behind
+const CHANNEL_CAPACITY: usize = 256;
+/// GraphQL error text returned when the zone can't query a dataset
+const DATASET_UNAVAILABLE_MARKERS: &[&str] = &["does not have access", "not authorized", "entitle"];
+
+/// Newest firewall events since a point in time
+const FIREWALL_EVENTS_QUERY: &str = r#"
+query FirewallEvents($zoneTag: string, $since: Time, $limit: uint64) {
+  viewer {
+    zones(filter: { zoneTag: $zoneTag }) {
+      firewallEventsAdaptive(
+        limit: $limit
+        filter: { datetime_geq: $since }
+        orderBy: [datetime_DESC]
+      ) {
+        rayName
+        action
+        source
+        clientCountryName
+        clientRequestHTTPHost
+        clientRequestPath
+        ruleId
+        datetime
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Deserialize)]
+struct FirewallEventsData {
+    viewer: FirewallEventsViewer,
+}
+
+#[derive(Debug, Deserialize)]
+struct FirewallEventsViewer {
+    zones: Vec<FirewallEventsZone>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FirewallEventsZone {
+    #[serde(default)]
+    firewall_events_adaptive: Vec<FirewallEventRow>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FirewallEventRow {
+    ray_name: String,
+    action: String,
+    source: String,
+    client_country_name: Option<String>,
+    #[serde(rename = "clientRequestHTTPHost")]
+    client_request_http_host: Option<String>,
+    client_request_path: Option<String>,
+    rule_id: Option<String>,
+    datetime: DateTime<Utc>,
+}
+
+impl From<FirewallEventRow> for SecurityEvent {
+    fn from(row: FirewallEventRow) -> Self {
+        let non_empty = |value: Option<String>| value.filter(|v| !v.is_empty());
+        SecurityEvent {
+            ray_id: row.ray_name,
+            action: row.action,
+            source: row.source,
+            country: non_empty(row.client_country_name),
+            host: non_empty(row.client_request_http_host),
+            path: non_empty(row.client_request_path),
+            rule_id: non_empty(row.rule_id),
+            datetime: row.datetime,
+        }
+    }
+}
+
+/// Up to `limit` of the newest firewall events at or after `since`, newest first
+pub async fn fetch_firewall_events(
+    client: &CloudflareClient,
+    since: DateTime<Utc>,
+    limit: u32,
+) -> CloudflareResult<Vec<SecurityEvent>> {
+    let variables = serde_json::json!({
+        "zoneTag": client.zone_id(),
+        "since": since.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        "limit": limit,
+    });
+
+    let data: FirewallEventsData = client
+        .graphql(FIREWALL_EVENTS_QUERY, variables)
+        .await
+        .map_err(dataset_error)?;
+    Ok(data
+        .viewer
+        .zones
+        .into_iter()
+        .flat_map(|zone| zone.firewall_events_adaptive)
+        .map(SecurityEvent::from)
+        .collect())
+}
+
+/// Turn the GraphQL error for a zone without access to the dataset into `PermissionDenied`
+fn dataset_error(error: CloudflareError) -> CloudflareError {
+    match &error {
+        CloudflareError::ApiError { message, .. }
+            if DATASET_UNAVAILABLE_MARKERS
+                .iter()
+                .any(|marker| message.to_lowercase().contains(marker)) =>
+        {
+            CloudflareError::PermissionDenied(
+                "Firewall events aren't available on this zone's plan".to_string(),
+            )
+        }
+        _ => error,
+    }
+}
+
+/// What subscribers of the feed receive
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedMessage {
+    Event(SecurityEvent),
+    /// The feed stopped for good, with the reason
+    Closed(String),
+}
+
+/// Remembers the most recent ray IDs so overlapping polls don't repeat events
+#[derive(Debug)]
+pub struct RayIdDedup {
+    seen: HashSet<String>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl RayIdDedup {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Events not seen before, oldest first
+    pub fn fresh(&mut self, mut events: Vec<SecurityEvent>) -> Vec<SecurityEvent> {
+        events.sort_by_key(|event| event.datetime);
+        events.retain(|event| self.insert(&event.ray_id));
+        events
+    }
+
+    fn insert(&mut self, ray_id: &str) -> bool {
+        if !self.seen.insert(ray_id.to_string()) {
+            return false;
+        }
+        self.order.push_back(ray_id.to_string());
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Firewall events pushed to every subscriber while anyone is listening
+#[derive(Debug, Clone)]
+pub struct SecurityEventFeed {
+    sender: Arc<Mutex<Option<broadcast::Sender<FeedMessage>>>>,
+    /// Client the poller uses, replaced when the plugin reconnects
+    client: Arc<Mutex<Option<Arc<CloudflareClient>>>>,
+    interval: Duration,
+}
+
+impl Default for SecurityEventFeed {
+    fn default() -> Self {
+        Self::new(POLL_INTERVAL)
+    }
+}
+
+impl SecurityEventFeed {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            sender: Arc::new(Mutex::new(None)),
+            client: Arc::new(Mutex::new(None)),
+            interval,
+        }
+    }
+
+    /// Poll with `client` from now on, including a poller already running
+    pub fn set_client(&self, client: Arc<CloudflareClient>) {
+        *self.client.lock().unwrap_or_else(|e| e.into_inner()) = Some(client);
+    }
+
+    /// Receive new events, starting the poller if nobody else is listening
+    pub fn subscribe(&self, client: Arc<CloudflareClient>) -> broadcast::Receiver<FeedMessage> {
+        self.set_client(client);
+        let mut sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(running) = sender.as_ref() {
+            return running.subscribe();
+        }
+
+        let (tx, rx) = broadcast::channel(CHANNEL_CAPACITY);
+        *sender = Some(tx.clone());
+        info!("Starting security event feed");
+        let feed = self.clone();
+        tokio::spawn(async move {
+            let clients = Arc::clone(&feed.client);
+            feed.poll(tx, move |since| {
+                let client = clients.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                async move {
+                    let client = client.ok_or(CloudflareError::NotConfigured)?;
+                    fetch_firewall_events(&client, since, MAX_EVENTS_PER_POLL).await
+                }
+            })
+            .await
+        });
+        rx
+    }
+
+    /// Number of subscribers currently listening
+    pub fn subscribers(&self) -> usize {
+        self.sender
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map_or(0, |tx| tx.receiver_count())
+    }
+
+    /// Poll with `fetch` until the last subscriber leaves or the dataset turns out to be unavailable
+    async fn poll<F, Fut>(&self, tx: broadcast::Sender<FeedMessage>, fetch: F)
+    where
+        F: Fn(DateTime<Utc>) -> Fut,
+        Fut: std::future::Future<Output = CloudflareResult<Vec<SecurityEvent>>>,
+    {
+        let mut dedup = RayIdDedup::new(SEEN_RAY_IDS);
+        let mut since = Utc::now() - self.interval;
+
+        loop {
+            if !self.keep_polling(&tx) {
+                break;
+            }
+
+            match fetch(since).await {
+                Ok(events) => {
+                    for event in dedup.fresh(events) {
+                        since = since.max(event.datetime);
+                        let _ = tx.send(FeedMessage::Event(event));
+                    }
+                }
+                Err(CloudflareError::PermissionDenied(message)) => {
+                    warn!("Security event feed stopped: {}", message);
+                    let _ = tx.send(FeedMessage::Closed(message));
+                    self.stop(&tx);
+                    return;
+                }
+                Err(e) => warn!("Failed to poll security events: {}", e),
+            }
+
+            tokio::time::sleep(self.interval).await;
+        }
+        info!("Security event feed stopped, no subscribers left");
+    }
+
+    /// Whether anyone is still listening; if not, retire the channel so the
+    /// next subscriber starts a fresh poller
+    fn keep_polling(&self, tx: &broadcast::Sender<FeedMessage>) -> bool {
+        let mut sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        if tx.receiver_count() > 0 {
+            return true;
+        }
+        if sender.as_ref().is_some_and(|current| current.same_channel(tx)) {
+            *sender = None;
+        }
+        false
+    }
+
+    fn stop(&self, tx: &broadcast::Sender<FeedMessage>) {
+        let mut sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        if sender.as_ref().is_some_and(|current| current.same_channel(tx)) {
+            *sender = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn event(ray_id: &str, second: u32) -> SecurityEvent {
+        SecurityEvent {
+            ray_id: ray_id.to_string(),
+            action: "block".to_string(),
+            source: "firewallCustom".to_string(),
+            country: Some("NL".to_string()),
+            host: Some("example.com".to_string()),
+            path: Some("/wp-login.php".to_string()),
+            rule_id: None,
+            datetime: format!("2026-10-16T10:00:{:02}Z", second).parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_graphql_response_maps_into_events() {
+        let data: FirewallEventsData = serde_json::from_value(serde_json::json!({
+            "viewer": {
+                "zones": [{
+                    "firewallEventsAdaptive": [
+                        {
+                            "rayName": "8c1f2e3d4a5b6c7d",
+                            "action": "block",
+                            "source": "firewallCustom",
+                            "clientCountryName": "NL",
+                            "clientRequestHTTPHost": "example.com",
+                            "clientRequestPath": "/wp-login.php",
+                            "ruleId": "2b8b1c3e0a5d4f6e",
+                            "datetime": "2026-10-16T10:00:05Z"
+                        },
+                        {
+                            "rayName": "8c1f2e3d4a5b6c7e",
+                            "action": "managed_challenge",
+                            "source": "botFight",
+                            "clientCountryName": "",
+                            "clientRequestHTTPHost": "example.com",
+                            "clientRequestPath": "/",
+                            "ruleId": "",
+                            "datetime": "2026-10-16T10:00:01Z"
+                        }
+                    ]
+                }]
+            }
+        }))
+        .unwrap();
+
+        let events: Vec<SecurityEvent> = data
+            .viewer
+            .zones
+            .into_iter()
+            .flat_map(|zone| zone.firewall_events_adaptive)
+            .map(SecurityEvent::from)
+            .collect();
+        assert_eq!(
+            events[0],
+            SecurityEvent {
+                ray_id: "8c1f2e3d4a5b6c7d".to_string(),
+                action: "block".to_string(),
+                source: "firewallCustom".to_string(),
+                country: Some("NL".to_string()),
+                host: Some("example.com".to_string()),
+                path: Some("/wp-login.php".to_string()),
+                rule_id: Some("2b8b1c3e0a5d4f6e".to_string()),
+                datetime: "2026-10-16T10:00:05Z".parse().unwrap(),
+            }
+        );
+        // Empty strings from the dataset become missing values
+        assert_eq!(events[1].country, None);
+        assert_eq!(events[1].rule_id, None);
+    }
+
+    #[test]
+    fn test_dedup_drops_repeats_and_orders_oldest_first() {
+        let mut dedup = RayIdDedup::new(2);
+
+        let first = dedup.fresh(vec![event("ray-2", 2), event("ray-1", 1)]);
+        assert_eq!(first.iter().map(|e| e.ray_id.as_str()).collect::<Vec<_>>(), ["ray-1", "ray-2"]);
+
+        // The next poll overlaps the last one
+        let second = dedup.fresh(vec![event("ray-3", 3), event("ray-2", 2)]);
+        assert_eq!(second.iter().map(|e| e.ray_id.as_str()).collect::<Vec<_>>(), ["ray-3"]);
+
+        // Only the most recent ray IDs are remembered
+        assert_eq!(dedup.fresh(vec![event("ray-1", 1)]).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_poll_pushes_new_events_and_stops_without_subscribers() {
+        let feed = SecurityEventFeed::new(Duration::from_millis(10));
+        let (tx, mut rx) = broadcast::channel(16);
+        *feed.sender.lock().unwrap() = Some(tx.clone());
+
+        let polls = Arc::new(AtomicUsize::new(0));
+        let poller = {
+            let feed = feed.clone();
+            let polls = Arc::clone(&polls);
+            tokio::spawn(async move {
+                feed.poll(tx, |_| {
+                    let poll = polls.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        Ok(match poll {
+                            0 => vec![event("ray-1", 1)],
+                            _ => vec![event("ray-2", 2), event("ray-1", 1)],
+                        })
+                    }
+                })
+                .await
+            })
+        };
+
+        assert_eq!(rx.recv().await.unwrap(), FeedMessage::Event(event("ray-1", 1)));
+        assert_eq!(rx.recv().await.unwrap(), FeedMessage::Event(event("ray-2", 2)));
+
+        drop(rx);
+        tokio::time::timeout(Duration::from_secs(1), poller).await.unwrap().unwrap();
+        assert_eq!(feed.subscribers(), 0);
+        assert!(feed.sender.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_poll_closes_feed_when_dataset_unavailable() {
+        let feed = SecurityEventFeed::new(Duration::from_millis(10));
+        let (tx, mut rx) = broadcast::channel(16);
+        *feed.sender.lock().unwrap() = Some(tx.clone());
+
+        feed.poll(tx, |_| async {
+            Err(dataset_error(CloudflareError::ApiError {
+                code: 0,
+                message: "zone 'zone' does not have access to the path".to_string(),
+                errors: Vec::new(),
+                ray_id: None,
+            }))
+        })
+        .await;
+
+        assert!(matches!(rx.recv().await.unwrap(), FeedMessage::Closed(m) if m.contains("plan")));
+        assert!(feed.sender.lock().unwrap().is_none());
+    }
+}
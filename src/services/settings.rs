@@ -1,10 +1,16 @@
 //! Settings service for Cloudflare credential management
 
+use crate::client::CloudflareClient;
+use crate::config::CloudflareConfig;
 use crate::error::{CloudflareError, CloudflareResult};
-use crate::hooks::AutoPurgeConfig;
+use crate::hooks::{default_quota_pause_minutes, AutoPurgeConfig, QuotaExhaustedAction};
+use crate::services::dns_scan::{default_probes, parse_nameservers, validate_probes, DnsProbe};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use sqlx::{Pool, Postgres};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 /// Cloudflare credentials
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +51,13 @@ pub struct ExtendedPluginSettings {
     pub auto_purge_archives: bool,
     pub auto_purge_custom_urls: Option<String>,
     pub auto_purge_delay_ms: u32,
+    #[serde(default)]
+    pub auto_purge_on_quota_exhausted: QuotaExhaustedAction,
+    /// How long auto-purge stays paused once the quota is exhausted, in minutes
+    #[serde(default = "default_quota_pause_minutes")]
+    pub auto_purge_quota_pause_minutes: u32,
+    #[serde(default)]
+    pub auto_purge_dry_run: bool,
 
     // Cache warming
     pub cache_warming_enabled: bool,
@@ -53,12 +66,34 @@ pub struct ExtendedPluginSettings {
     // Notifications
     pub security_email_alerts: bool,
     pub security_slack_webhook: Option<String>,
+    #[serde(default)]
+    pub security_webhook_alerts: bool,
+
+    // Under Attack auto-trigger
+    #[serde(default)]
+    pub under_attack_auto_enabled: bool,
+    /// Threats within the window that switch Under Attack mode on
+    #[serde(default = "default_under_attack_threshold")]
+    pub under_attack_threshold: u32,
+    #[serde(default = "default_under_attack_window_minutes")]
+    pub under_attack_window_minutes: u32,
+    /// Quiet minutes after the last spike before Under Attack mode is switched off
+    #[serde(default = "default_under_attack_cooldown_minutes")]
+    pub under_attack_cooldown_minutes: u32,
 
     // Advanced settings
     pub development_mode_duration: u32,
     pub analytics_retention_days: u32,
+    #[serde(default = "default_analytics_cache_ttl_secs")]
+    pub analytics_cache_ttl_secs: u64,
     pub r2_default_bucket: Option<String>,
     pub workers_enabled: bool,
+    /// Names and record types the DNS scan looks up
+    #[serde(default = "default_probes")]
+    pub dns_scan_probes: Vec<DnsProbe>,
+    /// Comma-separated nameservers to scan instead of the domain's own
+    #[serde(default)]
+    pub dns_scan_nameservers: Option<String>,
 }
 
 impl Default for ExtendedPluginSettings {
@@ -78,28 +113,420 @@ impl Default for ExtendedPluginSettings {
             auto_purge_archives: true,
             auto_purge_custom_urls: None,
             auto_purge_delay_ms: 500,
+            auto_purge_on_quota_exhausted: QuotaExhaustedAction::default(),
+            auto_purge_quota_pause_minutes: default_quota_pause_minutes(),
+            auto_purge_dry_run: false,
             cache_warming_enabled: false,
             cache_warming_schedule: "immediate".to_string(),
             security_email_alerts: false,
             security_slack_webhook: None,
+            security_webhook_alerts: false,
+            under_attack_auto_enabled: false,
+            under_attack_threshold: default_under_attack_threshold(),
+            under_attack_window_minutes: default_under_attack_window_minutes(),
+            under_attack_cooldown_minutes: default_under_attack_cooldown_minutes(),
             development_mode_duration: 180,
             analytics_retention_days: 30,
+            analytics_cache_ttl_secs: default_analytics_cache_ttl_secs(),
             r2_default_bucket: None,
             workers_enabled: true,
+            dns_scan_probes: default_probes(),
+            dns_scan_nameservers: None,
+        }
+    }
+}
+
+fn default_analytics_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_under_attack_threshold() -> u32 {
+    1000
+}
+
+fn default_under_attack_window_minutes() -> u32 {
+    5
+}
+
+fn default_under_attack_cooldown_minutes() -> u32 {
+    30
+}
+
+/// Longest auto-purge delay, so a save doesn't leave purges queued for minutes
+pub const MAX_AUTO_PURGE_DELAY_MS: u32 = 60_000;
+/// How long analytics snapshots may be kept, in days
+pub const ANALYTICS_RETENTION_DAYS: std::ops::RangeInclusive<u32> = 1..=365;
+/// Development mode durations offered, in minutes
+pub const DEVELOPMENT_MODE_DURATIONS: &[u32] = &[60, 180, 360, 720, 1440];
+/// When cache warming runs
+pub const CACHE_WARMING_SCHEDULES: &[&str] = &["immediate", "hourly", "daily", "manual"];
+/// Windows threats can be counted over for the Under Attack auto-trigger, in minutes
+pub const UNDER_ATTACK_WINDOW_MINUTES: std::ops::RangeInclusive<u32> = 1..=60;
+/// Cooldowns allowed before the auto-trigger switches Under Attack mode off, in minutes
+pub const UNDER_ATTACK_COOLDOWN_MINUTES: std::ops::RangeInclusive<u32> = 1..=1440;
+
+impl ExtendedPluginSettings {
+    /// Check values are in range before they're saved
+    pub fn validate(&self) -> CloudflareResult<()> {
+        if self.auto_purge_delay_ms > MAX_AUTO_PURGE_DELAY_MS {
+            return Err(CloudflareError::ValidationError(format!(
+                "auto_purge_delay_ms must be at most {}",
+                MAX_AUTO_PURGE_DELAY_MS
+            )));
+        }
+
+        if self.auto_purge_quota_pause_minutes == 0 {
+            return Err(CloudflareError::ValidationError(
+                "auto_purge_quota_pause_minutes must be at least 1".to_string(),
+            ));
+        }
+
+        if !ANALYTICS_RETENTION_DAYS.contains(&self.analytics_retention_days) {
+            return Err(CloudflareError::ValidationError(format!(
+                "analytics_retention_days must be between {} and {}",
+                ANALYTICS_RETENTION_DAYS.start(),
+                ANALYTICS_RETENTION_DAYS.end()
+            )));
+        }
+
+        if !DEVELOPMENT_MODE_DURATIONS.contains(&self.development_mode_duration) {
+            return Err(CloudflareError::ValidationError(format!(
+                "development_mode_duration must be one of {:?} minutes",
+                DEVELOPMENT_MODE_DURATIONS
+            )));
+        }
+
+        if !CACHE_WARMING_SCHEDULES.contains(&self.cache_warming_schedule.as_str()) {
+            return Err(CloudflareError::ValidationError(format!(
+                "cache_warming_schedule must be one of: {}",
+                CACHE_WARMING_SCHEDULES.join(", ")
+            )));
+        }
+
+        if self.under_attack_threshold == 0 {
+            return Err(CloudflareError::ValidationError(
+                "under_attack_threshold must be at least 1".to_string(),
+            ));
+        }
+
+        if !UNDER_ATTACK_WINDOW_MINUTES.contains(&self.under_attack_window_minutes) {
+            return Err(CloudflareError::ValidationError(format!(
+                "under_attack_window_minutes must be between {} and {}",
+                UNDER_ATTACK_WINDOW_MINUTES.start(),
+                UNDER_ATTACK_WINDOW_MINUTES.end()
+            )));
+        }
+
+        if !UNDER_ATTACK_COOLDOWN_MINUTES.contains(&self.under_attack_cooldown_minutes) {
+            return Err(CloudflareError::ValidationError(format!(
+                "under_attack_cooldown_minutes must be between {} and {}",
+                UNDER_ATTACK_COOLDOWN_MINUTES.start(),
+                UNDER_ATTACK_COOLDOWN_MINUTES.end()
+            )));
+        }
+
+        validate_probes(&self.dns_scan_probes)?;
+        if let Some(nameservers) = &self.dns_scan_nameservers {
+            parse_nameservers(nameservers)?;
+        }
+
+        if let Some(webhook) = self.security_slack_webhook.as_deref().filter(|w| !w.is_empty()) {
+            let valid = url::Url::parse(webhook)
+                .map(|u| u.scheme() == "https" && u.host_str().is_some())
+                .unwrap_or(false);
+            if !valid {
+                return Err(CloudflareError::ValidationError(
+                    "security_slack_webhook must be an https URL".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A zone setting derived from a `CloudflareConfig` field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ZoneSettingUpdate {
+    /// `CloudflareConfig` field the value comes from
+    pub field: &'static str,
+    /// Cloudflare zone setting ID
+    pub setting_id: &'static str,
+    pub value: serde_json::Value,
+}
+
+/// What happened to a single setting in a batch update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneSettingOutcome {
+    Applied,
+    Unchanged,
+    /// Not available (or not editable) on the zone's plan
+    Skipped,
+    Failed,
+    /// Applied, then restored after a later setting in a transaction failed
+    RolledBack,
+    /// Applied, but couldn't be restored after a later failure
+    RollbackFailed,
+}
+
+/// Result of applying a single zone setting
+#[derive(Debug, Clone, Serialize)]
+pub struct ZoneSettingResult {
+    pub field: &'static str,
+    pub setting_id: &'static str,
+    pub value: serde_json::Value,
+    pub outcome: ZoneSettingOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Version of the settings export format
+pub const SETTINGS_EXPORT_VERSION: u32 = 1;
+/// Stands in for secrets left out of an export
+pub const REDACTED_SETTING: &str = "[redacted]";
+/// Settings only exported when secrets are asked for. A Slack incoming
+/// webhook URL is itself the credential for posting to the channel.
+pub const SECRET_SETTINGS: &[&str] = &["api_token", "stream_webhook_secret", "security_slack_webhook"];
+
+/// JSON type a stored setting must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingKind {
+    Bool,
+    Integer,
+    String,
+    QuotaExhaustedAction,
+    FeatureFlags,
+    DnsProbes,
+}
+
+impl SettingKind {
+    fn accepts(self, value: &serde_json::Value) -> bool {
+        match self {
+            Self::Bool => value.is_boolean(),
+            Self::Integer => value.as_u64().is_some_and(|v| v <= u32::MAX as u64),
+            Self::String => value.is_string(),
+            Self::QuotaExhaustedAction => serde_json::from_value::<QuotaExhaustedAction>(value.clone()).is_ok(),
+            Self::FeatureFlags => value.as_object().is_some_and(|flags| flags.values().all(|v| v.is_boolean())),
+            Self::DnsProbes => serde_json::from_value::<Vec<DnsProbe>>(value.clone()).is_ok(),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Bool => "a boolean",
+            Self::Integer => "a non-negative integer",
+            Self::String => "a string",
+            Self::QuotaExhaustedAction => "a quota exhausted action",
+            Self::FeatureFlags => "an object of boolean flags",
+            Self::DnsProbes => "a list of DNS probes",
         }
     }
 }
 
+/// Settings the plugin reads, with the type each is stored as
+const KNOWN_SETTINGS: &[(&str, SettingKind)] = &[
+    ("api_token", SettingKind::String),
+    ("account_id", SettingKind::String),
+    ("zone_id", SettingKind::String),
+    ("cdn_enabled", SettingKind::Bool),
+    ("cache_level", SettingKind::String),
+    ("security_level", SettingKind::String),
+    ("ssl_mode", SettingKind::String),
+    ("auto_purge_on_update", SettingKind::Bool),
+    ("development_mode", SettingKind::Bool),
+    ("development_mode_expires_at", SettingKind::String),
+    ("auto_purge_enabled", SettingKind::Bool),
+    ("auto_purge_on_post_update", SettingKind::Bool),
+    ("auto_purge_on_page_update", SettingKind::Bool),
+    ("auto_purge_on_media_upload", SettingKind::Bool),
+    ("auto_purge_on_theme_change", SettingKind::Bool),
+    ("auto_purge_on_menu_update", SettingKind::Bool),
+    ("auto_purge_entire_site", SettingKind::Bool),
+    ("auto_purge_homepage", SettingKind::Bool),
+    ("auto_purge_archives", SettingKind::Bool),
+    ("auto_purge_custom_urls", SettingKind::String),
+    ("auto_purge_delay_ms", SettingKind::Integer),
+    ("auto_purge_on_quota_exhausted", SettingKind::QuotaExhaustedAction),
+    ("auto_purge_quota_pause_minutes", SettingKind::Integer),
+    ("auto_purge_dry_run", SettingKind::Bool),
+    ("cache_warming_enabled", SettingKind::Bool),
+    ("cache_warming_schedule", SettingKind::String),
+    ("security_email_alerts", SettingKind::Bool),
+    ("security_slack_webhook", SettingKind::String),
+    ("security_webhook_alerts", SettingKind::Bool),
+    ("under_attack_auto_enabled", SettingKind::Bool),
+    ("under_attack_threshold", SettingKind::Integer),
+    ("under_attack_window_minutes", SettingKind::Integer),
+    ("under_attack_cooldown_minutes", SettingKind::Integer),
+    ("development_mode_duration", SettingKind::Integer),
+    ("analytics_retention_days", SettingKind::Integer),
+    ("analytics_cache_ttl_secs", SettingKind::Integer),
+    ("r2_default_bucket", SettingKind::String),
+    ("workers_enabled", SettingKind::Bool),
+    ("dns_scan_probes", SettingKind::DnsProbes),
+    ("dns_scan_nameservers", SettingKind::String),
+    ("feature_flags", SettingKind::FeatureFlags),
+    ("stream_webhook_secret", SettingKind::String),
+];
+
+fn setting_kind(key: &str) -> Option<SettingKind> {
+    KNOWN_SETTINGS.iter().find(|(k, _)| *k == key).map(|(_, kind)| *kind)
+}
+
+/// What an import did with each setting in the file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SettingsImport {
+    pub imported: Vec<String>,
+    /// Already set (without `overwrite`), empty or redacted
+    pub skipped: Vec<String>,
+    /// Not a setting this version of the plugin knows
+    pub unknown: Vec<String>,
+}
+
+/// Build an export document from stored settings, redacting secrets unless
+/// `include_secrets` is set
+pub fn settings_export(
+    rows: impl IntoIterator<Item = (String, Option<serde_json::Value>)>,
+    include_secrets: bool,
+) -> serde_json::Value {
+    let settings: serde_json::Map<String, serde_json::Value> = rows
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Some(_) if !include_secrets && SECRET_SETTINGS.contains(&key.as_str()) => json!(REDACTED_SETTING),
+                value => value.unwrap_or(serde_json::Value::Null),
+            };
+            (key, value)
+        })
+        .collect();
+
+    json!({
+        "version": SETTINGS_EXPORT_VERSION,
+        "exported_at": chrono::Utc::now(),
+        "settings": settings,
+    })
+}
+
+/// Check an export document and split it into settings to write, settings to
+/// skip and unknown keys. Any known setting with the wrong type fails the
+/// whole import.
+fn plan_settings_import(
+    document: &serde_json::Value,
+) -> CloudflareResult<(Vec<(String, serde_json::Value)>, SettingsImport)> {
+    let settings = document.get("settings").and_then(|s| s.as_object()).ok_or_else(|| {
+        CloudflareError::ValidationError("Settings import must have a \"settings\" object".to_string())
+    })?;
+
+    let mut writes = Vec::new();
+    let mut summary = SettingsImport::default();
+    for (key, value) in settings {
+        let Some(kind) = setting_kind(key) else {
+            warn!("Skipping unknown setting '{}' in import", key);
+            summary.unknown.push(key.clone());
+            continue;
+        };
+
+        let redacted = SECRET_SETTINGS.contains(&key.as_str()) && value.as_str() == Some(REDACTED_SETTING);
+        if value.is_null() || redacted {
+            summary.skipped.push(key.clone());
+        } else if kind.accepts(value) {
+            writes.push((key.clone(), value.clone()));
+        } else {
+            return Err(CloudflareError::ValidationError(format!(
+                "Setting '{}' must be {}",
+                key,
+                kind.describe()
+            )));
+        }
+    }
+
+    Ok((writes, summary))
+}
+
 /// Settings service for managing plugin configuration
 #[derive(Clone)]
 pub struct SettingsService {
     pool: Pool<Postgres>,
+    client: Option<Arc<CloudflareClient>>,
 }
 
 impl SettingsService {
     /// Create a new settings service
     pub fn new(pool: Pool<Postgres>) -> Self {
-        Self { pool }
+        Self { pool, client: None }
+    }
+
+    /// Use a client for the settings that live on Cloudflare
+    pub fn with_client(mut self, client: Arc<CloudflareClient>) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Get the client or return an error if not configured
+    fn get_client(&self) -> CloudflareResult<&CloudflareClient> {
+        self.client.as_ref()
+            .map(|c| c.as_ref())
+            .ok_or(CloudflareError::NotConfigured)
+    }
+
+    /// Current zone settings as reported by Cloudflare
+    pub async fn get_zone_settings(&self) -> CloudflareResult<Vec<crate::models::ZoneSetting>> {
+        let client = self.get_client()?;
+        client.get_zone_settings().await
+    }
+
+    /// Push every zone-level toggle in `config` to Cloudflare
+    pub async fn apply_zone_settings(&self, config: &CloudflareConfig) -> CloudflareResult<Vec<ZoneSettingResult>> {
+        self.apply_zone_setting_updates(zone_setting_updates(config)).await
+    }
+
+    /// Apply zone settings one by one, collecting a result for each.
+    ///
+    /// Settings the zone doesn't have or can't edit on its plan are skipped,
+    /// settings already at the wanted value are left alone, and a failure
+    /// doesn't stop the rest of the batch.
+    pub async fn apply_zone_setting_updates(
+        &self,
+        updates: Vec<ZoneSettingUpdate>,
+    ) -> CloudflareResult<Vec<ZoneSettingResult>> {
+        let client = self.get_client()?;
+        let current: HashMap<String, crate::models::ZoneSetting> = client
+            .get_zone_settings()
+            .await?
+            .into_iter()
+            .map(|s| (s.id.clone(), s))
+            .collect();
+
+        let mut results = Vec::with_capacity(updates.len());
+        for update in updates {
+            let (outcome, error) = match current.get(update.setting_id) {
+                None => (ZoneSettingOutcome::Skipped, Some("Not available on this zone".to_string())),
+                Some(setting) if !setting.editable => {
+                    (ZoneSettingOutcome::Skipped, Some("Not editable on this zone's plan".to_string()))
+                }
+                Some(setting) if setting.value == update.value => (ZoneSettingOutcome::Unchanged, None),
+                Some(_) => match client.update_zone_setting(update.setting_id, update.value.clone()).await {
+                    Ok(_) => (ZoneSettingOutcome::Applied, None),
+                    Err(e) => {
+                        warn!("Failed to update zone setting {}: {}", update.setting_id, e);
+                        (ZoneSettingOutcome::Failed, Some(e.to_string()))
+                    }
+                },
+            };
+
+            results.push(ZoneSettingResult {
+                field: update.field,
+                setting_id: update.setting_id,
+                value: update.value,
+                outcome,
+                error,
+            });
+        }
+
+        let applied = results.iter().filter(|r| r.outcome == ZoneSettingOutcome::Applied).count();
+        info!("Applied {} of {} zone settings", applied, results.len());
+        Ok(results)
     }
 
     /// Get Cloudflare credentials from database
@@ -147,6 +574,21 @@ impl SettingsService {
         Ok(())
     }
 
+    /// Replace the stored API token after checking the new one has the required permissions.
+    ///
+    /// The live client keeps the old token until the new one is verified and
+    /// persisted, so a failed rotation leaves the current connection untouched.
+    pub async fn rotate_token(&self, client: &CloudflareClient, new_token: &str) -> CloudflareResult<()> {
+        let candidate = client.with_token(new_token)?;
+        candidate.verify_required_permissions().await?;
+
+        self.set_setting("api_token", &serde_json::json!(new_token)).await?;
+        client.set_api_token(new_token)?;
+
+        info!("Cloudflare API token rotated");
+        Ok(())
+    }
+
     /// Get plugin settings
     pub async fn get_plugin_settings(&self) -> CloudflareResult<PluginSettings> {
         let cdn_enabled = self.get_setting("cdn_enabled").await?
@@ -285,6 +727,16 @@ impl SettingsService {
             .and_then(|v| v.as_u64())
             .map(|v| v as u32)
             .unwrap_or(500);
+        settings.auto_purge_on_quota_exhausted = self.get_setting("auto_purge_on_quota_exhausted").await?
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+        settings.auto_purge_quota_pause_minutes = self.get_setting("auto_purge_quota_pause_minutes").await?
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32)
+            .unwrap_or_else(default_quota_pause_minutes);
+        settings.auto_purge_dry_run = self.get_setting("auto_purge_dry_run").await?
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
         // Cache warming
         settings.cache_warming_enabled = self.get_setting("cache_warming_enabled").await?
@@ -300,6 +752,26 @@ impl SettingsService {
             .unwrap_or(false);
         settings.security_slack_webhook = self.get_setting("security_slack_webhook").await?
             .and_then(|v| v.as_str().map(|s| s.to_string()));
+        settings.security_webhook_alerts = self.get_setting("security_webhook_alerts").await?
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        // Under Attack auto-trigger
+        settings.under_attack_auto_enabled = self.get_setting("under_attack_auto_enabled").await?
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        settings.under_attack_threshold = self.get_setting("under_attack_threshold").await?
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32)
+            .unwrap_or_else(default_under_attack_threshold);
+        settings.under_attack_window_minutes = self.get_setting("under_attack_window_minutes").await?
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32)
+            .unwrap_or_else(default_under_attack_window_minutes);
+        settings.under_attack_cooldown_minutes = self.get_setting("under_attack_cooldown_minutes").await?
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32)
+            .unwrap_or_else(default_under_attack_cooldown_minutes);
 
         // Advanced settings
         settings.development_mode_duration = self.get_setting("development_mode_duration").await?
@@ -310,17 +782,27 @@ impl SettingsService {
             .and_then(|v| v.as_u64())
             .map(|v| v as u32)
             .unwrap_or(30);
+        settings.analytics_cache_ttl_secs = self.get_setting("analytics_cache_ttl_secs").await?
+            .and_then(|v| v.as_u64())
+            .unwrap_or_else(default_analytics_cache_ttl_secs);
         settings.r2_default_bucket = self.get_setting("r2_default_bucket").await?
             .and_then(|v| v.as_str().map(|s| s.to_string()));
         settings.workers_enabled = self.get_setting("workers_enabled").await?
             .and_then(|v| v.as_bool())
             .unwrap_or(true);
+        settings.dns_scan_probes = self.get_setting("dns_scan_probes").await?
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_else(default_probes);
+        settings.dns_scan_nameservers = self.get_setting("dns_scan_nameservers").await?
+            .and_then(|v| v.as_str().map(|s| s.to_string()));
 
         Ok(settings)
     }
 
     /// Update extended plugin settings
     pub async fn update_extended_settings(&self, settings: &ExtendedPluginSettings) -> CloudflareResult<()> {
+        settings.validate()?;
+
         // Auto-purge settings
         self.set_setting("auto_purge_enabled", &serde_json::json!(settings.auto_purge_enabled)).await?;
         self.set_setting("auto_purge_on_post_update", &serde_json::json!(settings.auto_purge_on_post_update)).await?;
@@ -335,6 +817,9 @@ impl SettingsService {
             self.set_setting("auto_purge_custom_urls", &serde_json::json!(urls)).await?;
         }
         self.set_setting("auto_purge_delay_ms", &serde_json::json!(settings.auto_purge_delay_ms)).await?;
+        self.set_setting("auto_purge_on_quota_exhausted", &serde_json::json!(settings.auto_purge_on_quota_exhausted)).await?;
+        self.set_setting("auto_purge_quota_pause_minutes", &serde_json::json!(settings.auto_purge_quota_pause_minutes)).await?;
+        self.set_setting("auto_purge_dry_run", &serde_json::json!(settings.auto_purge_dry_run)).await?;
 
         // Cache warming
         self.set_setting("cache_warming_enabled", &serde_json::json!(settings.cache_warming_enabled)).await?;
@@ -342,22 +827,95 @@ impl SettingsService {
 
         // Notifications
         self.set_setting("security_email_alerts", &serde_json::json!(settings.security_email_alerts)).await?;
+        self.set_setting("security_webhook_alerts", &serde_json::json!(settings.security_webhook_alerts)).await?;
         if let Some(webhook) = &settings.security_slack_webhook {
             self.set_setting("security_slack_webhook", &serde_json::json!(webhook)).await?;
         }
 
+        // Under Attack auto-trigger
+        self.set_setting("under_attack_auto_enabled", &serde_json::json!(settings.under_attack_auto_enabled)).await?;
+        self.set_setting("under_attack_threshold", &serde_json::json!(settings.under_attack_threshold)).await?;
+        self.set_setting("under_attack_window_minutes", &serde_json::json!(settings.under_attack_window_minutes)).await?;
+        self.set_setting("under_attack_cooldown_minutes", &serde_json::json!(settings.under_attack_cooldown_minutes)).await?;
+
         // Advanced settings
         self.set_setting("development_mode_duration", &serde_json::json!(settings.development_mode_duration)).await?;
         self.set_setting("analytics_retention_days", &serde_json::json!(settings.analytics_retention_days)).await?;
+        self.set_setting("analytics_cache_ttl_secs", &serde_json::json!(settings.analytics_cache_ttl_secs)).await?;
         if let Some(bucket) = &settings.r2_default_bucket {
             self.set_setting("r2_default_bucket", &serde_json::json!(bucket)).await?;
         }
         self.set_setting("workers_enabled", &serde_json::json!(settings.workers_enabled)).await?;
+        self.set_setting("dns_scan_probes", &serde_json::json!(settings.dns_scan_probes)).await?;
+        if let Some(nameservers) = &settings.dns_scan_nameservers {
+            self.set_setting("dns_scan_nameservers", &serde_json::json!(nameservers)).await?;
+        }
 
         info!("Extended plugin settings updated");
         Ok(())
     }
 
+    /// Get the stored feature flags
+    pub async fn get_feature_flags(&self) -> CloudflareResult<HashMap<String, bool>> {
+        Ok(self.get_setting("feature_flags").await?
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default())
+    }
+
+    /// Store feature flags
+    pub async fn save_feature_flags(&self, flags: &BTreeMap<String, bool>) -> CloudflareResult<()> {
+        self.set_setting("feature_flags", &serde_json::json!(flags)).await
+    }
+
+    /// Get the secret Stream signs webhook notifications with
+    pub async fn get_stream_webhook_secret(&self) -> CloudflareResult<Option<String>> {
+        Ok(self.get_setting("stream_webhook_secret").await?
+            .and_then(|v| v.as_str().map(str::to_string)))
+    }
+
+    /// Store the Stream webhook secret
+    pub async fn save_stream_webhook_secret(&self, secret: &str) -> CloudflareResult<()> {
+        self.set_setting("stream_webhook_secret", &serde_json::json!(secret)).await
+    }
+
+    /// Every stored setting as an export document; secrets are redacted
+    /// unless `include_secrets` is set
+    pub async fn export_all(&self, include_secrets: bool) -> CloudflareResult<serde_json::Value> {
+        let rows: Vec<(String, Option<serde_json::Value>)> =
+            sqlx::query_as(r#"SELECT key, value FROM cloudflare_settings ORDER BY key"#)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        Ok(settings_export(rows, include_secrets))
+    }
+
+    /// Restore settings from an `export_all` document.
+    ///
+    /// Every known setting is type-checked before anything is written; unknown
+    /// keys are skipped with a warning. Settings that already have a value are
+    /// only replaced with `overwrite`.
+    pub async fn import_all(&self, document: &serde_json::Value, overwrite: bool) -> CloudflareResult<SettingsImport> {
+        let (writes, mut summary) = plan_settings_import(document)?;
+
+        for (key, value) in writes {
+            if !overwrite && self.get_setting(&key).await?.is_some() {
+                summary.skipped.push(key);
+                continue;
+            }
+            self.set_setting(&key, &value).await?;
+            summary.imported.push(key);
+        }
+
+        info!(
+            "Imported {} settings ({} skipped, {} unknown)",
+            summary.imported.len(),
+            summary.skipped.len(),
+            summary.unknown.len()
+        );
+        Ok(summary)
+    }
+
     /// Get auto-purge configuration compatible with hooks module
     pub async fn get_auto_purge_config(&self) -> CloudflareResult<AutoPurgeConfig> {
         let settings = self.get_extended_settings().await?;
@@ -376,6 +934,351 @@ impl SettingsService {
             purge_archives: settings.auto_purge_archives,
             custom_purge_urls: settings.auto_purge_custom_urls,
             purge_delay_ms: settings.auto_purge_delay_ms,
+            on_quota_exhausted: settings.auto_purge_on_quota_exhausted,
+            quota_pause_minutes: settings.auto_purge_quota_pause_minutes,
+            dry_run: settings.auto_purge_dry_run,
         })
     }
 }
+
+/// Map the zone-level toggles in `config` to Cloudflare zone setting IDs and values
+pub fn zone_setting_updates(config: &CloudflareConfig) -> Vec<ZoneSettingUpdate> {
+    let on_off = |enabled: bool| json!(if enabled { "on" } else { "off" });
+    let minify = |kind: &str| on_off(config.auto_minify.iter().any(|m| m == kind));
+    let update = |field, setting_id, value| ZoneSettingUpdate { field, setting_id, value };
+
+    vec![
+        update("brotli_compression", "brotli", on_off(config.brotli_compression)),
+        update("early_hints", "early_hints", on_off(config.early_hints)),
+        update("rocket_loader", "rocket_loader", on_off(config.rocket_loader)),
+        update(
+            "auto_minify",
+            "minify",
+            json!({ "css": minify("css"), "html": minify("html"), "js": minify("javascript") }),
+        ),
+        update("cache_level", "cache_level", json!(config.cache_level)),
+        update("browser_cache_ttl", "browser_cache_ttl", json!(config.browser_cache_ttl)),
+        update("edge_cache_ttl", "edge_cache_ttl", json!(config.edge_cache_ttl)),
+        update("cache_deception_armor", "cache_deception_armor", on_off(config.cache_deception_armor)),
+        update("security_level", "security_level", json!(config.security_level)),
+        update("challenge_passage", "challenge_ttl", json!(config.challenge_passage)),
+        update("browser_integrity_check", "browser_check", on_off(config.browser_integrity_check)),
+        update("ssl_mode", "ssl", json!(config.ssl_mode)),
+        update("always_use_https", "always_use_https", on_off(config.always_use_https)),
+        update("min_tls_version", "min_tls_version", json!(config.min_tls_version)),
+        update("automatic_https_rewrites", "automatic_https_rewrites", on_off(config.automatic_https_rewrites)),
+        update("opportunistic_encryption", "opportunistic_encryption", on_off(config.opportunistic_encryption)),
+        update("http2", "http2", on_off(config.http2)),
+        update("http3", "http3", on_off(config.http3)),
+        update("zero_rtt", "0rtt", on_off(config.zero_rtt)),
+        update("websockets", "websockets", on_off(config.websockets)),
+        update("polish", "polish", json!(config.polish)),
+        update("webp", "webp", on_off(config.webp)),
+        update("mirage", "mirage", on_off(config.mirage)),
+        update("image_resizing", "image_resizing", on_off(config.image_resizing)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{lazy_pool, mock_client, success};
+    use sqlx::postgres::PgPoolOptions;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_rotate_token_rejects_token_missing_scopes() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/verify"))
+            .respond_with(success(serde_json::json!({ "id": "token", "status": "active" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .and(header("authorization", "Bearer new-token"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 10000, "message": "Authentication error" }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings"))
+            .respond_with(success(serde_json::json!([])))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/dns_records"))
+            .respond_with(success(serde_json::json!([])))
+            .mount(&server)
+            .await;
+
+        let client = mock_client(&server);
+        let settings = SettingsService::new(lazy_pool());
+
+        let err = settings.rotate_token(&client, "new-token").await.unwrap_err();
+        match err {
+            CloudflareError::PermissionDenied(message) => assert!(message.contains("Zone Read")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        // The live client still authenticates with its original token
+        server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/verify"))
+            .and(header("authorization", "Bearer token"))
+            .respond_with(success(serde_json::json!({ "id": "token", "status": "active" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        client.verify_connection().await.unwrap();
+    }
+
+    fn assert_invalid(settings: ExtendedPluginSettings, field: &str) {
+        match settings.validate() {
+            Err(CloudflareError::ValidationError(message)) => assert!(message.contains(field), "{}", message),
+            other => panic!("expected validation error for {}, got {:?}", field, other),
+        }
+    }
+
+    #[test]
+    fn test_extended_settings_validate_boundaries() {
+        let defaults = ExtendedPluginSettings::default();
+        defaults.validate().unwrap();
+
+        let with = |f: fn(&mut ExtendedPluginSettings)| {
+            let mut settings = ExtendedPluginSettings::default();
+            f(&mut settings);
+            settings
+        };
+
+        with(|s| s.auto_purge_delay_ms = MAX_AUTO_PURGE_DELAY_MS).validate().unwrap();
+        assert_invalid(with(|s| s.auto_purge_delay_ms = MAX_AUTO_PURGE_DELAY_MS + 1), "auto_purge_delay_ms");
+
+        with(|s| s.auto_purge_quota_pause_minutes = 1).validate().unwrap();
+        assert_invalid(with(|s| s.auto_purge_quota_pause_minutes = 0), "auto_purge_quota_pause_minutes");
+
+        with(|s| s.analytics_retention_days = 1).validate().unwrap();
+        with(|s| s.analytics_retention_days = 365).validate().unwrap();
+        assert_invalid(with(|s| s.analytics_retention_days = 0), "analytics_retention_days");
+        assert_invalid(with(|s| s.analytics_retention_days = 366), "analytics_retention_days");
+
+        with(|s| s.development_mode_duration = 1440).validate().unwrap();
+        assert_invalid(with(|s| s.development_mode_duration = 0), "development_mode_duration");
+        assert_invalid(with(|s| s.development_mode_duration = 90), "development_mode_duration");
+
+        with(|s| s.cache_warming_schedule = "manual".to_string()).validate().unwrap();
+        assert_invalid(with(|s| s.cache_warming_schedule = "weekly".to_string()), "cache_warming_schedule");
+
+        with(|s| s.security_slack_webhook = Some(String::new())).validate().unwrap();
+        with(|s| s.security_slack_webhook = Some("https://hooks.slack.com/services/T0/B0/x".to_string()))
+            .validate()
+            .unwrap();
+        assert_invalid(
+            with(|s| s.security_slack_webhook = Some("http://hooks.slack.com/services/T0/B0/x".to_string())),
+            "security_slack_webhook",
+        );
+        assert_invalid(with(|s| s.security_slack_webhook = Some("not a url".to_string())), "security_slack_webhook");
+
+        with(|s| s.under_attack_threshold = 1).validate().unwrap();
+        assert_invalid(with(|s| s.under_attack_threshold = 0), "under_attack_threshold");
+        with(|s| s.under_attack_window_minutes = 60).validate().unwrap();
+        assert_invalid(with(|s| s.under_attack_window_minutes = 0), "under_attack_window_minutes");
+        assert_invalid(with(|s| s.under_attack_window_minutes = 61), "under_attack_window_minutes");
+        assert_invalid(with(|s| s.under_attack_cooldown_minutes = 0), "under_attack_cooldown_minutes");
+
+        with(|s| s.dns_scan_probes = vec![DnsProbe::new("@", "TXT")]).validate().unwrap();
+        assert_invalid(with(|s| s.dns_scan_probes = Vec::new()), "dns_scan_probes");
+        assert_invalid(with(|s| s.dns_scan_probes = vec![DnsProbe::new("@", "SRV")]), "SRV");
+        with(|s| s.dns_scan_nameservers = Some("ns1.example.net, 192.0.2.53".to_string())).validate().unwrap();
+        assert_invalid(with(|s| s.dns_scan_nameservers = Some("not a host".to_string())), "not a host");
+    }
+
+    #[tokio::test]
+    async fn test_update_extended_settings_rejects_invalid_before_saving() {
+        // Lazy pool that can't connect: validation has to fail before any write
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost:1/unreachable")
+            .unwrap();
+        let settings = SettingsService::new(pool);
+        let invalid = ExtendedPluginSettings { analytics_retention_days: 0, ..Default::default() };
+
+        let err = settings.update_extended_settings(&invalid).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(_)));
+    }
+
+    #[test]
+    fn test_zone_setting_updates_map_config_fields() {
+        let config = CloudflareConfig {
+            brotli_compression: false,
+            zero_rtt: true,
+            min_tls_version: "1.3".to_string(),
+            ssl_mode: crate::config::SslMode::Full,
+            security_level: crate::config::SecurityLevel::UnderAttack,
+            challenge_passage: 900,
+            auto_minify: vec!["css".to_string(), "javascript".to_string()],
+            ..Default::default()
+        };
+        let updates = zone_setting_updates(&config);
+        let find = |field: &str| updates.iter().find(|u| u.field == field).unwrap();
+
+        assert_eq!((find("brotli_compression").setting_id, &find("brotli_compression").value), ("brotli", &json!("off")));
+        assert_eq!((find("zero_rtt").setting_id, &find("zero_rtt").value), ("0rtt", &json!("on")));
+        assert_eq!((find("min_tls_version").setting_id, &find("min_tls_version").value), ("min_tls_version", &json!("1.3")));
+        assert_eq!((find("ssl_mode").setting_id, &find("ssl_mode").value), ("ssl", &json!("full")));
+        assert_eq!(find("security_level").value, json!("under_attack"));
+        assert_eq!((find("challenge_passage").setting_id, &find("challenge_passage").value), ("challenge_ttl", &json!(900)));
+        assert_eq!(find("auto_minify").value, json!({ "css": "on", "html": "off", "js": "on" }));
+    }
+
+    #[tokio::test]
+    async fn test_apply_zone_settings_skips_unavailable_and_keeps_going_after_failure() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings"))
+            .respond_with(success(json!([
+                { "id": "brotli", "value": "on", "editable": true },
+                { "id": "http3", "value": "off", "editable": true },
+                { "id": "polish", "value": "off", "editable": false },
+                { "id": "0rtt", "value": "off", "editable": true }
+            ])))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/http3"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "success": false,
+                "errors": [{ "code": 1007, "message": "Invalid value" }],
+                "messages": [],
+                "result": null
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/0rtt"))
+            .respond_with(success(json!({ "id": "0rtt", "value": "on", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost:1/rustpress_test")
+            .unwrap();
+        let settings = SettingsService::new(pool).with_client(mock_client(&server));
+
+        let updates = zone_setting_updates(&CloudflareConfig::default())
+            .into_iter()
+            .filter(|u| ["brotli", "http3", "polish", "0rtt", "webp"].contains(&u.setting_id))
+            .collect();
+        let results = settings.apply_zone_setting_updates(updates).await.unwrap();
+        let outcome = |id: &str| results.iter().find(|r| r.setting_id == id).unwrap().outcome;
+
+        assert_eq!(outcome("brotli"), ZoneSettingOutcome::Unchanged);
+        assert_eq!(outcome("http3"), ZoneSettingOutcome::Failed);
+        assert_eq!(outcome("polish"), ZoneSettingOutcome::Skipped);
+        assert_eq!(outcome("0rtt"), ZoneSettingOutcome::Applied);
+        assert_eq!(outcome("webp"), ZoneSettingOutcome::Skipped);
+    }
+
+    #[test]
+    fn test_settings_export_redacts_secrets_by_default() {
+        let rows = || {
+            vec![
+                ("account_id".to_string(), Some(json!("acct"))),
+                ("api_token".to_string(), Some(json!("secret-token"))),
+                ("cdn_enabled".to_string(), Some(json!(true))),
+                ("r2_default_bucket".to_string(), None),
+            ]
+        };
+
+        let export = settings_export(rows(), false);
+        assert_eq!(export["version"], json!(SETTINGS_EXPORT_VERSION));
+        assert_eq!(
+            export["settings"],
+            json!({
+                "account_id": "acct",
+                "api_token": REDACTED_SETTING,
+                "cdn_enabled": true,
+                "r2_default_bucket": null
+            })
+        );
+        assert_eq!(settings_export(rows(), true)["settings"]["api_token"], json!("secret-token"));
+
+        // A redacted export doesn't wipe the stored token on import
+        let (writes, summary) = plan_settings_import(&export).unwrap();
+        assert_eq!(writes, vec![("account_id".to_string(), json!("acct")), ("cdn_enabled".to_string(), json!(true))]);
+        assert_eq!(summary.skipped, vec!["api_token", "r2_default_bucket"]);
+    }
+
+    #[test]
+    fn test_settings_export_redacts_slack_webhook() {
+        let webhook = "https://hooks.slack.com/services/T000/B000/XXXX";
+        let rows = || vec![("security_slack_webhook".to_string(), Some(json!(webhook)))];
+
+        let export = settings_export(rows(), false);
+        assert_eq!(export["settings"]["security_slack_webhook"], json!(REDACTED_SETTING));
+        assert_eq!(settings_export(rows(), true)["settings"]["security_slack_webhook"], json!(webhook));
+
+        let (writes, summary) = plan_settings_import(&export).unwrap();
+        assert!(writes.is_empty());
+        assert_eq!(summary.skipped, vec!["security_slack_webhook"]);
+    }
+
+    #[test]
+    fn test_settings_import_checks_types_and_skips_unknown_keys() {
+        let (writes, summary) = plan_settings_import(&json!({ "settings": {
+            "auto_purge_delay_ms": 250,
+            "auto_purge_on_quota_exhausted": "disable_auto_purge",
+            "feature_flags": { "r2": true },
+            "legacy_option": "x"
+        }}))
+        .unwrap();
+        assert_eq!(writes.len(), 3);
+        assert_eq!(summary.unknown, vec!["legacy_option"]);
+
+        for (key, value) in [
+            ("cdn_enabled", json!("yes")),
+            ("auto_purge_delay_ms", json!(-1)),
+            ("auto_purge_on_quota_exhausted", json!("explode")),
+            ("feature_flags", json!({ "r2": "on" })),
+            ("zone_id", json!(42)),
+        ] {
+            let err = plan_settings_import(&json!({ "settings": { key: value } })).unwrap_err();
+            assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains(key)), "{}", key);
+        }
+        assert!(plan_settings_import(&json!({ "cdn_enabled": true })).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_settings_export_import_round_trip() {
+        let pool = sqlx::PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let settings = SettingsService::new(pool);
+        settings.set_setting("api_token", &json!("round-trip-token")).await.unwrap();
+        settings.set_setting("auto_purge_delay_ms", &json!(750)).await.unwrap();
+        settings.set_setting("cache_warming_schedule", &json!("daily")).await.unwrap();
+
+        let export = settings.export_all(true).await.unwrap();
+        settings.set_setting("auto_purge_delay_ms", &json!(100)).await.unwrap();
+        settings.delete_setting("cache_warming_schedule").await.unwrap();
+
+        // Without overwrite only the missing setting comes back
+        let summary = settings.import_all(&export, false).await.unwrap();
+        assert!(summary.imported.contains(&"cache_warming_schedule".to_string()));
+        assert!(summary.skipped.contains(&"auto_purge_delay_ms".to_string()));
+        assert_eq!(settings.get_setting("auto_purge_delay_ms").await.unwrap(), Some(json!(100)));
+
+        settings.import_all(&export, true).await.unwrap();
+        assert_eq!(settings.get_setting("auto_purge_delay_ms").await.unwrap(), Some(json!(750)));
+        assert_eq!(settings.get_setting("cache_warming_schedule").await.unwrap(), Some(json!("daily")));
+        assert_eq!(settings.get_setting("api_token").await.unwrap(), Some(json!("round-trip-token")));
+        assert_eq!(settings.export_all(false).await.unwrap()["settings"]["api_token"], json!(REDACTED_SETTING));
+    }
+}
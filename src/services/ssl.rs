@@ -0,0 +1,302 @@
+//! SSL/TLS service
+//!
+//! SSL mode, TLS versions and custom hostnames (SSL for SaaS)
+
+use crate::client::CloudflareClient;
+use crate::error::{CloudflareError, CloudflareResult};
+use crate::models::{CustomHostname, DeleteResponse, SslSettings, ZoneSetting, CUSTOM_HOSTNAME_SSL_METHODS};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::info;
+
+/// SSL modes a zone can use
+pub const SSL_MODES: &[&str] = &["off", "flexible", "full", "strict"];
+/// TLS versions accepted as a zone's minimum
+pub const TLS_VERSIONS: &[&str] = &["1.0", "1.1", "1.2", "1.3"];
+
+/// SSL/TLS service
+pub struct SslService {
+    client: Option<Arc<CloudflareClient>>,
+    #[allow(dead_code)]
+    db: PgPool,
+}
+
+impl SslService {
+    /// Create a new SSL service
+    pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
+        Self {
+            client: Some(client),
+            db,
+        }
+    }
+
+    /// Create a new SSL service without a client
+    pub fn new_unconfigured(db: PgPool) -> Self {
+        Self { client: None, db }
+    }
+
+    /// Get the client or return an error if not configured
+    fn get_client(&self) -> CloudflareResult<&CloudflareClient> {
+        self.client.as_ref()
+            .map(|c| c.as_ref())
+            .ok_or(CloudflareError::NotConfigured)
+    }
+
+    /// Set the zone's SSL mode
+    pub async fn set_ssl_mode(&self, mode: &str) -> CloudflareResult<SslSettings> {
+        if !SSL_MODES.contains(&mode) {
+            return Err(CloudflareError::ValidationError(format!(
+                "Invalid SSL mode '{}'. Valid options: {}",
+                mode,
+                SSL_MODES.join(", ")
+            )));
+        }
+
+        let updated = self.get_client()?.update_ssl_mode(mode).await?;
+        info!("SSL mode set to {}", mode);
+        Ok(updated)
+    }
+
+    /// Set the oldest TLS version visitors may connect with
+    pub async fn set_min_tls_version(&self, version: &str) -> CloudflareResult<ZoneSetting> {
+        if !TLS_VERSIONS.contains(&version) {
+            return Err(CloudflareError::ValidationError(format!(
+                "Invalid minimum TLS version '{}'. Valid options: {}",
+                version,
+                TLS_VERSIONS.join(", ")
+            )));
+        }
+
+        let updated = self
+            .get_client()?
+            .update_zone_setting("min_tls_version", serde_json::json!(version))
+            .await?;
+        info!("Minimum TLS version set to {}", version);
+        Ok(updated)
+    }
+
+    /// Enable or disable TLS 1.3
+    pub async fn set_tls_1_3(&self, enabled: bool) -> CloudflareResult<ZoneSetting> {
+        let updated = self
+            .get_client()?
+            .update_zone_setting("tls_1_3", serde_json::json!(if enabled { "on" } else { "off" }))
+            .await?;
+        info!("TLS 1.3 {}", if enabled { "enabled" } else { "disabled" });
+        Ok(updated)
+    }
+
+    /// List custom hostnames
+    pub async fn list_custom_hostnames(&self) -> CloudflareResult<Vec<CustomHostname>> {
+        self.get_client()?.list_custom_hostnames().await
+    }
+
+    /// Add a customer domain, validating its certificate with `ssl_method`.
+    ///
+    /// The returned hostname's `dcv_records()` are what the customer has to
+    /// publish before it goes active.
+    pub async fn create_custom_hostname(&self, hostname: &str, ssl_method: &str) -> CloudflareResult<CustomHostname> {
+        let hostname = hostname.trim().trim_end_matches('.').to_lowercase();
+        validate_hostname(&hostname)?;
+        if !CUSTOM_HOSTNAME_SSL_METHODS.contains(&ssl_method) {
+            return Err(CloudflareError::ValidationError(format!(
+                "Invalid SSL validation method '{}'. Valid options: {}",
+                ssl_method,
+                CUSTOM_HOSTNAME_SSL_METHODS.join(", ")
+            )));
+        }
+
+        let client = self.get_client()?;
+        let created = client.create_custom_hostname(&hostname, ssl_method).await?;
+        info!("Created custom hostname {} ({})", created.hostname, created.id);
+        Ok(created)
+    }
+
+    /// Get a custom hostname
+    pub async fn get_custom_hostname(&self, id: &str) -> CloudflareResult<CustomHostname> {
+        self.get_client()?.get_custom_hostname(id).await
+    }
+
+    /// Remove a custom hostname
+    pub async fn delete_custom_hostname(&self, id: &str) -> CloudflareResult<DeleteResponse> {
+        let deleted = self.get_client()?.delete_custom_hostname(id).await?;
+        info!("Deleted custom hostname {}", id);
+        Ok(deleted)
+    }
+}
+
+/// Check `hostname` is a fully qualified domain name
+fn validate_hostname(hostname: &str) -> CloudflareResult<()> {
+    let labels: Vec<&str> = hostname.split('.').collect();
+    let valid = hostname.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(CloudflareError::ValidationError(format!("Invalid hostname: {}", hostname)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DcvRecord;
+    use crate::test_support::{mock_service, success};
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer};
+
+    #[tokio::test]
+    async fn test_create_custom_hostname_request_and_dcv_records() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/custom_hostnames"))
+            .and(body_json(serde_json::json!({
+                "hostname": "shop.customer.com",
+                "ssl": { "method": "txt", "type": "dv" }
+            })))
+            .respond_with(success(serde_json::json!({
+                "id": "ch-1",
+                "hostname": "shop.customer.com",
+                "status": "pending",
+                "ssl": {
+                    "id": "ssl-1",
+                    "type": "dv",
+                    "method": "txt",
+                    "status": "pending_validation",
+                    "validation_records": [{
+                        "txt_name": "_acme-challenge.shop.customer.com",
+                        "txt_value": "ca3-token"
+                    }]
+                },
+                "ownership_verification": {
+                    "type": "txt",
+                    "name": "_cf-custom-hostname.shop.customer.com",
+                    "value": "5cc07c04-ea62-4a5a-95f0-419334a875a4"
+                },
+                "created_at": "2026-01-01T00:00:00Z"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let created = mock_service(&server, SslService::new)
+            .create_custom_hostname(" Shop.Customer.com. ", "txt")
+            .await
+            .unwrap();
+
+        assert_eq!(created.id, "ch-1");
+        assert_eq!(
+            created.dcv_records(),
+            vec![
+                DcvRecord {
+                    record_type: "TXT".to_string(),
+                    name: "_cf-custom-hostname.shop.customer.com".to_string(),
+                    value: "5cc07c04-ea62-4a5a-95f0-419334a875a4".to_string(),
+                },
+                DcvRecord {
+                    record_type: "TXT".to_string(),
+                    name: "_acme-challenge.shop.customer.com".to_string(),
+                    value: "ca3-token".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dcv_records_for_http_and_email_validation() {
+        let hostname: CustomHostname = serde_json::from_value(serde_json::json!({
+            "id": "ch-2",
+            "hostname": "blog.customer.com",
+            "ssl": {
+                "method": "http",
+                "validation_records": [
+                    {
+                        "http_url": "http://blog.customer.com/.well-known/pki-validation/ca3.txt",
+                        "http_body": "ca3-body"
+                    },
+                    { "emails": ["admin@customer.com"] }
+                ]
+            }
+        }))
+        .unwrap();
+
+        let records = hostname.dcv_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].record_type, "HTTP");
+        assert_eq!(records[0].value, "ca3-body");
+        assert_eq!(records[1].record_type, "EMAIL");
+        assert_eq!(records[1].name, "admin@customer.com");
+    }
+
+    #[tokio::test]
+    async fn test_create_custom_hostname_validates_input() {
+        let server = MockServer::start().await;
+        let ssl = mock_service(&server, SslService::new);
+
+        for (hostname, method) in [
+            ("https://shop.customer.com", "txt"),
+            ("localhost", "txt"),
+            ("-bad.customer.com", "txt"),
+            ("shop.customer.com", "dns"),
+        ] {
+            let err = ssl.create_custom_hostname(hostname, method).await.unwrap_err();
+            assert!(matches!(err, CloudflareError::ValidationError(_)), "{} / {}", hostname, method);
+        }
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_set_min_tls_version_validates_and_patches() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/min_tls_version"))
+            .and(body_json(serde_json::json!({ "value": "1.2" })))
+            .respond_with(success(serde_json::json!({ "id": "min_tls_version", "value": "1.2", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let ssl = mock_service(&server, SslService::new);
+        for version in ["1.4", "TLS1.2", "", "1"] {
+            let err = ssl.set_min_tls_version(version).await.unwrap_err();
+            assert!(matches!(err, CloudflareError::ValidationError(_)), "{}", version);
+        }
+
+        let updated = ssl.set_min_tls_version("1.2").await.unwrap();
+        assert_eq!(updated.value, serde_json::json!("1.2"));
+    }
+
+    #[tokio::test]
+    async fn test_set_ssl_mode_and_tls_1_3_call_cloudflare() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/ssl"))
+            .and(body_json(serde_json::json!({ "value": "full" })))
+            .respond_with(success(serde_json::json!({ "id": "ssl", "value": "full", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/tls_1_3"))
+            .and(body_json(serde_json::json!({ "value": "off" })))
+            .respond_with(success(serde_json::json!({ "id": "tls_1_3", "value": "off", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let ssl = mock_service(&server, SslService::new);
+        assert_eq!(ssl.set_ssl_mode("full").await.unwrap().value, "full");
+        assert_eq!(ssl.set_tls_1_3(false).await.unwrap().value, serde_json::json!("off"));
+        assert!(matches!(
+            ssl.set_ssl_mode("very-strict").await,
+            Err(CloudflareError::ValidationError(_))
+        ));
+    }
+}
@@ -4,15 +4,28 @@
 
 use crate::client::CloudflareClient;
 use crate::error::{CloudflareError, CloudflareResult};
-use crate::models::{StreamVideo, LiveInput, CreateLiveInput, StreamStats};
+use crate::models::{
+    CreateLiveInput, LiveInput, Page, Pagination, StreamAccessRule, StreamCaption, StreamCopyRequest,
+    StreamListParams, StreamStats, StreamTokenRequest, StreamVideo, StreamVideoPage, StreamWebhook,
+};
+use crate::services::settings::SettingsService;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use sqlx::PgPool;
 use std::sync::Arc;
-use tracing::{debug, info};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Longest lifetime allowed for a signed URL token
+pub const MAX_SIGNED_URL_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Oldest webhook signature timestamp that is still accepted
+pub const WEBHOOK_MAX_AGE: Duration = Duration::from_secs(5 * 60);
 
 /// Stream video service
 pub struct StreamService {
     client: Option<Arc<CloudflareClient>>,
-    #[allow(dead_code)]
     db: PgPool,
 }
 
@@ -50,12 +63,53 @@ impl StreamService {
         Ok(videos)
     }
 
+    /// List a page of videos; pass `next_cursor` back as `cursor` for the next one
+    pub async fn list_videos_paginated(&self, params: StreamListParams) -> CloudflareResult<StreamVideoPage> {
+        let client = self.client()?;
+        let page = client.list_stream_videos_paginated(&params).await?;
+        debug!(
+            "Found {} Stream videos (more: {})",
+            page.videos.len(),
+            page.next_cursor.is_some()
+        );
+        Ok(page)
+    }
+
+    /// One page of videos, cut from a single upstream list call.
+    ///
+    /// Stream has no page numbers, so `total` counts the videos fetched in
+    /// that call; the returned cursor continues past them.
+    pub async fn list_videos_page(
+        &self,
+        params: StreamListParams,
+        pagination: Pagination,
+    ) -> CloudflareResult<(Page<StreamVideo>, Option<String>)> {
+        let fetched = self.list_videos_paginated(params).await?;
+        Ok((Page::from_vec(fetched.videos, pagination), fetched.next_cursor))
+    }
+
     /// Get a video by ID
     pub async fn get_video(&self, video_id: &str) -> CloudflareResult<StreamVideo> {
         let client = self.client()?;
         client.get_stream_video(video_id).await
     }
 
+    /// Copy a video into Stream from an http(s) URL.
+    ///
+    /// The video is returned straight away, usually still processing.
+    pub async fn copy_from_url(
+        &self,
+        url: &str,
+        meta: Option<serde_json::Value>,
+    ) -> CloudflareResult<StreamVideo> {
+        let request = copy_request(url, meta)?;
+        let client = self.client()?;
+        info!("Copying video into Stream from {}", request.url);
+        let video = client.copy_stream_video(&request).await?;
+        info!("Stream video created from URL: {}", video.uid);
+        Ok(video)
+    }
+
     /// Delete a video
     pub async fn delete_video(&self, video_id: &str) -> CloudflareResult<()> {
         let client = self.client()?;
@@ -65,6 +119,38 @@ impl StreamService {
         Ok(())
     }
 
+    // =========================================================================
+    // Captions
+    // =========================================================================
+
+    /// List caption tracks on a video
+    pub async fn list_captions(&self, video_id: &str) -> CloudflareResult<Vec<StreamCaption>> {
+        let client = self.client()?;
+        client.list_stream_captions(video_id).await
+    }
+
+    /// Upload WebVTT captions for `language`, replacing any existing track
+    pub async fn upload_caption(
+        &self,
+        video_id: &str,
+        language: &str,
+        vtt: &str,
+    ) -> CloudflareResult<StreamCaption> {
+        validate_caption_language(language)?;
+        validate_webvtt(vtt)?;
+        let client = self.client()?;
+        info!("Uploading {} captions for Stream video {}", language, video_id);
+        client.upload_stream_caption(video_id, language, vtt).await
+    }
+
+    /// Remove the caption track for `language`
+    pub async fn delete_caption(&self, video_id: &str, language: &str) -> CloudflareResult<()> {
+        validate_caption_language(language)?;
+        let client = self.client()?;
+        info!("Deleting {} captions from Stream video {}", language, video_id);
+        client.delete_stream_caption(video_id, language).await
+    }
+
     /// Get embed URL for a video
     pub fn get_embed_url(&self, video_id: &str) -> String {
         format!(
@@ -73,6 +159,26 @@ impl StreamService {
         )
     }
 
+    /// Create a signed URL token for a video that requires signed URLs
+    pub async fn generate_signed_token(
+        &self,
+        video_id: &str,
+        ttl: Duration,
+        restrictions: SignedUrlRestrictions,
+    ) -> CloudflareResult<String> {
+        let request = signed_token_request(ttl, &restrictions, Utc::now())?;
+        let client = self.client()?;
+        debug!("Creating signed token for Stream video {} (ttl {}s)", video_id, ttl.as_secs());
+        client.create_stream_token(video_id, &request).await
+    }
+
+    /// Get embed URL for a signed token.
+    ///
+    /// The token already identifies the video, so it takes the place of the video ID.
+    pub fn get_signed_embed_url(&self, token: &str) -> String {
+        self.get_embed_url(token)
+    }
+
     /// Get HLS URL for a video
     pub fn get_hls_url(&self, video_id: &str) -> String {
         format!(
@@ -168,6 +274,21 @@ impl StreamService {
         Ok(inputs)
     }
 
+    /// Get a live input by ID
+    pub async fn get_live_input(&self, input_id: &str) -> CloudflareResult<LiveInput> {
+        let client = self.client()?;
+        client.get_live_input(input_id).await
+    }
+
+    /// Delete a live input
+    pub async fn delete_live_input(&self, input_id: &str) -> CloudflareResult<()> {
+        let client = self.client()?;
+        info!("Deleting live input: {}", input_id);
+        client.delete_live_input(input_id).await?;
+        info!("Live input deleted: {}", input_id);
+        Ok(())
+    }
+
     /// Create a live input
     pub async fn create_live_input(&self, input: CreateLiveInput) -> CloudflareResult<LiveInput> {
         let client = self.client()?;
@@ -217,6 +338,91 @@ impl StreamService {
         live_input.webrtc.as_ref().map(|w| w.url.clone())
     }
 
+    // =========================================================================
+    // Webhooks
+    // =========================================================================
+
+    /// Get the current webhook subscription
+    pub async fn get_webhook(&self) -> CloudflareResult<StreamWebhook> {
+        let client = self.client()?;
+        client.get_stream_webhook().await
+    }
+
+    /// Send Stream notifications to `notification_url`, keeping the signing secret
+    pub async fn set_webhook(&self, notification_url: &str) -> CloudflareResult<StreamWebhook> {
+        let parsed = url::Url::parse(notification_url)
+            .map_err(|e| CloudflareError::ValidationError(format!("Invalid webhook URL: {}", e)))?;
+        if parsed.scheme() != "https" {
+            return Err(CloudflareError::ValidationError(
+                "Webhook URL must use https".to_string(),
+            ));
+        }
+
+        let client = self.client()?;
+        let webhook = client.set_stream_webhook(notification_url).await?;
+        match &webhook.secret {
+            Some(secret) => {
+                SettingsService::new(self.db.clone())
+                    .save_stream_webhook_secret(secret)
+                    .await?
+            }
+            None => warn!("Stream webhook saved without a signing secret"),
+        }
+        info!("Stream webhook set to {}", webhook.notification_url);
+        Ok(webhook)
+    }
+
+    /// Stop sending Stream notifications
+    pub async fn delete_webhook(&self) -> CloudflareResult<()> {
+        let client = self.client()?;
+        client.delete_stream_webhook().await?;
+        SettingsService::new(self.db.clone())
+            .delete_setting("stream_webhook_secret")
+            .await?;
+        info!("Stream webhook removed");
+        Ok(())
+    }
+
+    /// Verify an incoming notification and record it.
+    ///
+    /// Returns the UID of the video the notification is about.
+    pub async fn handle_webhook(&self, signature: Option<&str>, body: &[u8]) -> CloudflareResult<String> {
+        let secret = SettingsService::new(self.db.clone())
+            .get_stream_webhook_secret()
+            .await?
+            .ok_or_else(|| CloudflareError::AuthenticationError(
+                "Stream webhook secret is not configured".to_string(),
+            ))?;
+        let signature = signature.ok_or_else(|| {
+            CloudflareError::AuthenticationError("Missing Webhook-Signature header".to_string())
+        })?;
+        verify_webhook_signature(&secret, signature, body, Utc::now())?;
+
+        let payload: serde_json::Value = serde_json::from_slice(body)
+            .map_err(|e| CloudflareError::ValidationError(format!("Invalid webhook payload: {}", e)))?;
+        let uid = payload["uid"]
+            .as_str()
+            .ok_or_else(|| CloudflareError::ValidationError("Webhook payload has no uid".to_string()))?
+            .to_string();
+        let state = payload["status"]["state"].as_str().map(str::to_string);
+
+        sqlx::query(
+            r#"
+            INSERT INTO cloudflare_stream_events (video_uid, state, details, created_at)
+            VALUES ($1, $2, $3, NOW())
+            "#,
+        )
+        .bind(&uid)
+        .bind(&state)
+        .bind(&payload)
+        .execute(&self.db)
+        .await
+        .map_err(|e| CloudflareError::DatabaseError(e.to_string()))?;
+
+        info!("Stream webhook: video {} is {}", uid, state.as_deref().unwrap_or("unknown"));
+        Ok(uid)
+    }
+
     // =========================================================================
     // Statistics
     // =========================================================================
@@ -257,22 +463,138 @@ impl StreamService {
         Ok(videos.into_iter().filter(|v| v.ready_to_stream == ready).collect())
     }
 
-    /// Search videos by name
+    /// Search videos by name, newest first
     pub async fn search_videos(&self, query: &str) -> CloudflareResult<Vec<StreamVideo>> {
-        let videos = self.list_videos().await?;
-        let query_lower = query.to_lowercase();
-        Ok(videos
-            .into_iter()
-            .filter(|v| {
-                v.meta
-                    .as_ref()
-                    .and_then(|m| m.get("name"))
-                    .and_then(|n| n.as_str())
-                    .map(|n| n.to_lowercase().contains(&query_lower))
-                    .unwrap_or(false)
-            })
-            .collect())
+        let params = StreamListParams {
+            search: Some(query.to_string()),
+            ..Default::default()
+        };
+        Ok(self.list_videos_paginated(params).await?.videos)
+    }
+}
+
+/// Restrictions baked into a signed URL token
+#[derive(Debug, Clone, Default)]
+pub struct SignedUrlRestrictions {
+    /// Allow the MP4 download of the video with this token
+    pub downloadable: bool,
+    /// Reject the token before this time
+    pub not_before: Option<DateTime<Utc>>,
+    /// Country and IP rules, evaluated in order
+    pub access_rules: Vec<StreamAccessRule>,
+}
+
+/// Check a `Webhook-Signature` header of the form `time=<unix>,sig1=<hex>`.
+///
+/// `sig1` is the HMAC-SHA256 of `"<time>.<body>"` keyed with the webhook secret.
+pub fn verify_webhook_signature(
+    secret: &str,
+    header: &str,
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> CloudflareResult<()> {
+    let invalid = |reason: &str| CloudflareError::AuthenticationError(format!(
+        "Invalid webhook signature: {}",
+        reason
+    ));
+
+    let mut time = None;
+    let mut sig = None;
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("time", value)) => time = Some(value),
+            Some(("sig1", value)) => sig = Some(value),
+            _ => {}
+        }
+    }
+    let time = time.ok_or_else(|| invalid("missing time"))?;
+    let sig = sig.ok_or_else(|| invalid("missing sig1"))?;
+
+    let timestamp: i64 = time.parse().map_err(|_| invalid("malformed time"))?;
+    if (now.timestamp() - timestamp).unsigned_abs() > WEBHOOK_MAX_AGE.as_secs() {
+        return Err(invalid("timestamp is too old"));
+    }
+
+    let expected = hex::decode(sig).map_err(|_| invalid("malformed sig1"))?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|_| invalid("unusable secret"))?;
+    mac.update(time.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac.verify_slice(&expected).map_err(|_| invalid("signature mismatch"))
+}
+
+/// Check a caption language is a well-formed BCP-47 tag such as `en`,
+/// `pt-BR`, `zh-Hant` or `es-419`
+fn validate_caption_language(language: &str) -> CloudflareResult<()> {
+    let mut subtags = language.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    let primary_ok = (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_alphabetic());
+    let rest_ok = subtags.all(|t| (1..=8).contains(&t.len()) && t.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    if primary_ok && rest_ok {
+        Ok(())
+    } else {
+        Err(CloudflareError::ValidationError(format!(
+            "Invalid caption language '{}': expected a BCP-47 tag such as 'en' or 'pt-BR'",
+            language
+        )))
+    }
+}
+
+/// Check a caption file starts with the `WEBVTT` header
+fn validate_webvtt(vtt: &str) -> CloudflareResult<()> {
+    let vtt = vtt.strip_prefix('\u{feff}').unwrap_or(vtt);
+    let header_ok = vtt
+        .strip_prefix("WEBVTT")
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n']));
+
+    if header_ok {
+        Ok(())
+    } else {
+        Err(CloudflareError::ValidationError(
+            "Captions must be a WebVTT file starting with a 'WEBVTT' header".to_string(),
+        ))
+    }
+}
+
+/// Build a copy request, rejecting anything but http(s) URLs
+fn copy_request(url: &str, meta: Option<serde_json::Value>) -> CloudflareResult<StreamCopyRequest> {
+    let parsed = url::Url::parse(url.trim())
+        .map_err(|e| CloudflareError::ValidationError(format!("Invalid video URL: {}", e)))?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(CloudflareError::ValidationError(format!(
+            "Video URL must use http or https, got {}",
+            parsed.scheme()
+        )));
+    }
+
+    Ok(StreamCopyRequest {
+        url: parsed.to_string(),
+        meta,
+    })
+}
+
+/// Build the token request body for a TTL starting at `now`
+fn signed_token_request(
+    ttl: Duration,
+    restrictions: &SignedUrlRestrictions,
+    now: DateTime<Utc>,
+) -> CloudflareResult<StreamTokenRequest> {
+    if ttl.is_zero() || ttl > MAX_SIGNED_URL_TTL {
+        return Err(CloudflareError::ValidationError(format!(
+            "Signed URL TTL must be between 1 and {} seconds",
+            MAX_SIGNED_URL_TTL.as_secs()
+        )));
     }
+
+    Ok(StreamTokenRequest {
+        exp: now.timestamp() + ttl.as_secs() as i64,
+        nbf: restrictions.not_before.map(|t| t.timestamp()),
+        downloadable: restrictions.downloadable.then_some(true),
+        access_rules: restrictions.access_rules.clone(),
+    })
 }
 
 /// Options for video embed code generation
@@ -333,3 +655,191 @@ impl EmbedOptions {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_service, success};
+    use wiremock::matchers::{body_json, body_string_contains, header_regex, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_caption_language_validation() {
+        for language in ["en", "fra", "pt-BR", "zh-Hant", "es-419", "sr-Latn-RS"] {
+            assert!(validate_caption_language(language).is_ok(), "{}", language);
+        }
+        for language in ["", "e", "english", "en_US", "en-", "-en", "pt-BR/../x", "en-toolongsubtag"] {
+            assert!(
+                matches!(validate_caption_language(language), Err(CloudflareError::ValidationError(_))),
+                "{}",
+                language
+            );
+        }
+
+        assert!(validate_webvtt("WEBVTT\n\n00:00.000 --> 00:01.000\nHello").is_ok());
+        assert!(validate_webvtt("\u{feff}WEBVTT - Intro\r\n").is_ok());
+        assert!(validate_webvtt("1\n00:00:00,000 --> 00:00:01,000\nHello").is_err());
+        assert!(validate_webvtt("WEBVTTX").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upload_caption_sends_vtt_file() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/accounts/acct/stream/video-1/captions/pt-BR"))
+            .and(header_regex("content-type", "^multipart/form-data"))
+            .and(body_string_contains("name=\"file\"; filename=\"pt-BR.vtt\""))
+            .and(body_string_contains("WEBVTT\n\n00:00.000 --> 00:01.000\nOlá"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "result": { "language": "pt-BR", "label": "Portuguese (Brazil)", "generated": false, "status": "ready" }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, StreamService::new);
+        let caption = service
+            .upload_caption("video-1", "pt-BR", "WEBVTT\n\n00:00.000 --> 00:01.000\nOlá")
+            .await
+            .unwrap();
+        assert_eq!(caption.language, "pt-BR");
+        assert_eq!(caption.label.as_deref(), Some("Portuguese (Brazil)"));
+
+        // Invalid input is rejected before reaching Cloudflare
+        assert!(service.upload_caption("video-1", "pt_BR", "WEBVTT").await.is_err());
+        assert!(service.upload_caption("video-1", "en", "not captions").await.is_err());
+    }
+
+    #[test]
+    fn test_signed_token_request_body() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let restrictions = SignedUrlRestrictions {
+            downloadable: true,
+            not_before: Some(now),
+            access_rules: vec![StreamAccessRule {
+                rule_type: "ip.geoip.country".to_string(),
+                action: "block".to_string(),
+                country: Some(vec!["US".to_string()]),
+                ip: None,
+            }],
+        };
+
+        let request = signed_token_request(Duration::from_secs(3600), &restrictions, now).unwrap();
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "exp": 1_700_003_600,
+                "nbf": 1_700_000_000,
+                "downloadable": true,
+                "accessRules": [
+                    { "type": "ip.geoip.country", "action": "block", "country": ["US"] }
+                ]
+            })
+        );
+
+        let request =
+            signed_token_request(MAX_SIGNED_URL_TTL, &SignedUrlRestrictions::default(), now).unwrap();
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({ "exp": 1_700_086_400 })
+        );
+
+        assert!(signed_token_request(MAX_SIGNED_URL_TTL + Duration::from_secs(1), &restrictions, now).is_err());
+        assert!(signed_token_request(Duration::ZERO, &restrictions, now).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_signed_embed_url_uses_created_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/stream/video-1/token"))
+            .respond_with(success(serde_json::json!({ "token": "signed.jwt.token" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, StreamService::new);
+
+        let token = service
+            .generate_signed_token("video-1", Duration::from_secs(600), SignedUrlRestrictions::default())
+            .await
+            .unwrap();
+        assert_eq!(token, "signed.jwt.token");
+        assert_eq!(
+            service.get_signed_embed_url(&token),
+            "https://iframe.cloudflarestream.com/signed.jwt.token"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_from_url_returns_processing_video() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/stream/copy"))
+            .and(body_json(serde_json::json!({
+                "url": "https://example.com/videos/intro.mp4",
+                "meta": { "name": "Intro" }
+            })))
+            .respond_with(success(serde_json::json!({
+                "uid": "video-2",
+                "readyToStream": false,
+                "status": { "state": "downloading", "pctComplete": "12.500000", "errorReasonCode": "" },
+                "meta": { "name": "Intro" },
+                "duration": -1
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, StreamService::new);
+
+        let video = service
+            .copy_from_url(
+                "https://example.com/videos/intro.mp4",
+                Some(serde_json::json!({ "name": "Intro" })),
+            )
+            .await
+            .unwrap();
+        assert_eq!(video.uid, "video-2");
+        assert!(!video.ready_to_stream);
+        let status = video.status.unwrap();
+        assert_eq!(status.state, "downloading");
+        assert_eq!(status.pct_complete, Some(12.5));
+
+        for url in ["ftp://example.com/intro.mp4", "file:///tmp/intro.mp4", "not a url"] {
+            assert!(matches!(
+                service.copy_from_url(url, None).await,
+                Err(CloudflareError::ValidationError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_webhook_signature_verification() {
+        let secret = "85011ed3a913c6ad5f9cf6c5573cc0a7";
+        let body = br#"{"uid":"video-1","readyToStream":true,"status":{"state":"ready"}}"#;
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let sign = |time: i64, body: &[u8]| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+            mac.update(format!("{}.", time).as_bytes());
+            mac.update(body);
+            format!("time={},sig1={}", time, hex::encode(mac.finalize().into_bytes()))
+        };
+
+        // Known signature for the secret and payload above
+        let header = "time=1700000000,sig1=a2956e0739fd1bc9f20acdd8f2d45c20bd58bdb529959aa6c0e6db83404f6f9a";
+        assert_eq!(sign(1_700_000_000, body), header);
+        assert!(verify_webhook_signature(secret, header, body, now).is_ok());
+        assert!(verify_webhook_signature(secret, &sign(1_699_999_800, body), body, now).is_ok());
+
+        let stale = sign(1_700_000_000 - 301, body);
+        assert!(verify_webhook_signature(secret, &stale, body, now).is_err());
+
+        let tampered = br#"{"uid":"video-2","readyToStream":true,"status":{"state":"ready"}}"#;
+        assert!(verify_webhook_signature(secret, &sign(1_700_000_000, body), tampered, now).is_err());
+        assert!(verify_webhook_signature("other-secret", &sign(1_700_000_000, body), body, now).is_err());
+        assert!(verify_webhook_signature(secret, "time=1700000000", body, now).is_err());
+        assert!(verify_webhook_signature(secret, "time=1700000000,sig1=zz", body, now).is_err());
+    }
+}
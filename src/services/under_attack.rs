@@ -0,0 +1,275 @@
+//! Under Attack auto-trigger
+//!
+//! Counts threats over a sliding window and switches Under Attack mode on
+//! when they reach the configured threshold, then off again once the zone
+//! has stayed below it for the cooldown. The monitor only ever switches off
+//! a mode it switched on itself; one an admin turned on is left alone.
+
+use crate::client::CloudflareClient;
+use crate::error::CloudflareResult;
+use crate::services::settings::ExtendedPluginSettings;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How often threat counts are polled while the auto-trigger is on
+pub const UNDER_ATTACK_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Security level of Under Attack mode
+pub const UNDER_ATTACK_LEVEL: &str = "under_attack";
+
+/// Security level restored when the level before engaging isn't known
+pub const DEFAULT_SECURITY_LEVEL: &str = "medium";
+
+/// Per-minute threat totals since a point in time
+const THREAT_COUNT_QUERY: &str = r#"
+query ThreatCount($zoneTag: string, $since: Time) {
+  viewer {
+    zones(filter: { zoneTag: $zoneTag }) {
+      httpRequests1mGroups(
+        limit: 100
+        filter: { datetime_geq: $since }
+      ) {
+        sum { threats }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Deserialize)]
+struct ThreatCountData {
+    viewer: ThreatCountViewer,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThreatCountViewer {
+    zones: Vec<ThreatCountZone>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThreatCountZone {
+    #[serde(default, rename = "httpRequests1mGroups")]
+    http_requests_1m_groups: Vec<ThreatCountGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThreatCountGroup {
+    sum: ThreatCountSum,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThreatCountSum {
+    #[serde(default)]
+    threats: u64,
+}
+
+/// Threats the zone has seen at or after `since`
+pub async fn fetch_threat_count(client: &CloudflareClient, since: DateTime<Utc>) -> CloudflareResult<u64> {
+    let variables = serde_json::json!({
+        "zoneTag": client.zone_id(),
+        "since": since.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+    });
+
+    let data: ThreatCountData = client.graphql(THREAT_COUNT_QUERY, variables).await?;
+    Ok(data
+        .viewer
+        .zones
+        .into_iter()
+        .flat_map(|zone| zone.http_requests_1m_groups)
+        .map(|group| group.sum.threats)
+        .sum())
+}
+
+/// When the auto-trigger engages and lets go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnderAttackThresholds {
+    /// Threats within `window` that engage Under Attack mode
+    pub threshold: u64,
+    pub window: Duration,
+    /// Time below the threshold before Under Attack mode is switched off
+    pub cooldown: Duration,
+}
+
+impl From<&ExtendedPluginSettings> for UnderAttackThresholds {
+    fn from(settings: &ExtendedPluginSettings) -> Self {
+        Self {
+            threshold: settings.under_attack_threshold as u64,
+            window: Duration::minutes(settings.under_attack_window_minutes as i64),
+            cooldown: Duration::minutes(settings.under_attack_cooldown_minutes as i64),
+        }
+    }
+}
+
+/// A change the monitor made to Under Attack mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnderAttackTransition {
+    Engaged,
+    Disengaged,
+}
+
+impl UnderAttackTransition {
+    /// Whether Under Attack mode is on afterwards
+    pub fn enabled(self) -> bool {
+        self == Self::Engaged
+    }
+}
+
+/// Tracks whether the auto-trigger has Under Attack mode on, when the last
+/// spike was seen and the security level to go back to.
+///
+/// Stored in settings after every change so an engagement survives a restart.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnderAttackMonitor {
+    engaged: bool,
+    last_spike_at: Option<DateTime<Utc>>,
+    prior_level: Option<String>,
+}
+
+impl UnderAttackMonitor {
+    /// Whether the monitor switched Under Attack mode on and hasn't switched it off yet
+    pub fn is_engaged(&self) -> bool {
+        self.engaged
+    }
+
+    /// Security level the zone had before the monitor engaged
+    pub fn prior_level(&self) -> Option<&str> {
+        self.prior_level.as_deref()
+    }
+
+    /// Remember the security level to restore on disengaging
+    pub fn set_prior_level(&mut self, level: impl Into<String>) {
+        self.prior_level = Some(level.into());
+    }
+
+    /// Feed one threat count taken at `now`, returning the change to make, if any.
+    ///
+    /// Counts at or above the threshold engage (or keep engaged) the
+    /// monitor; it disengages on the first count below the threshold that
+    /// comes at least `cooldown` after the last spike.
+    pub fn observe(
+        &mut self,
+        threats: u64,
+        now: DateTime<Utc>,
+        thresholds: &UnderAttackThresholds,
+    ) -> Option<UnderAttackTransition> {
+        let spiking = threats >= thresholds.threshold;
+        if spiking {
+            self.last_spike_at = Some(now);
+        }
+
+        match (self.engaged, spiking) {
+            (false, true) => {
+                self.engaged = true;
+                Some(UnderAttackTransition::Engaged)
+            }
+            (true, false) if self.last_spike_at.is_none_or(|at| now - at >= thresholds.cooldown) => {
+                self.release();
+                Some(UnderAttackTransition::Disengaged)
+            }
+            _ => None,
+        }
+    }
+
+    /// Forget any engagement, e.g. when the auto-trigger is turned off or
+    /// an admin already has Under Attack mode on
+    pub fn release(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock_client;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn thresholds() -> UnderAttackThresholds {
+        UnderAttackThresholds {
+            threshold: 500,
+            window: Duration::minutes(5),
+            cooldown: Duration::minutes(15),
+        }
+    }
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().with_timezone(&Utc) + Duration::minutes(minute)
+    }
+
+    #[test]
+    fn test_engages_when_threshold_is_crossed() {
+        let mut monitor = UnderAttackMonitor::default();
+        let thresholds = thresholds();
+
+        assert_eq!(monitor.observe(120, at(0), &thresholds), None);
+        assert_eq!(monitor.observe(499, at(1), &thresholds), None);
+        assert!(!monitor.is_engaged());
+
+        assert_eq!(monitor.observe(500, at(2), &thresholds), Some(UnderAttackTransition::Engaged));
+        assert!(monitor.is_engaged());
+        // Still spiking: nothing more to do
+        assert_eq!(monitor.observe(2_000, at(3), &thresholds), None);
+    }
+
+    #[test]
+    fn test_disengages_only_after_cooldown() {
+        let mut monitor = UnderAttackMonitor::default();
+        let thresholds = thresholds();
+
+        assert_eq!(monitor.observe(900, at(0), &thresholds), Some(UnderAttackTransition::Engaged));
+        assert_eq!(monitor.observe(10, at(5), &thresholds), None);
+        assert_eq!(monitor.observe(10, at(14), &thresholds), None);
+
+        // A new spike restarts the cooldown
+        assert_eq!(monitor.observe(600, at(14), &thresholds), None);
+        assert_eq!(monitor.observe(10, at(28), &thresholds), None);
+        assert!(monitor.is_engaged());
+
+        monitor.set_prior_level("high");
+        assert_eq!(monitor.observe(10, at(29), &thresholds), Some(UnderAttackTransition::Disengaged));
+        assert!(!monitor.is_engaged());
+        assert_eq!(monitor.prior_level(), None);
+        assert_eq!(monitor.observe(10, at(30), &thresholds), None);
+
+        // And engages again on the next spike
+        assert_eq!(monitor.observe(500, at(31), &thresholds), Some(UnderAttackTransition::Engaged));
+    }
+
+    #[test]
+    fn test_released_monitor_does_not_disengage() {
+        let mut monitor = UnderAttackMonitor::default();
+        let thresholds = thresholds();
+
+        assert_eq!(monitor.observe(900, at(0), &thresholds), Some(UnderAttackTransition::Engaged));
+        monitor.release();
+        assert_eq!(monitor.observe(0, at(60), &thresholds), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_threat_count_sums_minutes() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {
+                    "viewer": {
+                        "zones": [{
+                            "httpRequests1mGroups": [
+                                { "sum": { "threats": 40 } },
+                                { "sum": { "threats": 2 } },
+                                { "sum": {} }
+                            ]
+                        }]
+                    }
+                },
+                "errors": null
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert_eq!(fetch_threat_count(&mock_client(&server), at(0)).await.unwrap(), 42);
+    }
+}
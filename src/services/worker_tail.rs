@@ -0,0 +1,164 @@
+//! Worker tail (live logs) bridge
+//!
+//! A tail session streams a Worker's trace events over a WebSocket. The
+//! admin UI can't open that socket itself, so the plugin connects to it and
+//! hands the events on; the session is deleted as soon as nobody is reading.
+
+use crate::client::CloudflareClient;
+use crate::error::{CloudflareError, CloudflareResult};
+use futures::stream::{self, Stream, StreamExt};
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::{debug, warn};
+
+/// WebSocket subprotocol Cloudflare serves trace events on
+pub const TAIL_PROTOCOL: &str = "trace-v1";
+
+type TailSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Deletes a tail session when dropped, so a closed browser tab doesn't
+/// leave it running until it expires
+pub struct TailSessionGuard {
+    client: Arc<CloudflareClient>,
+    script: String,
+    id: String,
+}
+
+impl TailSessionGuard {
+    pub fn new(client: Arc<CloudflareClient>, script: &str, id: &str) -> Self {
+        Self { client, script: script.to_string(), id: id.to_string() }
+    }
+}
+
+impl Drop for TailSessionGuard {
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("No runtime to delete tail {} of Worker {}", self.id, self.script);
+            return;
+        };
+        let client = Arc::clone(&self.client);
+        let script = std::mem::take(&mut self.script);
+        let id = std::mem::take(&mut self.id);
+        runtime.spawn(async move {
+            match client.delete_worker_tail(&script, &id).await {
+                Ok(()) => debug!("Deleted tail {} of Worker {}", id, script),
+                Err(e) => warn!("Failed to delete tail {} of Worker {}: {}", id, script, e),
+            }
+        });
+    }
+}
+
+/// Open the tail WebSocket at `url`
+pub async fn connect_tail(url: &str) -> CloudflareResult<TailSocket> {
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| CloudflareError::WorkerError(format!("Invalid tail URL: {}", e)))?;
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", HeaderValue::from_static(TAIL_PROTOCOL));
+
+    let (socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| CloudflareError::NetworkError(format!("Failed to connect to tail: {}", e)))?;
+    Ok(socket)
+}
+
+/// Trace events read from `socket`, ending when it closes.
+///
+/// `guard` lives as long as the stream, so dropping the stream deletes the
+/// session. A read error is yielded once and ends the stream.
+pub fn tail_events(
+    socket: TailSocket,
+    guard: TailSessionGuard,
+) -> impl Stream<Item = CloudflareResult<serde_json::Value>> + Send + 'static {
+    stream::unfold((Some(socket), guard), |(socket, guard)| async move {
+        let mut socket = socket?;
+        loop {
+            let event = match socket.next().await? {
+                Ok(Message::Text(text)) => parse_trace_event(text.as_bytes()),
+                Ok(Message::Binary(data)) => parse_trace_event(&data),
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => continue,
+                Err(e) => {
+                    let error = CloudflareError::NetworkError(format!("Tail connection failed: {}", e));
+                    return Some((Err(error), (None, guard)));
+                }
+            };
+            return Some((Ok(event), (Some(socket), guard)));
+        }
+    })
+}
+
+/// A trace event as JSON, or its text if it isn't JSON
+fn parse_trace_event(data: &[u8]) -> serde_json::Value {
+    serde_json::from_slice(data)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(data).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_client, success, wait_for_requests};
+    use futures::SinkExt;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer};
+
+    async fn mount_tail_delete(server: &MockServer) {
+        Mock::given(method("DELETE"))
+            .and(path("/accounts/acct/workers/scripts/edge/tails/tail-1"))
+            .respond_with(success(serde_json::json!(null)))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_dropping_guard_deletes_session() {
+        let server = MockServer::start().await;
+        mount_tail_delete(&server).await;
+
+        drop(TailSessionGuard::new(mock_client(&server), "edge", "tail-1"));
+
+        wait_for_requests(&server, 1).await;
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_tail_events_forwards_messages_and_cleans_up() {
+        let server = MockServer::start().await;
+        mount_tail_delete(&server).await;
+
+        // A tail endpoint sending one trace event and one non-JSON frame
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(tcp).await.unwrap();
+            let event = serde_json::json!({
+                "outcome": "ok",
+                "scriptName": "edge",
+                "logs": [{ "message": ["hello"], "level": "log", "timestamp": 1700000000000u64 }]
+            });
+            socket.send(Message::Binary(event.to_string().into_bytes())).await.unwrap();
+            socket.send(Message::Text("not json".to_string())).await.unwrap();
+            socket.close(None).await.unwrap();
+        });
+
+        let socket = connect_tail(&url).await.unwrap();
+        let guard = TailSessionGuard::new(mock_client(&server), "edge", "tail-1");
+        let events: Vec<_> = tail_events(socket, guard).collect().await;
+
+        assert_eq!(events.len(), 2);
+        let first = events[0].as_ref().unwrap();
+        assert_eq!(first["scriptName"], "edge");
+        assert_eq!(first["logs"][0]["message"][0], "hello");
+        assert_eq!(events[1].as_ref().unwrap(), &serde_json::json!("not json"));
+
+        wait_for_requests(&server, 1).await;
+        server.verify().await;
+    }
+}
@@ -3,7 +3,11 @@
 use crate::client::CloudflareClient;
 use crate::error::{CloudflareError, CloudflareResult};
 use crate::models::*;
+use crate::services::worker_tail::{connect_tail, tail_events, TailSessionGuard};
+use crate::workers::get_templates;
+use futures::Stream;
 use sqlx::PgPool;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub struct WorkersService {
@@ -26,7 +30,7 @@ impl WorkersService {
     fn get_client(&self) -> CloudflareResult<&CloudflareClient> {
         self.client.as_ref()
             .map(|c| c.as_ref())
-            .ok_or_else(|| CloudflareError::ConfigError("Cloudflare not configured. Please connect your account.".to_string()))
+            .ok_or(CloudflareError::NotConfigured)
     }
 
     pub async fn list_workers(&self) -> CloudflareResult<Vec<Worker>> {
@@ -39,17 +43,78 @@ impl WorkersService {
         client.get_worker(name).await
     }
 
+    /// Bindings, compatibility settings and usage model of a Worker, with secret values redacted
+    pub async fn get_worker_settings(&self, name: &str) -> CloudflareResult<WorkerSettings> {
+        let client = self.get_client()?;
+        let mut settings = client.get_worker_settings(name).await?;
+        redact_secret_bindings(&mut settings.bindings);
+        Ok(settings)
+    }
+
     pub async fn deploy(&self, name: &str, script: &str) -> CloudflareResult<Worker> {
         let client = self.get_client()?;
         client.deploy_worker(name, script).await
     }
 
+    /// Deploy one of the bundled templates as Worker `name`, rendered with `params`
+    pub async fn deploy_from_template(
+        &self,
+        template_id: &str,
+        name: &str,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> CloudflareResult<Worker> {
+        let template = get_templates()
+            .into_iter()
+            .find(|t| t.id == template_id)
+            .ok_or_else(|| CloudflareError::NotFound(format!("Worker template '{}' not found", template_id)))?;
+        let script = template.render(params)?;
+        self.deploy(name, &script).await
+    }
+
+    /// Deploy only if the live script still has `expected_etag`, otherwise `Conflict`
+    pub async fn deploy_if_match(
+        &self,
+        name: &str,
+        script: &str,
+        expected_etag: &str,
+    ) -> CloudflareResult<Worker> {
+        let client = self.get_client()?;
+        client.deploy_worker_if_match(name, script, expected_etag).await
+    }
+
     pub async fn delete(&self, name: &str) -> CloudflareResult<()> {
         let client = self.get_client()?;
         client.delete_worker(name).await?;
         Ok(())
     }
 
+    /// Start a tail (live log) session for `script`
+    pub async fn start_tail(&self, script: &str) -> CloudflareResult<TailSession> {
+        let client = self.get_client()?;
+        client.create_worker_tail(script).await
+    }
+
+    /// End the tail session `id` for `script`
+    pub async fn delete_tail(&self, script: &str, id: &str) -> CloudflareResult<()> {
+        let client = self.get_client()?;
+        client.delete_worker_tail(script, id).await
+    }
+
+    /// Start a tail session and connect to it, returning the session and its
+    /// trace events. The session is deleted once the stream is dropped, or
+    /// right away if connecting fails.
+    pub async fn open_tail(
+        &self,
+        script: &str,
+    ) -> CloudflareResult<(TailSession, impl Stream<Item = CloudflareResult<serde_json::Value>> + Send + 'static)> {
+        let client = self.client.as_ref().ok_or(CloudflareError::NotConfigured)?;
+        let session = self.start_tail(script).await?;
+        let guard = TailSessionGuard::new(Arc::clone(client), script, &session.id);
+
+        let socket = connect_tail(&session.url).await?;
+        Ok((session, tail_events(socket, guard)))
+    }
+
     pub async fn list_routes(&self) -> CloudflareResult<Vec<WorkerRoute>> {
         let client = self.get_client()?;
         client.list_worker_routes().await
@@ -60,17 +125,41 @@ impl WorkersService {
         client.create_worker_route(pattern, script).await
     }
 
+    pub async fn delete_route(&self, id: &str) -> CloudflareResult<DeleteResponse> {
+        let client = self.get_client()?;
+        client.delete_worker_route(id).await
+    }
+
     // KV Operations
     pub async fn list_kv_namespaces(&self) -> CloudflareResult<Vec<KvNamespace>> {
         let client = self.get_client()?;
         client.list_kv_namespaces().await
     }
 
-    pub async fn create_kv_namespace(&self, title: &str) -> CloudflareResult<KvNamespace> {
+    /// Create a KV namespace.
+    ///
+    /// Cloudflare accepts several namespaces with the same title, which are
+    /// then hard to tell apart, so a title already in use is refused with
+    /// `Conflict` unless `allow_duplicate` is set.
+    pub async fn create_kv_namespace(&self, title: &str, allow_duplicate: bool) -> CloudflareResult<KvNamespace> {
         let client = self.get_client()?;
+        if !allow_duplicate {
+            let namespaces = client.list_kv_namespaces().await?;
+            if let Some(existing) = namespaces.iter().find(|ns| ns.title == title) {
+                return Err(CloudflareError::Conflict(format!(
+                    "A KV namespace titled '{}' already exists ({}). Choose another title or allow a duplicate.",
+                    title, existing.id
+                )));
+            }
+        }
         client.create_kv_namespace(title).await
     }
 
+    pub async fn delete_kv_namespace(&self, id: &str) -> CloudflareResult<()> {
+        let client = self.get_client()?;
+        client.delete_kv_namespace(id).await
+    }
+
     pub async fn list_kv_keys(&self, namespace_id: &str) -> CloudflareResult<Vec<KvKey>> {
         let client = self.get_client()?;
         client.list_kv_keys(namespace_id).await
@@ -81,6 +170,16 @@ impl WorkersService {
         client.get_kv_value(namespace_id, key).await
     }
 
+    /// A KV value, typed as text, JSON or binary, with its metadata
+    pub async fn get_kv_entry(&self, namespace_id: &str, key: &str) -> CloudflareResult<KvEntry> {
+        let client = self.get_client()?;
+        let (value, metadata) = futures::try_join!(
+            client.get_kv_value_typed(namespace_id, key),
+            client.get_kv_metadata(namespace_id, key),
+        )?;
+        Ok(KvEntry { key: key.to_string(), value, metadata })
+    }
+
     pub async fn set_kv(&self, namespace_id: &str, key: &str, value: &str) -> CloudflareResult<()> {
         let client = self.get_client()?;
         client.set_kv_value(namespace_id, key, value).await
@@ -91,3 +190,189 @@ impl WorkersService {
         client.delete_kv_value(namespace_id, key).await
     }
 }
+
+/// Binding types whose values must never be returned to the client
+const SECRET_BINDING_TYPES: &[&str] = &["secret_text", "secret_key"];
+
+/// Placeholder for redacted binding values
+const REDACTED: &str = "[REDACTED]";
+
+/// Replace the values of secret bindings with a placeholder, keeping their names
+pub fn redact_secret_bindings(bindings: &mut [WorkerBinding]) {
+    for binding in bindings
+        .iter_mut()
+        .filter(|b| SECRET_BINDING_TYPES.contains(&b.binding_type.as_str()))
+    {
+        for value in binding.details.values_mut() {
+            *value = serde_json::json!(REDACTED);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_service, success, wait_for_requests};
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_start_tail_creates_session() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/workers/scripts/edge/tails"))
+            .respond_with(success(serde_json::json!({
+                "id": "tail-1",
+                "url": "wss://tail.developers.workers.dev/tail-1",
+                "expires_at": "2024-06-01T12:00:00Z"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let session = mock_service(&server, WorkersService::new).start_tail("edge").await.unwrap();
+        assert_eq!(session.id, "tail-1");
+        assert_eq!(session.url, "wss://tail.developers.workers.dev/tail-1");
+        assert!(session.expires_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_open_tail_deletes_session_it_cannot_connect_to() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/workers/scripts/edge/tails"))
+            .respond_with(success(serde_json::json!({ "id": "tail-1", "url": "ws://127.0.0.1:9/tail-1", "expires_at": null })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/accounts/acct/workers/scripts/edge/tails/tail-1"))
+            .respond_with(success(serde_json::json!(null)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert!(mock_service(&server, WorkersService::new).open_tail("edge").await.is_err());
+
+        wait_for_requests(&server, 2).await;
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_deploy_from_cache_template() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/accounts/acct/workers/scripts/edge-cache"))
+            .respond_with(success(serde_json::json!({ "id": "edge-cache", "etag": "etag-1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let worker = mock_service(&server, WorkersService::new)
+            .deploy_from_template("cache", "edge-cache", &HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(worker.id, "edge-cache");
+
+        let requests = server.received_requests().await.unwrap();
+        let body = String::from_utf8_lossy(&requests[0].body).into_owned();
+        assert!(body.contains(crate::workers::CACHE_WORKER));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_from_unknown_template_is_not_found() {
+        let server = MockServer::start().await;
+
+        let err = mock_service(&server, WorkersService::new)
+            .deploy_from_template("no-such-template", "edge", &HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CloudflareError::NotFound(ref m) if m.contains("no-such-template")));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_worker_settings_bindings_are_parsed_and_redacted() {
+        let mut settings: WorkerSettings = serde_json::from_value(serde_json::json!({
+            "bindings": [
+                { "type": "kv_namespace", "name": "CACHE", "namespace_id": "ns-1" },
+                { "type": "r2_bucket", "name": "MEDIA", "bucket_name": "media" },
+                { "type": "plain_text", "name": "ENV", "text": "production" },
+                { "type": "secret_text", "name": "API_KEY", "text": "hunter2" }
+            ],
+            "compatibility_date": "2024-06-01",
+            "compatibility_flags": ["nodejs_compat"],
+            "usage_model": "standard",
+            "logpush": false
+        }))
+        .unwrap();
+
+        redact_secret_bindings(&mut settings.bindings);
+
+        let names: Vec<&str> = settings.bindings.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["CACHE", "MEDIA", "ENV", "API_KEY"]);
+        assert_eq!(settings.bindings[0].details["namespace_id"], "ns-1");
+        assert_eq!(settings.bindings[2].details["text"], "production");
+        assert_eq!(settings.bindings[3].details["text"], REDACTED);
+        assert_eq!(settings.compatibility_flags, vec!["nodejs_compat"]);
+        assert_eq!(settings.usage_model.as_deref(), Some("standard"));
+    }
+
+    async fn mount_namespace_pages(server: &MockServer) {
+        for (page, titles) in [("1", ["sessions", "cache"]), ("2", ["flags", "config"])] {
+            let namespaces: Vec<_> = titles
+                .iter()
+                .map(|title| serde_json::json!({ "id": format!("ns-{}", title), "title": title }))
+                .collect();
+            Mock::given(method("GET"))
+                .and(path("/accounts/acct/storage/kv/namespaces"))
+                .and(query_param("page", page))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "errors": [],
+                    "messages": [],
+                    "result": namespaces,
+                    "result_info": { "page": 1, "per_page": 2, "count": 2, "total_count": 4, "total_pages": 2 }
+                })))
+                .mount(server)
+                .await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_kv_namespace_rejects_existing_title() {
+        let server = MockServer::start().await;
+        mount_namespace_pages(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/storage/kv/namespaces"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        // The duplicate is on the second page
+        let err = mock_service(&server, WorkersService::new).create_kv_namespace("config", false).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::Conflict(ref m) if m.contains("'config'") && m.contains("ns-config")));
+    }
+
+    #[tokio::test]
+    async fn test_create_kv_namespace_allows_duplicate_when_asked() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/acct/storage/kv/namespaces"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/accounts/acct/storage/kv/namespaces"))
+            .and(body_json(serde_json::json!({ "title": "config" })))
+            .respond_with(success(serde_json::json!({ "id": "ns-config-2", "title": "config" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let namespace = mock_service(&server, WorkersService::new).create_kv_namespace("config", true).await.unwrap();
+        assert_eq!(namespace.id, "ns-config-2");
+    }
+}
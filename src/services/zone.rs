@@ -0,0 +1,775 @@
+//! Zone service
+//!
+//! Zone details, settings, development mode, Argo and pausing
+
+use crate::client::CloudflareClient;
+use crate::error::{CloudflareError, CloudflareResult};
+use crate::config::CloudflareConfig;
+use crate::models::{Zone, ZoneSetting};
+use crate::plan::{map_plan_error, plan_upgrade_error, Feature, PlanTier};
+use crate::services::cache::CacheService;
+use crate::services::settings::{
+    zone_setting_updates, SettingsService, ZoneSettingOutcome, ZoneSettingResult, ZoneSettingUpdate,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Cloudflare turns development mode off by itself after this many minutes
+pub const CLOUDFLARE_DEV_MODE_MINUTES: u32 = 180;
+/// Setting holding when development mode is due to be switched off
+pub const DEV_MODE_EXPIRES_SETTING: &str = "development_mode_expires_at";
+/// How long the zone's plan tier is reused before asking Cloudflare again
+pub const PLAN_TIER_TTL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Argo features and whether each is on
+#[derive(Debug, Clone, Serialize)]
+pub struct ArgoStatus {
+    pub smart_routing: bool,
+    pub tiered_caching: bool,
+}
+
+/// Development mode after a toggle
+#[derive(Debug, Clone, Serialize)]
+pub struct DevelopmentModeStatus {
+    pub enabled: bool,
+    /// Minutes development mode stays on, `None` when turned off
+    pub duration_minutes: Option<u32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of an all-or-nothing batch of zone setting changes
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsTransaction {
+    /// Whether every change was kept; `false` when a failure rolled the batch back
+    pub committed: bool,
+    pub results: Vec<ZoneSettingResult>,
+}
+
+impl SettingsTransaction {
+    /// Settings with the given outcome
+    pub fn with_outcome(&self, outcome: ZoneSettingOutcome) -> Vec<&'static str> {
+        self.results.iter().filter(|r| r.outcome == outcome).map(|r| r.setting_id).collect()
+    }
+}
+
+/// Runs one delayed task at a time; scheduling another cancels the first
+#[derive(Default)]
+pub struct DevModeTimer {
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl DevModeTimer {
+    /// Run `action` after `delay`, cancelling any task already scheduled
+    pub fn schedule<F>(&self, delay: std::time::Duration, action: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut task = self.task.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(previous) = task.take() {
+            previous.abort();
+        }
+        *task = Some(tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            action.await;
+        }));
+    }
+
+    /// Cancel the scheduled task; returns whether one was still waiting
+    pub fn cancel(&self) -> bool {
+        match self.task.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            Some(task) => {
+                let pending = !task.is_finished();
+                task.abort();
+                pending
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a task is waiting to run
+    pub fn is_scheduled(&self) -> bool {
+        self.task
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+    }
+}
+
+impl Drop for DevModeTimer {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// The zone's plan tier, fetched once per `PLAN_TIER_TTL`.
+///
+/// Clones share the cached tier, so every service gating on the plan asks
+/// Cloudflare at most once an hour between them.
+#[derive(Clone, Default)]
+pub struct PlanLookup {
+    client: Option<Arc<CloudflareClient>>,
+    /// Plan tier and when it was fetched
+    cached: Arc<Mutex<Option<(Instant, PlanTier)>>>,
+}
+
+impl PlanLookup {
+    pub fn new(client: Option<Arc<CloudflareClient>>) -> Self {
+        Self { client, cached: Arc::default() }
+    }
+
+    /// The zone's plan tier; a zone that reports no plan is treated as Free
+    pub async fn plan(&self) -> CloudflareResult<PlanTier> {
+        if let Some((fetched_at, tier)) = *self.cached.lock().unwrap_or_else(|e| e.into_inner()) {
+            if fetched_at.elapsed() < PLAN_TIER_TTL {
+                return Ok(tier);
+            }
+        }
+
+        let client = self.client.as_ref().ok_or(CloudflareError::NotConfigured)?;
+        let zone = client.get_zone().await?;
+        let tier = zone.plan.as_ref().map(PlanTier::from_plan).unwrap_or(PlanTier::Free);
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), tier));
+        Ok(tier)
+    }
+
+    /// Fail with `PermissionDenied` when the zone's plan lacks `feature`,
+    /// so handlers can refuse before Cloudflare does
+    pub async fn require_feature(&self, feature: Feature) -> CloudflareResult<()> {
+        if self.plan().await?.supports(feature) {
+            Ok(())
+        } else {
+            Err(plan_upgrade_error(feature))
+        }
+    }
+}
+
+/// Zone service
+pub struct ZoneService {
+    client: Option<Arc<CloudflareClient>>,
+    db: PgPool,
+    plan: PlanLookup,
+    /// Switches development mode off when its duration is up
+    dev_mode_timer: Arc<DevModeTimer>,
+}
+
+impl ZoneService {
+    /// Create a new zone service
+    pub fn new(client: Arc<CloudflareClient>, db: PgPool) -> Self {
+        Self {
+            plan: PlanLookup::new(Some(Arc::clone(&client))),
+            client: Some(client),
+            db,
+            dev_mode_timer: Arc::default(),
+        }
+    }
+
+    /// Create a new zone service without a client
+    pub fn new_unconfigured(db: PgPool) -> Self {
+        Self { client: None, db, plan: PlanLookup::default(), dev_mode_timer: Arc::default() }
+    }
+
+    /// The plan lookup, for services that gate features on the same cached tier
+    pub fn plan_lookup(&self) -> PlanLookup {
+        self.plan.clone()
+    }
+
+    /// Use a timer that outlives this service, so rebuilding the services
+    /// neither drops a scheduled switch-off nor leaves two of them running
+    pub fn set_dev_mode_timer(&mut self, timer: Arc<DevModeTimer>) {
+        self.dev_mode_timer = timer;
+    }
+
+    /// Get the client or return an error if not configured
+    fn get_client(&self) -> CloudflareResult<&CloudflareClient> {
+        self.client.as_ref()
+            .map(|c| c.as_ref())
+            .ok_or(CloudflareError::NotConfigured)
+    }
+
+    /// Details of the configured zone
+    pub async fn get_zone_info(&self) -> CloudflareResult<Zone> {
+        self.get_client()?.get_zone().await
+    }
+
+    /// The zone's plan tier, fetched once per `PLAN_TIER_TTL`.
+    ///
+    /// A zone that reports no plan is treated as Free.
+    pub async fn plan(&self) -> CloudflareResult<PlanTier> {
+        self.plan.plan().await
+    }
+
+    /// Whether the zone's plan includes `feature`
+    pub async fn supports(&self, feature: Feature) -> CloudflareResult<bool> {
+        Ok(self.plan().await?.supports(feature))
+    }
+
+    /// Fail with `PermissionDenied` when the zone's plan lacks `feature`,
+    /// so handlers can refuse before Cloudflare does
+    pub async fn require_feature(&self, feature: Feature) -> CloudflareResult<()> {
+        self.plan.require_feature(feature).await
+    }
+
+    /// All of the zone's settings
+    pub async fn get_settings(&self) -> CloudflareResult<Vec<ZoneSetting>> {
+        self.get_client()?.get_zone_settings().await
+    }
+
+    /// Turn development mode on or off.
+    ///
+    /// `duration_minutes` defaults to the `development_mode_duration` setting.
+    /// Cloudflare itself ends development mode after
+    /// `CLOUDFLARE_DEV_MODE_MINUTES`, so longer durations are cut to that.
+    /// Enabling schedules development mode to be switched off (and the cache
+    /// purged) once the duration is up, replacing any earlier schedule; the
+    /// time is stored so `resume_development_mode_timer` can pick it up
+    /// after a restart.
+    pub async fn set_development_mode(
+        &self,
+        enabled: bool,
+        duration_minutes: Option<u32>,
+    ) -> CloudflareResult<DevelopmentModeStatus> {
+        let client = self.get_client()?;
+        let settings = SettingsService::new(self.db.clone());
+
+        let duration = if enabled {
+            let minutes = match duration_minutes {
+                Some(minutes) => minutes,
+                None => settings.get_extended_settings().await?.development_mode_duration,
+            };
+            if minutes == 0 {
+                return Err(CloudflareError::ValidationError(
+                    "Development mode duration must be at least one minute".to_string(),
+                ));
+            }
+            if minutes > CLOUDFLARE_DEV_MODE_MINUTES {
+                warn!(
+                    "Development mode requested for {} minutes, Cloudflare ends it after {}",
+                    minutes, CLOUDFLARE_DEV_MODE_MINUTES
+                );
+            }
+            Some(minutes.min(CLOUDFLARE_DEV_MODE_MINUTES))
+        } else {
+            None
+        };
+
+        client.toggle_development_mode(enabled).await?;
+        settings.set_setting("development_mode", &serde_json::json!(enabled)).await?;
+        info!("Development mode {}", if enabled { "enabled" } else { "disabled" });
+
+        let expires_at = duration.map(|minutes| Utc::now() + Duration::minutes(minutes as i64));
+        match expires_at {
+            Some(expires_at) => {
+                settings.set_setting(DEV_MODE_EXPIRES_SETTING, &serde_json::json!(expires_at)).await?;
+                self.schedule_development_mode_off(expires_at)?;
+            }
+            None => {
+                self.dev_mode_timer.cancel();
+                settings.delete_setting(DEV_MODE_EXPIRES_SETTING).await?;
+            }
+        }
+
+        Ok(DevelopmentModeStatus {
+            enabled,
+            duration_minutes: duration,
+            expires_at,
+        })
+    }
+
+    /// Reschedule the stored development mode switch-off, e.g. after a
+    /// restart. One that came due while the plugin was down runs now.
+    /// Returns when development mode will be switched off, if at all.
+    pub async fn resume_development_mode_timer(&self) -> CloudflareResult<Option<DateTime<Utc>>> {
+        let expires_at = SettingsService::new(self.db.clone())
+            .get_setting(DEV_MODE_EXPIRES_SETTING)
+            .await?
+            .and_then(|v| serde_json::from_value::<DateTime<Utc>>(v).ok());
+        if let Some(expires_at) = expires_at {
+            self.schedule_development_mode_off(expires_at)?;
+        }
+        Ok(expires_at)
+    }
+
+    /// Whether development mode is scheduled to be switched off
+    pub fn development_mode_timer_scheduled(&self) -> bool {
+        self.dev_mode_timer.is_scheduled()
+    }
+
+    fn schedule_development_mode_off(&self, at: DateTime<Utc>) -> CloudflareResult<()> {
+        let client = Arc::clone(self.client.as_ref().ok_or(CloudflareError::NotConfigured)?);
+        let db = self.db.clone();
+        let delay = (at - Utc::now()).to_std().unwrap_or_default();
+        self.dev_mode_timer.schedule(delay, end_development_mode(client, db));
+        Ok(())
+    }
+
+    /// Whether Argo Smart Routing and Tiered Caching are on
+    pub async fn get_argo(&self) -> CloudflareResult<ArgoStatus> {
+        let client = self.get_client()?;
+        let (smart_routing, tiered_caching) = futures::try_join!(
+            client.get_argo_setting("smart_routing"),
+            client.get_argo_setting("tiered_caching"),
+        )?;
+        Ok(ArgoStatus {
+            smart_routing: smart_routing.value == "on",
+            tiered_caching: tiered_caching.value == "on",
+        })
+    }
+
+    /// Turn Argo Smart Routing on or off. Needs an Argo subscription.
+    pub async fn set_argo_smart_routing(&self, enabled: bool) -> CloudflareResult<bool> {
+        self.set_argo_feature("smart_routing", Feature::ArgoSmartRouting, enabled).await
+    }
+
+    /// Turn Tiered Caching on or off. Available on every plan, though
+    /// Cloudflare keeps the setting under Argo.
+    pub async fn set_tiered_caching(&self, enabled: bool) -> CloudflareResult<bool> {
+        self.set_argo_feature("tiered_caching", Feature::TieredCaching, enabled).await
+    }
+
+    async fn set_argo_feature(&self, setting: &str, feature: Feature, enabled: bool) -> CloudflareResult<bool> {
+        let setting = self
+            .get_client()?
+            .set_argo_setting(setting, enabled)
+            .await
+            .map_err(|e| map_plan_error(e, feature))?;
+        info!("{} {}", feature.label(), if enabled { "enabled" } else { "disabled" });
+        Ok(setting.value == "on")
+    }
+
+    /// Push every zone-level toggle in `config` to Cloudflare, all or nothing
+    pub async fn apply_settings_transactional(&self, config: &CloudflareConfig) -> CloudflareResult<SettingsTransaction> {
+        self.apply_setting_updates_transactional(zone_setting_updates(config)).await
+    }
+
+    /// Apply zone settings in order, undoing them if one fails.
+    ///
+    /// The current values are snapshotted first. Settings the zone doesn't
+    /// have or can't edit are skipped as in a plain batch, but the first
+    /// failure stops the batch and restores every setting changed so far from
+    /// the snapshot, newest first. Settings after the failure aren't attempted.
+    pub async fn apply_setting_updates_transactional(
+        &self,
+        updates: Vec<ZoneSettingUpdate>,
+    ) -> CloudflareResult<SettingsTransaction> {
+        let client = self.get_client()?;
+        let snapshot: HashMap<String, ZoneSetting> = client
+            .get_zone_settings()
+            .await?
+            .into_iter()
+            .map(|s| (s.id.clone(), s))
+            .collect();
+
+        let mut results: Vec<ZoneSettingResult> = Vec::with_capacity(updates.len());
+        let mut failed = false;
+        for update in updates {
+            let (outcome, error) = match snapshot.get(update.setting_id) {
+                _ if failed => (ZoneSettingOutcome::Skipped, Some("Not attempted after an earlier failure".to_string())),
+                None => (ZoneSettingOutcome::Skipped, Some("Not available on this zone".to_string())),
+                Some(setting) if !setting.editable => {
+                    (ZoneSettingOutcome::Skipped, Some("Not editable on this zone's plan".to_string()))
+                }
+                Some(setting) if setting.value == update.value => (ZoneSettingOutcome::Unchanged, None),
+                Some(_) => match client.update_zone_setting(update.setting_id, update.value.clone()).await {
+                    Ok(_) => (ZoneSettingOutcome::Applied, None),
+                    Err(e) => {
+                        warn!("Failed to update zone setting {}, rolling back: {}", update.setting_id, e);
+                        failed = true;
+                        (ZoneSettingOutcome::Failed, Some(e.to_string()))
+                    }
+                },
+            };
+
+            results.push(ZoneSettingResult {
+                field: update.field,
+                setting_id: update.setting_id,
+                value: update.value,
+                outcome,
+                error,
+            });
+        }
+
+        if failed {
+            for result in results.iter_mut().rev().filter(|r| r.outcome == ZoneSettingOutcome::Applied) {
+                let previous = snapshot[result.setting_id].value.clone();
+                match client.update_zone_setting(result.setting_id, previous).await {
+                    Ok(_) => result.outcome = ZoneSettingOutcome::RolledBack,
+                    Err(e) => {
+                        warn!("Failed to roll back zone setting {}: {}", result.setting_id, e);
+                        result.outcome = ZoneSettingOutcome::RollbackFailed;
+                        result.error = Some(e.to_string());
+                    }
+                }
+            }
+        }
+
+        let transaction = SettingsTransaction { committed: !failed, results };
+        if failed {
+            warn!(
+                "Zone settings batch rolled back: {} restored, {} couldn't be restored",
+                transaction.with_outcome(ZoneSettingOutcome::RolledBack).len(),
+                transaction.with_outcome(ZoneSettingOutcome::RollbackFailed).len()
+            );
+        } else {
+            info!(
+                "Applied {} of {} zone settings",
+                transaction.with_outcome(ZoneSettingOutcome::Applied).len(),
+                transaction.results.len()
+            );
+        }
+        Ok(transaction)
+    }
+
+    /// Pause or unpause the zone. A paused zone serves traffic straight
+    /// from the origin without Cloudflare's proxy, cache or security.
+    pub async fn pause_zone(&self, paused: bool) -> CloudflareResult<Zone> {
+        let zone = self.get_client()?.set_zone_paused(paused).await?;
+        info!("Zone {} {}", zone.name, if paused { "paused" } else { "unpaused" });
+        Ok(zone)
+    }
+}
+
+/// Switch development mode off once its time is up and purge the cache so
+/// visitors get the latest content from then on
+async fn end_development_mode(client: Arc<CloudflareClient>, db: PgPool) {
+    if let Err(e) = client.toggle_development_mode(false).await {
+        // The stored time stays, so the next restart tries again
+        warn!("Failed to switch development mode off: {}", e);
+        return;
+    }
+    info!("Development mode switched off after its duration ended");
+
+    let settings = SettingsService::new(db.clone());
+    if let Err(e) = settings.set_setting("development_mode", &serde_json::json!(false)).await {
+        warn!("Failed to record development mode as off: {}", e);
+    }
+    if let Err(e) = settings.delete_setting(DEV_MODE_EXPIRES_SETTING).await {
+        warn!("Failed to clear the development mode switch-off time: {}", e);
+    }
+    if let Err(e) = CacheService::new(client, db).purge_all(None).await {
+        warn!("Failed to purge cache after development mode ended: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CloudflareConfig;
+    use crate::test_support::{lazy_pool, mock_client, mock_service, success};
+    use sqlx::postgres::PgPoolOptions;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_plan_is_cached_and_gates_features() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone"))
+            .respond_with(success(serde_json::json!({
+                "id": "zone", "name": "example.com", "status": "active", "paused": false,
+                "development_mode": 0, "type": "full", "name_servers": [],
+                "plan": {
+                    "id": "plan", "name": "Pro Website", "price": 20, "currency": "USD",
+                    "frequency": "monthly", "is_subscribed": true, "can_subscribe": false,
+                    "legacy_id": "pro"
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let service = mock_service(&server, ZoneService::new);
+
+        assert_eq!(service.plan().await.unwrap(), PlanTier::Pro);
+        assert!(service.supports(Feature::WafManagedRules).await.unwrap());
+        service.require_feature(Feature::SuperBotFightMode).await.unwrap();
+        let err = service.require_feature(Feature::TagPurge).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m) if m.contains("an Enterprise plan")));
+    }
+
+    #[tokio::test]
+    async fn test_pause_zone_patches_zone() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone"))
+            .and(body_json(serde_json::json!({ "paused": true })))
+            .respond_with(success(serde_json::json!({
+                "id": "zone", "name": "example.com", "status": "active", "paused": true,
+                "development_mode": 0, "type": "full", "name_servers": []
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert!(mock_service(&server, ZoneService::new).pause_zone(true).await.unwrap().paused);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_set_development_mode_toggles_zone_setting() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/development_mode"))
+            .and(body_json(serde_json::json!({ "value": "on" })))
+            .respond_with(success(serde_json::json!({
+                "id": "development_mode", "value": "on", "editable": true
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let zone = ZoneService::new(mock_client(&server), db);
+
+        let on = zone.set_development_mode(true, Some(60)).await.unwrap();
+        assert!(on.enabled);
+        assert_eq!(on.duration_minutes, Some(60));
+        let remaining = on.expires_at.unwrap() - Utc::now();
+        assert!(remaining > Duration::minutes(59) && remaining <= Duration::minutes(60));
+
+        // Longer than Cloudflare allows is cut to its limit
+        server.verify().await;
+        server.reset().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/development_mode"))
+            .respond_with(success(serde_json::json!({
+                "id": "development_mode", "value": "on", "editable": true
+            })))
+            .mount(&server)
+            .await;
+        let long = zone.set_development_mode(true, Some(1440)).await.unwrap();
+        assert_eq!(long.duration_minutes, Some(CLOUDFLARE_DEV_MODE_MINUTES));
+        server.reset().await;
+
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/development_mode"))
+            .and(body_json(serde_json::json!({ "value": "off" })))
+            .respond_with(success(serde_json::json!({
+                "id": "development_mode", "value": "off", "editable": true
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let off = zone.set_development_mode(false, None).await.unwrap();
+        assert!(!off.enabled);
+        assert!(off.duration_minutes.is_none() && off.expires_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dev_mode_timer_reschedule_and_cancel() {
+        let timer = DevModeTimer::default();
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let record = |label: &'static str| {
+            let fired = Arc::clone(&fired);
+            async move { fired.lock().unwrap().push(label) }
+        };
+
+        timer.schedule(std::time::Duration::from_millis(50), record("first"));
+        assert!(timer.is_scheduled());
+        // Enabling again replaces the pending switch-off
+        timer.schedule(std::time::Duration::from_millis(100), record("second"));
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        assert_eq!(*fired.lock().unwrap(), vec!["second"]);
+        assert!(!timer.is_scheduled());
+
+        // Disabling cancels it
+        timer.schedule(std::time::Duration::from_millis(50), record("third"));
+        assert!(timer.cancel());
+        tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+        assert_eq!(*fired.lock().unwrap(), vec!["second"]);
+        assert!(!timer.cancel());
+    }
+
+    #[tokio::test]
+    async fn test_shared_dev_mode_timer_survives_rebuilt_service() {
+        let server = MockServer::start().await;
+        let db = lazy_pool();
+        let timer = Arc::new(DevModeTimer::default());
+
+        let mut old = ZoneService::new(mock_client(&server), db.clone());
+        old.set_dev_mode_timer(Arc::clone(&timer));
+        old.schedule_development_mode_off(Utc::now() + Duration::minutes(30)).unwrap();
+        drop(old);
+        assert!(timer.is_scheduled());
+
+        // The rebuilt service takes over the same schedule rather than adding one
+        let mut rebuilt = ZoneService::new(mock_client(&server), db);
+        rebuilt.set_dev_mode_timer(Arc::clone(&timer));
+        rebuilt.schedule_development_mode_off(Utc::now() + Duration::minutes(30)).unwrap();
+        assert!(rebuilt.development_mode_timer_scheduled());
+        assert!(timer.cancel());
+        assert!(!rebuilt.development_mode_timer_scheduled());
+    }
+
+    #[tokio::test]
+    async fn test_overdue_dev_mode_is_switched_off_and_cache_purged() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/development_mode"))
+            .and(body_json(serde_json::json!({ "value": "off" })))
+            .respond_with(success(serde_json::json!({
+                "id": "development_mode", "value": "off", "editable": true
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/zones/zone/purge_cache"))
+            .and(body_json(serde_json::json!({ "purge_everything": true })))
+            .respond_with(success(serde_json::json!({ "id": "purge" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Settings writes fail fast; they're only logged
+        let db = PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_millis(100))
+            .connect_lazy("postgres://localhost:1/rustpress_test")
+            .unwrap();
+        let zone = ZoneService::new(mock_client(&server), db);
+        zone.schedule_development_mode_off(Utc::now() - Duration::minutes(5)).unwrap();
+
+        for _ in 0..50 {
+            if !zone.development_mode_timer_scheduled() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(!zone.development_mode_timer_scheduled());
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_set_development_mode_rejects_zero_duration() {
+        let server = MockServer::start().await;
+        let err = mock_service(&server, ZoneService::new).set_development_mode(true, Some(0)).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(_)));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_argo_toggles_send_on_and_off() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/argo/smart_routing"))
+            .and(body_json(serde_json::json!({ "value": "on" })))
+            .respond_with(success(serde_json::json!({ "id": "smart_routing", "value": "on", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/argo/tiered_caching"))
+            .and(body_json(serde_json::json!({ "value": "off" })))
+            .respond_with(success(serde_json::json!({ "id": "tiered_caching", "value": "off", "editable": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let zone = mock_service(&server, ZoneService::new);
+        assert!(zone.set_argo_smart_routing(true).await.unwrap());
+        assert!(!zone.set_tiered_caching(false).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_argo_not_subscribed_is_permission_denied() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/argo/smart_routing"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 1007, "message": "Argo Smart Routing requires an active subscription" }],
+                "messages": [],
+                "result": null
+            })))
+            .mount(&server)
+            .await;
+
+        let err = mock_service(&server, ZoneService::new).set_argo_smart_routing(true).await.unwrap_err();
+        assert!(matches!(err, CloudflareError::PermissionDenied(ref m) if m.contains("Argo subscription")));
+
+        // Other API errors pass through untouched
+        let other = CloudflareError::from_api_error(1004, "DNS validation error".to_string());
+        assert!(matches!(map_plan_error(other, Feature::TieredCaching), CloudflareError::ApiError { code: 1004, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_transactional_settings_roll_back_after_mid_batch_failure() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/zones/zone/settings"))
+            .respond_with(success(serde_json::json!([
+                { "id": "brotli", "value": "off", "editable": true },
+                { "id": "early_hints", "value": "on", "editable": true },
+                { "id": "rocket_loader", "value": "off", "editable": true },
+                { "id": "http3", "value": "off", "editable": true },
+                { "id": "0rtt", "value": "off", "editable": true }
+            ])))
+            .mount(&server)
+            .await;
+        for (id, from, to) in [("brotli", "off", "on"), ("rocket_loader", "off", "on")] {
+            Mock::given(method("PATCH"))
+                .and(path(format!("/zones/zone/settings/{}", id)))
+                .and(body_json(serde_json::json!({ "value": to })))
+                .respond_with(success(serde_json::json!({ "id": id, "value": to, "editable": true })))
+                .expect(1)
+                .mount(&server)
+                .await;
+            // Restored to the snapshot value after the failure
+            Mock::given(method("PATCH"))
+                .and(path(format!("/zones/zone/settings/{}", id)))
+                .and(body_json(serde_json::json!({ "value": from })))
+                .respond_with(success(serde_json::json!({ "id": id, "value": from, "editable": true })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/http3"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false,
+                "errors": [{ "code": 1007, "message": "Invalid value" }],
+                "messages": [],
+                "result": null
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/zones/zone/settings/0rtt"))
+            .respond_with(success(serde_json::json!({ "id": "0rtt", "value": "on", "editable": true })))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let config = CloudflareConfig {
+            brotli_compression: true,
+            early_hints: true,
+            rocket_loader: true,
+            http3: true,
+            zero_rtt: true,
+            ..Default::default()
+        };
+        let updates = zone_setting_updates(&config)
+            .into_iter()
+            .filter(|u| ["brotli", "early_hints", "rocket_loader", "http3", "0rtt"].contains(&u.setting_id))
+            .collect();
+        let transaction = mock_service(&server, ZoneService::new)
+            .apply_setting_updates_transactional(updates)
+            .await
+            .unwrap();
+
+        assert!(!transaction.committed);
+        assert_eq!(transaction.with_outcome(ZoneSettingOutcome::RolledBack), vec!["brotli", "rocket_loader"]);
+        assert_eq!(transaction.with_outcome(ZoneSettingOutcome::Unchanged), vec!["early_hints"]);
+        assert_eq!(transaction.with_outcome(ZoneSettingOutcome::Failed), vec!["http3"]);
+        assert_eq!(transaction.with_outcome(ZoneSettingOutcome::Skipped), vec!["0rtt"]);
+        assert!(transaction.with_outcome(ZoneSettingOutcome::Applied).is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+//! Single-flight request coalescing
+//!
+//! The admin dashboard loads many widgets at once, several of which read the
+//! same resource. Calls made with the same key while one is already in flight
+//! wait for that call and share its result instead of hitting the API again.
+
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// In-flight calls by key, shared between clones
+pub struct SingleFlight<V: Clone> {
+    in_flight: Arc<Mutex<HashMap<String, Shared<BoxFuture<'static, V>>>>>,
+}
+
+impl<V: Clone> Clone for SingleFlight<V> {
+    fn clone(&self) -> Self {
+        Self { in_flight: Arc::clone(&self.in_flight) }
+    }
+}
+
+impl<V: Clone> Default for SingleFlight<V> {
+    fn default() -> Self {
+        Self { in_flight: Arc::new(Mutex::new(HashMap::new())) }
+    }
+}
+
+impl<V: Clone> fmt::Debug for SingleFlight<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner()).len();
+        f.debug_struct("SingleFlight").field("in_flight", &in_flight).finish()
+    }
+}
+
+impl<V: Clone + Send + Sync + 'static> SingleFlight<V> {
+    /// Run the future `call` makes, unless a call with the same `key` is
+    /// already in flight, in which case wait for that one's result.
+    ///
+    /// The call keeps running if the caller that started it goes away while
+    /// others are still waiting.
+    pub async fn run<F, Fut>(&self, key: &str, call: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V> + Send + 'static,
+    {
+        let shared = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            in_flight
+                .entry(key.to_string())
+                .or_insert_with(|| call().boxed().shared())
+                .clone()
+        };
+
+        let result = shared.clone().await;
+
+        // Only the entry for this call; a newer call may have taken the key
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if in_flight.get(key).is_some_and(|current| current.ptr_eq(&shared)) {
+            in_flight.remove(key);
+        }
+        result
+    }
+
+    /// Number of calls currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_run() {
+        let flights: SingleFlight<usize> = SingleFlight::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let calls = (0..5).map(|_| {
+            let runs = Arc::clone(&runs);
+            flights.run("key", move || async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                runs.fetch_add(1, Ordering::SeqCst) + 1
+            })
+        });
+        let results = futures::future::join_all(calls).await;
+
+        assert_eq!(results, vec![1; 5]);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(flights.in_flight(), 0);
+
+        // Finished calls aren't reused
+        assert_eq!(flights.run("key", || async { 7 }).await, 7);
+        assert_eq!(flights.run("other", || async { 8 }).await, 8);
+    }
+}
@@ -0,0 +1,68 @@
+//! Helpers shared by the unit tests
+
+use crate::client::CloudflareClient;
+use crate::config::CloudflareConfig;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::{MockServer, ResponseTemplate};
+
+/// Successful Cloudflare API response wrapping `result`
+pub fn success(result: serde_json::Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "success": true,
+        "errors": [],
+        "messages": [],
+        "result": result
+    }))
+}
+
+/// Pool that only connects once a test touches the database
+pub fn lazy_pool() -> PgPool {
+    PgPoolOptions::new()
+        .connect_lazy("postgres://localhost/rustpress_test")
+        .unwrap()
+}
+
+fn build_client(server: &MockServer, zone_id: &str) -> CloudflareClient {
+    let config = CloudflareConfig {
+        api_token: "token".to_string(),
+        account_id: "acct".to_string(),
+        zone_id: zone_id.to_string(),
+        ..Default::default()
+    };
+    CloudflareClient::new(&config).unwrap().with_base_url(&server.uri())
+}
+
+/// Client for account `acct` and zone `zone_id`, sending requests to `server`
+pub fn zone_client(server: &MockServer, zone_id: &str) -> Arc<CloudflareClient> {
+    Arc::new(build_client(server, zone_id))
+}
+
+/// Like `mock_client`, but not yet shared so the test can keep configuring it
+pub fn unshared_client(server: &MockServer) -> CloudflareClient {
+    build_client(server, "zone")
+}
+
+/// Client for account `acct` and zone `zone`, sending requests to `server`
+pub fn mock_client(server: &MockServer) -> Arc<CloudflareClient> {
+    zone_client(server, "zone")
+}
+
+/// Wait up to a second for `server` to have received `count` requests, for
+/// requests sent from a background task
+pub async fn wait_for_requests(server: &MockServer, count: usize) {
+    for _ in 0..50 {
+        if server.received_requests().await.unwrap().len() >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+/// Service built by `new` from `mock_client` and `lazy_pool`, e.g.
+/// `mock_service(&server, CacheService::new)`
+pub fn mock_service<S>(server: &MockServer, new: impl FnOnce(Arc<CloudflareClient>, PgPool) -> S) -> S {
+    new(mock_client(server), lazy_pool())
+}
@@ -0,0 +1,264 @@
+//! Version-gated upgrade steps
+//!
+//! Each schema migration or data transform is registered with the plugin
+//! version that introduced it. Upgrading from one version to another runs
+//! only the steps in between, oldest first, and records every applied step
+//! in `cloudflare_upgrade_steps` so an interrupted or repeated upgrade never
+//! runs one twice.
+//!
+//! This is the plugin's only migration runner; the manifest doesn't hand
+//! `migrations/` to the host. Installs whose schema was created before steps
+//! were recorded run the scripts again, so every script must be idempotent.
+
+use crate::error::{CloudflareError, CloudflareResult};
+use futures::future::BoxFuture;
+use semver::Version;
+use sqlx::{Executor, PgConnection, PgPool};
+use tracing::info;
+
+/// Records which upgrade steps have been applied
+const UPGRADE_STEPS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS cloudflare_upgrade_steps (
+    name VARCHAR(255) PRIMARY KEY,
+    version VARCHAR(64) NOT NULL,
+    applied_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+)
+"#;
+
+/// A data transform, run inside the step's transaction
+pub type DataTransform = for<'c> fn(&'c mut PgConnection) -> BoxFuture<'c, CloudflareResult<()>>;
+
+/// What an upgrade step does
+#[derive(Clone, Copy)]
+pub enum UpgradeAction {
+    /// SQL script, usually one of the files in `migrations/`
+    Migration(&'static str),
+    /// Rewrites existing data, e.g. re-encoding stored values
+    Transform(DataTransform),
+}
+
+/// A migration or data transform introduced by a plugin version
+#[derive(Clone)]
+pub struct UpgradeStep {
+    /// Unique name the step is recorded under once applied
+    pub name: &'static str,
+    /// Plugin version that introduced the step
+    pub version: Version,
+    pub action: UpgradeAction,
+}
+
+impl UpgradeStep {
+    pub const fn migration(name: &'static str, version: Version, sql: &'static str) -> Self {
+        Self { name, version, action: UpgradeAction::Migration(sql) }
+    }
+
+    pub const fn transform(name: &'static str, version: Version, transform: DataTransform) -> Self {
+        Self { name, version, action: UpgradeAction::Transform(transform) }
+    }
+
+    /// Whether upgrading from `from` to `to` crosses the step's version,
+    /// i.e. `from < version <= to`
+    pub fn applies(&self, from: &Version, to: &Version) -> bool {
+        *from < self.version && self.version <= *to
+    }
+}
+
+/// Every upgrade step the plugin knows, in the order they were added
+pub const UPGRADE_STEPS: &[UpgradeStep] = &[
+    UpgradeStep::migration(
+        "1.0.0_initial_schema",
+        Version::new(1, 0, 0),
+        include_str!("../migrations/1.0.0_initial_schema.sql"),
+    ),
+    UpgradeStep::migration(
+        "1.1.0_cache_tag_registry",
+        Version::new(1, 1, 0),
+        include_str!("../migrations/1.1.0_cache_tag_registry.sql"),
+    ),
+    UpgradeStep::migration(
+        "1.2.0_stream_webhook_events",
+        Version::new(1, 2, 0),
+        include_str!("../migrations/1.2.0_stream_webhook_events.sql"),
+    ),
+    UpgradeStep::migration(
+        "1.3.0_r2_media_offload",
+        Version::new(1, 3, 0),
+        include_str!("../migrations/1.3.0_r2_media_offload.sql"),
+    ),
+];
+
+/// Steps to run when upgrading from `from` to `to`, oldest version first.
+///
+/// Steps for the same version keep their registry order. Nothing is
+/// selected for a downgrade or a reinstall of the same version.
+pub fn steps_between<'a>(steps: &'a [UpgradeStep], from: &Version, to: &Version) -> Vec<&'a UpgradeStep> {
+    let mut selected: Vec<&UpgradeStep> = steps.iter().filter(|step| step.applies(from, to)).collect();
+    selected.sort_by(|a, b| a.version.cmp(&b.version));
+    selected
+}
+
+/// Parse a version as reported by the plugin host
+pub fn parse_version(version: &str) -> CloudflareResult<Version> {
+    Version::parse(version.trim().trim_start_matches('v'))
+        .map_err(|e| CloudflareError::ValidationError(format!("Invalid plugin version '{}': {}", version, e)))
+}
+
+/// Run the registered steps between two versions, returning the names of
+/// those applied
+pub async fn run_upgrade(pool: &PgPool, from: &Version, to: &Version) -> CloudflareResult<Vec<&'static str>> {
+    run_upgrade_steps(pool, UPGRADE_STEPS, from, to).await
+}
+
+/// Apply every step up to version `to` that hasn't been recorded yet,
+/// bringing a fresh or pre-registry install up to date
+pub async fn run_pending(pool: &PgPool, to: &Version) -> CloudflareResult<Vec<&'static str>> {
+    run_upgrade_steps(pool, UPGRADE_STEPS, &Version::new(0, 0, 0), to).await
+}
+
+/// Run `steps` between two versions, skipping any already recorded.
+///
+/// Each step and its record are committed together, so a failed step
+/// leaves no trace and is retried by the next upgrade; steps after it
+/// don't run.
+pub async fn run_upgrade_steps(
+    pool: &PgPool,
+    steps: &[UpgradeStep],
+    from: &Version,
+    to: &Version,
+) -> CloudflareResult<Vec<&'static str>> {
+    pool.execute(UPGRADE_STEPS_TABLE).await.map_err(db_error)?;
+
+    let mut applied = Vec::new();
+    for step in steps_between(steps, from, to) {
+        let done: Option<(String,)> = sqlx::query_as("SELECT name FROM cloudflare_upgrade_steps WHERE name = $1")
+            .bind(step.name)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error)?;
+        if done.is_some() {
+            continue;
+        }
+
+        let mut tx = pool.begin().await.map_err(db_error)?;
+        match step.action {
+            UpgradeAction::Migration(sql) => {
+                (&mut *tx).execute(sql).await.map_err(|e| step_error(step, e))?;
+            }
+            UpgradeAction::Transform(transform) => transform(&mut tx).await?,
+        }
+        sqlx::query("INSERT INTO cloudflare_upgrade_steps (name, version) VALUES ($1, $2)")
+            .bind(step.name)
+            .bind(step.version.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+
+        info!("Applied upgrade step {} ({})", step.name, step.version);
+        applied.push(step.name);
+    }
+
+    Ok(applied)
+}
+
+fn db_error(e: sqlx::Error) -> CloudflareError {
+    CloudflareError::DatabaseError(e.to_string())
+}
+
+fn step_error(step: &UpgradeStep, e: sqlx::Error) -> CloudflareError {
+    CloudflareError::DatabaseError(format!("Upgrade step {} failed: {}", step.name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(version: &str) -> Version {
+        parse_version(version).unwrap()
+    }
+
+    fn names(steps: Vec<&UpgradeStep>) -> Vec<&'static str> {
+        steps.into_iter().map(|step| step.name).collect()
+    }
+
+    #[test]
+    fn test_steps_between_versions() {
+        assert_eq!(
+            names(steps_between(UPGRADE_STEPS, &v("1.1.0"), &v("1.3.0"))),
+            vec!["1.2.0_stream_webhook_events", "1.3.0_r2_media_offload"]
+        );
+        assert_eq!(
+            names(steps_between(UPGRADE_STEPS, &v("1.0.5"), &v("1.1.0"))),
+            vec!["1.1.0_cache_tag_registry"]
+        );
+        // Pre-releases come before their release
+        assert_eq!(
+            names(steps_between(UPGRADE_STEPS, &v("1.3.0-beta.1"), &v("v1.3.0"))),
+            vec!["1.3.0_r2_media_offload"]
+        );
+
+        assert!(steps_between(UPGRADE_STEPS, &v("1.3.0"), &v("1.3.0")).is_empty());
+        assert!(steps_between(UPGRADE_STEPS, &v("1.3.0"), &v("1.1.0")).is_empty());
+        assert!(parse_version("latest").is_err());
+    }
+
+    #[test]
+    fn test_steps_run_in_version_order() {
+        fn noop(_: &mut PgConnection) -> BoxFuture<'_, CloudflareResult<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        let steps = [
+            UpgradeStep::transform("reencrypt_tokens", Version::new(2, 0, 0), noop),
+            UpgradeStep::migration("add_column", Version::new(1, 5, 0), "SELECT 1"),
+            UpgradeStep::migration("backfill_column", Version::new(1, 5, 0), "SELECT 1"),
+        ];
+
+        assert_eq!(
+            names(steps_between(&steps, &v("1.4.0"), &v("2.0.0"))),
+            vec!["add_column", "backfill_column", "reencrypt_tokens"]
+        );
+        assert_eq!(names(steps_between(&steps, &v("1.5.0"), &v("1.9.9"))), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_crate_version_has_its_steps() {
+        let version = v(crate::VERSION);
+        assert!(UPGRADE_STEPS.iter().all(|step| step.version <= version));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_migrations_can_run_on_an_existing_schema() {
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let mut tx = pool.begin().await.unwrap();
+        for step in UPGRADE_STEPS {
+            if let UpgradeAction::Migration(sql) = step.action {
+                // Twice, in case the database predates the step
+                for _ in 0..2 {
+                    (&mut *tx).execute(sql).await.unwrap_or_else(|e| panic!("{}: {}", step.name, e));
+                }
+            }
+        }
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL pointing at a migrated database"]
+    async fn test_applied_steps_are_not_run_again() {
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let suffix = &uuid::Uuid::new_v4().simple().to_string()[..12];
+        let name: &'static str = Box::leak(format!("test_step_{}", suffix).into_boxed_str());
+        let sql: &'static str = Box::leak(format!("CREATE TABLE upgrade_test_{} (id INT)", suffix).into_boxed_str());
+        let steps = [UpgradeStep::migration(name, Version::new(9, 0, 0), sql)];
+
+        let applied = run_upgrade_steps(&pool, &steps, &v("8.0.0"), &v("9.0.0")).await.unwrap();
+        assert_eq!(applied, vec![name]);
+        // Would fail if the table were created again
+        let applied = run_upgrade_steps(&pool, &steps, &v("8.0.0"), &v("9.0.0")).await.unwrap();
+        assert!(applied.is_empty());
+
+        sqlx::query(&format!("DROP TABLE upgrade_test_{}", suffix)).execute(&pool).await.unwrap();
+        sqlx::query("DELETE FROM cloudflare_upgrade_steps WHERE name = $1").bind(name).execute(&pool).await.unwrap();
+    }
+}
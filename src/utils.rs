@@ -0,0 +1,139 @@
+//! Shared helpers
+
+use url::Url;
+
+/// Placeholder for redacted values in logs
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Field names whose values are credentials
+const SECRET_FIELD_MARKERS: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passphrase",
+    "authorization",
+    "api_key",
+    "apikey",
+    "private_key",
+];
+
+/// Build the canonical cache purge URL(s) for a path on a site.
+///
+/// `path` may be relative to `base` or an absolute URL, and an empty `path`
+/// purges `base` itself. Scheme and host are lowercased, default ports,
+/// fragments and repeated slashes are dropped, and a missing scheme defaults
+/// to `https`. Page-like paths return both the slash and no-slash variants
+/// since either may be cached separately; files, query URLs and the site root
+/// return a single URL. Returns nothing if the URL can't be parsed.
+pub fn normalize_purge_url(base: &str, path: &str) -> Vec<String> {
+    let path = path.trim();
+    let base = base.trim();
+
+    let raw = if path.contains("://") {
+        path.to_string()
+    } else if path.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}/{}", base.trim_end_matches('/'), path.trim_start_matches('/'))
+    };
+
+    let raw = if raw.contains("://") {
+        raw
+    } else {
+        format!("https://{}", raw)
+    };
+
+    let mut url = match Url::parse(&raw) {
+        Ok(url) if url.host_str().is_some_and(|h| !h.is_empty()) => url,
+        _ => return Vec::new(),
+    };
+    url.set_fragment(None);
+
+    let trailing_slash = url.path().ends_with('/');
+    let segments: Vec<String> = url
+        .path()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    if segments.is_empty() {
+        url.set_path("/");
+        return vec![url.to_string()];
+    }
+
+    let joined = format!("/{}", segments.join("/"));
+    let is_file = segments.last().is_some_and(|s| s.contains('.'));
+
+    if is_file || url.query().is_some() {
+        if trailing_slash && !is_file {
+            url.set_path(&format!("{}/", joined));
+        } else {
+            url.set_path(&joined);
+        }
+        return vec![url.to_string()];
+    }
+
+    let mut with_slash = url.clone();
+    url.set_path(&joined);
+    with_slash.set_path(&format!("{}/", joined));
+    vec![url.to_string(), with_slash.to_string()]
+}
+
+/// Copy of a JSON value with credential-looking fields replaced by `REDACTED`
+pub fn redact_secrets(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| {
+                let key_lower = key.to_lowercase();
+                let value = if SECRET_FIELD_MARKERS.iter().any(|m| key_lower.contains(m)) {
+                    serde_json::Value::String(REDACTED.to_string())
+                } else {
+                    redact_secrets(value)
+                };
+                (key.clone(), value)
+            })
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().map(redact_secrets).collect(),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_purge_url_canonicalizes_messy_inputs() {
+        assert_eq!(
+            normalize_purge_url("example.com/", "/blog"),
+            vec!["https://example.com/blog", "https://example.com/blog/"]
+        );
+        assert_eq!(
+            normalize_purge_url("HTTPS://Example.COM:443", "category//news/"),
+            vec!["https://example.com/category/news", "https://example.com/category/news/"]
+        );
+        assert_eq!(
+            normalize_purge_url("https://example.com", ""),
+            vec!["https://example.com/"]
+        );
+        assert_eq!(
+            normalize_purge_url("https://example.com/", "/"),
+            vec!["https://example.com/"]
+        );
+        assert_eq!(
+            normalize_purge_url("https://example.com", "https://CDN.example.com/app.css#v2"),
+            vec!["https://cdn.example.com/app.css"]
+        );
+        assert_eq!(
+            normalize_purge_url("http://example.com:8080", "sitemap.xml"),
+            vec!["http://example.com:8080/sitemap.xml"]
+        );
+        assert_eq!(
+            normalize_purge_url("https://example.com", "/search/?q=rust"),
+            vec!["https://example.com/search/?q=rust"]
+        );
+        assert!(normalize_purge_url("", "").is_empty());
+    }
+}
@@ -1,4 +1,12 @@
 //! Pre-built Workers templates for RustPress integration
+//!
+//! Templates may take parameters, written as `{{name}}` placeholders in the
+//! script. Rendering replaces each with its value as a JSON literal, so a
+//! string, list or map drops straight into the JavaScript.
+
+use crate::error::{CloudflareError, CloudflareResult};
+use serde_json::Value;
+use std::collections::HashMap;
 
 /// Cache Worker template - intelligent caching with RustPress awareness
 pub const CACHE_WORKER: &str = r#"
@@ -109,10 +117,8 @@ function isImagePath(pathname) {
 
 /// Redirect Worker template for URL management
 pub const REDIRECT_WORKER: &str = r#"
-const redirects = {
-  // Add your redirects here
-  // '/old-path': '/new-path',
-}
+// Paths mapped to where they now live, e.g. { "/old-path": "/new-path" }
+const redirects = {{redirects}}
 
 addEventListener('fetch', event => {
   event.respondWith(handleRequest(event.request))
@@ -131,6 +137,8 @@ async function handleRequest(request) {
 
 /// Analytics Worker template
 pub const ANALYTICS_WORKER: &str = r#"
+const ANALYTICS_ENDPOINT = {{analytics_endpoint}}
+
 addEventListener('fetch', event => {
   event.passThroughOnException()
   event.respondWith(handleRequest(event))
@@ -157,11 +165,40 @@ async function logAnalytics(request, response) {
     device: request.headers.get('user-agent'),
   }
 
-  // Send to analytics endpoint
-  // await fetch('https://your-analytics-endpoint.com', {
-  //   method: 'POST',
-  //   body: JSON.stringify(data)
-  // })
+  if (!ANALYTICS_ENDPOINT) {
+    return
+  }
+
+  await fetch(ANALYTICS_ENDPOINT, {
+    method: 'POST',
+    body: JSON.stringify(data)
+  })
+}
+"#;
+
+/// Maintenance mode Worker template
+pub const MAINTENANCE_WORKER: &str = r#"
+// Visitors from these IPs still reach the site
+const allowedIps = {{allowed_ips}}
+const message = {{message}}
+
+addEventListener('fetch', event => {
+  event.respondWith(handleRequest(event.request))
+})
+
+async function handleRequest(request) {
+  const ip = request.headers.get('cf-connecting-ip')
+  if (allowedIps.includes(ip)) {
+    return fetch(request)
+  }
+
+  return new Response(message, {
+    status: 503,
+    headers: {
+      'Content-Type': 'text/html; charset=utf-8',
+      'Retry-After': '3600'
+    }
+  })
 }
 "#;
 
@@ -173,30 +210,60 @@ pub fn get_templates() -> Vec<WorkerTemplate> {
             name: "Intelligent Cache".to_string(),
             description: "Smart caching with RustPress route awareness".to_string(),
             script: CACHE_WORKER.to_string(),
+            params: Vec::new(),
         },
         WorkerTemplate {
             id: "security".to_string(),
             name: "Security Headers".to_string(),
             description: "Add security headers to all responses".to_string(),
             script: SECURITY_WORKER.to_string(),
+            params: Vec::new(),
         },
         WorkerTemplate {
             id: "image".to_string(),
             name: "Image Optimization".to_string(),
             description: "On-the-fly image optimization and resizing".to_string(),
             script: IMAGE_WORKER.to_string(),
+            params: Vec::new(),
         },
         WorkerTemplate {
             id: "redirect".to_string(),
             name: "URL Redirects".to_string(),
             description: "Manage URL redirections at the edge".to_string(),
             script: REDIRECT_WORKER.to_string(),
+            params: vec![TemplateParam::required(
+                "redirects",
+                "Map of old paths to the paths they redirect to",
+            )],
         },
         WorkerTemplate {
             id: "analytics".to_string(),
             name: "Edge Analytics".to_string(),
             description: "Collect analytics data at the edge".to_string(),
             script: ANALYTICS_WORKER.to_string(),
+            params: vec![TemplateParam::optional(
+                "analytics_endpoint",
+                "URL request data is POSTed to; nothing is sent if unset",
+                Value::Null,
+            )],
+        },
+        WorkerTemplate {
+            id: "maintenance".to_string(),
+            name: "Maintenance Mode".to_string(),
+            description: "Display a maintenance page".to_string(),
+            script: MAINTENANCE_WORKER.to_string(),
+            params: vec![
+                TemplateParam::optional(
+                    "allowed_ips",
+                    "IP addresses that bypass the maintenance page",
+                    Value::Array(Vec::new()),
+                ),
+                TemplateParam::optional(
+                    "message",
+                    "HTML shown to everyone else",
+                    Value::String("<h1>Down for maintenance</h1><p>We'll be back shortly.</p>".to_string()),
+                ),
+            ],
         },
     ]
 }
@@ -207,4 +274,165 @@ pub struct WorkerTemplate {
     pub name: String,
     pub description: String,
     pub script: String,
+    /// Parameters substituted into the script by `render`
+    #[serde(default)]
+    pub params: Vec<TemplateParam>,
+}
+
+/// A value a template's script needs
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TemplateParam {
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+    /// Used when an optional parameter isn't given
+    #[serde(default)]
+    pub default: Option<Value>,
+}
+
+impl TemplateParam {
+    pub fn required(name: &str, description: &str) -> Self {
+        Self { name: name.to_string(), description: description.to_string(), required: true, default: None }
+    }
+
+    pub fn optional(name: &str, description: &str, default: Value) -> Self {
+        Self { name: name.to_string(), description: description.to_string(), required: false, default: Some(default) }
+    }
+}
+
+impl WorkerTemplate {
+    /// Script with every `{{param}}` placeholder replaced by its value.
+    ///
+    /// Fails if a required parameter is missing or a parameter the template
+    /// doesn't declare is given.
+    pub fn render(&self, params: &HashMap<String, Value>) -> CloudflareResult<String> {
+        let mut unknown: Vec<&str> = params
+            .keys()
+            .filter(|name| !self.params.iter().any(|p| &p.name == *name))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort_unstable();
+            return Err(CloudflareError::ValidationError(format!(
+                "Template '{}' has no parameters named {}",
+                self.id,
+                unknown.join(", ")
+            )));
+        }
+
+        let mut values = HashMap::new();
+        let mut missing = Vec::new();
+        for param in &self.params {
+            match params.get(&param.name).or(param.default.as_ref()) {
+                Some(value) => {
+                    let literal = serde_json::to_string(value).map_err(|e| CloudflareError::Internal(e.to_string()))?;
+                    values.insert(param.name.as_str(), literal);
+                }
+                None => missing.push(param.name.as_str()),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(CloudflareError::ValidationError(format!(
+                "Template '{}' is missing required parameters: {}",
+                self.id,
+                missing.join(", ")
+            )));
+        }
+
+        // One pass over the script, so a substituted value is never scanned
+        // for placeholders itself.
+        let mut script = String::with_capacity(self.script.len());
+        let mut rest = self.script.as_str();
+        while let Some(start) = rest.find("{{") {
+            script.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            match after.find("}}").and_then(|end| values.get(&after[..end]).map(|v| (end, v))) {
+                Some((end, literal)) => {
+                    script.push_str(literal);
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    script.push_str("{{");
+                    rest = after;
+                }
+            }
+        }
+        script.push_str(rest);
+
+        Ok(script)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(id: &str) -> WorkerTemplate {
+        get_templates().into_iter().find(|t| t.id == id).unwrap()
+    }
+
+    #[test]
+    fn test_render_redirect_template_with_map() {
+        let params = HashMap::from([(
+            "redirects".to_string(),
+            serde_json::json!({ "/about-us": "/about", "/blog/old": "/blog/new" }),
+        )]);
+
+        let script = template("redirect").render(&params).unwrap();
+
+        assert!(script.contains(r#"const redirects = {"/about-us":"/about","/blog/old":"/blog/new"}"#));
+        assert!(!script.contains("{{"));
+    }
+
+    #[test]
+    fn test_render_does_not_expand_placeholders_inside_values() {
+        let worker = WorkerTemplate {
+            id: "pair".to_string(),
+            name: "Pair".to_string(),
+            description: String::new(),
+            script: "const a = {{a}}; const b = {{b}}; const c = {{c}};".to_string(),
+            params: vec![
+                TemplateParam::required("a", ""),
+                TemplateParam::required("b", ""),
+                TemplateParam::optional("c", "", Value::from(1)),
+            ],
+        };
+        let params = HashMap::from([
+            ("a".to_string(), Value::from("{{b}}")),
+            ("b".to_string(), Value::from("x")),
+        ]);
+
+        let script = worker.render(&params).unwrap();
+
+        assert_eq!(script, r#"const a = "{{b}}"; const b = "x"; const c = 1;"#);
+    }
+
+    #[test]
+    fn test_render_requires_params_and_rejects_unknown_ones() {
+        let err = template("redirect").render(&HashMap::new()).unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("redirects")));
+
+        let params = HashMap::from([("endpoint".to_string(), Value::from("https://example.com"))]);
+        let err = template("analytics").render(&params).unwrap_err();
+        assert!(matches!(err, CloudflareError::ValidationError(ref m) if m.contains("endpoint")));
+    }
+
+    #[test]
+    fn test_optional_params_fall_back_to_defaults() {
+        let script = template("maintenance").render(&HashMap::new()).unwrap();
+        assert!(script.contains("const allowedIps = []"));
+
+        let script = template("analytics").render(&HashMap::new()).unwrap();
+        assert!(script.contains("const ANALYTICS_ENDPOINT = null"));
+
+        // Every placeholder in every template is declared
+        for template in get_templates() {
+            let params = template
+                .params
+                .iter()
+                .map(|p| (p.name.clone(), p.default.clone().unwrap_or_else(|| serde_json::json!({}))))
+                .collect();
+            assert!(!template.render(&params).unwrap().contains("{{"), "{} left a placeholder", template.id);
+        }
+    }
 }